@@ -108,7 +108,7 @@ async fn main() {
     println!("\nFinal statistics:");
     println!("  - Total cards in game: {}", game.cards.len());
     println!("  - Cards on battlefield: {}", game.battlefield.cards.len());
-    println!("  - Cards on stack: {}", game.stack.cards.len());
+    println!("  - Cards on stack: {}", game.stack.len());
 }
 
 /// Set up Lightning Bolt effects for all Lightning Bolts in the game
@@ -170,6 +170,7 @@ fn run_simplified_game() {
             let card_id = game.next_card_id();
             let mut card = Card::new(card_id, format!("Mountain {i}"), *player_id);
             card.types.push(CardType::Land);
+            card.subtypes.push("Mountain".into());
             card.colors.push(Color::Red);
             game.cards.insert(card_id, card);
 
@@ -149,8 +149,10 @@ async fn main() {
         let cast_result = game.cast_spell_8_step(
             alice,
             sol_ring,
-            |_game, _card_id| Vec::new(), // No targets needed
-            |_game, _cost| Vec::new(),    // No mana sources needed (already have mana)
+            |_game, _card_id| Default::default(),           // No modes or X
+            |_game, _card_id| Vec::new(),                   // No targets needed
+            |_game, _card_id, _targets, _total| Vec::new(), // Nothing to divide
+            |_game, _cost| Vec::new(),                      // No mana sources needed (already have mana)
         );
 
         match cast_result {
@@ -159,12 +161,12 @@ async fn main() {
                 println!("  Sol Ring is on the stack");
 
                 // Check stack
-                let stack_size = game.stack.cards.len();
+                let stack_size = game.stack.len();
                 println!("  Stack has {stack_size} card(s)");
 
                 // Resolve the spell
                 println!("\n=== Resolving Sol Ring ===");
-                game.resolve_spell(sol_ring, &[]).expect("Failed to resolve");
+                game.resolve_spell(sol_ring).expect("Failed to resolve");
                 println!("  ✓ Sol Ring resolved");
 
                 // Check battlefield
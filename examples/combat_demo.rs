@@ -10,9 +10,11 @@
 //!
 //! Uses classic cards from Limited/Alpha/Beta/4th Edition
 
-use mtg_forge_rs::core::{Card, CardId, CardType, EntityId, ManaCost, Player, PlayerId, SpellAbility};
+use mtg_forge_rs::core::{
+    Card, CardId, CardType, Color, Cost, EntityId, ManaCost, Player, PlayerId, ReplacementKey, SpellAbility,
+};
 use mtg_forge_rs::game::controller::PlayerController;
-use mtg_forge_rs::game::{GameLoop, GameState, GameStateView, Step};
+use mtg_forge_rs::game::{AttackTarget, GameLoop, GameState, GameStateView, StackAbility, Step};
 use smallvec::SmallVec;
 
 /// Alice's controller - attacks with all creatures
@@ -56,6 +58,35 @@ impl PlayerController for AliceController {
         SmallVec::new() // Alice doesn't cast spells in this demo
     }
 
+    fn choose_modes(
+        &mut self,
+        _view: &GameStateView,
+        _spell: CardId,
+        _modes: &[String],
+        _min: u8,
+        _max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        SmallVec::new() // Alice doesn't cast spells in this demo
+    }
+
+    fn choose_x_value(&mut self, _view: &GameStateView, _spell: CardId, _max_x: u8) -> u8 {
+        0 // Alice doesn't cast spells in this demo
+    }
+
+    fn choose_alternative_cost(&mut self, _view: &GameStateView, _spell: CardId, _cost: &Cost) -> bool {
+        false
+    }
+
+    fn choose_division(
+        &mut self,
+        _view: &GameStateView,
+        _spell: CardId,
+        _targets: &[CardId],
+        _total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        SmallVec::new() // Alice doesn't cast spells in this demo
+    }
+
     fn choose_mana_sources_to_pay(
         &mut self,
         _view: &GameStateView,
@@ -65,7 +96,16 @@ impl PlayerController for AliceController {
         SmallVec::new() // Alice doesn't tap for mana in this demo
     }
 
-    fn choose_attackers(&mut self, view: &GameStateView, available_creatures: &[CardId]) -> SmallVec<[CardId; 8]> {
+    fn choose_mana_color(&mut self, _view: &GameStateView, _source: CardId, colors: &[Color]) -> Color {
+        colors[0]
+    }
+
+    fn choose_attackers(
+        &mut self,
+        view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
         // Attack with all our creatures that are in the list
         let mut attackers = SmallVec::new();
         for &creature_id in available_creatures {
@@ -74,7 +114,7 @@ impl PlayerController for AliceController {
                     "  Alice declares {} as attacker",
                     view.get_card_name(creature_id).unwrap_or_else(|| "Unknown".to_string())
                 );
-                attackers.push(creature_id);
+                attackers.push((creature_id, defenders[0]));
             }
         }
         if !attackers.is_empty() {
@@ -102,6 +142,43 @@ impl PlayerController for AliceController {
         blockers.iter().copied().collect()
     }
 
+    fn choose_trigger_order(&mut self, _view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        (0..triggers.len()).collect()
+    }
+
+    fn choose_replacement_effect(&mut self, _view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        effects[0]
+    }
+
+    fn choose_legend_to_keep(&mut self, _view: &GameStateView, legends: &[CardId]) -> CardId {
+        legends[0]
+    }
+
+    fn choose_mulligan(&mut self, _view: &GameStateView, _hand: &[CardId], _mulligans_taken: u8) -> bool {
+        // Alice keeps every hand
+        false
+    }
+
+    fn choose_cards_to_bottom(
+        &mut self,
+        _view: &GameStateView,
+        hand: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 7]> {
+        hand.iter().take(count).copied().collect()
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        _view: &GameStateView,
+        _source: CardId,
+        _cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        candidates.iter().take(count).copied().collect()
+    }
+
     fn choose_cards_to_discard(
         &mut self,
         _view: &GameStateView,
@@ -162,6 +239,35 @@ impl PlayerController for BobController {
         SmallVec::new() // Bob doesn't cast spells in this demo
     }
 
+    fn choose_modes(
+        &mut self,
+        _view: &GameStateView,
+        _spell: CardId,
+        _modes: &[String],
+        _min: u8,
+        _max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        SmallVec::new() // Bob doesn't cast spells in this demo
+    }
+
+    fn choose_x_value(&mut self, _view: &GameStateView, _spell: CardId, _max_x: u8) -> u8 {
+        0 // Bob doesn't cast spells in this demo
+    }
+
+    fn choose_alternative_cost(&mut self, _view: &GameStateView, _spell: CardId, _cost: &Cost) -> bool {
+        false
+    }
+
+    fn choose_division(
+        &mut self,
+        _view: &GameStateView,
+        _spell: CardId,
+        _targets: &[CardId],
+        _total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        SmallVec::new() // Bob doesn't cast spells in this demo
+    }
+
     fn choose_mana_sources_to_pay(
         &mut self,
         _view: &GameStateView,
@@ -171,7 +277,16 @@ impl PlayerController for BobController {
         SmallVec::new() // Bob doesn't tap for mana in this demo
     }
 
-    fn choose_attackers(&mut self, _view: &GameStateView, _available_creatures: &[CardId]) -> SmallVec<[CardId; 8]> {
+    fn choose_mana_color(&mut self, _view: &GameStateView, _source: CardId, colors: &[Color]) -> Color {
+        colors[0]
+    }
+
+    fn choose_attackers(
+        &mut self,
+        _view: &GameStateView,
+        _available_creatures: &[CardId],
+        _defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
         SmallVec::new() // Bob doesn't attack in this demo
     }
 
@@ -211,6 +326,43 @@ impl PlayerController for BobController {
         blockers.iter().copied().collect()
     }
 
+    fn choose_trigger_order(&mut self, _view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        (0..triggers.len()).collect()
+    }
+
+    fn choose_replacement_effect(&mut self, _view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        effects[0]
+    }
+
+    fn choose_legend_to_keep(&mut self, _view: &GameStateView, legends: &[CardId]) -> CardId {
+        legends[0]
+    }
+
+    fn choose_mulligan(&mut self, _view: &GameStateView, _hand: &[CardId], _mulligans_taken: u8) -> bool {
+        // Bob keeps every hand
+        false
+    }
+
+    fn choose_cards_to_bottom(
+        &mut self,
+        _view: &GameStateView,
+        hand: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 7]> {
+        hand.iter().take(count).copied().collect()
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        _view: &GameStateView,
+        _source: CardId,
+        _cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        candidates.iter().take(count).copied().collect()
+    }
+
     fn choose_cards_to_discard(
         &mut self,
         _view: &GameStateView,
@@ -47,7 +47,7 @@ fn main() -> Result<()> {
 
     // Show stack state
     println!("Stack (top to bottom): [Lightning Bolt ({bolt_id})]");
-    println!("Stack size: {} spell(s)\n", game.stack.cards.len());
+    println!("Stack size: {} spell(s)\n", game.stack.len());
 
     // Bob responds with Counterspell
     println!("=== Bob responds with Counterspell ===");
@@ -67,14 +67,14 @@ fn main() -> Result<()> {
 
     // Show stack state
     println!("Stack (top to bottom): [Counterspell ({counter_id}), Lightning Bolt ({bolt_id})]");
-    println!("Stack size: {} spell(s)\n", game.stack.cards.len());
+    println!("Stack size: {} spell(s)\n", game.stack.len());
 
     // Resolve Counterspell (LIFO - Last In, First Out)
     println!("=== Resolving the Stack ===");
     println!("Stack resolves in Last-In-First-Out (LIFO) order:\n");
 
     println!("1. Counterspell ({counter_id}) resolves:");
-    game.resolve_spell(counter_id, &[])?;
+    game.resolve_spell(counter_id)?;
     println!("   ✓ Countered Lightning Bolt ({bolt_id})");
     println!("   ✓ Lightning Bolt moved from stack to graveyard");
     println!("   ✓ Counterspell moved to graveyard\n");
@@ -83,8 +83,8 @@ fn main() -> Result<()> {
     println!("=== Final State ===");
 
     // Check stack
-    println!("Stack: {} spell(s)", game.stack.cards.len());
-    assert_eq!(game.stack.cards.len(), 0, "Stack should be empty after resolution");
+    println!("Stack: {} spell(s)", game.stack.len());
+    assert_eq!(game.stack.len(), 0, "Stack should be empty after resolution");
 
     // Check Bob's life
     let bob = game.get_player(bob_id)?;
@@ -47,6 +47,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let land_id = game.next_card_id();
         let mut land = Card::new(land_id, format!("Forest {}", i + 1), alice_id);
         land.types.push(CardType::Land);
+        land.subtypes.push("Forest".into());
         land.colors.push(mtg_forge_rs::core::Color::Green);
         game.cards.insert(land_id, land);
         game.battlefield.add(land_id);
@@ -87,7 +88,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         game.cards.insert(creature_id, creature);
         game.stack.add(creature_id);
-        game.resolve_spell(creature_id, &[])?;
+        game.resolve_spell(creature_id)?;
 
         let hand_after = game.get_player_zones(alice_id).map(|z| z.hand.cards.len()).unwrap_or(0);
 
@@ -122,7 +123,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         game.cards.insert(creature_id, creature);
         game.stack.add(creature_id);
-        game.resolve_spell(creature_id, &[])?;
+        game.resolve_spell(creature_id)?;
 
         let hand_after = game.get_player_zones(alice_id).map(|z| z.hand.cards.len()).unwrap_or(0);
 
@@ -172,7 +173,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     game.cards.insert(kavu_id, kavu);
     game.stack.add(kavu_id);
-    game.resolve_spell(kavu_id, &[])?;
+    game.resolve_spell(kavu_id)?;
 
     let bears_alive_after = game.battlefield.contains(target_id);
     let bears_in_graveyard = game
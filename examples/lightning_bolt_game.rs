@@ -33,16 +33,15 @@ fn print_game_state(game: &GameState, label: &str) {
     }
 
     // Print stack
-    let stack_count = game.stack.cards.len();
+    let stack_count = game.stack.len();
     print!("  Stack: ");
     if stack_count == 0 {
         println!("(empty)");
     } else {
         let cards: Vec<String> = game
             .stack
-            .cards
-            .iter()
-            .filter_map(|id| game.cards.get(*id).ok())
+            .spells()
+            .filter_map(|id| game.cards.get(id).ok())
             .map(|c| c.name.as_str().to_string())
             .collect();
         println!("{}", cards.join(", "));
@@ -78,6 +77,7 @@ fn main() {
         let card_id = game.next_card_id();
         let mut card = Card::new(card_id, format!("Mountain {}", i + 1), alice);
         card.types.push(CardType::Land);
+        card.subtypes.push("Mountain".into());
         card.colors.push(Color::Red);
         game.cards.insert(card_id, card);
 
@@ -193,7 +193,7 @@ fn main() {
 
     println!("\nLightning Bolt resolves:");
     // Resolve the spell (executes effects and moves to graveyard)
-    game.resolve_spell(bolt_id, &[]).expect("Failed to resolve spell");
+    game.resolve_spell(bolt_id).expect("Failed to resolve spell");
 
     let bob_player = game.get_player(bob).unwrap();
     println!("  Bob takes 3 damage!");
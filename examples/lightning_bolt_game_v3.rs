@@ -201,7 +201,7 @@ async fn main() {
 
             // Resolve the spell
             println!("\nLightning Bolt resolves:");
-            if let Err(e) = game.resolve_spell(bolt_id, &[]) {
+            if let Err(e) = game.resolve_spell(bolt_id) {
                 println!("  Error: {e:?}");
             } else {
                 let bob_life = game.players[bob.as_u32() as usize].life;
@@ -219,7 +219,7 @@ async fn main() {
     println!("  Alice: {} life", game.players[alice.as_u32() as usize].life);
     println!("  Bob: {} life", game.players[bob.as_u32() as usize].life);
     println!("  Battlefield: {} cards", game.battlefield.cards.len());
-    println!("  Stack: {} cards", game.stack.cards.len());
+    println!("  Stack: {} cards", game.stack.len());
 
     if let Some(zones) = game.get_player_zones(alice) {
         println!("  Alice's graveyard: {} cards", zones.graveyard.cards.len());
@@ -1,7 +1,8 @@
 //! Card types and definitions
 
 use crate::core::{
-    CardId, CardName, Color, CounterType, Effect, GameEntity, Keyword, ManaCost, PlayerId, Subtype, Trigger,
+    CardId, CardName, Color, CounterType, DerivedCharacteristics, Effect, GameEntity, Keyword, ManaCost, PlayerId,
+    ReplacementEffect, Selector, StaticAbility, Subtype, Trigger,
 };
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
@@ -67,6 +68,13 @@ pub struct Card {
     /// Is the card tapped?
     pub tapped: bool,
 
+    /// Damage marked on this permanent (MTG Rules 120.3e), removed during cleanup
+    pub damage_marked: i32,
+
+    /// Was this permanent dealt damage by a deathtouch source since damage was last removed?
+    /// (MTG Rules 704.5h)
+    pub damaged_by_deathtouch: bool,
+
     /// Turn number when this permanent entered the battlefield
     /// Used for summoning sickness (creatures can't attack the turn they enter)
     /// None = not on battlefield yet, Some(turn) = entered on this turn
@@ -91,6 +99,71 @@ pub struct Card {
     /// Activated abilities (costs and effects)
     /// These can be activated by paying their cost
     pub activated_abilities: Vec<crate::core::ActivatedAbility>,
+
+    /// Continuous static abilities (anthems, lords, "creatures you control have flying")
+    /// These apply while this card is on the battlefield
+    pub static_abilities: Vec<StaticAbility>,
+
+    /// Replacement and prevention effects ("enters the battlefield tapped",
+    /// "if it would die, exile it instead"); see [`crate::core::replacement`]
+    pub replacement_effects: Vec<ReplacementEffect>,
+
+    /// Characteristics after continuous effects, kept up to date by
+    /// `GameState::apply_continuous_effects`; None when no effect applies
+    pub derived: Option<Box<DerivedCharacteristics>>,
+
+    /// Restriction on what this card's spell ability may target (from ValidTgts$)
+    /// None means targets are inferred from the effect kinds alone
+    pub target_restriction: Option<crate::core::Selector>,
+
+    /// Modes of a modal spell (Charms); empty for ordinary spells, which use `effects`
+    pub modes: Vec<crate::core::SpellMode>,
+
+    /// How many modes the caster chooses (Forge MinCharmNum$ and CharmNum$)
+    pub min_modes: u8,
+    pub max_modes: u8,
+
+    /// How many targets this card's spell ability takes (Forge TargetMin$ and TargetMax$);
+    /// [`COUNT_X`](crate::core::COUNT_X) stands for the X paid and
+    /// [`COUNT_ANY`](crate::core::COUNT_ANY) for any number, see `target_count`
+    pub min_targets: u8,
+    pub max_targets: u8,
+
+    /// Whether the spell divides its damage or counters among its targets (DividedAsYouChoose$)
+    pub divided_as_you_choose: bool,
+
+    /// Whether the spell divides its damage evenly, rounded down, among its targets
+    /// (DivideEvenly$, Fireball)
+    pub divided_evenly: bool,
+
+    /// Generic mana the spell costs more for each target beyond the first
+    /// (Fireball's "{1} more to cast for each target beyond the first")
+    pub extra_target_cost: u8,
+
+    /// A cost that may be paid rather than the mana cost (MTG Rules 118.9), like
+    /// Fireblast's "sacrifice two Mountains"
+    pub alternative_cost: Option<crate::core::Cost>,
+
+    /// Permanent this card is attached to (Auras, Equipment)
+    pub attached_to: Option<CardId>,
+
+    /// Printed loyalty of a planeswalker, the loyalty counters it enters with (MTG Rules 306.5b)
+    pub loyalty: Option<u8>,
+
+    /// Turn on which one of this planeswalker's loyalty abilities was last activated
+    /// (only one per turn, MTG Rules 606.3)
+    pub loyalty_activated_turn: Option<u32>,
+
+    /// Is this a token? (MTG Rules 111) Tokens cease to exist outside the battlefield
+    pub is_token: bool,
+
+    /// Is this card a commander? (MTG Rules 903.3) Not a characteristic, so it
+    /// stays with the card across zones
+    pub is_commander: bool,
+
+    /// How many times this commander has been cast from the command zone
+    /// (commander tax, MTG Rules 903.8)
+    pub commander_casts: u8,
 }
 
 impl Card {
@@ -110,17 +183,162 @@ impl Card {
             owner,
             controller: owner,
             tapped: false,
+            damage_marked: 0,
+            damaged_by_deathtouch: false,
             turn_entered_battlefield: None,
             counters: SmallVec::new(),
             keywords: Vec::new(),
             effects: Vec::new(),
             triggers: Vec::new(),
             activated_abilities: Vec::new(),
+            static_abilities: Vec::new(),
+            replacement_effects: Vec::new(),
+            derived: None,
+            target_restriction: None,
+            modes: Vec::new(),
+            min_modes: 1,
+            max_modes: 1,
+            min_targets: 1,
+            max_targets: 1,
+            divided_as_you_choose: false,
+            divided_evenly: false,
+            extra_target_cost: 0,
+            alternative_cost: None,
+            attached_to: None,
+            loyalty: None,
+            loyalty_activated_turn: None,
+            is_token: false,
+            is_commander: false,
+            commander_casts: 0,
+        }
+    }
+
+    /// Is this a modal spell (MTG Rules 700.2)?
+    pub fn is_modal(&self) -> bool {
+        !self.modes.is_empty()
+    }
+
+    /// The effects of this card as a spell, given the modes and X chosen when casting
+    ///
+    /// `modes` index into `modes` and are ignored for non-modal spells.
+    pub fn spell_effects(&self, modes: &[usize], x: u8) -> Vec<Effect> {
+        let effects: Vec<&Effect> = if self.is_modal() {
+            modes
+                .iter()
+                .filter_map(|&mode| self.modes.get(mode))
+                .flat_map(|mode| &mode.effects)
+                .collect()
+        } else {
+            self.effects.iter().collect()
+        };
+        effects.into_iter().map(|effect| effect.with_x(x)).collect()
+    }
+
+    /// Does this spell divide an amount among its targets, as you choose or evenly?
+    pub fn is_divided(&self) -> bool {
+        self.divided_as_you_choose || self.divided_evenly
+    }
+
+    /// The amount this spell divides among its targets, given the modes and X chosen
+    ///
+    /// That's the amount of its first damage or counter effect with a placeholder
+    /// target; `None` unless the spell divides it.
+    pub fn divided_amount(&self, modes: &[usize], x: u8) -> Option<u32> {
+        if !self.is_divided() {
+            return None;
+        }
+        self.spell_effects(modes, x).iter().find_map(Effect::divisible_amount)
+    }
+
+    /// The minimum and maximum number of targets of this card's spell, given X
+    ///
+    /// Any number ([`COUNT_ANY`](crate::core::COUNT_ANY)) has no limit here: the
+    /// valid targets run out first.
+    pub fn target_count(&self, x: u8) -> (u8, u8) {
+        let count = |value: u8| match value {
+            crate::core::COUNT_X => x,
+            crate::core::COUNT_ANY => u8::MAX,
+            value => value,
+        };
+        (count(self.min_targets), count(self.max_targets))
+    }
+
+    /// How much generic mana casting this spell with `targets` targets adds to its cost
+    pub fn extra_targets_cost(&self, targets: usize) -> u8 {
+        let extra = u8::try_from(targets.saturating_sub(1)).unwrap_or(u8::MAX);
+        self.extra_target_cost.saturating_mul(extra)
+    }
+
+    /// Whether a set of chosen mode indices is legal for this card
+    ///
+    /// Modal spells need between `min_modes` and `max_modes` distinct, existing
+    /// modes (MTG Rules 700.2); other spells take no modes.
+    pub fn are_valid_modes(&self, modes: &[usize]) -> bool {
+        if !self.is_modal() {
+            return modes.is_empty();
+        }
+        let count_ok = (self.min_modes as usize..=self.max_modes as usize).contains(&modes.len());
+        let distinct = modes.iter().enumerate().all(|(i, m)| !modes[..i].contains(m));
+        count_ok && distinct && modes.iter().all(|&m| m < self.modes.len())
+    }
+
+    /// The targeting restriction of this card as a spell, given the chosen modes
+    pub fn spell_target_restriction(&self, modes: &[usize]) -> Option<&crate::core::Selector> {
+        if self.is_modal() {
+            modes
+                .iter()
+                .filter_map(|&mode| self.modes.get(mode))
+                .find_map(|mode| mode.target_restriction.as_ref())
+        } else {
+            self.target_restriction.as_ref()
+        }
+    }
+
+    /// Card types after continuous effects
+    pub fn current_types(&self) -> &[CardType] {
+        self.derived.as_ref().map_or(&self.types, |d| &d.types)
+    }
+
+    /// Subtypes after continuous effects
+    pub fn current_subtypes(&self) -> &[Subtype] {
+        self.derived.as_ref().map_or(&self.subtypes, |d| &d.subtypes)
+    }
+
+    /// Colors after continuous effects
+    pub fn current_colors(&self) -> &[Color] {
+        self.derived.as_ref().map_or(&self.colors, |d| &d.colors)
+    }
+
+    /// Keywords after continuous effects
+    pub fn current_keywords(&self) -> &[Keyword] {
+        self.derived.as_ref().map_or(&self.keywords, |d| &d.keywords)
+    }
+
+    /// A fresh copy of this card's own characteristics, for continuous effects to modify
+    pub fn base_characteristics(&self) -> DerivedCharacteristics {
+        DerivedCharacteristics {
+            base_controller: self.controller,
+            types: self.types.clone(),
+            subtypes: self.subtypes.clone(),
+            colors: self.colors.clone(),
+            keywords: self.keywords.clone(),
+            power: self.power,
+            toughness: self.toughness,
+            power_bonus: 0,
+            toughness_bonus: 0,
+        }
+    }
+
+    /// Drop the characteristics derived from continuous effects, restoring the
+    /// controller from before any control-changing effect
+    pub fn clear_derived(&mut self) {
+        if let Some(derived) = self.derived.take() {
+            self.controller = derived.base_controller;
         }
     }
 
     pub fn is_type(&self, card_type: &CardType) -> bool {
-        self.types.contains(card_type)
+        self.current_types().contains(card_type)
     }
 
     pub fn is_creature(&self) -> bool {
@@ -144,11 +362,66 @@ impl Card {
     }
 
     pub fn is_aura(&self) -> bool {
-        self.is_enchantment() && self.subtypes.iter().any(|s| s.as_str().eq_ignore_ascii_case("aura"))
+        self.is_enchantment()
+            && self
+                .current_subtypes()
+                .iter()
+                .any(|s| s.as_str().eq_ignore_ascii_case("aura"))
+    }
+
+    pub fn is_equipment(&self) -> bool {
+        self.is_artifact() && self.current_subtypes().iter().any(|s| s.as_str() == "Equipment")
+    }
+
+    pub fn is_planeswalker(&self) -> bool {
+        self.is_type(&CardType::Planeswalker)
+    }
+
+    /// Current loyalty: the number of loyalty counters on it (MTG Rules 306.5b)
+    pub fn current_loyalty(&self) -> u8 {
+        self.get_counter(CounterType::Loyalty)
+    }
+
+    /// What this Aura can enchant, from its `Enchant` keyword (MTG Rules 303.4a)
+    pub fn enchant_restriction(&self) -> Option<Selector> {
+        self.current_keywords().iter().find_map(|keyword| match keyword {
+            Keyword::Enchant(valid) => Some(Selector::parse(valid)),
+            _ => None,
+        })
+    }
+
+    /// The Legendary supertype is kept alongside subtypes by the card loader
+    pub fn is_legendary(&self) -> bool {
+        self.current_subtypes().iter().any(|s| s.as_str() == "Legendary")
+    }
+
+    /// The Snow supertype, kept alongside subtypes like Legendary
+    pub fn is_snow(&self) -> bool {
+        self.current_subtypes().iter().any(|s| s.as_str() == "Snow")
+    }
+
+    /// Colors of the basic land types this card has, in WUBRG order
+    ///
+    /// Each basic land type grants an intrinsic "{T}: Add {C}" ability (MTG Rules 305.6),
+    /// so a Taiga (Mountain Forest) taps for either red or green.
+    pub fn basic_land_colors(&self) -> SmallVec<[Color; 5]> {
+        const BASIC_LAND_TYPES: [(&str, Color); 5] = [
+            ("Plains", Color::White),
+            ("Island", Color::Blue),
+            ("Swamp", Color::Black),
+            ("Mountain", Color::Red),
+            ("Forest", Color::Green),
+        ];
+        let subtypes = self.current_subtypes();
+        BASIC_LAND_TYPES
+            .iter()
+            .filter(|(land_type, _)| subtypes.iter().any(|s| s.as_str() == *land_type))
+            .map(|(_, color)| *color)
+            .collect()
     }
 
     pub fn has_keyword(&self, keyword: &Keyword) -> bool {
-        self.keywords.contains(keyword)
+        self.current_keywords().contains(keyword)
     }
 
     pub fn has_flying(&self) -> bool {
@@ -205,6 +478,19 @@ impl Card {
         self.has_keyword(&Keyword::Shroud)
     }
 
+    /// Check for protection from a color (MTG Rules 702.16)
+    pub fn has_protection_from(&self, color: Color) -> bool {
+        let keyword = match color {
+            Color::White => Keyword::ProtectionFromWhite,
+            Color::Blue => Keyword::ProtectionFromBlue,
+            Color::Black => Keyword::ProtectionFromBlack,
+            Color::Red => Keyword::ProtectionFromRed,
+            Color::Green => Keyword::ProtectionFromGreen,
+            Color::Colorless => return false,
+        };
+        self.has_keyword(&keyword)
+    }
+
     pub fn tap(&mut self) {
         self.tapped = true;
     }
@@ -275,22 +561,28 @@ impl Card {
             .unwrap_or(0)
     }
 
-    /// Get current power (including counters and temporary bonuses)
+    /// Get current power (including continuous effects, counters and temporary bonuses)
     pub fn current_power(&self) -> i8 {
-        let base = self.power.unwrap_or(0);
+        let (base, static_bonus) = match &self.derived {
+            Some(derived) => (derived.power, derived.power_bonus as i8),
+            None => (self.power, 0),
+        };
         let plus_counters = self.get_counter(CounterType::P1P1) as i8;
         let minus_counters = self.get_counter(CounterType::M1M1) as i8;
         let bonus = self.power_bonus as i8;
-        base + plus_counters - minus_counters + bonus
+        base.unwrap_or(0) + static_bonus + plus_counters - minus_counters + bonus
     }
 
-    /// Get current toughness (including counters and temporary bonuses)
+    /// Get current toughness (including continuous effects, counters and temporary bonuses)
     pub fn current_toughness(&self) -> i8 {
-        let base = self.toughness.unwrap_or(0);
+        let (base, static_bonus) = match &self.derived {
+            Some(derived) => (derived.toughness, derived.toughness_bonus as i8),
+            None => (self.toughness, 0),
+        };
         let plus_counters = self.get_counter(CounterType::P1P1) as i8;
         let minus_counters = self.get_counter(CounterType::M1M1) as i8;
         let bonus = self.toughness_bonus as i8;
-        base + plus_counters - minus_counters + bonus
+        base.unwrap_or(0) + static_bonus + plus_counters - minus_counters + bonus
     }
 }
 
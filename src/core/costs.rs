@@ -3,7 +3,7 @@
 //! Represents the various costs players can pay to activate abilities,
 //! such as tapping, paying mana, sacrificing permanents, etc.
 
-use crate::core::{CardId, ManaCost};
+use crate::core::{CardId, CounterType, ManaCost};
 use serde::{Deserialize, Serialize};
 
 /// A cost that must be paid to activate an ability
@@ -37,6 +37,27 @@ pub enum Cost {
     /// Discard a card
     Discard { card_id: CardId },
 
+    /// Discard cards matching a pattern (e.g., "Discard<1/Card>", "Discard<1/Creature>")
+    /// "Hand" discards the whole hand and "Random" discards at random
+    DiscardPattern { count: u8, card_type: String },
+
+    /// Exile cards matching a pattern from your graveyard (e.g., "ExileFromGrave<1/Creature>")
+    ExileFromGraveyard { count: u8, card_type: String },
+
+    /// Tap untapped permanents you control matching a pattern (e.g., "tapXType<1/Creature>")
+    /// Unlike {T}, this can tap creatures that came under your control this turn
+    TapPattern { count: u8, card_type: String },
+
+    /// Return permanents you control matching a pattern to their owner's hand
+    /// (e.g., "Return<1/Land>", "Return<1/CARDNAME>")
+    ReturnToHand { count: u8, card_type: String },
+
+    /// Put counters on the permanent (e.g., "AddCounter<1/LOYALTY>" for a +1 loyalty ability)
+    AddCounter { counter_type: CounterType, amount: u8 },
+
+    /// Remove counters from the permanent (e.g., "SubCounter<2/LOYALTY>" for a -2 loyalty ability)
+    SubCounter { counter_type: CounterType, amount: u8 },
+
     /// Composite cost (multiple costs combined)
     Composite(Vec<Cost>),
 }
@@ -48,20 +69,18 @@ impl Cost {
 
         // Check for composite costs (space-separated, but not mana symbols like "2 T" which we handle specially)
         // Look for patterns like "T Sac<1/Land>" or "1 Sac<1/CARDNAME>" or "PayLife<1> T"
-        let has_sac = trimmed.contains("Sac<");
-        let has_pay_life = trimmed.contains("PayLife<");
+        let has_special = trimmed.contains('<');
         let has_tap = trimmed.contains(" T")
             || trimmed.contains(" Tap")
             || trimmed.starts_with("T ")
             || trimmed.starts_with("Tap ");
 
         // If we have multiple cost components, parse as composite
-        if (has_sac || has_pay_life) && (has_tap || trimmed.chars().any(|c| c.is_ascii_digit() || "WUBRG".contains(c)))
-        {
+        if has_special && (has_tap || trimmed.chars().any(|c| c.is_ascii_digit() || "WUBRG".contains(c))) {
             // Parse each component separately
             let mut components = Vec::new();
 
-            // Split by space but keep Sac<...> and PayLife<...> together
+            // Split by space but keep Sac<...>, PayLife<...> etc. together
             let mut current_token = String::new();
             let mut in_angle_brackets = false;
 
@@ -148,17 +167,35 @@ impl Cost {
             return Some(Cost::Untap);
         }
 
-        // Sacrifice cost (e.g., "Sac<1/Land>", "Sac<1/Creature.Other>", "Sac<1/CARDNAME>")
-        if trimmed.starts_with("Sac<") && trimmed.ends_with('>') {
-            if let Some(sac_spec) = trimmed.strip_prefix("Sac<").and_then(|s| s.strip_suffix('>')) {
-                // Parse format: "N/Type" or "N/Type/description"
-                let parts: Vec<&str> = sac_spec.split('/').collect();
-                if parts.len() >= 2 {
-                    if let Ok(count) = parts[0].parse::<u8>() {
-                        let card_type = parts[1].to_string();
-                        return Some(Cost::SacrificePattern { count, card_type });
-                    }
-                }
+        // Costs choosing cards by pattern (e.g., "Sac<1/Land>", "Sac<1/Creature.Other>",
+        // "Sac<1/CARDNAME>", "Discard<1/Card>", "ExileFromGrave<1/Creature>")
+        if let Some((count, card_type)) = Self::parse_pattern(trimmed, "Sac<") {
+            return Some(Cost::SacrificePattern { count, card_type });
+        }
+        if let Some((count, card_type)) = Self::parse_pattern(trimmed, "Discard<") {
+            return Some(Cost::DiscardPattern { count, card_type });
+        }
+        if let Some((count, card_type)) = Self::parse_pattern(trimmed, "ExileFromGrave<") {
+            return Some(Cost::ExileFromGraveyard { count, card_type });
+        }
+        if let Some((count, card_type)) = Self::parse_pattern(trimmed, "tapXType<") {
+            return Some(Cost::TapPattern { count, card_type });
+        }
+        if let Some((count, card_type)) = Self::parse_pattern(trimmed, "Return<") {
+            return Some(Cost::ReturnToHand { count, card_type });
+        }
+
+        // Counter costs (e.g., "AddCounter<1/LOYALTY>", "SubCounter<2/LOYALTY>") - check before mana parsing
+        for (prefix, add) in [("AddCounter<", true), ("SubCounter<", false)] {
+            if let Some(spec) = trimmed.strip_prefix(prefix).and_then(|s| s.strip_suffix('>')) {
+                let (amount, counter_type) = spec.split_once('/')?;
+                let amount = amount.parse::<u8>().ok()?;
+                let counter_type = CounterType::parse(counter_type.split('/').next()?)?;
+                return Some(if add {
+                    Cost::AddCounter { counter_type, amount }
+                } else {
+                    Cost::SubCounter { counter_type, amount }
+                });
             }
         }
 
@@ -198,6 +235,23 @@ impl Cost {
         None
     }
 
+    /// Parse a "Prefix<N/Type>" or "Prefix<N/Type/description>" cost component
+    fn parse_pattern(trimmed: &str, prefix: &str) -> Option<(u8, String)> {
+        let spec = trimmed.strip_prefix(prefix)?.strip_suffix('>')?;
+        let mut parts = spec.split('/');
+        let count = parts.next()?.parse::<u8>().ok()?;
+        let card_type = parts.next()?.to_string();
+        Some((count, card_type))
+    }
+
+    /// The parts of this cost: the costs of a composite, or the cost itself
+    pub fn parts(&self) -> &[Cost] {
+        match self {
+            Cost::Composite(costs) => costs,
+            _ => std::slice::from_ref(self),
+        }
+    }
+
     /// Check if this cost includes a tap
     pub fn includes_tap(&self) -> bool {
         match self {
@@ -225,6 +279,22 @@ impl Cost {
         }
     }
 
+    /// Net change to the permanent's loyalty, if this is a loyalty cost (MTG Rules 606.4)
+    pub fn loyalty_change(&self) -> Option<i32> {
+        match self {
+            Cost::AddCounter {
+                counter_type: CounterType::Loyalty,
+                amount,
+            } => Some(*amount as i32),
+            Cost::SubCounter {
+                counter_type: CounterType::Loyalty,
+                amount,
+            } => Some(-(*amount as i32)),
+            Cost::Composite(costs) => costs.iter().find_map(|c| c.loyalty_change()),
+            _ => None,
+        }
+    }
+
     /// Get the life cost component if present
     pub fn get_life_cost(&self) -> Option<i32> {
         match self {
@@ -292,6 +362,22 @@ mod tests {
         assert_eq!(cost, Cost::PayLife { amount: 2 });
     }
 
+    #[test]
+    fn test_parse_loyalty_costs() {
+        let plus = Cost::parse("AddCounter<1/LOYALTY>").unwrap();
+        assert_eq!(
+            plus,
+            Cost::AddCounter {
+                counter_type: CounterType::Loyalty,
+                amount: 1
+            }
+        );
+        assert_eq!(plus.loyalty_change(), Some(1));
+        assert_eq!(Cost::parse("AddCounter<0/LOYALTY>").unwrap().loyalty_change(), Some(0));
+        assert_eq!(Cost::parse("SubCounter<3/LOYALTY>").unwrap().loyalty_change(), Some(-3));
+        assert_eq!(Cost::parse("T").unwrap().loyalty_change(), None);
+    }
+
     #[test]
     fn test_parse_sacrifice_land() {
         let cost = Cost::parse("Sac<1/Land>").unwrap();
@@ -328,4 +414,40 @@ mod tests {
             _ => panic!("Expected Composite cost, got {cost:?}"),
         }
     }
+
+    #[test]
+    fn test_parse_card_choice_costs() {
+        let pattern = |count: u8, card_type: &str| (count, card_type.to_string());
+        match Cost::parse("Discard<1/Card>").unwrap() {
+            Cost::DiscardPattern { count, card_type } => assert_eq!((count, card_type), pattern(1, "Card")),
+            cost => panic!("Expected DiscardPattern cost, got {cost:?}"),
+        }
+        match Cost::parse("ExileFromGrave<2/Creature/creature cards>").unwrap() {
+            Cost::ExileFromGraveyard { count, card_type } => assert_eq!((count, card_type), pattern(2, "Creature")),
+            cost => panic!("Expected ExileFromGraveyard cost, got {cost:?}"),
+        }
+        match Cost::parse("Return<1/Land>").unwrap() {
+            Cost::ReturnToHand { count, card_type } => assert_eq!((count, card_type), pattern(1, "Land")),
+            cost => panic!("Expected ReturnToHand cost, got {cost:?}"),
+        }
+
+        // Springleaf Drum: {T}, Tap an untapped creature you control
+        let cost = Cost::parse("T tapXType<1/Creature>").unwrap();
+        assert_eq!(
+            cost.parts(),
+            &[
+                Cost::TapPattern {
+                    count: 1,
+                    card_type: "Creature".to_string()
+                },
+                Cost::Tap
+            ]
+        );
+        assert_eq!(Cost::parse("T").unwrap().parts(), &[Cost::Tap]);
+
+        // Mana and a discard
+        let cost = Cost::parse("1 R Discard<1/Card>").unwrap();
+        assert_eq!(cost.get_mana_cost(), Some(&ManaCost::from_string("1R")));
+        assert_eq!(cost.parts().len(), 2);
+    }
 }
@@ -1,6 +1,6 @@
 //! Card effects and ability system
 
-use crate::core::{CardId, PlayerId};
+use crate::core::{CardId, PlayerId, Selector, TokenDefinition};
 use serde::{Deserialize, Serialize};
 
 /// Target reference for effects
@@ -47,7 +47,7 @@ pub enum Keyword {
     Madness(String),
     /// Flashback cost (e.g., "Flashback:3 R")
     Flashback(String),
-    /// Enchant type (e.g., "Enchant:Creature")
+    /// What an Aura can enchant, as a selector (e.g. "Creature" for "Enchant creature")
     Enchant(String),
 
     // Commander-specific
@@ -57,6 +57,37 @@ pub enum Keyword {
     Other(String),
 }
 
+impl Keyword {
+    /// Look up a parameterless keyword by its card-script name (e.g. "First Strike")
+    pub fn from_name(name: &str) -> Option<Keyword> {
+        let keyword = match name {
+            "Flying" => Keyword::Flying,
+            "First Strike" => Keyword::FirstStrike,
+            "Double Strike" => Keyword::DoubleStrike,
+            "Deathtouch" => Keyword::Deathtouch,
+            "Haste" => Keyword::Haste,
+            "Hexproof" => Keyword::Hexproof,
+            "Indestructible" => Keyword::Indestructible,
+            "Lifelink" => Keyword::Lifelink,
+            "Menace" => Keyword::Menace,
+            "Reach" => Keyword::Reach,
+            "Trample" => Keyword::Trample,
+            "Vigilance" => Keyword::Vigilance,
+            "Defender" => Keyword::Defender,
+            "Shroud" => Keyword::Shroud,
+            "Choose a Background" => Keyword::ChooseABackground,
+            // Protection variants
+            "Protection from red" => Keyword::ProtectionFromRed,
+            "Protection from blue" => Keyword::ProtectionFromBlue,
+            "Protection from black" => Keyword::ProtectionFromBlack,
+            "Protection from white" => Keyword::ProtectionFromWhite,
+            "Protection from green" => Keyword::ProtectionFromGreen,
+            _ => return None,
+        };
+        Some(keyword)
+    }
+}
+
 /// Basic card effects that can be executed
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Effect {
@@ -107,6 +138,14 @@ pub enum Effect {
         mana: crate::core::ManaCost,
     },
 
+    /// Add `amount` mana of one color, chosen from `colors`, to a player's mana pool
+    /// Example: "Add one mana of any color" or "Add {R} or {G}"
+    AddManaOfChoice {
+        player: PlayerId,
+        colors: Vec<crate::core::Color>,
+        amount: u8,
+    },
+
     /// Put counters on a permanent
     /// Example: "Put a +1/+1 counter on target creature"
     PutCounter {
@@ -127,6 +166,190 @@ pub enum Effect {
     /// Example: "Exile target creature" (Swords to Plowshares)
     /// Moves a card from the battlefield to the exile zone
     ExilePermanent { target: CardId },
+
+    /// Destroy all permanents matching a selector
+    /// Example: "Destroy all creatures" (Wrath of God, ValidCards$ Creature)
+    /// `controller` is evaluated as "You" for relative properties like YouCtrl
+    DestroyAll { selector: Selector, controller: PlayerId },
+
+    /// Pump all creatures matching a selector until end of turn
+    /// Example: "Creatures you control get +1/+1 until end of turn" (ValidCards$ Creature.YouCtrl)
+    PumpAll {
+        selector: Selector,
+        controller: PlayerId,
+        power_bonus: i32,
+        toughness_bonus: i32,
+    },
+
+    /// Create token permanents under a player's control
+    /// Example: "Create two 1/1 white Soldier creature tokens" (TokenScript$ w_1_1_soldier | TokenAmount$ 2)
+    CreateToken {
+        owner: PlayerId,
+        amount: u8,
+        token: TokenDefinition,
+    },
+
+    /// Attach an Aura or Equipment to a permanent
+    /// Example: "Equip {2}" or an Aura spell resolving (SP$ Attach | ValidTgts$ Creature)
+    /// A placeholder `attachment` is the spell or ability's source
+    Attach { attachment: CardId, target: CardId },
+
+    /// Create replacement effects that last until end of turn
+    /// Example: "Prevent all combat damage that would be dealt this turn" (Fog, SP$ Effect | ReplacementEffects$ ...)
+    /// `controller` is the "You" of the effects
+    CreateReplacementEffects {
+        controller: PlayerId,
+        effects: Vec<crate::core::ReplacementEffect>,
+    },
+}
+
+/// Placeholder amount meaning "X" (Forge's `Count$xPaid`) in damage, life and pump effects
+///
+/// Replaced by [`Effect::with_x`] with the value of X chosen when the spell was cast.
+pub const AMOUNT_X: i32 = i32::MIN;
+
+/// Placeholder count meaning "X" in draw, mill, token and counter effects (see [`AMOUNT_X`])
+pub const COUNT_X: u8 = u8::MAX;
+
+/// Placeholder target count meaning "any number", for a `TargetMax$` the game
+/// computes (Fireball's count of players and creatures)
+pub const COUNT_ANY: u8 = u8::MAX - 1;
+
+impl Effect {
+    /// Replace X placeholders ([`AMOUNT_X`], [`COUNT_X`]) with the chosen value of X
+    pub fn with_x(&self, x: u8) -> Effect {
+        let amount = |value: i32| if value == AMOUNT_X { i32::from(x) } else { value };
+        let count = |value: u8| if value == COUNT_X { x } else { value };
+
+        let mut effect = self.clone();
+        match &mut effect {
+            Effect::DealDamage { amount: a, .. } | Effect::GainLife { amount: a, .. } => *a = amount(*a),
+            Effect::PumpCreature {
+                power_bonus,
+                toughness_bonus,
+                ..
+            }
+            | Effect::PumpAll {
+                power_bonus,
+                toughness_bonus,
+                ..
+            } => {
+                *power_bonus = amount(*power_bonus);
+                *toughness_bonus = amount(*toughness_bonus);
+            }
+            Effect::DrawCards { count: c, .. }
+            | Effect::Mill { count: c, .. }
+            | Effect::CreateToken { amount: c, .. }
+            | Effect::PutCounter { amount: c, .. } => *c = count(*c),
+            _ => {}
+        }
+        effect
+    }
+
+    /// The amount of a damage or counter effect that can be divided among targets
+    ///
+    /// Only effects whose target is still a placeholder qualify (Forge `DividedAsYouChoose$`).
+    pub fn divisible_amount(&self) -> Option<u32> {
+        match self {
+            Effect::DealDamage {
+                target: TargetRef::None,
+                amount,
+            } => u32::try_from(*amount).ok(),
+            Effect::PutCounter { target, amount, .. } if target.as_u32() == 0 => Some(u32::from(*amount)),
+            _ => None,
+        }
+    }
+
+    /// This divisible effect applied to one target with its share of the amount
+    ///
+    /// Damage can be divided among players too; counters only go on permanents.
+    pub fn with_divided_share(&self, target: TargetRef, share: u32) -> Effect {
+        let mut effect = self.clone();
+        match &mut effect {
+            Effect::DealDamage { target: t, amount, .. } => {
+                *t = target;
+                *amount = i32::try_from(share).unwrap_or(i32::MAX);
+            }
+            Effect::PutCounter { target: t, amount, .. } => {
+                if let TargetRef::Permanent(target) = target {
+                    *t = target;
+                }
+                *amount = u8::try_from(share).unwrap_or(u8::MAX);
+            }
+            _ => {}
+        }
+        effect
+    }
+
+    /// The permanent or spell this effect targets, once its target is filled in
+    pub fn target_card(&self) -> Option<CardId> {
+        let target = match self {
+            Effect::DealDamage {
+                target: TargetRef::Permanent(target),
+                ..
+            }
+            | Effect::DestroyPermanent { target }
+            | Effect::TapPermanent { target }
+            | Effect::UntapPermanent { target }
+            | Effect::PumpCreature { target, .. }
+            | Effect::CounterSpell { target }
+            | Effect::PutCounter { target, .. }
+            | Effect::RemoveCounter { target, .. }
+            | Effect::ExilePermanent { target }
+            | Effect::Attach { target, .. } => *target,
+            _ => return None,
+        };
+        // CardId 0 is the "not chosen yet" placeholder
+        (target.as_u32() != 0).then_some(target)
+    }
+
+    /// Does this effect target a permanent that hasn't been chosen yet?
+    pub fn needs_target(&self) -> bool {
+        match self {
+            Effect::DealDamage { target, .. } => *target == TargetRef::None,
+            Effect::DestroyPermanent { target }
+            | Effect::TapPermanent { target }
+            | Effect::UntapPermanent { target }
+            | Effect::PumpCreature { target, .. }
+            | Effect::PutCounter { target, .. }
+            | Effect::RemoveCounter { target, .. }
+            | Effect::ExilePermanent { target } => target.as_u32() == 0,
+            _ => false,
+        }
+    }
+
+    /// This effect with its placeholder target (see `needs_target`) filled in
+    pub fn with_target(&self, target_id: CardId) -> Effect {
+        let mut effect = self.clone();
+        if !self.needs_target() {
+            return effect;
+        }
+        match &mut effect {
+            Effect::DealDamage { target, .. } => *target = TargetRef::Permanent(target_id),
+            Effect::DestroyPermanent { target }
+            | Effect::TapPermanent { target }
+            | Effect::UntapPermanent { target }
+            | Effect::PumpCreature { target, .. }
+            | Effect::PutCounter { target, .. }
+            | Effect::RemoveCounter { target, .. }
+            | Effect::ExilePermanent { target } => *target = target_id,
+            _ => {}
+        }
+        effect
+    }
+}
+
+/// One mode of a modal spell (Forge `Charm` with `Choices$`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpellMode {
+    /// Description shown when choosing modes
+    pub description: String,
+
+    /// The effects of this mode
+    pub effects: Vec<Effect>,
+
+    /// Restriction on what this mode may target (from ValidTgts$), if any
+    pub target_restriction: Option<Selector>,
 }
 
 /// Events that can trigger abilities
@@ -176,6 +399,10 @@ pub struct Trigger {
 
     /// Description of the trigger (for logging)
     pub description: String,
+
+    /// Restriction on what this trigger may target (from ValidTgts$), if any
+    #[serde(default)]
+    pub target_restriction: Option<Selector>,
 }
 
 impl Trigger {
@@ -185,8 +412,15 @@ impl Trigger {
             event,
             effects,
             description,
+            target_restriction: None,
         }
     }
+
+    /// Set the targeting restriction (e.g. "Creature.OppCtrl")
+    pub fn with_target_restriction(mut self, restriction: Option<Selector>) -> Self {
+        self.target_restriction = restriction;
+        self
+    }
 }
 
 /// An activated ability that can be activated by paying a cost
@@ -204,6 +438,13 @@ pub struct ActivatedAbility {
 
     /// Whether this is a mana ability (doesn't use the stack)
     pub is_mana_ability: bool,
+
+    /// Restriction on what this ability may target (from ValidTgts$), if any
+    pub target_restriction: Option<Selector>,
+
+    /// Whether this ability can only be activated any time you could cast a sorcery
+    /// (SorcerySpeed$ True, Equip)
+    pub sorcery_speed: bool,
 }
 
 impl ActivatedAbility {
@@ -214,8 +455,43 @@ impl ActivatedAbility {
             effects,
             description,
             is_mana_ability,
+            target_restriction: None,
+            sorcery_speed: false,
         }
     }
+
+    /// The intrinsic "{T}: Add {C}" ability granted by a basic land type (MTG Rules 305.6)
+    pub fn basic_land_mana(color: crate::core::Color) -> Self {
+        let mut mana = crate::core::ManaCost::new();
+        mana.add_color(color);
+        ActivatedAbility::new(
+            crate::core::Cost::Tap,
+            vec![Effect::AddMana {
+                player: PlayerId::new(0), // Placeholder - filled in when activated
+                mana,
+            }],
+            format!("Add {mana}"),
+            true,
+        )
+    }
+
+    /// Set the targeting restriction (e.g. "Creature.tapped" for Royal Assassin)
+    pub fn with_target_restriction(mut self, restriction: Option<Selector>) -> Self {
+        self.target_restriction = restriction;
+        self
+    }
+
+    /// Restrict activation to sorcery timing (MTG Rules 602.5d)
+    pub fn with_sorcery_speed(mut self, sorcery_speed: bool) -> Self {
+        self.sorcery_speed = sorcery_speed;
+        self
+    }
+
+    /// Is this a planeswalker's loyalty ability (its cost adds or removes loyalty counters)?
+    /// MTG Rules 606.3-606.4
+    pub fn is_loyalty_ability(&self) -> bool {
+        self.cost.loyalty_change().is_some()
+    }
 }
 
 #[cfg(test)]
@@ -27,8 +27,40 @@ impl fmt::Display for Color {
     }
 }
 
+/// The ten two-color pairs, in the order [`ManaCost::hybrid`] counts hybrid symbols
+pub const HYBRID_PAIRS: [(Color, Color); 10] = [
+    (Color::White, Color::Blue),
+    (Color::Blue, Color::Black),
+    (Color::Black, Color::Red),
+    (Color::Red, Color::Green),
+    (Color::Green, Color::White),
+    (Color::White, Color::Black),
+    (Color::Blue, Color::Red),
+    (Color::Black, Color::Green),
+    (Color::Red, Color::White),
+    (Color::Green, Color::Blue),
+];
+
+/// The five colors in WUBRG order, used to index per-color symbol counts
+pub const WUBRG: [Color; 5] = [Color::White, Color::Blue, Color::Black, Color::Red, Color::Green];
+
+/// Index of a mana symbol letter in WUBRG order
+fn wubrg_index(symbol: &str) -> Option<usize> {
+    match symbol {
+        "W" => Some(0),
+        "U" => Some(1),
+        "B" => Some(2),
+        "R" => Some(3),
+        "G" => Some(4),
+        _ => None,
+    }
+}
+
 /// Represents a mana cost (e.g., "2RR" = 2 generic + 2 red, "X R" = X + 1 red)
-/// Copy-eligible since it's just 8 u8 fields (8 bytes)
+///
+/// Besides generic and colored mana, a cost can contain hybrid symbols ({R/G}),
+/// Phyrexian symbols ({W/P}), monocolored hybrid symbols ({2/U}) and snow
+/// symbols ({S}). Copy-eligible since it's just a few dozen u8 fields.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ManaCost {
     pub generic: u8,
@@ -41,6 +73,18 @@ pub struct ManaCost {
     /// Number of X symbols in the cost (e.g., "X R" has x_count=1, "X X R R" has x_count=2)
     /// The actual value of X is determined when the spell is cast
     pub x_count: u8,
+    /// Hybrid symbols, counted per color pair in [`HYBRID_PAIRS`] order
+    #[serde(default)]
+    pub hybrid: [u8; 10],
+    /// Phyrexian symbols, per color in WUBRG order (payable with the color or 2 life)
+    #[serde(default)]
+    pub phyrexian: [u8; 5],
+    /// Monocolored hybrid symbols, per color in WUBRG order (the color or {2})
+    #[serde(default)]
+    pub two_hybrid: [u8; 5],
+    /// Snow symbols, payable with mana produced by a snow source
+    #[serde(default)]
+    pub snow: u8,
 }
 
 impl ManaCost {
@@ -54,38 +98,260 @@ impl ManaCost {
             green: 0,
             colorless: 0,
             x_count: 0,
+            hybrid: [0; 10],
+            phyrexian: [0; 5],
+            two_hybrid: [0; 5],
+            snow: 0,
         }
     }
 
-    /// Parse a mana cost string like "2RR", "1UB", or "X R"
+    /// Parse a mana cost string like "2RR", "1UB", "X R", "1 R/G W/P" or "{2/U}{S}"
+    ///
+    /// Symbols are separated by whitespace or braces. Symbols containing a slash are
+    /// hybrid ("R/G"), Phyrexian ("W/P") or monocolored hybrid ("2/U"); a Phyrexian
+    /// hybrid symbol ("G/W/P") is kept as plain hybrid, which never makes it cheaper.
     pub fn from_string(s: &str) -> Self {
         let mut cost = ManaCost::new();
-        let mut generic_str = String::new();
-
-        for c in s.chars() {
-            match c {
-                'W' => cost.white += 1,
-                'U' => cost.blue += 1,
-                'B' => cost.black += 1,
-                'R' => cost.red += 1,
-                'G' => cost.green += 1,
-                'C' => cost.colorless += 1,
-                'X' => cost.x_count += 1,
-                '0'..='9' => generic_str.push(c),
-                _ => {} // Ignore other characters (spaces, etc.)
+
+        for token in s.split(|c: char| c.is_whitespace() || c == '{' || c == '}') {
+            if token.contains('/') {
+                cost.add_slash_symbol(token);
+                continue;
             }
-        }
 
-        if !generic_str.is_empty() {
-            cost.generic = generic_str.parse().unwrap_or(0);
+            let mut generic_str = String::new();
+            for c in token.chars() {
+                match c {
+                    'W' => cost.white += 1,
+                    'U' => cost.blue += 1,
+                    'B' => cost.black += 1,
+                    'R' => cost.red += 1,
+                    'G' => cost.green += 1,
+                    'C' => cost.colorless += 1,
+                    'X' => cost.x_count += 1,
+                    'S' => cost.snow += 1,
+                    '0'..='9' => generic_str.push(c),
+                    _ => {} // Ignore other characters
+                }
+            }
+            if !generic_str.is_empty() {
+                cost.generic = cost.generic.saturating_add(generic_str.parse().unwrap_or(0));
+            }
         }
 
         cost
     }
 
+    /// Add one symbol written with a slash ("R/G", "W/P", "2/U" or "G/W/P")
+    fn add_slash_symbol(&mut self, symbol: &str) {
+        let mut parts = symbol.split('/');
+        let first = parts.next().unwrap_or("");
+        let second = parts.next().unwrap_or("");
+
+        match (first, wubrg_index(first), wubrg_index(second)) {
+            (_, Some(color), None) if second == "P" => self.phyrexian[color] += 1,
+            ("2", None, Some(color)) => self.two_hybrid[color] += 1,
+            (_, Some(a), Some(b)) => {
+                let (a, b) = (WUBRG[a], WUBRG[b]);
+                if let Some(pair) = HYBRID_PAIRS
+                    .iter()
+                    .position(|&(x, y)| (x, y) == (a, b) || (x, y) == (b, a))
+                {
+                    self.hybrid[pair] += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// Total converted mana cost
+    ///
+    /// Hybrid and Phyrexian symbols count 1, monocolored hybrid symbols count 2
+    /// (MTG Rules 202.2).
     pub fn cmc(&self) -> u8 {
-        self.generic + self.white + self.blue + self.black + self.red + self.green + self.colorless
+        self.generic
+            + self.white
+            + self.blue
+            + self.black
+            + self.red
+            + self.green
+            + self.colorless
+            + self.snow
+            + self.hybrid.iter().sum::<u8>()
+            + self.phyrexian.iter().sum::<u8>()
+            + 2 * self.two_hybrid.iter().sum::<u8>()
+    }
+
+    /// Whether the cost contains hybrid, Phyrexian, monocolored hybrid or snow symbols
+    ///
+    /// Costs without them can be paid by simply counting mana of each color.
+    pub fn has_special_symbols(&self) -> bool {
+        self.snow > 0
+            || self.hybrid.iter().any(|&n| n > 0)
+            || self.phyrexian.iter().any(|&n| n > 0)
+            || self.two_hybrid.iter().any(|&n| n > 0)
+    }
+
+    /// Whether any symbol of the cost has the given color (MTG Rules 105.2)
+    pub fn contains_color(&self, color: Color) -> bool {
+        self.symbols_of_color(color) > 0
+    }
+
+    /// How many symbols of the cost have the given color, counting hybrid and
+    /// Phyrexian symbols that could also be paid another way
+    pub fn symbols_of_color(&self, color: Color) -> u8 {
+        let plain = match color {
+            Color::White => self.white,
+            Color::Blue => self.blue,
+            Color::Black => self.black,
+            Color::Red => self.red,
+            Color::Green => self.green,
+            Color::Colorless => return 0,
+        };
+        let index = WUBRG.iter().position(|&c| c == color).unwrap_or(0);
+        let hybrid: u8 = HYBRID_PAIRS
+            .iter()
+            .zip(self.hybrid.iter())
+            .filter(|(&(a, b), _)| a == color || b == color)
+            .map(|(_, &n)| n)
+            .sum();
+
+        plain + self.phyrexian[index] + self.two_hybrid[index] + hybrid
+    }
+
+    /// Add one mana of the given color, e.g. to record mana added to a pool
+    pub fn add_color(&mut self, color: Color) {
+        match color {
+            Color::White => self.white += 1,
+            Color::Blue => self.blue += 1,
+            Color::Black => self.black += 1,
+            Color::Red => self.red += 1,
+            Color::Green => self.green += 1,
+            Color::Colorless => self.colorless += 1,
+        }
+    }
+
+    /// Add `amount` mana of the WUBRG color at `index`
+    fn add_wubrg(&mut self, index: usize, amount: u8) {
+        let field = match index {
+            0 => &mut self.white,
+            1 => &mut self.blue,
+            2 => &mut self.black,
+            3 => &mut self.red,
+            _ => &mut self.green,
+        };
+        *field += amount;
+    }
+
+    /// Visit each way to pay the hybrid, monocolored hybrid and Phyrexian symbols
+    ///
+    /// Each option is a cost with only generic, colored, colorless and snow mana,
+    /// together with the number of Phyrexian symbols paid with 2 life instead (at
+    /// most `max_life_payments`). Options paying more with mana come first. The
+    /// visitor returns `true` to stop; the return value says whether it did.
+    /// Costs without special symbols are visited exactly once, as themselves.
+    pub fn for_each_payment_option<F>(&self, max_life_payments: u8, visit: &mut F) -> bool
+    where
+        F: FnMut(&ManaCost, u8) -> bool,
+    {
+        let base = ManaCost {
+            hybrid: [0; 10],
+            phyrexian: [0; 5],
+            two_hybrid: [0; 5],
+            ..*self
+        };
+        self.expand_payment_options(base, 0, max_life_payments, 0, visit)
+    }
+
+    /// Expand the choices of symbol group `slot` onwards
+    ///
+    /// Slots 0-9 are the hybrid pairs, 10-14 the monocolored hybrid colors and
+    /// 15-19 the Phyrexian colors.
+    fn expand_payment_options<F>(
+        &self,
+        base: ManaCost,
+        slot: usize,
+        life_left: u8,
+        life_used: u8,
+        visit: &mut F,
+    ) -> bool
+    where
+        F: FnMut(&ManaCost, u8) -> bool,
+    {
+        let mut slot = slot;
+        while slot < 20 && self.symbols_in_slot(slot) == 0 {
+            slot += 1;
+        }
+        if slot == 20 {
+            return visit(&base, life_used);
+        }
+
+        let count = self.symbols_in_slot(slot);
+        match slot {
+            0..=9 => {
+                // k symbols paid with the first color of the pair, the rest with the second
+                let (a, b) = HYBRID_PAIRS[slot];
+                let a = WUBRG.iter().position(|&c| c == a).unwrap_or(0);
+                let b = WUBRG.iter().position(|&c| c == b).unwrap_or(0);
+                for k in (0..=count).rev() {
+                    let mut option = base;
+                    option.add_wubrg(a, k);
+                    option.add_wubrg(b, count - k);
+                    if self.expand_payment_options(option, slot + 1, life_left, life_used, visit) {
+                        return true;
+                    }
+                }
+            }
+            10..=14 => {
+                // k symbols paid with the color, the rest with {2}
+                for k in (0..=count).rev() {
+                    let mut option = base;
+                    option.add_wubrg(slot - 10, k);
+                    option.generic += 2 * (count - k);
+                    if self.expand_payment_options(option, slot + 1, life_left, life_used, visit) {
+                        return true;
+                    }
+                }
+            }
+            _ => {
+                // k symbols paid with 2 life each, the rest with the color
+                for k in 0..=count.min(life_left) {
+                    let mut option = base;
+                    option.add_wubrg(slot - 15, count - k);
+                    if self.expand_payment_options(option, slot + 1, life_left - k, life_used + k, visit) {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn symbols_in_slot(&self, slot: usize) -> u8 {
+        match slot {
+            0..=9 => self.hybrid[slot],
+            10..=14 => self.two_hybrid[slot - 10],
+            _ => self.phyrexian[slot - 15],
+        }
+    }
+
+    /// The cost to pay once X has been chosen (MTG Rules 601.2f)
+    ///
+    /// Each X symbol adds `x` generic mana.
+    pub fn with_x(&self, x: u8) -> Self {
+        ManaCost {
+            generic: self.generic.saturating_add(x.saturating_mul(self.x_count)),
+            x_count: 0,
+            ..*self
+        }
+    }
+
+    /// The cost with additional generic mana, such as commander tax (MTG Rules 601.2f)
+    pub fn plus_generic(&self, amount: u8) -> Self {
+        ManaCost {
+            generic: self.generic.saturating_add(amount),
+            ..*self
+        }
     }
 
     /// Multiply all mana amounts by a factor
@@ -101,6 +367,10 @@ impl ManaCost {
             green: self.green.saturating_mul(factor),
             colorless: self.colorless.saturating_mul(factor),
             x_count: self.x_count, // X is not multiplied
+            hybrid: self.hybrid.map(|n| n.saturating_mul(factor)),
+            phyrexian: self.phyrexian.map(|n| n.saturating_mul(factor)),
+            two_hybrid: self.two_hybrid.map(|n| n.saturating_mul(factor)),
+            snow: self.snow.saturating_mul(factor),
         }
     }
 }
@@ -120,6 +390,9 @@ impl fmt::Display for ManaCost {
         if self.generic > 0 {
             write!(f, "{}", self.generic)?;
         }
+        for _ in 0..self.snow {
+            write!(f, "S")?;
+        }
         for _ in 0..self.white {
             write!(f, "W")?;
         }
@@ -138,12 +411,30 @@ impl fmt::Display for ManaCost {
         for _ in 0..self.colorless {
             write!(f, "C")?;
         }
+        // Slash symbols are written in braces so the result parses back
+        for (&(a, b), &n) in HYBRID_PAIRS.iter().zip(self.hybrid.iter()) {
+            for _ in 0..n {
+                write!(f, "{{{a}/{b}}}")?;
+            }
+        }
+        for (color, &n) in WUBRG.iter().zip(self.two_hybrid.iter()) {
+            for _ in 0..n {
+                write!(f, "{{2/{color}}}")?;
+            }
+        }
+        for (color, &n) in WUBRG.iter().zip(self.phyrexian.iter()) {
+            for _ in 0..n {
+                write!(f, "{{{color}/P}}")?;
+            }
+        }
         Ok(())
     }
 }
 
 /// Mana pool for a player
-/// Copy-eligible since it's just 6 u8 fields (6 bytes)
+///
+/// Mana from snow sources is tracked per color in `snow`, so {S} can be paid.
+/// Copy-eligible since it's just 12 u8 fields (12 bytes)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ManaPool {
     pub white: u8,
@@ -152,6 +443,11 @@ pub struct ManaPool {
     pub red: u8,
     pub green: u8,
     pub colorless: u8,
+    /// How much of each color (WUBRG, then colorless) came from snow sources
+    ///
+    /// Never more than the pool's amount of that color.
+    #[serde(default)]
+    pub snow: [u8; 6],
 }
 
 impl ManaPool {
@@ -163,6 +459,7 @@ impl ManaPool {
             red: 0,
             green: 0,
             colorless: 0,
+            snow: [0; 6],
         }
     }
 
@@ -177,6 +474,33 @@ impl ManaPool {
         }
     }
 
+    /// How much mana of the given color is in the pool
+    pub fn amount_of(&self, color: Color) -> u8 {
+        self.amount(Self::color_index(color))
+    }
+
+    /// Add one mana produced by a snow source (MTG Rules 107.4h)
+    pub fn add_snow_color(&mut self, color: Color) {
+        self.add_color(color);
+        self.snow[Self::color_index(color)] += 1;
+    }
+
+    /// Remove mana that was added to the pool, such as when undoing a mana ability
+    ///
+    /// A positive `mana.snow` means the mana came from a snow source.
+    pub fn remove_mana(&mut self, mana: &ManaCost) {
+        let removed = [mana.white, mana.blue, mana.black, mana.red, mana.green, mana.colorless];
+        for (index, &amount) in removed.iter().enumerate() {
+            let field = self.amount_mut(index);
+            *field = field.saturating_sub(amount);
+            let remaining = *field;
+            if mana.snow > 0 {
+                self.snow[index] = self.snow[index].saturating_sub(amount);
+            }
+            self.snow[index] = self.snow[index].min(remaining);
+        }
+    }
+
     pub fn clear(&mut self) {
         self.white = 0;
         self.blue = 0;
@@ -184,10 +508,24 @@ impl ManaPool {
         self.red = 0;
         self.green = 0;
         self.colorless = 0;
+        self.snow = [0; 6];
     }
 
     /// Check if we can pay the given mana cost
+    ///
+    /// Phyrexian symbols must be paid with mana; see [`ManaPool::can_pay_with_life`].
     pub fn can_pay(&self, cost: &ManaCost) -> bool {
+        self.can_pay_with_life(cost, 0)
+    }
+
+    /// Check if we can pay the given mana cost, paying up to `max_life_payments`
+    /// Phyrexian symbols with life instead of mana
+    pub fn can_pay_with_life(&self, cost: &ManaCost, max_life_payments: u8) -> bool {
+        if cost.has_special_symbols() {
+            return cost
+                .for_each_payment_option(max_life_payments, &mut |option, _| self.after_paying(option).is_some());
+        }
+
         // Check colored mana requirements
         if self.white < cost.white
             || self.blue < cost.blue
@@ -212,58 +550,122 @@ impl ManaPool {
     ///
     /// Returns Ok(()) if payment successful, Err if insufficient mana.
     pub fn pay_cost(&mut self, cost: &ManaCost) -> Result<(), String> {
-        // First check if we can pay
-        if !self.can_pay(cost) {
-            return Err(format!(
+        self.pay_cost_with_life(cost, 0).map(|_| ())
+    }
+
+    /// Pay a mana cost, paying up to `max_life_payments` Phyrexian symbols with life
+    ///
+    /// Hybrid and monocolored hybrid symbols are paid whichever way the pool allows.
+    /// Among the ways that work, the one paying the fewest Phyrexian symbols with
+    /// life is used, then the one spending the least mana. Returns how many
+    /// Phyrexian symbols were paid with life; the caller pays 2 life for each.
+    pub fn pay_cost_with_life(&mut self, cost: &ManaCost, max_life_payments: u8) -> Result<u8, String> {
+        let mut best: Option<(ManaPool, u8, u8)> = None;
+        cost.for_each_payment_option(max_life_payments, &mut |option, life_payments| {
+            if let Some(pool) = self.after_paying(option) {
+                let is_better =
+                    best.is_none_or(|(_, best_life, best_mana)| (life_payments, option.cmc()) < (best_life, best_mana));
+                if is_better {
+                    best = Some((pool, life_payments, option.cmc()));
+                }
+            }
+            false
+        });
+
+        match best {
+            Some((pool, life_payments, _)) => {
+                *self = pool;
+                Ok(life_payments)
+            }
+            None => Err(format!(
                 "Insufficient mana to pay cost {}. Pool has: {}W {}U {}B {}R {}G {}C",
                 cost, self.white, self.blue, self.black, self.red, self.green, self.colorless
-            ));
+            )),
         }
+    }
 
-        // Pay colored requirements first
-        self.white -= cost.white;
-        self.blue -= cost.blue;
-        self.black -= cost.black;
-        self.red -= cost.red;
-        self.green -= cost.green;
-        self.colorless -= cost.colorless;
-
-        // Pay generic cost using any remaining mana (WUBRG order)
-        let mut generic_remaining = cost.generic;
-
-        // Use white mana for generic
-        let white_used = generic_remaining.min(self.white);
-        self.white -= white_used;
-        generic_remaining -= white_used;
-
-        // Use blue mana for generic
-        let blue_used = generic_remaining.min(self.blue);
-        self.blue -= blue_used;
-        generic_remaining -= blue_used;
+    /// The pool left after paying a cost without hybrid or Phyrexian symbols, if it can be paid
+    ///
+    /// Colored requirements use non-snow mana first, so as much snow mana as possible
+    /// is left for {S}; generic mana is paid last, in WUBRG order.
+    fn after_paying(&self, cost: &ManaCost) -> Option<ManaPool> {
+        let mut pool = *self;
+        let required = [cost.white, cost.blue, cost.black, cost.red, cost.green, cost.colorless];
+
+        for (index, &need) in required.iter().enumerate() {
+            let available = pool.amount(index);
+            if available < need {
+                return None;
+            }
+            let from_plain = need.min(available - pool.snow[index]);
+            pool.snow[index] -= need - from_plain;
+            *pool.amount_mut(index) -= need;
+        }
 
-        // Use black mana for generic
-        let black_used = generic_remaining.min(self.black);
-        self.black -= black_used;
-        generic_remaining -= black_used;
+        let mut snow_remaining = cost.snow;
+        for index in 0..6 {
+            let used = snow_remaining.min(pool.snow[index]);
+            pool.snow[index] -= used;
+            *pool.amount_mut(index) -= used;
+            snow_remaining -= used;
+        }
+        if snow_remaining > 0 {
+            return None;
+        }
 
-        // Use red mana for generic
-        let red_used = generic_remaining.min(self.red);
-        self.red -= red_used;
-        generic_remaining -= red_used;
+        // Pay generic cost using any remaining mana (WUBRG order), keeping snow mana for last
+        let mut generic_remaining = cost.generic;
+        for use_snow in [false, true] {
+            for index in 0..6 {
+                let available = if use_snow {
+                    pool.snow[index]
+                } else {
+                    pool.amount(index) - pool.snow[index]
+                };
+                let used = generic_remaining.min(available);
+                if use_snow {
+                    pool.snow[index] -= used;
+                }
+                *pool.amount_mut(index) -= used;
+                generic_remaining -= used;
+            }
+        }
 
-        // Use green mana for generic
-        let green_used = generic_remaining.min(self.green);
-        self.green -= green_used;
-        generic_remaining -= green_used;
+        (generic_remaining == 0).then_some(pool)
+    }
 
-        // Use colorless mana for generic
-        let colorless_used = generic_remaining.min(self.colorless);
-        self.colorless -= colorless_used;
-        generic_remaining -= colorless_used;
+    /// Index of a color in the pool's WUBRG-then-colorless order
+    fn color_index(color: Color) -> usize {
+        match color {
+            Color::White => 0,
+            Color::Blue => 1,
+            Color::Black => 2,
+            Color::Red => 3,
+            Color::Green => 4,
+            Color::Colorless => 5,
+        }
+    }
 
-        debug_assert_eq!(generic_remaining, 0, "Failed to pay generic cost");
+    fn amount(&self, index: usize) -> u8 {
+        match index {
+            0 => self.white,
+            1 => self.blue,
+            2 => self.black,
+            3 => self.red,
+            4 => self.green,
+            _ => self.colorless,
+        }
+    }
 
-        Ok(())
+    fn amount_mut(&mut self, index: usize) -> &mut u8 {
+        match index {
+            0 => &mut self.white,
+            1 => &mut self.blue,
+            2 => &mut self.black,
+            3 => &mut self.red,
+            4 => &mut self.green,
+            _ => &mut self.colorless,
+        }
     }
 
     /// Total mana in pool
@@ -421,4 +823,133 @@ mod tests {
         pool.clear();
         assert_eq!(pool.total(), 0);
     }
+
+    #[test]
+    fn test_special_symbol_parsing() {
+        // Forge card script notation
+        let cost = ManaCost::from_string("1 R/G R/G B/P 2/U S");
+        assert_eq!(cost.generic, 1);
+        assert_eq!(cost.hybrid[3], 2); // R/G
+        assert_eq!(cost.phyrexian[2], 1); // B/P
+        assert_eq!(cost.two_hybrid[1], 1); // 2/U
+        assert_eq!(cost.snow, 1);
+        assert_eq!(cost.red, 0);
+        assert_eq!(cost.cmc(), 1 + 2 + 1 + 2 + 1);
+        assert!(cost.has_special_symbols());
+        assert!(!ManaCost::from_string("2RR").has_special_symbols());
+
+        // Brace notation, with the pair written in either order
+        let braces = ManaCost::from_string("{1}{G/R}{G/R}{B/P}{2/U}{S}");
+        assert_eq!(braces, cost);
+
+        // Display parses back to the same cost
+        assert_eq!(ManaCost::from_string(&cost.to_string()), cost);
+    }
+
+    #[test]
+    fn test_contains_color() {
+        let cost = ManaCost::from_string("W/U 2/B R/P");
+        assert!(cost.contains_color(Color::White));
+        assert!(cost.contains_color(Color::Blue));
+        assert!(cost.contains_color(Color::Black));
+        assert!(cost.contains_color(Color::Red));
+        assert!(!cost.contains_color(Color::Green));
+        assert!(!ManaCost::from_string("3 S").contains_color(Color::Green));
+    }
+
+    #[test]
+    fn test_pay_hybrid_with_either_color() {
+        let cost = ManaCost::from_string("R/G R/G");
+
+        let mut pool = ManaPool::new();
+        pool.add_color(Color::Red);
+        pool.add_color(Color::Green);
+        assert!(pool.pay_cost(&cost).is_ok());
+        assert_eq!(pool.total(), 0);
+
+        // Hybrid symbols can't be paid with generic mana of another color
+        let mut pool = ManaPool::new();
+        pool.add_color(Color::Red);
+        pool.add_color(Color::Blue);
+        assert!(!pool.can_pay(&cost));
+        assert!(pool.pay_cost(&cost).is_err());
+        assert_eq!(pool.total(), 2);
+    }
+
+    #[test]
+    fn test_pay_two_hybrid_with_color_or_generic() {
+        let cost = ManaCost::from_string("2/W");
+
+        let mut pool = ManaPool::new();
+        pool.add_color(Color::White);
+        pool.add_color(Color::Blue);
+        assert!(pool.pay_cost(&cost).is_ok());
+        // The cheaper way, with {W}, is used
+        assert_eq!(pool.white, 0);
+        assert_eq!(pool.blue, 1);
+
+        let mut pool = ManaPool::new();
+        pool.add_color(Color::Blue);
+        pool.add_color(Color::Black);
+        assert!(pool.pay_cost(&cost).is_ok());
+        assert_eq!(pool.total(), 0);
+    }
+
+    #[test]
+    fn test_pay_phyrexian_with_mana_or_life() {
+        let cost = ManaCost::from_string("1 B/P");
+
+        // Mana is used when the pool has it
+        let mut pool = ManaPool::new();
+        pool.add_color(Color::Black);
+        pool.add_color(Color::Red);
+        assert_eq!(pool.pay_cost_with_life(&cost, 10), Ok(0));
+        assert_eq!(pool.total(), 0);
+
+        // Otherwise 2 life pays for the symbol, if allowed
+        let mut pool = ManaPool::new();
+        pool.add_color(Color::Red);
+        assert!(!pool.can_pay(&cost));
+        assert!(pool.can_pay_with_life(&cost, 1));
+        assert!(pool.pay_cost_with_life(&cost, 0).is_err());
+        assert_eq!(pool.pay_cost_with_life(&cost, 1), Ok(1));
+        assert_eq!(pool.total(), 0);
+    }
+
+    #[test]
+    fn test_pay_snow_with_snow_mana() {
+        let cost = ManaCost::from_string("R S");
+
+        // Two red, only one of them snow: the non-snow red pays {R}
+        let mut pool = ManaPool::new();
+        pool.add_snow_color(Color::Red);
+        pool.add_color(Color::Red);
+        assert!(pool.pay_cost(&cost).is_ok());
+        assert_eq!(pool.total(), 0);
+        assert_eq!(pool.snow, [0; 6]);
+
+        // A single snow red can't pay for both symbols
+        let mut pool = ManaPool::new();
+        pool.add_snow_color(Color::Red);
+        pool.add_color(Color::Green);
+        assert!(!pool.can_pay(&cost));
+
+        // Non-snow mana can't pay {S}
+        let mut pool = ManaPool::new();
+        pool.add_color(Color::Red);
+        pool.add_color(Color::Red);
+        assert!(!pool.can_pay(&cost));
+
+        // Undoing snow mana removes it from the snow count too
+        let mut pool = ManaPool::new();
+        pool.add_color(Color::Green);
+        pool.add_snow_color(Color::Green);
+        pool.remove_mana(&ManaCost {
+            green: 1,
+            snow: 1,
+            ..ManaCost::new()
+        });
+        assert_eq!(pool.green, 1);
+        assert_eq!(pool.snow[4], 0);
+    }
 }
@@ -6,16 +6,26 @@ pub mod effects;
 pub mod entity;
 pub mod mana;
 pub mod player;
+pub mod replacement;
+pub mod selector;
 pub mod spell_ability;
+pub mod static_ability;
+pub mod token;
 pub mod types;
 
 pub use card::{Card, CardType};
 pub use costs::Cost;
-pub use effects::{ActivatedAbility, Effect, Keyword, TargetRef, Trigger, TriggerEvent};
+pub use effects::{
+    ActivatedAbility, Effect, Keyword, SpellMode, TargetRef, Trigger, TriggerEvent, AMOUNT_X, COUNT_ANY, COUNT_X,
+};
 pub use entity::{EntityId, EntityStore, GameEntity};
-pub use mana::{Color, ManaCost, ManaPool};
+pub use mana::{Color, ManaCost, ManaPool, HYBRID_PAIRS, WUBRG};
 pub use player::Player;
+pub use replacement::{ReplacementAction, ReplacementEffect, ReplacementEvent, ReplacementId, ReplacementKey};
+pub use selector::{Selector, SelectorContext};
 pub use spell_ability::SpellAbility;
+pub use static_ability::{ContinuousModification, DerivedCharacteristics, Layer, StaticAbility};
+pub use token::TokenDefinition;
 pub use types::{CardName, CounterType, PlayerName, Subtype};
 
 // Type aliases for strongly-typed entity IDs
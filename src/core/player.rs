@@ -1,6 +1,6 @@
 //! Player representation
 
-use crate::core::{GameEntity, ManaPool, PlayerId, PlayerName};
+use crate::core::{CardId, GameEntity, ManaPool, PlayerId, PlayerName};
 use serde::{Deserialize, Serialize};
 
 /// Represents a player in the game
@@ -21,6 +21,20 @@ pub struct Player {
     /// Has the player lost?
     pub has_lost: bool,
 
+    /// Has the player left a game that went on without them? (MTG Rules 800.4a)
+    #[serde(default)]
+    pub left_game: bool,
+
+    /// Poison counters (MTG Rules 704.5c: ten or more and the player loses)
+    pub poison_counters: u8,
+
+    /// Combat damage dealt to the player by each commander (MTG Rules 903.10a)
+    #[serde(default)]
+    pub commander_damage: Vec<(CardId, i32)>,
+
+    /// Set when the player attempted to draw from an empty library (MTG Rules 704.5b)
+    pub drew_from_empty_library: bool,
+
     /// Lands played this turn
     pub lands_played_this_turn: u8,
 
@@ -39,6 +53,10 @@ impl Player {
             life: starting_life,
             mana_pool: ManaPool::new(),
             has_lost: false,
+            left_game: false,
+            poison_counters: 0,
+            commander_damage: Vec::new(),
+            drew_from_empty_library: false,
             lands_played_this_turn: 0,
             max_lands_per_turn: 1,
             max_hand_size: 7, // Standard MTG hand size limit
@@ -0,0 +1,149 @@
+//! Replacement and prevention effects (MTG Rules 614, 615, 616)
+//!
+//! Forge writes replacement effects on `R:` lines. What happens instead of the
+//! event is an SVar named by `ReplaceWith$`:
+//!
+//! ```text
+//! R:Event$ Moved | ValidCard$ Card.Self | Origin$ Battlefield | Destination$ Graveyard | ReplaceWith$ Exile | Description$ ...
+//! SVar:Exile:DB$ ChangeZone | Hidden$ True | Origin$ All | Destination$ Exile | Defined$ ReplacedCard
+//! ```
+//!
+//! A permanent's [`ReplacementEffect`]s function while it's on the battlefield;
+//! the ones that only affect the card itself ("CARDNAME enters the battlefield
+//! tapped") also function as it moves from another zone (MTG Rules 614.12).
+//! Spells like Fog create effects that last until end of turn, which `GameState`
+//! keeps in its replacement registry. When several effects would modify the same
+//! event, the affected player's ranking decides which one applies first (616.1).
+
+use crate::core::{CardId, Effect, EntityId, Selector};
+use crate::zones::Zone;
+use serde::{Deserialize, Serialize};
+
+/// ID of a replacement effect in `GameState`'s registry
+pub type ReplacementId = EntityId<ReplacementEffect>;
+
+/// The kind of event a replacement effect watches for (Forge `Event$`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplacementEvent {
+    /// A card would move between zones (`Event$ Moved`); `None` matches any zone
+    Moved {
+        origin: Option<Zone>,
+        destination: Option<Zone>,
+    },
+    /// Damage would be dealt (`Event$ DamageDone`); `IsCombat$ True` limits it to combat damage
+    DamageDone { combat_only: bool },
+    /// A player would draw a card (`Event$ Draw`)
+    Draw,
+    /// A player would gain life (`Event$ GainLife`)
+    GainLife,
+    /// A player would lose life (`Event$ LifeReduced`)
+    LoseLife,
+}
+
+/// What a replacement effect does to the event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplacementAction {
+    /// The permanent enters the battlefield tapped (`DB$ Tap | ETB$ True`)
+    EnterTapped,
+    /// The card is put into another zone instead (`DB$ ChangeZone | Destination$ Exile`)
+    ChangeZone(Zone),
+    /// The event doesn't happen; for damage, the damage is prevented (MTG Rules 615)
+    Prevent,
+    /// The next this much damage is prevented (MTG Rules 615.7); a shield in the
+    /// registry is used up as it prevents damage, and ends once none is left
+    PreventAmount(u32),
+    /// These effects happen instead of the event
+    Instead(Vec<Effect>),
+}
+
+/// A replacement or prevention effect (MTG Rules 614.1, 615.1)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplacementEffect {
+    pub event: ReplacementEvent,
+    /// What the event has to affect, evaluated relative to the effect's source:
+    /// the moving card (`ValidCard$`), the damaged permanent or player
+    /// (`ValidTarget$`), or the player drawing or gaining or losing life
+    /// (`ValidPlayer$`). `None` matches anything.
+    pub affected: Option<Selector>,
+    /// The source damage has to come from (`ValidSource$`)
+    pub valid_source: Option<Selector>,
+    pub action: ReplacementAction,
+    /// Whether the effect ends once it has applied, like a shield that prevents
+    /// "the next time" damage would be dealt (its `ReplaceWith$` exiles the effect)
+    pub single_use: bool,
+    /// Rules text (`Description$`)
+    pub description: String,
+}
+
+impl ReplacementEffect {
+    /// Is this a prevention effect (MTG Rules 615.1)?
+    pub fn is_prevention(&self) -> bool {
+        matches!(self.event, ReplacementEvent::DamageDone { .. })
+            && matches!(
+                self.action,
+                ReplacementAction::Prevent | ReplacementAction::PreventAmount(_)
+            )
+    }
+
+    /// How much damage a "prevent the next N damage" shield still prevents
+    pub fn shield_remaining(&self) -> Option<u32> {
+        match self.action {
+            ReplacementAction::PreventAmount(remaining) => Some(remaining),
+            _ => None,
+        }
+    }
+
+    /// Does this effect only affect a card moving to the battlefield, so that it
+    /// changes how the card enters (MTG Rules 614.1c)?
+    pub fn modifies_entering(&self) -> bool {
+        matches!(
+            self.event,
+            ReplacementEvent::Moved {
+                destination: Some(Zone::Battlefield),
+                ..
+            }
+        )
+    }
+}
+
+/// Identifies a replacement effect that is currently functioning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ReplacementKey {
+    /// The `index`th replacement effect printed on a card
+    Card { card: CardId, index: usize },
+    /// An effect in `GameState`'s registry, created by a resolved spell or ability
+    Registered(ReplacementId),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prevention_and_entering_effects() {
+        let fog = ReplacementEffect {
+            event: ReplacementEvent::DamageDone { combat_only: true },
+            affected: None,
+            valid_source: None,
+            action: ReplacementAction::Prevent,
+            single_use: false,
+            description: "Prevent all combat damage".to_string(),
+        };
+        assert!(fog.is_prevention());
+        assert!(!fog.modifies_entering());
+
+        let tapland = ReplacementEffect {
+            event: ReplacementEvent::Moved {
+                origin: None,
+                destination: Some(Zone::Battlefield),
+            },
+            affected: Some(Selector::parse("Card.Self")),
+            valid_source: None,
+            action: ReplacementAction::EnterTapped,
+            single_use: false,
+            description: "CARDNAME enters the battlefield tapped.".to_string(),
+        };
+        assert!(tapland.modifies_entering());
+        assert!(!tapland.is_prevention());
+    }
+}
@@ -0,0 +1,601 @@
+//! Selector expressions for targeting and "all" effects
+//!
+//! Forge describes which objects an ability may affect with property
+//! expressions such as `ValidTgts$ Creature.nonArtifact+nonBlack` or
+//! `ValidCards$ Permanent.YouCtrl+tapped`:
+//!
+//! - `,` separates alternatives (`Creature,Player`)
+//! - `.` separates the base type from its properties (`Creature.OppCtrl`)
+//! - `+` joins properties that must all hold (`nonArtifact+nonBlack`)
+//!
+//! [`Selector::parse`] compiles an expression once (at card load time) and
+//! [`Selector::matches_card`]/[`Selector::matches_player`] evaluate it during
+//! the game. Properties we don't understand yet are kept as
+//! [`SelectorProperty::Unknown`]; they match everything so that cards remain
+//! playable, and the loader reports them.
+
+use crate::core::{Card, CardId, CardType, Color, Keyword, PlayerId, Subtype};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Context needed to evaluate relative properties like `YouCtrl` or `Other`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectorContext {
+    /// The player controlling the spell or ability ("You")
+    pub controller: PlayerId,
+    /// The spell or permanent the selector belongs to ("Self")
+    pub source: Option<CardId>,
+    /// The permanent the source is attached to (`EnchantedBy`, `EquippedBy`)
+    pub attached_to: Option<CardId>,
+}
+
+impl SelectorContext {
+    pub fn new(controller: PlayerId, source: Option<CardId>) -> Self {
+        SelectorContext {
+            controller,
+            source,
+            attached_to: None,
+        }
+    }
+
+    /// Set the permanent the source is attached to
+    pub fn with_attached_to(mut self, attached_to: Option<CardId>) -> Self {
+        self.attached_to = attached_to;
+        self
+    }
+}
+
+/// The base of a selector alternative (the part before the first `.`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelectorBase {
+    /// `Any` - any creature, planeswalker or player
+    Any,
+    /// `Card` - any card
+    Card,
+    /// `Permanent` - any permanent card
+    Permanent,
+    /// A card type, e.g. `Creature`
+    Type(CardType),
+    /// A subtype, e.g. `Goblin`
+    Subtype(Subtype),
+    /// `Player` - any player
+    Player,
+    /// `Opponent` - an opponent of the controller
+    Opponent,
+    /// `You` - the controller
+    You,
+}
+
+/// Numeric characteristic used by comparison properties
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelectorStat {
+    Power,
+    Toughness,
+    Cmc,
+}
+
+/// Comparison operator used by properties like `powerLE2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareOp {
+    LT,
+    LE,
+    EQ,
+    NE,
+    GE,
+    GT,
+}
+
+impl CompareOp {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "LT" => Some(CompareOp::LT),
+            "LE" => Some(CompareOp::LE),
+            "EQ" => Some(CompareOp::EQ),
+            "NE" => Some(CompareOp::NE),
+            "GE" => Some(CompareOp::GE),
+            "GT" => Some(CompareOp::GT),
+            _ => None,
+        }
+    }
+
+    /// Apply the comparison `lhs OP rhs`
+    pub fn compare(&self, lhs: i32, rhs: i32) -> bool {
+        match self {
+            CompareOp::LT => lhs < rhs,
+            CompareOp::LE => lhs <= rhs,
+            CompareOp::EQ => lhs == rhs,
+            CompareOp::NE => lhs != rhs,
+            CompareOp::GE => lhs >= rhs,
+            CompareOp::GT => lhs > rhs,
+        }
+    }
+}
+
+/// A single property after the `.` of a selector alternative
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelectorProperty {
+    /// `YouCtrl` - controlled by the controller
+    YouCtrl,
+    /// `OppCtrl` - controlled by an opponent
+    OppCtrl,
+    /// `YouOwn` - owned by the controller
+    YouOwn,
+    /// `OppOwn` - owned by an opponent
+    OppOwn,
+    /// `tapped`
+    Tapped,
+    /// `untapped`
+    Untapped,
+    /// `Self` - the source itself
+    SelfCard,
+    /// `Other` - anything but the source
+    Other,
+    /// `EnchantedBy`/`EquippedBy`/`AttachedBy` - the permanent the source is attached to
+    AttachedBy,
+    /// A card type (`Creature`) or its negation (`nonCreature`)
+    Type { card_type: CardType, negated: bool },
+    /// A color (`Black`) or its negation (`nonBlack`)
+    Color { color: Color, negated: bool },
+    /// `Colorless`
+    Colorless,
+    /// `MultiColor`
+    MultiColor,
+    /// A subtype (`Goblin`) or its negation (`nonGoblin`)
+    Subtype { subtype: Subtype, negated: bool },
+    /// A numeric comparison such as `powerLE2` or `cmcGE3`
+    Compare {
+        stat: SelectorStat,
+        op: CompareOp,
+        value: i32,
+    },
+    /// `withFlying` / `withoutFlying`
+    Keyword { keyword: Keyword, negated: bool },
+    /// `Opponent` (on a `Player` base)
+    Opponent,
+    /// `You` (on a `Player` base)
+    You,
+    /// A property we can't evaluate yet (matches everything)
+    Unknown(String),
+}
+
+pub(crate) fn parse_card_type(s: &str) -> Option<CardType> {
+    match s {
+        "Creature" => Some(CardType::Creature),
+        "Instant" => Some(CardType::Instant),
+        "Sorcery" => Some(CardType::Sorcery),
+        "Enchantment" => Some(CardType::Enchantment),
+        "Artifact" => Some(CardType::Artifact),
+        "Land" => Some(CardType::Land),
+        "Planeswalker" => Some(CardType::Planeswalker),
+        _ => None,
+    }
+}
+
+pub(crate) fn parse_color(s: &str) -> Option<Color> {
+    match s {
+        "White" => Some(Color::White),
+        "Blue" => Some(Color::Blue),
+        "Black" => Some(Color::Black),
+        "Red" => Some(Color::Red),
+        "Green" => Some(Color::Green),
+        _ => None,
+    }
+}
+
+/// Subtypes look like capitalized words ("Goblin", "Aura")
+fn looks_like_subtype(s: &str) -> bool {
+    s.chars().next().is_some_and(|c| c.is_ascii_uppercase()) && s.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+impl SelectorProperty {
+    fn parse(s: &str) -> Self {
+        match s {
+            "YouCtrl" => return SelectorProperty::YouCtrl,
+            "OppCtrl" => return SelectorProperty::OppCtrl,
+            "YouOwn" => return SelectorProperty::YouOwn,
+            "OppOwn" => return SelectorProperty::OppOwn,
+            "tapped" => return SelectorProperty::Tapped,
+            "untapped" => return SelectorProperty::Untapped,
+            "Self" => return SelectorProperty::SelfCard,
+            "Other" => return SelectorProperty::Other,
+            "EnchantedBy" | "EquippedBy" | "AttachedBy" => return SelectorProperty::AttachedBy,
+            "Colorless" => return SelectorProperty::Colorless,
+            "MultiColor" => return SelectorProperty::MultiColor,
+            "Opponent" => return SelectorProperty::Opponent,
+            "You" => return SelectorProperty::You,
+            _ => {}
+        }
+
+        // Numeric comparisons: powerLE2, toughnessGE4, cmcLT3
+        for (prefix, stat) in [
+            ("power", SelectorStat::Power),
+            ("toughness", SelectorStat::Toughness),
+            ("cmc", SelectorStat::Cmc),
+        ] {
+            if let Some(rest) = s.strip_prefix(prefix) {
+                if rest.len() > 2 {
+                    let (op, value) = rest.split_at(2);
+                    if let (Some(op), Ok(value)) = (CompareOp::parse(op), value.parse::<i32>()) {
+                        return SelectorProperty::Compare { stat, op, value };
+                    }
+                }
+                return SelectorProperty::Unknown(s.to_string());
+            }
+        }
+
+        // Keyword presence: withFlying / withoutFlying
+        if let Some(name) = s.strip_prefix("without") {
+            return match Keyword::from_name(name) {
+                Some(keyword) => SelectorProperty::Keyword { keyword, negated: true },
+                None => SelectorProperty::Unknown(s.to_string()),
+            };
+        }
+        if let Some(name) = s.strip_prefix("with") {
+            return match Keyword::from_name(name) {
+                Some(keyword) => SelectorProperty::Keyword {
+                    keyword,
+                    negated: false,
+                },
+                None => SelectorProperty::Unknown(s.to_string()),
+            };
+        }
+
+        let (negated, name) = match s.strip_prefix("non") {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        if let Some(card_type) = parse_card_type(name) {
+            return SelectorProperty::Type { card_type, negated };
+        }
+        if let Some(color) = parse_color(name) {
+            return SelectorProperty::Color { color, negated };
+        }
+        if looks_like_subtype(name) {
+            return SelectorProperty::Subtype {
+                subtype: Subtype::new(name),
+                negated,
+            };
+        }
+
+        SelectorProperty::Unknown(s.to_string())
+    }
+
+    fn matches_card(&self, card: &Card, ctx: &SelectorContext) -> bool {
+        match self {
+            SelectorProperty::YouCtrl => card.controller == ctx.controller,
+            SelectorProperty::OppCtrl => card.controller != ctx.controller,
+            SelectorProperty::YouOwn => card.owner == ctx.controller,
+            SelectorProperty::OppOwn => card.owner != ctx.controller,
+            SelectorProperty::Tapped => card.tapped,
+            SelectorProperty::Untapped => !card.tapped,
+            SelectorProperty::SelfCard => ctx.source == Some(card.id),
+            SelectorProperty::Other => ctx.source != Some(card.id),
+            SelectorProperty::AttachedBy => ctx.attached_to == Some(card.id),
+            SelectorProperty::Type { card_type, negated } => card.is_type(card_type) != *negated,
+            SelectorProperty::Color { color, negated } => card.current_colors().contains(color) != *negated,
+            SelectorProperty::Colorless => card.current_colors().iter().all(|c| *c == Color::Colorless),
+            SelectorProperty::MultiColor => {
+                card.current_colors().iter().filter(|c| **c != Color::Colorless).count() > 1
+            }
+            SelectorProperty::Subtype { subtype, negated } => card.current_subtypes().contains(subtype) != *negated,
+            SelectorProperty::Compare { stat, op, value } => {
+                let actual = match stat {
+                    SelectorStat::Power => card.current_power() as i32,
+                    SelectorStat::Toughness => card.current_toughness() as i32,
+                    SelectorStat::Cmc => card.mana_cost.cmc() as i32,
+                };
+                op.compare(actual, *value)
+            }
+            SelectorProperty::Keyword { keyword, negated } => card.has_keyword(keyword) != *negated,
+            // Player-only properties never match cards
+            SelectorProperty::Opponent | SelectorProperty::You => false,
+            SelectorProperty::Unknown(_) => true,
+        }
+    }
+
+    fn matches_player(&self, player: PlayerId, ctx: &SelectorContext) -> bool {
+        match self {
+            SelectorProperty::Opponent | SelectorProperty::OppCtrl => player != ctx.controller,
+            SelectorProperty::You | SelectorProperty::YouCtrl => player == ctx.controller,
+            SelectorProperty::Unknown(_) => true,
+            // Card properties never match players
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for SelectorProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectorProperty::YouCtrl => write!(f, "YouCtrl"),
+            SelectorProperty::OppCtrl => write!(f, "OppCtrl"),
+            SelectorProperty::YouOwn => write!(f, "YouOwn"),
+            SelectorProperty::OppOwn => write!(f, "OppOwn"),
+            SelectorProperty::Tapped => write!(f, "tapped"),
+            SelectorProperty::Untapped => write!(f, "untapped"),
+            SelectorProperty::SelfCard => write!(f, "Self"),
+            SelectorProperty::Other => write!(f, "Other"),
+            SelectorProperty::AttachedBy => write!(f, "AttachedBy"),
+            SelectorProperty::Type { card_type, negated } => {
+                write!(f, "{}{card_type:?}", if *negated { "non" } else { "" })
+            }
+            SelectorProperty::Color { color, negated } => {
+                write!(f, "{}{color:?}", if *negated { "non" } else { "" })
+            }
+            SelectorProperty::Colorless => write!(f, "Colorless"),
+            SelectorProperty::MultiColor => write!(f, "MultiColor"),
+            SelectorProperty::Subtype { subtype, negated } => {
+                write!(f, "{}{subtype}", if *negated { "non" } else { "" })
+            }
+            SelectorProperty::Compare { stat, op, value } => {
+                let stat = match stat {
+                    SelectorStat::Power => "power",
+                    SelectorStat::Toughness => "toughness",
+                    SelectorStat::Cmc => "cmc",
+                };
+                write!(f, "{stat}{op:?}{value}")
+            }
+            SelectorProperty::Keyword { keyword, negated } => {
+                write!(f, "{}{keyword:?}", if *negated { "without" } else { "with" })
+            }
+            SelectorProperty::Opponent => write!(f, "Opponent"),
+            SelectorProperty::You => write!(f, "You"),
+            SelectorProperty::Unknown(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// One `,`-separated alternative: a base plus properties that must all hold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectorClause {
+    pub base: SelectorBase,
+    pub properties: Vec<SelectorProperty>,
+}
+
+impl SelectorClause {
+    fn parse(s: &str) -> Self {
+        let (base_str, props_str) = match s.split_once('.') {
+            Some((base, props)) => (base, Some(props)),
+            None => (s, None),
+        };
+
+        let mut properties = Vec::new();
+        let base = match base_str {
+            "Any" => SelectorBase::Any,
+            "Card" => SelectorBase::Card,
+            "Permanent" => SelectorBase::Permanent,
+            "Player" => SelectorBase::Player,
+            "Opponent" => SelectorBase::Opponent,
+            "You" => SelectorBase::You,
+            other => match parse_card_type(other) {
+                Some(card_type) => SelectorBase::Type(card_type),
+                None if looks_like_subtype(other) => SelectorBase::Subtype(Subtype::new(other)),
+                None => {
+                    // Keep the whole base so it gets reported, but don't restrict on it
+                    properties.push(SelectorProperty::Unknown(other.to_string()));
+                    SelectorBase::Card
+                }
+            },
+        };
+
+        if let Some(props) = props_str {
+            properties.extend(props.split('+').filter(|p| !p.is_empty()).map(SelectorProperty::parse));
+        }
+
+        SelectorClause { base, properties }
+    }
+
+    fn is_player_clause(&self) -> bool {
+        matches!(
+            self.base,
+            SelectorBase::Any | SelectorBase::Player | SelectorBase::Opponent | SelectorBase::You
+        )
+    }
+
+    fn matches_card(&self, card: &Card, ctx: &SelectorContext) -> bool {
+        let base_matches = match &self.base {
+            SelectorBase::Any => card.is_creature() || card.is_type(&CardType::Planeswalker),
+            SelectorBase::Card => true,
+            SelectorBase::Permanent => !card.is_instant() && !card.is_type(&CardType::Sorcery),
+            SelectorBase::Type(card_type) => card.is_type(card_type),
+            SelectorBase::Subtype(subtype) => card.current_subtypes().contains(subtype),
+            SelectorBase::Player | SelectorBase::Opponent | SelectorBase::You => false,
+        };
+        base_matches && self.properties.iter().all(|p| p.matches_card(card, ctx))
+    }
+
+    fn matches_player(&self, player: PlayerId, ctx: &SelectorContext) -> bool {
+        let base_matches = match self.base {
+            SelectorBase::Any | SelectorBase::Player => true,
+            SelectorBase::Opponent => player != ctx.controller,
+            SelectorBase::You => player == ctx.controller,
+            _ => false,
+        };
+        // Properties on "Any" describe cards ("Any" has none in practice)
+        base_matches
+            && (self.base == SelectorBase::Any || self.properties.iter().all(|p| p.matches_player(player, ctx)))
+    }
+}
+
+/// A compiled Forge selector expression (e.g. `Creature.nonArtifact+nonBlack`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selector {
+    clauses: Vec<SelectorClause>,
+}
+
+impl Selector {
+    /// Compile a selector expression
+    ///
+    /// Parsing never fails; unrecognized properties become
+    /// [`SelectorProperty::Unknown`] (see [`Selector::unknown_properties`]).
+    pub fn parse(expr: &str) -> Self {
+        let clauses = expr
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(SelectorClause::parse)
+            .collect();
+        Selector { clauses }
+    }
+
+    /// The `,`-separated alternatives of this selector
+    pub fn clauses(&self) -> &[SelectorClause] {
+        &self.clauses
+    }
+
+    /// Does this card satisfy any alternative?
+    pub fn matches_card(&self, card: &Card, ctx: &SelectorContext) -> bool {
+        self.clauses.iter().any(|c| c.matches_card(card, ctx))
+    }
+
+    /// Does this player satisfy any alternative?
+    pub fn matches_player(&self, player: PlayerId, ctx: &SelectorContext) -> bool {
+        self.clauses.iter().any(|c| c.matches_player(player, ctx))
+    }
+
+    /// Can this selector select players at all?
+    pub fn can_select_players(&self) -> bool {
+        self.clauses.iter().any(|c| c.is_player_clause())
+    }
+
+    /// Can this selector select cards at all?
+    pub fn can_select_cards(&self) -> bool {
+        self.clauses.iter().any(|c| {
+            !matches!(
+                c.base,
+                SelectorBase::Player | SelectorBase::Opponent | SelectorBase::You
+            )
+        })
+    }
+
+    /// Does this selector only ever select its source (`Card.Self`)?
+    pub fn is_self_only(&self) -> bool {
+        !self.clauses.is_empty()
+            && self
+                .clauses
+                .iter()
+                .all(|c| c.properties.contains(&SelectorProperty::SelfCard))
+    }
+
+    /// Properties that could not be compiled (they match everything)
+    pub fn unknown_properties(&self) -> impl Iterator<Item = &str> {
+        self.clauses.iter().flat_map(|c| {
+            c.properties.iter().filter_map(|p| match p {
+                SelectorProperty::Unknown(s) => Some(s.as_str()),
+                _ => None,
+            })
+        })
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            match &clause.base {
+                SelectorBase::Any => write!(f, "Any")?,
+                SelectorBase::Card => write!(f, "Card")?,
+                SelectorBase::Permanent => write!(f, "Permanent")?,
+                SelectorBase::Type(t) => write!(f, "{t:?}")?,
+                SelectorBase::Subtype(s) => write!(f, "{s}")?,
+                SelectorBase::Player => write!(f, "Player")?,
+                SelectorBase::Opponent => write!(f, "Opponent")?,
+                SelectorBase::You => write!(f, "You")?,
+            }
+            for (j, prop) in clause.properties.iter().enumerate() {
+                write!(f, "{}{prop}", if j == 0 { "." } else { "+" })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creature(id: u32, owner: u32, power: i8, colors: &[Color]) -> Card {
+        let mut card = Card::new(CardId::new(id), "Test Creature", PlayerId::new(owner));
+        card.types.push(CardType::Creature);
+        card.power = Some(power);
+        card.toughness = Some(power);
+        card.colors.extend(colors.iter().copied());
+        card
+    }
+
+    #[test]
+    fn test_nonartifact_nonblack() {
+        let selector = Selector::parse("Creature.nonArtifact+nonBlack");
+        let ctx = SelectorContext::new(PlayerId::new(0), None);
+
+        let green = creature(10, 1, 2, &[Color::Green]);
+        let black = creature(11, 1, 2, &[Color::Black]);
+        let mut artifact = creature(12, 1, 2, &[Color::Colorless]);
+        artifact.types.push(CardType::Artifact);
+
+        assert!(selector.matches_card(&green, &ctx));
+        assert!(!selector.matches_card(&black, &ctx));
+        assert!(!selector.matches_card(&artifact, &ctx));
+        assert_eq!(selector.unknown_properties().count(), 0);
+    }
+
+    #[test]
+    fn test_control_and_tapped() {
+        let you = PlayerId::new(0);
+        let ctx = SelectorContext::new(you, None);
+
+        let mut mine = creature(10, 0, 2, &[Color::Green]);
+        let theirs = creature(11, 1, 2, &[Color::Green]);
+
+        assert!(Selector::parse("Card.OppCtrl").matches_card(&theirs, &ctx));
+        assert!(!Selector::parse("Card.OppCtrl").matches_card(&mine, &ctx));
+
+        let tapped_mine = Selector::parse("Permanent.YouCtrl+tapped");
+        assert!(!tapped_mine.matches_card(&mine, &ctx));
+        mine.tap();
+        assert!(tapped_mine.matches_card(&mine, &ctx));
+    }
+
+    #[test]
+    fn test_power_comparison() {
+        let selector = Selector::parse("Creature.powerLE2");
+        let ctx = SelectorContext::new(PlayerId::new(0), None);
+
+        assert!(selector.matches_card(&creature(10, 1, 2, &[]), &ctx));
+        assert!(!selector.matches_card(&creature(11, 1, 3, &[]), &ctx));
+    }
+
+    #[test]
+    fn test_players_and_alternatives() {
+        let you = PlayerId::new(0);
+        let opp = PlayerId::new(1);
+        let ctx = SelectorContext::new(you, None);
+
+        let any = Selector::parse("Any");
+        assert!(any.can_select_players());
+        assert!(any.matches_player(opp, &ctx));
+        assert!(any.matches_card(&creature(10, 1, 2, &[]), &ctx));
+
+        let creature_or_player = Selector::parse("Creature,Player");
+        assert!(creature_or_player.can_select_cards());
+        assert!(creature_or_player.matches_player(you, &ctx));
+
+        let opponent = Selector::parse("Player.Opponent");
+        assert!(opponent.matches_player(opp, &ctx));
+        assert!(!opponent.matches_player(you, &ctx));
+        assert!(!opponent.can_select_cards());
+    }
+
+    #[test]
+    fn test_unknown_properties_are_permissive() {
+        let selector = Selector::parse("Creature.attackingLKI+nonBlack");
+        let ctx = SelectorContext::new(PlayerId::new(0), None);
+
+        assert_eq!(selector.unknown_properties().collect::<Vec<_>>(), vec!["attackingLKI"]);
+        assert!(selector.matches_card(&creature(10, 1, 2, &[Color::Red]), &ctx));
+        assert!(!selector.matches_card(&creature(11, 1, 2, &[Color::Black]), &ctx));
+        assert_eq!(selector.to_string(), "Creature.attackingLKI+nonBlack");
+    }
+}
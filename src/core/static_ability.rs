@@ -0,0 +1,199 @@
+//! Static abilities and continuous effects (MTG Rules 604, 611, 613)
+//!
+//! Forge writes static abilities on `S:` lines; the continuous ones look like
+//!
+//! ```text
+//! S:Mode$ Continuous | Affected$ Creature.YouCtrl | AddPower$ 1 | AddToughness$ 1 | Description$ ...
+//! ```
+//!
+//! A [`StaticAbility`] holds the permanents it affects and the
+//! [`ContinuousModification`]s it makes to them. `GameState::apply_continuous_effects`
+//! applies the modifications of every permanent's static abilities layer by layer
+//! (MTG Rules 613.1) and stores the results on each card as
+//! [`DerivedCharacteristics`], which `Card`'s accessors read.
+
+use crate::core::{CardType, Color, Keyword, PlayerId, Selector, Subtype};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+/// Layers in which continuous effects are applied (MTG Rules 613.1)
+///
+/// Copy (layer 1) and text-changing (layer 3) effects are not supported. Layer 7 is split
+/// into its sublayers; 7c also holds the "until end of turn" bonuses and
+/// counters, which live on the card itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Layer {
+    /// Layer 2: control-changing effects
+    Control,
+    /// Layer 4: type-changing effects
+    Type,
+    /// Layer 5: color-changing effects
+    Color,
+    /// Layer 6: ability-adding and ability-removing effects
+    Ability,
+    /// Layer 7b: effects that set power and/or toughness
+    SetPowerToughness,
+    /// Layer 7c: effects that modify power and/or toughness
+    ModifyPowerToughness,
+}
+
+impl Layer {
+    /// All supported layers, in the order they're applied
+    pub const ALL: [Layer; 6] = [
+        Layer::Control,
+        Layer::Type,
+        Layer::Color,
+        Layer::Ability,
+        Layer::SetPowerToughness,
+        Layer::ModifyPowerToughness,
+    ];
+}
+
+/// One change a continuous effect makes to the permanents it affects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContinuousModification {
+    /// The controller of the source gains control (`GainControl$ You`)
+    GainControl,
+    /// `AddType$ Artifact & Creature`
+    AddType(CardType),
+    /// `AddType$ Zombie`
+    AddSubtype(Subtype),
+    /// `RemoveType$ Creature`
+    RemoveType(CardType),
+    /// `SetColor$ Blue & Red` (replaces all colors)
+    SetColors(SmallVec<[Color; 2]>),
+    /// `AddColor$ Black`
+    AddColor(Color),
+    /// `AddKeyword$ Flying & Trample`
+    AddKeyword(Keyword),
+    /// `RemoveKeyword$ Flying`
+    RemoveKeyword(Keyword),
+    /// `RemoveAllAbilities$ True` (keywords only; other abilities aren't tracked per layer)
+    RemoveAllKeywords,
+    /// `SetPower$ 0`
+    SetPower(i8),
+    /// `SetToughness$ 1`
+    SetToughness(i8),
+    /// `AddPower$ 1`
+    AddPower(i32),
+    /// `AddToughness$ 1`
+    AddToughness(i32),
+}
+
+impl ContinuousModification {
+    /// The layer this modification is applied in
+    pub fn layer(&self) -> Layer {
+        match self {
+            ContinuousModification::GainControl => Layer::Control,
+            ContinuousModification::AddType(_)
+            | ContinuousModification::AddSubtype(_)
+            | ContinuousModification::RemoveType(_) => Layer::Type,
+            ContinuousModification::SetColors(_) | ContinuousModification::AddColor(_) => Layer::Color,
+            ContinuousModification::AddKeyword(_)
+            | ContinuousModification::RemoveKeyword(_)
+            | ContinuousModification::RemoveAllKeywords => Layer::Ability,
+            ContinuousModification::SetPower(_) | ContinuousModification::SetToughness(_) => Layer::SetPowerToughness,
+            ContinuousModification::AddPower(_) | ContinuousModification::AddToughness(_) => {
+                Layer::ModifyPowerToughness
+            }
+        }
+    }
+}
+
+/// A continuous static ability of a permanent (Forge `S:Mode$ Continuous`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StaticAbility {
+    /// Permanents the ability affects (`Affected$`), evaluated relative to its source
+    pub affected: Selector,
+    /// What the ability does to them
+    pub modifications: Vec<ContinuousModification>,
+    /// Rules text (`Description$`)
+    pub description: String,
+}
+
+impl StaticAbility {
+    /// The first layer the ability applies in, where its affected set is locked in
+    /// (MTG Rules 613.6)
+    pub fn first_layer(&self) -> Option<Layer> {
+        self.modifications.iter().map(ContinuousModification::layer).min()
+    }
+}
+
+/// A permanent's characteristics after continuous effects (MTG Rules 613)
+///
+/// Starts as a copy of the card's own values and is modified in layer order.
+/// Power and toughness here exclude counters and "until end of turn" bonuses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DerivedCharacteristics {
+    /// The controller before control-changing effects, restored when they stop applying
+    pub base_controller: PlayerId,
+    pub types: SmallVec<[CardType; 2]>,
+    pub subtypes: SmallVec<[Subtype; 2]>,
+    pub colors: SmallVec<[Color; 2]>,
+    pub keywords: Vec<Keyword>,
+    pub power: Option<i8>,
+    pub toughness: Option<i8>,
+    /// Power and toughness added by static abilities (layer 7c)
+    pub power_bonus: i32,
+    pub toughness_bonus: i32,
+}
+
+impl DerivedCharacteristics {
+    /// Apply one modification; `source_controller` is used by control-changing effects
+    ///
+    /// Returns the new controller for control-changing modifications.
+    pub fn apply(&mut self, modification: &ContinuousModification, source_controller: PlayerId) -> Option<PlayerId> {
+        match modification {
+            ContinuousModification::GainControl => return Some(source_controller),
+            ContinuousModification::AddType(card_type) => {
+                if !self.types.contains(card_type) {
+                    self.types.push(*card_type);
+                }
+            }
+            ContinuousModification::AddSubtype(subtype) => {
+                if !self.subtypes.contains(subtype) {
+                    self.subtypes.push(subtype.clone());
+                }
+            }
+            ContinuousModification::RemoveType(card_type) => self.types.retain(|t| t != card_type),
+            ContinuousModification::SetColors(colors) => self.colors = colors.clone(),
+            ContinuousModification::AddColor(color) => {
+                self.colors.retain(|c| *c != Color::Colorless);
+                if !self.colors.contains(color) {
+                    self.colors.push(*color);
+                }
+            }
+            ContinuousModification::AddKeyword(keyword) => {
+                if !self.keywords.contains(keyword) {
+                    self.keywords.push(keyword.clone());
+                }
+            }
+            ContinuousModification::RemoveKeyword(keyword) => self.keywords.retain(|k| k != keyword),
+            ContinuousModification::RemoveAllKeywords => self.keywords.clear(),
+            ContinuousModification::SetPower(power) => self.power = Some(*power),
+            ContinuousModification::SetToughness(toughness) => self.toughness = Some(*toughness),
+            ContinuousModification::AddPower(amount) => self.power_bonus += amount,
+            ContinuousModification::AddToughness(amount) => self.toughness_bonus += amount,
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modification_layers() {
+        let anthem = StaticAbility {
+            affected: Selector::parse("Creature.YouCtrl"),
+            modifications: vec![
+                ContinuousModification::AddPower(1),
+                ContinuousModification::AddKeyword(Keyword::Flying),
+            ],
+            description: String::new(),
+        };
+        assert_eq!(anthem.first_layer(), Some(Layer::Ability));
+        assert!(Layer::Control < Layer::Type && Layer::SetPowerToughness < Layer::ModifyPowerToughness);
+    }
+}
@@ -0,0 +1,181 @@
+//! Token definitions
+//!
+//! Forge describes tokens in `res/tokenscripts/<name>.txt` files that use the
+//! same format as card scripts. Abilities reference them by file name through
+//! `TokenScript$` (e.g. `TokenScript$ w_1_1_soldier`). A [`TokenDefinition`] is
+//! the resolved, game-ready form of such a script.
+
+use crate::core::{
+    ActivatedAbility, Card, CardId, CardType, Color, Keyword, PlayerId, StaticAbility, Subtype, Trigger,
+};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+/// Everything needed to create a token permanent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenDefinition {
+    /// Forge token script name (e.g. "w_1_1_soldier")
+    pub script: String,
+    /// Token name (e.g. "Soldier Token")
+    pub name: String,
+    pub types: Vec<CardType>,
+    pub subtypes: Vec<Subtype>,
+    pub colors: Vec<Color>,
+    pub power: Option<i8>,
+    pub toughness: Option<i8>,
+    pub keywords: Vec<Keyword>,
+    pub triggers: Vec<Trigger>,
+    pub activated_abilities: Vec<ActivatedAbility>,
+    pub static_abilities: Vec<StaticAbility>,
+}
+
+impl TokenDefinition {
+    /// Build a definition from a script name alone, following Forge's naming convention
+    ///
+    /// Script names encode colors, P/T, card type letters, subtypes and keywords:
+    /// `w_1_1_soldier`, `b_2_2_zombie`, `c_1_1_a_thopter_flying`, `c_a_treasure_sac`.
+    /// This is a fallback for when the tokenscripts folder is not available; the
+    /// loaded script (see `loader::TokenLoader`) is preferred. Abilities that are
+    /// only written in the script (e.g. Treasure's mana ability) are not recovered.
+    pub fn from_script_name(script: &str) -> Self {
+        let mut parts = script.split('_').filter(|p| !p.is_empty()).peekable();
+
+        let mut colors = Vec::new();
+        if let Some(prefix) = parts.peek() {
+            if prefix.chars().all(|c| "wubrgc".contains(c)) {
+                colors = prefix.chars().filter_map(color_from_letter).collect();
+                parts.next();
+            }
+        }
+        if colors.is_empty() {
+            colors.push(Color::Colorless);
+        }
+
+        let rest: Vec<&str> = parts.collect();
+        let mut rest = rest.as_slice();
+
+        let mut types = Vec::new();
+        let (mut power, mut toughness) = (None, None);
+        if let [p, t, tail @ ..] = rest {
+            if let (Ok(p), Ok(t)) = (p.parse::<i8>(), t.parse::<i8>()) {
+                power = Some(p);
+                toughness = Some(t);
+                rest = tail;
+            }
+        }
+
+        // Single letters name additional card types ("a" artifact, "e" enchantment, "l" land)
+        while let [letter, tail @ ..] = rest {
+            let card_type = match *letter {
+                "a" => CardType::Artifact,
+                "e" => CardType::Enchantment,
+                "l" => CardType::Land,
+                _ => break,
+            };
+            types.push(card_type);
+            rest = tail;
+        }
+        if power.is_some() {
+            types.push(CardType::Creature);
+        }
+
+        // The first remaining word is the creature/artifact subtype; later words are
+        // keywords or script modifiers like "sac" that we can't interpret from the name
+        let mut subtypes = Vec::new();
+        let mut keywords = Vec::new();
+        if let [subtype, tail @ ..] = rest {
+            subtypes.push(Subtype::new(capitalize(subtype)));
+            keywords.extend(tail.iter().filter_map(|word| Keyword::from_name(&capitalize(word))));
+        }
+
+        let name = match subtypes.first() {
+            Some(first) => format!("{first} Token"),
+            None => format!("{script} Token"),
+        };
+
+        TokenDefinition {
+            script: script.to_string(),
+            name,
+            types,
+            subtypes,
+            colors,
+            power,
+            toughness,
+            keywords,
+            triggers: Vec::new(),
+            activated_abilities: Vec::new(),
+            static_abilities: Vec::new(),
+        }
+    }
+
+    /// Create a token card with this definition
+    pub fn instantiate(&self, id: CardId, owner: PlayerId) -> Card {
+        let mut card = Card::new(id, self.name.as_str(), owner);
+        card.types = SmallVec::from_vec(self.types.clone());
+        card.subtypes = SmallVec::from_vec(self.subtypes.clone());
+        card.colors = SmallVec::from_vec(self.colors.clone());
+        card.power = self.power;
+        card.toughness = self.toughness;
+        card.keywords = self.keywords.clone();
+        card.triggers = self.triggers.clone();
+        card.activated_abilities = self.activated_abilities.clone();
+        card.static_abilities = self.static_abilities.clone();
+        card.is_token = true;
+        card
+    }
+}
+
+fn color_from_letter(letter: char) -> Option<Color> {
+    match letter {
+        'w' => Some(Color::White),
+        'u' => Some(Color::Blue),
+        'b' => Some(Color::Black),
+        'r' => Some(Color::Red),
+        'g' => Some(Color::Green),
+        _ => None,
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_creature_token_from_script_name() {
+        let token = TokenDefinition::from_script_name("w_1_1_soldier");
+        assert_eq!(token.name, "Soldier Token");
+        assert_eq!(token.types, vec![CardType::Creature]);
+        assert_eq!(token.subtypes, vec![Subtype::new("Soldier")]);
+        assert_eq!(token.colors, vec![Color::White]);
+        assert_eq!((token.power, token.toughness), (Some(1), Some(1)));
+    }
+
+    #[test]
+    fn test_artifact_creature_token_with_keyword() {
+        let token = TokenDefinition::from_script_name("c_1_1_a_thopter_flying");
+        assert_eq!(token.types, vec![CardType::Artifact, CardType::Creature]);
+        assert_eq!(token.subtypes, vec![Subtype::new("Thopter")]);
+        assert_eq!(token.keywords, vec![Keyword::Flying]);
+        assert_eq!(token.colors, vec![Color::Colorless]);
+    }
+
+    #[test]
+    fn test_noncreature_token() {
+        let token = TokenDefinition::from_script_name("c_a_treasure_sac");
+        assert_eq!(token.name, "Treasure Token");
+        assert_eq!(token.types, vec![CardType::Artifact]);
+        assert_eq!(token.power, None);
+
+        let card = token.instantiate(CardId::new(5), PlayerId::new(1));
+        assert!(card.is_token);
+        assert!(!card.is_creature());
+    }
+}
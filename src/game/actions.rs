@@ -1,10 +1,13 @@
 //! Game actions and mechanics
 
-use crate::core::{CardId, CardType, Cost, Effect, Keyword, PlayerId, TargetRef, TriggerEvent};
-use crate::game::GameState;
+use crate::core::{
+    CardId, CardType, Cost, CounterType, Effect, Keyword, PlayerId, SelectorContext, TargetRef, Trigger, TriggerEvent,
+};
+use crate::game::{AttackTarget, GameState, SpellChoices, StackAbility};
 use crate::zones::Zone;
 use crate::{MtgError, Result};
 use smallvec::SmallVec;
+use std::collections::HashMap;
 
 /// Types of game actions
 #[derive(Debug, Clone)]
@@ -62,16 +65,16 @@ impl GameState {
         }
 
         // Move card to battlefield
-        self.move_card(card_id, Zone::Hand, Zone::Battlefield, player_id)?;
+        let destination = self.move_card(card_id, Zone::Hand, Zone::Battlefield, player_id)?;
 
         // Record the turn number when this land entered the battlefield
-        if let Ok(card) = self.cards.get_mut(card_id) {
-            card.turn_entered_battlefield = Some(self.turn.turn_number);
+        if destination == Zone::Battlefield {
+            self.record_entered_battlefield(card_id)?;
         }
 
         // Increment lands played
-        let player = self.get_player_mut(player_id)?;
-        player.play_land();
+        let lands_played = self.get_player(player_id)?.lands_played_this_turn;
+        self.set_lands_played(player_id, lands_played + 1)?;
 
         Ok(())
     }
@@ -79,49 +82,90 @@ impl GameState {
     /// Cast a spell (put it on the stack)
     ///
     /// This validates mana payment and deducts the cost from the player's mana pool.
-    pub fn cast_spell(&mut self, player_id: PlayerId, card_id: CardId, _targets: Vec<CardId>) -> Result<()> {
-        // Check if card is in hand
-        if let Some(zones) = self.get_player_zones(player_id) {
-            if !zones.hand.contains(card_id) {
-                return Err(MtgError::InvalidAction("Card not in hand".to_string()));
-            }
-        }
+    /// The targets are recorded on the spell's stack object for resolution.
+    pub fn cast_spell(&mut self, player_id: PlayerId, card_id: CardId, targets: Vec<CardId>) -> Result<()> {
+        // Cast from hand, or a commander from the command zone
+        let from = self.casting_zone(player_id, card_id)?;
 
-        // Get the mana cost (need to do this before mutable borrow)
-        let mana_cost = {
-            let card = self.cards.get(card_id)?;
-            card.mana_cost
-        };
+        // Get the mana cost, with commander tax (need to do this before mutable borrow)
+        let mana_cost = self.casting_cost(card_id)?;
 
         // Pay the mana cost
-        let player = self.get_player_mut(player_id)?;
-        player.mana_pool.pay_cost(&mana_cost).map_err(MtgError::InvalidAction)?;
+        self.pay_mana_cost(player_id, &mana_cost)?;
+        if from == Zone::Command {
+            self.record_commander_cast(card_id)?;
+        }
 
         // Move card to stack
-        self.move_card(card_id, Zone::Hand, Zone::Stack, player_id)?;
+        self.move_card(card_id, from, Zone::Stack, player_id)?;
+        self.set_spell_targets(card_id, targets)?;
+
+        Ok(())
+    }
+
+    /// Pay a mana cost from a player's mana pool (MTG Rules 601.2h)
+    ///
+    /// Phyrexian symbols the pool can't pay for are paid with 2 life each, as long
+    /// as the player has the life to pay (MTG Rules 107.4f, 119.4). The life
+    /// payment is logged for undo.
+    pub fn pay_mana_cost(&mut self, player_id: PlayerId, cost: &crate::core::ManaCost) -> Result<()> {
+        let max_life_payments = self.max_life_payments(player_id);
+        let player = self.get_player_mut(player_id)?;
+        let prev_pool = player.mana_pool;
+        let life_payments = player
+            .mana_pool
+            .pay_cost_with_life(cost, max_life_payments)
+            .map_err(MtgError::InvalidAction)?;
+        let pool = player.mana_pool;
+        if pool != prev_pool {
+            self.undo_log.log(crate::undo::GameAction::SpendMana {
+                player_id,
+                prev_pool,
+                pool,
+            });
+        }
 
+        if life_payments > 0 {
+            let life = 2 * life_payments as i32;
+            self.change_life(player_id, -life)?;
+        }
         Ok(())
     }
 
+    /// How many Phyrexian symbols a player could pay for with 2 life each
+    ///
+    /// A player can pay life only up to their life total (MTG Rules 119.4).
+    pub fn max_life_payments(&self, player_id: PlayerId) -> u8 {
+        self.get_player(player_id)
+            .map(|player| (player.life.max(0) / 2).min(u8::MAX as i32) as u8)
+            .unwrap_or(0)
+    }
+
     /// Resolve a spell from the stack
     ///
-    /// ## Parameters
-    /// - `card_id`: The spell card on the stack to resolve
-    /// - `chosen_targets`: Targets selected by the controller during casting (optional)
+    /// The choices recorded on the spell's stack object when it was cast select
+    /// its modes and value of X, and its targets fill in placeholder targets in
+    /// its effects. If it has no targets, effects must already have their
+    /// targets specified.
     ///
-    /// If targets are provided, they will be used to fill in placeholder targets in effects.
-    /// Otherwise, effects must already have their targets specified.
-    pub fn resolve_spell(&mut self, card_id: CardId, chosen_targets: &[CardId]) -> Result<()> {
-        // Get card owner and effects
-        let (card_owner, mut effects) = {
-            let card = self.cards.get(card_id)?;
-            // TODO: eliminate this clone and instead just take a reference. Why does it need to be mutable?
-            (card.owner, card.effects.clone())
-        };
+    /// Targets are checked again first (MTG Rules 608.2b): if all of them have
+    /// become illegal the spell is countered on resolution, otherwise effects on
+    /// illegal targets are skipped.
+    pub fn resolve_spell(&mut self, card_id: CardId) -> Result<()> {
+        let chosen_targets = self
+            .stack
+            .spell_targets(card_id)
+            .ok_or_else(|| MtgError::InvalidAction(format!("Spell {card_id} is not on the stack")))?
+            .to_vec();
+
+        // Get card owner and the effects of the chosen modes, with X filled in
+        let card_owner = self.cards.get(card_id)?.owner;
+        let mut effects = self.spell_effects(card_id)?;
 
         // Fill in targets for effects using the chosen targets
         // If no targets were chosen (empty slice), effects must already be fully specified
-        let mut target_index = 0;
+        // A divided effect already took its targets, the first ones chosen
+        let mut target_index = self.stack.spell_choices(card_id).map_or(0, |c| c.division.len());
         for effect in &mut effects {
             match effect {
                 Effect::DealDamage {
@@ -130,7 +174,7 @@ impl GameState {
                 } if target_index < chosen_targets.len() => {
                     // Use the chosen target
                     *effect = Effect::DealDamage {
-                        target: TargetRef::Permanent(chosen_targets[target_index]),
+                        target: self.target_ref(chosen_targets[target_index]),
                         amount: *amount,
                     };
                     target_index += 1;
@@ -183,6 +227,13 @@ impl GameState {
                     };
                     target_index += 1;
                 }
+                Effect::PutCounter { target, .. } | Effect::Attach { target, .. }
+                    if target.as_u32() == 0 && target_index < chosen_targets.len() =>
+                {
+                    // Use the chosen target
+                    *target = chosen_targets[target_index];
+                    target_index += 1;
+                }
                 _ => {
                     // Effect doesn't need a target, or target is already specified
                 }
@@ -215,13 +266,31 @@ impl GameState {
                         count: *count,
                     };
                 }
+                Effect::DestroyAll { controller, .. } | Effect::PumpAll { controller, .. }
+                    if controller.as_u32() == 0 =>
+                {
+                    // Placeholder player ID 0 means "controller" (the "You" of YouCtrl)
+                    *controller = card_owner;
+                }
+                Effect::CreateToken { owner, .. } if owner.as_u32() == 0 => {
+                    // Placeholder player ID 0 means "controller" (TokenOwner$ You)
+                    *owner = card_owner;
+                }
+                Effect::CreateReplacementEffects { controller, .. } if controller.as_u32() == 0 => {
+                    // Placeholder player ID 0 means "controller"
+                    *controller = card_owner;
+                }
+                Effect::Attach { attachment, .. } if attachment.as_u32() == 0 => {
+                    // Placeholder attachment means the spell itself (an Aura)
+                    *attachment = card_id;
+                }
                 Effect::DealDamage {
                     target: TargetRef::None,
                     amount,
                 } => {
-                    // If no target was chosen, default to opponent for damage
+                    // If no target was chosen, default to an opponent still in the game
                     // This handles untargeted damage like "deals 1 damage to each opponent"
-                    if let Some(opponent_id) = self.players.iter().map(|p| p.id).find(|id| *id != card_owner) {
+                    if let Some(opponent_id) = self.untargeted_damage_recipient(card_owner) {
                         *effect = Effect::DealDamage {
                             target: TargetRef::Player(opponent_id),
                             amount: *amount,
@@ -232,67 +301,75 @@ impl GameState {
             }
         }
 
-        // Check if targets are still valid before executing effects
-        // MTG Rules 608.2b: If all targets are illegal, the spell doesn't resolve
-        //
-        // For damage spells targeting permanents, verify targets are still on battlefield
-        // (they might have been removed by another spell resolving first)
-        let mut all_targets_illegal = false;
-        if !chosen_targets.is_empty() {
-            // Check if this spell deals damage to permanents
-            let targets_permanents = effects.iter().any(|effect| {
-                matches!(
-                    effect,
+        // MTG Rules 608.2b: check target legality again before executing effects
+        // (targets may have left play, gained hexproof or protection, ...)
+        let illegal_targets: Vec<CardId> = chosen_targets
+            .iter()
+            .copied()
+            .filter(|&target_id| !self.is_legal_spell_target(card_id, target_id))
+            .collect();
+        let countered = !chosen_targets.is_empty() && illegal_targets.len() == chosen_targets.len();
+
+        // MTG Rules 303.4f: an Aura spell enters the battlefield attached to its target
+        let mut attach_on_entry = Vec::new();
+        if countered {
+            let message = format!(
+                "{} is countered on resolution (all its targets are illegal)",
+                self.cards.get(card_id)?.name
+            );
+            self.logger.normal(&message);
+        } else {
+            for effect in effects {
+                // The spell doesn't affect illegal targets, players included
+                let target = match effect {
                     Effect::DealDamage {
-                        target: TargetRef::Permanent(_),
+                        target: TargetRef::Player(player),
                         ..
+                    } => Some(CardId::new(player.as_u32())),
+                    _ => effect.target_card(),
+                };
+                if target.is_some_and(|t| illegal_targets.contains(&t)) {
+                    continue;
+                }
+                if let Effect::Attach { attachment, target } = effect {
+                    if attachment == card_id {
+                        attach_on_entry.push(target);
+                        continue;
                     }
-                )
-            });
-
-            if targets_permanents {
-                // Check if any permanent target is no longer on the battlefield
-                let any_target_gone = chosen_targets
-                    .iter()
-                    .any(|&target_id| !self.battlefield.contains(target_id));
-                if any_target_gone {
-                    // Spell fizzles - permanent targets are no longer valid
-                    all_targets_illegal = true;
                 }
-            }
-        }
-
-        // Execute effects only if targets are still valid
-        if !all_targets_illegal {
-            for effect in effects {
-                self.execute_effect(&effect)?;
+                self.execute_effect_from(card_id, &effect)?;
             }
         }
 
         // Determine destination based on card type
         let destination = {
             let card = self.cards.get(card_id)?;
-            if card.is_type(&CardType::Instant) || card.is_type(&CardType::Sorcery) {
+            if countered || card.is_type(&CardType::Instant) || card.is_type(&CardType::Sorcery) {
                 Zone::Graveyard
             } else {
                 Zone::Battlefield
             }
         };
 
-        // Move card from stack to destination
+        // Move card from stack to destination (replacement effects may send it elsewhere)
         let owner = self.cards.get(card_id)?.owner;
-        self.move_card(card_id, Zone::Stack, destination, owner)?;
+        let destination = self.move_card(card_id, Zone::Stack, destination, owner)?;
 
         // If it entered the battlefield, record the turn number (for summoning sickness)
         if destination == Zone::Battlefield {
-            if let Ok(card) = self.cards.get_mut(card_id) {
-                card.turn_entered_battlefield = Some(self.turn.turn_number);
+            self.record_entered_battlefield(card_id)?;
+            for target in attach_on_entry {
+                self.attach(card_id, target)?;
             }
 
             // Check for ETB triggers on all permanents (including the one that just entered)
             self.check_triggers(TriggerEvent::EntersBattlefield, card_id)?;
         }
 
+        // MTG Rules 704.3: state-based actions are checked once resolution is complete
+        // (e.g., creatures dealt lethal damage by this spell die now)
+        self.check_state_based_actions()?;
+
         Ok(())
     }
 
@@ -307,12 +384,25 @@ impl GameState {
     /// Returns a vector of valid target CardIds that can be chosen by the controller.
     /// For effects that target players, use TargetRef::Player instead.
     pub fn get_valid_targets_for_spell(&self, spell_card_id: CardId) -> Result<SmallVec<[CardId; 8]>> {
+        // A spell on the stack targets according to the modes chosen for it
+        let modes = self
+            .stack
+            .spell_choices(spell_card_id)
+            .map(|choices| choices.modes.clone())
+            .unwrap_or_default();
+        self.get_valid_targets_for_modes(spell_card_id, &modes)
+    }
+
+    /// Get valid targets for a spell with the given modes chosen (see `Card::spell_effects`)
+    ///
+    /// Used while casting, after modes are chosen but before targets are.
+    pub fn get_valid_targets_for_modes(&self, spell_card_id: CardId, modes: &[usize]) -> Result<SmallVec<[CardId; 8]>> {
         let mut valid_targets = SmallVec::new();
 
-        // Get the spell's owner and effects
+        // Get the spell's controller and effects
         let card = self.cards.get(spell_card_id)?;
-        let spell_owner = card.owner;
-        let effects = &card.effects;
+        let spell_controller = card.controller;
+        let effects = &card.spell_effects(modes, 0);
 
         // For each effect, determine what targets are valid
         for effect in effects {
@@ -327,14 +417,19 @@ impl GameState {
                         if let Ok(card) = self.cards.get(card_id) {
                             if card.is_creature() && !card.has_shroud() {
                                 // Hexproof only protects from opponent's spells
-                                if card.owner == spell_owner || !card.has_hexproof() {
+                                if card.controller == spell_controller || !card.has_hexproof() {
                                     valid_targets.push(card_id);
                                 }
                             }
                         }
                     }
-                    // Note: Players are also valid targets, but we handle them separately
-                    // via TargetRef::Player since they don't have CardIds
+                    // Damage divided among targets can also go to players still in the game,
+                    // chosen under their own IDs (see `target_player`)
+                    if card.is_divided() {
+                        for player in self.players.iter().filter(|p| !p.has_lost) {
+                            valid_targets.push(CardId::new(player.id.as_u32()));
+                        }
+                    }
                 }
                 Effect::DestroyPermanent { target } if target.as_u32() == 0 => {
                     // Destroy can target any permanent (typically creatures)
@@ -342,7 +437,7 @@ impl GameState {
                         if let Ok(card) = self.cards.get(card_id) {
                             if !card.has_shroud() {
                                 // Hexproof only protects from opponent's spells
-                                if card.owner == spell_owner || !card.has_hexproof() {
+                                if card.controller == spell_controller || !card.has_hexproof() {
                                     valid_targets.push(card_id);
                                 }
                             }
@@ -355,7 +450,7 @@ impl GameState {
                         if let Ok(card) = self.cards.get(card_id) {
                             if card.is_creature() && !card.has_shroud() {
                                 // Hexproof only protects from opponent's spells
-                                if card.owner == spell_owner || !card.has_hexproof() {
+                                if card.controller == spell_controller || !card.has_hexproof() {
                                     valid_targets.push(card_id);
                                 }
                             }
@@ -368,7 +463,7 @@ impl GameState {
                         if let Ok(card) = self.cards.get(card_id) {
                             if !card.tapped && !card.has_shroud() {
                                 // Hexproof only protects from opponent's spells
-                                if card.owner == spell_owner || !card.has_hexproof() {
+                                if card.controller == spell_controller || !card.has_hexproof() {
                                     valid_targets.push(card_id);
                                 }
                             }
@@ -381,7 +476,7 @@ impl GameState {
                         if let Ok(card) = self.cards.get(card_id) {
                             if card.tapped && !card.has_shroud() {
                                 // Hexproof only protects from opponent's spells
-                                if card.owner == spell_owner || !card.has_hexproof() {
+                                if card.controller == spell_controller || !card.has_hexproof() {
                                     valid_targets.push(card_id);
                                 }
                             }
@@ -390,12 +485,38 @@ impl GameState {
                 }
                 Effect::CounterSpell { target } if target.as_u32() == 0 => {
                     // Counter can target spells on the stack (except self)
-                    for &card_id in &self.stack.cards {
+                    for card_id in self.stack.spells() {
                         if card_id != spell_card_id {
                             valid_targets.push(card_id);
                         }
                     }
                 }
+                Effect::PutCounter { target, .. } if target.as_u32() == 0 => {
+                    // Counters can go on any permanent; ValidTgts$ narrows it down (usually to creatures)
+                    for &card_id in &self.battlefield.cards {
+                        if let Ok(card) = self.cards.get(card_id) {
+                            if !card.has_shroud() {
+                                // Hexproof only protects from opponent's spells
+                                if card.controller == spell_controller || !card.has_hexproof() {
+                                    valid_targets.push(card_id);
+                                }
+                            }
+                        }
+                    }
+                }
+                Effect::Attach { target, .. } if target.as_u32() == 0 => {
+                    // An Aura spell targets what it can enchant (MTG Rules 303.4a)
+                    for &card_id in &self.battlefield.cards {
+                        if let Ok(card) = self.cards.get(card_id) {
+                            if !card.has_shroud()
+                                && (card.controller == spell_controller || !card.has_hexproof())
+                                && self.can_attach(spell_card_id, card_id)
+                            {
+                                valid_targets.push(card_id);
+                            }
+                        }
+                    }
+                }
                 Effect::ExilePermanent { target } if target.as_u32() == 0 => {
                     // Exile can target any permanent (typically creatures, like Swords to Plowshares)
                     // In Swords to Plowshares: ValidTgts$ Creature
@@ -405,7 +526,7 @@ impl GameState {
                             // Swords to Plowshares specifically targets creatures
                             if card.is_creature() && !card.has_shroud() {
                                 // Hexproof only protects from opponent's spells
-                                if card.owner == spell_owner || !card.has_hexproof() {
+                                if card.controller == spell_controller || !card.has_hexproof() {
                                     valid_targets.push(card_id);
                                 }
                             }
@@ -419,11 +540,91 @@ impl GameState {
             }
         }
 
+        // Apply the spell's ValidTgts$ restriction (e.g. Terror: "Creature.nonArtifact+nonBlack")
+        if let Some(restriction) = card.spell_target_restriction(modes) {
+            let ctx = SelectorContext::new(spell_controller, Some(spell_card_id));
+            valid_targets.retain(|target_id| match self.target_player(*target_id) {
+                Some(player) => restriction.matches_player(player, &ctx),
+                None => self
+                    .cards
+                    .get(*target_id)
+                    .is_ok_and(|target| restriction.matches_card(target, &ctx)),
+            });
+        }
+
         // Sort for deterministic ordering (critical for snapshot/resume)
         valid_targets.sort();
         Ok(valid_targets)
     }
 
+    /// Check whether a chosen target is still legal for a spell or ability (MTG Rules 608.2b)
+    ///
+    /// The target must still be on the battlefield (or be a spell on the stack) and
+    /// must not have gained shroud, hexproof against `controller`, or protection
+    /// from one of the source's colors.
+    pub fn is_legal_target(&self, controller: PlayerId, source_id: CardId, target_id: CardId) -> bool {
+        // Spells have no hexproof or protection of their own
+        if self.stack.contains(target_id) {
+            return true;
+        }
+        if !self.battlefield.contains(target_id) {
+            return false;
+        }
+        let Ok(target) = self.cards.get(target_id) else {
+            return false;
+        };
+
+        // Hexproof only protects from opponents of its controller (same rule as targeting at cast time)
+        if target.has_shroud() || (target.has_hexproof() && target.controller != controller) {
+            return false;
+        }
+
+        match self.cards.get(source_id) {
+            Ok(source) => !source
+                .current_colors()
+                .iter()
+                .any(|&color| target.has_protection_from(color)),
+            Err(_) => true,
+        }
+    }
+
+    /// Check whether a spell's chosen target is still legal, including its ValidTgts$ restriction
+    ///
+    /// A player target is legal while that player is still in the game.
+    pub fn is_legal_spell_target(&self, spell_id: CardId, target_id: CardId) -> bool {
+        let Ok(spell) = self.cards.get(spell_id) else {
+            return false;
+        };
+        let modes = self
+            .stack
+            .spell_choices(spell_id)
+            .map(|choices| choices.modes.as_slice())
+            .unwrap_or_default();
+        let ctx = SelectorContext::new(spell.controller, Some(spell_id));
+
+        if let Some(player) = self.target_player(target_id) {
+            return self.get_player(player).is_ok_and(|p| !p.has_lost)
+                && spell
+                    .spell_target_restriction(modes)
+                    .is_none_or(|restriction| restriction.matches_player(player, &ctx));
+        }
+        if !self.is_legal_target(spell.controller, spell_id, target_id) {
+            return false;
+        }
+        // An Aura spell's target has to be something it could enchant
+        if spell.is_aura() && !self.can_attach(spell_id, target_id) {
+            return false;
+        }
+
+        match spell.spell_target_restriction(modes) {
+            Some(restriction) => self
+                .cards
+                .get(target_id)
+                .is_ok_and(|target| restriction.matches_card(target, &ctx)),
+            None => true,
+        }
+    }
+
     /// Check if a cost sacrifices the source card itself
     ///
     /// Returns true if the cost includes sacrificing "CARDNAME" (the source card itself).
@@ -469,13 +670,17 @@ impl GameState {
         // If so, the source card won't be on the battlefield when the effect resolves
         let sacrifices_self = Self::cost_sacrifices_self(&ability.cost);
 
-        // Check for targeting restrictions in the ability description
+        // Targeting restrictions come from the compiled ValidTgts$ selector when the
+        // ability has one; otherwise fall back to the ability description
         // For Royal Assassin: "Destroy target tapped creature"
         // For Strip Mine: "Destroy target land"
-        let requires_tapped = ability.description.to_lowercase().contains("tapped");
-        let requires_untapped = ability.description.to_lowercase().contains("untapped");
-        let targets_creature = ability.description.to_lowercase().contains("creature");
-        let targets_land = ability.description.to_lowercase().contains("land");
+        let restriction = ability.target_restriction.as_ref();
+        let description = ability.description.to_lowercase();
+        let use_description = restriction.is_none();
+        let requires_tapped = use_description && description.contains("tapped");
+        let requires_untapped = use_description && description.contains("untapped");
+        let targets_creature = use_description && description.contains("creature");
+        let targets_land = use_description && description.contains("land");
 
         // Check each effect to determine valid targets
         for effect in &ability.effects {
@@ -519,7 +724,7 @@ impl GameState {
                             }
 
                             // Hexproof only protects from opponent's abilities
-                            if card.has_hexproof() && card.owner != ability_controller {
+                            if card.has_hexproof() && card.controller != ability_controller {
                                 is_valid = false;
                             }
 
@@ -536,7 +741,7 @@ impl GameState {
                             let mut is_valid = !card.tapped && !card.has_shroud();
 
                             // Check hexproof
-                            if card.has_hexproof() && card.owner != ability_controller {
+                            if card.has_hexproof() && card.controller != ability_controller {
                                 is_valid = false;
                             }
 
@@ -553,7 +758,7 @@ impl GameState {
                             let mut is_valid = card.tapped && !card.has_shroud();
 
                             // Check hexproof
-                            if card.has_hexproof() && card.owner != ability_controller {
+                            if card.has_hexproof() && card.controller != ability_controller {
                                 is_valid = false;
                             }
 
@@ -563,6 +768,22 @@ impl GameState {
                         }
                     }
                 }
+                Effect::Attach { target, .. } if target.as_u32() == 0 => {
+                    // Equip: a creature the Equipment could be attached to, other than
+                    // the one it's already attached to (moving it there would do nothing)
+                    let attached_to = source_card.attached_to;
+                    for &card_id in &self.battlefield.cards {
+                        if let Ok(card) = self.cards.get(card_id) {
+                            let is_valid = !card.has_shroud()
+                                && !(card.has_hexproof() && card.controller != ability_controller)
+                                && attached_to != Some(card_id)
+                                && self.can_attach(source_card_id, card_id);
+                            if is_valid {
+                                valid_targets.push(card_id);
+                            }
+                        }
+                    }
+                }
                 Effect::DealDamage {
                     target: TargetRef::None,
                     ..
@@ -573,7 +794,7 @@ impl GameState {
                             let mut is_valid = card.is_creature() && !card.has_shroud();
 
                             // Check hexproof
-                            if card.has_hexproof() && card.owner != ability_controller {
+                            if card.has_hexproof() && card.controller != ability_controller {
                                 is_valid = false;
                             }
 
@@ -589,6 +810,17 @@ impl GameState {
             }
         }
 
+        if let Some(restriction) = restriction {
+            let ctx = SelectorContext::new(ability_controller, Some(source_card_id));
+            valid_targets.retain(|target_id| match self.target_player(*target_id) {
+                Some(player) => restriction.matches_player(player, &ctx),
+                None => self
+                    .cards
+                    .get(*target_id)
+                    .is_ok_and(|target| restriction.matches_card(target, &ctx)),
+            });
+        }
+
         // Sort for deterministic ordering (critical for snapshot/resume)
         valid_targets.sort();
         Ok(valid_targets)
@@ -598,9 +830,9 @@ impl GameState {
     ///
     /// This method implements the complete spell casting sequence:
     /// 1. Propose the spell (move to stack)
-    /// 2. Make choices (modes, X values) - TODO
+    /// 2. Make choices (modes, X values)
     /// 3. Choose targets
-    /// 4. Divide effects - TODO
+    /// 4. Divide effects (damage or counters "divided as you choose")
     /// 5. Determine total cost
     /// 6. Activate mana abilities (tap sources for mana)
     /// 7. Pay costs
@@ -609,70 +841,180 @@ impl GameState {
     /// ## Parameters
     /// - `player_id`: The player casting the spell
     /// - `card_id`: The spell card to cast
+    /// - `choose_choices_fn`: Callback to choose modes and the value of X (step 2);
+    ///   the targets of the returned choices are ignored
     /// - `choose_targets_fn`: Callback to choose targets (step 3)
+    /// - `choose_division_fn`: Callback to split a divided amount among the targets,
+    ///   one share per target (step 4); only called with two or more targets
     /// - `choose_mana_sources_fn`: Callback to choose what to tap for mana (step 6)
     ///
+    /// Cards paying an alternative cost (see `SpellChoices::alternative_cost`)
+    /// are the first ones that qualify; see
+    /// [`cast_spell_8_step_with_cost_choices`](Self::cast_spell_8_step_with_cost_choices)
+    /// to let the player choose.
+    ///
     /// ## Java Forge Equivalent
     /// This matches `ComputerUtil.handlePlayingSpellAbility()` which:
     /// 1. Moves spell to stack (line 99)
     /// 2. Handles targeting
     /// 3. Pays costs with `CostPayment.payComputerCosts()` (line 125)
-    pub fn cast_spell_8_step<TargetFn, ManaFn>(
+    pub fn cast_spell_8_step<ChoiceFn, TargetFn, DivideFn, ManaFn>(
+        &mut self,
+        player_id: PlayerId,
+        card_id: CardId,
+        choose_choices_fn: ChoiceFn,
+        choose_targets_fn: TargetFn,
+        choose_division_fn: DivideFn,
+        choose_mana_sources_fn: ManaFn,
+    ) -> Result<()>
+    where
+        ChoiceFn: FnMut(&GameState, CardId) -> SpellChoices,
+        TargetFn: FnMut(&GameState, CardId) -> Vec<CardId>,
+        DivideFn: FnMut(&GameState, CardId, &[CardId], u32) -> Vec<u32>,
+        ManaFn: FnMut(&GameState, &crate::core::ManaCost) -> Vec<CardId>,
+    {
+        self.cast_spell_8_step_with_cost_choices(
+            player_id,
+            card_id,
+            choose_choices_fn,
+            choose_targets_fn,
+            choose_division_fn,
+            choose_mana_sources_fn,
+            |_, _, candidates, count| candidates.iter().take(count).copied().collect(),
+        )
+    }
+
+    /// Cast a spell following the full 8-step process, asking which cards pay
+    /// its alternative cost
+    ///
+    /// Like [`cast_spell_8_step`](Self::cast_spell_8_step); `choose_cost_cards_fn`
+    /// is called in step 7 for each part of an alternative cost where there's a
+    /// choice, as in [`pay_ability_cost_with_choices`](Self::pay_ability_cost_with_choices).
+    #[allow(clippy::too_many_arguments)]
+    pub fn cast_spell_8_step_with_cost_choices<ChoiceFn, TargetFn, DivideFn, ManaFn, CostFn>(
         &mut self,
         player_id: PlayerId,
         card_id: CardId,
+        mut choose_choices_fn: ChoiceFn,
         mut choose_targets_fn: TargetFn,
+        mut choose_division_fn: DivideFn,
         mut choose_mana_sources_fn: ManaFn,
+        mut choose_cost_cards_fn: CostFn,
     ) -> Result<()>
     where
+        ChoiceFn: FnMut(&GameState, CardId) -> SpellChoices,
         TargetFn: FnMut(&GameState, CardId) -> Vec<CardId>,
+        DivideFn: FnMut(&GameState, CardId, &[CardId], u32) -> Vec<u32>,
         ManaFn: FnMut(&GameState, &crate::core::ManaCost) -> Vec<CardId>,
+        CostFn: FnMut(&GameState, &crate::core::Cost, &[CardId], usize) -> SmallVec<[CardId; 8]>,
     {
-        // Verify card is in hand
-        if let Some(zones) = self.get_player_zones(player_id) {
-            if !zones.hand.contains(card_id) {
-                return Err(MtgError::InvalidAction("Card not in hand".to_string()));
-            }
-        }
+        // Verify card is in hand, or is a commander in the command zone (MTG Rules 903.8)
+        let from = self.casting_zone(player_id, card_id)?;
+        let commander_tax = if from == Zone::Command {
+            self.commander_tax(card_id)
+        } else {
+            0
+        };
 
         // Step 1: Propose the spell - move card to stack
         // This happens BEFORE paying costs (unlike our old implementation)
-        self.move_card(card_id, Zone::Hand, Zone::Stack, player_id)?;
+        self.move_card(card_id, from, Zone::Stack, player_id)?;
 
         // Step 2: Make choices (modes, X values)
-        // TODO: Implement modal spell choices and X value selection
+        // Modes must be chosen before targets, since they determine what the spell targets
+        let choices = SpellChoices {
+            targets: Vec::new(),
+            ..choose_choices_fn(self, card_id)
+        };
+        if !self.cards.get(card_id)?.are_valid_modes(&choices.modes) {
+            self.move_card_without_replacement(card_id, Zone::Stack, from, player_id)?;
+            return Err(MtgError::InvalidAction(format!(
+                "Invalid modes {:?} chosen for spell {card_id}",
+                choices.modes
+            )));
+        }
+        // An alternative cost is paid rather than the mana cost (MTG Rules 118.9)
+        let alternative_cost = if choices.alternative_cost {
+            let alternative_cost = self.cards.get(card_id)?.alternative_cost.clone();
+            match alternative_cost.filter(|cost| self.can_pay_ability_cost(player_id, card_id, cost)) {
+                Some(cost) => Some(cost),
+                None => {
+                    self.move_card_without_replacement(card_id, Zone::Stack, from, player_id)?;
+                    return Err(MtgError::InvalidAction(format!(
+                        "Spell {card_id} has no alternative cost that can be paid"
+                    )));
+                }
+            }
+        } else {
+            None
+        };
+        let (modes, x_value) = (choices.modes.clone(), choices.x_value);
+        self.set_spell_choices(card_id, choices)?;
 
         // Step 3: Choose targets
-        let _targets = choose_targets_fn(self, card_id);
-        // TODO: Store targets on the spell for resolution
-        // For now, we'll use them to update effects immediately (simplified)
+        // They're stored on the spell's stack object and checked again on resolution
+        let targets = choose_targets_fn(self, card_id);
+        self.set_spell_targets(card_id, targets.clone())?;
 
         // Step 4: Divide effects
-        // TODO: Implement dividing damage/counters among targets
+        // Each target gets at least 1 of a divided amount (MTG Rules 601.2d), unless
+        // the spell divides it evenly, rounded down (Fireball), which isn't a choice
+        let (divided_amount, divided_evenly) = {
+            let card = self.cards.get(card_id)?;
+            (card.divided_amount(&modes, x_value), card.divided_evenly)
+        };
+        if let Some(total) = divided_amount.filter(|_| !targets.is_empty()) {
+            let division = if divided_evenly {
+                vec![total / targets.len() as u32; targets.len()]
+            } else if targets.len() == 1 {
+                vec![total]
+            } else {
+                choose_division_fn(self, card_id, &targets, total)
+            };
+            let valid = divided_evenly
+                || (division.len() == targets.len() && !division.contains(&0) && division.iter().sum::<u32>() == total);
+            if !valid {
+                self.move_card_without_replacement(card_id, Zone::Stack, from, player_id)?;
+                return Err(MtgError::InvalidAction(format!(
+                    "Invalid division {division:?} of {total} among {} targets",
+                    targets.len()
+                )));
+            }
+            let mut choices = self.stack.spell_choices(card_id).cloned().unwrap_or_default();
+            choices.division = division;
+            self.set_spell_choices(card_id, choices)?;
+        }
 
         // Step 5: Determine total cost
+        // That's the mana cost, or the mana part of an alternative cost, with X
+        // replaced by the chosen value; commander tax and any cost for targets
+        // beyond the first (Fireball) are added (MTG Rules 601.2f)
         let mana_cost = {
             let card = self.cards.get(card_id)?;
-            card.mana_cost
+            let base_cost = match &alternative_cost {
+                Some(cost) => cost.get_mana_cost().copied().unwrap_or_default(),
+                None => card.mana_cost,
+            };
+            base_cost
+                .with_x(x_value)
+                .plus_generic(commander_tax)
+                .plus_generic(card.extra_targets_cost(targets.len()))
         };
 
         // Step 6: Activate mana abilities
         // This is where mana gets tapped - AFTER the spell is on the stack
-        let sources_to_tap = choose_mana_sources_fn(self, &mana_cost);
-        for &source_id in &sources_to_tap {
-            self.tap_for_mana_for_cost(player_id, source_id, &mana_cost)?;
-        }
+        let chosen_sources = choose_mana_sources_fn(self, &mana_cost);
+        let sources_to_tap = self.tap_mana_sources_for_cost(player_id, &chosen_sources, &mana_cost)?;
 
         // Step 7: Pay costs
-        let player = self.get_player_mut(player_id)?;
-        if let Err(e) = player.mana_pool.pay_cost(&mana_cost) {
+        if let Err(e) = self.pay_mana_cost(player_id, &mana_cost) {
             // If we can't pay, we need to unwind:
-            // 1. Move card back to hand from stack
+            // 1. Move card back to where it was cast from
             // 2. Untap all mana sources that were tapped
             // 3. Clear the mana pool
 
-            // Move card back to hand
-            self.move_card(card_id, Zone::Stack, Zone::Hand, player_id)?;
+            // Move card back to hand (or the command zone)
+            self.move_card_without_replacement(card_id, Zone::Stack, from, player_id)?;
 
             // Untap all sources that were tapped
             for &source_id in &sources_to_tap {
@@ -687,12 +1029,22 @@ impl GameState {
             }
 
             // Clear the mana pool (remove any mana that was added)
-            let player = self.get_player_mut(player_id)?;
-            player.mana_pool.clear();
+            self.empty_mana_pool(player_id)?;
 
             return Err(MtgError::InvalidAction(format!("Failed to pay mana cost: {e}")));
         }
 
+        // The rest of an alternative cost: sacrificing, discarding, ... (checked in step 2)
+        if let Some(cost) = &alternative_cost {
+            for part in cost.parts().iter().filter(|part| part.get_mana_cost().is_none()) {
+                self.pay_ability_cost_with_choices(player_id, card_id, part, &mut choose_cost_cards_fn)?;
+            }
+        }
+
+        if from == Zone::Command {
+            self.record_commander_cast(card_id)?;
+        }
+
         // Step 8: Spell becomes cast
         // TODO: Trigger "whenever you cast a spell" abilities
         // For now, this is complete - spell is on stack and costs are paid
@@ -702,13 +1054,31 @@ impl GameState {
 
     /// Execute a single effect
     pub fn execute_effect(&mut self, effect: &Effect) -> Result<()> {
+        self.execute_effect_with_source(None, effect)
+    }
+
+    /// Execute a single effect of a spell or ability of `source`
+    ///
+    /// The source matters to replacement and prevention effects that only apply
+    /// to damage from certain sources (Circle of Protection).
+    pub fn execute_effect_from(&mut self, source: CardId, effect: &Effect) -> Result<()> {
+        self.execute_effect_with_source(Some(source), effect)
+    }
+
+    fn execute_effect_with_source(&mut self, source: Option<CardId>, effect: &Effect) -> Result<()> {
         match effect {
             Effect::DealDamage { target, amount } => match target {
                 TargetRef::Player(player_id) => {
-                    self.deal_damage(*player_id, *amount)?;
+                    if !self.players.iter().any(|p| p.id == *player_id) {
+                        return Err(MtgError::InvalidAction("Invalid damage target".to_string()));
+                    }
+                    self.deal_damage_from(source, target, *amount, false)?;
                 }
                 TargetRef::Permanent(card_id) => {
-                    self.deal_damage_to_creature(*card_id, *amount)?;
+                    if !self.cards.get(*card_id)?.is_creature() {
+                        return Err(MtgError::InvalidAction("Invalid damage target".to_string()));
+                    }
+                    self.deal_damage_from(source, target, *amount, false)?;
                 }
                 TargetRef::None => {
                     return Err(MtgError::InvalidAction(
@@ -722,14 +1092,7 @@ impl GameState {
                 }
             }
             Effect::GainLife { player, amount } => {
-                let p = self.get_player_mut(*player)?;
-                p.gain_life(*amount);
-
-                // Log the life gain
-                self.undo_log.log(crate::undo::GameAction::ModifyLife {
-                    player_id: *player,
-                    delta: *amount,
-                });
+                self.gain_life(*player, *amount)?;
             }
             Effect::DestroyPermanent { target } => {
                 // Skip if target is still placeholder (0) - no valid targets found
@@ -830,6 +1193,22 @@ impl GameState {
                     mana: *mana,
                 });
             }
+            Effect::AddManaOfChoice { player, colors, amount } => {
+                // The color is chosen before we get here: the game loop asks the player
+                // when they activate a mana ability, and paying a cost picks from the
+                // cost. Only mana added by spells and triggered abilities, which resolve
+                // out of the controllers' reach, still falls back to the first color.
+                let color = colors.first().copied().unwrap_or(crate::core::Color::Colorless);
+                let mut mana = crate::core::ManaCost::new();
+                for _ in 0..*amount {
+                    self.get_player_mut(*player)?.mana_pool.add_color(color);
+                    mana.add_color(color);
+                }
+                self.undo_log.log(crate::undo::GameAction::AddMana {
+                    player_id: *player,
+                    mana,
+                });
+            }
             Effect::PutCounter {
                 target,
                 counter_type,
@@ -866,167 +1245,173 @@ impl GameState {
                 let owner = self.cards.get(*target)?.owner;
                 self.move_card(*target, Zone::Battlefield, Zone::Exile, owner)?;
             }
+            Effect::DestroyAll { selector, controller } => {
+                let ctx = SelectorContext::new(*controller, None);
+                let to_destroy: SmallVec<[(CardId, PlayerId); 8]> = self
+                    .battlefield
+                    .cards
+                    .iter()
+                    .filter_map(|&card_id| {
+                        let card = self.cards.get(card_id).ok()?;
+                        // MTG Rules 702.12b: Permanents with indestructible can't be destroyed
+                        (selector.matches_card(card, &ctx) && !card.has_indestructible())
+                            .then_some((card_id, card.owner))
+                    })
+                    .collect();
+                for (card_id, owner) in to_destroy {
+                    self.move_card(card_id, Zone::Battlefield, Zone::Graveyard, owner)?;
+                }
+            }
+            Effect::PumpAll {
+                selector,
+                controller,
+                power_bonus,
+                toughness_bonus,
+            } => {
+                let ctx = SelectorContext::new(*controller, None);
+                let to_pump: SmallVec<[CardId; 8]> = self
+                    .battlefield
+                    .cards
+                    .iter()
+                    .copied()
+                    .filter(|&card_id| {
+                        self.cards
+                            .get(card_id)
+                            .is_ok_and(|card| card.is_creature() && selector.matches_card(card, &ctx))
+                    })
+                    .collect();
+                for card_id in to_pump {
+                    self.execute_effect(&Effect::PumpCreature {
+                        target: card_id,
+                        power_bonus: *power_bonus,
+                        toughness_bonus: *toughness_bonus,
+                    })?;
+                }
+            }
+            Effect::CreateToken { owner, amount, token } => {
+                for _ in 0..*amount {
+                    let token_id = self.create_token(token, *owner)?;
+                    // Tokens entering the battlefield trigger ETB abilities like any permanent
+                    self.check_triggers(TriggerEvent::EntersBattlefield, token_id)?;
+                }
+            }
+            Effect::CreateReplacementEffects { controller, effects } => {
+                for replacement in effects {
+                    self.add_replacement_effect(source, *controller, replacement.clone());
+                }
+            }
+            Effect::Attach { attachment, target } => {
+                // Placeholder attachment means the source (an Equipment's equip ability)
+                let attachment = match (attachment.as_u32(), source) {
+                    (0, Some(source)) => source,
+                    _ => *attachment,
+                };
+                self.attach(attachment, *target)?;
+            }
         }
         Ok(())
     }
 
-    /// Check for triggered abilities and execute them
+    /// Check a card's triggered abilities for an event
     ///
-    /// This checks all permanents on the battlefield for triggers matching the given event.
-    /// When triggers are found, their effects are executed immediately (for now).
+    /// The triggers this card has for `event` become pending triggered abilities
+    /// controlled by the card's controller. They are put on the stack the next time
+    /// a player would receive priority (MTG Rules 603.3), so players can respond.
     ///
-    /// TODO: In full MTG rules, triggers should go on the stack and wait for priority,
-    /// but for simplicity we're executing them immediately.
+    /// All supported trigger events refer to the source card itself ("When CARDNAME
+    /// enters", "At the beginning of your upkeep"), so only `source_card_id`'s own
+    /// triggers are checked.
     pub fn check_triggers(&mut self, event: TriggerEvent, source_card_id: CardId) -> Result<()> {
-        // Collect all triggered effects to execute (without holding a borrow on self.cards)
-        let triggered_effects: Vec<(CardId, Vec<Effect>)> = self
-            .battlefield
-            .cards
-            .iter()
-            .filter_map(|&card_id| {
-                if let Ok(card) = self.cards.get(card_id) {
-                    // Find triggers matching this event
-                    let matching_triggers: Vec<Effect> = card
-                        .triggers
-                        .iter()
-                        .filter(|trigger| trigger.event == event)
-                        .flat_map(|trigger| trigger.effects.clone())
-                        .collect();
-
-                    if !matching_triggers.is_empty() {
-                        Some((card_id, matching_triggers))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect();
+        let (controller, triggered) = {
+            let card = self.cards.get(source_card_id)?;
+            let triggered: Vec<Trigger> = card
+                .triggers
+                .iter()
+                // Triggers without any supported effects have nothing to put on the stack
+                .filter(|trigger| trigger.event == event && !trigger.effects.is_empty())
+                .cloned()
+                .collect();
+            (card.controller, triggered)
+        };
 
-        // Execute all triggered effects
-        for (_trigger_source, effects) in triggered_effects {
-            for mut effect in effects {
-                // Fill in placeholder values in trigger effects
-                // Similar to resolve_spell, we need to fill in targets
+        for trigger in triggered {
+            let mut filled_effects = Vec::with_capacity(trigger.effects.len());
+            for mut effect in trigger.effects {
+                // Fill in placeholder players with the controller of the trigger source.
+                // Targets are chosen later, as the ability is put on the stack (MTG Rules
+                // 603.3d; see `get_valid_targets_for_trigger`)
                 match &mut effect {
-                    Effect::DrawCards { player, .. } if player.as_u32() == 0 => {
-                        // Placeholder player ID 0 means the controller of the trigger source
-                        let controller = self.cards.get(source_card_id)?.controller;
-                        if let Effect::DrawCards { player: _, count } = effect {
-                            effect = Effect::DrawCards {
-                                player: controller,
-                                count,
-                            };
-                        }
-                    }
-                    Effect::DealDamage {
-                        target: TargetRef::None,
-                        amount,
-                    } => {
-                        // Find a valid target (opponent's creature)
-                        let controller = self.cards.get(source_card_id)?.controller;
-                        if let Some(target_id) = self
-                            .battlefield
-                            .cards
-                            .iter()
-                            .find(|&card_id| {
-                                if let Ok(card) = self.cards.get(*card_id) {
-                                    card.is_creature()
-                                        && card.owner != controller
-                                        && !card.has_hexproof()
-                                        && !card.has_shroud()
-                                } else {
-                                    false
-                                }
-                            })
-                            .copied()
-                        {
-                            effect = Effect::DealDamage {
-                                target: TargetRef::Permanent(target_id),
-                                amount: *amount,
-                            };
-                        }
-                    }
-                    Effect::GainLife { player, amount } if player.as_u32() == 0 => {
-                        // Placeholder player ID 0 means the controller of the trigger source
-                        let controller = self.cards.get(source_card_id)?.controller;
-                        effect = Effect::GainLife {
-                            player: controller,
-                            amount: *amount,
-                        };
-                    }
-                    Effect::DestroyPermanent { target } if target.as_u32() == 0 => {
-                        // Find a valid target (opponent's creature)
-                        let controller = self.cards.get(source_card_id)?.controller;
-                        if let Some(target_id) = self
-                            .battlefield
-                            .cards
-                            .iter()
-                            .find(|&card_id| {
-                                if let Ok(card) = self.cards.get(*card_id) {
-                                    card.is_creature()
-                                        && card.owner != controller
-                                        && !card.has_hexproof()
-                                        && !card.has_shroud()
-                                } else {
-                                    false
-                                }
-                            })
-                            .copied()
-                        {
-                            effect = Effect::DestroyPermanent { target: target_id };
-                        }
-                    }
-                    Effect::PumpCreature {
-                        target,
-                        power_bonus,
-                        toughness_bonus,
-                    } if target.as_u32() == 0 => {
-                        // Find a valid target (any creature on battlefield)
-                        if let Some(target_id) = self
-                            .battlefield
-                            .cards
-                            .iter()
-                            .find(|&card_id| {
-                                if let Ok(card) = self.cards.get(*card_id) {
-                                    card.is_creature() && !card.has_shroud()
-                                } else {
-                                    false
-                                }
-                            })
-                            .copied()
-                        {
-                            effect = Effect::PumpCreature {
-                                target: target_id,
-                                power_bonus: *power_bonus,
-                                toughness_bonus: *toughness_bonus,
-                            };
-                        }
+                    Effect::CreateToken { owner: player, .. }
+                    | Effect::CreateReplacementEffects { controller: player, .. }
+                    | Effect::DrawCards { player, .. }
+                    | Effect::GainLife { player, .. }
+                        if player.as_u32() == 0 =>
+                    {
+                        *player = controller;
                     }
                     _ => {}
                 }
 
-                self.execute_effect(&effect)?;
+                filled_effects.push(effect);
             }
+
+            self.add_pending_trigger(StackAbility {
+                source: source_card_id,
+                controller,
+                effects: filled_effects,
+                targets: Vec::new(),
+                description: trigger.description,
+                target_restriction: trigger.target_restriction,
+            });
         }
 
         Ok(())
     }
 
+    /// Get valid targets for a pending triggered ability (MTG Rules 603.3d)
+    ///
+    /// Candidates come from the kind of effect, like `get_valid_targets_for_spell`,
+    /// narrowed by the trigger's ValidTgts$ restriction. Shroud, hexproof against the
+    /// ability's controller and protection from the source's colors are respected.
+    /// Returns nothing if the ability doesn't target.
+    pub fn get_valid_targets_for_trigger(&self, ability: &StackAbility) -> SmallVec<[CardId; 8]> {
+        let ctx = SelectorContext::new(ability.controller, Some(ability.source));
+        let mut valid_targets: SmallVec<[CardId; 8]> = SmallVec::new();
+        for effect in ability.effects.iter().filter(|effect| effect.needs_target()) {
+            for &card_id in &self.battlefield.cards {
+                let Ok(card) = self.cards.get(card_id) else {
+                    continue;
+                };
+                let fits_effect = match &ability.target_restriction {
+                    Some(restriction) => restriction.matches_card(card, &ctx),
+                    None => match effect {
+                        Effect::DealDamage { .. } | Effect::PumpCreature { .. } | Effect::ExilePermanent { .. } => {
+                            card.is_creature()
+                        }
+                        Effect::TapPermanent { .. } => !card.tapped,
+                        Effect::UntapPermanent { .. } => card.tapped,
+                        _ => true,
+                    },
+                };
+                if fits_effect
+                    && !valid_targets.contains(&card_id)
+                    && self.is_legal_target(ability.controller, ability.source, card_id)
+                {
+                    valid_targets.push(card_id);
+                }
+            }
+        }
+
+        // Sort for deterministic ordering (critical for snapshot/resume)
+        valid_targets.sort();
+        valid_targets
+    }
+
     /// Deal damage to a player target
     pub fn deal_damage(&mut self, target_id: PlayerId, amount: i32) -> Result<()> {
         // Check if target is a player
         if self.players.iter().any(|p| p.id == target_id) {
-            let player = self.get_player_mut(target_id)?;
-            player.lose_life(amount);
-
-            // Log the life change
-            self.undo_log.log(crate::undo::GameAction::ModifyLife {
-                player_id: target_id,
-                delta: -amount,
-            });
-
+            self.deal_damage_from(None, &TargetRef::Player(target_id), amount, false)?;
             return Ok(());
         }
 
@@ -1034,186 +1419,298 @@ impl GameState {
     }
 
     /// Deal damage to a creature
+    ///
+    /// Damage is marked on the creature; it is destroyed by state-based actions
+    /// once the total marked damage is lethal (MTG Rules 704.5g).
     pub fn deal_damage_to_creature(&mut self, target_id: CardId, amount: i32) -> Result<()> {
-        // Get info about the creature first (without holding the borrow)
-        let (is_creature, toughness, owner, has_indestructible) = {
-            let card = self.cards.get(target_id)?;
-            (
-                card.is_creature(),
-                card.current_toughness(),
-                card.owner,
-                card.has_indestructible(),
-            )
-        };
-
-        if is_creature {
-            // Mark damage (simplified - real MTG has damage tracking)
-            // MTG Rules 702.12b: Permanents with indestructible aren't destroyed by lethal damage
-            // For now, if damage >= toughness and creature doesn't have indestructible, creature dies
-            if amount >= toughness as i32 && !has_indestructible {
-                self.move_card(target_id, Zone::Battlefield, Zone::Graveyard, owner)?;
-            }
+        if self.cards.get(target_id)?.is_creature() {
+            self.deal_damage_from(None, &TargetRef::Permanent(target_id), amount, false)?;
             return Ok(());
         }
 
         Err(MtgError::InvalidAction("Invalid damage target".to_string()))
     }
 
-    /// Tap a land for mana (without cost hint)
+    /// Deal damage from `source` (if known) to a player or permanent
+    ///
+    /// Replacement and prevention effects are applied first (MTG Rules 614, 615).
+    /// Damage to a player causes that much life loss, damage to a planeswalker
+    /// removes loyalty counters, and damage to a creature is marked on it (120.3). A source with deathtouch marks the damage as such,
+    /// and one with lifelink makes its controller gain that much life (702.15b).
+    ///
+    /// Returns the damage actually dealt.
+    pub fn deal_damage_from(
+        &mut self,
+        source: Option<CardId>,
+        target: &TargetRef,
+        amount: i32,
+        combat: bool,
+    ) -> Result<i32> {
+        let amount = self.replace_damage(source, target, amount, combat)?;
+        if amount <= 0 {
+            return Ok(0);
+        }
+
+        let (deathtouch, lifelink) = source
+            .and_then(|source| self.cards.get(source).ok())
+            .map_or((false, None), |card| {
+                (card.has_deathtouch(), card.has_lifelink().then_some(card.controller))
+            });
+        match target {
+            TargetRef::Player(player_id) => {
+                self.lose_life(*player_id, amount)?;
+                // MTG Rules 903.10a: combat damage from commanders is tracked per commander
+                if let Some(commander) = source.filter(|&s| combat && self.cards.get(s).is_ok_and(|c| c.is_commander)) {
+                    self.record_commander_damage(*player_id, commander, amount)?;
+                }
+            }
+            TargetRef::Permanent(card_id) => {
+                // MTG Rules 120.3c: damage to a planeswalker removes that many loyalty counters;
+                // it's only marked if the planeswalker is also a creature (120.3e)
+                let card = self.cards.get(*card_id)?;
+                let (is_planeswalker, is_creature) = (card.is_planeswalker(), card.is_creature());
+                if is_planeswalker {
+                    self.remove_counters(*card_id, CounterType::Loyalty, amount.min(u8::MAX as i32) as u8)?;
+                }
+                if is_creature || !is_planeswalker {
+                    self.mark_damage(*card_id, amount, deathtouch)?;
+                }
+            }
+            TargetRef::None => return Ok(0),
+        }
+        if let Some(controller) = lifelink {
+            self.gain_life(controller, amount)?;
+        }
+        Ok(amount)
+    }
+
+    /// A player gains life, unless a replacement effect says otherwise
+    ///
+    /// Returns the life actually gained.
+    pub fn gain_life(&mut self, player_id: PlayerId, amount: i32) -> Result<i32> {
+        let amount = self.replace_life_gain(player_id, amount)?;
+        if amount <= 0 {
+            return Ok(0);
+        }
+        self.change_life(player_id, amount)?;
+        Ok(amount)
+    }
+
+    /// A player loses life, unless a replacement effect says otherwise
+    ///
+    /// Returns the life actually lost.
+    pub fn lose_life(&mut self, player_id: PlayerId, amount: i32) -> Result<i32> {
+        let amount = self.replace_life_loss(player_id, amount)?;
+        if amount <= 0 {
+            return Ok(0);
+        }
+        self.change_life(player_id, -amount)?;
+        Ok(amount)
+    }
+
+    /// Change a player's life total and log it
+    ///
+    /// Losing life down to 0 makes the player lose at once, which is logged too.
+    pub(crate) fn change_life(&mut self, player_id: PlayerId, delta: i32) -> Result<()> {
+        let player = self.get_player_mut(player_id)?;
+        let had_lost = player.has_lost;
+        if delta >= 0 {
+            player.gain_life(delta);
+        } else {
+            player.lose_life(-delta);
+        }
+        let lost = player.has_lost && !had_lost;
+        self.undo_log
+            .log(crate::undo::GameAction::ModifyLife { player_id, delta });
+        if lost {
+            self.undo_log.log(crate::undo::GameAction::PlayerLoses { player_id });
+        }
+        Ok(())
+    }
+
+    /// Whether a creature entered the battlefield this turn and lacks haste, so it
+    /// can't attack or activate abilities with {T} in their cost (MTG Rules 302.6)
+    pub fn has_summoning_sickness(&self, card_id: CardId) -> bool {
+        self.cards.get(card_id).is_ok_and(|card| {
+            card.is_creature()
+                && card.turn_entered_battlefield == Some(self.turn.turn_number)
+                && !card.has_keyword(&Keyword::Haste)
+        })
+    }
+
+    /// Tap a permanent for mana (without cost hint)
     pub fn tap_for_mana(&mut self, player_id: PlayerId, card_id: CardId) -> Result<()> {
         // Create an empty cost hint
         let empty_cost = crate::core::ManaCost::new();
         self.tap_for_mana_for_cost(player_id, card_id, &empty_cost)
     }
 
-    /// Tap a land for mana with a cost hint to guide color production for any-color lands
+    /// Activate a permanent's mana ability, with a cost hint to guide which ability
+    /// to use and which color to produce
+    ///
+    /// Works for any permanent with a mana ability whose cost includes {T} (see
+    /// [`mana_productions`](crate::game::mana_payment::mana_productions)). Abilities
+    /// producing a color the cost still needs are preferred, then ones without life
+    /// or mana to pay. Those extra costs are paid here, before the mana is added.
     pub fn tap_for_mana_for_cost(
         &mut self,
         player_id: PlayerId,
         card_id: CardId,
         cost_hint: &crate::core::ManaCost,
     ) -> Result<()> {
-        let card = self.cards.get_mut(card_id)?;
-
-        // Check if card is a land and untapped
-        if !card.is_land() {
-            return Err(MtgError::InvalidAction("Card is not a land".to_string()));
-        }
+        use crate::core::Color;
+        use crate::game::mana_payment::{mana_productions, ManaProductionKind};
 
+        let card = self.cards.get(card_id)?;
         if card.tapped {
-            return Err(MtgError::InvalidAction("Land is already tapped".to_string()));
+            return Err(MtgError::InvalidAction("Permanent is already tapped".to_string()));
+        }
+        if self.has_summoning_sickness(card_id) {
+            return Err(MtgError::InvalidAction(
+                "Creature has summoning sickness and can't tap for mana".to_string(),
+            ));
+        }
+        let productions = mana_productions(card);
+        if productions.is_empty() {
+            return Err(MtgError::InvalidAction("Card has no mana ability".to_string()));
         }
 
-        // Get land name before tapping (to avoid borrow conflicts)
-        let land_name = card.name.to_lowercase();
-
-        // Tap the land
-        card.tap();
+        // Colors the cost asks for that the pool doesn't have enough of yet
+        let player = self.get_player(player_id)?;
+        let still_needed = |color: Color| match color {
+            Color::Colorless => player.mana_pool.colorless < cost_hint.colorless,
+            _ => player.mana_pool.amount_of(color) < cost_hint.symbols_of_color(color),
+        };
+        let colors_of = |kind: &ManaProductionKind| -> SmallVec<[Color; 5]> {
+            match kind {
+                ManaProductionKind::Fixed(color) => smallvec::smallvec![color.to_color()],
+                ManaProductionKind::Choice(colors) => colors.iter().map(|c| c.to_color()).collect(),
+                ManaProductionKind::AnyColor => crate::core::WUBRG.into_iter().collect(),
+                ManaProductionKind::Colorless => smallvec::smallvec![Color::Colorless],
+            }
+        };
 
-        // Log the tap
-        self.undo_log
-            .log(crate::undo::GameAction::TapCard { card_id, tapped: true });
+        let production = productions
+            .iter()
+            .filter(|p| {
+                i32::from(p.life_cost) <= player.life
+                    && p.activation_cost.is_none_or(|cost| player.mana_pool.can_pay(&cost))
+            })
+            .min_by_key(|p| {
+                let useful = colors_of(&p.kind).into_iter().any(still_needed);
+                let mana_cost = p.activation_cost.map_or(0, |cost| cost.cmc());
+                (!useful, p.life_cost, mana_cost)
+            })
+            .cloned()
+            .ok_or_else(|| MtgError::InvalidAction("Can't pay for any of the card's mana abilities".to_string()))?;
 
-        // Add mana to player's pool based on land type
-        // For basic lands and simple cases, check name
-        // For dual lands (e.g., Underground Sea = Island Swamp), we need smarter logic
-        // First, check subtypes and any-color before we borrow player_mut
-        let (
-            has_swamp_subtype,
-            has_mountain_subtype,
-            has_island_subtype,
-            has_forest_subtype,
-            has_plains_subtype,
-            is_any_color_land,
-        ) = {
-            let card = self.cards.get(card_id)?;
-            (
-                card.subtypes.iter().any(|s| s.as_str().eq_ignore_ascii_case("swamp")),
-                card.subtypes
-                    .iter()
-                    .any(|s| s.as_str().eq_ignore_ascii_case("mountain")),
-                card.subtypes.iter().any(|s| s.as_str().eq_ignore_ascii_case("island")),
-                card.subtypes.iter().any(|s| s.as_str().eq_ignore_ascii_case("forest")),
-                card.subtypes.iter().any(|s| s.as_str().eq_ignore_ascii_case("plains")),
-                card.text.to_lowercase().contains("any color"),
-            )
-        };
+        // Produce the first color the cost needs, or the first one the ability makes
+        let options = colors_of(&production.kind);
+        let color = options.iter().copied().find(|&c| still_needed(c)).unwrap_or(options[0]);
 
-        let player = self.get_player_mut(player_id)?;
+        self.activate_mana_production(player_id, card_id, &production, color)
+    }
 
-        // Determine what colors this land can produce
-        let mut available_colors = Vec::new();
-        if has_plains_subtype || land_name.contains("plains") {
-            available_colors.push(crate::core::Color::White);
-        }
-        if has_island_subtype || land_name.contains("island") {
-            available_colors.push(crate::core::Color::Blue);
-        }
-        if has_swamp_subtype || land_name.contains("swamp") {
-            available_colors.push(crate::core::Color::Black);
-        }
-        if has_mountain_subtype || land_name.contains("mountain") {
-            available_colors.push(crate::core::Color::Red);
-        }
-        if has_forest_subtype || land_name.contains("forest") {
-            available_colors.push(crate::core::Color::Green);
-        }
-
-        let color = if is_any_color_land || available_colors.len() > 1 {
-            // Multi-color or any-color land: choose based on cost hint
-            // Produce the first color needed by the cost that this land can produce
-            if cost_hint.white > 0 && (is_any_color_land || available_colors.contains(&crate::core::Color::White)) {
-                Some(crate::core::Color::White)
-            } else if cost_hint.blue > 0 && (is_any_color_land || available_colors.contains(&crate::core::Color::Blue))
-            {
-                Some(crate::core::Color::Blue)
-            } else if cost_hint.black > 0
-                && (is_any_color_land || available_colors.contains(&crate::core::Color::Black))
-            {
-                Some(crate::core::Color::Black)
-            } else if cost_hint.red > 0 && (is_any_color_land || available_colors.contains(&crate::core::Color::Red)) {
-                Some(crate::core::Color::Red)
-            } else if cost_hint.green > 0
-                && (is_any_color_land || available_colors.contains(&crate::core::Color::Green))
-            {
-                Some(crate::core::Color::Green)
-            } else {
-                // Cost doesn't need a specific color - produce the first available color
-                available_colors.first().copied().or(Some(crate::core::Color::White))
+    /// Tap mana sources to pay for a cost, following the best plan the
+    /// [`BacktrackingResolver`](crate::game::BacktrackingResolver) finds among them
+    ///
+    /// Only the sources the plan needs are tapped, each making the color the plan
+    /// chose. If the sources can't pay for the cost, they're all tapped one by one
+    /// with the cost as a hint. Returns the sources tapped.
+    pub fn tap_mana_sources_for_cost(
+        &mut self,
+        player_id: PlayerId,
+        source_ids: &[CardId],
+        cost: &crate::core::ManaCost,
+    ) -> Result<Vec<CardId>> {
+        use crate::game::mana_payment::{BacktrackingResolver, ManaSource};
+
+        let mut sources = Vec::new();
+        for &source_id in source_ids {
+            sources.extend(ManaSource::for_permanent(self, self.cards.get(source_id)?));
+        }
+        let max_life_payments = self.max_life_payments(player_id);
+        let Some((plan, _)) = BacktrackingResolver::new().solve_with_life(cost, &sources, max_life_payments) else {
+            for &source_id in source_ids {
+                self.tap_for_mana_for_cost(player_id, source_id, cost)?;
             }
-        } else if available_colors.len() == 1 {
-            // Single-color land
-            available_colors.first().copied()
-        } else {
-            // Unknown land type
-            None
+            return Ok(source_ids.to_vec());
         };
 
-        if let Some(color) = color {
-            player.mana_pool.add_color(color);
+        for tap in &plan {
+            self.activate_mana_production(player_id, tap.card_id, &sources[tap.source].production, tap.color)?;
+        }
+        Ok(plan.iter().map(|tap| tap.card_id).collect())
+    }
 
-            // Log the mana addition
-            let mut mana = crate::core::ManaCost::new();
-            let color_symbol = match color {
-                crate::core::Color::White => {
-                    mana.white = 1;
-                    "W"
-                }
-                crate::core::Color::Blue => {
-                    mana.blue = 1;
-                    "U"
-                }
-                crate::core::Color::Black => {
-                    mana.black = 1;
-                    "B"
-                }
-                crate::core::Color::Red => {
-                    mana.red = 1;
-                    "R"
-                }
-                crate::core::Color::Green => {
-                    mana.green = 1;
-                    "G"
-                }
-                crate::core::Color::Colorless => {
-                    mana.colorless = 1;
-                    "C"
-                }
-            };
-            self.undo_log.log(crate::undo::GameAction::AddMana { player_id, mana });
+    /// Tap a permanent and pay for one of its mana abilities, then add the mana
+    fn activate_mana_production(
+        &mut self,
+        player_id: PlayerId,
+        card_id: CardId,
+        production: &crate::game::mana_payment::ManaProduction,
+        color: crate::core::Color,
+    ) -> Result<()> {
+        use crate::core::Color;
+
+        // Pay the ability's costs: tap, then mana and life
+        self.cards.get_mut(card_id)?.tap();
+        self.undo_log
+            .log(crate::undo::GameAction::TapCard { card_id, tapped: true });
+        if let Some(activation_cost) = production.activation_cost {
+            self.pay_mana_cost(player_id, &activation_cost)?;
+        }
+        if production.life_cost > 0 {
+            self.change_life(player_id, -i32::from(production.life_cost))?;
+        }
 
-            // Log visible message for mana tapping
-            if self.logger.verbosity() >= crate::game::VerbosityLevel::Normal {
-                let card_name = self.cards.get(card_id).map(|c| c.name.as_str()).unwrap_or("Unknown");
-                println!("  Tap {} for {{{}}}", card_name, color_symbol);
+        // Add the mana; mana from a snow source is snow mana, which can pay for {S}
+        let player = self.get_player_mut(player_id)?;
+        let mut mana = crate::core::ManaCost::new();
+        for _ in 0..production.amount {
+            if production.is_snow {
+                player.mana_pool.add_snow_color(color);
+            } else {
+                player.mana_pool.add_color(color);
             }
+            mana.add_color(color);
+        }
+        if production.is_snow {
+            mana.snow = production.amount;
+        }
+        self.undo_log.log(crate::undo::GameAction::AddMana { player_id, mana });
+
+        // Log visible message for mana tapping
+        if self.logger.verbosity() >= crate::game::VerbosityLevel::Normal {
+            let card_name = self.cards.get(card_id).map(|c| c.name.as_str()).unwrap_or("Unknown");
+            let symbol = match color {
+                Color::White => "{W}",
+                Color::Blue => "{U}",
+                Color::Black => "{B}",
+                Color::Red => "{R}",
+                Color::Green => "{G}",
+                Color::Colorless => "{C}",
+            };
+            println!("  Tap {} for {}", card_name, symbol.repeat(production.amount as usize));
         }
 
         Ok(())
     }
 
-    /// Declare a creature as an attacker
+    /// Declare a creature as an attacker, attacking the opponent
     pub fn declare_attacker(&mut self, player_id: PlayerId, card_id: CardId) -> Result<()> {
+        // Get defending player: the next opponent in turn order (for 2-player, the other player)
+        let defending_player = Some(self.next_player_in_turn_order(player_id))
+            .filter(|&opponent| opponent != player_id)
+            .ok_or_else(|| MtgError::InvalidAction("No opponent found".to_string()))?;
+        self.declare_attacker_at(player_id, card_id, AttackTarget::Player(defending_player))
+    }
+
+    /// Declare a creature as an attacker, attacking a player or planeswalker
+    ///
+    /// MTG Rules 508.1b: the target has to be an opponent or a planeswalker an
+    /// opponent controls.
+    pub fn declare_attacker_at(&mut self, player_id: PlayerId, card_id: CardId, target: AttackTarget) -> Result<()> {
         // Validate creature can attack
         let card = self.cards.get(card_id)?;
 
@@ -1253,24 +1750,18 @@ impl GameState {
 
         // Check for summoning sickness
         // Creatures can't attack the turn they entered the battlefield unless they have haste
-        if let Some(entered_turn) = card.turn_entered_battlefield {
-            if entered_turn == self.turn.turn_number && !card.has_keyword(&Keyword::Haste) {
-                return Err(MtgError::InvalidAction(
-                    "Creature has summoning sickness and can't attack this turn".to_string(),
-                ));
-            }
+        if self.has_summoning_sickness(card_id) {
+            return Err(MtgError::InvalidAction(
+                "Creature has summoning sickness and can't attack this turn".to_string(),
+            ));
         }
 
-        // Get defending player (for 2-player, it's the other player)
-        let defending_player = self
-            .players
-            .iter()
-            .find(|p| p.id != player_id)
-            .map(|p| p.id)
-            .ok_or_else(|| MtgError::InvalidAction("No opponent found".to_string()))?;
+        if !self.attack_targets(player_id).contains(&target) {
+            return Err(MtgError::InvalidAction(format!("Can't attack {target:?}")));
+        }
 
         // Declare attacker in combat state
-        self.combat.declare_attacker(card_id, defending_player);
+        self.add_attacker(card_id, target)?;
 
         // Tap the creature (unless it has vigilance)
         let has_vigilance = self.cards.get(card_id)?.has_keyword(&Keyword::Vigilance);
@@ -1360,7 +1851,7 @@ impl GameState {
         for &attacker in &attackers {
             attackers_vec.push(attacker);
         }
-        self.combat.declare_blocker(blocker_id, attackers_vec);
+        self.add_blocker(blocker_id, attackers_vec);
 
         Ok(())
     }
@@ -1386,10 +1877,30 @@ impl GameState {
         blocker_controller: &mut dyn crate::game::controller::PlayerController,
         first_strike_step: bool,
     ) -> Result<()> {
+        self.assign_multiplayer_combat_damage(&mut [attacker_controller, blocker_controller], first_strike_step)
+    }
+
+    /// Assign and deal combat damage, with a controller for each player in the game
+    ///
+    /// See `assign_combat_damage`; the controller of each attacker blocked by several
+    /// creatures is asked for its damage assignment order.
+    pub fn assign_multiplayer_combat_damage(
+        &mut self,
+        controllers: &mut [&mut dyn crate::game::controller::PlayerController],
+        first_strike_step: bool,
+    ) -> Result<()> {
+        let damage_orders = self.choose_damage_assignment_orders(controllers)?;
+        self.deal_combat_damage(&damage_orders, first_strike_step)
+    }
+
+    /// Ask the controller of each attacker blocked by several creatures for its
+    /// damage assignment order
+    pub fn choose_damage_assignment_orders(
+        &self,
+        controllers: &mut [&mut dyn crate::game::controller::PlayerController],
+    ) -> Result<HashMap<CardId, SmallVec<[CardId; 4]>>> {
         use crate::game::controller::GameStateView;
-        use std::collections::HashMap;
 
-        // First pass: collect all damage assignment orders for attackers with multiple blockers
         let mut damage_orders: HashMap<CardId, SmallVec<[CardId; 4]>> = HashMap::new();
 
         // Use iterator to avoid Vec allocation for attackers
@@ -1403,24 +1914,29 @@ impl GameState {
                     let attacker_owner = attacker.owner;
                     let view = GameStateView::new(self, attacker_owner);
 
-                    let ordered_blockers = if attacker_owner == attacker_controller.player_id() {
-                        attacker_controller.choose_damage_assignment_order(&view, attacker_id, &blockers)
-                    } else {
-                        blocker_controller.choose_damage_assignment_order(&view, attacker_id, &blockers)
-                    };
-
-                    damage_orders.insert(attacker_id, ordered_blockers);
+                    if let Some(controller) = controllers.iter_mut().find(|c| c.player_id() == attacker_owner) {
+                        let ordered_blockers = controller.choose_damage_assignment_order(&view, attacker_id, &blockers);
+                        damage_orders.insert(attacker_id, ordered_blockers);
+                    }
                 }
             }
         }
 
-        // Second pass: assign all damage
-        let mut damage_to_creatures: HashMap<CardId, i32> = HashMap::new();
-        let mut damage_to_players: HashMap<PlayerId, i32> = HashMap::new();
-        // Track damage dealt by each creature for lifelink (creature_id -> total damage dealt)
-        let mut damage_dealt_by_creature: HashMap<CardId, i32> = HashMap::new();
-        // Track creatures dealt deathtouch damage (for state-based destruction)
-        let mut deathtouch_damaged_creatures: std::collections::HashSet<CardId> = std::collections::HashSet::new();
+        Ok(damage_orders)
+    }
+
+    /// Assign and deal combat damage in one combat damage step
+    ///
+    /// Attackers blocked by several creatures assign damage in the order from
+    /// `damage_orders`, or in blocking order if they have none.
+    pub fn deal_combat_damage(
+        &mut self,
+        damage_orders: &HashMap<CardId, SmallVec<[CardId; 4]>>,
+        first_strike_step: bool,
+    ) -> Result<()> {
+        // Assign all damage as (source, recipient, amount), dealt once all of it
+        // is assigned. Deathtouch and lifelink come from each source.
+        let mut assignments: Vec<(CardId, TargetRef, i32)> = Vec::new();
 
         // Use iterator again for second pass (zero allocation)
         for attacker_id in self.combat.attackers_iter() {
@@ -1466,24 +1982,27 @@ impl GameState {
                 //   * WITH trample: assign at least lethal, rest can trample over
                 // - If multiple creatures are blocking: assign at least lethal to each
                 //   before assigning to the next (can assign more)
-                // Note: Current implementation doesn't track damage, so lethal = toughness
                 let has_trample = attacker.has_trample();
                 for blocker_id in &ordered_blockers {
                     if remaining_power <= 0 {
                         break;
                     }
+                    // A blocker that left the battlefield (or the game) is removed from combat
+                    if !self.battlefield.contains(*blocker_id) {
+                        continue;
+                    }
 
                     let blocker = self.cards.get(*blocker_id)?;
                     let blocker_toughness = blocker.current_toughness();
 
-                    // Lethal damage is the creature's toughness
+                    // Lethal damage is the creature's toughness minus damage already marked
                     // MTG Rules 702.2c: If attacker has deathtouch, any nonzero damage is lethal
-                    // (In full MTG, this would be toughness minus damage already marked)
                     let has_deathtouch = attacker.has_deathtouch();
                     let lethal_damage = if has_deathtouch && blocker_toughness > 0 {
                         1 // Any nonzero damage from deathtouch is lethal
                     } else {
-                        blocker_toughness
+                        let already_marked = blocker.damage_marked.clamp(0, i8::MAX as i32) as i8;
+                        blocker_toughness.saturating_sub(already_marked).max(0)
                     };
 
                     let damage_to_assign = if ordered_blockers.len() == 1 && !has_trample {
@@ -1498,13 +2017,7 @@ impl GameState {
                     };
 
                     if damage_to_assign > 0 {
-                        *damage_to_creatures.entry(*blocker_id).or_insert(0) += damage_to_assign as i32;
-                        // Track damage for lifelink
-                        *damage_dealt_by_creature.entry(attacker_id).or_insert(0) += damage_to_assign as i32;
-                        // Track deathtouch damage (MTG Rules 702.2b)
-                        if has_deathtouch {
-                            deathtouch_damaged_creatures.insert(*blocker_id);
-                        }
+                        assignments.push((attacker_id, TargetRef::Permanent(*blocker_id), damage_to_assign as i32));
                         remaining_power -= damage_to_assign;
                     }
                 }
@@ -1513,10 +2026,8 @@ impl GameState {
                 // assigning lethal to all blockers, assign remaining to defending player
                 // MTG Rules 702.19
                 if attacker.has_trample() && remaining_power > 0 {
-                    if let Some(defending_player) = self.combat.get_defending_player(attacker_id) {
-                        *damage_to_players.entry(defending_player).or_insert(0) += remaining_power as i32;
-                        // Track damage for lifelink
-                        *damage_dealt_by_creature.entry(attacker_id).or_insert(0) += remaining_power as i32;
+                    if let Some(recipient) = self.attack_damage_recipient(attacker_id) {
+                        assignments.push((attacker_id, recipient, remaining_power as i32));
                     }
                 }
 
@@ -1543,81 +2054,84 @@ impl GameState {
 
                     let blocker_power = blocker.current_power();
                     if blocker_power > 0 {
-                        *damage_to_creatures.entry(attacker_id).or_insert(0) += blocker_power as i32;
-                        // Track damage for lifelink
-                        *damage_dealt_by_creature.entry(*blocker_id).or_insert(0) += blocker_power as i32;
-                        // Track deathtouch damage from blocker (MTG Rules 702.2b)
-                        if blocker.has_deathtouch() {
-                            deathtouch_damaged_creatures.insert(attacker_id);
-                        }
+                        assignments.push((*blocker_id, TargetRef::Permanent(attacker_id), blocker_power as i32));
                     }
                 }
             } else {
-                // Unblocked attacker deals damage to defending player
-                if let Some(defending_player) = self.combat.get_defending_player(attacker_id) {
-                    *damage_to_players.entry(defending_player).or_insert(0) += remaining_power as i32;
-                    // Track damage for lifelink
-                    *damage_dealt_by_creature.entry(attacker_id).or_insert(0) += remaining_power as i32;
+                // Unblocked attacker deals damage to the player or planeswalker it's attacking
+                if let Some(recipient) = self.attack_damage_recipient(attacker_id) {
+                    assignments.push((attacker_id, recipient, remaining_power as i32));
                 }
             }
         }
 
-        // Apply lifelink BEFORE dealing damage (since creatures might die)
-        // MTG Rules 702.15: Damage dealt by a source with lifelink also causes
-        // its controller to gain that much life
-        for (creature_id, total_damage) in &damage_dealt_by_creature {
-            if let Ok(creature) = self.cards.get(*creature_id) {
-                if creature.has_lifelink() {
-                    let controller = creature.controller;
-                    if let Ok(player) = self.get_player_mut(controller) {
-                        player.gain_life(*total_damage);
-                    }
-                }
-            }
+        // Deal all damage simultaneously (MTG Rules 510.2); no creature dies before
+        // state-based actions are checked, so every source still has its abilities.
+        // Deathtouch marks the damage (702.2b) and lifelink gains its controller
+        // that much life (702.15b), counting only damage that isn't prevented.
+        for (source, recipient, damage) in assignments {
+            self.deal_damage_from(Some(source), &recipient, damage, true)?;
         }
 
-        // Deal all damage simultaneously
-        for (creature_id, damage) in damage_to_creatures {
-            self.deal_damage_to_creature(creature_id, damage)?;
-        }
+        // MTG Rules 510.2 / 704.3: creatures dealt lethal or deathtouch damage are destroyed
+        // by state-based actions before anyone receives priority (and before the regular
+        // damage step when there was a first strike step)
+        self.check_state_based_actions()?;
 
-        for (player_id, damage) in damage_to_players {
-            self.deal_damage(player_id, damage)?;
-        }
+        Ok(())
+    }
 
-        // Apply deathtouch state-based action (MTG Rules 702.2b)
-        // Any creature with toughness > 0 that was dealt damage by a deathtouch source is destroyed
-        // MTG Rules 702.12b: Permanents with indestructible can't be destroyed
-        for creature_id in deathtouch_damaged_creatures {
-            // Check if creature is still on battlefield (might have died from normal damage)
-            if self.battlefield.contains(creature_id) {
-                if let Ok(creature) = self.cards.get(creature_id) {
-                    // Only destroy if it has toughness > 0 and doesn't have indestructible
-                    if creature.is_creature() && creature.current_toughness() > 0 && !creature.has_indestructible() {
-                        let owner = creature.owner;
-                        self.move_card(creature_id, Zone::Battlefield, Zone::Graveyard, owner)?;
-                    }
-                }
-            }
+    /// Who an unblocked (or trampling) attacker deals its combat damage to
+    ///
+    /// A planeswalker that has left the battlefield is removed from combat, so
+    /// creatures attacking it deal no combat damage (MTG Rules 506.4); neither
+    /// do creatures attacking a player who has left the game (800.4a).
+    fn attack_damage_recipient(&self, attacker_id: CardId) -> Option<TargetRef> {
+        match self.combat.get_attack_target(attacker_id)? {
+            AttackTarget::Player(player_id) => self.is_in_game(player_id).then_some(TargetRef::Player(player_id)),
+            AttackTarget::Planeswalker(planeswalker) => self
+                .battlefield
+                .contains(planeswalker)
+                .then_some(TargetRef::Permanent(planeswalker)),
         }
-
-        Ok(())
     }
 
     /// Pay the cost for an activated ability
     ///
+    /// Cards for costs that choose them (sacrifice, discard, etc.) are the first
+    /// ones that qualify; see [`pay_ability_cost_with_choices`](Self::pay_ability_cost_with_choices)
+    /// to let the player choose.
+    pub fn pay_ability_cost(&mut self, player_id: PlayerId, card_id: CardId, cost: &crate::core::Cost) -> Result<()> {
+        self.pay_ability_cost_with_choices(player_id, card_id, cost, &mut |_, _, candidates, count| {
+            candidates.iter().take(count).copied().collect()
+        })
+    }
+
+    /// Pay the cost for an activated ability, asking which cards pay for it
+    ///
     /// This method pays costs in the correct order:
     /// 1. Tap costs (must happen before zone changes)
     /// 2. Mana costs (pay from mana pool)
-    /// 3. Other costs (sacrifice, discard, etc.) - TODO
+    /// 3. Other costs (sacrifice, discard, exile, etc.)
+    ///
+    /// `choose_cards` is called for each part of the cost where there's a choice
+    /// (see [`cost_choice`](Self::cost_choice)) with the part, the cards that
+    /// could pay it and how many to choose. It must return that many of them.
     ///
     /// Returns Ok(()) if costs were successfully paid, Err otherwise.
     ///
-    /// Note: This is a simplified implementation. Full implementation would:
-    /// - Support cost refund if payment fails midway
-    /// - Handle cost ordering more comprehensively
-    /// - Support all cost types (sacrifice, discard, pay life, etc.)
-    pub fn pay_ability_cost(&mut self, player_id: PlayerId, card_id: CardId, cost: &crate::core::Cost) -> Result<()> {
+    /// Note: costs paid before a failing part aren't refunded; check
+    /// [`can_pay_ability_cost`](Self::can_pay_ability_cost) first.
+    pub fn pay_ability_cost_with_choices<F>(
+        &mut self,
+        player_id: PlayerId,
+        card_id: CardId,
+        cost: &crate::core::Cost,
+        choose_cards: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&GameState, &crate::core::Cost, &[CardId], usize) -> SmallVec<[CardId; 8]>,
+    {
         use crate::core::Cost;
 
         match cost {
@@ -1628,17 +2142,14 @@ impl GameState {
                     return Err(MtgError::InvalidAction("Permanent is already tapped".to_string()));
                 }
                 card.tap();
+                self.undo_log
+                    .log(crate::undo::GameAction::TapCard { card_id, tapped: true });
                 Ok(())
             }
 
             Cost::Mana(mana_cost) => {
-                // Pay mana from pool
-                let player = self.get_player_mut(player_id)?;
-                if !player.mana_pool.can_pay(mana_cost) {
-                    return Err(MtgError::InvalidAction("Cannot pay mana cost".to_string()));
-                }
-                player.mana_pool.pay_cost(mana_cost).map_err(MtgError::InvalidAction)?;
-                Ok(())
+                // Pay mana from pool (leaves the pool alone if it can't be paid)
+                self.pay_mana_cost(player_id, mana_cost)
             }
 
             Cost::TapAndMana(mana_cost) => {
@@ -1649,25 +2160,20 @@ impl GameState {
                     return Err(MtgError::InvalidAction("Permanent is already tapped".to_string()));
                 }
                 card.tap();
+                self.undo_log
+                    .log(crate::undo::GameAction::TapCard { card_id, tapped: true });
 
                 // Then pay mana
-                let player = self.get_player_mut(player_id)?;
-                if !player.mana_pool.can_pay(mana_cost) {
-                    // TODO: Should refund the tap here
-                    return Err(MtgError::InvalidAction("Cannot pay mana cost".to_string()));
-                }
-                player.mana_pool.pay_cost(mana_cost).map_err(MtgError::InvalidAction)?;
-                Ok(())
+                // TODO: Should refund the tap if this fails
+                self.pay_mana_cost(player_id, mana_cost)
             }
 
             Cost::PayLife { amount } => {
                 // Pay life
-                let player = self.get_player_mut(player_id)?;
-                if player.life < *amount {
+                if self.get_player(player_id)?.life < *amount {
                     return Err(MtgError::InvalidAction("Not enough life".to_string()));
                 }
-                player.life -= amount;
-                Ok(())
+                self.change_life(player_id, -amount)
             }
 
             Cost::Untap => {
@@ -1677,106 +2183,239 @@ impl GameState {
                     return Err(MtgError::InvalidAction("Permanent is not tapped".to_string()));
                 }
                 card.untap();
+                self.undo_log
+                    .log(crate::undo::GameAction::TapCard { card_id, tapped: false });
                 Ok(())
             }
 
-            Cost::SacrificePattern { count, card_type } => {
-                // Find permanents matching the pattern and sacrifice them
-                // For now, automatically choose without asking the controller
-                // TODO: Let controller choose which permanents to sacrifice
-
-                let mut to_sacrifice = Vec::new();
-
-                // Special case: CARDNAME means the card with this ability
-                if card_type == "CARDNAME" {
-                    to_sacrifice.push(card_id);
-                } else {
-                    // Find permanents on battlefield matching the type
-                    // Collect IDs first to avoid borrowing issues
-                    let battlefield_cards = self.battlefield.cards.to_vec();
-
-                    for permanent_id in battlefield_cards {
-                        if to_sacrifice.len() >= *count as usize {
-                            break;
+            Cost::SacrificePattern { .. }
+            | Cost::DiscardPattern { .. }
+            | Cost::ExileFromGraveyard { .. }
+            | Cost::TapPattern { .. }
+            | Cost::ReturnToHand { .. } => {
+                let chosen = self.choose_cost_cards(player_id, card_id, cost, choose_cards)?;
+                for chosen_id in chosen {
+                    let owner = self.cards.get(chosen_id)?.owner;
+                    match cost {
+                        Cost::SacrificePattern { .. } => {
+                            self.move_card(chosen_id, Zone::Battlefield, Zone::Graveyard, owner)?;
                         }
-
-                        let card = self.cards.get(permanent_id)?;
-
-                        // Check ownership
-                        if card.owner != player_id {
-                            continue;
+                        Cost::DiscardPattern { .. } => {
+                            self.move_card(chosen_id, Zone::Hand, Zone::Graveyard, owner)?;
                         }
-
-                        // Check if it matches the pattern
-                        let matches = if card_type == "Land" {
-                            card.is_land()
-                        } else if card_type.starts_with("Creature") {
-                            if card_type == "Creature.Other" {
-                                // Other means not the card with the ability
-                                card.is_creature() && permanent_id != card_id
-                            } else {
-                                card.is_creature()
-                            }
-                        } else if card_type == "Artifact" {
-                            card.is_artifact()
-                        } else {
-                            // Generic type match - check if any type contains the string
-                            card.types.iter().any(|t| format!("{t:?}").contains(card_type))
-                        };
-
-                        if matches {
-                            to_sacrifice.push(permanent_id);
+                        Cost::ExileFromGraveyard { .. } => {
+                            self.move_card(chosen_id, Zone::Graveyard, Zone::Exile, owner)?;
+                        }
+                        Cost::ReturnToHand { .. } => {
+                            self.move_card(chosen_id, Zone::Battlefield, Zone::Hand, owner)?;
+                        }
+                        _ => {
+                            self.cards.get_mut(chosen_id)?.tap();
+                            self.undo_log.log(crate::undo::GameAction::TapCard {
+                                card_id: chosen_id,
+                                tapped: true,
+                            });
                         }
                     }
                 }
-
-                // Check if we found enough permanents to sacrifice
-                if to_sacrifice.len() < *count as usize {
-                    return Err(MtgError::InvalidAction(format!(
-                        "Not enough permanents of type {} to sacrifice (need {}, found {})",
-                        card_type,
-                        count,
-                        to_sacrifice.len()
-                    )));
-                }
-
-                // Sacrifice the permanents (move to graveyard)
-                for sac_id in to_sacrifice.iter().take(*count as usize) {
-                    let owner = self.cards.get(*sac_id)?.owner;
-                    self.move_card(*sac_id, Zone::Battlefield, Zone::Graveyard, owner)?;
-                }
-
                 Ok(())
             }
 
             Cost::Sacrifice { card_id: sac_id } => {
                 // Sacrifice a specific permanent (move to graveyard)
                 let owner = self.cards.get(*sac_id)?.owner;
-                self.move_card(*sac_id, Zone::Battlefield, Zone::Graveyard, owner)
+                self.move_card(*sac_id, Zone::Battlefield, Zone::Graveyard, owner)?;
+                Ok(())
+            }
+
+            Cost::AddCounter { counter_type, amount } => {
+                self.add_counters(card_id, *counter_type, *amount)?;
+                Ok(())
+            }
+
+            Cost::SubCounter { counter_type, amount } => {
+                // Can't remove counters that aren't there (MTG Rules 606.6 for loyalty)
+                if self.cards.get(card_id)?.get_counter(*counter_type) < *amount {
+                    return Err(MtgError::InvalidAction(format!("Not enough {counter_type:?} counters")));
+                }
+                self.remove_counters(card_id, *counter_type, *amount)?;
+                Ok(())
             }
 
-            Cost::Discard { card_id: _ } => {
-                // TODO: Implement discard cost
-                Err(MtgError::InvalidAction(format!(
-                    "Cost type {cost:?} not yet implemented"
-                )))
+            Cost::Discard { card_id: discard_id } => {
+                if !self
+                    .get_player_zones(player_id)
+                    .is_some_and(|zones| zones.hand.contains(*discard_id))
+                {
+                    return Err(MtgError::InvalidAction("Card to discard isn't in hand".to_string()));
+                }
+                self.move_card(*discard_id, Zone::Hand, Zone::Graveyard, player_id)?;
+                Ok(())
             }
 
             Cost::Composite(costs) => {
                 // Pay each cost in order
                 for sub_cost in costs {
-                    self.pay_ability_cost(player_id, card_id, sub_cost)?;
+                    self.pay_ability_cost_with_choices(player_id, card_id, sub_cost, choose_cards)?;
                 }
                 Ok(())
             }
         }
     }
+
+    /// The cards that could pay a part of a cost that chooses cards, and how many it takes
+    ///
+    /// Covers sacrificing, tapping and returning permanents the player controls,
+    /// discarding, and exiling from their graveyard. `CARDNAME` means the source
+    /// itself. Returns None for other kinds of cost.
+    pub fn cost_candidates(
+        &self,
+        player_id: PlayerId,
+        source_id: CardId,
+        part: &crate::core::Cost,
+    ) -> Option<(Vec<CardId>, usize)> {
+        use crate::core::{Card, Cost, Selector, SelectorContext};
+
+        let (count, card_type, zone) = match part {
+            Cost::SacrificePattern { count, card_type }
+            | Cost::TapPattern { count, card_type }
+            | Cost::ReturnToHand { count, card_type } => (count, card_type, Zone::Battlefield),
+            Cost::DiscardPattern { count, card_type } => (count, card_type, Zone::Hand),
+            Cost::ExileFromGraveyard { count, card_type } => (count, card_type, Zone::Graveyard),
+            _ => return None,
+        };
+
+        let cards: &[CardId] = match zone {
+            Zone::Battlefield => &self.battlefield.cards,
+            Zone::Hand => &self.get_player_zones(player_id)?.hand.cards,
+            _ => &self.get_player_zones(player_id)?.graveyard.cards,
+        };
+        let is_candidate = |card: &Card| match part {
+            _ if zone != Zone::Battlefield => true,
+            Cost::TapPattern { .. } => card.controller == player_id && !card.tapped,
+            _ => card.controller == player_id,
+        };
+
+        let candidates: Vec<CardId> = if card_type.eq_ignore_ascii_case("CARDNAME") {
+            cards.iter().copied().filter(|&id| id == source_id).collect()
+        } else {
+            // "Hand" and "Random" are the whole hand
+            let selector = match card_type.as_str() {
+                "Hand" | "Random" => Selector::parse("Card"),
+                _ => Selector::parse(card_type),
+            };
+            let ctx = SelectorContext::new(player_id, Some(source_id));
+            cards
+                .iter()
+                .copied()
+                .filter(|&id| {
+                    self.cards
+                        .get(id)
+                        .is_ok_and(|card| is_candidate(card) && selector.matches_card(card, &ctx))
+                })
+                .collect()
+        };
+        let count = if card_type == "Hand" {
+            candidates.len()
+        } else {
+            *count as usize
+        };
+        Some((candidates, count))
+    }
+
+    /// The cards the player has to choose between for a part of a cost, and how many to choose
+    ///
+    /// None if there's no choice to make: the part doesn't choose cards, every
+    /// candidate is needed, there aren't enough, or the cards are chosen at random.
+    pub fn cost_choice(
+        &self,
+        player_id: PlayerId,
+        source_id: CardId,
+        part: &crate::core::Cost,
+    ) -> Option<(Vec<CardId>, usize)> {
+        let (candidates, count) = self.cost_candidates(player_id, source_id, part)?;
+        let random = matches!(part, crate::core::Cost::DiscardPattern { card_type, .. } if card_type == "Random");
+        (candidates.len() > count && !random).then_some((candidates, count))
+    }
+
+    /// Choose the cards paying a part of a cost, and check the choice
+    fn choose_cost_cards<F>(
+        &mut self,
+        player_id: PlayerId,
+        source_id: CardId,
+        part: &crate::core::Cost,
+        choose_cards: &mut F,
+    ) -> Result<SmallVec<[CardId; 8]>>
+    where
+        F: FnMut(&GameState, &crate::core::Cost, &[CardId], usize) -> SmallVec<[CardId; 8]>,
+    {
+        let (candidates, count) = self
+            .cost_candidates(player_id, source_id, part)
+            .ok_or_else(|| MtgError::InvalidAction(format!("Cost {part:?} doesn't choose cards")))?;
+        if candidates.len() < count {
+            return Err(MtgError::InvalidAction(format!(
+                "Not enough cards to pay {part:?} (need {count}, found {})",
+                candidates.len()
+            )));
+        }
+
+        let chosen: SmallVec<[CardId; 8]> = if self.cost_choice(player_id, source_id, part).is_some() {
+            choose_cards(self, part, &candidates, count)
+        } else if candidates.len() > count {
+            // Random discard
+            use rand::seq::SliceRandom;
+            let prev_word_pos = self.rng.get_mut().get_word_pos();
+            let chosen = candidates.choose_multiple(self.rng.get_mut(), count).copied().collect();
+            self.undo_log.log(crate::undo::GameAction::UseRng { prev_word_pos });
+            chosen
+        } else {
+            candidates.iter().copied().collect()
+        };
+
+        let distinct = chosen.iter().enumerate().all(|(i, id)| !chosen[..i].contains(id));
+        if chosen.len() != count || !distinct || !chosen.iter().all(|id| candidates.contains(id)) {
+            return Err(MtgError::InvalidAction(format!(
+                "Invalid choice of cards {chosen:?} to pay {part:?}"
+            )));
+        }
+        Ok(chosen)
+    }
+
+    /// Check whether a player could pay the non-mana parts of an ability's cost
+    ///
+    /// Mana is left to the [`ManaEngine`](crate::game::ManaEngine). A {T} or {Q}
+    /// cost can't be paid by a creature with summoning sickness (MTG Rules 302.6),
+    /// and life can be paid up to the player's life total (119.4).
+    pub fn can_pay_ability_cost(&self, player_id: PlayerId, card_id: CardId, cost: &crate::core::Cost) -> bool {
+        use crate::core::Cost;
+
+        let Ok(card) = self.cards.get(card_id) else {
+            return false;
+        };
+        cost.parts().iter().all(|part| match part {
+            Cost::Tap | Cost::TapAndMana(_) => !card.tapped && !self.has_summoning_sickness(card_id),
+            Cost::Untap => card.tapped && !self.has_summoning_sickness(card_id),
+            Cost::Mana(_) | Cost::AddCounter { .. } | Cost::Composite(_) => true,
+            Cost::PayLife { amount } => self.get_player(player_id).is_ok_and(|p| p.life >= *amount),
+            Cost::SubCounter { counter_type, amount } => card.get_counter(*counter_type) >= *amount,
+            Cost::Sacrifice { card_id: sac_id } => {
+                self.battlefield.contains(*sac_id) && self.cards.get(*sac_id).is_ok_and(|c| c.controller == player_id)
+            }
+            Cost::Discard { card_id: discard_id } => self
+                .get_player_zones(player_id)
+                .is_some_and(|zones| zones.hand.contains(*discard_id)),
+            _ => self
+                .cost_candidates(player_id, card_id, part)
+                .is_some_and(|(candidates, count)| candidates.len() >= count),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::core::{Card, ManaCost};
+    use crate::game::test_support::add_permanent;
     use crate::game::ZeroController;
     use crate::loader::CardDatabase;
     use std::path::PathBuf;
@@ -1811,6 +2450,7 @@ mod tests {
         let card_id = game.next_entity_id();
         let mut card = Card::new(card_id, "Mountain".to_string(), p1_id);
         card.types.push(CardType::Land);
+        card.subtypes.push("Mountain".into());
         game.cards.insert(card_id, card);
 
         // Add to hand
@@ -1839,6 +2479,7 @@ mod tests {
         let card_id = game.next_entity_id();
         let mut card = Card::new(card_id, "Mountain".to_string(), p1_id);
         card.types.push(CardType::Land);
+        card.subtypes.push("Mountain".into());
         game.cards.insert(card_id, card);
         game.battlefield.add(card_id);
 
@@ -1854,6 +2495,185 @@ mod tests {
         assert!(card.tapped);
     }
 
+    #[test]
+    fn test_tap_for_mana_uses_mana_abilities() {
+        use crate::core::{ActivatedAbility, Cost};
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players.first().unwrap().id;
+
+        // Llanowar Elves ({T}: Add {G}) that just entered the battlefield
+        let elf_id = game.next_entity_id();
+        let mut elf = Card::new(elf_id, "Llanowar Elves".to_string(), p1_id);
+        elf.types.push(CardType::Creature);
+        let add_green = Effect::AddMana {
+            player: PlayerId::new(0),
+            mana: ManaCost::from_string("G"),
+        };
+        elf.activated_abilities.push(ActivatedAbility::new(
+            Cost::Tap,
+            vec![add_green],
+            "Add {G}.".to_string(),
+            true,
+        ));
+        elf.turn_entered_battlefield = Some(game.turn.turn_number);
+        game.cards.insert(elf_id, elf);
+        game.battlefield.add(elf_id);
+
+        // Mana Confluence ({T}, Pay 1 life: Add one mana of any color)
+        let confluence_id = game.next_entity_id();
+        let mut confluence = Card::new(confluence_id, "Mana Confluence".to_string(), p1_id);
+        confluence.types.push(CardType::Land);
+        let add_any = Effect::AddManaOfChoice {
+            player: PlayerId::new(0),
+            colors: crate::core::WUBRG.to_vec(),
+            amount: 1,
+        };
+        confluence.activated_abilities.push(ActivatedAbility::new(
+            Cost::parse("T PayLife<1>").unwrap(),
+            vec![add_any],
+            "Add one mana of any color.".to_string(),
+            true,
+        ));
+        game.cards.insert(confluence_id, confluence);
+        game.battlefield.add(confluence_id);
+
+        // The elf is summoning sick
+        assert!(game.tap_for_mana(p1_id, elf_id).is_err());
+        assert!(!game.cards.get(elf_id).unwrap().tapped);
+
+        // Mana Confluence makes the color the cost needs and costs 1 life
+        game.tap_for_mana_for_cost(p1_id, confluence_id, &ManaCost::from_string("1U"))
+            .unwrap();
+        let player = game.get_player(p1_id).unwrap();
+        assert_eq!((player.mana_pool.blue, player.life), (1, 19));
+
+        // Next turn the elf can tap for mana
+        game.turn.turn_number += 1;
+        game.tap_for_mana(p1_id, elf_id).unwrap();
+        assert_eq!(game.get_player(p1_id).unwrap().mana_pool.green, 1);
+    }
+
+    #[test]
+    fn test_tap_mana_sources_for_cost_follows_plan() {
+        use crate::core::{ActivatedAbility, Color, Cost};
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players.first().unwrap().id;
+
+        let add_land = |game: &mut GameState, name: &str, colors: Vec<Color>| {
+            let land_id = game.next_entity_id();
+            let mut land = Card::new(land_id, name.to_string(), p1_id);
+            land.types.push(CardType::Land);
+            let effect = Effect::AddManaOfChoice {
+                player: PlayerId::new(0),
+                colors,
+                amount: 1,
+            };
+            land.activated_abilities.push(ActivatedAbility::new(
+                Cost::Tap,
+                vec![effect],
+                "Add one mana.".to_string(),
+                true,
+            ));
+            game.cards.insert(land_id, land);
+            game.battlefield.add(land_id);
+            land_id
+        };
+        let city_id = add_land(&mut game, "City of Brass", crate::core::WUBRG.to_vec());
+        let scrubland_id = add_land(&mut game, "Scrubland", vec![Color::White, Color::Black]);
+        let tundra_id = add_land(&mut game, "Tundra", vec![Color::White, Color::Blue]);
+
+        // {W}{U} is paid by the two duals, and City of Brass stays untapped
+        let sources = [city_id, scrubland_id, tundra_id];
+        let tapped = game
+            .tap_mana_sources_for_cost(p1_id, &sources, &ManaCost::from_string("WU"))
+            .unwrap();
+        assert_eq!(tapped.len(), 2);
+        assert!(!game.cards.get(city_id).unwrap().tapped);
+        let pool = &game.get_player(p1_id).unwrap().mana_pool;
+        assert_eq!((pool.white, pool.blue), (1, 1));
+        game.pay_mana_cost(p1_id, &ManaCost::from_string("WU")).unwrap();
+    }
+
+    #[test]
+    fn test_pay_ability_costs_that_choose_cards() {
+        use crate::core::Cost;
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players.first().unwrap().id;
+
+        let add_card = |game: &mut GameState, name: &str, card_type: CardType, zone: Zone| {
+            let card_id = game.next_entity_id();
+            let mut card = Card::new(card_id, name.to_string(), p1_id);
+            card.types.push(card_type);
+            game.cards.insert(card_id, card);
+            match zone {
+                Zone::Battlefield => game.battlefield.add(card_id),
+                Zone::Hand => game.get_player_zones_mut(p1_id).unwrap().hand.add(card_id),
+                _ => game.get_player_zones_mut(p1_id).unwrap().graveyard.add(card_id),
+            }
+            card_id
+        };
+        let drum = add_card(&mut game, "Springleaf Drum", CardType::Artifact, Zone::Battlefield);
+        let elf = add_card(&mut game, "Llanowar Elves", CardType::Creature, Zone::Battlefield);
+        let bear = add_card(&mut game, "Grizzly Bears", CardType::Creature, Zone::Battlefield);
+        let bolt = add_card(&mut game, "Lightning Bolt", CardType::Instant, Zone::Hand);
+        let dead_bear = add_card(&mut game, "Grizzly Bears", CardType::Creature, Zone::Graveyard);
+
+        // A creature that just entered can be tapped for tapXType, unlike for {T}
+        game.cards.get_mut(elf).unwrap().turn_entered_battlefield = Some(game.turn.turn_number);
+        let tap_creature = Cost::parse("T tapXType<1/Creature>").unwrap();
+        assert_eq!(
+            game.cost_choice(p1_id, drum, &tap_creature.parts()[0]),
+            Some((vec![elf, bear], 1))
+        );
+        assert!(game.can_pay_ability_cost(p1_id, drum, &tap_creature));
+        let mut asked = Vec::new();
+        game.pay_ability_cost_with_choices(p1_id, drum, &tap_creature, &mut |_, _, candidates, _| {
+            asked.push(candidates.to_vec());
+            smallvec::smallvec![elf]
+        })
+        .unwrap();
+        assert_eq!(asked, vec![vec![elf, bear]]);
+        assert!(game.cards.get(drum).unwrap().tapped && game.cards.get(elf).unwrap().tapped);
+        assert!(!game.can_pay_ability_cost(p1_id, drum, &tap_creature));
+
+        // A choice of cards that aren't candidates is rejected
+        let return_creature = Cost::parse("Return<1/Creature>").unwrap();
+        assert!(game
+            .pay_ability_cost_with_choices(p1_id, drum, &return_creature, &mut |_, _, _, _| smallvec::smallvec![
+                drum
+            ])
+            .is_err());
+        game.pay_ability_cost_with_choices(p1_id, drum, &return_creature, &mut |_, _, _, _| {
+            smallvec::smallvec![bear]
+        })
+        .unwrap();
+        assert!(game.get_player_zones(p1_id).unwrap().hand.contains(bear));
+
+        // Discard and exile from the graveyard; with one candidate there's no choice
+        let discard_creature = Cost::parse("Discard<1/Creature>").unwrap();
+        assert_eq!(game.cost_choice(p1_id, drum, &discard_creature), None);
+        game.pay_ability_cost(p1_id, drum, &discard_creature).unwrap();
+        assert!(game.get_player_zones(p1_id).unwrap().graveyard.contains(bear));
+        assert!(game.get_player_zones(p1_id).unwrap().hand.contains(bolt));
+
+        let exile_two = Cost::parse("ExileFromGrave<2/Creature>").unwrap();
+        assert!(game.can_pay_ability_cost(p1_id, drum, &exile_two));
+        game.pay_ability_cost(p1_id, drum, &exile_two).unwrap();
+        let exile = &game.get_player_zones(p1_id).unwrap().exile;
+        assert!(exile.contains(bear) && exile.contains(dead_bear));
+        assert!(!game.can_pay_ability_cost(p1_id, drum, &exile_two));
+
+        // Discarding the hand always works, even an empty one
+        let discard_hand = Cost::parse("Discard<1/Hand>").unwrap();
+        game.pay_ability_cost(p1_id, drum, &discard_hand).unwrap();
+        assert!(game.get_player_zones(p1_id).unwrap().hand.is_empty());
+        assert!(game.can_pay_ability_cost(p1_id, drum, &discard_hand));
+        assert!(!game.can_pay_ability_cost(p1_id, drum, &Cost::parse("Discard<1/Card>").unwrap()));
+    }
+
     #[test]
     fn test_deal_damage_to_player() {
         let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
@@ -1900,22 +2720,82 @@ mod tests {
 
         // Create a 2/2 creature on battlefield
         let card_id = game.next_card_id();
-        let mut card = Card::new(card_id, "Grizzly Bears".to_string(), p1_id);
+        let mut card = Card::new(card_id, "Grizzly Bears".to_string(), p1_id);
+        card.types.push(CardType::Creature);
+        card.power = Some(2);
+        card.toughness = Some(2);
+        game.cards.insert(card_id, card);
+        game.battlefield.add(card_id);
+
+        // Deal 2 damage (lethal once state-based actions are checked)
+        let result = game.deal_damage_to_creature(card_id, 2);
+        assert!(result.is_ok(), "deal_damage_to_creature failed: {result:?}");
+        assert_eq!(game.cards.get(card_id).unwrap().damage_marked, 2);
+        game.check_state_based_actions().unwrap();
+
+        // Check it's in graveyard
+        assert!(!game.battlefield.contains(card_id), "Card still on battlefield");
+        if let Some(zones) = game.get_player_zones(p1_id) {
+            assert!(zones.graveyard.contains(card_id), "Card not in graveyard");
+        }
+    }
+
+    #[test]
+    fn test_damage_accumulates_across_events() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players.first().unwrap().id;
+
+        // A 3/3 survives one Shock but not two
+        let card_id = game.next_card_id();
+        let mut card = Card::new(card_id, "Centaur Courser".to_string(), p1_id);
+        card.types.push(CardType::Creature);
+        card.power = Some(3);
+        card.toughness = Some(3);
+        game.cards.insert(card_id, card);
+        game.battlefield.add(card_id);
+
+        game.deal_damage_to_creature(card_id, 2).unwrap();
+        game.check_state_based_actions().unwrap();
+        assert!(game.battlefield.contains(card_id));
+
+        game.deal_damage_to_creature(card_id, 2).unwrap();
+        assert_eq!(game.cards.get(card_id).unwrap().damage_marked, 4);
+        game.check_state_based_actions().unwrap();
+        assert!(!game.battlefield.contains(card_id));
+        // A permanent leaving the battlefield loses its damage
+        assert_eq!(game.cards.get(card_id).unwrap().damage_marked, 0);
+
+        // Rewinding restores both the creature and its marked damage
+        while game.undo().unwrap() {}
+        assert!(game.battlefield.contains(card_id));
+        assert_eq!(game.cards.get(card_id).unwrap().damage_marked, 0);
+    }
+
+    #[test]
+    fn test_damage_removed_in_cleanup() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players.first().unwrap().id;
+
+        let card_id = game.next_card_id();
+        let mut card = Card::new(card_id, "Centaur Courser".to_string(), p1_id);
         card.types.push(CardType::Creature);
-        card.power = Some(2);
-        card.toughness = Some(2);
+        card.power = Some(3);
+        card.toughness = Some(3);
         game.cards.insert(card_id, card);
         game.battlefield.add(card_id);
 
-        // Deal 2 damage (should kill it)
-        let result = game.deal_damage_to_creature(card_id, 2);
-        assert!(result.is_ok(), "deal_damage_to_creature failed: {result:?}");
+        game.mark_damage(card_id, 2, true).unwrap();
+        assert!(game.cards.get(card_id).unwrap().damaged_by_deathtouch);
+        game.clear_all_damage().unwrap();
+        let card = game.cards.get(card_id).unwrap();
+        assert_eq!(card.damage_marked, 0);
+        assert!(!card.damaged_by_deathtouch);
 
-        // Check it's in graveyard
-        assert!(!game.battlefield.contains(card_id), "Card still on battlefield");
-        if let Some(zones) = game.get_player_zones(p1_id) {
-            assert!(zones.graveyard.contains(card_id), "Card not in graveyard");
-        }
+        // Undoing the cleanup puts the damage back
+        game.undo().unwrap();
+        let card = game.cards.get(card_id).unwrap();
+        assert_eq!(card.damage_marked, 2);
+        assert!(card.damaged_by_deathtouch);
     }
 
     #[test]
@@ -1996,6 +2876,294 @@ mod tests {
         assert!(game.stack.contains(spell_id));
     }
 
+    #[test]
+    fn test_cast_spell_paying_phyrexian_mana_with_life() {
+        use crate::core::{Color, ManaCost};
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players.first().unwrap().id;
+
+        // Dismember costs {1}{B/P}{B/P}
+        let spell_id = game.next_card_id();
+        let mut spell = Card::new(spell_id, "Dismember".to_string(), p1_id);
+        spell.types.push(CardType::Instant);
+        spell.mana_cost = ManaCost::from_string("1 B/P B/P");
+        game.cards.insert(spell_id, spell);
+        game.get_player_zones_mut(p1_id).unwrap().hand.add(spell_id);
+
+        // One black mana pays one symbol; the other costs 2 life
+        let player = game.get_player_mut(p1_id).unwrap();
+        player.mana_pool.add_color(Color::Black);
+        player.mana_pool.add_color(Color::Red);
+
+        game.cast_spell(p1_id, spell_id, vec![]).unwrap();
+        let player = game.get_player(p1_id).unwrap();
+        assert_eq!(player.mana_pool.total(), 0);
+        assert_eq!(player.life, 18);
+
+        // The life payment is undone with the cast
+        while game.undo().unwrap() {}
+        assert_eq!(game.get_player(p1_id).unwrap().life, 20);
+    }
+
+    /// Put a 2/2 creature onto the battlefield for targeting tests
+    fn add_bear(game: &mut GameState, owner: PlayerId) -> CardId {
+        let id = game.next_card_id();
+        let mut bear = Card::new(id, "Grizzly Bears".to_string(), owner);
+        bear.types.push(CardType::Creature);
+        bear.power = Some(2);
+        bear.toughness = Some(2);
+        game.cards.insert(id, bear);
+        game.battlefield.add(id);
+        id
+    }
+
+    #[test]
+    fn test_spell_targets_are_stored_at_cast_time() {
+        use crate::core::Color;
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+        let bear_id = add_bear(&mut game, p2_id);
+
+        let bolt_id = game.next_card_id();
+        let mut bolt = Card::new(bolt_id, "Lightning Bolt".to_string(), p1_id);
+        bolt.types.push(CardType::Instant);
+        bolt.mana_cost = ManaCost::from_string("R");
+        bolt.effects.push(Effect::DealDamage {
+            target: TargetRef::None,
+            amount: 3,
+        });
+        game.cards.insert(bolt_id, bolt);
+        game.get_player_zones_mut(p1_id).unwrap().hand.add(bolt_id);
+        game.get_player_mut(p1_id).unwrap().mana_pool.add_color(Color::Red);
+
+        game.cast_spell(p1_id, bolt_id, vec![bear_id]).unwrap();
+        assert_eq!(game.stack.spell_targets(bolt_id), Some(&[bear_id][..]));
+
+        game.resolve_spell(bolt_id).unwrap();
+        assert!(game.get_player_zones(p2_id).unwrap().graveyard.contains(bear_id));
+        assert!(game.stack.spell_targets(bolt_id).is_none());
+
+        // Rewinding past the resolution puts the spell back with its targets
+        while game.stack.spell_targets(bolt_id).is_none_or(|t| t.is_empty()) {
+            assert!(game.undo().unwrap());
+        }
+        assert_eq!(game.stack.spell_targets(bolt_id), Some(&[bear_id][..]));
+        assert!(game.battlefield.contains(bear_id));
+    }
+
+    #[test]
+    fn test_spell_countered_when_all_targets_illegal() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+        let bear_id = add_bear(&mut game, p2_id);
+
+        let spell_id = game.next_card_id();
+        let mut spell = Card::new(spell_id, "Murder".to_string(), p1_id);
+        spell.types.push(CardType::Instant);
+        spell.effects.push(Effect::DestroyPermanent { target: CardId::new(0) });
+        game.cards.insert(spell_id, spell);
+        game.stack.add_with_targets(spell_id, vec![bear_id]);
+
+        // The target gains hexproof in response
+        game.cards.get_mut(bear_id).unwrap().keywords.push(Keyword::Hexproof);
+        assert!(!game.is_legal_spell_target(spell_id, bear_id));
+
+        game.resolve_spell(spell_id).unwrap();
+        assert!(
+            game.battlefield.contains(bear_id),
+            "Countered spell must not destroy its target"
+        );
+        assert!(game.get_player_zones(p1_id).unwrap().graveyard.contains(spell_id));
+    }
+
+    #[test]
+    fn test_hexproof_protects_from_opponents_of_controller() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+
+        // P2's hexproof bear, stolen by P1
+        let bear_id = add_bear(&mut game, p2_id);
+        let bear = game.cards.get_mut(bear_id).unwrap();
+        bear.keywords.push(Keyword::Hexproof);
+        bear.controller = p1_id;
+
+        let add_spell = |game: &mut GameState, caster: PlayerId| {
+            let spell_id = game.next_card_id();
+            let mut spell = Card::new(spell_id, "Murder".to_string(), caster);
+            spell.types.push(CardType::Instant);
+            spell.effects.push(Effect::DestroyPermanent { target: CardId::new(0) });
+            game.cards.insert(spell_id, spell);
+            game.stack.add_with_targets(spell_id, vec![bear_id]);
+            spell_id
+        };
+        let thief_spell = add_spell(&mut game, p1_id);
+        let owner_spell = add_spell(&mut game, p2_id);
+
+        assert!(game.is_legal_spell_target(thief_spell, bear_id));
+        assert!(!game.is_legal_spell_target(owner_spell, bear_id));
+        assert!(!game.is_legal_target(p2_id, owner_spell, bear_id));
+    }
+
+    #[test]
+    fn test_spell_skips_illegal_targets_when_some_remain_legal() {
+        use crate::core::Color;
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+        let first_id = add_bear(&mut game, p2_id);
+        let second_id = add_bear(&mut game, p2_id);
+
+        let spell_id = game.next_card_id();
+        let mut spell = Card::new(spell_id, "Twin Bolt".to_string(), p1_id);
+        spell.types.push(CardType::Instant);
+        spell.colors.push(Color::Red);
+        spell.effects.push(Effect::DestroyPermanent { target: CardId::new(0) });
+        spell.effects.push(Effect::DestroyPermanent { target: CardId::new(0) });
+        game.cards.insert(spell_id, spell);
+        game.stack.add_with_targets(spell_id, vec![first_id, second_id]);
+
+        // The first target gains protection from red
+        game.cards
+            .get_mut(first_id)
+            .unwrap()
+            .keywords
+            .push(Keyword::ProtectionFromRed);
+
+        game.resolve_spell(spell_id).unwrap();
+        assert!(game.battlefield.contains(first_id));
+        assert!(!game.battlefield.contains(second_id));
+    }
+
+    #[test]
+    fn test_cast_x_spell_pays_and_deals_x() {
+        use crate::core::{Color, AMOUNT_X};
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+        let bear_id = add_bear(&mut game, p2_id);
+        game.cards.get_mut(bear_id).unwrap().toughness = Some(5);
+
+        let blaze_id = game.next_card_id();
+        let mut blaze = Card::new(blaze_id, "Blaze".to_string(), p1_id);
+        blaze.types.push(CardType::Sorcery);
+        blaze.mana_cost = ManaCost::from_string("XR");
+        blaze.effects.push(Effect::DealDamage {
+            target: TargetRef::None,
+            amount: AMOUNT_X,
+        });
+        game.cards.insert(blaze_id, blaze);
+        game.get_player_zones_mut(p1_id).unwrap().hand.add(blaze_id);
+        for color in [Color::Red, Color::Colorless, Color::Colorless, Color::Colorless] {
+            game.get_player_mut(p1_id).unwrap().mana_pool.add_color(color);
+        }
+
+        // X = 4 costs five mana, one more than is available
+        let choose_x = |x_value| {
+            move |_: &GameState, _: CardId| SpellChoices {
+                x_value,
+                ..Default::default()
+            }
+        };
+        let result = game.cast_spell_8_step(
+            p1_id,
+            blaze_id,
+            choose_x(4),
+            |_, _| vec![bear_id],
+            |_, _, _, _| Vec::new(),
+            |_, _| Vec::new(),
+        );
+        assert!(result.is_err());
+        assert!(game.get_player_zones(p1_id).unwrap().hand.contains(blaze_id));
+
+        for color in [Color::Red, Color::Colorless, Color::Colorless, Color::Colorless] {
+            game.get_player_mut(p1_id).unwrap().mana_pool.add_color(color);
+        }
+        game.cast_spell_8_step(
+            p1_id,
+            blaze_id,
+            choose_x(3),
+            |_, _| vec![bear_id],
+            |_, _, _, _| Vec::new(),
+            |_, _| Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(game.get_player(p1_id).unwrap().mana_pool.total(), 0);
+        assert_eq!(game.stack.spell_choices(blaze_id).unwrap().x_value, 3);
+
+        game.resolve_spell(blaze_id).unwrap();
+        assert_eq!(game.cards.get(bear_id).unwrap().damage_marked, 3);
+    }
+
+    #[test]
+    fn test_modal_spell_resolves_chosen_mode() {
+        use crate::core::SpellMode;
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+        let bear_id = add_bear(&mut game, p2_id);
+
+        let charm_id = game.next_card_id();
+        let mut charm = Card::new(charm_id, "Test Charm".to_string(), p1_id);
+        charm.types.push(CardType::Instant);
+        charm.modes = vec![
+            SpellMode {
+                description: "You gain 4 life.".to_string(),
+                effects: vec![Effect::GainLife {
+                    player: PlayerId::new(0),
+                    amount: 4,
+                }],
+                target_restriction: None,
+            },
+            SpellMode {
+                description: "Destroy target creature.".to_string(),
+                effects: vec![Effect::DestroyPermanent { target: CardId::new(0) }],
+                target_restriction: None,
+            },
+        ];
+        game.cards.insert(charm_id, charm);
+        game.get_player_zones_mut(p1_id).unwrap().hand.add(charm_id);
+
+        // Invalid mode choices return the spell to hand
+        let choose_modes = |modes: Vec<usize>| {
+            move |_: &GameState, _: CardId| SpellChoices {
+                modes: modes.clone(),
+                ..Default::default()
+            }
+        };
+        let result = game.cast_spell_8_step(
+            p1_id,
+            charm_id,
+            choose_modes(vec![0, 1]),
+            |_, _| Vec::new(),
+            |_, _, _, _| Vec::new(),
+            |_, _| Vec::new(),
+        );
+        assert!(result.is_err());
+        assert!(game.get_player_zones(p1_id).unwrap().hand.contains(charm_id));
+
+        // Only the destroy mode can target, so only it offers the bear
+        assert!(game.get_valid_targets_for_modes(charm_id, &[0]).unwrap().is_empty());
+        assert!(game
+            .get_valid_targets_for_modes(charm_id, &[1])
+            .unwrap()
+            .contains(&bear_id));
+
+        game.cast_spell_8_step(
+            p1_id,
+            charm_id,
+            choose_modes(vec![1]),
+            |_, _| vec![bear_id],
+            |_, _, _, _| Vec::new(),
+            |_, _| Vec::new(),
+        )
+        .unwrap();
+        game.resolve_spell(charm_id).unwrap();
+        assert!(!game.battlefield.contains(bear_id));
+        assert_eq!(game.get_player(p1_id).unwrap().life, 20);
+    }
+
     #[test]
     fn test_execute_damage_effect_to_player() {
         use crate::core::{Effect, TargetRef};
@@ -2070,7 +3238,7 @@ mod tests {
         game.stack.add(bolt_id);
 
         // Resolve the spell
-        assert!(game.resolve_spell(bolt_id, &[]).is_ok());
+        assert!(game.resolve_spell(bolt_id).is_ok());
 
         // Check damage was dealt
         let p2 = game.get_player(p2_id).unwrap();
@@ -2123,7 +3291,7 @@ mod tests {
 
         // Resolve the spell
         assert!(
-            game.resolve_spell(draw_spell_id, &[]).is_ok(),
+            game.resolve_spell(draw_spell_id).is_ok(),
             "Failed to resolve draw spell"
         );
 
@@ -2173,7 +3341,7 @@ mod tests {
         game.cards.insert(destroy_spell_id, destroy_spell);
 
         // Put it on the stack (simulating cast)
-        game.stack.add(destroy_spell_id);
+        game.stack.add_with_targets(destroy_spell_id, vec![target_creature_id]);
 
         // Check initial state
         assert!(
@@ -2183,7 +3351,7 @@ mod tests {
 
         // Resolve the spell with the target creature
         assert!(
-            game.resolve_spell(destroy_spell_id, &[target_creature_id]).is_ok(),
+            game.resolve_spell(destroy_spell_id).is_ok(),
             "Failed to resolve destroy spell"
         );
 
@@ -2238,7 +3406,7 @@ mod tests {
 
         // Resolve the spell
         assert!(
-            game.resolve_spell(gainlife_spell_id, &[]).is_ok(),
+            game.resolve_spell(gainlife_spell_id).is_ok(),
             "Failed to resolve gain life spell"
         );
 
@@ -2363,6 +3531,44 @@ mod tests {
         assert_eq!(p2.life, 15); // 20 - 5 = 15
     }
 
+    #[test]
+    fn test_combat_damage_to_attacked_planeswalker() {
+        use crate::game::zero_controller::ZeroController;
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+        let p2_id = game.players[1].id;
+
+        let attacker_id = game.next_card_id();
+        let mut attacker = Card::new(attacker_id, "Grizzly Bears".to_string(), p1_id);
+        attacker.types.push(CardType::Creature);
+        attacker.power = Some(2);
+        attacker.toughness = Some(2);
+        game.cards.insert(attacker_id, attacker);
+        game.battlefield.add(attacker_id);
+
+        let planeswalker_id = game.next_card_id();
+        let mut planeswalker = Card::new(planeswalker_id, "Ajani Goldmane".to_string(), p2_id);
+        planeswalker.types.push(CardType::Planeswalker);
+        planeswalker.loyalty = Some(4);
+        game.cards.insert(planeswalker_id, planeswalker);
+        game.get_player_zones_mut(p2_id).unwrap().hand.add(planeswalker_id);
+        game.move_card(planeswalker_id, Zone::Hand, Zone::Battlefield, p2_id)
+            .unwrap();
+
+        game.declare_attacker_at(p1_id, attacker_id, AttackTarget::Planeswalker(planeswalker_id))
+            .unwrap();
+
+        let mut controller1 = ZeroController::new(p1_id);
+        let mut controller2 = ZeroController::new(p2_id);
+        game.assign_combat_damage(&mut controller1, &mut controller2, false)
+            .unwrap();
+
+        // Damage goes to the planeswalker, not its controller
+        assert_eq!(game.get_player(p2_id).unwrap().life, 20);
+        assert_eq!(game.cards.get(planeswalker_id).unwrap().current_loyalty(), 2);
+    }
+
     #[test]
     fn test_combat_damage_blocked() {
         use crate::game::zero_controller::ZeroController;
@@ -3072,7 +4278,7 @@ mod tests {
 
         // Resolve the spell
         assert!(
-            game.resolve_spell(pump_spell_id, &[]).is_ok(),
+            game.resolve_spell(pump_spell_id).is_ok(),
             "Failed to resolve pump spell"
         );
 
@@ -3250,10 +4456,7 @@ mod tests {
         game.stack.add(tap_spell_id);
 
         // Resolve the spell
-        assert!(
-            game.resolve_spell(tap_spell_id, &[]).is_ok(),
-            "Failed to resolve tap spell"
-        );
+        assert!(game.resolve_spell(tap_spell_id).is_ok(), "Failed to resolve tap spell");
 
         // Check creature is tapped
         let creature_after = game.cards.get(creature_id).unwrap();
@@ -3280,6 +4483,7 @@ mod tests {
         let land_id = game.next_card_id();
         let mut land = Card::new(land_id, "Forest".to_string(), p1_id);
         land.types.push(CardType::Land);
+        land.subtypes.push("Forest".into());
         land.controller = p1_id;
         land.tapped = true; // Start tapped
         game.cards.insert(land_id, land);
@@ -3303,7 +4507,7 @@ mod tests {
 
         // Resolve the spell
         assert!(
-            game.resolve_spell(untap_spell_id, &[]).is_ok(),
+            game.resolve_spell(untap_spell_id).is_ok(),
             "Failed to resolve untap spell"
         );
 
@@ -4310,11 +5514,11 @@ mod tests {
         game.cards.insert(destroy_spell_id, destroy_spell);
 
         // Put it on the stack (simulating cast)
-        game.stack.add(destroy_spell_id);
+        game.stack.add_with_targets(destroy_spell_id, vec![normal_creature_id]);
 
         // Resolve the spell - explicitly target the normal creature
         // (controller would have chosen normal_creature_id, not hexproof one)
-        let result = game.resolve_spell(destroy_spell_id, &[normal_creature_id]);
+        let result = game.resolve_spell(destroy_spell_id);
         assert!(result.is_ok(), "Destroy spell should resolve successfully");
 
         // Check that the hexproof creature is still alive
@@ -4368,11 +5572,11 @@ mod tests {
         game.cards.insert(tap_spell_id, tap_spell);
 
         // Put spell on stack (simulating cast)
-        game.stack.add(tap_spell_id);
+        game.stack.add_with_targets(tap_spell_id, vec![normal_creature_id]);
 
         // Resolve the spell - explicitly target the normal creature
         // (controller would have chosen normal_creature_id, not hexproof one)
-        let result = game.resolve_spell(tap_spell_id, &[normal_creature_id]);
+        let result = game.resolve_spell(tap_spell_id);
         assert!(result.is_ok(), "Tap spell should resolve successfully");
 
         // Check that the hexproof creature is not tapped
@@ -4415,10 +5619,10 @@ mod tests {
         game.cards.insert(pump_spell_id, pump_spell);
 
         // Put spell on stack (simulating cast)
-        game.stack.add(pump_spell_id);
+        game.stack.add_with_targets(pump_spell_id, vec![hexproof_creature_id]);
 
         // Resolve the spell
-        let result = game.resolve_spell(pump_spell_id, &[hexproof_creature_id]);
+        let result = game.resolve_spell(pump_spell_id);
         assert!(
             result.is_ok(),
             "Pump spell on own hexproof creature should resolve successfully"
@@ -4470,7 +5674,7 @@ mod tests {
         game.stack.add(destroy_spell_id);
 
         // Resolve the spell - should succeed but do nothing (no valid targets)
-        let result = game.resolve_spell(destroy_spell_id, &[]);
+        let result = game.resolve_spell(destroy_spell_id);
         assert!(result.is_ok(), "Spell with no valid targets should still resolve");
 
         // Check that the hexproof creature is still alive
@@ -4570,7 +5774,7 @@ mod tests {
         game.stack.add(destroy_spell_id);
 
         // Resolve the spell
-        let result = game.resolve_spell(destroy_spell_id, &[]);
+        let result = game.resolve_spell(destroy_spell_id);
         assert!(result.is_ok(), "Destroy spell should resolve successfully");
 
         // Indestructible creature should still be alive
@@ -4742,9 +5946,9 @@ mod tests {
             .effects
             .push(Effect::DestroyPermanent { target: CardId::new(0) });
         game.cards.insert(destroy_spell_id, destroy_spell);
-        game.stack.add(destroy_spell_id);
+        game.stack.add_with_targets(destroy_spell_id, vec![normal_creature_id]);
 
-        let result = game.resolve_spell(destroy_spell_id, &[normal_creature_id]);
+        let result = game.resolve_spell(destroy_spell_id);
         assert!(result.is_ok(), "Destroy spell should resolve");
 
         // Shroud creature should still be alive
@@ -4799,9 +6003,9 @@ mod tests {
             toughness_bonus: 3,
         });
         game.cards.insert(pump_spell_id, pump_spell);
-        game.stack.add(pump_spell_id);
+        game.stack.add_with_targets(pump_spell_id, vec![normal_creature_id]);
 
-        let result = game.resolve_spell(pump_spell_id, &[normal_creature_id]);
+        let result = game.resolve_spell(pump_spell_id);
         assert!(result.is_ok(), "Pump spell should resolve");
 
         // Shroud creature should NOT have the pump
@@ -4854,9 +6058,9 @@ mod tests {
         tap_spell.mana_cost = ManaCost::from_string("2U");
         tap_spell.effects.push(Effect::TapPermanent { target: CardId::new(0) });
         game.cards.insert(tap_spell_id, tap_spell);
-        game.stack.add(tap_spell_id);
+        game.stack.add_with_targets(tap_spell_id, vec![normal_creature_id]);
 
-        let result = game.resolve_spell(tap_spell_id, &[normal_creature_id]);
+        let result = game.resolve_spell(tap_spell_id);
         assert!(result.is_ok(), "Tap spell should resolve");
 
         // Shroud creature should not be tapped
@@ -4967,7 +6171,7 @@ mod tests {
         assert!(game.stack.contains(counter_id));
 
         // Resolve counterspell (counters Lightning Bolt)
-        assert!(game.resolve_spell(counter_id, &[]).is_ok());
+        assert!(game.resolve_spell(counter_id).is_ok());
 
         // Verify counterspell is in graveyard
         if let Some(zones) = game.get_player_zones(p2_id) {
@@ -5019,10 +6223,10 @@ mod tests {
             target: crate::core::CardId::new(0),
         });
         game.cards.insert(counter_id, counterspell);
-        game.stack.add(counter_id);
+        game.stack.add_with_targets(counter_id, vec![bolt_id]);
 
         // Resolve counterspell - should automatically find and counter Lightning Bolt
-        assert!(game.resolve_spell(counter_id, &[bolt_id]).is_ok());
+        assert!(game.resolve_spell(counter_id).is_ok());
 
         // Verify Lightning Bolt was countered
         assert!(!game.stack.contains(bolt_id));
@@ -5075,7 +6279,12 @@ mod tests {
         game.stack.add(creature_id);
 
         // Resolve the creature spell (moves it to battlefield and triggers ETB)
-        assert!(game.resolve_spell(creature_id, &[]).is_ok());
+        assert!(game.resolve_spell(creature_id).is_ok());
+
+        // The ETB trigger waits to be put on the stack, then resolves
+        assert_eq!(game.pending_triggers.len(), 1);
+        game.put_triggers_on_stack(p1_id, &[0]).unwrap();
+        game.resolve_top_ability().unwrap();
 
         // Verify the creature is on the battlefield
         assert!(game.battlefield.contains(creature_id));
@@ -5116,7 +6325,7 @@ mod tests {
         kavu.triggers.push(Trigger::new(
             TriggerEvent::EntersBattlefield,
             vec![Effect::DealDamage {
-                target: TargetRef::None, // Will be filled to target an opponent's creature
+                target: TargetRef::None, // Chosen as the trigger is put on the stack
                 amount: 4,
             }],
             "When Flametongue Kavu enters, it deals 4 damage to target creature.".to_string(),
@@ -5128,7 +6337,15 @@ mod tests {
         game.stack.add(kavu_id);
 
         // Resolve the kavu spell (moves it to battlefield and triggers ETB)
-        assert!(game.resolve_spell(kavu_id, &[]).is_ok());
+        assert!(game.resolve_spell(kavu_id).is_ok());
+
+        // The ETB trigger waits to be put on the stack, targeting either creature, then resolves
+        assert_eq!(game.pending_triggers.len(), 1);
+        let valid_targets = game.get_valid_targets_for_trigger(&game.pending_triggers[0]);
+        assert_eq!(valid_targets.as_slice(), &[target_creature_id, kavu_id]);
+        game.target_pending_trigger(0, &[target_creature_id]).unwrap();
+        game.put_triggers_on_stack(p1_id, &[0]).unwrap();
+        game.resolve_top_ability().unwrap();
 
         // Verify the kavu is on the battlefield
         assert!(game.battlefield.contains(kavu_id));
@@ -5147,6 +6364,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_create_token_spell() {
+        use crate::core::{Effect, TokenDefinition};
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+
+        // "Create two 1/1 white Soldier creature tokens"
+        let spell_id = game.next_card_id();
+        let mut spell = Card::new(spell_id, "Raise the Alarm".to_string(), p1_id);
+        spell.types.push(CardType::Instant);
+        spell.effects.push(Effect::CreateToken {
+            owner: PlayerId::new(0), // Placeholder - filled with the caster
+            amount: 2,
+            token: TokenDefinition::from_script_name("w_1_1_soldier"),
+        });
+        game.cards.insert(spell_id, spell);
+        game.stack.add(spell_id);
+
+        let undo_start = game.undo_log.len();
+        game.resolve_spell(spell_id).unwrap();
+
+        let tokens: Vec<CardId> = game
+            .battlefield
+            .cards
+            .iter()
+            .copied()
+            .filter(|id| game.cards.get(*id).unwrap().is_token)
+            .collect();
+        assert_eq!(tokens.len(), 2);
+        for token_id in &tokens {
+            let token = game.cards.get(*token_id).unwrap();
+            assert_eq!(token.owner, p1_id);
+            assert_eq!(token.name.as_str(), "Soldier Token");
+            assert_eq!(token.current_power(), 1);
+        }
+
+        // Undoing the resolution removes the tokens entirely
+        while game.undo_log.len() > undo_start {
+            game.undo().unwrap();
+        }
+        assert!(game.battlefield.is_empty());
+        assert!(tokens.iter().all(|id| !game.cards.contains(*id)));
+    }
+
     #[test]
     fn test_elvish_visionary_from_cardsfolder() {
         // Test loading Elvish Visionary from the actual cardsfolder and verifying
@@ -5191,11 +6453,15 @@ mod tests {
         game.stack.add(creature_id);
 
         // Resolve the creature spell (moves it to battlefield and triggers ETB)
-        assert!(game.resolve_spell(creature_id, &[]).is_ok());
+        assert!(game.resolve_spell(creature_id).is_ok());
 
         // Verify the creature is on the battlefield
         assert!(game.battlefield.contains(creature_id));
 
+        // Put the ETB trigger on the stack and resolve it
+        game.put_triggers_on_stack(p1_id, &[0]).unwrap();
+        game.resolve_top_ability().unwrap();
+
         // Verify the ETB trigger drew a card
         if let Some(zones) = game.get_player_zones(p1_id) {
             assert_eq!(zones.hand.cards.len(), 1, "Should have drawn 1 card from ETB trigger");
@@ -5253,7 +6519,7 @@ mod tests {
 
         // Resolve Counterspell (should counter Lightning Bolt)
         assert!(
-            game.resolve_spell(counter_id, &[]).is_ok(),
+            game.resolve_spell(counter_id).is_ok(),
             "Counterspell should resolve successfully"
         );
 
@@ -5311,7 +6577,12 @@ mod tests {
 
         // Put the creature on the stack and resolve it
         game.stack.add(creature_id);
-        assert!(game.resolve_spell(creature_id, &[]).is_ok());
+        assert!(game.resolve_spell(creature_id).is_ok());
+
+        // The ETB trigger waits to be put on the stack, then resolves
+        assert_eq!(game.pending_triggers.len(), 1);
+        game.put_triggers_on_stack(p1_id, &[0]).unwrap();
+        game.resolve_top_ability().unwrap();
 
         // Verify the creature is on the battlefield
         assert!(game.battlefield.contains(creature_id));
@@ -5363,7 +6634,13 @@ mod tests {
 
         // Put the creature on the stack and resolve it
         game.stack.add(creature_id);
-        assert!(game.resolve_spell(creature_id, &[]).is_ok());
+        assert!(game.resolve_spell(creature_id).is_ok());
+
+        // The ETB trigger waits to be put on the stack with its target, then resolves
+        assert_eq!(game.pending_triggers.len(), 1);
+        game.target_pending_trigger(0, &[target_id]).unwrap();
+        game.put_triggers_on_stack(p1_id, &[0]).unwrap();
+        game.resolve_top_ability().unwrap();
 
         // Verify the creature is on the battlefield
         assert!(game.battlefield.contains(creature_id));
@@ -5373,4 +6650,400 @@ mod tests {
         assert_eq!(pumped_card.power_bonus, 2, "Target should have +2 power bonus");
         assert_eq!(pumped_card.toughness_bonus, 2, "Target should have +2 toughness bonus");
     }
+
+    #[test]
+    fn test_terror_respects_valid_targets_selector() {
+        use crate::core::{Color, Effect, Selector};
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+        let p2_id = game.players[1].id;
+
+        let add_creature = |game: &mut GameState, name: &str, color: Color, artifact: bool| {
+            let id = game.next_card_id();
+            let mut card = Card::new(id, name.to_string(), p2_id);
+            card.types.push(CardType::Creature);
+            if artifact {
+                card.types.push(CardType::Artifact);
+            }
+            card.colors.push(color);
+            card.power = Some(2);
+            card.toughness = Some(2);
+            game.cards.insert(id, card);
+            game.battlefield.add(id);
+            id
+        };
+        let bears = add_creature(&mut game, "Grizzly Bears", Color::Green, false);
+        let zombie = add_creature(&mut game, "Black Zombie", Color::Black, false);
+        let golem = add_creature(&mut game, "Golem", Color::Colorless, true);
+
+        // Terror: "Destroy target nonartifact, nonblack creature"
+        let terror_id = game.next_card_id();
+        let mut terror = Card::new(terror_id, "Terror".to_string(), p1_id);
+        terror.types.push(CardType::Instant);
+        terror.effects.push(Effect::DestroyPermanent { target: CardId::new(0) });
+        terror.target_restriction = Some(Selector::parse("Creature.nonArtifact+nonBlack"));
+        game.cards.insert(terror_id, terror);
+
+        let targets = game.get_valid_targets_for_spell(terror_id).unwrap();
+        assert_eq!(targets.as_slice(), &[bears]);
+        assert!(!targets.contains(&zombie));
+        assert!(!targets.contains(&golem));
+    }
+
+    #[test]
+    fn test_destroy_all_uses_selector() {
+        use crate::core::{Effect, Selector};
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+        let p2_id = game.players[1].id;
+
+        let mut ids = Vec::new();
+        for (owner, is_creature) in [(p1_id, true), (p2_id, true), (p2_id, false)] {
+            let id = game.next_card_id();
+            let mut card = Card::new(id, "Permanent".to_string(), owner);
+            card.types.push(if is_creature {
+                CardType::Creature
+            } else {
+                CardType::Land
+            });
+            game.cards.insert(id, card);
+            game.battlefield.add(id);
+            ids.push(id);
+        }
+
+        // "Destroy all creatures your opponents control"
+        game.execute_effect(&Effect::DestroyAll {
+            selector: Selector::parse("Creature.OppCtrl"),
+            controller: p1_id,
+        })
+        .unwrap();
+
+        assert!(game.battlefield.contains(ids[0]), "Own creature survives");
+        assert!(!game.battlefield.contains(ids[1]), "Opponent's creature is destroyed");
+        assert!(game.battlefield.contains(ids[2]), "Lands are not creatures");
+    }
+
+    #[test]
+    fn test_divided_damage_is_split_among_targets() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+        let first_id = add_bear(&mut game, p2_id);
+        let second_id = add_bear(&mut game, p2_id);
+        game.cards.get_mut(second_id).unwrap().toughness = Some(5);
+
+        let arc_id = game.next_card_id();
+        let mut arc = Card::new(arc_id, "Arc Lightning".to_string(), p1_id);
+        arc.types.push(CardType::Sorcery);
+        arc.max_targets = 3;
+        arc.divided_as_you_choose = true;
+        arc.effects.push(Effect::DealDamage {
+            target: TargetRef::None,
+            amount: 3,
+        });
+        game.cards.insert(arc_id, arc);
+        game.get_player_zones_mut(p1_id).unwrap().hand.add(arc_id);
+
+        // Every target must get at least 1, and the shares must add up to 3
+        let targets = vec![first_id, second_id];
+        for bad_division in [vec![3, 0], vec![1, 1], vec![3]] {
+            let result = game.cast_spell_8_step(
+                p1_id,
+                arc_id,
+                |_, _| SpellChoices::default(),
+                |_, _| targets.clone(),
+                |_, _, _, _| bad_division.clone(),
+                |_, _| Vec::new(),
+            );
+            assert!(result.is_err());
+            assert!(game.get_player_zones(p1_id).unwrap().hand.contains(arc_id));
+        }
+
+        game.cast_spell_8_step(
+            p1_id,
+            arc_id,
+            |_, _| SpellChoices::default(),
+            |_, _| targets.clone(),
+            |_, _, targets, total| {
+                assert_eq!((targets.len(), total), (2, 3));
+                vec![2, 1]
+            },
+            |_, _| Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(game.stack.spell_choices(arc_id).unwrap().division, vec![2, 1]);
+
+        game.resolve_spell(arc_id).unwrap();
+        assert!(game.get_player_zones(p2_id).unwrap().graveyard.contains(first_id));
+        assert_eq!(game.cards.get(second_id).unwrap().damage_marked, 1);
+    }
+
+    #[test]
+    fn test_divided_damage_can_go_to_a_creature_and_a_player() {
+        use crate::core::Selector;
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+        let bear_id = add_bear(&mut game, p2_id);
+
+        // Forked Bolt: "deals 2 damage divided as you choose among one or two targets"
+        let bolt_id = game.next_card_id();
+        let mut bolt = Card::new(bolt_id, "Forked Bolt".to_string(), p1_id);
+        bolt.types.push(CardType::Sorcery);
+        bolt.max_targets = 2;
+        bolt.divided_as_you_choose = true;
+        bolt.target_restriction = Some(Selector::parse("Any"));
+        bolt.effects.push(Effect::DealDamage {
+            target: TargetRef::None,
+            amount: 2,
+        });
+        game.cards.insert(bolt_id, bolt);
+        game.get_player_zones_mut(p1_id).unwrap().hand.add(bolt_id);
+
+        // Both players are valid targets, under their own IDs
+        let p2_target = CardId::new(p2_id.as_u32());
+        let valid_targets = game.get_valid_targets_for_spell(bolt_id).unwrap();
+        assert!(valid_targets.contains(&bear_id));
+        assert!(valid_targets.contains(&p2_target));
+        assert!(valid_targets.contains(&CardId::new(p1_id.as_u32())));
+
+        game.cast_spell_8_step(
+            p1_id,
+            bolt_id,
+            |_, _| SpellChoices::default(),
+            |_, _| vec![bear_id, p2_target],
+            |_, _, _, _| vec![1, 1],
+            |_, _| Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            game.spell_effects(bolt_id).unwrap(),
+            vec![
+                Effect::DealDamage {
+                    target: TargetRef::Permanent(bear_id),
+                    amount: 1,
+                },
+                Effect::DealDamage {
+                    target: TargetRef::Player(p2_id),
+                    amount: 1,
+                },
+            ]
+        );
+
+        game.resolve_spell(bolt_id).unwrap();
+        assert_eq!(game.cards.get(bear_id).unwrap().damage_marked, 1);
+        assert_eq!(game.get_player(p2_id).unwrap().life, 19);
+    }
+
+    #[test]
+    fn test_fireball_divides_damage_evenly_among_any_number_of_targets() {
+        use crate::core::{ManaCost, COUNT_ANY};
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+        let first_id = add_bear(&mut game, p2_id);
+        let second_id = add_bear(&mut game, p2_id);
+
+        // Fireball: "deals X damage divided evenly, rounded down, among any number of targets"
+        let fireball_id = game.next_card_id();
+        let mut fireball = Card::new(fireball_id, "Fireball".to_string(), p1_id);
+        fireball.types.push(CardType::Sorcery);
+        fireball.mana_cost = ManaCost::from_string("XR");
+        fireball.max_targets = COUNT_ANY;
+        fireball.divided_evenly = true;
+        fireball.effects.push(Effect::DealDamage {
+            target: TargetRef::None,
+            amount: crate::core::AMOUNT_X,
+        });
+        fireball.extra_target_cost = 1;
+        game.cards.insert(fireball_id, fireball);
+        game.get_player_zones_mut(p1_id).unwrap().hand.add(fireball_id);
+        game.get_player_mut(p1_id).unwrap().mana_pool.red = 7;
+
+        // Any number of targets: as many as there are
+        assert_eq!(game.cards.get(fireball_id).unwrap().target_count(5).1, u8::MAX);
+
+        // X = 5 and three targets cost {5}{R} plus {2} for the two extra targets
+        let targets = vec![first_id, second_id, CardId::new(p2_id.as_u32())];
+        let result = game.cast_spell_8_step(
+            p1_id,
+            fireball_id,
+            |_, _| SpellChoices {
+                x_value: 5,
+                ..Default::default()
+            },
+            |_, _| targets.clone(),
+            |_, _, _, _| panic!("an even division isn't chosen"),
+            |_, _| Vec::new(),
+        );
+        assert!(result.is_err());
+        assert!(game.get_player_zones(p1_id).unwrap().hand.contains(fireball_id));
+
+        game.get_player_mut(p1_id).unwrap().mana_pool.red = 8;
+        game.cast_spell_8_step(
+            p1_id,
+            fireball_id,
+            |_, _| SpellChoices {
+                x_value: 5,
+                ..Default::default()
+            },
+            |_, _| targets.clone(),
+            |_, _, _, _| panic!("an even division isn't chosen"),
+            |_, _| Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(game.stack.spell_choices(fireball_id).unwrap().division, vec![1, 1, 1]);
+        assert_eq!(game.get_player(p1_id).unwrap().mana_pool.red, 0);
+
+        game.resolve_spell(fireball_id).unwrap();
+        assert_eq!(game.cards.get(first_id).unwrap().damage_marked, 1);
+        assert_eq!(game.cards.get(second_id).unwrap().damage_marked, 1);
+        assert_eq!(game.get_player(p2_id).unwrap().life, 19);
+    }
+
+    #[test]
+    fn test_spell_cast_for_its_alternative_cost() {
+        use crate::core::Cost;
+        use crate::game::test_support::add_to_hand;
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1_id, p2_id) = (game.players[0].id, game.players[1].id);
+        let mountains: Vec<CardId> = (0..3)
+            .map(|_| {
+                add_permanent(&mut game, p1_id, "Mountain", |card| {
+                    card.types.push(CardType::Land);
+                    card.subtypes.push("Mountain".into());
+                })
+            })
+            .collect();
+        let bear_id = add_bear(&mut game, p2_id);
+
+        // Fireblast: "You may sacrifice two Mountains rather than pay this spell's mana cost."
+        let fireblast_id = add_to_hand(&mut game, p1_id, "Fireblast", |card| {
+            card.types.push(CardType::Instant);
+            card.mana_cost = ManaCost::from_string("4RR");
+            card.alternative_cost = Cost::parse("Sac<2/Mountain>");
+            card.effects.push(Effect::DealDamage {
+                target: TargetRef::None,
+                amount: 4,
+            });
+        });
+
+        game.cast_spell_8_step_with_cost_choices(
+            p1_id,
+            fireblast_id,
+            |_, _| SpellChoices {
+                alternative_cost: true,
+                ..Default::default()
+            },
+            |_, _| vec![bear_id],
+            |_, _, _, _| Vec::new(),
+            |_, cost| {
+                assert_eq!(cost.cmc(), 0, "no mana is paid");
+                Vec::new()
+            },
+            |_, part, candidates, count| {
+                assert_eq!(
+                    (part, candidates, count),
+                    (&Cost::parse("Sac<2/Mountain>").unwrap(), &mountains[..], 2)
+                );
+                candidates[1..].iter().copied().collect()
+            },
+        )
+        .unwrap();
+        assert!(game.stack.spell_choices(fireblast_id).unwrap().alternative_cost);
+        let graveyard = &game.get_player_zones(p1_id).unwrap().graveyard;
+        assert!(game.battlefield.contains(mountains[0]));
+        assert!(mountains[1..].iter().all(|&id| graveyard.contains(id)));
+
+        game.resolve_spell(fireblast_id).unwrap();
+        assert!(game.get_player_zones(p2_id).unwrap().graveyard.contains(bear_id));
+    }
+
+    #[test]
+    fn test_alternative_cost_that_cant_be_paid() {
+        use crate::core::Cost;
+        use crate::game::test_support::add_to_hand;
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+        let fireblast_id = add_to_hand(&mut game, p1_id, "Fireblast", |card| {
+            card.types.push(CardType::Instant);
+            card.mana_cost = ManaCost::from_string("4RR");
+            card.alternative_cost = Cost::parse("Sac<2/Mountain>");
+        });
+
+        // No Mountains to sacrifice: the spell goes back to hand
+        let result = game.cast_spell_8_step(
+            p1_id,
+            fireblast_id,
+            |_, _| SpellChoices {
+                alternative_cost: true,
+                ..Default::default()
+            },
+            |_, _| Vec::new(),
+            |_, _, _, _| Vec::new(),
+            |_, _| Vec::new(),
+        );
+        assert!(result.is_err());
+        assert!(game.get_player_zones(p1_id).unwrap().hand.contains(fireblast_id));
+    }
+
+    #[test]
+    fn test_aura_spell_enters_attached_to_its_target() {
+        use crate::core::{ContinuousModification, Keyword, Selector, StaticAbility, Subtype};
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+        let bear_id = add_bear(&mut game, p1_id);
+        let land_id = game.next_card_id();
+        let mut land = Card::new(land_id, "Forest".to_string(), p1_id);
+        land.types.push(CardType::Land);
+        land.subtypes.push("Forest".into());
+        game.cards.insert(land_id, land);
+        game.battlefield.add(land_id);
+
+        // Holy Strength: "Enchant creature. Enchanted creature gets +1/+2."
+        let aura_id = game.next_card_id();
+        let mut aura = Card::new(aura_id, "Holy Strength".to_string(), p1_id);
+        aura.types.push(CardType::Enchantment);
+        aura.subtypes.push(Subtype::new("Aura"));
+        aura.keywords.push(Keyword::Enchant("Creature".to_string()));
+        aura.effects.push(Effect::Attach {
+            attachment: CardId::new(0),
+            target: CardId::new(0),
+        });
+        aura.static_abilities.push(StaticAbility {
+            affected: Selector::parse("Creature.EnchantedBy"),
+            modifications: vec![ContinuousModification::AddPower(1)],
+            description: String::new(),
+        });
+        game.cards.insert(aura_id, aura);
+        game.get_player_zones_mut(p1_id).unwrap().hand.add(aura_id);
+
+        let targets = game.get_valid_targets_for_modes(aura_id, &[]).unwrap();
+        assert_eq!(targets.as_slice(), &[bear_id], "Enchant creature can't target a land");
+
+        game.cast_spell_8_step(
+            p1_id,
+            aura_id,
+            |_, _| SpellChoices::default(),
+            |_, _| vec![bear_id],
+            |_, _, _, _| Vec::new(),
+            |_, _| Vec::new(),
+        )
+        .unwrap();
+        game.resolve_spell(aura_id).unwrap();
+
+        assert!(game.battlefield.contains(aura_id));
+        assert_eq!(game.cards.get(aura_id).unwrap().attached_to, Some(bear_id));
+        assert_eq!(game.cards.get(bear_id).unwrap().current_power(), 3);
+
+        // The Aura leaving the battlefield takes its bonus with it
+        game.move_card(aura_id, Zone::Battlefield, Zone::Graveyard, p1_id)
+            .unwrap();
+        assert_eq!(game.cards.get(aura_id).unwrap().attached_to, None);
+        assert_eq!(game.cards.get(bear_id).unwrap().current_power(), 2);
+    }
 }
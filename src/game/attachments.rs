@@ -0,0 +1,192 @@
+//! Auras and Equipment (MTG Rules 301.5, 303.4)
+//!
+//! An Aura or Equipment on the battlefield records the permanent it's attached
+//! to in `Card::attached_to`. Aura spells target what they'll enchant and enter
+//! the battlefield attached to it; Equipment is attached by its equip ability.
+//! Static abilities of the attached card reach the permanent it's attached to
+//! through the `EnchantedBy`/`EquippedBy` selector properties.
+//!
+//! Attachments that become illegal are handled by state-based actions: an Aura
+//! goes to its owner's graveyard (704.5m) and Equipment becomes unattached but
+//! stays on the battlefield (704.5n).
+
+use crate::core::{Card, CardId};
+use crate::game::GameState;
+use crate::Result;
+
+impl GameState {
+    /// Could `attachment` be attached to `target` right now?
+    ///
+    /// The target has to be another permanent on the battlefield. An Aura's
+    /// target has to match its `Enchant` restriction (any permanent without one),
+    /// Equipment can only equip creatures, and neither can be attached to a
+    /// permanent with protection from one of its colors (702.16c-d).
+    pub fn can_attach(&self, attachment: CardId, target: CardId) -> bool {
+        if attachment == target || !self.battlefield.contains(target) {
+            return false;
+        }
+        let (Ok(attachment), Ok(target)) = (self.cards.get(attachment), self.cards.get(target)) else {
+            return false;
+        };
+        let allowed = if attachment.is_aura() {
+            attachment.enchant_restriction().is_none_or(|restriction| {
+                let ctx = crate::core::SelectorContext::new(attachment.controller, Some(attachment.id));
+                restriction.matches_card(target, &ctx)
+            })
+        } else if attachment.is_equipment() {
+            target.is_creature()
+        } else {
+            false
+        };
+        allowed && !Self::protected_from(target, attachment)
+    }
+
+    /// Does `target` have protection from one of `attachment`'s colors?
+    fn protected_from(target: &Card, attachment: &Card) -> bool {
+        attachment
+            .current_colors()
+            .iter()
+            .any(|&color| target.has_protection_from(color))
+    }
+
+    /// Attach `attachment` to `target` (MTG Rules 701.3)
+    ///
+    /// Nothing happens if it can't legally be attached to the target, or is
+    /// already attached to it (701.3b). Returns whether it was attached.
+    pub fn attach(&mut self, attachment: CardId, target: CardId) -> Result<bool> {
+        if !self.can_attach(attachment, target) {
+            return Ok(false);
+        }
+        let previous = self.cards.get(attachment)?.attached_to;
+        if previous == Some(target) {
+            return Ok(false);
+        }
+
+        self.set_attached_to(attachment, Some(target))?;
+        let (attachment_name, target_name) = (
+            self.cards.get(attachment)?.name.to_string(),
+            self.cards.get(target)?.name.to_string(),
+        );
+        self.logger.normal(&format!(
+            "{attachment_name} ({attachment}) is attached to {target_name} ({target})"
+        ));
+        Ok(true)
+    }
+
+    /// Unattach `attachment` from whatever it's attached to
+    pub fn detach(&mut self, attachment: CardId) -> Result<()> {
+        if self.cards.get(attachment)?.attached_to.is_some() {
+            self.set_attached_to(attachment, None)?;
+        }
+        Ok(())
+    }
+
+    /// The Auras and Equipment attached to `card_id`, in battlefield order
+    pub fn attachments(&self, card_id: CardId) -> Vec<CardId> {
+        self.battlefield
+            .cards
+            .iter()
+            .copied()
+            .filter(|&id| self.cards.get(id).is_ok_and(|card| card.attached_to == Some(card_id)))
+            .collect()
+    }
+
+    /// Change what a card is attached to, logging the change for undo
+    fn set_attached_to(&mut self, card_id: CardId, attached_to: Option<CardId>) -> Result<()> {
+        let card = self.cards.get_mut(card_id)?;
+        let previous = std::mem::replace(&mut card.attached_to, attached_to);
+        self.undo_log.log(crate::undo::GameAction::SetAttachedTo {
+            card_id,
+            attached_to,
+            previous,
+        });
+        // Static abilities of the attachment now affect a different permanent
+        self.apply_continuous_effects();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{Card, CardId, CardType, Color, ContinuousModification, Keyword, PlayerId, Selector};
+    use crate::core::{StaticAbility, Subtype};
+    use crate::game::GameState;
+    use crate::zones::Zone;
+
+    fn add_permanent(game: &mut GameState, owner: PlayerId, name: &str, setup: impl FnOnce(&mut Card)) -> CardId {
+        let id = game.next_card_id();
+        let mut card = Card::new(id, name.to_string(), owner);
+        setup(&mut card);
+        game.cards.insert(id, card);
+        game.get_player_zones_mut(owner).unwrap().hand.add(id);
+        game.move_card(id, Zone::Hand, Zone::Battlefield, owner).unwrap();
+        id
+    }
+
+    fn creature(card: &mut Card) {
+        card.types.push(CardType::Creature);
+        card.power = Some(2);
+        card.toughness = Some(2);
+    }
+
+    fn bonus(affected: &str, power: i32) -> StaticAbility {
+        StaticAbility {
+            affected: Selector::parse(affected),
+            modifications: vec![ContinuousModification::AddPower(power)],
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_equipment_moves_its_bonus() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let bear = add_permanent(&mut game, alice, "Bear", creature);
+        let wolf = add_permanent(&mut game, alice, "Wolf", creature);
+        let land = add_permanent(&mut game, alice, "Forest", |card| card.types.push(CardType::Land));
+        let sword = add_permanent(&mut game, alice, "Short Sword", |card| {
+            card.types.push(CardType::Artifact);
+            card.subtypes.push(Subtype::new("Equipment"));
+            card.static_abilities.push(bonus("Creature.EquippedBy", 1));
+        });
+
+        // Equipment only equips creatures
+        assert!(!game.attach(sword, land).unwrap());
+        assert!(game.attach(sword, bear).unwrap());
+        assert_eq!(game.cards.get(bear).unwrap().current_power(), 3);
+        assert_eq!(game.attachments(bear), vec![sword]);
+
+        // Re-equipping moves the bonus, and attaching it where it already is does nothing
+        assert!(game.attach(sword, wolf).unwrap());
+        assert!(!game.attach(sword, wolf).unwrap());
+        assert_eq!(game.cards.get(bear).unwrap().current_power(), 2);
+        assert_eq!(game.cards.get(wolf).unwrap().current_power(), 3);
+
+        game.undo().unwrap();
+        assert_eq!(game.cards.get(sword).unwrap().attached_to, Some(bear));
+        assert_eq!(game.cards.get(bear).unwrap().current_power(), 3);
+    }
+
+    #[test]
+    fn test_enchant_restriction_and_protection() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let ours = add_permanent(&mut game, alice, "Bear", creature);
+        let theirs = add_permanent(&mut game, bob, "Knight", |card| {
+            creature(card);
+            card.keywords.push(Keyword::ProtectionFromWhite);
+        });
+        let land = add_permanent(&mut game, bob, "Plains", |card| card.types.push(CardType::Land));
+        let aura = add_permanent(&mut game, alice, "Pacifism", |card| {
+            card.types.push(CardType::Enchantment);
+            card.subtypes.push(Subtype::new("Aura"));
+            card.colors.push(Color::White);
+            card.keywords.push(Keyword::Enchant("Creature".to_string()));
+        });
+
+        assert!(game.can_attach(aura, ours));
+        assert!(!game.can_attach(aura, land), "Enchant creature");
+        assert!(!game.can_attach(aura, theirs), "Protection from white");
+        assert!(!game.can_attach(aura, aura));
+    }
+}
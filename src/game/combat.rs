@@ -3,10 +3,22 @@
 //! Handles declaring attackers, declaring blockers, and combat damage
 
 use crate::core::{CardId, PlayerId};
+use crate::game::GameState;
+use crate::undo::GameAction;
+use crate::Result;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::collections::BTreeMap;
 
+/// What an attacking creature attacks (MTG Rules 508.1b)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AttackTarget {
+    /// A player
+    Player(PlayerId),
+    /// A planeswalker; its controller is the defending player
+    Planeswalker(CardId),
+}
+
 /// Combat state for the current combat phase
 ///
 /// This tracks all combat-related information during a combat phase.
@@ -15,9 +27,14 @@ use std::collections::BTreeMap;
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CombatState {
     /// Creatures that are attacking this combat
-    /// Maps attacker ID to the player/planeswalker being attacked
+    /// Maps attacker ID to the defending player (the player attacked, or the
+    /// controller of the planeswalker attacked)
     pub attackers: BTreeMap<CardId, PlayerId>,
 
+    /// Attackers that attack a planeswalker rather than a player
+    /// Maps attacker ID to the planeswalker being attacked
+    pub attacked_planeswalkers: BTreeMap<CardId, CardId>,
+
     /// Creatures that are blocking
     /// Maps blocker ID to the list of attackers it's blocking
     pub blockers: BTreeMap<CardId, SmallVec<[CardId; 2]>>,
@@ -42,6 +59,17 @@ impl CombatState {
         self.combat_active = true;
     }
 
+    /// Declare a creature as attacking a planeswalker controlled by `defending_player`
+    pub fn declare_attacker_at_planeswalker(
+        &mut self,
+        attacker: CardId,
+        planeswalker: CardId,
+        defending_player: PlayerId,
+    ) {
+        self.declare_attacker(attacker, defending_player);
+        self.attacked_planeswalkers.insert(attacker, planeswalker);
+    }
+
     /// Declare a creature as a blocker
     pub fn declare_blocker(&mut self, blocker: CardId, attackers: SmallVec<[CardId; 2]>) {
         // Add blocker -> attackers mapping
@@ -53,6 +81,27 @@ impl CombatState {
         }
     }
 
+    /// Take back a declared attacker (rewinding `declare_attacker`)
+    pub(crate) fn undeclare_attacker(&mut self, attacker: CardId, prev_combat_active: bool) {
+        self.attackers.remove(&attacker);
+        self.attacked_planeswalkers.remove(&attacker);
+        self.combat_active = prev_combat_active;
+    }
+
+    /// Take back a declared blocker (rewinding `declare_blocker`)
+    pub(crate) fn undeclare_blocker(&mut self, blocker: CardId) {
+        for attacker in self.blockers.remove(&blocker).unwrap_or_default() {
+            if let Some(blockers) = self.attacker_blockers.get_mut(&attacker) {
+                if let Some(pos) = blockers.iter().rposition(|&id| id == blocker) {
+                    blockers.remove(pos);
+                }
+                if blockers.is_empty() {
+                    self.attacker_blockers.remove(&attacker);
+                }
+            }
+        }
+    }
+
     /// Check if a creature is attacking
     pub fn is_attacking(&self, card_id: CardId) -> bool {
         self.attackers.contains_key(&card_id)
@@ -80,6 +129,14 @@ impl CombatState {
         self.attackers.get(&attacker).copied()
     }
 
+    /// Get what a creature is attacking: a player or a planeswalker
+    pub fn get_attack_target(&self, attacker: CardId) -> Option<AttackTarget> {
+        match self.attacked_planeswalkers.get(&attacker) {
+            Some(&planeswalker) => Some(AttackTarget::Planeswalker(planeswalker)),
+            None => self.get_defending_player(attacker).map(AttackTarget::Player),
+        }
+    }
+
     /// Get all attacking creatures (returns Vec for compatibility)
     pub fn get_attackers(&self) -> Vec<CardId> {
         self.attackers.keys().copied().collect()
@@ -108,12 +165,69 @@ impl CombatState {
     /// Clear all combat state (called at end of combat)
     pub fn clear(&mut self) {
         self.attackers.clear();
+        self.attacked_planeswalkers.clear();
         self.blockers.clear();
         self.attacker_blockers.clear();
         self.combat_active = false;
     }
 }
 
+impl GameState {
+    /// Declare an attacker in the combat state and log it
+    pub(crate) fn add_attacker(&mut self, attacker: CardId, target: AttackTarget) -> Result<()> {
+        let prev_combat_active = self.combat.combat_active;
+        let (defending_player, planeswalker) = match target {
+            AttackTarget::Player(defending_player) => {
+                self.combat.declare_attacker(attacker, defending_player);
+                (defending_player, None)
+            }
+            AttackTarget::Planeswalker(planeswalker) => {
+                let defending_player = self.cards.get(planeswalker)?.controller;
+                self.combat
+                    .declare_attacker_at_planeswalker(attacker, planeswalker, defending_player);
+                (defending_player, Some(planeswalker))
+            }
+        };
+        self.undo_log.log(GameAction::DeclareAttacker {
+            card_id: attacker,
+            defending_player,
+            planeswalker,
+            prev_combat_active,
+        });
+        Ok(())
+    }
+
+    /// Declare a blocker in the combat state and log it
+    pub(crate) fn add_blocker(&mut self, blocker: CardId, attackers: SmallVec<[CardId; 2]>) {
+        if self.combat.is_blocking(blocker) {
+            // Blocking again replaces the earlier block
+            let previous = self.combat.clone();
+            self.combat.undeclare_blocker(blocker);
+            self.undo_log.log(GameAction::SetCombat {
+                previous: Box::new(previous),
+                combat: Box::new(self.combat.clone()),
+            });
+        }
+        self.combat.declare_blocker(blocker, attackers.clone());
+        self.undo_log.log(GameAction::DeclareBlocker {
+            card_id: blocker,
+            attackers,
+        });
+    }
+
+    /// End combat: clear the combat state and log what it was
+    pub(crate) fn end_combat(&mut self) {
+        let combat = &self.combat;
+        if combat.combat_active || combat.has_attackers() || !combat.blockers.is_empty() {
+            let previous = std::mem::take(&mut self.combat);
+            self.undo_log.log(GameAction::SetCombat {
+                previous: Box::new(previous),
+                combat: Box::default(),
+            });
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,6 +285,24 @@ mod tests {
         assert_eq!(combat.attackers.len(), 0);
     }
 
+    #[test]
+    fn test_attack_planeswalker() {
+        let mut combat = CombatState::new();
+        let (attacker, planeswalker) = (CardId::new(1), CardId::new(2));
+        let defender = PlayerId::new(3);
+
+        combat.declare_attacker_at_planeswalker(attacker, planeswalker, defender);
+
+        assert_eq!(combat.get_defending_player(attacker), Some(defender));
+        assert_eq!(
+            combat.get_attack_target(attacker),
+            Some(AttackTarget::Planeswalker(planeswalker))
+        );
+
+        combat.clear();
+        assert_eq!(combat.get_attack_target(attacker), None);
+    }
+
     #[test]
     fn test_unblocked_attacker() {
         let mut combat = CombatState::new();
@@ -0,0 +1,247 @@
+//! Commander (MTG Rules 903)
+//!
+//! Each player's commander starts the game in the command zone and can be cast
+//! from there, for an additional {2} for each previous time it was cast from
+//! there (commander tax, 903.8). If a commander would be put into its owner's
+//! hand, library, graveyard or exile, it's put into the command zone instead
+//! (903.9). A player who has been dealt 21 or more combat damage by the same
+//! commander loses the game (704.6c).
+
+use crate::core::{CardId, ManaCost, PlayerId};
+use crate::game::GameState;
+use crate::zones::Zone;
+use crate::{MtgError, Result};
+
+/// Starting life total in a Commander game (MTG Rules 903.7)
+pub const COMMANDER_STARTING_LIFE: i32 = 40;
+
+/// Combat damage from a single commander that makes a player lose (MTG Rules 704.6c)
+pub const COMMANDER_DAMAGE_LOSS_THRESHOLD: i32 = 21;
+
+impl GameState {
+    /// Make a card its owner's commander and put it into their command zone (MTG Rules 903.6)
+    ///
+    /// Used while setting up the game; the card must not be in a zone yet.
+    pub fn add_commander(&mut self, card_id: CardId) -> Result<()> {
+        let card = self.cards.get_mut(card_id)?;
+        card.is_commander = true;
+        let owner = card.owner;
+        self.get_player_zones_mut(owner)
+            .ok_or_else(|| MtgError::InvalidAction(format!("No zones for player {owner}")))?
+            .command
+            .add(card_id);
+        Ok(())
+    }
+
+    /// The commanders a player owns, in card id order
+    pub fn commanders(&self, player_id: PlayerId) -> Vec<CardId> {
+        let mut commanders: Vec<CardId> = self
+            .cards
+            .iter()
+            .filter(|(_, card)| card.is_commander && card.owner == player_id)
+            .map(|(&id, _)| id)
+            .collect();
+        commanders.sort();
+        commanders
+    }
+
+    /// Can `player_id` cast this card from their command zone?
+    pub fn can_cast_from_command_zone(&self, player_id: PlayerId, card_id: CardId) -> bool {
+        self.cards
+            .get(card_id)
+            .is_ok_and(|card| card.is_commander && card.owner == player_id)
+            && self
+                .get_player_zones(player_id)
+                .is_some_and(|zones| zones.command.contains(card_id))
+    }
+
+    /// The zone `player_id` would cast this card from: their hand or, for
+    /// their commander, the command zone
+    pub(crate) fn casting_zone(&self, player_id: PlayerId, card_id: CardId) -> Result<Zone> {
+        if self
+            .get_player_zones(player_id)
+            .is_some_and(|zones| zones.hand.contains(card_id))
+        {
+            Ok(Zone::Hand)
+        } else if self.can_cast_from_command_zone(player_id, card_id) {
+            Ok(Zone::Command)
+        } else {
+            Err(MtgError::InvalidAction("Card not in hand".to_string()))
+        }
+    }
+
+    /// The additional generic mana it costs to cast this commander from the
+    /// command zone: {2} for each previous time (MTG Rules 903.8)
+    pub fn commander_tax(&self, card_id: CardId) -> u8 {
+        self.cards
+            .get(card_id)
+            .map_or(0, |card| card.commander_casts.saturating_mul(2))
+    }
+
+    /// The mana cost to cast a card from where it is now, including commander tax
+    pub fn casting_cost(&self, card_id: CardId) -> Result<ManaCost> {
+        let card = self.cards.get(card_id)?;
+        if self.can_cast_from_command_zone(card.owner, card_id) {
+            Ok(card.mana_cost.plus_generic(self.commander_tax(card_id)))
+        } else {
+            Ok(card.mana_cost)
+        }
+    }
+
+    /// Record that a commander was cast from the command zone
+    pub(crate) fn record_commander_cast(&mut self, card_id: CardId) -> Result<()> {
+        let card = self.cards.get_mut(card_id)?;
+        card.commander_casts = card.commander_casts.saturating_add(1);
+        self.undo_log
+            .log(crate::undo::GameAction::CastFromCommandZone { card_id });
+        Ok(())
+    }
+
+    /// Where a commander goes instead of `to` (MTG Rules 903.9)
+    ///
+    /// The owner may put it into the command zone instead of their hand,
+    /// library, graveyard or exile; they always do.
+    pub(crate) fn commander_replacement(&self, card_id: CardId, to: Zone) -> Option<Zone> {
+        let returns = matches!(to, Zone::Hand | Zone::Library | Zone::Graveyard | Zone::Exile);
+        (returns && self.cards.get(card_id).is_ok_and(|card| card.is_commander)).then_some(Zone::Command)
+    }
+
+    /// Combat damage a player has been dealt by a commander over the game
+    pub fn commander_damage(&self, player_id: PlayerId, commander: CardId) -> i32 {
+        self.get_player(player_id).map_or(0, |player| {
+            player
+                .commander_damage
+                .iter()
+                .find(|(id, _)| *id == commander)
+                .map_or(0, |&(_, damage)| damage)
+        })
+    }
+
+    /// Has a player been dealt 21 or more combat damage by the same commander? (MTG Rules 704.6c)
+    pub fn has_lethal_commander_damage(&self, player_id: PlayerId) -> bool {
+        self.get_player(player_id).is_ok_and(|player| {
+            player
+                .commander_damage
+                .iter()
+                .any(|&(_, damage)| damage >= COMMANDER_DAMAGE_LOSS_THRESHOLD)
+        })
+    }
+
+    /// Track combat damage a commander dealt to a player (MTG Rules 903.10a)
+    pub(crate) fn record_commander_damage(
+        &mut self,
+        player_id: PlayerId,
+        commander: CardId,
+        amount: i32,
+    ) -> Result<()> {
+        let player = self.get_player_mut(player_id)?;
+        match player.commander_damage.iter_mut().find(|(id, _)| *id == commander) {
+            Some((_, damage)) => *damage += amount,
+            None => player.commander_damage.push((commander, amount)),
+        }
+        self.undo_log.log(crate::undo::GameAction::CommanderDamage {
+            player_id,
+            commander,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Take back combat damage recorded from a commander (used by undo)
+    pub(crate) fn unrecord_commander_damage(&mut self, player_id: PlayerId, commander: CardId, amount: i32) {
+        if let Ok(player) = self.get_player_mut(player_id) {
+            if let Some(index) = player.commander_damage.iter().position(|(id, _)| *id == commander) {
+                player.commander_damage[index].1 -= amount;
+                if player.commander_damage[index].1 <= 0 {
+                    player.commander_damage.remove(index);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{Card, CardId, CardType, Color, ManaCost, PlayerId, TargetRef};
+    use crate::game::GameState;
+    use crate::zones::Zone;
+
+    fn add_commander(game: &mut GameState, owner: PlayerId) -> CardId {
+        let id = game.next_card_id();
+        let mut card = Card::new(id, "Ezuri, Renegade Leader".to_string(), owner);
+        card.types.push(CardType::Creature);
+        card.mana_cost = ManaCost::from_string("1GG");
+        card.power = Some(2);
+        card.toughness = Some(2);
+        game.cards.insert(id, card);
+        game.add_commander(id).unwrap();
+        id
+    }
+
+    fn add_green_mana(game: &mut GameState, player: PlayerId, amount: usize) {
+        let pool = &mut game.get_player_mut(player).unwrap().mana_pool;
+        for _ in 0..amount {
+            pool.add_color(Color::Green);
+        }
+    }
+
+    #[test]
+    fn test_cast_from_command_zone_with_tax() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 40);
+        let alice = game.players[0].id;
+        let ezuri = add_commander(&mut game, alice);
+        assert_eq!(game.casting_cost(ezuri).unwrap().cmc(), 3);
+
+        add_green_mana(&mut game, alice, 3);
+        game.cast_spell(alice, ezuri, Vec::new()).unwrap();
+        assert!(game.stack.contains(ezuri));
+
+        // MTG Rules 903.9: it goes back to the command zone instead of the graveyard
+        game.move_card(ezuri, Zone::Stack, Zone::Graveyard, alice).unwrap();
+        let zones = game.get_player_zones(alice).unwrap();
+        assert!(zones.command.contains(ezuri));
+        assert!(!zones.graveyard.contains(ezuri));
+
+        // MTG Rules 903.8: {2} more for each previous cast from the command zone
+        assert_eq!(game.commander_tax(ezuri), 2);
+        assert_eq!(game.casting_cost(ezuri).unwrap().cmc(), 5);
+        add_green_mana(&mut game, alice, 3);
+        assert!(game.cast_spell(alice, ezuri, Vec::new()).is_err());
+
+        // Rewinding the first cast takes the tax back
+        while game.commander_tax(ezuri) > 0 {
+            game.undo().unwrap();
+        }
+        assert!(game.get_player_zones(alice).unwrap().command.contains(ezuri));
+    }
+
+    #[test]
+    fn test_21_commander_damage_loses() {
+        let mut game = GameState::new_multiplayer(vec!["Alice".into(), "Bob".into(), "Carol".into()], 40);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let ezuri = add_commander(&mut game, alice);
+        game.get_player_zones_mut(alice).unwrap().command.remove(ezuri);
+        game.battlefield.add(ezuri);
+
+        // Only combat damage counts (MTG Rules 903.10a)
+        game.deal_damage_from(Some(ezuri), &TargetRef::Player(bob), 5, false)
+            .unwrap();
+        assert_eq!(game.commander_damage(bob, ezuri), 0);
+        for _ in 0..3 {
+            game.deal_damage_from(Some(ezuri), &TargetRef::Player(bob), 7, true)
+                .unwrap();
+        }
+        assert_eq!(game.commander_damage(bob, ezuri), 21);
+        assert_eq!(game.get_player(bob).unwrap().life, 14);
+
+        game.check_state_based_actions().unwrap();
+        assert!(!game.is_in_game(bob));
+
+        // Rewinding restores the damage record one hit at a time
+        while game.commander_damage(bob, ezuri) > 14 {
+            game.undo().unwrap();
+        }
+        assert_eq!(game.commander_damage(bob, ezuri), 14);
+        assert!(game.is_in_game(bob));
+    }
+}
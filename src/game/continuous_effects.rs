@@ -0,0 +1,310 @@
+//! Continuous effects from static abilities (MTG Rules 611.3, 613)
+//!
+//! Characteristics derived from static abilities are recomputed from scratch
+//! whenever something they depend on may have changed: when a permanent enters
+//! or leaves the battlefield (including when that is undone) and before
+//! state-based actions are checked. The results are cached on each card as
+//! `Card::derived`, so reading a card's power, keywords or types stays cheap.
+
+use crate::core::{CardId, Layer, SelectorContext, StaticAbility};
+use crate::game::GameState;
+
+impl GameState {
+    /// Recompute every permanent's characteristics from the static abilities on the battlefield
+    ///
+    /// Effects are applied layer by layer (MTG Rules 613.1) and, within a layer,
+    /// in timestamp order (613.7). A permanent's timestamp is its position on the
+    /// battlefield, which is the order permanents entered it. The set of
+    /// permanents an effect affects is determined in the first layer the effect
+    /// applies in and kept for the later layers (613.6). Dependencies (613.8) are
+    /// not taken into account.
+    pub fn apply_continuous_effects(&mut self) {
+        // Start over from the cards' own characteristics
+        let mut any_static = false;
+        for &card_id in &self.battlefield.cards {
+            if let Ok(card) = self.cards.get_mut(card_id) {
+                card.clear_derived();
+                any_static |= !card.static_abilities.is_empty();
+            }
+        }
+        if !any_static {
+            return;
+        }
+
+        // Static abilities function only on the battlefield (MTG Rules 611.3b)
+        let effects: Vec<(CardId, StaticAbility)> = self
+            .battlefield
+            .cards
+            .iter()
+            .filter_map(|&card_id| self.cards.get(card_id).ok())
+            .flat_map(|card| {
+                card.static_abilities
+                    .iter()
+                    .map(move |ability| (card.id, ability.clone()))
+            })
+            .collect();
+        let mut affected: Vec<Option<Vec<CardId>>> = vec![None; effects.len()];
+
+        for layer in Layer::ALL {
+            for (i, (source_id, ability)) in effects.iter().enumerate() {
+                let modifications: Vec<_> = ability.modifications.iter().filter(|m| m.layer() == layer).collect();
+                if modifications.is_empty() {
+                    continue;
+                }
+                let Ok((source_controller, source_attached_to)) = self
+                    .cards
+                    .get(*source_id)
+                    .map(|source| (source.controller, source.attached_to))
+                else {
+                    continue;
+                };
+
+                let targets = affected[i].get_or_insert_with(|| {
+                    let ctx =
+                        SelectorContext::new(source_controller, Some(*source_id)).with_attached_to(source_attached_to);
+                    self.battlefield
+                        .cards
+                        .iter()
+                        .copied()
+                        .filter(|&card_id| {
+                            self.cards
+                                .get(card_id)
+                                .is_ok_and(|card| ability.affected.matches_card(card, &ctx))
+                        })
+                        .collect()
+                });
+
+                for &card_id in targets.iter() {
+                    let Ok(card) = self.cards.get_mut(card_id) else {
+                        continue;
+                    };
+                    if card.derived.is_none() {
+                        card.derived = Some(Box::new(card.base_characteristics()));
+                    }
+                    let derived = card.derived.as_mut().expect("derived characteristics were just set");
+                    for modification in &modifications {
+                        if let Some(controller) = derived.apply(modification, source_controller) {
+                            card.controller = controller;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        Card, CardId, CardType, Color, ContinuousModification, Keyword, PlayerId, Selector, StaticAbility,
+    };
+    use crate::game::GameState;
+    use crate::zones::Zone;
+
+    fn add_creature(game: &mut GameState, owner: PlayerId, power: i8, toughness: i8) -> CardId {
+        let id = game.next_card_id();
+        let mut card = Card::new(id, "Bear".to_string(), owner);
+        card.types.push(CardType::Creature);
+        card.power = Some(power);
+        card.toughness = Some(toughness);
+        game.cards.insert(id, card);
+        game.get_player_zones_mut(owner).unwrap().hand.add(id);
+        game.move_card(id, Zone::Hand, Zone::Battlefield, owner).unwrap();
+        id
+    }
+
+    fn add_enchantment(game: &mut GameState, owner: PlayerId, ability: StaticAbility) -> CardId {
+        let id = game.next_card_id();
+        let mut card = Card::new(id, "Anthem".to_string(), owner);
+        card.types.push(CardType::Enchantment);
+        card.static_abilities.push(ability);
+        game.cards.insert(id, card);
+        game.get_player_zones_mut(owner).unwrap().hand.add(id);
+        game.move_card(id, Zone::Hand, Zone::Battlefield, owner).unwrap();
+        id
+    }
+
+    fn ability(affected: &str, modifications: Vec<ContinuousModification>) -> StaticAbility {
+        StaticAbility {
+            affected: Selector::parse(affected),
+            modifications,
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_anthem_applies_while_on_battlefield() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let ours = add_creature(&mut game, alice, 2, 2);
+        let theirs = add_creature(&mut game, bob, 2, 2);
+
+        let anthem = add_enchantment(
+            &mut game,
+            alice,
+            ability(
+                "Creature.YouCtrl",
+                vec![
+                    ContinuousModification::AddPower(1),
+                    ContinuousModification::AddToughness(1),
+                    ContinuousModification::AddKeyword(Keyword::Flying),
+                ],
+            ),
+        );
+        let card = game.cards.get(ours).unwrap();
+        assert_eq!((card.current_power(), card.current_toughness()), (3, 3));
+        assert!(card.has_flying());
+        let card = game.cards.get(theirs).unwrap();
+        assert_eq!((card.current_power(), card.current_toughness()), (2, 2));
+        assert!(!card.has_flying());
+
+        // A creature that enters later is affected too
+        let late = add_creature(&mut game, alice, 1, 1);
+        assert_eq!(game.cards.get(late).unwrap().current_power(), 2);
+
+        // The bonus ends when the anthem leaves
+        game.move_card(anthem, Zone::Battlefield, Zone::Graveyard, alice)
+            .unwrap();
+        let card = game.cards.get(ours).unwrap();
+        assert_eq!((card.current_power(), card.current_toughness()), (2, 2));
+        assert!(!card.has_flying());
+        assert!(card.derived.is_none());
+
+        // ...and comes back when that move is undone
+        game.undo().unwrap();
+        assert_eq!(game.cards.get(ours).unwrap().current_power(), 3);
+    }
+
+    #[test]
+    fn test_layers_apply_in_order_regardless_of_timestamp() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let bear = add_creature(&mut game, alice, 2, 2);
+
+        // The +1/+1 (layer 7c) came first, but it still applies after setting
+        // base P/T to 0/1 (layer 7b)
+        add_enchantment(
+            &mut game,
+            alice,
+            ability(
+                "Creature",
+                vec![
+                    ContinuousModification::AddPower(1),
+                    ContinuousModification::AddToughness(1),
+                ],
+            ),
+        );
+        add_enchantment(
+            &mut game,
+            alice,
+            ability(
+                "Creature",
+                vec![
+                    ContinuousModification::SetPower(0),
+                    ContinuousModification::SetToughness(1),
+                ],
+            ),
+        );
+        let card = game.cards.get(bear).unwrap();
+        assert_eq!((card.current_power(), card.current_toughness()), (1, 2));
+    }
+
+    #[test]
+    fn test_affected_set_is_locked_in_first_layer() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let bear = add_creature(&mut game, alice, 2, 2);
+
+        // "Green creatures are blue and get +1/+1": the bear stops being green in
+        // layer 5 but still gets the bonus in layer 7c (MTG Rules 613.6)
+        game.cards.get_mut(bear).unwrap().colors.push(Color::Green);
+        add_enchantment(
+            &mut game,
+            alice,
+            ability(
+                "Creature.Green",
+                vec![
+                    ContinuousModification::SetColors([Color::Blue].into_iter().collect()),
+                    ContinuousModification::AddPower(1),
+                ],
+            ),
+        );
+        let card = game.cards.get(bear).unwrap();
+        assert_eq!(card.current_colors(), &[Color::Blue]);
+        assert_eq!(card.current_power(), 3);
+    }
+
+    #[test]
+    fn test_type_changes_affect_later_layers() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+
+        // An artifact becomes a 2/2 creature, then an anthem for creatures pumps it
+        let relic = game.next_card_id();
+        let mut card = Card::new(relic, "Relic".to_string(), alice);
+        card.types.push(CardType::Artifact);
+        game.cards.insert(relic, card);
+        game.battlefield.add(relic);
+        add_enchantment(
+            &mut game,
+            alice,
+            ability("Creature", vec![ContinuousModification::AddPower(1)]),
+        );
+        add_enchantment(
+            &mut game,
+            alice,
+            ability(
+                "Artifact",
+                vec![
+                    ContinuousModification::AddType(CardType::Creature),
+                    ContinuousModification::SetPower(2),
+                    ContinuousModification::SetToughness(2),
+                ],
+            ),
+        );
+        let card = game.cards.get(relic).unwrap();
+        assert!(card.is_creature());
+        assert_eq!((card.current_power(), card.current_toughness()), (3, 2));
+    }
+
+    #[test]
+    fn test_control_changing_effect() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let bear = add_creature(&mut game, bob, 2, 2);
+
+        let thief = add_enchantment(
+            &mut game,
+            alice,
+            ability("Creature.OppCtrl", vec![ContinuousModification::GainControl]),
+        );
+        assert_eq!(game.cards.get(bear).unwrap().controller, alice);
+
+        game.move_card(thief, Zone::Battlefield, Zone::Graveyard, alice)
+            .unwrap();
+        assert_eq!(game.cards.get(bear).unwrap().controller, bob);
+    }
+
+    #[test]
+    fn test_state_based_actions_use_derived_toughness() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let bear = add_creature(&mut game, alice, 2, 2);
+        let anthem = add_enchantment(
+            &mut game,
+            alice,
+            ability("Creature", vec![ContinuousModification::AddToughness(1)]),
+        );
+
+        // Two damage doesn't kill a 2/3...
+        game.cards.get_mut(bear).unwrap().damage_marked = 2;
+        game.check_state_based_actions().unwrap();
+        assert!(game.battlefield.contains(bear));
+
+        // ...but once the anthem is gone it's lethal
+        game.move_card(anthem, Zone::Battlefield, Zone::Graveyard, alice)
+            .unwrap();
+        game.check_state_based_actions().unwrap();
+        assert!(game.get_player_zones(alice).unwrap().graveyard.contains(bear));
+    }
+}
@@ -14,8 +14,8 @@
 //! - **Callback-Based Casting**: Controller provides callbacks for targeting and
 //!   mana payment during the casting sequence
 
-use crate::core::{CardId, ManaCost, PlayerId, SpellAbility};
-use crate::game::{GameState, Step};
+use crate::core::{CardId, Color, Cost, ManaCost, PlayerId, ReplacementKey, SpellAbility};
+use crate::game::{AttackTarget, GameState, StackAbility, Step};
 use crate::zones::Zone;
 use smallvec::SmallVec;
 
@@ -215,6 +215,12 @@ impl<'a> GameStateView<'a> {
         self.player_id
     }
 
+    /// The whole game state, for tests that inspect it while a choice is pending
+    #[cfg(test)]
+    pub(crate) fn game(&self) -> &'a GameState {
+        self.game
+    }
+
     /// Get cards in this player's hand
     pub fn hand(&self) -> &[CardId] {
         self.game
@@ -277,7 +283,11 @@ impl<'a> GameStateView<'a> {
                 .get_player_zones(self.player_id)
                 .map(|z| z.exile.contains(card_id))
                 .unwrap_or(false),
-            Zone::Command => false, // Command zone not yet implemented
+            Zone::Command => self
+                .game
+                .get_player_zones(self.player_id)
+                .map(|z| z.command.contains(card_id))
+                .unwrap_or(false),
         }
     }
 
@@ -313,6 +323,11 @@ impl<'a> GameStateView<'a> {
         self.game.cards.get(card_id).ok()
     }
 
+    /// Look up a functioning replacement effect, with its source and controller
+    pub fn replacement_effect(&self, key: ReplacementKey) -> Option<crate::game::FunctioningReplacement<'_>> {
+        self.game.replacement_by_key(key)
+    }
+
     /// Check if a card is tapped
     pub fn is_tapped(&self, card_id: CardId) -> bool {
         self.game.cards.get(card_id).map(|c| c.tapped).unwrap_or(false)
@@ -344,6 +359,20 @@ impl<'a> GameStateView<'a> {
             })
     }
 
+    /// Name of a player or planeswalker that can be attacked
+    pub fn attack_target_name(&self, target: AttackTarget) -> String {
+        match target {
+            AttackTarget::Player(player_id) => self
+                .game
+                .get_player(player_id)
+                .map(|p| p.name.to_string())
+                .unwrap_or_else(|_| format!("Player {}", player_id.as_u32() + 1)),
+            AttackTarget::Planeswalker(card_id) => {
+                self.card_name(card_id).unwrap_or_else(|| format!("Card {card_id:?}"))
+            }
+        }
+    }
+
     /// Get a specific player's life total
     pub fn player_life(&self, player_id: PlayerId) -> i32 {
         self.game.get_player(player_id).ok().map(|p| p.life).unwrap_or(0)
@@ -351,14 +380,14 @@ impl<'a> GameStateView<'a> {
 
     /// Get all opponent player IDs
     ///
-    /// Returns an iterator over all players except the current player.
-    /// Useful for multiplayer games.
+    /// Returns an iterator over all players except the current player and
+    /// those who have left the game. Useful for multiplayer games.
     pub fn opponents(&self) -> impl Iterator<Item = PlayerId> + '_ {
         self.game
             .players
             .iter()
+            .filter(move |p| p.id != self.player_id && !p.has_lost)
             .map(|p| p.id)
-            .filter(move |&id| id != self.player_id)
     }
 
     /// Get opponent life total in a 2-player game
@@ -388,6 +417,24 @@ impl<'a> GameStateView<'a> {
             .unwrap_or((0, 0, 0, 0, 0, 0))
     }
 
+    /// The sources to tap to pay for a cost, keeping the most flexible ones untapped
+    ///
+    /// Searches over `available_sources` with the
+    /// [`BacktrackingResolver`](crate::game::BacktrackingResolver); Phyrexian
+    /// symbols may be paid with life. Returns None if they can't pay for the cost.
+    pub fn mana_tap_plan(&self, cost: &ManaCost, available_sources: &[CardId]) -> Option<SmallVec<[CardId; 8]>> {
+        use crate::game::{BacktrackingResolver, ManaSource};
+
+        let sources: Vec<ManaSource> = available_sources
+            .iter()
+            .filter_map(|&id| self.game.cards.get(id).ok())
+            .flat_map(|card| ManaSource::for_permanent(self.game, card))
+            .collect();
+        let max_life_payments = self.game.max_life_payments(self.player_id);
+        let (plan, _) = BacktrackingResolver::new().solve_with_life(cost, &sources, max_life_payments)?;
+        Some(plan.iter().map(|tap| tap.card_id).collect())
+    }
+
     /// Check if player can play lands this turn
     pub fn can_play_land(&self) -> bool {
         self.game
@@ -440,6 +487,44 @@ pub trait PlayerController {
         available: &[SpellAbility],
     ) -> Option<SpellAbility>;
 
+    /// Choose the modes of a modal spell (e.g. a Charm)
+    ///
+    /// Called during step 2 of casting a spell (MTG Rules 601.2b). `modes` holds
+    /// the description of each mode. Returns indices into `modes`: between
+    /// `min` and `max` of them, each at most once.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches `PlayerController.chooseModeForAbility(SpellAbility, List, int, int, boolean)`
+    fn choose_modes(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        modes: &[String],
+        min: u8,
+        max: u8,
+    ) -> SmallVec<[usize; 4]>;
+
+    /// Choose the value of X for a spell with X in its mana cost
+    ///
+    /// Called during step 2 of casting a spell (MTG Rules 601.2b). `max_x` is
+    /// the largest value the player can currently pay for; larger answers are
+    /// reduced to it.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches the X announcement in `PlayerController.announceRequirements(SpellAbility, String)`
+    fn choose_x_value(&mut self, view: &GameStateView, spell: CardId, max_x: u8) -> u8;
+
+    /// Choose whether to pay a spell's alternative cost rather than its mana cost
+    ///
+    /// Called during step 2 of casting a spell (MTG Rules 601.2b, 118.9) when the
+    /// player could pay either one, e.g. Fireblast's "sacrifice two Mountains".
+    /// `cost` is the alternative cost. Returns true to pay it.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches choosing between a card's spell abilities in
+    /// `PlayerController.getAbilityToPlay(Card, List<SpellAbility>)`
+    fn choose_alternative_cost(&mut self, view: &GameStateView, spell: CardId, cost: &Cost) -> bool;
+
     /// Choose targets for a spell or ability
     ///
     /// Called during step 3 of casting a spell (MTG Rules 601.2c).
@@ -457,6 +542,23 @@ pub trait PlayerController {
         valid_targets: &[CardId],
     ) -> SmallVec<[CardId; 4]>;
 
+    /// Divide an amount among the chosen targets of a spell
+    ///
+    /// Called during step 4 of casting a spell (MTG Rules 601.2d) for spells that
+    /// divide damage or counters "as you choose", when there are two or more
+    /// targets. Returns one share per target, in the order of `targets`; each
+    /// share must be at least 1 and the shares must add up to `total`.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches the division made while targeting (`SpellAbility.addDividedAllocation`)
+    fn choose_division(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        targets: &[CardId],
+        total: u32,
+    ) -> SmallVec<[u32; 4]>;
+
     /// Choose which mana sources to tap to pay a cost
     ///
     /// Called during step 6 of casting a spell (MTG Rules 601.2g).
@@ -475,11 +577,27 @@ pub trait PlayerController {
         available_sources: &[CardId],
     ) -> SmallVec<[CardId; 8]>;
 
-    /// Choose which creatures to declare as attackers
+    /// Choose the color of mana a mana ability adds ("Add one mana of any color")
+    ///
+    /// Called when this player activates a mana ability that adds mana of a
+    /// color they choose, outside of paying a cost (while paying, the color
+    /// comes from the cost). Returns one of `colors`.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches `PlayerController.chooseColor(String, SpellAbility, ColorSet)`
+    fn choose_mana_color(&mut self, view: &GameStateView, source: CardId, colors: &[Color]) -> Color;
+
+    /// Choose which creatures to declare as attackers, and what each one attacks
     ///
-    /// Called during the declare attackers step.
-    /// Returns a list of creature card IDs that should attack.
-    fn choose_attackers(&mut self, view: &GameStateView, available_creatures: &[CardId]) -> SmallVec<[CardId; 8]>;
+    /// Called during the declare attackers step. `defenders` lists what can be
+    /// attacked: the defending player first, then the planeswalkers they control.
+    /// Returns (creature, attack target) pairs for the creatures that should attack.
+    fn choose_attackers(
+        &mut self,
+        view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]>;
 
     /// Choose how to block attacking creatures
     ///
@@ -507,6 +625,82 @@ pub trait PlayerController {
         blockers: &[CardId],
     ) -> SmallVec<[CardId; 4]>;
 
+    /// Choose the order to put simultaneous triggered abilities on the stack
+    ///
+    /// Called when two or more of this player's triggered abilities are waiting to
+    /// be put on the stack. MTG Rules 603.3b: each player puts their triggers on the
+    /// stack in any order they choose.
+    ///
+    /// Returns indices into `triggers`; the first goes on the stack first and so
+    /// resolves last. All triggers must be included exactly once.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches `PlayerController.orderSimultaneousAbilities(List<SpellAbility>)`
+    fn choose_trigger_order(&mut self, view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]>;
+
+    /// Choose which replacement effect applies first to an event
+    ///
+    /// Called when two or more replacement effects would modify the same event
+    /// affecting this player (or an object they control). MTG Rules 616.1: the
+    /// player chooses one of them to apply; the rest are checked again against
+    /// the modified event, so the player may be asked again for the same event.
+    /// `effects` is in timestamp order, and the view shows the game as it was
+    /// before the action that causes the event. Returns one key from `effects`.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches `PlayerController.chooseSingleReplacementEffect(List<ReplacementEffect>)`
+    fn choose_replacement_effect(&mut self, view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey;
+
+    /// Choose which legendary permanent to keep under the legend rule
+    ///
+    /// Called when this player controls two or more legendary permanents with
+    /// the same name. MTG Rules 704.5j: the player chooses one of them, and the
+    /// rest are put into their owners' graveyards. `legends` is in battlefield
+    /// order, so the most recent arrival is last. Returns one card from `legends`.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches `PlayerController.chooseSingleEntityForEffect()` with `ApiType.InternalLegendaryRule`
+    fn choose_legend_to_keep(&mut self, view: &GameStateView, legends: &[CardId]) -> CardId;
+
+    /// Decide whether to mulligan an opening hand
+    ///
+    /// Called during game setup (MTG Rules 103.5) with the hand just drawn and
+    /// the number of mulligans this player has taken so far. Returns true to
+    /// shuffle the hand into the library and draw a new one, false to keep it.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches `PlayerController.mulliganKeepHand(Player, int)`, with the answer inverted
+    fn choose_mulligan(&mut self, view: &GameStateView, hand: &[CardId], mulligans_taken: u8) -> bool;
+
+    /// Choose cards from a kept opening hand to put on the bottom of the library
+    ///
+    /// Called once a player keeps a hand after taking mulligans (MTG Rules 103.5,
+    /// the London mulligan). Returns exactly `count` cards from `hand`; they are
+    /// put on the bottom in the order returned, so the last one ends up at the very bottom.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches `PlayerController.londonMulliganReturnCards(Player, int)`
+    fn choose_cards_to_bottom(&mut self, view: &GameStateView, hand: &[CardId], count: usize) -> SmallVec<[CardId; 7]>;
+
+    /// Choose the cards that pay part of an ability's cost
+    ///
+    /// Called when activating an ability whose cost sacrifices, taps or returns
+    /// permanents, discards cards, or exiles cards from the graveyard, and more
+    /// cards qualify than the cost needs (MTG Rules 601.2h). `cost` is the part
+    /// being paid, e.g. `Cost::SacrificePattern`. Returns exactly `count` distinct
+    /// cards from `candidates`.
+    ///
+    /// ## Java Forge Equivalent
+    /// Matches the `CostDecisionMakerBase` visits (`AiCostDecision`/`HumanCostDecision`)
+    fn choose_cards_for_cost(
+        &mut self,
+        view: &GameStateView,
+        source: CardId,
+        cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]>;
+
     /// Choose cards to discard to maximum hand size
     ///
     /// Called during cleanup step if hand size exceeds maximum.
@@ -4,9 +4,10 @@
 //! for testing specific game scenarios. Once the script is exhausted, it defaults
 //! to choosing the first option (index 0).
 
-use crate::core::{CardId, ManaCost, PlayerId, SpellAbility};
+use crate::core::{CardId, Color, Cost, ManaCost, PlayerId, ReplacementKey, SpellAbility};
 use crate::game::controller::GameStateView;
 use crate::game::controller::PlayerController;
+use crate::game::{AttackTarget, StackAbility};
 use smallvec::SmallVec;
 
 /// A controller that follows a fixed script of choices for testing
@@ -116,6 +117,50 @@ impl PlayerController for FixedScriptController {
         Some(available[ability_index].clone())
     }
 
+    fn choose_modes(
+        &mut self,
+        view: &GameStateView,
+        _spell: CardId,
+        modes: &[String],
+        min: u8,
+        _max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        // Each script entry picks one of the modes not chosen yet, so any script
+        // yields distinct modes; as few modes as allowed are chosen
+        let mut remaining: Vec<usize> = (0..modes.len()).collect();
+        let mut chosen = SmallVec::new();
+        for _ in 0..(min as usize).min(modes.len()) {
+            let choice_index = self.next_choice().min(remaining.len() - 1);
+            chosen.push(remaining.remove(choice_index));
+        }
+        view.logger().controller_choice(
+            "SCRIPT",
+            &format!("chose modes {:?} out of choices 0-{}", chosen, modes.len() - 1),
+        );
+        chosen
+    }
+
+    fn choose_x_value(&mut self, view: &GameStateView, _spell: CardId, max_x: u8) -> u8 {
+        let choice = self.next_choice();
+        let x = choice.min(max_x as usize) as u8;
+        view.logger()
+            .controller_choice("SCRIPT", &format!("chose X = {x} out of 0-{max_x}"));
+        x
+    }
+
+    fn choose_alternative_cost(&mut self, view: &GameStateView, _spell: CardId, _cost: &Cost) -> bool {
+        // 0 pays the mana cost, anything else the alternative cost
+        let alternative = self.next_choice() != 0;
+        view.logger().controller_choice(
+            "SCRIPT",
+            &format!(
+                "chose to pay the {} cost",
+                if alternative { "alternative" } else { "mana" }
+            ),
+        );
+        alternative
+    }
+
     fn choose_targets(
         &mut self,
         view: &GameStateView,
@@ -165,6 +210,31 @@ impl PlayerController for FixedScriptController {
         targets
     }
 
+    fn choose_division(
+        &mut self,
+        view: &GameStateView,
+        _spell: CardId,
+        targets: &[CardId],
+        total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        // Each script entry is one target's share, clamped so every later target
+        // can still get 1; the last target gets what's left
+        let mut division = SmallVec::new();
+        let mut remaining = total;
+        for left_after in (0..targets.len() as u32).rev() {
+            let share = if left_after == 0 {
+                remaining
+            } else {
+                (self.next_choice() as u32).clamp(1, remaining - left_after)
+            };
+            division.push(share);
+            remaining -= share;
+        }
+        view.logger()
+            .controller_choice("SCRIPT", &format!("divided {total} as {division:?}"));
+        division
+    }
+
     fn choose_mana_sources_to_pay(
         &mut self,
         view: &GameStateView,
@@ -194,7 +264,31 @@ impl PlayerController for FixedScriptController {
         sources
     }
 
-    fn choose_attackers(&mut self, view: &GameStateView, available_creatures: &[CardId]) -> SmallVec<[CardId; 8]> {
+    fn choose_mana_color(&mut self, view: &GameStateView, _source: CardId, colors: &[Color]) -> Color {
+        // Use script to pick the color
+        let choice_index = self.next_choice();
+        let clamped_index = choice_index.min(colors.len() - 1);
+        view.logger().controller_choice(
+            "SCRIPT",
+            &format!(
+                "chose mana color {} ({}) out of choices 0-{}",
+                clamped_index,
+                colors[clamped_index],
+                colors.len() - 1
+            ),
+        );
+        colors[clamped_index]
+    }
+
+    fn choose_attackers(
+        &mut self,
+        view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
+        let Some(&defender) = defenders.first() else {
+            return SmallVec::new();
+        };
         if available_creatures.is_empty() {
             view.logger()
                 .controller_choice("SCRIPT", "chose no attackers (none available)");
@@ -214,10 +308,10 @@ impl PlayerController for FixedScriptController {
             ),
         );
 
-        // Attack with the first N creatures
+        // Attack the defending player with the first N creatures
         let mut attackers = SmallVec::new();
         for &creature_id in available_creatures.iter().take(num_attackers) {
-            attackers.push(creature_id);
+            attackers.push((creature_id, defender));
         }
 
         attackers
@@ -278,6 +372,93 @@ impl PlayerController for FixedScriptController {
         blockers.iter().copied().collect()
     }
 
+    fn choose_trigger_order(&mut self, view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        // Script controller doesn't reorder
+        if triggers.len() >= 2 {
+            view.logger().controller_choice(
+                "SCRIPT",
+                &format!(
+                    "chose trigger order (kept original order of {} triggers)",
+                    triggers.len()
+                ),
+            );
+        }
+
+        (0..triggers.len()).collect()
+    }
+
+    fn choose_replacement_effect(&mut self, view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        // Use script to pick which one applies first
+        let choice_index = self.next_choice();
+        let clamped_index = choice_index.min(effects.len() - 1);
+        view.logger().controller_choice(
+            "SCRIPT",
+            &format!(
+                "chose replacement effect {} out of choices 0-{}",
+                clamped_index,
+                effects.len() - 1
+            ),
+        );
+        effects[clamped_index]
+    }
+
+    fn choose_legend_to_keep(&mut self, view: &GameStateView, legends: &[CardId]) -> CardId {
+        // Use script to pick which one stays
+        let choice_index = self.next_choice();
+        let clamped_index = choice_index.min(legends.len() - 1);
+        view.logger().controller_choice(
+            "SCRIPT",
+            &format!(
+                "chose legend {} out of choices 0-{} to keep",
+                clamped_index,
+                legends.len() - 1
+            ),
+        );
+        legends[clamped_index]
+    }
+
+    fn choose_mulligan(&mut self, view: &GameStateView, hand: &[CardId], _mulligans_taken: u8) -> bool {
+        // Script controller always keeps, so scripts start from the first hand drawn
+        view.logger()
+            .controller_choice("SCRIPT", &format!("chose to keep a {}-card hand", hand.len()));
+        false
+    }
+
+    fn choose_cards_to_bottom(&mut self, view: &GameStateView, hand: &[CardId], count: usize) -> SmallVec<[CardId; 7]> {
+        // Put the first N cards from hand on the bottom
+        let num_bottom = count.min(hand.len());
+        view.logger().controller_choice(
+            "SCRIPT",
+            &format!(
+                "chose first {} cards to put on the bottom from {} cards in hand",
+                num_bottom,
+                hand.len()
+            ),
+        );
+        hand.iter().take(num_bottom).copied().collect()
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        view: &GameStateView,
+        _source: CardId,
+        _cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        // Pay with the first N candidates
+        let num_chosen = count.min(candidates.len());
+        view.logger().controller_choice(
+            "SCRIPT",
+            &format!(
+                "chose first {} cards to pay a cost from {} candidates",
+                num_chosen,
+                candidates.len()
+            ),
+        );
+        candidates.iter().take(num_chosen).copied().collect()
+    }
+
     fn choose_cards_to_discard(
         &mut self,
         view: &GameStateView,
@@ -420,6 +601,22 @@ mod tests {
         assert_eq!(targets2[0], valid_targets[0]);
     }
 
+    #[test]
+    fn test_choose_modes_and_x() {
+        let player_id = EntityId::new(1);
+        // Modes: index 2, then index 1 of the remaining [0, 1]; then X = 9, clamped to 5
+        let mut controller = FixedScriptController::new(player_id, vec![2, 1, 9]);
+        let game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let view = GameStateView::new(&game, player_id);
+
+        let spell_id = EntityId::new(100);
+        let modes = vec!["Counter".to_string(), "Damage".to_string(), "Draw".to_string()];
+
+        let chosen = controller.choose_modes(&view, spell_id, &modes, 2, 2);
+        assert_eq!(chosen.as_slice(), &[2, 1]);
+        assert_eq!(controller.choose_x_value(&view, spell_id, 5), 5);
+    }
+
     #[test]
     fn test_choose_attackers() {
         let player_id = EntityId::new(1);
@@ -429,15 +626,16 @@ mod tests {
         let view = GameStateView::new(&game, player_id);
 
         let creatures = vec![EntityId::new(20), EntityId::new(21), EntityId::new(22)];
+        let defenders = [AttackTarget::Player(EntityId::new(2))];
 
         // First choice: 2 attackers
-        let attackers1 = controller.choose_attackers(&view, &creatures);
+        let attackers1 = controller.choose_attackers(&view, &creatures, &defenders);
         assert_eq!(attackers1.len(), 2);
-        assert_eq!(attackers1[0], creatures[0]);
-        assert_eq!(attackers1[1], creatures[1]);
+        assert_eq!(attackers1[0], (creatures[0], defenders[0]));
+        assert_eq!(attackers1[1], (creatures[1], defenders[0]));
 
         // Second choice: 0 attackers
-        let attackers2 = controller.choose_attackers(&view, &creatures);
+        let attackers2 = controller.choose_attackers(&view, &creatures, &defenders);
         assert_eq!(attackers2.len(), 0);
     }
 
@@ -25,7 +25,7 @@ use crate::game::controller::{
     format_attackers_prompt, format_blockers_prompt, format_choice_menu, format_discard_prompt, GameStateView,
 };
 use crate::game::phase::Step;
-use crate::game::GameState;
+use crate::game::{AttackTarget, GameState, SpellChoices, StackAbility, StackObject};
 use crate::{MtgError, Result};
 
 // Legacy v1 action type (kept for compatibility with dead code)
@@ -75,6 +75,10 @@ pub enum GameEndReason {
     PlayerDeath(PlayerId),
     /// A player won by decking their opponent
     Decking(PlayerId),
+    /// A player won by giving their opponent ten or more poison counters
+    Poison(PlayerId),
+    /// A player won by dealing 21 combat damage to their opponent with a commander
+    CommanderDamage(PlayerId),
     /// Game reached maximum turn limit
     TurnLimit,
     /// Game ended in a draw
@@ -99,9 +103,6 @@ pub struct GameLoop<'a> {
     pub verbosity: VerbosityLevel,
     /// Track if current step header has been printed (for lazy printing)
     step_header_printed: bool,
-    /// Track targets for spells on the stack (spell_id -> chosen_targets)
-    /// This is needed because targets are chosen at cast time but used at resolution time
-    spell_targets: Vec<(CardId, Vec<CardId>)>,
     /// Global choice counter for tracking all player choices
     /// Increments each time a controller makes any decision
     choice_counter: u32,
@@ -140,7 +141,6 @@ impl<'a> GameLoop<'a> {
             turns_elapsed: 0,
             verbosity,
             step_header_printed: false,
-            spell_targets: Vec::new(),
             choice_counter: 0,
             stop_when_fixed_exhausted: false,
             snapshot_path_for_fixed: None,
@@ -301,7 +301,6 @@ impl<'a> GameLoop<'a> {
     pub fn reset(&mut self) {
         self.turns_elapsed = 0;
         self.step_header_printed = false;
-        self.spell_targets.clear();
         self.choice_counter = 0;
         self.game.logger.reset_step_header();
     }
@@ -337,6 +336,18 @@ impl<'a> GameLoop<'a> {
         }
     }
 
+    /// The controller of a player
+    fn controller_for<'c, 'p>(
+        controllers: &'c mut [&'p mut dyn PlayerController],
+        player_id: PlayerId,
+    ) -> Result<&'c mut (dyn PlayerController + 'p)> {
+        controllers
+            .iter_mut()
+            .find(|c| c.player_id() == player_id)
+            .map(|c| &mut **c)
+            .ok_or_else(|| MtgError::InvalidAction(format!("No controller for player {player_id:?}")))
+    }
+
     /// Check if we should save a snapshot before asking for next controller choice
     ///
     /// This is the PREAMBLE check that happens BEFORE presenting a choice to the controller.
@@ -392,16 +403,23 @@ impl<'a> GameLoop<'a> {
         controller1: &mut dyn PlayerController,
         controller2: &mut dyn PlayerController,
     ) -> Result<GameResult> {
+        self.run_multiplayer_game(&mut [controller1, controller2])
+    }
+
+    /// Run the game loop with one controller per player, in turn order
+    ///
+    /// Returns when the game reaches a win condition or turn limit
+    pub fn run_multiplayer_game(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<GameResult> {
         // Setup: verify controllers and shuffle libraries
-        let (player1_id, player2_id) = self.setup_game(controller1, controller2)?;
+        self.setup_game(controllers)?;
 
         // Main game loop - repeatedly run turns until game ends
         loop {
             // Run one turn and check if game should end
-            if let Some(result) = self.run_turn_once(controller1, controller2)? {
+            if let Some(result) = self.run_multiplayer_turn_once(controllers)? {
                 // Check if this is a snapshot request
                 if result.end_reason == GameEndReason::Snapshot {
-                    // We're at the top level - save snapshot with access to both controllers!
+                    // We're at the top level - save snapshot with access to all controllers!
 
                     // Determine which snapshot type and path to use
                     let (choice_count, snapshot_path) =
@@ -416,11 +434,11 @@ impl<'a> GameLoop<'a> {
                             return Ok(result);
                         };
 
-                    return self.save_snapshot_and_exit(choice_count, &snapshot_path, controller1, controller2);
+                    return self.save_snapshot_and_exit(choice_count, &snapshot_path, controllers);
                 }
 
                 // Notify controllers of game end
-                self.notify_game_end(controller1, controller2, player1_id, player2_id, result.winner);
+                self.notify_game_end(controllers, result.winner);
                 return Ok(result);
             }
         }
@@ -439,9 +457,20 @@ impl<'a> GameLoop<'a> {
         controller1: &mut dyn PlayerController,
         controller2: &mut dyn PlayerController,
         turns_to_run: u32,
+    ) -> Result<GameResult> {
+        self.run_multiplayer_turns(&mut [controller1, controller2], turns_to_run)
+    }
+
+    /// Run a bounded number of turns with one controller per player, in turn order
+    ///
+    /// See `run_turns`.
+    pub fn run_multiplayer_turns(
+        &mut self,
+        controllers: &mut [&mut dyn PlayerController],
+        turns_to_run: u32,
     ) -> Result<GameResult> {
         for _ in 0..turns_to_run {
-            if let Some(result) = self.run_turn_once(controller1, controller2)? {
+            if let Some(result) = self.run_multiplayer_turn_once(controllers)? {
                 // Game ended, return the result
                 return Ok(result);
             }
@@ -479,37 +508,26 @@ impl<'a> GameLoop<'a> {
         total_count.saturating_sub(self.baseline_choice_count)
     }
 
-    /// Set up a game for two-player gameplay
+    /// Set up a game for play
     ///
     /// This verifies that:
-    /// - Exactly 2 players exist in the game
-    /// - Controllers match the player IDs
+    /// - The game has at least 2 players
+    /// - There is one controller per player, in turn order
     /// - Libraries are shuffled using the game's RNG seed (unless resuming from snapshot)
-    ///
-    /// Returns the player IDs for both players.
-    fn setup_game(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<(PlayerId, PlayerId)> {
-        // Verify controllers match players (extract exactly 2 player IDs without allocating)
-        let (player1_id, player2_id) = {
-            let mut players_iter = self.game.players.iter().map(|p| p.id);
-            let player1_id = players_iter
-                .next()
-                .ok_or_else(|| MtgError::InvalidAction("Game loop requires exactly 2 players".to_string()))?;
-            let player2_id = players_iter
-                .next()
-                .ok_or_else(|| MtgError::InvalidAction("Game loop requires exactly 2 players".to_string()))?;
-            if players_iter.next().is_some() {
-                return Err(MtgError::InvalidAction(
-                    "Game loop requires exactly 2 players".to_string(),
-                ));
-            }
-            (player1_id, player2_id)
-        };
+    fn setup_game(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<()> {
+        if self.game.players.len() < 2 {
+            return Err(MtgError::InvalidAction(
+                "Game loop requires at least 2 players".to_string(),
+            ));
+        }
 
-        if controller1.player_id() != player1_id || controller2.player_id() != player2_id {
+        let player_ids: SmallVec<[PlayerId; 4]> = self.game.players.iter().map(|p| p.id).collect();
+        if controllers.len() != player_ids.len()
+            || controllers
+                .iter()
+                .zip(&player_ids)
+                .any(|(controller, &player_id)| controller.player_id() != player_id)
+        {
             return Err(MtgError::InvalidAction(
                 "Controller player IDs don't match game players".to_string(),
             ));
@@ -522,9 +540,8 @@ impl<'a> GameLoop<'a> {
         let is_resuming_from_snapshot = !self.game.undo_log.actions().is_empty();
 
         // Detect puzzle-loaded games: they have turn > 1 or cards already in zones other than library
-        let player_ids_for_check = [player1_id, player2_id];
         let has_cards_in_play = !self.game.battlefield.cards.is_empty()
-            || player_ids_for_check.iter().any(|&pid| {
+            || player_ids.iter().any(|&pid| {
                 if let Some(zones) = self.game.get_player_zones(pid) {
                     !zones.hand.cards.is_empty() || !zones.graveyard.cards.is_empty()
                 } else {
@@ -536,17 +553,97 @@ impl<'a> GameLoop<'a> {
         if !is_resuming_from_snapshot && !is_puzzle_game {
             // Setup opening hands using unified hand setup logic (MTG Rules 103.2-103.4)
             // This handles shuffling, drawing, and optional controlled hand setup for testing
-            // TODO(mtg-102): Implement mulligan system (MTG Rules 103.5)
-            let player_ids: [PlayerId; 2] = [player1_id, player2_id];
             crate::game::setup_opening_hands(
                 self.game,
                 &player_ids,
                 self.p1_hand_setup.as_ref(),
                 self.p2_hand_setup.as_ref(),
             )?;
+
+            // Players with a controlled opening hand keep it
+            let mulligan_players: SmallVec<[PlayerId; 4]> = self
+                .game
+                .players_in_apnap_order()
+                .into_iter()
+                .filter(|&pid| {
+                    let idx = player_ids.iter().position(|&id| id == pid);
+                    !(idx == Some(0) && self.p1_hand_setup.is_some() || idx == Some(1) && self.p2_hand_setup.is_some())
+                })
+                .collect();
+            self.run_mulligans(controllers, &mulligan_players)?;
+        }
+
+        Ok(())
+    }
+
+    /// Let players mulligan their opening hands (MTG Rules 103.5, the London mulligan)
+    ///
+    /// Starting with the starting player, each player in turn order keeps their
+    /// hand or mulligans. Those who mulligan shuffle their hand into their
+    /// library and draw seven new cards, then decide again, until everyone has
+    /// kept. A player who keeps puts a card from their hand on the bottom of
+    /// their library for each mulligan they took; in a multiplayer game the
+    /// first mulligan is free (103.5c).
+    fn run_mulligans(&mut self, controllers: &mut [&mut dyn PlayerController], players: &[PlayerId]) -> Result<()> {
+        let free_mulligan = u8::from(self.game.players.len() > 2);
+        let mut deciding: SmallVec<[(PlayerId, u8); 4]> = players.iter().map(|&pid| (pid, 0)).collect();
+
+        while !deciding.is_empty() {
+            let mut mulliganing: SmallVec<[(PlayerId, u8); 4]> = SmallVec::new();
+
+            for (player_id, mulligans_taken) in deciding {
+                let to_bottom = mulligans_taken.saturating_sub(free_mulligan) as usize;
+                let hand: SmallVec<[CardId; 7]> = self
+                    .game
+                    .get_player_zones(player_id)
+                    .map(|zones| zones.hand.cards.iter().copied().collect())
+                    .unwrap_or_default();
+
+                // Once a mulligan would leave no cards, there's nothing left to decide
+                // This is not a choice, so don't log ChoicePoint
+                let mulligan = to_bottom < hand.len() && {
+                    let controller = Self::controller_for(controllers, player_id)?;
+                    let view = GameStateView::new(self.game, player_id);
+                    let mulligan = controller.choose_mulligan(&view, &hand, mulligans_taken);
+                    self.log_choice_point(player_id, Some(crate::game::ReplayChoice::Mulligan(mulligan)));
+                    mulligan
+                };
+
+                let name = self.game.get_player(player_id)?.name.to_string();
+                if mulligan {
+                    self.game.logger.normal(&format!("{name} mulligans"));
+                    mulliganing.push((player_id, mulligans_taken + 1));
+                    continue;
+                }
+
+                let to_bottom = to_bottom.min(hand.len());
+                if to_bottom > 0 {
+                    let controller = Self::controller_for(controllers, player_id)?;
+                    let view = GameStateView::new(self.game, player_id);
+                    let bottom = controller.choose_cards_to_bottom(&view, &hand, to_bottom);
+                    self.log_choice_point(player_id, Some(crate::game::ReplayChoice::Bottom(bottom.clone())));
+
+                    if bottom.len() != to_bottom {
+                        return Err(MtgError::InvalidAction(format!(
+                            "Must put exactly {to_bottom} cards on the bottom, got {}",
+                            bottom.len()
+                        )));
+                    }
+                    self.game.put_on_bottom_of_library(player_id, &bottom)?;
+                }
+                self.game
+                    .logger
+                    .normal(&format!("{name} keeps a {}-card hand", hand.len() - to_bottom));
+            }
+
+            // Everyone who mulliganed draws their new hand at the same time
+            for &(player_id, _) in &mulliganing {
+                self.game.mulligan_hand(player_id)?;
+            }
+            deciding = mulliganing;
         }
 
-        Ok((player1_id, player2_id))
+        Ok(())
     }
 
     /// Assert that we're stopping at a valid point in the game
@@ -615,9 +712,14 @@ impl<'a> GameLoop<'a> {
         &mut self,
         choice_limit: usize,
         snapshot_path: P,
-        controller1: &dyn PlayerController,
-        controller2: &dyn PlayerController,
+        controllers: &[&mut dyn PlayerController],
     ) -> Result<GameResult> {
+        let [controller1, controller2] = controllers else {
+            return Err(MtgError::InvalidAction(
+                "Snapshots are only supported in two-player games".to_string(),
+            ));
+        };
+
         // Assert that we're stopping at a valid point (after a choice or game end)
         self.assert_valid_stopping_point();
 
@@ -632,11 +734,17 @@ impl<'a> GameLoop<'a> {
             result
         } else {
             // No ChangeTurn action found - we're still in turn 1!
-            // Extract all ChoicePoint actions from the undo log as intra-turn choices
+            // Extract all ChoicePoint actions from the undo log as intra-turn choices,
+            // except the mulligans: they were made during setup, which isn't rerun on resume
             let mut intra_turn_choices = Vec::new();
             for action in self.game.undo_log.actions() {
-                if let crate::undo::GameAction::ChoicePoint { .. } = action {
-                    intra_turn_choices.push(action.clone());
+                if let crate::undo::GameAction::ChoicePoint { choice, .. } = action {
+                    if !matches!(
+                        choice,
+                        Some(crate::game::ReplayChoice::Mulligan(_) | crate::game::ReplayChoice::Bottom(_))
+                    ) {
+                        intra_turn_choices.push(action.clone());
+                    }
                 }
             }
 
@@ -701,26 +809,15 @@ impl<'a> GameLoop<'a> {
         })
     }
 
-    /// Notify both controllers that the game has ended
+    /// Notify every controller that the game has ended
     ///
     /// Calls the `on_game_end` callback for each controller with their view
     /// of the game state and whether they won.
-    fn notify_game_end(
-        &self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-        player1_id: PlayerId,
-        player2_id: PlayerId,
-        winner_id: Option<PlayerId>,
-    ) {
-        controller1.on_game_end(
-            &GameStateView::new(self.game, player1_id),
-            winner_id == Some(player1_id),
-        );
-        controller2.on_game_end(
-            &GameStateView::new(self.game, player2_id),
-            winner_id == Some(player2_id),
-        );
+    fn notify_game_end(&self, controllers: &mut [&mut dyn PlayerController], winner_id: Option<PlayerId>) {
+        for controller in controllers.iter_mut() {
+            let player_id = controller.player_id();
+            controller.on_game_end(&GameStateView::new(self.game, player_id), winner_id == Some(player_id));
+        }
     }
 
     /// Run a single turn and check for game-ending conditions
@@ -736,6 +833,16 @@ impl<'a> GameLoop<'a> {
         &mut self,
         controller1: &mut dyn PlayerController,
         controller2: &mut dyn PlayerController,
+    ) -> Result<Option<GameResult>> {
+        self.run_multiplayer_turn_once(&mut [controller1, controller2])
+    }
+
+    /// Run a single turn with one controller per player, in turn order
+    ///
+    /// See `run_turn_once`.
+    pub fn run_multiplayer_turn_once(
+        &mut self,
+        controllers: &mut [&mut dyn PlayerController],
     ) -> Result<Option<GameResult>> {
         // Check win conditions before running the turn
         if let Some(result) = self.check_win_condition() {
@@ -752,7 +859,7 @@ impl<'a> GameLoop<'a> {
         }
 
         // Run the turn
-        if let Some(result) = self.run_turn(controller1, controller2)? {
+        if let Some(result) = self.run_turn(controllers)? {
             // Mid-turn snapshot triggered
             return Ok(Some(result));
         }
@@ -771,11 +878,7 @@ impl<'a> GameLoop<'a> {
     ///
     /// This is an internal method that executes one complete turn from untap through cleanup.
     /// For running one turn and checking end conditions, use `run_turn_once` instead.
-    fn run_turn(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
+    fn run_turn(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
         let active_player = self.game.turn.active_player;
 
         // Check if we're in the resumed turn (skip header) or a new turn (print header)
@@ -810,7 +913,7 @@ impl<'a> GameLoop<'a> {
         // Run through all steps of the turn
         loop {
             // Execute the step
-            if let Some(result) = self.execute_step(controller1, controller2)? {
+            if let Some(result) = self.execute_multiplayer_step(controllers)? {
                 // Mid-turn snapshot triggered (e.g., fixed controller exhausted)
                 return Ok(Some(result));
             }
@@ -902,6 +1005,10 @@ impl<'a> GameLoop<'a> {
             }
             println!("{}{}: ", player.name, marker);
             println!("  Life: {}", player.life);
+            for &(commander, damage) in &player.commander_damage {
+                let name = self.game.cards.get(commander).map_or("Unknown", |c| c.name.as_str());
+                println!("  Commander damage from {name}: {damage}");
+            }
 
             // Zone sizes
             if let Some(zones) = self.game.get_player_zones(player.id) {
@@ -913,6 +1020,14 @@ impl<'a> GameLoop<'a> {
                     zones.exile.len()
                 );
 
+                // Commanders waiting in the command zone, with the tax to cast them
+                for &card_id in &zones.command.cards {
+                    if let Ok(card) = self.game.cards.get(card_id) {
+                        let tax = self.game.commander_tax(card_id);
+                        println!("  Command zone: {} (commander tax {{{tax}}})", card.name);
+                    }
+                }
+
                 // Show hand contents for active player (whose turn it is)
                 if is_active && !zones.hand.is_empty() {
                     println!("  Hand contents:");
@@ -978,8 +1093,8 @@ impl<'a> GameLoop<'a> {
 
                     // Format card display based on type
                     if card.is_creature() {
-                        let power = card.power.unwrap_or(0) + card.power_bonus as i8;
-                        let toughness = card.toughness.unwrap_or(0) + card.toughness_bonus as i8;
+                        let power = card.current_power();
+                        let toughness = card.current_toughness();
                         println!(
                             "    {} ({}) - {}/{}{}{}",
                             card.name, card_id, power, toughness, tap_status, sickness_status
@@ -1045,7 +1160,7 @@ impl<'a> GameLoop<'a> {
         source_name: &str,
         source_id: CardId,
         effect: &crate::core::Effect,
-        _source_owner: PlayerId,
+        source_owner: PlayerId,
     ) {
         use crate::core::{Effect, TargetRef};
 
@@ -1065,8 +1180,8 @@ impl<'a> GameLoop<'a> {
                     println!("  {source_name} ({source_id}) deals {amount} damage to {target_name} ({target_card_id})");
                 }
                 TargetRef::None => {
-                    // Target will be filled in by resolve_spell - log against opponent
-                    if let Some(opponent_id) = self.game.players.iter().map(|p| p.id).find(|id| *id != _source_owner) {
+                    // Target will be filled in by resolve_spell - log against the same opponent
+                    if let Some(opponent_id) = self.game.untargeted_damage_recipient(source_owner) {
                         let target_name = self.get_player_name(opponent_id);
                         println!("  {source_name} ({source_id}) deals {amount} damage to {target_name}");
                     }
@@ -1139,6 +1254,14 @@ impl<'a> GameLoop<'a> {
                 let player_name = self.get_player_name(*player);
                 println!("  {source_name} ({source_id}) adds {mana} to {player_name}'s mana pool");
             }
+            Effect::AddManaOfChoice { player, colors, amount } => {
+                let player_name = self.get_player_name(*player);
+                let colors: Vec<String> = colors.iter().map(|c| c.to_string()).collect();
+                println!(
+                    "  {source_name} ({source_id}) adds {amount} mana of one of {} to {player_name}'s mana pool",
+                    colors.join("/")
+                );
+            }
             Effect::PutCounter {
                 target,
                 counter_type,
@@ -1178,23 +1301,68 @@ impl<'a> GameLoop<'a> {
                     .unwrap_or("Unknown");
                 println!("  {source_name} ({source_id}) exiles {target_name} ({target})");
             }
+            Effect::DestroyAll { selector, .. } => {
+                println!("  {source_name} ({source_id}) destroys all {selector}");
+            }
+            Effect::CreateToken { owner, amount, token } => {
+                let owner_name = self.get_player_name(*owner);
+                println!(
+                    "  {source_name} ({source_id}) creates {amount} {} for {owner_name}",
+                    token.name
+                );
+            }
+            Effect::PumpAll {
+                selector,
+                power_bonus,
+                toughness_bonus,
+                ..
+            } => {
+                println!(
+                    "  {source_name} ({source_id}) gives all {selector} {power_bonus:+}/{toughness_bonus:+} until end of turn"
+                );
+            }
+            Effect::CreateReplacementEffects { effects, .. } => {
+                for effect in effects {
+                    println!(
+                        "  {source_name} ({source_id}) creates an effect: {}",
+                        effect.description
+                    );
+                }
+            }
+            Effect::Attach { attachment, target } => {
+                let attachment = if attachment.as_u32() == 0 {
+                    source_id
+                } else {
+                    *attachment
+                };
+                let attachment_name = self
+                    .game
+                    .cards
+                    .get(attachment)
+                    .map(|c| c.name.as_str())
+                    .unwrap_or("Unknown");
+                let target_name = self
+                    .game
+                    .cards
+                    .get(*target)
+                    .map(|c| c.name.as_str())
+                    .unwrap_or("Unknown");
+                println!(
+                    "  {source_name} ({source_id}) attaches {attachment_name} ({attachment}) to {target_name} ({target})"
+                );
+            }
         }
     }
 
     /// Reset turn-based state for the active player
     fn reset_turn_state(&mut self, active_player: PlayerId) -> Result<()> {
         // Reset lands played this turn
-        if let Ok(player) = self.game.get_player_mut(active_player) {
-            player.reset_lands_played();
-        }
+        self.game.set_lands_played(active_player, 0)?;
 
         // Empty mana pools at start of turn
-        // Use fixed-size array instead of Vec allocation (MTG always has 2 players)
-        let player_ids: [PlayerId; 2] = [self.game.players[0].id, self.game.players[1].id];
+        let player_ids: SmallVec<[PlayerId; 4]> = self.game.players.iter().map(|p| p.id).collect();
         for player_id in player_ids {
-            if let Ok(player) = self.game.get_player_mut(player_id) {
-                player.mana_pool.clear();
-            }
+            self.game.empty_mana_pool(player_id)?;
         }
 
         Ok(())
@@ -1205,6 +1373,14 @@ impl<'a> GameLoop<'a> {
         &mut self,
         controller1: &mut dyn PlayerController,
         controller2: &mut dyn PlayerController,
+    ) -> Result<Option<GameResult>> {
+        self.execute_multiplayer_step(&mut [controller1, controller2])
+    }
+
+    /// Execute a single step with one controller per player, in turn order
+    pub fn execute_multiplayer_step(
+        &mut self,
+        controllers: &mut [&mut dyn PlayerController],
     ) -> Result<Option<GameResult>> {
         let step = self.game.turn.current_step;
 
@@ -1221,16 +1397,16 @@ impl<'a> GameLoop<'a> {
                 self.untap_step()?;
                 Ok(None)
             }
-            Step::Upkeep => self.upkeep_step(controller1, controller2),
-            Step::Draw => self.draw_step(controller1, controller2),
-            Step::Main1 | Step::Main2 => self.main_phase(controller1, controller2),
-            Step::BeginCombat => self.begin_combat_step(controller1, controller2),
-            Step::DeclareAttackers => self.declare_attackers_step(controller1, controller2),
-            Step::DeclareBlockers => self.declare_blockers_step(controller1, controller2),
-            Step::CombatDamage => self.combat_damage_step(controller1, controller2),
-            Step::EndCombat => self.end_combat_step(controller1, controller2),
-            Step::End => self.end_step(controller1, controller2),
-            Step::Cleanup => self.cleanup_step(controller1, controller2),
+            Step::Upkeep => self.upkeep_step(controllers),
+            Step::Draw => self.draw_step(controllers),
+            Step::Main1 | Step::Main2 => self.main_phase(controllers),
+            Step::BeginCombat => self.begin_combat_step(controllers),
+            Step::DeclareAttackers => self.declare_attackers_step(controllers),
+            Step::DeclareBlockers => self.declare_blockers_step(controllers),
+            Step::CombatDamage => self.combat_damage_step(controllers),
+            Step::EndCombat => self.end_combat_step(controllers),
+            Step::End => self.end_step(controllers),
+            Step::Cleanup => self.cleanup_step(controllers),
         }
     }
 
@@ -1258,13 +1434,16 @@ impl<'a> GameLoop<'a> {
         for card_id in cards_to_untap {
             if let Ok(card) = self.game.cards.get_mut(card_id) {
                 card.untap();
+                self.game
+                    .undo_log
+                    .log(crate::undo::GameAction::TapCard { card_id, tapped: false });
             }
         }
 
         Ok(())
     }
 
-    /// Check and execute phase-triggered abilities
+    /// Check phase-triggered abilities (they go on the stack before the next priority)
     fn check_phase_triggers(&mut self, trigger_event: crate::core::TriggerEvent) -> Result<()> {
         // Collect all permanents with triggers matching this event
         // Also collect trigger descriptions for logging
@@ -1293,7 +1472,7 @@ impl<'a> GameLoop<'a> {
             })
             .collect();
 
-        // For each card with a matching trigger, log and execute
+        // For each card with a matching trigger, log and queue it
         // Note: In the future, this will need to handle optional triggers, conditions, etc.
         for (card_id, descriptions) in triggered_info {
             // Log trigger activation if verbose
@@ -1303,7 +1482,7 @@ impl<'a> GameLoop<'a> {
                 }
             }
 
-            // Use the existing check_triggers method to execute effects
+            // Queue the card's triggered abilities; they go on the stack before the next priority
             // Pass the card_id as the source for filling in placeholders
             self.game.check_triggers(trigger_event, card_id)?;
         }
@@ -1312,31 +1491,24 @@ impl<'a> GameLoop<'a> {
     }
 
     /// Upkeep step - priority round for triggers and actions
-    fn upkeep_step(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
+    fn upkeep_step(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
         // Check for beginning of upkeep triggers
         self.check_phase_triggers(crate::core::TriggerEvent::BeginningOfUpkeep)?;
 
         // Pass priority
-        if let Some(result) = self.priority_round(controller1, controller2)? {
+        if let Some(result) = self.priority_round(controllers)? {
             return Ok(Some(result));
         }
         Ok(None)
     }
 
     /// Draw step - active player draws a card
-    fn draw_step(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
+    fn draw_step(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
         let active_player = self.game.turn.active_player;
 
-        // Skip draw on first turn (player going first doesn't draw)
-        if self.game.turn.turn_number == 1 {
+        // Skip draw on first turn (player going first doesn't draw, MTG Rules 103.8a);
+        // in multiplayer games everyone draws (103.8c)
+        if self.game.turn.turn_number == 1 && self.game.players.len() == 2 {
             self.log_normal("(First turn - no draw)");
             return Ok(None);
         }
@@ -1350,7 +1522,7 @@ impl<'a> GameLoop<'a> {
         }
 
         // Draw a card
-        self.game.draw_card(active_player)?;
+        self.with_replacement_choices(controllers, |game| game.draw_card(active_player))?;
 
         #[cfg(feature = "verbose-logging")]
         {
@@ -1374,7 +1546,7 @@ impl<'a> GameLoop<'a> {
         }
 
         // MTG Rules 504.2: After draw, players receive priority
-        if let Some(result) = self.priority_round(controller1, controller2)? {
+        if let Some(result) = self.priority_round(controllers)? {
             return Ok(Some(result));
         }
 
@@ -1382,42 +1554,26 @@ impl<'a> GameLoop<'a> {
     }
 
     /// Main phase - players can play spells and lands
-    fn main_phase(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
+    fn main_phase(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
         // Priority round where players can take actions
-        if let Some(result) = self.priority_round(controller1, controller2)? {
+        if let Some(result) = self.priority_round(controllers)? {
             return Ok(Some(result));
         }
         Ok(None)
     }
 
     /// Combat phases (simplified for now)
-    fn begin_combat_step(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
-        if let Some(result) = self.priority_round(controller1, controller2)? {
+    fn begin_combat_step(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
+        if let Some(result) = self.priority_round(controllers)? {
             return Ok(Some(result));
         }
         Ok(None)
     }
 
-    fn declare_attackers_step(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
+    fn declare_attackers_step(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
         // Active player declares attackers
         let active_player = self.game.turn.active_player;
-        let controller: &mut dyn PlayerController = if active_player == controller1.player_id() {
-            controller1
-        } else {
-            controller2
-        };
+        let controller = Self::controller_for(controllers, active_player)?;
 
         // Get available creatures that can attack
         let available_creatures = self.get_available_attacker_creatures(active_player);
@@ -1467,19 +1623,20 @@ impl<'a> GameLoop<'a> {
                 return Ok(Some(result));
             }
 
-            // Ask controller to choose all attackers at once (v2 interface)
+            // Ask controller to choose all attackers, and what each attacks, at once (v2 interface)
+            let defenders = self.game.attack_targets(active_player);
             let view = GameStateView::new(self.game, active_player);
-            let attackers = controller.choose_attackers(&view, &available_creatures);
+            let attackers = controller.choose_attackers(&view, &available_creatures, &defenders);
 
             // Log this choice point for snapshot/replay
             let replay_choice = crate::game::ReplayChoice::Attackers(attackers.clone());
             self.log_choice_point(active_player, Some(replay_choice));
 
             // Declare each chosen attacker
-            for attacker_id in attackers.iter() {
-                // Use GameState::declare_attacker() which taps the creature (MTG Rules 508.1f)
+            for (attacker_id, target) in attackers.iter() {
+                // Use GameState::declare_attacker_at() which taps the creature (MTG Rules 508.1f)
                 // NOT Combat::declare_attacker() which only adds to the attackers list
-                if let Err(e) = self.game.declare_attacker(active_player, *attacker_id) {
+                if let Err(e) = self.game.declare_attacker_at(active_player, *attacker_id, *target) {
                     if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
                         eprintln!("  Error declaring attacker: {e}");
                     }
@@ -1496,15 +1653,27 @@ impl<'a> GameLoop<'a> {
 
                     // Get power/toughness for more detail
                     if let Ok(card) = self.game.cards.get(*attacker_id) {
-                        let power = card.power.unwrap_or(0);
-                        let toughness = card.toughness.unwrap_or(0);
+                        let power = card.current_power();
+                        let toughness = card.current_toughness();
+                        let attacking = match target {
+                            AttackTarget::Planeswalker(planeswalker) => format!(
+                                " attacking {} ({planeswalker})",
+                                self.game
+                                    .cards
+                                    .get(*planeswalker)
+                                    .map(|c| c.name.as_str())
+                                    .unwrap_or("Unknown")
+                            ),
+                            AttackTarget::Player(_) => String::new(),
+                        };
                         println!(
-                            "  {} declares {} ({}) ({}/{}) as attacker",
+                            "  {} declares {} ({}) ({}/{}) as attacker{}",
                             self.get_player_name(active_player),
                             card_name,
                             attacker_id,
                             power,
-                            toughness
+                            toughness,
+                            attacking
                         );
                     }
                 }
@@ -1512,130 +1681,117 @@ impl<'a> GameLoop<'a> {
         }
 
         // MTG Rules 508.4: After attackers are declared, players receive priority
-        if let Some(result) = self.priority_round(controller1, controller2)? {
+        if let Some(result) = self.priority_round(controllers)? {
             return Ok(Some(result));
         }
 
         Ok(None)
     }
 
-    fn declare_blockers_step(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
-        // Defending player declares blockers
+    fn declare_blockers_step(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
+        // Each defending player declares blockers, in APNAP order (MTG Rules 509.1, 802.4)
         let active_player = self.game.turn.active_player;
-        let defending_player = self
-            .game
-            .get_other_player_id(active_player)
-            .expect("Should have defending player");
-
-        let controller: &mut dyn PlayerController = if defending_player == controller1.player_id() {
-            controller1
-        } else {
-            controller2
-        };
+        let defending_players = self.game.players_in_apnap_order();
+        for defending_player in defending_players.into_iter().filter(|&p| p != active_player) {
+            let controller = Self::controller_for(controllers, defending_player)?;
+
+            // Get available blockers and the attackers they can block: those
+            // attacking this player or a planeswalker they control
+            let available_blockers = self.get_available_blocker_creatures(defending_player);
+            let attackers = self.get_attackers_of(defending_player);
+
+            if !available_blockers.is_empty() && !attackers.is_empty() {
+                // Clear replay mode if all choices have been replayed
+                // This happens BEFORE checking stop conditions, so a snapshot taken here will NOT
+                // include the upcoming choice (which hasn't been presented yet)
+                //
+                // We stay in replay mode until BOTH conditions are met:
+                // 1. All intra-turn choices have been replayed (replay_choices_remaining == 0)
+                // 2. We've passed the baseline choice count from the snapshot
+                //
+                // This ensures that automatic actions (like draws) that happen before the first
+                // NEW choice point are properly suppressed, avoiding duplicate logging.
+                if self.replaying
+                    && self.replay_choices_remaining == 0
+                    && (self.choice_counter as usize) >= self.baseline_choice_count
+                {
+                    eprintln!(
+                        "🔍 [REPLAY_CLEAR_BLOCKERS] choice_counter={}, baseline={}, CLEARING replay mode",
+                        self.choice_counter, self.baseline_choice_count
+                    );
+                    self.replaying = false;
+                    if self.verbosity >= VerbosityLevel::Verbose {
+                        println!("✅ REPLAY MODE COMPLETE - will present blocker choice to controller");
+                    }
+                } else if self.replaying {
+                    eprintln!(
+                        "🔍 [REPLAY_STILL_ACTIVE_BLOCKERS] choice_counter={}, baseline={}, remaining={}",
+                        self.choice_counter, self.baseline_choice_count, self.replay_choices_remaining
+                    );
+                }
 
-        // Get available blockers and attackers
-        let available_blockers = self.get_available_blocker_creatures(defending_player);
-        let attackers = self.get_current_attackers();
+                // Create view and print prompt BEFORE checking stop conditions
+                // so users see what choice was about to be made when using --stop-when-fixed-exhausted
+                {
+                    let view = GameStateView::new(self.game, defending_player);
+                    // Print blocker selection prompt (controlled by show_choice_menu flag)
+                    if view.logger().should_show_choice_menu() {
+                        print!("{}", format_blockers_prompt(&view, &available_blockers, &attackers));
+                    }
+                } // Drop view before mutable borrow
 
-        if !available_blockers.is_empty() && !attackers.is_empty() {
-            // Clear replay mode if all choices have been replayed
-            // This happens BEFORE checking stop conditions, so a snapshot taken here will NOT
-            // include the upcoming choice (which hasn't been presented yet)
-            //
-            // We stay in replay mode until BOTH conditions are met:
-            // 1. All intra-turn choices have been replayed (replay_choices_remaining == 0)
-            // 2. We've passed the baseline choice count from the snapshot
-            //
-            // This ensures that automatic actions (like draws) that happen before the first
-            // NEW choice point are properly suppressed, avoiding duplicate logging.
-            if self.replaying
-                && self.replay_choices_remaining == 0
-                && (self.choice_counter as usize) >= self.baseline_choice_count
-            {
-                eprintln!(
-                    "🔍 [REPLAY_CLEAR_BLOCKERS] choice_counter={}, baseline={}, CLEARING replay mode",
-                    self.choice_counter, self.baseline_choice_count
-                );
-                self.replaying = false;
-                if self.verbosity >= VerbosityLevel::Verbose {
-                    println!("✅ REPLAY MODE COMPLETE - will present blocker choice to controller");
+                // PREAMBLE: Check stop conditions before asking for choice
+                if let Some(result) = self.check_stop_conditions(controller, defending_player)? {
+                    return Ok(Some(result));
                 }
-            } else if self.replaying {
-                eprintln!(
-                    "🔍 [REPLAY_STILL_ACTIVE_BLOCKERS] choice_counter={}, baseline={}, remaining={}",
-                    self.choice_counter, self.baseline_choice_count, self.replay_choices_remaining
-                );
-            }
 
-            // Create view and print prompt BEFORE checking stop conditions
-            // so users see what choice was about to be made when using --stop-when-fixed-exhausted
-            {
+                // Ask controller to choose all blocker assignments at once (v2 interface)
                 let view = GameStateView::new(self.game, defending_player);
-                // Print blocker selection prompt (controlled by show_choice_menu flag)
-                if view.logger().should_show_choice_menu() {
-                    print!("{}", format_blockers_prompt(&view, &available_blockers, &attackers));
-                }
-            } // Drop view before mutable borrow
-
-            // PREAMBLE: Check stop conditions before asking for choice
-            if let Some(result) = self.check_stop_conditions(controller, defending_player)? {
-                return Ok(Some(result));
-            }
-
-            // Ask controller to choose all blocker assignments at once (v2 interface)
-            let view = GameStateView::new(self.game, defending_player);
-            let blocks = controller.choose_blockers(&view, &available_blockers, &attackers);
+                let blocks = controller.choose_blockers(&view, &available_blockers, &attackers);
 
-            // Log this choice point for snapshot/replay
-            let replay_choice = crate::game::ReplayChoice::Blockers(blocks.clone());
-            self.log_choice_point(defending_player, Some(replay_choice));
+                // Log this choice point for snapshot/replay
+                let replay_choice = crate::game::ReplayChoice::Blockers(blocks.clone());
+                self.log_choice_point(defending_player, Some(replay_choice));
 
-            // Declare each blocking assignment
-            for (blocker_id, attacker_id) in blocks.iter() {
-                let mut attackers_vec = SmallVec::new();
-                attackers_vec.push(*attacker_id);
-                self.game.combat.declare_blocker(*blocker_id, attackers_vec);
+                // Declare each blocking assignment
+                for (blocker_id, attacker_id) in blocks.iter() {
+                    let mut attackers_vec = SmallVec::new();
+                    attackers_vec.push(*attacker_id);
+                    self.game.add_blocker(*blocker_id, attackers_vec);
 
-                if self.verbosity >= VerbosityLevel::Verbose && !self.replaying {
-                    let blocker_name = self
-                        .game
-                        .cards
-                        .get(*blocker_id)
-                        .map(|c| c.name.as_str())
-                        .unwrap_or("Unknown");
-                    let attacker_name = self
-                        .game
-                        .cards
-                        .get(*attacker_id)
-                        .map(|c| c.name.as_str())
-                        .unwrap_or("Unknown");
-                    println!(
-                        "  {} blocks {} with {}",
-                        self.get_player_name(defending_player),
-                        attacker_name,
-                        blocker_name
-                    );
+                    if self.verbosity >= VerbosityLevel::Verbose && !self.replaying {
+                        let blocker_name = self
+                            .game
+                            .cards
+                            .get(*blocker_id)
+                            .map(|c| c.name.as_str())
+                            .unwrap_or("Unknown");
+                        let attacker_name = self
+                            .game
+                            .cards
+                            .get(*attacker_id)
+                            .map(|c| c.name.as_str())
+                            .unwrap_or("Unknown");
+                        println!(
+                            "  {} blocks {} with {}",
+                            self.get_player_name(defending_player),
+                            attacker_name,
+                            blocker_name
+                        );
+                    }
                 }
             }
         }
 
         // MTG Rules 509.4: After blockers are declared, players receive priority
-        if let Some(result) = self.priority_round(controller1, controller2)? {
+        if let Some(result) = self.priority_round(controllers)? {
             return Ok(Some(result));
         }
 
         Ok(None)
     }
 
-    fn combat_damage_step(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
+    fn combat_damage_step(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
         // Check if any attacking or blocking creature has first strike or double strike
         // MTG Rules 510.4: If so, we have two combat damage steps
         let has_first_strike = self.has_first_strike_combat();
@@ -1646,8 +1802,8 @@ impl<'a> GameLoop<'a> {
                 println!("--- First Strike Combat Damage ---");
             }
             self.log_combat_damage(true)?;
-            self.game.assign_combat_damage(controller1, controller2, true)?;
-            if let Some(result) = self.priority_round(controller1, controller2)? {
+            self.deal_combat_damage(controllers, true)?;
+            if let Some(result) = self.priority_round(controllers)? {
                 return Ok(Some(result));
             }
         }
@@ -1657,10 +1813,10 @@ impl<'a> GameLoop<'a> {
             println!("--- Normal Combat Damage ---");
         }
         self.log_combat_damage(false)?;
-        self.game.assign_combat_damage(controller1, controller2, false)?;
+        self.deal_combat_damage(controllers, false)?;
 
         // After damage is dealt, players get priority
-        if let Some(result) = self.priority_round(controller1, controller2)? {
+        if let Some(result) = self.priority_round(controllers)? {
             return Ok(Some(result));
         }
         Ok(None)
@@ -1692,6 +1848,21 @@ impl<'a> GameLoop<'a> {
         false
     }
 
+    /// Deal the combat damage of one combat damage step
+    ///
+    /// Damage assignment orders are chosen first, so replacement effect choices
+    /// don't ask for them again (see `with_replacement_choices`).
+    fn deal_combat_damage(
+        &mut self,
+        controllers: &mut [&mut dyn PlayerController],
+        first_strike_step: bool,
+    ) -> Result<()> {
+        let damage_orders = self.game.choose_damage_assignment_orders(controllers)?;
+        self.with_replacement_choices(controllers, |game| {
+            game.deal_combat_damage(&damage_orders, first_strike_step)
+        })
+    }
+
     /// Log combat damage for debugging
     fn log_combat_damage(&self, first_strike_step: bool) -> Result<()> {
         if self.verbosity < VerbosityLevel::Normal || self.replaying {
@@ -1750,8 +1921,16 @@ impl<'a> GameLoop<'a> {
                     }
                 } else {
                     // Unblocked attacker
-                    if let Some(defending_player) = self.game.combat.get_defending_player(*attacker_id) {
-                        let defender_name = self.get_player_name(defending_player);
+                    if let Some(target) = self.game.combat.get_attack_target(*attacker_id) {
+                        let defender_name = match target {
+                            AttackTarget::Player(defending_player) => self.get_player_name(defending_player),
+                            AttackTarget::Planeswalker(planeswalker) => self
+                                .game
+                                .cards
+                                .get(planeswalker)
+                                .map(|c| format!("{} ({planeswalker})", c.name))
+                                .unwrap_or_else(|_| "Unknown".to_string()),
+                        };
                         if power > 0 {
                             println!("  {attacker_name} ({attacker_id}) deals {power} damage to {defender_name}");
                         }
@@ -1763,51 +1942,31 @@ impl<'a> GameLoop<'a> {
         Ok(())
     }
 
-    fn end_combat_step(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
+    fn end_combat_step(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
         // Clear combat state at end of combat
-        self.game.combat.clear();
+        self.game.end_combat();
 
         // Players get priority
-        if let Some(result) = self.priority_round(controller1, controller2)? {
+        if let Some(result) = self.priority_round(controllers)? {
             return Ok(Some(result));
         }
         Ok(None)
     }
 
-    fn end_step(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
+    fn end_step(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
         // Check for beginning of end step triggers
         self.check_phase_triggers(crate::core::TriggerEvent::BeginningOfEndStep)?;
 
-        if let Some(result) = self.priority_round(controller1, controller2)? {
+        if let Some(result) = self.priority_round(controllers)? {
             return Ok(Some(result));
         }
         Ok(None)
     }
 
     /// Cleanup step - discard to hand size, remove damage
-    fn cleanup_step(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
-        let active_player = self.game.turn.active_player;
-
-        // Get non-active player
-        let non_active_player = self
-            .game
-            .get_other_player_id(active_player)
-            .expect("Should have non-active player");
-
-        // Process active player first, then non-active player
-        for &player_id in &[active_player, non_active_player] {
+    fn cleanup_step(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
+        // Process players in APNAP order
+        for player_id in self.game.players_in_apnap_order() {
             let hand_size = self
                 .game
                 .get_player_zones(player_id)
@@ -1829,11 +1988,7 @@ impl<'a> GameLoop<'a> {
                 );
 
                 // Get the appropriate controller
-                let controller: &mut dyn PlayerController = if player_id == controller1.player_id() {
-                    controller1
-                } else {
-                    controller2
-                };
+                let controller = Self::controller_for(controllers, player_id)?;
 
                 // Create view and print prompt BEFORE checking stop conditions
                 // so users see what choice was about to be made when using --stop-when-fixed-exhausted
@@ -1880,12 +2035,14 @@ impl<'a> GameLoop<'a> {
                     }
 
                     // Use move_card to properly log the action for undo
-                    self.game.move_card(
-                        card_id,
-                        crate::zones::Zone::Hand,
-                        crate::zones::Zone::Graveyard,
-                        player_id,
-                    )?;
+                    self.with_replacement_choices(controllers, |game| {
+                        game.move_card(
+                            card_id,
+                            crate::zones::Zone::Hand,
+                            crate::zones::Zone::Graveyard,
+                            player_id,
+                        )
+                    })?;
 
                     log_if_verbose!(
                         self,
@@ -1903,13 +2060,15 @@ impl<'a> GameLoop<'a> {
         }
 
         // Empty mana pools
-        for &player_id in &[active_player, non_active_player] {
-            if let Ok(player) = self.game.get_player_mut(player_id) {
-                player.mana_pool.clear();
-            }
+        let player_ids: SmallVec<[PlayerId; 4]> = self.game.players.iter().map(|p| p.id).collect();
+        for player_id in player_ids {
+            self.game.empty_mana_pool(player_id)?;
         }
 
-        // TODO: Remove damage from creatures
+        // MTG Rules 514.2: Remove all damage marked on permanents, and end
+        // "until end of turn" effects
+        self.game.clear_all_damage()?;
+        self.game.end_replacement_effects();
 
         Ok(None)
     }
@@ -1918,32 +2077,40 @@ impl<'a> GameLoop<'a> {
     ///
     /// This removes the spell from the stack and executes its effects.
     /// Implements MTG Comprehensive Rules 608 (Resolving Spells and Abilities).
-    fn resolve_top_spell_from_stack(&mut self, spell_id: CardId) -> Result<()> {
-        // Look up the targets for this spell
-        let targets = self
-            .spell_targets
+    fn resolve_top_spell_from_stack(
+        &mut self,
+        controllers: &mut [&mut dyn PlayerController],
+        spell_id: CardId,
+    ) -> Result<()> {
+        // Look up the targets recorded on the spell when it was cast, and which of
+        // them are still legal (resolution skips the rest, MTG Rules 608.2b)
+        let targets = self.game.stack.spell_targets(spell_id).unwrap_or_default().to_vec();
+        let illegal_targets: Vec<CardId> = targets
             .iter()
-            .find(|(id, _)| *id == spell_id)
-            .map(|(_, t)| t.clone())
-            .unwrap_or_else(Vec::new);
+            .copied()
+            .filter(|&target_id| !self.game.is_legal_spell_target(spell_id, target_id))
+            .collect();
+        let countered = !targets.is_empty() && illegal_targets.len() == targets.len();
 
-        // Get card name and effects for logging (before resolution)
-        let (card_name, card_effects, card_owner) = if let Ok(card) = self.game.cards.get(spell_id) {
-            (card.name.to_string(), card.effects.clone(), card.owner)
+        // Get card name and effects of the chosen modes for logging (before resolution)
+        let (card_name, card_owner) = if let Ok(card) = self.game.cards.get(spell_id) {
+            (card.name.to_string(), card.owner)
         } else {
             return Err(crate::MtgError::EntityNotFound(spell_id.as_u32()));
         };
+        let card_effects = self.game.spell_effects(spell_id)?;
 
-        if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
+        // A countered spell is reported by resolve_spell itself
+        if self.verbosity >= VerbosityLevel::Normal && !self.replaying && !countered {
             println!("  {} ({}) resolves", card_name, spell_id);
         }
 
         // Resolve the spell (this modifies effects with target replacement)
-        self.game.resolve_spell(spell_id, &targets)?;
+        self.with_replacement_choices(controllers, |game| game.resolve_spell(spell_id))?;
 
         // Log effects for instants/sorceries
         // Note: We need to manually replace placeholder targets for logging
-        if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
+        if self.verbosity >= VerbosityLevel::Normal && !self.replaying && !countered {
             use crate::core::Effect;
             let mut target_index = 0;
             for effect in &card_effects {
@@ -1993,6 +2160,12 @@ impl<'a> GameLoop<'a> {
                     _ => effect.clone(),
                 };
 
+                if effect_to_log
+                    .target_card()
+                    .is_some_and(|t| illegal_targets.contains(&t))
+                {
+                    continue;
+                }
                 self.log_effect_execution(&card_name, spell_id, &effect_to_log, card_owner);
             }
 
@@ -2003,23 +2176,251 @@ impl<'a> GameLoop<'a> {
                         "  {} ({}) enters the battlefield as a {}/{} creature",
                         card_name,
                         spell_id,
-                        card.power.unwrap_or(0),
-                        card.toughness.unwrap_or(0)
+                        card.current_power(),
+                        card.current_toughness()
                     );
                 }
             }
         }
 
-        // Remove the spell from our targets tracking
-        self.spell_targets.retain(|(id, _)| *id != spell_id);
-
         Ok(())
     }
 
-    /// Priority round - players get chances to act until both pass
-    ///
-    /// This implements the priority system where players alternate making choices
-    /// until both pass in succession, then resolves spells from the stack.
+    /// Put pending triggered abilities on the stack (MTG Rules 603.3b)
+    ///
+    /// The active player puts all of theirs on the stack first, then the
+    /// non-active player (APNAP order), so the non-active player's triggers
+    /// resolve first. Each player chooses the relative order of their own triggers.
+    fn put_pending_triggers_on_stack(
+        &mut self,
+        controllers: &mut [&mut dyn PlayerController],
+    ) -> Result<Option<GameResult>> {
+        for player_id in self.game.players_in_apnap_order() {
+            if self.game.pending_triggers_for(player_id).is_empty() {
+                continue;
+            }
+
+            if let Some(result) = self.choose_trigger_targets(controllers, player_id)? {
+                return Ok(Some(result));
+            }
+            let pending = self.game.pending_triggers_for(player_id);
+            if pending.is_empty() {
+                continue;
+            }
+
+            let order: SmallVec<[usize; 4]> = if pending.len() == 1 {
+                smallvec::smallvec![0]
+            } else {
+                let controller = Self::controller_for(controllers, player_id)?;
+
+                // PREAMBLE: Check stop conditions before asking for choice
+                if let Some(result) = self.check_stop_conditions(controller, player_id)? {
+                    return Ok(Some(result));
+                }
+
+                let triggers: Vec<StackAbility> =
+                    pending.iter().map(|&i| self.game.pending_triggers[i].clone()).collect();
+                let view = GameStateView::new(self.game, player_id);
+                let order = controller.choose_trigger_order(&view, &triggers);
+
+                // Log this choice point for snapshot/replay
+                let replay_choice = crate::game::ReplayChoice::TriggerOrder(order.clone());
+                self.log_choice_point(player_id, Some(replay_choice));
+
+                order
+            };
+
+            self.game.put_triggers_on_stack(player_id, &order)?;
+        }
+
+        Ok(None)
+    }
+
+    /// Choose targets for a player's pending triggers (MTG Rules 603.3d)
+    ///
+    /// Targets are chosen as each triggered ability is put on the stack. One that
+    /// needs a target but has none is removed instead.
+    fn choose_trigger_targets(
+        &mut self,
+        controllers: &mut [&mut dyn PlayerController],
+        player_id: PlayerId,
+    ) -> Result<Option<GameResult>> {
+        let mut removed = 0;
+        for index in self.game.pending_triggers_for(player_id) {
+            let index = index - removed;
+            let ability = &self.game.pending_triggers[index];
+            if !ability.effects.iter().any(|effect| effect.needs_target()) {
+                continue;
+            }
+            let source = ability.source;
+            let valid_targets = self.game.get_valid_targets_for_trigger(ability);
+
+            if valid_targets.is_empty() {
+                let ability = self.game.remove_pending_trigger(index)?;
+                removed += 1;
+                if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
+                    println!("  {} is removed (no legal targets)", ability.description);
+                }
+                continue;
+            }
+
+            let target = if valid_targets.len() == 1 {
+                // Only one valid target - auto-select without calling controller
+                // This is not a choice, so don't log ChoicePoint
+                valid_targets[0]
+            } else {
+                let controller = Self::controller_for(controllers, player_id)?;
+
+                // PREAMBLE: Check stop conditions before asking for choice
+                if let Some(result) = self.check_stop_conditions(controller, player_id)? {
+                    return Ok(Some(result));
+                }
+
+                let view = GameStateView::new(self.game, player_id);
+                let chosen = controller.choose_targets(&view, source, &valid_targets);
+
+                // Log this choice point for snapshot/replay
+                let replay_choice = crate::game::ReplayChoice::Targets(chosen.clone());
+                self.log_choice_point(player_id, Some(replay_choice));
+
+                // A trigger's target is required, so an invalid answer gets the first one
+                chosen
+                    .first()
+                    .copied()
+                    .filter(|target| valid_targets.contains(target))
+                    .unwrap_or(valid_targets[0])
+            };
+            self.game.target_pending_trigger(index, &[target])?;
+        }
+        Ok(None)
+    }
+
+    /// Apply the legend rule, letting each player choose which legend to keep (MTG Rules 704.5j)
+    ///
+    /// Part of the state-based action check before a player receives priority.
+    /// Every player chooses before any legend leaves the battlefield.
+    fn apply_legend_rule(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
+        let mut kept = Vec::new();
+        for (player_id, legends) in self.game.legend_rule_groups() {
+            let controller = Self::controller_for(controllers, player_id)?;
+
+            // PREAMBLE: Check stop conditions before asking for choice
+            if let Some(result) = self.check_stop_conditions(controller, player_id)? {
+                return Ok(Some(result));
+            }
+
+            let view = GameStateView::new(self.game, player_id);
+            let chosen = controller.choose_legend_to_keep(&view, &legends);
+
+            // Log this choice point for snapshot/replay
+            let replay_choice = crate::game::ReplayChoice::LegendToKeep(chosen);
+            self.log_choice_point(player_id, Some(replay_choice));
+
+            // An invalid answer keeps the most recent arrival
+            let keep = if legends.contains(&chosen) {
+                chosen
+            } else {
+                legends[legends.len() - 1]
+            };
+            kept.push((keep, legends));
+        }
+
+        for (keep, legends) in kept {
+            self.with_replacement_choices(controllers, |game| game.apply_legend_rule(keep, &legends))?;
+        }
+        Ok(None)
+    }
+
+    /// Carry out an action, letting players choose among replacement effects (MTG Rules 616.1)
+    ///
+    /// When several replacement effects would modify the same event, the
+    /// affected player chooses which one applies first. Events happen inside
+    /// `GameState`, out of the controllers' reach, so the action runs until an
+    /// event needs a choice (see the `replacement` module). The action is then
+    /// undone, the player chooses, and the action runs again with the answer;
+    /// this repeats for each such event. The action must do the same thing
+    /// each time it runs, given the answers. Without an undo log, effects apply
+    /// in timestamp order.
+    fn with_replacement_choices<R>(
+        &mut self,
+        controllers: &mut [&mut dyn PlayerController],
+        mut action: impl FnMut(&mut GameState) -> Result<R>,
+    ) -> Result<R> {
+        if !self.game.undo_log.is_enabled() {
+            return action(self.game);
+        }
+
+        self.game.begin_replacement_choices();
+        let mut start = self.game.undo_log.len();
+        let result = loop {
+            let result = action(self.game);
+            let Some(choice) = self.game.take_replacement_choice() else {
+                break result;
+            };
+
+            // Undo the action, then ask before it runs again
+            let mut undone = Ok(());
+            while undone.is_ok() && self.game.undo_log.len() > start {
+                undone = self.game.undo().map(|_| ());
+            }
+            if let Err(e) = undone {
+                break Err(e);
+            }
+            let controller = match Self::controller_for(controllers, choice.player) {
+                Ok(controller) => controller,
+                Err(e) => break Err(e),
+            };
+            let view = GameStateView::new(self.game, choice.player);
+            let chosen = controller.choose_replacement_effect(&view, &choice.effects);
+            let chosen = if choice.effects.contains(&chosen) {
+                chosen
+            } else {
+                choice.effects[0]
+            };
+
+            // Log this choice point for snapshot/replay
+            let replay_choice = crate::game::ReplayChoice::ReplacementEffect(chosen);
+            self.log_choice_point(choice.player, Some(replay_choice));
+
+            start = self.game.undo_log.len();
+            self.game.answer_replacement_choice(chosen);
+        };
+        self.game.end_replacement_choices();
+        result
+    }
+
+    /// Resolve the triggered or activated ability on top of the stack
+    fn resolve_top_ability_from_stack(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<()> {
+        let kind = match self.game.stack.top() {
+            Some(StackObject::ActivatedAbility { .. }) => "ability",
+            _ => "trigger",
+        };
+        let ability = self.with_replacement_choices(controllers, |game| game.resolve_top_ability())?;
+
+        if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
+            let source_name = self
+                .game
+                .cards
+                .get(ability.source)
+                .map(|c| c.name.to_string())
+                .unwrap_or_else(|_| "Unknown".to_string());
+            println!(
+                "  {} ({}) {} resolves: {}",
+                source_name, ability.source, kind, ability.description
+            );
+            for effect in &ability.effects {
+                self.log_effect_execution(&source_name, ability.source, effect, ability.controller);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Priority round - players get chances to act until all of them pass
+    ///
+    /// This implements the priority system where players take turns making choices,
+    /// in turn order, until all players still in the game pass in succession, then
+    /// resolves spells from the stack.
     ///
     /// ## MTG Rules Implementation
     /// - Gets all available spell abilities (lands, spells, abilities)
@@ -2027,30 +2428,27 @@ impl<'a> GameLoop<'a> {
     /// - Handles the chosen ability appropriately:
     ///   - PlayLand: Resolves directly (no stack)
     ///   - CastSpell: Puts spell on stack (MTG Rules 601)
-    ///   - ActivateAbility: TODO - should go on stack for non-mana abilities
-    /// - When both players pass with spells on stack, resolves top spell (MTG Rules 117.4)
-    /// - Repeats until stack is empty and both players pass
-    fn priority_round(
-        &mut self,
-        controller1: &mut dyn PlayerController,
-        controller2: &mut dyn PlayerController,
-    ) -> Result<Option<GameResult>> {
+    ///   - ActivateAbility: Pays costs, then puts the ability on stack (MTG Rules 602.2)
+    /// - Before each priority, pending triggers are put on the stack (MTG Rules 117.5)
+    /// - When all players pass with objects on stack, resolves the top one (MTG Rules 117.4)
+    /// - Repeats until stack is empty and all players pass
+    fn priority_round(&mut self, controllers: &mut [&mut dyn PlayerController]) -> Result<Option<GameResult>> {
         let active_player = self.game.turn.active_player;
-        let non_active_player = self
-            .game
-            .get_other_player_id(active_player)
-            .expect("Should have non-active player");
 
         // Outer loop: resolve stack until empty
         loop {
-            // Active player gets priority first in each round
-            let mut current_priority = active_player;
+            // Active player gets priority first in each round (if they're still in the game)
+            let mut current_priority = if self.game.is_in_game(active_player) {
+                active_player
+            } else {
+                self.game.next_player_in_turn_order(active_player)
+            };
             let mut consecutive_passes = 0;
             let mut action_count = 0;
             const MAX_ACTIONS_PER_PRIORITY: usize = 1000;
 
-            // Inner loop: pass priority until both players pass
-            while consecutive_passes < 2 {
+            // Inner loop: pass priority until all players pass in succession
+            while consecutive_passes < self.game.players_in_game() {
                 // Safety check to prevent infinite loops
                 action_count += 1;
                 if action_count > MAX_ACTIONS_PER_PRIORITY {
@@ -2059,12 +2457,37 @@ impl<'a> GameLoop<'a> {
                     )));
                 }
 
+                // MTG Rules 117.5: state-based actions are checked before a player receives priority,
+                // then triggered abilities are put on the stack
+                let mut performed_sba =
+                    self.with_replacement_choices(controllers, |game| game.check_state_based_actions())?;
+                while !self.game.legend_rule_groups().is_empty() {
+                    if let Some(result) = self.apply_legend_rule(controllers)? {
+                        return Ok(Some(result));
+                    }
+                    // Losing legends can make other state-based actions apply (MTG Rules 704.3)
+                    self.with_replacement_choices(controllers, |game| game.check_state_based_actions())?;
+                    performed_sba = true;
+                }
+                if performed_sba {
+                    if let Some(result) = self.check_win_condition() {
+                        return Ok(Some(result));
+                    }
+                    // A player who just left the game doesn't get priority (MTG Rules 800.4a)
+                    if !self.game.is_in_game(current_priority) {
+                        current_priority = self.game.next_player_in_turn_order(current_priority);
+                    }
+                }
+                if !self.game.pending_triggers.is_empty() {
+                    if let Some(result) = self.put_pending_triggers_on_stack(controllers)? {
+                        return Ok(Some(result));
+                    }
+                    // The stack changed, so players must pass in succession again
+                    consecutive_passes = 0;
+                }
+
                 // Get the appropriate controller
-                let controller: &mut dyn PlayerController = if current_priority == controller1.player_id() {
-                    controller1
-                } else {
-                    controller2
-                };
+                let controller = Self::controller_for(controllers, current_priority)?;
 
                 // Get all available spell abilities for this player
                 let available = self.get_available_spell_abilities(current_priority);
@@ -2140,12 +2563,8 @@ impl<'a> GameLoop<'a> {
                         let view = GameStateView::new(self.game, current_priority);
                         controller.on_priority_passed(&view);
 
-                        // Switch priority to other player
-                        current_priority = if current_priority == active_player {
-                            non_active_player
-                        } else {
-                            active_player
-                        };
+                        // Pass priority to the next player in turn order
+                        current_priority = self.game.next_player_in_turn_order(current_priority);
                     }
                     Some(ability) => {
                         // Controller chose an ability to play
@@ -2169,7 +2588,9 @@ impl<'a> GameLoop<'a> {
                                 self.game.debug_log_state_hash(&play_msg);
 
                                 // Play land - resolves directly (no stack)
-                                if let Err(e) = self.game.play_land(current_priority, card_id) {
+                                if let Err(e) = self.with_replacement_choices(controllers, |game| {
+                                    game.play_land(current_priority, card_id)
+                                }) {
                                     if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
                                         eprintln!("  Error playing land: {e}");
                                     }
@@ -2238,17 +2659,130 @@ impl<'a> GameLoop<'a> {
                                     }
                                 }
 
-                                // Get valid targets BEFORE calling cast_spell_8_step
+                                // Make the step 2 choices BEFORE calling cast_spell_8_step
                                 // (we can't borrow controller inside the closure)
+                                let (mode_descriptions, min_modes, max_modes, has_x) =
+                                    match self.game.cards.get(card_id) {
+                                        Ok(card) => (
+                                            card.modes.iter().map(|m| m.description.clone()).collect::<Vec<_>>(),
+                                            card.min_modes,
+                                            card.max_modes,
+                                            card.mana_cost.x_count > 0,
+                                        ),
+                                        Err(_) => (Vec::new(), 1, 1, false),
+                                    };
+
+                                // Choose modes for modal spells (MTG Rules 601.2b)
+                                let modes: Vec<usize> = if mode_descriptions.is_empty() {
+                                    Vec::new()
+                                } else {
+                                    let view = GameStateView::new(self.game, current_priority);
+                                    let modes = controller.choose_modes(
+                                        &view,
+                                        card_id,
+                                        &mode_descriptions,
+                                        min_modes,
+                                        max_modes,
+                                    );
+
+                                    // Log this choice point for snapshot/replay
+                                    let replay_choice = crate::game::ReplayChoice::Modes(modes.clone());
+                                    self.log_choice_point(current_priority, Some(replay_choice));
+
+                                    modes.into_iter().collect()
+                                };
+
+                                // Choose between the mana cost and an alternative cost, if the
+                                // player can pay both (MTG Rules 601.2b, 118.9)
+                                let alternative_cost = match self.alternative_cost_options(current_priority, card_id) {
+                                    Some((cost, true)) => {
+                                        let view = GameStateView::new(self.game, current_priority);
+                                        let alternative = controller.choose_alternative_cost(&view, card_id, &cost);
+
+                                        // Log this choice point for snapshot/replay
+                                        let replay_choice = crate::game::ReplayChoice::AlternativeCost(alternative);
+                                        self.log_choice_point(current_priority, Some(replay_choice));
+                                        alternative
+                                    }
+                                    Some((_, false)) => true,
+                                    None => false,
+                                };
+
+                                // Choose X, up to what the player can pay for (MTG Rules 601.2b)
+                                let x_value = if has_x {
+                                    let max_x = self.max_x_value(current_priority, card_id);
+                                    let view = GameStateView::new(self.game, current_priority);
+                                    let x_value = controller.choose_x_value(&view, card_id, max_x).min(max_x);
+
+                                    // Log this choice point for snapshot/replay
+                                    let replay_choice = crate::game::ReplayChoice::XValue(x_value);
+                                    self.log_choice_point(current_priority, Some(replay_choice));
+
+                                    if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
+                                        println!("    X = {x_value}");
+                                    }
+                                    x_value
+                                } else {
+                                    0
+                                };
+
+                                // Get valid targets for the chosen modes
                                 let valid_targets = self
                                     .game
-                                    .get_valid_targets_for_spell(card_id)
+                                    .get_valid_targets_for_modes(card_id, &modes)
                                     .unwrap_or_else(|_| SmallVec::new());
 
+                                // How many targets to choose, and the amount to divide among them, if any
+                                // (an even division is no choice, the spell makes it when cast)
+                                let (min_targets, max_targets, divided_amount) = match self.game.cards.get(card_id) {
+                                    Ok(card) if card.divided_evenly => {
+                                        let (min_targets, max_targets) = card.target_count(x_value);
+                                        (min_targets, max_targets, None)
+                                    }
+                                    Ok(card) => {
+                                        let divided_amount = card.divided_amount(&modes, x_value);
+                                        let (min_targets, max_targets) = card.target_count(x_value);
+                                        // Each target of a divided spell gets at least 1 (MTG Rules 601.2d)
+                                        let max_targets = divided_amount
+                                            .map_or(max_targets, |total| max_targets.min(total.min(255) as u8));
+                                        (min_targets, max_targets, divided_amount)
+                                    }
+                                    Err(_) => (1, 1, None),
+                                };
+                                // Targets that cost more (Fireball) are limited to what's left after X
+                                let max_targets =
+                                    max_targets.min(self.max_affordable_targets(current_priority, card_id, x_value));
+
                                 // Ask controller to choose targets (only if there are valid targets)
-                                let chosen_targets_vec: Vec<CardId> = if valid_targets.is_empty() {
-                                    // No targets needed - spell has no targeting effects
+                                let chosen_targets_vec: Vec<CardId> = if valid_targets.is_empty() || max_targets == 0 {
+                                    // No targets needed - spell has no targeting effects (or X is 0)
                                     Vec::new()
+                                } else if max_targets > 1 {
+                                    // Several targets (TargetMax$) - ask for them one at a time,
+                                    // until the controller stops or there are no more
+                                    let mut chosen: Vec<CardId> = Vec::new();
+                                    while chosen.len() < max_targets as usize {
+                                        let remaining: SmallVec<[CardId; 8]> =
+                                            valid_targets.iter().copied().filter(|t| !chosen.contains(t)).collect();
+                                        if remaining.is_empty() {
+                                            break;
+                                        }
+                                        let view = GameStateView::new(self.game, current_priority);
+                                        let picked = controller.choose_targets(&view, card_id, &remaining);
+
+                                        // Log this choice point for snapshot/replay
+                                        let replay_choice = crate::game::ReplayChoice::Targets(picked.clone());
+                                        self.log_choice_point(current_priority, Some(replay_choice));
+
+                                        // Targets up to TargetMin$ are required
+                                        let target = match picked.first().copied().filter(|t| remaining.contains(t)) {
+                                            Some(target) => target,
+                                            None if chosen.len() < min_targets as usize => remaining[0],
+                                            None => break,
+                                        };
+                                        chosen.push(target);
+                                    }
+                                    chosen
                                 } else if valid_targets.len() == 1 {
                                     // Only one valid target - auto-select without calling controller
                                     // This is not a choice, so don't log ChoicePoint
@@ -2266,79 +2800,110 @@ impl<'a> GameLoop<'a> {
                                     chosen_targets.into_iter().collect()
                                 };
 
+                                // Divide the amount among several targets (MTG Rules 601.2d)
+                                let division: Vec<u32> = match divided_amount {
+                                    Some(total) if chosen_targets_vec.len() > 1 => {
+                                        let view = GameStateView::new(self.game, current_priority);
+                                        let division =
+                                            controller.choose_division(&view, card_id, &chosen_targets_vec, total);
+
+                                        // Log this choice point for snapshot/replay
+                                        let replay_choice = crate::game::ReplayChoice::Division(division.clone());
+                                        self.log_choice_point(current_priority, Some(replay_choice));
+
+                                        division.into_iter().collect()
+                                    }
+                                    _ => Vec::new(),
+                                };
+
+                                // Choose cards to sacrifice, discard, etc. for an alternative cost
+                                // (MTG Rules 601.2h)
+                                let mut cost_choices: Vec<SmallVec<[CardId; 8]>> = Vec::new();
+                                let paid_cost = self
+                                    .game
+                                    .cards
+                                    .get(card_id)
+                                    .ok()
+                                    .and_then(|card| card.alternative_cost.clone())
+                                    .filter(|_| alternative_cost);
+                                for part in paid_cost.iter().flat_map(|cost| cost.parts()) {
+                                    let Some((candidates, count)) =
+                                        self.game.cost_choice(current_priority, card_id, part)
+                                    else {
+                                        continue;
+                                    };
+                                    let view = GameStateView::new(self.game, current_priority);
+                                    let chosen =
+                                        controller.choose_cards_for_cost(&view, card_id, part, &candidates, count);
+
+                                    // Log this choice point for snapshot/replay
+                                    let replay_choice = crate::game::ReplayChoice::CostCards(chosen.clone());
+                                    self.log_choice_point(current_priority, Some(replay_choice));
+
+                                    cost_choices.push(chosen);
+                                }
+
                                 // Clone for closure (which will move it)
                                 let targets_for_callback = chosen_targets_vec.clone();
 
-                                // Create callbacks for targeting and mana payment
+                                // Create callbacks for choices, targeting, division and mana payment
+                                let choices_callback = move |_game: &GameState, _spell_id: CardId| SpellChoices {
+                                    modes: modes.clone(),
+                                    x_value,
+                                    alternative_cost,
+                                    ..Default::default()
+                                };
+
                                 let targeting_callback = move |_game: &GameState, _spell_id: CardId| {
                                     // Return the pre-selected targets
                                     targets_for_callback.clone()
                                 };
 
+                                let division_callback =
+                                    move |_game: &GameState, _spell_id: CardId, _targets: &[CardId], _total: u32| {
+                                        division.clone()
+                                    };
+
                                 let mana_callback = |game: &GameState, cost: &crate::core::ManaCost| {
-                                    // Use ManaEngine to compute proper color-aware tap order
+                                    // Use ManaEngine to compute proper color-aware tap order over every
+                                    // permanent with a mana ability, allowing Phyrexian symbols to be
+                                    // paid with life the player can afford
                                     use crate::game::mana_engine::ManaEngine;
 
                                     let mut mana_engine = ManaEngine::new(current_priority);
                                     mana_engine.update(game);
-
-                                    // The mana engine already knows which sources to tap
-                                    // It uses the GreedyManaResolver internally to compute tap order
-                                    // TODO: Extract tap order from mana_engine instead of computing separately
-
-                                    // For now, use the same logic that get_castable_spells uses:
-                                    // Build ManaSource list and use GreedyManaResolver
-                                    use crate::game::mana_payment::{
-                                        GreedyManaResolver, ManaPaymentResolver, ManaSource,
-                                    };
-
-                                    let mut mana_sources = Vec::new();
-                                    for &card_id in &game.battlefield.cards {
-                                        if let Ok(card) = game.cards.get(card_id) {
-                                            if card.owner == current_priority && card.is_land() && !card.tapped {
-                                                // Determine mana production for this land
-                                                let production = if let Some(prod) = Self::get_mana_production(card) {
-                                                    prod
-                                                } else {
-                                                    continue; // Skip lands we don't know how to tap yet
-                                                };
-
-                                                mana_sources.push(ManaSource {
-                                                    card_id,
-                                                    production,
-                                                    is_tapped: card.tapped,
-                                                    has_summoning_sickness: false, // Lands don't have summoning sickness
-                                                });
-                                            }
-                                        }
-                                    }
-
-                                    // Use GreedyManaResolver to compute proper tap order
-                                    let resolver = GreedyManaResolver::new();
-                                    resolver.compute_tap_order(cost, &mana_sources).unwrap_or_else(Vec::new)
+                                    mana_engine.compute_tap_order(cost).unwrap_or_default()
                                 };
 
                                 // Cast using 8-step process
-                                if let Err(e) = self.game.cast_spell_8_step(
-                                    current_priority,
-                                    card_id,
-                                    targeting_callback,
-                                    mana_callback,
-                                ) {
+                                if let Err(e) = self.with_replacement_choices(controllers, |game| {
+                                    let mut cost_choices = cost_choices.iter().cloned();
+                                    game.cast_spell_8_step_with_cost_choices(
+                                        current_priority,
+                                        card_id,
+                                        &choices_callback,
+                                        &targeting_callback,
+                                        &division_callback,
+                                        &mana_callback,
+                                        |_, _, candidates, count| {
+                                            cost_choices
+                                                .next()
+                                                .unwrap_or_else(|| candidates.iter().take(count).copied().collect())
+                                        },
+                                    )
+                                }) {
                                     if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
                                         eprintln!("  Error casting spell: {e}");
                                     }
                                 } else {
-                                    // Store targets for this spell (will be used when it resolves)
-                                    self.spell_targets.push((card_id, chosen_targets_vec));
-
-                                    // Spell is now on the stack - it will resolve later
-                                    // when both players pass priority
+                                    // Spell is now on the stack with its targets - it will
+                                    // resolve later when both players pass priority
                                 }
                             }
                             crate::core::SpellAbility::ActivateAbility { card_id, ability_index } => {
-                                // Activate ability from a permanent
-                                // TODO(mtg-70): This should go on the stack for non-mana abilities
+                                // Activate ability from a permanent (MTG Rules 602.2)
+                                // Only mana abilities that can't be tapped for mana while paying
+                                // costs (like Ashnod's Altar's) are offered here; they resolve at once
 
                                 // Get the card and ability
                                 let card_name = self.game.cards.get(card_id).ok().map(|c| c.name.clone());
@@ -2382,19 +2947,53 @@ impl<'a> GameLoop<'a> {
                                         chosen_targets.into_iter().collect()
                                     };
 
+                                    // Choose cards to sacrifice, discard, etc. (MTG Rules 602.2b, 601.2h)
+                                    let mut cost_choices: Vec<SmallVec<[CardId; 8]>> = Vec::new();
+                                    for part in ability.cost.parts() {
+                                        let Some((candidates, count)) =
+                                            self.game.cost_choice(current_priority, card_id, part)
+                                        else {
+                                            continue;
+                                        };
+                                        let view = GameStateView::new(self.game, current_priority);
+                                        let chosen =
+                                            controller.choose_cards_for_cost(&view, card_id, part, &candidates, count);
+
+                                        // Log this choice point for snapshot/replay
+                                        let replay_choice = crate::game::ReplayChoice::CostCards(chosen.clone());
+                                        self.log_choice_point(current_priority, Some(replay_choice));
+
+                                        cost_choices.push(chosen);
+                                    }
+
                                     // Pay costs
-                                    if let Err(e) = self.game.pay_ability_cost(current_priority, card_id, &ability.cost)
-                                    {
+                                    if let Err(e) = self.with_replacement_choices(controllers, |game| {
+                                        let mut cost_choices = cost_choices.iter().cloned();
+                                        game.pay_ability_cost_with_choices(
+                                            current_priority,
+                                            card_id,
+                                            &ability.cost,
+                                            &mut |_, _, candidates, count| {
+                                                cost_choices
+                                                    .next()
+                                                    .unwrap_or_else(|| candidates.iter().take(count).copied().collect())
+                                            },
+                                        )
+                                    }) {
                                         if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
                                             eprintln!("    Failed to pay cost: {e}");
                                         }
                                         continue;
                                     }
 
-                                    // Execute effects immediately (not on the stack)
-                                    // TODO(mtg-70): Put non-mana abilities on the stack
+                                    if ability.is_loyalty_ability() {
+                                        self.game.record_loyalty_activation(card_id)?;
+                                    }
+
+                                    // Fix placeholder player IDs and targets now, so the ability
+                                    // resolves the same way even if its source leaves play
+                                    let mut fixed_effects = Vec::with_capacity(ability.effects.len());
                                     for effect in &ability.effects {
-                                        // Fix placeholder player IDs and targets for effects
                                         let fixed_effect = match effect {
                                             crate::core::Effect::AddMana { player, mana } if player.as_u32() == 0 => {
                                                 // Replace placeholder with current player
@@ -2403,6 +3002,16 @@ impl<'a> GameLoop<'a> {
                                                     mana: *mana,
                                                 }
                                             }
+                                            crate::core::Effect::AddManaOfChoice { player, colors, amount }
+                                                if player.as_u32() == 0 =>
+                                            {
+                                                // Replace placeholder with current player
+                                                crate::core::Effect::AddManaOfChoice {
+                                                    player: current_priority,
+                                                    colors: colors.clone(),
+                                                    amount: *amount,
+                                                }
+                                            }
                                             crate::core::Effect::GainLife { player, amount }
                                                 if player.as_u32() == 0 =>
                                             {
@@ -2421,6 +3030,25 @@ impl<'a> GameLoop<'a> {
                                                     count: *count,
                                                 }
                                             }
+                                            crate::core::Effect::CreateToken { owner, amount, token }
+                                                if owner.as_u32() == 0 =>
+                                            {
+                                                // Replace placeholder with current player
+                                                crate::core::Effect::CreateToken {
+                                                    owner: current_priority,
+                                                    amount: *amount,
+                                                    token: token.clone(),
+                                                }
+                                            }
+                                            crate::core::Effect::CreateReplacementEffects { controller, effects }
+                                                if controller.as_u32() == 0 =>
+                                            {
+                                                // Replace placeholder with current player
+                                                crate::core::Effect::CreateReplacementEffects {
+                                                    controller: current_priority,
+                                                    effects: effects.clone(),
+                                                }
+                                            }
                                             // Replace placeholder targets with chosen targets
                                             crate::core::Effect::DestroyPermanent { target }
                                                 if target.as_u32() == 0 && !chosen_targets_vec.is_empty() =>
@@ -2476,47 +3104,101 @@ impl<'a> GameLoop<'a> {
                                                     amount: *amount,
                                                 }
                                             }
+                                            crate::core::Effect::Attach { attachment, target }
+                                                if target.as_u32() == 0 && !chosen_targets_vec.is_empty() =>
+                                            {
+                                                // Placeholder attachment means the source (equip)
+                                                crate::core::Effect::Attach {
+                                                    attachment: if attachment.as_u32() == 0 {
+                                                        card_id
+                                                    } else {
+                                                        *attachment
+                                                    },
+                                                    target: chosen_targets_vec[0],
+                                                }
+                                            }
+                                            crate::core::Effect::DealDamage {
+                                                target: crate::core::TargetRef::None,
+                                                amount,
+                                            } if !chosen_targets_vec.is_empty() => crate::core::Effect::DealDamage {
+                                                target: crate::core::TargetRef::Permanent(chosen_targets_vec[0]),
+                                                amount: *amount,
+                                            },
                                             _ => effect.clone(),
                                         };
+                                        fixed_effects.push(fixed_effect);
+                                    }
 
-                                        if let Err(e) = self.game.execute_effect(&fixed_effect) {
-                                            if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
-                                                eprintln!("    Failed to execute effect: {e}");
-                                            }
+                                    // Mana abilities don't use the stack (MTG Rules 605.3a)
+                                    if ability.is_mana_ability {
+                                        let controller = Self::controller_for(controllers, current_priority)?;
+                                        for effect in fixed_effects {
+                                            // The player chooses the color of "any color" mana as it's added
+                                            let effect = match effect {
+                                                crate::core::Effect::AddManaOfChoice { player, colors, amount }
+                                                    if colors.len() > 1 =>
+                                                {
+                                                    let view = GameStateView::new(self.game, current_priority);
+                                                    let chosen = controller.choose_mana_color(&view, card_id, &colors);
+
+                                                    // Log this choice point for snapshot/replay
+                                                    let replay_choice = crate::game::ReplayChoice::ManaColor(chosen);
+                                                    self.log_choice_point(current_priority, Some(replay_choice));
+
+                                                    let color =
+                                                        if colors.contains(&chosen) { chosen } else { colors[0] };
+                                                    crate::core::Effect::AddManaOfChoice {
+                                                        player,
+                                                        colors: vec![color],
+                                                        amount,
+                                                    }
+                                                }
+                                                effect => effect,
+                                            };
+                                            self.game.execute_effect(&effect)?;
                                         }
+                                        continue;
                                     }
+
+                                    self.game.put_ability_on_stack(
+                                        StackAbility {
+                                            source: card_id,
+                                            controller: current_priority,
+                                            effects: fixed_effects,
+                                            targets: chosen_targets_vec,
+                                            description: ability.description.clone(),
+                                            target_restriction: ability.target_restriction.clone(),
+                                        },
+                                        ability_index,
+                                    );
                                 } else if self.verbosity >= VerbosityLevel::Normal && !self.replaying {
                                     eprintln!("  Ability not found");
                                 }
                             }
                         }
 
-                        // After taking an action, switch priority to other player
-                        current_priority = if current_priority == active_player {
-                            non_active_player
-                        } else {
-                            active_player
-                        };
+                        // After taking an action, priority moves to the next player in turn order
+                        current_priority = self.game.next_player_in_turn_order(current_priority);
                     }
                 }
             }
 
-            // Both players passed priority
+            // All players passed priority
             // Check if there are spells on the stack to resolve
             if self.game.stack.is_empty() {
                 // Stack is empty, priority round is complete
                 break;
             }
 
-            // Resolve the top spell from the stack (MTG Rules 608: Resolving Spells and Abilities)
+            // Resolve the top object of the stack (MTG Rules 608: Resolving Spells and Abilities)
             // In MTG, the stack is LIFO (Last In, First Out)
-            if let Some(&spell_id) = self.game.stack.cards.last() {
-                self.resolve_top_spell_from_stack(spell_id)?;
-                // After resolving a spell, players get priority again
-                // Loop continues to give priority
-            } else {
-                // Stack was reported non-empty but has no cards (shouldn't happen)
-                break;
+            // After resolving, players get priority again - the loop continues
+            match self.game.stack.top() {
+                Some(&StackObject::Spell { card_id, .. }) => self.resolve_top_spell_from_stack(controllers, card_id)?,
+                Some(StackObject::TriggeredAbility(_) | StackObject::ActivatedAbility { .. }) => {
+                    self.resolve_top_ability_from_stack(controllers)?
+                }
+                None => break,
             }
         }
 
@@ -2613,10 +3295,14 @@ impl<'a> GameLoop<'a> {
             }
         }
 
-        // Can tap lands for mana
+        // Can tap permanents with mana abilities for mana
         for &card_id in &self.game.battlefield.cards {
             if let Ok(card) = self.game.cards.get(card_id) {
-                if card.owner == player_id && card.is_land() && !card.tapped {
+                if card.controller == player_id
+                    && !card.tapped
+                    && !crate::game::mana_payment::mana_productions(card).is_empty()
+                    && !self.game.has_summoning_sickness(card_id)
+                {
                     actions.push(PlayerAction::TapForMana(card_id));
                 }
             }
@@ -2624,13 +3310,20 @@ impl<'a> GameLoop<'a> {
 
         // Can cast spells from hand
         if let Some(zones) = self.game.get_player_zones(player_id) {
-            for &card_id in &zones.hand.cards {
+            // Cards in hand, and commanders in the command zone (MTG Rules 903.8)
+            let commanders = zones
+                .command
+                .cards
+                .iter()
+                .filter(|&&card_id| self.game.can_cast_from_command_zone(player_id, card_id));
+            for &card_id in zones.hand.cards.iter().chain(commanders) {
                 if let Ok(card) = self.game.cards.get(card_id) {
                     // Check if card is castable (not a land)
                     if !card.is_land() {
-                        // Check if player has enough mana
+                        // Check if player has enough mana (Phyrexian symbols may be paid with life)
                         if let Ok(player) = self.game.get_player(player_id) {
-                            if player.mana_pool.can_pay(&card.mana_cost) {
+                            let max_life_payments = self.game.max_life_payments(player_id);
+                            if player.mana_pool.can_pay_with_life(&card.mana_cost, max_life_payments) {
                                 actions.push(PlayerAction::CastSpell {
                                     card_id,
                                     targets: vec![],
@@ -2704,9 +3397,15 @@ impl<'a> GameLoop<'a> {
         creatures
     }
 
-    /// Get currently attacking creatures (v2 interface)
-    fn get_current_attackers(&self) -> Vec<CardId> {
-        self.game.combat.get_attackers()
+    /// Get the attackers attacking a player or a planeswalker they control
+    fn get_attackers_of(&self, defending_player: PlayerId) -> Vec<CardId> {
+        self.game
+            .combat
+            .attackers
+            .iter()
+            .filter(|&(_, &defender)| defender == defending_player)
+            .map(|(&attacker, _)| attacker)
+            .collect()
     }
 
     /// Get lands in player's hand (v2 interface)
@@ -2764,21 +3463,19 @@ impl<'a> GameLoop<'a> {
                         };
 
                         if can_cast_now {
-                            // Check if we can pay for this spell's mana cost
-                            if mana_engine.can_pay(&card.mana_cost) {
+                            // Check if we can pay for this spell's mana cost, with commander tax,
+                            // or its alternative cost
+                            let mana_cost = self.game.casting_cost(card_id).unwrap_or(card.mana_cost);
+                            if mana_engine.can_pay(&mana_cost)
+                                || self.alternative_cost_options(player_id, card_id).is_some()
+                            {
                                 // For Aura spells, check if there are valid targets
                                 // MTG Rule 303.4a: You can only cast an Aura spell if there's a legal object or player it could enchant
                                 if card.is_aura() {
                                     // Check if there are valid enchantment targets on the battlefield
                                     let has_valid_targets = self.game.battlefield.cards.iter().any(|&target_id| {
-                                        if let Ok(target_card) = self.game.cards.get(target_id) {
-                                            // Paralyze enchants creatures, so check for creatures
-                                            // TODO: Parse enchant restrictions from card data (e.g., "Enchant creature")
-                                            // For now, assume Auras enchant creatures
-                                            target_card.is_creature()
-                                        } else {
-                                            false
-                                        }
+                                        self.game.can_attach(card_id, target_id)
+                                            && self.game.is_legal_target(player_id, card_id, target_id)
                                     });
 
                                     if has_valid_targets {
@@ -2806,6 +3503,7 @@ impl<'a> GameLoop<'a> {
     /// Get activatable abilities on player's permanents (v2 interface)
     fn get_activatable_abilities(&self, player_id: PlayerId) -> Vec<(CardId, usize)> {
         use crate::game::mana_engine::ManaEngine;
+        use crate::game::mana_payment::ManaProduction;
 
         let mut abilities = Vec::new();
 
@@ -2823,18 +3521,14 @@ impl<'a> GameLoop<'a> {
 
                 // Check each activated ability on this card
                 for (ability_index, ability) in card.activated_abilities.iter().enumerate() {
-                    // Skip mana abilities for now (they'll be handled specially)
-                    if ability.is_mana_ability {
+                    // Mana abilities of mana sources are activated while paying costs;
+                    // others (like Ashnod's Altar's) are offered here
+                    if ability.is_mana_ability && ManaProduction::from_ability(ability).is_some() {
                         continue;
                     }
 
-                    // Check if cost can be paid
-                    let mut can_activate = true;
-
-                    // Check tap cost
-                    if ability.cost.includes_tap() && card.tapped {
-                        can_activate = false;
-                    }
+                    // Check tap, sacrifice, discard and other non-mana costs
+                    let mut can_activate = self.game.can_pay_ability_cost(player_id, card_id, &ability.cost);
 
                     // Check mana cost
                     if let Some(mana_cost) = ability.cost.get_mana_cost() {
@@ -2855,8 +3549,26 @@ impl<'a> GameLoop<'a> {
                         }
                     }
 
-                    // TODO: Check other cost types (sacrifice, discard, etc.)
-                    // TODO: Check timing restrictions (sorcery speed abilities)
+                    // Loyalty abilities: only one per planeswalker each turn (MTG Rules 606.3),
+                    // and a negative cost can't remove more loyalty than it has (606.6)
+                    if let Some(change) = ability.cost.loyalty_change() {
+                        if self.game.loyalty_ability_activated_this_turn(card_id)
+                            || (card.current_loyalty() as i32) + change < 0
+                        {
+                            can_activate = false;
+                        }
+                    }
+
+                    // Sorcery-speed abilities (like equip and loyalty abilities) follow the
+                    // same timing as sorceries (MTG Rules 602.5d, 606.3)
+                    if (ability.sorcery_speed || ability.is_loyalty_ability())
+                        && !(self.game.turn.active_player == player_id
+                            && self.game.turn.current_step.is_sorcery_speed()
+                            && self.game.stack.is_empty())
+                    {
+                        can_activate = false;
+                    }
+
                     // TODO: Check activation limits
 
                     // TODO(mtg-70): Check if ability has valid targets
@@ -2875,7 +3587,8 @@ impl<'a> GameLoop<'a> {
                         //
                         // We need to distinguish between these cases.
                         // For now, check if the ability description contains "target"
-                        let requires_targets = ability.description.to_lowercase().contains("target");
+                        let requires_targets = ability.target_restriction.is_some()
+                            || ability.description.to_lowercase().contains("target");
 
                         if requires_targets && valid_targets.is_empty() {
                             // Ability requires targets but none are available
@@ -2988,7 +3701,7 @@ impl<'a> GameLoop<'a> {
 
                 // Immediately resolve spell (simplified - no stack interaction yet)
                 // Legacy v1 path - no targets chosen, rely on auto-targeting
-                self.game.resolve_spell(*card_id, &[])?;
+                self.game.resolve_spell(*card_id)?;
 
                 log_if_verbose!(
                     self,
@@ -3077,104 +3790,134 @@ impl<'a> GameLoop<'a> {
 
     /// Check if the game has reached a win condition
     fn check_win_condition(&self) -> Option<GameResult> {
-        // Check for player death (life <= 0)
-        for player in &self.game.players {
-            if player.life <= 0 {
-                let winner = self.game.get_other_player_id(player.id);
-                return Some(GameResult {
-                    winner,
-                    turns_played: self.turns_elapsed,
-                    end_reason: GameEndReason::PlayerDeath(player.id),
-                });
-            }
-        }
+        // A player has lost if state-based actions said so, or will as soon as they're checked
+        let has_lost = |player: &crate::core::Player| {
+            player.has_lost
+                || player.life <= 0
+                || player.drew_from_empty_library
+                || player.poison_counters >= 10
+                || self.game.has_lethal_commander_damage(player.id)
+        };
 
-        // Check for decking (empty library when trying to draw)
-        for player in &self.game.players {
-            if let Some(zones) = self.game.get_player_zones(player.id) {
-                if zones.library.is_empty() {
-                    let winner = self.game.get_other_player_id(player.id);
-                    return Some(GameResult {
-                        winner,
-                        turns_played: self.turns_elapsed,
-                        end_reason: GameEndReason::Decking(player.id),
-                    });
-                }
-            }
+        // The game goes on while two or more players remain (MTG Rules 104.2a, 800.4)
+        let mut remaining = self.game.players.iter().filter(|p| !has_lost(p));
+        let last_player = remaining.next().map(|p| p.id);
+        if remaining.next().is_some() {
+            return None;
         }
 
-        None
+        // Player death (life <= 0), then decking (attempted to draw from an empty
+        // library), then poison (ten or more poison counters), then commander
+        // damage, among the players whose loss ended the game rather than those
+        // who left it earlier
+        let players: Vec<&crate::core::Player> = self.game.players.iter().filter(|p| !p.left_game).collect();
+        let (loser, end_reason) = players
+            .iter()
+            .find(|p| p.life <= 0)
+            .map(|p| (p.id, GameEndReason::PlayerDeath(p.id)))
+            .or_else(|| {
+                players
+                    .iter()
+                    .find(|p| p.drew_from_empty_library)
+                    .map(|p| (p.id, GameEndReason::Decking(p.id)))
+            })
+            .or_else(|| {
+                players
+                    .iter()
+                    .find(|p| p.poison_counters >= 10)
+                    .map(|p| (p.id, GameEndReason::Poison(p.id)))
+            })
+            .or_else(|| {
+                players
+                    .iter()
+                    .find(|p| self.game.has_lethal_commander_damage(p.id))
+                    .map(|p| (p.id, GameEndReason::CommanderDamage(p.id)))
+            })?;
+
+        Some(GameResult {
+            // If everyone lost at once, credit the loser's opponent as in two-player games
+            winner: last_player.or_else(|| self.game.get_other_player_id(loser)),
+            turns_played: self.turns_elapsed,
+            end_reason,
+        })
     }
 
-    /// Check if a spell requires a target on the stack (e.g., Counterspell)
+    /// The largest value of X a player can pay for when casting a spell
     ///
-    /// Returns true if the spell has effects that target spells on the stack,
-    /// meaning it can only be cast when there's a spell to target.
-    fn spell_requires_stack_target(card: &crate::core::Card) -> bool {
-        use crate::core::Effect;
+    /// Only mana available from untapped sources counts, like when checking
+    /// whether the spell is castable at all.
+    fn max_x_value(&self, player_id: PlayerId, card_id: CardId) -> u8 {
+        use crate::game::mana_engine::ManaEngine;
 
-        // Check if any effect is CounterSpell with a placeholder target
-        // Placeholder target (CardId(0)) means the spell needs to choose a target when cast
-        card.effects
-            .iter()
-            .any(|effect| matches!(effect, Effect::CounterSpell { target } if target.as_u32() == 0))
+        let Ok(mana_cost) = self.game.casting_cost(card_id) else {
+            return 0;
+        };
+        let mut mana_engine = ManaEngine::new(player_id);
+        mana_engine.update(self.game);
+
+        let mut max_x = 0;
+        while max_x < u8::MAX && mana_engine.can_pay(&mana_cost.with_x(max_x + 1)) {
+            max_x += 1;
+        }
+        max_x
     }
 
-    /// Determine mana production for a land card
-    /// Returns None if we don't know how to handle this land yet
-    fn get_mana_production(card: &crate::core::Card) -> Option<crate::game::mana_payment::ManaProduction> {
-        use crate::core::CardType;
-        use crate::game::mana_payment::{ManaColor, ManaProduction, ManaProductionKind};
+    /// A spell's alternative cost, if the player can pay it (Fireblast), and
+    /// whether they could pay its mana cost instead
+    fn alternative_cost_options(&self, player_id: PlayerId, card_id: CardId) -> Option<(crate::core::Cost, bool)> {
+        use crate::game::mana_engine::ManaEngine;
 
-        // Must be a land
-        if !card.types.contains(&CardType::Land) {
-            return None;
-        }
+        let cost = self.game.cards.get(card_id).ok()?.alternative_cost.clone()?;
+        let mut mana_engine = ManaEngine::new(player_id);
+        mana_engine.update(self.game);
 
-        // Check for basic lands first (simple sources)
-        let simple_color = match card.name.as_str() {
-            "Plains" => Some(ManaColor::White),
-            "Island" => Some(ManaColor::Blue),
-            "Swamp" => Some(ManaColor::Black),
-            "Mountain" => Some(ManaColor::Red),
-            "Forest" => Some(ManaColor::Green),
-            "Wastes" => return Some(ManaProduction::free(ManaProductionKind::Colorless)),
-            _ => None,
-        };
+        let can_pay_alternative = self.game.can_pay_ability_cost(player_id, card_id, &cost)
+            && cost
+                .get_mana_cost()
+                .is_none_or(|mana_cost| mana_engine.can_pay(mana_cost));
+        let can_pay_mana = self
+            .game
+            .casting_cost(card_id)
+            .is_ok_and(|mana_cost| mana_engine.can_pay(&mana_cost));
+        can_pay_alternative.then_some((cost, can_pay_mana))
+    }
 
-        if let Some(color) = simple_color {
-            return Some(ManaProduction::free(ManaProductionKind::Fixed(color)));
-        }
+    /// The most targets a player can pay for, given X, when each target beyond
+    /// the first costs more (Fireball); other spells have no limit here
+    fn max_affordable_targets(&self, player_id: PlayerId, card_id: CardId, x_value: u8) -> u8 {
+        use crate::game::mana_engine::ManaEngine;
 
-        // Check for dual lands by looking at basic land subtypes
-        let mut colors = Vec::new();
-        for subtype in &card.subtypes {
-            let color = match subtype.as_str() {
-                "Plains" => Some(ManaColor::White),
-                "Island" => Some(ManaColor::Blue),
-                "Swamp" => Some(ManaColor::Black),
-                "Mountain" => Some(ManaColor::Red),
-                "Forest" => Some(ManaColor::Green),
-                _ => None,
-            };
-            if let Some(c) = color {
-                colors.push(c);
-            }
+        let (Ok(card), Ok(mana_cost)) = (self.game.cards.get(card_id), self.game.casting_cost(card_id)) else {
+            return 0;
+        };
+        if card.extra_target_cost == 0 {
+            return u8::MAX;
         }
+        let mut mana_engine = ManaEngine::new(player_id);
+        mana_engine.update(self.game);
 
-        // If we have exactly 2 basic land subtypes, it's a dual land
-        if colors.len() == 2 {
-            return Some(ManaProduction::free(ManaProductionKind::Choice(colors)));
+        let mana_cost = mana_cost.with_x(x_value);
+        let mut max_targets = 1;
+        while max_targets < u8::MAX
+            && mana_engine.can_pay(&mana_cost.plus_generic(card.extra_targets_cost(usize::from(max_targets) + 1)))
+        {
+            max_targets += 1;
         }
+        max_targets
+    }
 
-        // Check oracle text for any-color lands (City of Brass pattern)
-        let text_lower = card.text.to_lowercase();
-        if text_lower.contains("any color") {
-            return Some(ManaProduction::free(ManaProductionKind::AnyColor));
-        }
+    /// Check if a spell requires a target on the stack (e.g., Counterspell)
+    ///
+    /// Returns true if the spell has effects that target spells on the stack,
+    /// meaning it can only be cast when there's a spell to target.
+    fn spell_requires_stack_target(card: &crate::core::Card) -> bool {
+        use crate::core::Effect;
 
-        // Not a complex source we can handle yet
-        None
+        // Check if any effect is CounterSpell with a placeholder target
+        // Placeholder target (CardId(0)) means the spell needs to choose a target when cast
+        card.effects
+            .iter()
+            .any(|effect| matches!(effect, Effect::CounterSpell { target } if target.as_u32() == 0))
     }
 }
 
@@ -3197,6 +3940,7 @@ mod tests {
         let land_id = game.next_card_id();
         let mut land = crate::core::Card::new(land_id, "Mountain".to_string(), alice);
         land.types.push(crate::core::CardType::Land);
+        land.subtypes.push("Mountain".into());
         land.tap();
         game.cards.insert(land_id, land);
         game.battlefield.add(land_id);
@@ -3238,7 +3982,7 @@ mod tests {
 
         // Run draw step
         let mut game_loop = GameLoop::new(&mut game);
-        game_loop.draw_step(&mut controller1, &mut controller2).unwrap();
+        game_loop.draw_step(&mut [&mut controller1, &mut controller2]).unwrap();
 
         // Card should be in hand
         if let Some(zones) = game.get_player_zones(alice) {
@@ -3247,6 +3991,173 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_three_player_game_until_last_player_standing() {
+        let names = ["Alice", "Bob", "Carol"].map(String::from).to_vec();
+        let mut game = GameState::new_multiplayer(names, 20);
+        let ids: Vec<PlayerId> = game.players.iter().map(|p| p.id).collect();
+        for &owner in &ids {
+            for _ in 0..8 {
+                let card_id = game.next_card_id();
+                let card = crate::core::Card::new(card_id, "Test Card".to_string(), owner);
+                game.cards.insert(card_id, card);
+                game.get_player_zones_mut(owner).unwrap().library.add(card_id);
+            }
+        }
+
+        let mut alice = crate::game::ZeroController::new(ids[0]);
+        let mut bob = crate::game::ZeroController::new(ids[1]);
+        let mut carol = crate::game::ZeroController::new(ids[2]);
+        let result = GameLoop::new(&mut game)
+            .with_verbosity(VerbosityLevel::Silent)
+            .run_multiplayer_game(&mut [&mut alice, &mut bob, &mut carol])
+            .unwrap();
+
+        // Everyone draws on the first turn of a multiplayer game (MTG Rules 103.8c),
+        // so Alice decks out on turn 4 and leaves, then Bob decks out on turn 5
+        assert_eq!(result.winner, Some(ids[2]));
+        assert_eq!(result.end_reason, GameEndReason::Decking(ids[1]));
+        assert_eq!(game.turn.turn_number, 5);
+        assert!(game.get_player_zones(ids[0]).unwrap().hand.is_empty());
+    }
+
+    #[test]
+    fn test_zobrist_hash_tracks_random_game() {
+        use crate::core::{Card, CardType, Effect, ManaCost, TargetRef};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let ids: Vec<PlayerId> = game.players.iter().map(|p| p.id).collect();
+        for &owner in &ids {
+            for i in 0..20 {
+                let card_id = game.next_card_id();
+                let mut card = Card::new(card_id, "Test Card".to_string(), owner);
+                match i % 3 {
+                    0 => {
+                        card.types.push(CardType::Land);
+                        card.subtypes.push("Mountain".into());
+                    }
+                    1 => {
+                        card.types.push(CardType::Creature);
+                        card.mana_cost = ManaCost::from_string("1R");
+                        card.power = Some(2);
+                        card.toughness = Some(2);
+                    }
+                    _ => {
+                        card.types.push(CardType::Instant);
+                        card.mana_cost = ManaCost::from_string("R");
+                        card.effects.push(Effect::DealDamage {
+                            target: TargetRef::None,
+                            amount: 3,
+                        });
+                    }
+                }
+                game.cards.insert(card_id, card);
+                game.get_player_zones_mut(owner).unwrap().library.add(card_id);
+            }
+        }
+        game.reset_zobrist_hash();
+        let start = game.zobrist_hash();
+
+        let mut alice = crate::game::RandomController::with_seed(ids[0], 7);
+        let mut bob = crate::game::RandomController::with_seed(ids[1], 8);
+        GameLoop::new(&mut game)
+            .with_verbosity(VerbosityLevel::Silent)
+            .run_turns(&mut alice, &mut bob, 8)
+            .unwrap();
+        assert!(!game.battlefield.is_empty());
+        assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+
+        while game.undo().unwrap() {}
+        assert_eq!(game.zobrist_hash(), start);
+    }
+
+    #[test]
+    fn test_london_mulligan_puts_cards_on_bottom() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        for (owner, name) in [(alice, "Forest"), (bob, "Grizzly Bears")] {
+            for _ in 0..20 {
+                let card_id = game.next_card_id();
+                let mut card = crate::core::Card::new(card_id, name.to_string(), owner);
+                card.types.push(if owner == alice {
+                    crate::core::CardType::Land
+                } else {
+                    crate::core::CardType::Creature
+                });
+                game.cards.insert(card_id, card);
+                game.get_player_zones_mut(owner).unwrap().library.add(card_id);
+            }
+        }
+
+        // Alice's all-land hands are mulligans until she'd keep five cards
+        let mut alice_controller = crate::game::HeuristicController::new(alice);
+        let mut bob_controller = crate::game::ZeroController::new(bob);
+        GameLoop::new(&mut game)
+            .with_verbosity(VerbosityLevel::Silent)
+            .setup_game(&mut [&mut alice_controller, &mut bob_controller])
+            .unwrap();
+
+        let zones = game.get_player_zones(alice).unwrap();
+        assert_eq!(zones.hand.len(), 5);
+        assert_eq!(zones.library.len(), 15);
+        assert_eq!(game.get_player_zones(bob).unwrap().hand.len(), 7);
+
+        let choices: Vec<(PlayerId, crate::game::ReplayChoice)> = game
+            .undo_log
+            .actions()
+            .iter()
+            .filter_map(|action| match action {
+                crate::undo::GameAction::ChoicePoint {
+                    player_id,
+                    choice: Some(choice),
+                    ..
+                } => Some((*player_id, choice.clone())),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(
+            choices.as_slice(),
+            [
+                (p1, crate::game::ReplayChoice::Mulligan(true)),
+                (p2, crate::game::ReplayChoice::Mulligan(false)),
+                (p3, crate::game::ReplayChoice::Mulligan(true)),
+                (p4, crate::game::ReplayChoice::Mulligan(false)),
+                (p5, crate::game::ReplayChoice::Bottom(bottom)),
+            ] if [*p1, *p3, *p4, *p5] == [alice; 4] && *p2 == bob && bottom.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_forced_keep_is_not_logged_as_a_choice() {
+        // Alice's library is empty, so her hand is too and there's no mulligan to decide
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        for _ in 0..20 {
+            let card_id = game.next_card_id();
+            let card = crate::core::Card::new(card_id, "Forest".to_string(), bob);
+            game.cards.insert(card_id, card);
+            game.get_player_zones_mut(bob).unwrap().library.add(card_id);
+        }
+
+        let mut alice_controller = crate::game::ZeroController::new(alice);
+        let mut bob_controller = crate::game::ZeroController::new(bob);
+        GameLoop::new(&mut game)
+            .with_verbosity(VerbosityLevel::Silent)
+            .setup_game(&mut [&mut alice_controller, &mut bob_controller])
+            .unwrap();
+
+        let choosers: Vec<PlayerId> = game
+            .undo_log
+            .actions()
+            .iter()
+            .filter_map(|action| match action {
+                crate::undo::GameAction::ChoicePoint { player_id, .. } => Some(*player_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(choosers, vec![bob]);
+    }
+
     #[test]
     fn test_check_win_condition_life() {
         let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
@@ -3266,4 +4177,580 @@ mod tests {
         let result = result.unwrap();
         assert_eq!(result.end_reason, GameEndReason::PlayerDeath(bob));
     }
+
+    #[test]
+    fn test_upkeep_triggers_use_stack_in_apnap_order() {
+        use crate::core::{Card, CardType, Effect, Trigger, TriggerEvent};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+
+        for (owner, name, amount) in [(alice, "First", 1), (alice, "Second", 2), (bob, "Third", 3)] {
+            let card_id = game.next_card_id();
+            let mut card = Card::new(card_id, name.to_string(), owner);
+            card.types.push(CardType::Enchantment);
+            card.triggers.push(Trigger::new(
+                TriggerEvent::BeginningOfUpkeep,
+                vec![Effect::GainLife { player: owner, amount }],
+                format!("{name}: gain {amount} life"),
+            ));
+            game.cards.insert(card_id, card);
+            game.battlefield.add(card_id);
+        }
+
+        let mut controller1 = crate::game::ZeroController::new(alice);
+        let mut controller2 = crate::game::ZeroController::new(bob);
+        let mut game_loop = GameLoop::new(&mut game);
+        game_loop
+            .upkeep_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+
+        assert!(game.stack.is_empty());
+        assert!(game.pending_triggers.is_empty());
+        assert_eq!(game.get_player(alice).unwrap().life, 23);
+        assert_eq!(game.get_player(bob).unwrap().life, 23);
+
+        // The active player's triggers go on the stack first, so the non-active player's resolve first
+        let resolved: Vec<&str> = game
+            .undo_log
+            .actions()
+            .iter()
+            .filter_map(|action| match action {
+                crate::undo::GameAction::ResolveAbility { object } => {
+                    object.ability().map(|ability| ability.description.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            resolved,
+            vec!["Third: gain 3 life", "Second: gain 2 life", "First: gain 1 life"]
+        );
+    }
+
+    #[test]
+    fn test_trigger_targets_are_chosen_by_controller() {
+        use crate::core::{Card, CardType, Effect, Keyword, Selector, Trigger, TriggerEvent};
+
+        // "At the beginning of your upkeep, destroy target creature an opponent controls."
+        let setup = |opponent_creatures: &[bool]| {
+            let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+            let (alice, bob) = (game.players[0].id, game.players[1].id);
+            let add_creature = |game: &mut GameState, owner, hexproof: bool| {
+                let card_id = game.next_card_id();
+                let mut card = Card::new(card_id, "Bear".to_string(), owner);
+                card.types.push(CardType::Creature);
+                card.power = Some(2);
+                card.toughness = Some(2);
+                if hexproof {
+                    card.keywords.push(Keyword::Hexproof);
+                }
+                game.cards.insert(card_id, card);
+                game.battlefield.add(card_id);
+                card_id
+            };
+            let engine_id = game.next_card_id();
+            let mut engine = Card::new(engine_id, "Spite Engine".to_string(), alice);
+            engine.types.push(CardType::Enchantment);
+            engine.triggers.push(
+                Trigger::new(
+                    TriggerEvent::BeginningOfUpkeep,
+                    vec![Effect::DestroyPermanent { target: CardId::new(0) }],
+                    "Destroy target creature an opponent controls".to_string(),
+                )
+                .with_target_restriction(Some(Selector::parse("Creature.OppCtrl"))),
+            );
+            game.cards.insert(engine_id, engine);
+            game.battlefield.add(engine_id);
+
+            add_creature(&mut game, alice, false);
+            let creatures: Vec<CardId> = opponent_creatures
+                .iter()
+                .map(|&hexproof| add_creature(&mut game, bob, hexproof))
+                .collect();
+            (game, creatures)
+        };
+
+        // Alice chooses the second of the two bears she can target
+        let (mut game, creatures) = setup(&[false, true, false]);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let mut controller1 = crate::game::FixedScriptController::new(alice, vec![1]);
+        let mut controller2 = crate::game::ZeroController::new(bob);
+        GameLoop::new(&mut game)
+            .upkeep_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+        let survivors: Vec<bool> = creatures.iter().map(|&id| game.battlefield.contains(id)).collect();
+        assert_eq!(survivors, vec![true, true, false]);
+
+        // With only a hexproof creature to target, the trigger is removed (MTG Rules 603.3d)
+        let (mut game, creatures) = setup(&[true]);
+        let mut controller1 = crate::game::ZeroController::new(alice);
+        GameLoop::new(&mut game)
+            .upkeep_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+        assert!(game.battlefield.contains(creatures[0]));
+        assert!(game.pending_triggers.is_empty());
+        assert!(!game
+            .undo_log
+            .actions()
+            .iter()
+            .any(|action| matches!(action, crate::undo::GameAction::ResolveAbility { .. })));
+    }
+
+    #[test]
+    fn test_legend_rule_lets_the_controller_choose() {
+        use crate::core::{Card, CardType, Subtype};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let legends: Vec<CardId> = (0..2)
+            .map(|_| {
+                let card_id = game.next_card_id();
+                let mut card = Card::new(card_id, "Isamaru, Hound of Konda".to_string(), alice);
+                card.types.push(CardType::Creature);
+                card.subtypes.push(Subtype::new("Legendary"));
+                card.power = Some(2);
+                card.toughness = Some(2);
+                game.cards.insert(card_id, card);
+                game.battlefield.add(card_id);
+                card_id
+            })
+            .collect();
+
+        // Alice keeps the older copy rather than the most recent arrival
+        let mut controller1 = crate::game::FixedScriptController::new(alice, vec![0]);
+        let mut controller2 = crate::game::ZeroController::new(bob);
+        GameLoop::new(&mut game)
+            .upkeep_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+        assert!(game.battlefield.contains(legends[0]));
+        assert!(game.get_player_zones(alice).unwrap().graveyard.contains(legends[1]));
+    }
+
+    #[test]
+    fn test_mana_ability_color_is_chosen_by_controller() {
+        use crate::core::{ActivatedAbility, Card, CardType, Cost, Effect};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+
+        // Sacrificed rather than tapped, so it's activated at priority, not while paying
+        let petal = game.next_card_id();
+        let mut card = Card::new(petal, "Lotus Petal".to_string(), alice);
+        card.types.push(CardType::Artifact);
+        card.activated_abilities.push(ActivatedAbility::new(
+            Cost::parse("Sac<1/CARDNAME>").unwrap(),
+            vec![Effect::AddManaOfChoice {
+                player: PlayerId::new(0),
+                colors: crate::core::WUBRG.to_vec(),
+                amount: 1,
+            }],
+            "Add one mana of any color.".to_string(),
+            true,
+        ));
+        game.cards.insert(petal, card);
+        game.battlefield.add(petal);
+
+        // Alice activates the Petal and chooses red (WUBRG index 3)
+        let mut controller1 = crate::game::FixedScriptController::new(alice, vec![1, 3]);
+        let mut controller2 = crate::game::ZeroController::new(bob);
+        GameLoop::new(&mut game)
+            .upkeep_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+
+        assert!(!game.battlefield.contains(petal));
+        let added: Vec<crate::core::ManaCost> = game
+            .undo_log
+            .actions()
+            .iter()
+            .filter_map(|action| match action {
+                crate::undo::GameAction::AddMana { player_id, mana } if *player_id == alice => Some(*mana),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(added.len(), 1);
+        assert_eq!((added[0].red, added[0].cmc()), (1, 1));
+        assert!(game.undo_log.actions().iter().any(|action| matches!(
+            action,
+            crate::undo::GameAction::ChoicePoint {
+                choice: Some(crate::game::ReplayChoice::ManaColor(crate::core::Color::Red)),
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn test_activated_ability_uses_stack_and_survives_its_source() {
+        use crate::core::{ActivatedAbility, Card, CardType, Cost, Effect};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+
+        let healer = game.next_card_id();
+        let mut card = Card::new(healer, "Healer".to_string(), alice);
+        card.types.push(CardType::Creature);
+        card.power = Some(1);
+        card.toughness = Some(1);
+        card.activated_abilities.push(ActivatedAbility::new(
+            Cost::Tap,
+            vec![Effect::GainLife {
+                player: PlayerId::new(0),
+                amount: 2,
+            }],
+            "You gain 2 life.".to_string(),
+            false,
+        ));
+        game.cards.insert(healer, card);
+        game.battlefield.add(healer);
+
+        let assassin = game.next_card_id();
+        let mut card = Card::new(assassin, "Assassin".to_string(), bob);
+        card.types.push(CardType::Creature);
+        card.power = Some(1);
+        card.toughness = Some(1);
+        card.activated_abilities.push(ActivatedAbility::new(
+            Cost::Tap,
+            vec![Effect::DestroyPermanent { target: CardId::new(0) }],
+            "Destroy target tapped creature.".to_string(),
+            false,
+        ));
+        game.cards.insert(assassin, card);
+        game.battlefield.add(assassin);
+
+        // Alice activates Healer; Bob responds by destroying the (now tapped) Healer
+        let mut controller1 = crate::game::FixedScriptController::new(alice, vec![1]);
+        let mut controller2 = crate::game::FixedScriptController::new(bob, vec![1]);
+        let mut game_loop = GameLoop::new(&mut game);
+        game_loop
+            .upkeep_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+
+        assert!(game.stack.is_empty());
+        assert!(!game.battlefield.contains(healer));
+        assert!(game.get_player_zones(alice).unwrap().graveyard.contains(healer));
+        assert_eq!(game.get_player(alice).unwrap().life, 22);
+
+        // Bob's response resolved first, then Alice's ability without its source
+        let resolved: Vec<(usize, &str)> = game
+            .undo_log
+            .actions()
+            .iter()
+            .filter_map(|action| match action {
+                crate::undo::GameAction::ResolveAbility { object } => match object.as_ref() {
+                    StackObject::ActivatedAbility { ability, ability_index } => {
+                        Some((*ability_index, ability.description.as_str()))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            resolved,
+            vec![(0, "Destroy target tapped creature."), (0, "You gain 2 life.")]
+        );
+    }
+
+    #[test]
+    fn test_activated_abilities_with_sacrifice_costs() {
+        use crate::core::{ActivatedAbility, Card, CardType, Cost, Effect};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+
+        // Zuran Orb: Sacrifice a land: You gain 2 life
+        let orb = game.next_card_id();
+        let mut card = Card::new(orb, "Zuran Orb".to_string(), alice);
+        card.types.push(CardType::Artifact);
+        card.activated_abilities.push(ActivatedAbility::new(
+            Cost::parse("Sac<1/Land>").unwrap(),
+            vec![Effect::GainLife {
+                player: PlayerId::new(0),
+                amount: 2,
+            }],
+            "You gain 2 life.".to_string(),
+            false,
+        ));
+        game.cards.insert(orb, card);
+        game.battlefield.add(orb);
+
+        // Ashnod's Altar: Sacrifice a creature: Add {C}{C}
+        let altar = game.next_card_id();
+        let mut card = Card::new(altar, "Ashnod's Altar".to_string(), alice);
+        card.types.push(CardType::Artifact);
+        card.activated_abilities.push(ActivatedAbility::new(
+            Cost::parse("Sac<1/Creature>").unwrap(),
+            vec![Effect::AddMana {
+                player: PlayerId::new(0),
+                mana: crate::core::ManaCost::from_string("CC"),
+            }],
+            "Add {C}{C}.".to_string(),
+            true,
+        ));
+        game.cards.insert(altar, card);
+        game.battlefield.add(altar);
+
+        // Without lands or creatures to sacrifice, neither ability can be activated
+        assert!(GameLoop::new(&mut game).get_activatable_abilities(alice).is_empty());
+
+        let mut lands = Vec::new();
+        for name in ["Plains", "Island"] {
+            let land = game.next_card_id();
+            let mut card = Card::new(land, name.to_string(), alice);
+            card.types.push(CardType::Land);
+            card.subtypes.push(name.into());
+            game.cards.insert(land, card);
+            game.battlefield.add(land);
+            lands.push(land);
+        }
+        let bear = game.next_card_id();
+        let mut card = Card::new(bear, "Grizzly Bears".to_string(), alice);
+        card.types.push(CardType::Creature);
+        card.power = Some(2);
+        card.toughness = Some(2);
+        game.cards.insert(bear, card);
+        game.battlefield.add(bear);
+        assert_eq!(
+            GameLoop::new(&mut game).get_activatable_abilities(alice),
+            vec![(orb, 0), (altar, 0)]
+        );
+
+        // Alice activates Zuran Orb, sacrificing the first land (the script's first
+        // candidate), then Ashnod's Altar, whose mana ability doesn't use the stack
+        let mut controller1 = crate::game::FixedScriptController::new(alice, vec![1, 2]);
+        let mut controller2 = crate::game::FixedScriptController::new(bob, vec![0, 0]);
+        GameLoop::new(&mut game)
+            .upkeep_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+        assert_eq!(game.get_player(alice).unwrap().life, 22);
+        assert!(game.get_player_zones(alice).unwrap().graveyard.contains(lands[0]));
+        assert!(game.battlefield.contains(lands[1]));
+        assert!(game.get_player_zones(alice).unwrap().graveyard.contains(bear));
+        assert_eq!(game.get_player(alice).unwrap().mana_pool.colorless, 2);
+    }
+
+    #[test]
+    fn test_replacement_effect_chosen_through_controller() {
+        use crate::core::{Effect, ReplacementAction, ReplacementEffect, ReplacementEvent, ReplacementKey};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        game.turn.turn_number = 2;
+
+        // Two effects replace Alice's draws: gain 2 life or gain 5 life instead
+        let mut keys = Vec::new();
+        for amount in [2, 5] {
+            let id = game.add_replacement_effect(
+                None,
+                alice,
+                ReplacementEffect {
+                    event: ReplacementEvent::Draw,
+                    affected: Some(crate::core::Selector::parse("You")),
+                    valid_source: None,
+                    action: ReplacementAction::Instead(vec![Effect::GainLife {
+                        player: PlayerId::new(0),
+                        amount,
+                    }]),
+                    single_use: false,
+                    description: format!("gain {amount} life instead"),
+                },
+            );
+            keys.push(ReplacementKey::Registered(id));
+        }
+
+        // Alice is asked as the draw happens and picks the second effect
+        let mut controller1 = crate::game::FixedScriptController::new(alice, vec![1]);
+        let mut controller2 = crate::game::FixedScriptController::new(bob, vec![]);
+        GameLoop::new(&mut game)
+            .draw_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+
+        assert_eq!(game.get_player(alice).unwrap().life, 25);
+        let choices: Vec<_> = game
+            .undo_log
+            .actions()
+            .iter()
+            .filter_map(|action| match action {
+                crate::undo::GameAction::ChoicePoint {
+                    choice: Some(crate::game::ReplayChoice::ReplacementEffect(key)),
+                    ..
+                } => Some(*key),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(choices, vec![keys[1]]);
+        assert!(!game.logger.is_muted());
+    }
+
+    #[test]
+    fn test_x_spell_cast_through_controller() {
+        use crate::core::{Card, CardType, Effect, ManaCost, TargetRef, AMOUNT_X};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+
+        for _ in 0..3 {
+            let mountain = game.next_card_id();
+            let mut card = Card::new(mountain, "Mountain".to_string(), alice);
+            card.types.push(CardType::Land);
+            card.subtypes.push("Mountain".into());
+            game.cards.insert(mountain, card);
+            game.battlefield.add(mountain);
+        }
+
+        let wall = game.next_card_id();
+        let mut card = Card::new(wall, "Wall".to_string(), bob);
+        card.types.push(CardType::Creature);
+        card.power = Some(0);
+        card.toughness = Some(5);
+        game.cards.insert(wall, card);
+        game.battlefield.add(wall);
+
+        let blast = game.next_card_id();
+        let mut card = Card::new(blast, "Blast".to_string(), alice);
+        card.types.push(CardType::Instant);
+        card.mana_cost = ManaCost::from_string("XR");
+        card.effects.push(Effect::DealDamage {
+            target: TargetRef::None,
+            amount: AMOUNT_X,
+        });
+        game.cards.insert(blast, card);
+        game.get_player_zones_mut(alice).unwrap().hand.add(blast);
+
+        // Alice casts Blast and asks for X = 9; three Mountains only pay for X = 2
+        let mut controller1 = crate::game::FixedScriptController::new(alice, vec![1, 9]);
+        let mut controller2 = crate::game::FixedScriptController::new(bob, vec![]);
+        let mut game_loop = GameLoop::new(&mut game);
+        game_loop
+            .upkeep_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+
+        assert!(game.get_player_zones(alice).unwrap().graveyard.contains(blast));
+        assert_eq!(game.cards.get(wall).unwrap().damage_marked, 2);
+        assert!(game.undo_log.actions().iter().any(|action| matches!(
+            action,
+            crate::undo::GameAction::ChoicePoint {
+                choice: Some(crate::game::ReplayChoice::XValue(2)),
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn test_divided_spell_cast_through_controller() {
+        use crate::core::{Card, CardType, Effect, ManaCost, TargetRef};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+
+        let mountain = game.next_card_id();
+        let mut card = Card::new(mountain, "Mountain".to_string(), alice);
+        card.types.push(CardType::Land);
+        card.subtypes.push("Mountain".into());
+        game.cards.insert(mountain, card);
+        game.battlefield.add(mountain);
+
+        let mut creatures = Vec::new();
+        for toughness in [1, 2] {
+            let id = game.next_card_id();
+            let mut card = Card::new(id, "Bear".to_string(), bob);
+            card.types.push(CardType::Creature);
+            card.power = Some(1);
+            card.toughness = Some(toughness);
+            game.cards.insert(id, card);
+            game.battlefield.add(id);
+            creatures.push(id);
+        }
+
+        let arc = game.next_card_id();
+        let mut card = Card::new(arc, "Arc Bolt".to_string(), alice);
+        card.types.push(CardType::Instant);
+        card.mana_cost = ManaCost::from_string("R");
+        card.max_targets = 3;
+        card.divided_as_you_choose = true;
+        card.effects.push(Effect::DealDamage {
+            target: TargetRef::None,
+            amount: 4,
+        });
+        game.cards.insert(arc, card);
+        game.get_player_zones_mut(alice).unwrap().hand.add(arc);
+
+        // Alice casts Arc Bolt, targets both creatures and then Bob (the players
+        // come first among the targets), and gives 1 to the first creature and 2
+        // to the second; Bob gets the remaining 1
+        let mut controller1 = crate::game::FixedScriptController::new(alice, vec![1, 2, 2, 1, 1, 2]);
+        let mut controller2 = crate::game::FixedScriptController::new(bob, vec![]);
+        let mut game_loop = GameLoop::new(&mut game);
+        game_loop
+            .upkeep_step(&mut [&mut controller1, &mut controller2])
+            .unwrap();
+
+        let graveyard = &game.get_player_zones(bob).unwrap().graveyard;
+        assert!(creatures.iter().all(|&id| graveyard.contains(id)));
+        assert_eq!(game.get_player(bob).unwrap().life, 19);
+        assert!(game.undo_log.actions().iter().any(|action| matches!(
+            action,
+            crate::undo::GameAction::ChoicePoint {
+                choice: Some(crate::game::ReplayChoice::Division(division)),
+                ..
+            } if division.as_slice() == [1, 2, 1]
+        )));
+    }
+
+    #[test]
+    fn test_alternative_cost_chosen_through_controller() {
+        use crate::core::{CardType, Cost, Effect, ManaCost, TargetRef};
+        use crate::game::test_support::{add_permanent, add_to_hand};
+
+        // Alice casts Fireblast at Bob with `mountains` Mountains, paying the
+        // alternative cost if she has to or if the script says so
+        let cast_fireblast = |mountains: usize, script: Vec<usize>| {
+            let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+            let (alice, bob) = (game.players[0].id, game.players[1].id);
+            for _ in 0..mountains {
+                add_permanent(&mut game, alice, "Mountain", |card| {
+                    card.types.push(CardType::Land);
+                    card.subtypes.push("Mountain".into());
+                });
+            }
+            add_to_hand(&mut game, alice, "Fireblast", |card| {
+                card.types.push(CardType::Instant);
+                card.mana_cost = ManaCost::from_string("4RR");
+                card.alternative_cost = Cost::parse("Sac<2/Mountain>");
+                card.effects.push(Effect::DealDamage {
+                    target: TargetRef::None,
+                    amount: 4,
+                });
+            });
+
+            let mut controller1 = crate::game::FixedScriptController::new(alice, script);
+            let mut controller2 = crate::game::FixedScriptController::new(bob, vec![]);
+            let mut game_loop = GameLoop::new(&mut game);
+            game_loop
+                .upkeep_step(&mut [&mut controller1, &mut controller2])
+                .unwrap();
+
+            assert_eq!(game.get_player(bob).unwrap().life, 16);
+            let choices: Vec<bool> = game
+                .undo_log
+                .actions()
+                .iter()
+                .filter_map(|action| match action {
+                    crate::undo::GameAction::ChoicePoint {
+                        choice: Some(crate::game::ReplayChoice::AlternativeCost(alternative)),
+                        ..
+                    } => Some(*alternative),
+                    _ => None,
+                })
+                .collect();
+            let sacrificed = game.get_player_zones(alice).unwrap().graveyard.len() - 1;
+            (choices, sacrificed)
+        };
+
+        // Two Mountains can't pay {4}{R}{R}, so there's no choice to make
+        assert_eq!(cast_fireblast(2, vec![1]), (vec![], 2));
+        // With six, Alice chooses
+        assert_eq!(cast_fireblast(6, vec![1, 1]), (vec![true], 2));
+        assert_eq!(cast_fireblast(6, vec![1, 0]), (vec![false], 0));
+    }
 }
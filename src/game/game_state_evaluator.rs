@@ -209,6 +209,11 @@ impl GameStateEvaluator {
                             if mana.green > 0 {
                                 colors_available[4] = true;
                             }
+                        } else if let crate::core::Effect::AddManaOfChoice { colors, amount, .. } = effect {
+                            total_mana_sources += (*amount as i32).max(1);
+                            for (available, color) in colors_available.iter_mut().zip(crate::core::WUBRG) {
+                                *available |= colors.contains(&color);
+                            }
                         }
                     }
                 }
@@ -301,6 +306,13 @@ impl GameStateEvaluator {
                     if mana.colorless > 0 {
                         colors_produced.insert("C");
                     }
+                } else if let crate::core::Effect::AddManaOfChoice { colors, amount, .. } = effect {
+                    mana_generated += *amount as i32;
+                    for (color, symbol) in crate::core::WUBRG.iter().zip(["W", "U", "B", "R", "G"]) {
+                        if colors.contains(color) {
+                            colors_produced.insert(symbol);
+                        }
+                    }
                 }
             }
 
@@ -1,12 +1,18 @@
 //! Initial hand setup and library shuffling
 //!
 //! Handles the process of shuffling libraries and drawing opening hands,
-//! with support for controlled initial hands for testing scenarios.
+//! with support for controlled initial hands for testing scenarios, and the
+//! game state side of mulligans (MTG Rules 103.5). The mulligan decisions
+//! themselves are asked of the controllers by the game loop.
 
-use crate::core::PlayerId;
+use crate::core::{CardId, PlayerId};
 use crate::game::GameState;
+use crate::zones::Zone;
 use crate::{MtgError, Result};
 
+/// Number of cards in an opening hand (MTG Rules 103.4)
+pub const OPENING_HAND_SIZE: usize = 7;
+
 /// Configuration for a player's initial hand
 #[derive(Debug, Clone)]
 pub struct HandSetup {
@@ -65,7 +71,7 @@ pub fn setup_opening_hands(
             setup_controlled_hand(game, player_id, hand_setup)?;
         } else {
             // Normal random draw
-            for _ in 0..7 {
+            for _ in 0..OPENING_HAND_SIZE {
                 game.draw_card(player_id)?;
             }
         }
@@ -106,13 +112,21 @@ fn setup_controlled_hand(game: &mut GameState, player_id: PlayerId, hand_setup:
         let zones = game
             .get_player_zones_mut(player_id)
             .ok_or_else(|| MtgError::InvalidAction(format!("Player {:?} not found", player_id)))?;
+        let from_index = zones.library.position(card_id).unwrap_or_default();
         zones.library.remove(card_id);
         zones.hand.add(card_id);
+        game.undo_log.log(crate::undo::GameAction::MoveCard {
+            card_id,
+            from_zone: Zone::Library,
+            to_zone: Zone::Hand,
+            owner: player_id,
+            from_index,
+        });
     }
 
     // Draw remaining cards randomly to reach 7 total
     let cards_in_hand = hand_setup.specific_cards.len();
-    let remaining_to_draw = 7usize.saturating_sub(cards_in_hand);
+    let remaining_to_draw = OPENING_HAND_SIZE.saturating_sub(cards_in_hand);
 
     for _ in 0..remaining_to_draw {
         game.draw_card(player_id)?;
@@ -121,6 +135,61 @@ fn setup_controlled_hand(game: &mut GameState, player_id: PlayerId, hand_setup:
     Ok(())
 }
 
+impl GameState {
+    /// Take a mulligan: shuffle the hand into the library and draw a new
+    /// opening hand (MTG Rules 103.5)
+    pub fn mulligan_hand(&mut self, player_id: PlayerId) -> Result<()> {
+        let zones = self
+            .get_player_zones_mut(player_id)
+            .ok_or_else(|| MtgError::InvalidAction(format!("Player {:?} not found", player_id)))?;
+        let hand = std::mem::take(&mut zones.hand.cards);
+        for &card_id in &hand {
+            zones.library.add(card_id);
+        }
+        // Logged as if taken from the front of the hand one at a time
+        for card_id in hand {
+            self.undo_log.log(crate::undo::GameAction::MoveCard {
+                card_id,
+                from_zone: Zone::Hand,
+                to_zone: Zone::Library,
+                owner: player_id,
+                from_index: 0,
+            });
+        }
+
+        self.shuffle_library(player_id);
+        for _ in 0..OPENING_HAND_SIZE {
+            self.draw_card(player_id)?;
+        }
+        Ok(())
+    }
+
+    /// Put cards from a player's hand on the bottom of their library, one at
+    /// a time, so the last card ends up at the very bottom
+    pub fn put_on_bottom_of_library(&mut self, player_id: PlayerId, cards: &[CardId]) -> Result<()> {
+        for &card_id in cards {
+            let zones = self
+                .get_player_zones_mut(player_id)
+                .ok_or_else(|| MtgError::InvalidAction(format!("Player {:?} not found", player_id)))?;
+            let Some(from_index) = zones.hand.position(card_id) else {
+                return Err(MtgError::InvalidAction(format!(
+                    "Card {card_id:?} not in player's hand"
+                )));
+            };
+            zones.hand.remove(card_id);
+            zones.library.add_to_bottom(card_id);
+            self.undo_log.log(crate::undo::GameAction::MoveCard {
+                card_id,
+                from_zone: Zone::Hand,
+                to_zone: Zone::Library,
+                owner: player_id,
+                from_index,
+            });
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
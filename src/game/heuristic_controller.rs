@@ -9,9 +9,9 @@
 //! - AiController.java (core logic)
 //! - CreatureEvaluator.java (creature scoring)
 
-use crate::core::{Card, CardId, Keyword, ManaCost, PlayerId, SpellAbility};
+use crate::core::{Card, CardId, Color, Cost, Effect, Keyword, ManaCost, PlayerId, ReplacementKey, SpellAbility};
 use crate::game::controller::{GameStateView, PlayerController};
-use crate::game::format_choice_menu;
+use crate::game::{format_choice_menu, AttackTarget, StackAbility};
 use smallvec::SmallVec;
 
 /// Combat factors for attack decisions
@@ -32,6 +32,12 @@ struct CombatFactors {
     number_of_blockers: usize,            // Count of valid blockers
 }
 
+/// Hand size below which the AI never mulligans, and at which it keeps any hand
+/// with a land in it
+///
+/// Reference: AiProps.MULLIGAN_THRESHOLD (MulliganThreshold=5 in Default.ai)
+const MULLIGAN_THRESHOLD: usize = 5;
+
 /// Heuristic AI controller that makes decisions using evaluation functions
 /// rather than simulation. Aims to faithfully reproduce Java Forge AI behavior.
 ///
@@ -95,12 +101,12 @@ impl HeuristicController {
 
         // Tokens are worth less than actual cards
         // Java: if (!c.isToken()) { value += addValue(20, "non-token"); }
-        // TODO: Add is_token flag to Card struct
-        // For now, assume all cards are non-tokens
-        value += 20;
+        if !card.is_token {
+            value += 20;
+        }
 
-        let power = card.power.unwrap_or(0) as i32;
-        let toughness = card.toughness.unwrap_or(0) as i32;
+        let power = card.current_power() as i32;
+        let toughness = card.current_toughness() as i32;
 
         // Stats scoring
         if consider_pt {
@@ -130,7 +136,7 @@ impl HeuristicController {
         // For now, we'll check for explicit Other keyword
         // TODO: Implement full static ability check
         let is_unblockable = card
-            .keywords
+            .current_keywords()
             .iter()
             .any(|k| matches!(k, Keyword::Other(s) if s.contains("can't be blocked") || s.contains("unblockable")));
 
@@ -138,15 +144,15 @@ impl HeuristicController {
             // Other evasion keywords - not yet in enum, check via Other variant
             // TODO: Add Fear, Intimidate, Skulk to Keyword enum
             let has_fear = card
-                .keywords
+                .current_keywords()
                 .iter()
                 .any(|k| matches!(k, Keyword::Other(s) if s.contains("Fear")));
             let has_intimidate = card
-                .keywords
+                .current_keywords()
                 .iter()
                 .any(|k| matches!(k, Keyword::Other(s) if s.contains("Intimidate")));
             let has_skulk = card
-                .keywords
+                .current_keywords()
                 .iter()
                 .any(|k| matches!(k, Keyword::Other(s) if s.contains("Skulk")));
 
@@ -201,11 +207,11 @@ impl HeuristicController {
             // Infect, Wither: Not in Keyword enum yet, check via Other
             // TODO: Add Infect, Wither to Keyword enum
             let has_infect = card
-                .keywords
+                .current_keywords()
                 .iter()
                 .any(|k| matches!(k, Keyword::Other(s) if s.contains("Infect")));
             let has_wither = card
-                .keywords
+                .current_keywords()
                 .iter()
                 .any(|k| matches!(k, Keyword::Other(s) if s.contains("Wither")));
 
@@ -305,6 +311,56 @@ impl HeuristicController {
         true
     }
 
+    /// Score an opening hand; a hand scoring 0 or less is worth a mulligan
+    ///
+    /// Reference: ComputerUtil.java scoreHand()
+    ///
+    /// `cards_to_return` is how many cards keeping this hand puts on the bottom
+    /// of the library (London mulligan). The Java AI:
+    /// 1. Never mulligans when the hand it would keep is below the threshold
+    /// 2. Rewards a hand that's about half lands (+10)
+    /// 3. Adds 2 for each card castable with the lands in hand
+    /// 4. Keeps any hand with a land once at the threshold
+    /// 5. Otherwise rejects hands with fewer than two lands, or only lands
+    ///
+    /// The Java check that the library has lands left is skipped: the view
+    /// doesn't show the library's contents.
+    fn score_hand(&self, view: &GameStateView, hand: &[CardId], cards_to_return: usize) -> i32 {
+        let final_hand_size = hand.len().saturating_sub(cards_to_return);
+        if final_hand_size < MULLIGAN_THRESHOLD {
+            return final_hand_size as i32;
+        }
+
+        let cards: Vec<&Card> = hand.iter().filter_map(|&id| view.get_card(id)).collect();
+        let land_count = cards.iter().filter(|c| c.is_land()).count();
+        let mut score = hand.len() as i32;
+
+        if hand.len() / 2 == land_count || hand.len() / 2 == land_count + 1 {
+            score += 10;
+        }
+
+        let castables = cards
+            .iter()
+            .filter(|c| c.mana_cost.cmc() as usize <= land_count)
+            .count();
+        score += castables as i32 * 2;
+
+        if final_hand_size == MULLIGAN_THRESHOLD && land_count > 0 {
+            return score;
+        }
+
+        if land_count < 2 {
+            // BAD hands: 0 or 1 lands
+            return 0;
+        }
+        if land_count == hand.len() && hand.len() > 1 {
+            // BAD hands: all lands
+            return 0;
+        }
+
+        score
+    }
+
     /// Choose the best land to play from available lands
     ///
     /// Reference: AiController.java:500-724 (chooseBestLandToPlay)
@@ -424,18 +480,71 @@ impl HeuristicController {
         None
     }
 
+    /// Score one mode of a modal spell for the current board
+    ///
+    /// Removal is only worth something if the opponent has creatures and pumps
+    /// only if we do; modes scoring 0 or less are not worth choosing.
+    ///
+    /// Reference: CharmAi.chooseOptionsAi() picks the first modes whose
+    /// sub-ability AI would play on its own; this approximates those checks.
+    fn evaluate_mode(&self, view: &GameStateView, effects: &[Effect]) -> i32 {
+        let creatures = |ours: bool| {
+            view.battlefield()
+                .iter()
+                .filter_map(|&id| view.get_card(id))
+                .filter(|c| c.is_creature() && (c.owner == self.player_id) == ours)
+                .count() as i32
+        };
+        let (our_creatures, their_creatures) = (creatures(true), creatures(false));
+
+        effects
+            .iter()
+            .map(|effect| match effect {
+                Effect::DealDamage { amount, .. } => 2 * amount,
+                Effect::DestroyPermanent { .. } | Effect::ExilePermanent { .. } => {
+                    if their_creatures > 0 {
+                        8
+                    } else {
+                        0
+                    }
+                }
+                Effect::DestroyAll { .. } => 4 * (their_creatures - our_creatures),
+                Effect::DrawCards { count, .. } => 3 * i32::from(*count),
+                Effect::GainLife { amount, .. } => *amount,
+                Effect::PumpCreature {
+                    power_bonus,
+                    toughness_bonus,
+                    ..
+                } => {
+                    if our_creatures > 0 {
+                        power_bonus + toughness_bonus
+                    } else {
+                        0
+                    }
+                }
+                Effect::PumpAll {
+                    power_bonus,
+                    toughness_bonus,
+                    ..
+                } => our_creatures * (power_bonus + toughness_bonus),
+                Effect::CreateToken { amount, .. } => 4 * i32::from(*amount),
+                _ => 1,
+            })
+            .sum()
+    }
+
     /// Calculate combat factors for an attacker against available blockers
     ///
     /// Reference: AiAttackController.SpellAbilityFactors.calculate() (lines 1374-1454)
     fn calculate_combat_factors(&self, attacker: &Card, view: &GameStateView) -> CombatFactors {
-        let _attacker_power = attacker.power.unwrap_or(0) as i32;
-        let _attacker_toughness = attacker.toughness.unwrap_or(0) as i32;
+        let _attacker_power = attacker.current_power() as i32;
+        let _attacker_toughness = attacker.current_toughness() as i32;
         let attacker_value = self.evaluate_creature(attacker);
 
         // Combat effect keywords (gain value even if blocked)
         let has_combat_effect = attacker.has_lifelink()
             || attacker
-                .keywords
+                .current_keywords()
                 .iter()
                 .any(|k| matches!(k, Keyword::Other(s) if s.contains("Wither") || s.contains("Afflict")));
 
@@ -453,7 +562,7 @@ impl HeuristicController {
         // Track if there are dangerous blockers (with combat effects)
         let dangerous_blockers_present = potential_blockers.iter().any(|b| {
             b.has_lifelink()
-                || b.keywords
+                || b.current_keywords()
                     .iter()
                     .any(|k| matches!(k, Keyword::Other(s) if s.contains("Wither")))
         });
@@ -467,8 +576,8 @@ impl HeuristicController {
 
         // Evaluate each potential blocker
         for blocker in &potential_blockers {
-            let _blocker_power = blocker.power.unwrap_or(0) as i32;
-            let _blocker_toughness = blocker.toughness.unwrap_or(0) as i32;
+            let _blocker_power = blocker.current_power() as i32;
+            let _blocker_toughness = blocker.current_toughness() as i32;
             let blocker_value = self.evaluate_creature(blocker);
 
             // Can this blocker kill the attacker?
@@ -489,7 +598,7 @@ impl HeuristicController {
                 // Check if this blocker is dangerous
                 let is_dangerous_blocker = blocker.has_lifelink()
                     || blocker
-                        .keywords
+                        .current_keywords()
                         .iter()
                         .any(|k| matches!(k, Keyword::Other(s) if s.contains("Wither")));
 
@@ -552,8 +661,8 @@ impl HeuristicController {
     ///
     /// Reference: ComputerUtilCombat.canDestroyBlocker()
     fn can_destroy_blocker(&self, attacker: &Card, blocker: &Card) -> bool {
-        let attacker_power = attacker.power.unwrap_or(0) as i32;
-        let blocker_toughness = blocker.toughness.unwrap_or(0) as i32;
+        let attacker_power = attacker.current_power() as i32;
+        let blocker_toughness = blocker.current_toughness() as i32;
 
         // Deathtouch kills any creature with toughness > 0
         if attacker.has_deathtouch() && blocker_toughness > 0 {
@@ -582,8 +691,8 @@ impl HeuristicController {
     ///
     /// Reference: ComputerUtilCombat.canDestroyAttacker()
     fn can_destroy_attacker(&self, attacker: &Card, blocker: &Card) -> bool {
-        let blocker_power = blocker.power.unwrap_or(0) as i32;
-        let attacker_toughness = attacker.toughness.unwrap_or(0) as i32;
+        let blocker_power = blocker.current_power() as i32;
+        let attacker_toughness = attacker.current_toughness() as i32;
 
         // Deathtouch kills any creature with toughness > 0
         if blocker.has_deathtouch() && attacker_toughness > 0 {
@@ -627,6 +736,60 @@ impl HeuristicController {
             .count()
     }
 
+    /// Decide what each chosen attacker attacks
+    ///
+    /// Loosely follows AiAttackController.choosePreferredDefender(): attack the
+    /// opponent with the lowest life, and when the attack can't be lethal to them,
+    /// send just enough power at the most loyal planeswalker the attack can kill,
+    /// and the rest at the player.
+    fn assign_attack_targets(
+        &self,
+        view: &GameStateView,
+        attackers: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
+        let weakest_player = defenders
+            .iter()
+            .filter_map(|&target| match target {
+                AttackTarget::Player(player_id) => Some((target, view.player_life(player_id))),
+                AttackTarget::Planeswalker(_) => None,
+            })
+            .min_by_key(|&(_, life)| life);
+        let Some((player, player_life)) = weakest_player.or_else(|| defenders.first().map(|&d| (d, i32::MAX))) else {
+            return SmallVec::new();
+        };
+        let power_of = |id: CardId| view.get_card(id).map_or(0, |c| c.current_power().max(0) as i32);
+        let total_power: i32 = attackers.iter().map(|&id| power_of(id)).sum();
+        let planeswalker = defenders
+            .iter()
+            .filter_map(|&target| match target {
+                AttackTarget::Planeswalker(id) => view.get_card(id).map(|c| (target, c.current_loyalty() as i32)),
+                AttackTarget::Player(_) => None,
+            })
+            .filter(|&(_, loyalty)| loyalty <= total_power)
+            .max_by_key(|&(_, loyalty)| loyalty);
+
+        let Some((planeswalker, loyalty)) = planeswalker.filter(|_| total_power < player_life) else {
+            return attackers.iter().map(|&id| (id, player)).collect();
+        };
+
+        // Biggest attackers go at the planeswalker until its loyalty is covered
+        let mut by_power: SmallVec<[CardId; 8]> = attackers.iter().copied().collect();
+        by_power.sort_by_key(|&id| std::cmp::Reverse(power_of(id)));
+        let mut assigned = 0;
+        by_power
+            .into_iter()
+            .map(|id| {
+                if assigned < loyalty {
+                    assigned += power_of(id);
+                    (id, planeswalker)
+                } else {
+                    (id, player)
+                }
+            })
+            .collect()
+    }
+
     /// Wrapper around should_attack that adds context about numerical advantage
     fn should_attack_with_context(
         &self,
@@ -639,7 +802,7 @@ impl HeuristicController {
         // This helps avoid stalemates where both sides have equal creatures
         if has_numerical_advantage {
             // With numerical advantage, attack with power > 0 creatures
-            let power = attacker.power.unwrap_or(0) as i32;
+            let power = attacker.current_power() as i32;
             if power > 0 {
                 // Still check basic combat factors for terrible situations
                 let factors = self.calculate_combat_factors(attacker, view);
@@ -661,7 +824,7 @@ impl HeuristicController {
     }
 
     fn should_attack(&self, attacker: &Card, view: &GameStateView) -> bool {
-        let power = attacker.power.unwrap_or(0) as i32;
+        let power = attacker.current_power() as i32;
 
         // Creatures with 0 power generally don't attack unless they have special abilities
         if power <= 0 {
@@ -758,7 +921,7 @@ impl HeuristicController {
             if !is_blocked {
                 // Add this attacker's damage
                 if let Some(attacker) = view.get_card(attacker_id) {
-                    let attacker_power = attacker.power.unwrap_or(0) as i32;
+                    let attacker_power = attacker.current_power() as i32;
                     damage += attacker_power;
 
                     // TODO: Handle trample damage (damage overflow from blocked attackers)
@@ -812,10 +975,10 @@ impl HeuristicController {
         attackers: &[CardId],
         current_blocks: &[(CardId, CardId)],
     ) -> bool {
-        let blocker_power = blocker.power.unwrap_or(0) as i32;
-        let blocker_toughness = blocker.toughness.unwrap_or(0) as i32;
-        let attacker_power = attacker.power.unwrap_or(0) as i32;
-        let attacker_toughness = attacker.toughness.unwrap_or(0) as i32;
+        let blocker_power = blocker.current_power() as i32;
+        let blocker_toughness = blocker.current_toughness() as i32;
+        let attacker_power = attacker.current_power() as i32;
+        let attacker_toughness = attacker.current_toughness() as i32;
 
         // Check for special blocking keywords
         let blocker_has_first_strike = blocker.has_first_strike() || blocker.has_double_strike();
@@ -943,6 +1106,55 @@ impl PlayerController for HeuristicController {
         choice
     }
 
+    fn choose_modes(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        modes: &[String],
+        min: u8,
+        max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        // Rank the modes by how much they'd do right now (X counted as 1)
+        let mut scored: Vec<(i32, usize)> = (0..modes.len())
+            .map(|mode| {
+                let score = view
+                    .get_card(spell)
+                    .map_or(0, |card| self.evaluate_mode(view, &card.spell_effects(&[mode], 1)));
+                (score, mode)
+            })
+            .collect();
+        scored.sort_by_key(|&(score, mode)| (-score, mode));
+
+        // Take the required number of modes, plus any other worthwhile ones up to the maximum
+        let mut chosen: SmallVec<[usize; 4]> = scored
+            .iter()
+            .enumerate()
+            .take_while(|&(i, &(score, _))| i < max as usize && (i < min as usize || score > 0))
+            .map(|(_, &(_, mode))| mode)
+            .collect();
+        chosen.sort_unstable();
+
+        view.logger()
+            .controller_choice("HEURISTIC", &format!("{} chose modes {:?}", view.player_name(), chosen));
+        chosen
+    }
+
+    fn choose_x_value(&mut self, view: &GameStateView, _spell: CardId, max_x: u8) -> u8 {
+        // Like ComputerUtilMana.determineLeftoverMana(), spend all available mana on X
+        view.logger()
+            .controller_choice("HEURISTIC", &format!("{} chose X = {}", view.player_name(), max_x));
+        max_x
+    }
+
+    fn choose_alternative_cost(&mut self, view: &GameStateView, _spell: CardId, _cost: &Cost) -> bool {
+        // Keep our permanents and cards when the mana cost can be paid instead
+        view.logger().controller_choice(
+            "HEURISTIC",
+            &format!("{} chose to pay the mana cost", view.player_name()),
+        );
+        false
+    }
+
     fn choose_targets(
         &mut self,
         view: &GameStateView,
@@ -996,14 +1208,49 @@ impl PlayerController for HeuristicController {
         targets
     }
 
+    fn choose_division(
+        &mut self,
+        view: &GameStateView,
+        _spell: CardId,
+        targets: &[CardId],
+        total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        // Like Forge's DamageDealAi when dividing damage: give each creature just
+        // enough to kill it, in target order, then put whatever is left on the first target
+        let mut division: SmallVec<[u32; 4]> = targets.iter().map(|_| 1).collect();
+        let mut remaining = total.saturating_sub(targets.len() as u32);
+        for (share, &target) in division.iter_mut().zip(targets) {
+            let lethal = view
+                .get_card(target)
+                .filter(|card| card.is_creature())
+                .map_or(0, |card| {
+                    (i32::from(card.current_toughness()) - card.damage_marked).max(1) as u32
+                });
+            let extra = lethal.saturating_sub(*share).min(remaining);
+            *share += extra;
+            remaining -= extra;
+        }
+        division[0] += remaining;
+
+        view.logger().controller_choice(
+            "HEURISTIC",
+            &format!("{} divided {} as {:?}", view.player_name(), total, division),
+        );
+        division
+    }
+
     fn choose_mana_sources_to_pay(
         &mut self,
-        _view: &GameStateView,
+        view: &GameStateView,
         cost: &ManaCost,
         available_sources: &[CardId],
     ) -> SmallVec<[CardId; 8]> {
-        // Simple greedy approach for now
-        // TODO: Implement intelligent mana tapping order from ComputerUtilMana
+        // Like ComputerUtilMana, tap the least flexible sources and keep the rest open
+        if let Some(plan) = view.mana_tap_plan(cost, available_sources) {
+            return plan;
+        }
+
+        // Can't pay: fall back to the first sources
         let mut sources = SmallVec::new();
         let needed = cost.cmc() as usize;
 
@@ -1014,11 +1261,37 @@ impl PlayerController for HeuristicController {
         sources
     }
 
-    fn choose_attackers(&mut self, view: &GameStateView, available_creatures: &[CardId]) -> SmallVec<[CardId; 8]> {
+    fn choose_mana_color(&mut self, view: &GameStateView, _source: CardId, colors: &[Color]) -> Color {
+        // Reference: ComputerUtilCard.getMostProminentColor() - the color our hand
+        // needs most (the first one on a tie)
+        let needed = |color: Color| -> u32 {
+            view.hand()
+                .iter()
+                .filter_map(|&id| view.get_card(id))
+                .map(|card| u32::from(card.mana_cost.symbols_of_color(color)))
+                .sum()
+        };
+        let color = colors
+            .iter()
+            .copied()
+            .rev()
+            .max_by_key(|&color| needed(color))
+            .unwrap_or(colors[0]);
+        view.logger()
+            .controller_choice("HEURISTIC", &format!("chose {color} mana"));
+        color
+    }
+
+    fn choose_attackers(
+        &mut self,
+        view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
         // Port of Java's AiAttackController.declareAttackers()
         // Reference: AiAttackController.java:818
 
-        let mut attackers = SmallVec::new();
+        let mut attackers: SmallVec<[CardId; 8]> = SmallVec::new();
 
         // Get creature cards
         let creatures: Vec<&Card> = available_creatures.iter().filter_map(|&id| view.get_card(id)).collect();
@@ -1060,7 +1333,7 @@ impl PlayerController for HeuristicController {
             );
         }
 
-        attackers
+        self.assign_attack_targets(view, &attackers, defenders)
     }
 
     fn choose_blockers(
@@ -1107,10 +1380,10 @@ impl PlayerController for HeuristicController {
                     // 2. Trade favorably (kill high-value attacker with low-value blocker)
                     // 3. Minimize damage taken
 
-                    let blocker_power = blocker.power.unwrap_or(0) as i32;
-                    let blocker_toughness = blocker.toughness.unwrap_or(0) as i32;
-                    let attacker_power = attacker.power.unwrap_or(0) as i32;
-                    let attacker_toughness = attacker.toughness.unwrap_or(0) as i32;
+                    let blocker_power = blocker.current_power() as i32;
+                    let blocker_toughness = blocker.current_toughness() as i32;
+                    let attacker_power = attacker.current_power() as i32;
+                    let attacker_toughness = attacker.current_toughness() as i32;
 
                     let can_kill = blocker_power >= attacker_toughness || blocker.has_deathtouch();
                     let will_survive = blocker_toughness > attacker_power;
@@ -1168,6 +1441,137 @@ impl PlayerController for HeuristicController {
         blockers.iter().copied().collect()
     }
 
+    fn choose_trigger_order(&mut self, _view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        // For now, put triggers on the stack in the order they triggered
+        // TODO: Order triggers so that, e.g., removal resolves before pump effects
+        (0..triggers.len()).collect()
+    }
+
+    fn choose_replacement_effect(&mut self, view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        // Prevention effects first, then our own effects (they're usually in our
+        // favor), otherwise in timestamp order (min_by_key returns the first minimum)
+        let choice = (0..effects.len())
+            .min_by_key(|&i| {
+                view.replacement_effect(effects[i]).map_or((true, true), |r| {
+                    (!r.effect.is_prevention(), r.controller != self.player_id)
+                })
+            })
+            .unwrap_or(0);
+        view.logger().controller_choice(
+            "HEURISTIC",
+            &format!("chose replacement effect {} of {}", choice, effects.len()),
+        );
+        effects[choice]
+    }
+
+    fn choose_legend_to_keep(&mut self, view: &GameStateView, legends: &[CardId]) -> CardId {
+        // Reference: LegendRuleAi.chooseSingleCard() - keep the one with the most
+        // counters (loyalty, +1/+1), then an untapped one, otherwise the newest
+        // (max_by_key returns the last maximum, i.e. the newest among ties)
+        let keep = legends
+            .iter()
+            .copied()
+            .max_by_key(|&id| {
+                view.get_card(id).map_or((0, false), |card| {
+                    let counters: u32 = card.counters.iter().map(|&(_, n)| n as u32).sum();
+                    (counters, !card.tapped)
+                })
+            })
+            .unwrap_or(legends[legends.len() - 1]);
+        view.logger().controller_choice(
+            "HEURISTIC",
+            &format!(
+                "chose to keep {} under the legend rule",
+                view.card_name(keep).unwrap_or_default()
+            ),
+        );
+        keep
+    }
+
+    fn choose_mulligan(&mut self, view: &GameStateView, hand: &[CardId], mulligans_taken: u8) -> bool {
+        // Reference: ComputerUtil.wantMulligan() - mulligan unless the hand scores above 0
+        let score = self.score_hand(view, hand, mulligans_taken as usize);
+        let mulligan = score <= 0;
+        view.logger().controller_choice(
+            "HEURISTIC",
+            &format!(
+                "chose to {} a {}-card hand (score {})",
+                if mulligan { "mulligan" } else { "keep" },
+                hand.len(),
+                score
+            ),
+        );
+        mulligan
+    }
+
+    fn choose_cards_to_bottom(&mut self, view: &GameStateView, hand: &[CardId], count: usize) -> SmallVec<[CardId; 7]> {
+        // Keep the hand about half lands: bottom a land while lands are the
+        // majority, otherwise the most expensive spell
+        let mut remaining: Vec<&Card> = hand.iter().filter_map(|&id| view.get_card(id)).collect();
+        let mut bottom = SmallVec::new();
+
+        while bottom.len() < count && !remaining.is_empty() {
+            let land_count = remaining.iter().filter(|c| c.is_land()).count();
+            let index = if land_count * 2 > remaining.len() {
+                remaining.iter().rposition(|c| c.is_land())
+            } else {
+                remaining
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| !c.is_land())
+                    .max_by_key(|(_, c)| c.mana_cost.cmc())
+                    .map(|(index, _)| index)
+            }
+            .unwrap_or(remaining.len() - 1);
+            bottom.push(remaining.remove(index).id);
+        }
+
+        bottom
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        view: &GameStateView,
+        _source: CardId,
+        cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        // Reference: ComputerUtil.chooseSacrificeType() and friends - pay with the
+        // least valuable cards
+        if let Cost::DiscardPattern { .. } = cost {
+            return self
+                .choose_cards_to_discard(view, candidates, count)
+                .into_iter()
+                .collect();
+        }
+
+        let mut cards: Vec<&Card> = candidates.iter().filter_map(|&id| view.get_card(id)).collect();
+        cards.sort_by_key(|c| {
+            if c.is_token {
+                0 // Tokens first
+            } else if c.is_creature() {
+                self.evaluate_creature(c)
+            } else if c.is_land() {
+                // Tapped lands have already been used this turn
+                if c.tapped {
+                    1
+                } else {
+                    2
+                }
+            } else {
+                100 + c.mana_cost.cmc() as i32
+            }
+        });
+
+        let chosen: SmallVec<[CardId; 8]> = cards.iter().take(count).map(|c| c.id).collect();
+        view.logger().controller_choice(
+            "HEURISTIC",
+            &format!("chose {} of {} cards to pay a cost", chosen.len(), candidates.len()),
+        );
+        chosen
+    }
+
     fn choose_cards_to_discard(
         &mut self,
         view: &GameStateView,
@@ -1242,4 +1646,141 @@ mod tests {
         controller.set_aggression(-5);
         assert_eq!(controller.aggression_level, 0);
     }
+
+    #[test]
+    fn test_mana_sources_keep_flexible_lands_untapped() {
+        use crate::core::{ActivatedAbility, CardType, Cost, Effect};
+
+        let mut game = crate::game::GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+
+        let city_id = game.next_entity_id();
+        let mut city = Card::new(city_id, "City of Brass".to_string(), p1_id);
+        city.types.push(CardType::Land);
+        let add_any = Effect::AddManaOfChoice {
+            player: PlayerId::new(0),
+            colors: crate::core::WUBRG.to_vec(),
+            amount: 1,
+        };
+        city.activated_abilities.push(ActivatedAbility::new(
+            Cost::Tap,
+            vec![add_any],
+            "Add one mana of any color.".to_string(),
+            true,
+        ));
+        game.cards.insert(city_id, city);
+        game.battlefield.add(city_id);
+
+        let mountain_id = game.next_entity_id();
+        let mut mountain = Card::new(mountain_id, "Mountain".to_string(), p1_id);
+        mountain.types.push(CardType::Land);
+        mountain.subtypes.push("Mountain".into());
+        game.cards.insert(mountain_id, mountain);
+        game.battlefield.add(mountain_id);
+
+        let mut controller = HeuristicController::new(p1_id);
+        let view = GameStateView::new(&game, p1_id);
+        let sources = [city_id, mountain_id];
+
+        // The Mountain pays for {R}, City of Brass for {W}
+        let chosen = controller.choose_mana_sources_to_pay(&view, &ManaCost::from_string("R"), &sources);
+        assert_eq!(chosen.as_slice(), &[mountain_id]);
+        let chosen = controller.choose_mana_sources_to_pay(&view, &ManaCost::from_string("W"), &sources);
+        assert_eq!(chosen.as_slice(), &[city_id]);
+        assert_eq!(view.mana_tap_plan(&ManaCost::from_string("WW"), &sources), None);
+    }
+
+    #[test]
+    fn test_sacrifices_least_valuable_permanent() {
+        use crate::core::CardType;
+
+        let mut game = crate::game::GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+
+        let add_creature = |game: &mut crate::game::GameState, name: &str, size: i8, is_token: bool| {
+            let card_id = game.next_entity_id();
+            let mut card = Card::new(card_id, name.to_string(), p1_id);
+            card.types.push(CardType::Creature);
+            card.power = Some(size);
+            card.toughness = Some(size);
+            card.is_token = is_token;
+            game.cards.insert(card_id, card);
+            game.battlefield.add(card_id);
+            card_id
+        };
+        let giant = add_creature(&mut game, "Hill Giant", 3, false);
+        let bear = add_creature(&mut game, "Grizzly Bears", 2, false);
+        let token = add_creature(&mut game, "Soldier", 1, true);
+
+        let mut controller = HeuristicController::new(p1_id);
+        let view = GameStateView::new(&game, p1_id);
+        let cost = Cost::parse("Sac<2/Creature>").unwrap();
+        let chosen = controller.choose_cards_for_cost(&view, giant, &cost, &[giant, bear, token], 2);
+        assert_eq!(chosen.as_slice(), &[token, bear]);
+    }
+
+    #[test]
+    fn test_choose_division_kills_creatures_first() {
+        use crate::core::CardType;
+        use crate::game::GameState;
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let mut targets = Vec::new();
+        for toughness in [1, 2] {
+            let id = game.next_card_id();
+            let mut card = Card::new(id, "Bear".to_string(), bob);
+            card.types.push(CardType::Creature);
+            card.power = Some(2);
+            card.toughness = Some(toughness);
+            game.cards.insert(id, card);
+            game.battlefield.add(id);
+            targets.push(id);
+        }
+
+        let mut controller = HeuristicController::new(alice);
+        let view = GameStateView::new(&game, alice);
+        let division = controller.choose_division(&view, EntityId::new(100), &targets, 4);
+        // Lethal damage to each, with the extra point on the first target
+        assert_eq!(division.as_slice(), &[2, 2]);
+    }
+
+    #[test]
+    fn test_mulligan_land_light_hands() {
+        use crate::core::CardType;
+        use crate::game::GameState;
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let mut cards = Vec::new();
+        for (name, cost) in [("Forest", ""); 3]
+            .into_iter()
+            .chain([("Grizzly Bears", "1G"); 2])
+            .chain([("Craw Wurm", "4GG"); 4])
+        {
+            let id = game.next_card_id();
+            let mut card = Card::new(id, name.to_string(), alice);
+            card.types.push(if cost.is_empty() {
+                CardType::Land
+            } else {
+                CardType::Creature
+            });
+            card.mana_cost = ManaCost::from_string(cost);
+            game.cards.insert(id, card);
+            cards.push(id);
+        }
+        let hand = &cards[..7];
+        let one_lander: Vec<CardId> = cards[2..].to_vec();
+
+        let mut controller = HeuristicController::new(alice);
+        let view = GameStateView::new(&game, alice);
+        assert!(!controller.choose_mulligan(&view, hand, 0));
+        // One land in seven: mulligan, unless we'd already be keeping fewer than five cards
+        assert!(controller.choose_mulligan(&view, &one_lander, 0));
+        assert!(!controller.choose_mulligan(&view, &one_lander, 3));
+
+        // Bottoming keeps lands and spells balanced, most expensive spells first
+        let bottom = controller.choose_cards_to_bottom(&view, hand, 2);
+        assert_eq!(bottom.as_slice(), &[hand[6], hand[5]]);
+    }
 }
@@ -2,10 +2,10 @@
 //!
 //! Reads player choices from stdin and displays game state using GameStateView
 
-use crate::core::{CardId, ManaCost, PlayerId, SpellAbility};
+use crate::core::{CardId, Color, Cost, ManaCost, PlayerId, ReplacementKey, SpellAbility};
 use crate::game::controller::GameStateView;
 use crate::game::controller::PlayerController;
-use crate::game::RichInputController;
+use crate::game::{AttackTarget, RichInputController, StackAbility};
 use smallvec::SmallVec;
 use std::io::{self, Write};
 
@@ -436,6 +436,90 @@ impl PlayerController for InteractiveController {
         }
     }
 
+    fn choose_modes(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        modes: &[String],
+        min: u8,
+        max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        let (min, max) = ((min as usize).min(modes.len()), (max as usize).min(modes.len()));
+        let spell_name = view.card_name(spell).unwrap_or_default();
+        println!("\n--- Modes for: {} ---", spell_name);
+        for (idx, mode) in modes.iter().enumerate() {
+            println!("  [{}] {}", idx, mode);
+        }
+
+        let mut chosen: SmallVec<[usize; 4]> = SmallVec::new();
+
+        if self.numeric_choices {
+            // Numeric mode: ask one at a time, allowing a pass once enough modes are chosen
+            for i in 0..max {
+                let optional = i >= min;
+                if let Some(choice) = self.get_user_choice(
+                    &format!(
+                        "Mode {} of {}-{} (0-{}{}):",
+                        i + 1,
+                        min,
+                        max,
+                        modes.len() - 1,
+                        if optional { ", 'p' to stop" } else { "" }
+                    ),
+                    modes.len(),
+                    optional,
+                ) {
+                    if !chosen.contains(&choice) {
+                        chosen.push(choice);
+                    }
+                } else if optional {
+                    break;
+                }
+            }
+        } else {
+            // Original mode: space-separated input
+            println!("\nChoose {}-{} modes (separated by space):", min, max);
+
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input).is_ok() {
+                for index_str in input.split_whitespace() {
+                    if let Ok(idx) = index_str.parse::<usize>() {
+                        if idx < modes.len() && !chosen.contains(&idx) && chosen.len() < max {
+                            chosen.push(idx);
+                        }
+                    }
+                }
+            }
+        }
+
+        // If the user chose too few modes, add the first remaining ones
+        for idx in 0..modes.len() {
+            if chosen.len() >= min {
+                break;
+            }
+            if !chosen.contains(&idx) {
+                chosen.push(idx);
+            }
+        }
+
+        chosen
+    }
+
+    fn choose_x_value(&mut self, view: &GameStateView, spell: CardId, max_x: u8) -> u8 {
+        let spell_name = view.card_name(spell).unwrap_or_default();
+        println!("\n--- Choose X for: {} ---", spell_name);
+        self.get_user_choice(&format!("Enter X (0-{}):", max_x), max_x as usize + 1, false)
+            .map_or(0, |x| x as u8)
+    }
+
+    fn choose_alternative_cost(&mut self, view: &GameStateView, spell: CardId, cost: &Cost) -> bool {
+        let spell_name = view.card_name(spell).unwrap_or_default();
+        println!("\n--- Choose how to pay for: {} ---", spell_name);
+        println!("  [0] Mana cost");
+        println!("  [1] Alternative cost: {:?}", cost);
+        self.get_user_choice("Mana or alternative cost (0-1):", 2, false) == Some(1)
+    }
+
     fn choose_targets(
         &mut self,
         view: &GameStateView,
@@ -492,6 +576,41 @@ impl PlayerController for InteractiveController {
         targets
     }
 
+    fn choose_division(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        targets: &[CardId],
+        total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        let spell_name = view.card_name(spell).unwrap_or_default();
+        println!("\n--- Divide {} among targets of: {} ---", total, spell_name);
+
+        // Ask for each target's share in turn; the last target gets what's left
+        let mut division = SmallVec::new();
+        let mut remaining = total;
+        for (idx, &target) in targets.iter().enumerate() {
+            let left_after = (targets.len() - idx - 1) as u32;
+            let name = view.card_name(target).unwrap_or_else(|| format!("Card {target:?}"));
+            let share = if left_after == 0 {
+                remaining
+            } else {
+                let max_share = remaining - left_after;
+                self.get_user_choice(
+                    &format!("Amount for {} (1-{}):", name, max_share),
+                    max_share as usize + 1,
+                    false,
+                )
+                .map_or(1, |choice| (choice as u32).max(1))
+            };
+            println!("  {} gets {}", name, share);
+            division.push(share);
+            remaining -= share;
+        }
+
+        division
+    }
+
     fn choose_mana_sources_to_pay(
         &mut self,
         view: &GameStateView,
@@ -532,13 +651,36 @@ impl PlayerController for InteractiveController {
         sources
     }
 
-    fn choose_attackers(&mut self, view: &GameStateView, available_creatures: &[CardId]) -> SmallVec<[CardId; 8]> {
+    fn choose_mana_color(&mut self, view: &GameStateView, source: CardId, colors: &[Color]) -> Color {
+        println!(
+            "\n--- Choose a color of mana for {} ---",
+            view.card_name(source).unwrap_or_default()
+        );
+        for (idx, color) in colors.iter().enumerate() {
+            println!("  [{}] {}", idx, color);
+        }
+        let choice = self.get_user_choice(&format!("Mana color (0-{}):", colors.len() - 1), colors.len(), false);
+        colors[choice.unwrap_or(0)]
+    }
+
+    fn choose_attackers(
+        &mut self,
+        view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
+        let Some(&defender) = defenders.first() else {
+            return SmallVec::new();
+        };
         if available_creatures.is_empty() {
             return SmallVec::new();
         }
 
         // Note: Attacker selection prompt is now printed by game loop before this method is called
-        let mut attackers = SmallVec::new();
+        let mut attackers: SmallVec<[(CardId, AttackTarget); 8]> = SmallVec::new();
+        let is_attacking = |attackers: &SmallVec<[(CardId, AttackTarget); 8]>, card_id: CardId| {
+            attackers.iter().any(|&(id, _)| id == card_id)
+        };
 
         if self.numeric_choices {
             // Numeric mode: 0 = Done, 1-N = creatures
@@ -548,7 +690,7 @@ impl PlayerController for InteractiveController {
                 for (idx, &card_id) in available_creatures.iter().enumerate() {
                     let name = view.card_name(card_id).unwrap_or_else(|| format!("Card {card_id:?}"));
                     let tapped = if view.is_tapped(card_id) { " (tapped)" } else { "" };
-                    let selected = if attackers.contains(&card_id) {
+                    let selected = if is_attacking(&attackers, card_id) {
                         " [SELECTED]"
                     } else {
                         ""
@@ -565,17 +707,42 @@ impl PlayerController for InteractiveController {
                         break; // Done
                     }
                     let card_id = available_creatures[choice - 1];
-                    if !attackers.contains(&card_id) {
-                        attackers.push(card_id);
+                    if is_attacking(&attackers, card_id) {
+                        continue;
                     }
+
+                    // Choose what it attacks when there are planeswalkers to attack
+                    let target = if defenders.len() > 1 {
+                        println!("Attack what?");
+                        for (idx, &target) in defenders.iter().enumerate() {
+                            println!("  [{}] {}", idx, view.attack_target_name(target));
+                        }
+                        match self.get_user_choice(
+                            &format!("Enter choice (0-{}):", defenders.len() - 1),
+                            defenders.len(),
+                            false,
+                        ) {
+                            Some(idx) => defenders[idx],
+                            None => break,
+                        }
+                    } else {
+                        defender
+                    };
+                    attackers.push((card_id, target));
                 } else {
                     break;
                 }
             }
         } else {
-            // Rich input mode: support "attack X" commands and buffering
+            // Rich input mode: support "attack X [at Y]" commands and buffering
             println!("Available creatures:");
             self.display_cards(view, available_creatures, "  ");
+            if defenders.len() > 1 {
+                println!("Can attack:");
+                for &target in defenders {
+                    println!("  {}", view.attack_target_name(target));
+                }
+            }
 
             loop {
                 // Check if we have a buffered command
@@ -583,7 +750,9 @@ impl PlayerController for InteractiveController {
                     cmd
                 } else {
                     // No buffered commands, read new input
-                    println!("\nSelect attackers ('attack X', numeric indices, 'done', or press Enter):");
+                    println!(
+                        "\nSelect attackers ('attack X', 'attack X at Y', numeric indices, 'done', or press Enter):"
+                    );
                     if self.read_and_buffer_commands().is_err() {
                         break;
                     }
@@ -604,16 +773,22 @@ impl PlayerController for InteractiveController {
                     break;
                 }
 
-                // Try parsing as "attack X" command
-                if let Some(card_pattern) = trimmed.strip_prefix("attack ") {
+                // Try parsing as "attack X" or "attack X at Y" command
+                if let Some(attack) = trimmed.strip_prefix("attack ") {
+                    let Some((card_pattern, target)) =
+                        RichInputController::parse_attack_clause(attack, view, defenders)
+                    else {
+                        eprintln!("Nothing to attack matches '{}'", attack);
+                        continue;
+                    };
                     let mut found = false;
                     for &creature_id in available_creatures {
                         if let Some(card_name) = view.card_name(creature_id) {
                             if RichInputController::card_matches(&card_name, card_pattern)
-                                && !attackers.contains(&creature_id)
+                                && !is_attacking(&attackers, creature_id)
                             {
-                                attackers.push(creature_id);
-                                println!("  Attacking with {}", card_name);
+                                attackers.push((creature_id, target));
+                                println!("  Attacking {} with {}", view.attack_target_name(target), card_name);
                                 found = true;
                                 break;
                             }
@@ -629,8 +804,8 @@ impl PlayerController for InteractiveController {
                 if let Ok(idx) = trimmed.parse::<usize>() {
                     if idx < available_creatures.len() {
                         let card_id = available_creatures[idx];
-                        if !attackers.contains(&card_id) {
-                            attackers.push(card_id);
+                        if !is_attacking(&attackers, card_id) {
+                            attackers.push((card_id, defender));
                             let name = view.card_name(card_id).unwrap_or_default();
                             println!("  Attacking with {}", name);
                         }
@@ -874,6 +1049,215 @@ impl PlayerController for InteractiveController {
         ordered
     }
 
+    fn choose_trigger_order(&mut self, view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        if triggers.len() <= 1 {
+            return (0..triggers.len()).collect();
+        }
+
+        println!("\n--- Trigger Order ---");
+        println!("Triggers (the first one put on the stack resolves last):");
+        for (idx, trigger) in triggers.iter().enumerate() {
+            let source = view.card_name(trigger.source).unwrap_or_default();
+            println!("  [{}] {}: {}", idx, source, trigger.description);
+        }
+
+        let mut ordered: SmallVec<[usize; 4]> = SmallVec::new();
+
+        if self.numeric_choices {
+            // Numeric mode: ask one at a time
+            for i in 0..triggers.len() {
+                if let Some(choice) = self.get_user_choice(
+                    &format!(
+                        "Trigger to put on the stack {} of {} (0-{}):",
+                        i + 1,
+                        triggers.len(),
+                        triggers.len() - 1
+                    ),
+                    triggers.len(),
+                    false,
+                ) {
+                    if !ordered.contains(&choice) {
+                        ordered.push(choice);
+                    }
+                }
+            }
+        } else {
+            // Original mode: space-separated input
+            println!("\nEnter trigger indices in the order to put them on the stack");
+            println!("(separated by space):");
+
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input).is_err() {
+                return (0..triggers.len()).collect();
+            }
+
+            for index_str in input.split_whitespace() {
+                if let Ok(idx) = index_str.parse::<usize>() {
+                    if idx < triggers.len() && !ordered.contains(&idx) {
+                        ordered.push(idx);
+                    }
+                }
+            }
+        }
+
+        // If user didn't specify all triggers, add remaining in original order
+        for idx in 0..triggers.len() {
+            if !ordered.contains(&idx) {
+                ordered.push(idx);
+            }
+        }
+
+        ordered
+    }
+
+    fn choose_replacement_effect(&mut self, view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        println!("\n--- Replacement Effects ---");
+        println!("Several effects would modify the same event; choose the one that applies first:");
+        for (idx, key) in effects.iter().enumerate() {
+            if let Some(replacement) = view.replacement_effect(*key) {
+                let source = replacement
+                    .source
+                    .and_then(|source| view.card_name(source))
+                    .unwrap_or_else(|| "Effect".to_string());
+                println!("  [{}] {}: {}", idx, source, replacement.effect.description);
+            }
+        }
+        let choice = self.get_user_choice(
+            &format!("Replacement effect to apply (0-{}):", effects.len() - 1),
+            effects.len(),
+            false,
+        );
+        effects[choice.unwrap_or(0)]
+    }
+
+    fn choose_legend_to_keep(&mut self, view: &GameStateView, legends: &[CardId]) -> CardId {
+        println!("\n--- Legend Rule ---");
+        println!("Choose the legendary permanent to keep; the rest go to the graveyard:");
+        for (idx, &card_id) in legends.iter().enumerate() {
+            println!(
+                "  [{}] {} ({})",
+                idx,
+                view.card_name(card_id).unwrap_or_default(),
+                card_id
+            );
+        }
+        let choice = self.get_user_choice(
+            &format!("Legend to keep (0-{}):", legends.len() - 1),
+            legends.len(),
+            false,
+        );
+        legends[choice.unwrap_or(0)]
+    }
+
+    fn choose_mulligan(&mut self, view: &GameStateView, hand: &[CardId], mulligans_taken: u8) -> bool {
+        println!("\n--- Opening Hand ({} mulligans taken) ---", mulligans_taken);
+        for &card_id in hand {
+            println!("  {}", view.card_name(card_id).unwrap_or_default());
+        }
+        println!("  [0] Keep");
+        println!("  [1] Mulligan");
+        self.get_user_choice("Keep or mulligan (0-1):", 2, false) == Some(1)
+    }
+
+    fn choose_cards_to_bottom(&mut self, view: &GameStateView, hand: &[CardId], count: usize) -> SmallVec<[CardId; 7]> {
+        println!("\n--- Put {} cards on the bottom of your library ---", count);
+        for (idx, &card_id) in hand.iter().enumerate() {
+            println!("  [{}] {}", idx, view.card_name(card_id).unwrap_or_default());
+        }
+
+        let mut bottom = SmallVec::new();
+
+        if self.numeric_choices {
+            // Numeric mode: loop and ask one at a time
+            while bottom.len() < count {
+                let Some(choice) = self.get_user_choice(
+                    &format!(
+                        "Choose card to put on the bottom ({}/{}, 0-{}):",
+                        bottom.len() + 1,
+                        count,
+                        hand.len() - 1
+                    ),
+                    hand.len(),
+                    false,
+                ) else {
+                    break;
+                };
+                if bottom.contains(&hand[choice]) {
+                    eprintln!("Card already selected, choose another.");
+                } else {
+                    bottom.push(hand[choice]);
+                }
+            }
+        } else {
+            // Original mode: space-separated input
+            println!("\nSelect cards to put on the bottom (enter indices separated by space):");
+
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input).is_ok() {
+                for index_str in input.split_whitespace() {
+                    if let Ok(idx) = index_str.parse::<usize>() {
+                        if idx < hand.len() && bottom.len() < count && !bottom.contains(&hand[idx]) {
+                            bottom.push(hand[idx]);
+                        }
+                    }
+                }
+            }
+        }
+
+        // If not enough cards selected, auto-select from the end of the hand
+        for &card in hand.iter().rev() {
+            if bottom.len() < count && !bottom.contains(&card) {
+                bottom.push(card);
+            }
+        }
+
+        bottom
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        view: &GameStateView,
+        _source: CardId,
+        _cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        println!("\n--- Choose {} cards to pay the cost ---", count);
+        for (idx, &card_id) in candidates.iter().enumerate() {
+            println!("  [{}] {}", idx, view.card_name(card_id).unwrap_or_default());
+        }
+
+        let mut chosen = SmallVec::new();
+        while chosen.len() < count {
+            let Some(choice) = self.get_user_choice(
+                &format!(
+                    "Choose card to pay the cost ({}/{}, 0-{}):",
+                    chosen.len() + 1,
+                    count,
+                    candidates.len() - 1
+                ),
+                candidates.len(),
+                false,
+            ) else {
+                break;
+            };
+            if chosen.contains(&candidates[choice]) {
+                eprintln!("Card already selected, choose another.");
+            } else {
+                chosen.push(candidates[choice]);
+            }
+        }
+
+        // If not enough cards selected, auto-select from the start
+        for &card in candidates {
+            if chosen.len() < count && !chosen.contains(&card) {
+                chosen.push(card);
+            }
+        }
+
+        chosen
+    }
+
     fn choose_cards_to_discard(
         &mut self,
         _view: &GameStateView,
@@ -90,6 +90,8 @@ pub struct GameLogger {
     show_choice_menu: bool,
     /// Enable state hash debugging (print hash before each logged action)
     debug_state_hash: bool,
+    /// Drop all messages (while events that were already logged are carried out again)
+    muted: bool,
 
     /// Bump allocator for temporary string formatting
     /// Reset after each format operation to avoid growth
@@ -110,6 +112,7 @@ impl GameLogger {
             output_mode: OutputMode::default(),
             show_choice_menu: false,
             debug_state_hash: false,
+            muted: false,
             format_bump: RefCell::new(Bump::new()),
             log_buffer: RefCell::new(Vec::new()),
         }
@@ -125,6 +128,7 @@ impl GameLogger {
             output_mode: OutputMode::default(),
             show_choice_menu: false,
             debug_state_hash: false,
+            muted: false,
             format_bump: RefCell::new(Bump::new()),
             log_buffer: RefCell::new(Vec::new()),
         }
@@ -295,6 +299,16 @@ impl GameLogger {
         self.debug_state_hash
     }
 
+    /// Drop all messages until unmuted
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    /// Check if messages are being dropped
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
     /// Reset the step header flag
     pub fn reset_step_header(&mut self) {
         self.step_header_printed = false;
@@ -333,7 +347,7 @@ impl GameLogger {
         let should_output = matches!(self.output_mode, OutputMode::Stdout | OutputMode::Both);
 
         // Early exit if message won't be used
-        if VerbosityLevel::Minimal > self.verbosity && !should_capture {
+        if self.muted || (VerbosityLevel::Minimal > self.verbosity && !should_capture) {
             return;
         }
 
@@ -359,7 +373,7 @@ impl GameLogger {
         let should_output = matches!(self.output_mode, OutputMode::Stdout | OutputMode::Both);
 
         // Early exit if message won't be used
-        if VerbosityLevel::Normal > self.verbosity && !should_capture {
+        if self.muted || (VerbosityLevel::Normal > self.verbosity && !should_capture) {
             return;
         }
 
@@ -385,7 +399,7 @@ impl GameLogger {
         let should_output = matches!(self.output_mode, OutputMode::Stdout | OutputMode::Both);
 
         // Early exit if message won't be used
-        if VerbosityLevel::Verbose > self.verbosity && !should_capture {
+        if self.muted || (VerbosityLevel::Verbose > self.verbosity && !should_capture) {
             return;
         }
 
@@ -417,7 +431,7 @@ impl GameLogger {
         let should_log = self.numeric_choices || self.verbosity >= VerbosityLevel::Normal;
 
         // Early exit if message won't be used
-        if !should_log && !should_capture {
+        if self.muted || (!should_log && !should_capture) {
             return;
         }
 
@@ -472,6 +486,7 @@ impl Clone for GameLogger {
             output_mode: self.output_mode,
             show_choice_menu: self.show_choice_menu,
             debug_state_hash: self.debug_state_hash,
+            muted: self.muted,
             format_bump: RefCell::new(Bump::new()),
             log_buffer: RefCell::new(Vec::new()),
         }
@@ -518,6 +533,7 @@ impl<'de> Deserialize<'de> for GameLogger {
             output_mode: data.output_mode,
             show_choice_menu: data.show_choice_menu,
             debug_state_hash: false,
+            muted: false,
             format_bump: RefCell::new(Bump::new()),
             log_buffer: RefCell::new(Vec::new()),
         })
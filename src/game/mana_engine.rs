@@ -13,15 +13,17 @@
 //!
 //! ## Mana Source Classification
 //!
-//! - **Simple sources**: Lands that produce a single specific color (e.g., Mountain → R, Plains → W)
+//! Sources come from the mana abilities parsed from card scripts (`AB$ Mana`), plus
+//! the intrinsic abilities of basic land types - see [`ManaSource::for_permanent`].
+//!
+//! - **Simple sources**: Noncreature permanents with a single "{T}: Add one mana" ability
+//!   of a fixed color (e.g., Mountain → R, Plains → W, Wastes → C)
 //!   - Cached as `ManaCapacity` counters (WUBRGC)
 //!   - O(1) query time - just compare counts
-//!   - Currently supports: Plains, Island, Swamp, Mountain, Forest, Wastes
 //!
-//! - **Complex sources**: Lands with choices or conditional costs (e.g., City of Brass → any color)
-//!   - Stored as list of `CardId`s for future search
-//!   - Not yet implemented - requires search algorithm
-//!   - Examples: dual lands, fetch lands, City of Brass
+//! - **Complex sources**: Everything else with a mana ability
+//!   - Examples: dual lands, City of Brass, Sol Ring, Llanowar Elves (which may be summoning sick)
+//!   - Handled by the `BacktrackingResolver`, an exact search
 //!
 //! ## Performance Characteristics
 //!
@@ -34,8 +36,10 @@
 //!   - Just arithmetic comparisons of cached counters
 //!   - Critical path for spell selection AI
 //!
-//! - **Query (with complex sources)**: Not yet implemented
-//!   - Will require small search (likely << 20 sources in practice)
+//! - **Query (with complex sources)**: Branch-and-bound search over the sources
+//!   - Exact: finds a payment whenever one exists, e.g. {W}{U} from a Plains/Island
+//!     and a Plains/Swamp dual
+//!   - Identical sources are interchangeable, so boards of basic lands stay cheap
 //!
 //! ## Integration with GameState
 //!
@@ -106,18 +110,18 @@
 //!
 //! # Future Enhancements
 //!
-//! - **Complex source handling**: Implement search algorithm for dual lands, City of Brass, etc.
-//! - **Creature mana abilities**: Recognize Llanowar Elves, Birds of Paradise
+//! - **Multi-color production**: Abilities adding several colors at once (Signets)
 //! - **Conditional sources**: Handle lands with tap conditions (e.g., "T: Add G if you control a Forest")
 //! - **Mana filtering**: Track color identity restrictions (e.g., Commander format)
 //! - **Cost reduction**: Handle effects like Goblin Electromancer that reduce spell costs
 
 use crate::core::{CardId, ManaCost, PlayerId};
 use crate::game::mana_payment::{
-    GreedyManaResolver, ManaColor, ManaPaymentResolver, ManaProduction, ManaProductionKind, ManaSource,
+    BacktrackingResolver, ManaColor, ManaPaymentResolver, ManaProductionKind, ManaSource, ManaTap, PaymentResult,
     SimpleManaResolver,
 };
 use crate::game::GameState;
+use smallvec::SmallVec;
 
 /// Maximum mana production capacity
 ///
@@ -220,6 +224,8 @@ pub struct ManaEngine {
     mana_sources: Vec<ManaSource>,
     /// Payment resolver (strategy pattern for complex mana handling)
     resolver: Box<dyn ManaPaymentResolver>,
+    /// How many Phyrexian symbols the player can pay for with life
+    max_life_payments: u8,
 }
 
 impl ManaEngine {
@@ -232,6 +238,7 @@ impl ManaEngine {
             simple_capacity: ManaCapacity::new(),
             mana_sources: Vec::new(),
             resolver: Box::new(SimpleManaResolver::new()),
+            max_life_payments: 0,
         }
     }
 
@@ -247,98 +254,45 @@ impl ManaEngine {
         self.complex_sources.clear();
         self.simple_capacity = ManaCapacity::new();
         self.mana_sources.clear();
+        self.max_life_payments = game.max_life_payments(self.player_id);
 
-        // Scan battlefield for mana-producing permanents owned by this player
-        // This includes lands and creatures with mana abilities (e.g., Llanowar Elves)
+        // Scan battlefield for permanents this player controls with mana abilities
+        // This includes lands and creatures like Llanowar Elves or artifacts like Sol Ring
         for &card_id in &game.battlefield.cards {
-            if let Ok(card) = game.cards.get(card_id) {
-                // Check if this is a mana-producing permanent owned by this player
-                let is_mana_source = card.is_land() || has_mana_ability(card);
-                if card.owner == self.player_id && is_mana_source {
-                    // Determine if this source has summoning sickness (for creatures with mana abilities)
-                    let has_summoning_sickness = if card.is_creature() {
-                        if let Some(entered_turn) = card.turn_entered_battlefield {
-                            entered_turn == game.turn.turn_number && !card.has_keyword(&crate::core::Keyword::Haste)
-                        } else {
-                            false
-                        }
-                    } else {
-                        false
-                    };
-
-                    // Determine the mana production type
-                    if let Some(color_char) = get_simple_mana_color(card.name.as_str()) {
-                        // Simple source - produces exactly one color
-                        let color = match color_char {
-                            'W' => ManaColor::White,
-                            'U' => ManaColor::Blue,
-                            'B' => ManaColor::Black,
-                            'R' => ManaColor::Red,
-                            'G' => ManaColor::Green,
-                            'C' => {
-                                // Colorless is handled separately in ManaProduction
-                                self.simple_sources.push(card_id);
-                                if !card.tapped {
-                                    self.simple_capacity.colorless += 1;
-                                }
-                                self.mana_sources.push(ManaSource {
-                                    card_id,
-                                    production: ManaProduction::free(ManaProductionKind::Colorless),
-                                    is_tapped: card.tapped,
-                                    has_summoning_sickness,
-                                });
-                                continue;
-                            }
-                            _ => continue, // Unknown color
-                        };
-
-                        self.simple_sources.push(card_id);
-                        if !card.tapped {
-                            match color {
-                                ManaColor::White => self.simple_capacity.white += 1,
-                                ManaColor::Blue => self.simple_capacity.blue += 1,
-                                ManaColor::Black => self.simple_capacity.black += 1,
-                                ManaColor::Red => self.simple_capacity.red += 1,
-                                ManaColor::Green => self.simple_capacity.green += 1,
-                            }
-                        }
+            let Ok(card) = game.cards.get(card_id) else {
+                continue;
+            };
+            if card.controller != self.player_id {
+                continue;
+            }
 
-                        self.mana_sources.push(ManaSource {
-                            card_id,
-                            production: ManaProduction::free(ManaProductionKind::Fixed(color)),
-                            is_tapped: card.tapped,
-                            has_summoning_sickness,
-                        });
-                    } else if card.is_creature() {
-                        // Check for creature mana abilities (Llanowar Elves, Birds of Paradise)
-                        if let Some(production) = get_creature_mana_production(card) {
-                            // Creatures with mana abilities are complex sources
-                            self.complex_sources.push(card_id);
-                            self.mana_sources.push(ManaSource {
-                                card_id,
-                                production,
-                                is_tapped: card.tapped,
-                                has_summoning_sickness,
-                            });
+            let sources = ManaSource::for_permanent(game, card);
+            match sources.as_slice() {
+                [] => continue,
+                // Simple source - a single "{T}: Add one mana" ability of a fixed color
+                [source] if source.production.is_simple() && !card.is_creature() => {
+                    self.simple_sources.push(card_id);
+                    if !card.tapped {
+                        match source.production.kind {
+                            ManaProductionKind::Fixed(ManaColor::White) => self.simple_capacity.white += 1,
+                            ManaProductionKind::Fixed(ManaColor::Blue) => self.simple_capacity.blue += 1,
+                            ManaProductionKind::Fixed(ManaColor::Black) => self.simple_capacity.black += 1,
+                            ManaProductionKind::Fixed(ManaColor::Red) => self.simple_capacity.red += 1,
+                            ManaProductionKind::Fixed(ManaColor::Green) => self.simple_capacity.green += 1,
+                            _ => self.simple_capacity.colorless += 1,
                         }
-                    } else if let Some(production) = get_complex_mana_production(card) {
-                        // Complex source - dual land or any-color land
-                        self.complex_sources.push(card_id);
-                        self.mana_sources.push(ManaSource {
-                            card_id,
-                            production,
-                            is_tapped: card.tapped,
-                            has_summoning_sickness,
-                        });
                     }
-                    // If we can't parse it, just ignore it for now
                 }
+                // Complex source - choices, costs, several abilities, or a creature
+                // that may have summoning sickness
+                _ => self.complex_sources.push(card_id),
             }
+            self.mana_sources.extend(sources);
         }
 
-        // Switch to GreedyManaResolver if we have complex sources
+        // Switch to BacktrackingResolver if we have complex sources
         if !self.complex_sources.is_empty() {
-            self.resolver = Box::new(GreedyManaResolver::new());
+            self.resolver = Box::new(BacktrackingResolver::new());
         } else {
             self.resolver = Box::new(SimpleManaResolver::new());
         }
@@ -348,9 +302,42 @@ impl ManaEngine {
     ///
     /// This considers all mana sources (simple and complex) and determines
     /// whether there exists a way to tap them to produce the required mana.
+    /// Phyrexian symbols may be paid with life the player can afford; costs with
+    /// only basic symbols take the resolver's direct path.
     pub fn can_pay(&self, cost: &ManaCost) -> bool {
         // Use the resolver to check payment
-        self.resolver.can_pay(cost, &self.mana_sources)
+        matches!(
+            self.resolver
+                .check_payment_with_life(cost, &self.mana_sources, self.max_life_payments),
+            PaymentResult::Yes(_)
+        )
+    }
+
+    /// Which sources to tap to pay for a cost, if it can be paid
+    ///
+    /// Phyrexian symbols may be paid with life, as in `can_pay`.
+    pub fn compute_tap_order(&self, cost: &ManaCost) -> Option<Vec<CardId>> {
+        match self
+            .resolver
+            .check_payment_with_life(cost, &self.mana_sources, self.max_life_payments)
+        {
+            PaymentResult::Yes(tap_order) => Some(tap_order),
+            _ => None,
+        }
+    }
+
+    /// The best plan for paying a cost: which abilities to activate for which
+    /// colors, and how many Phyrexian symbols to pay with life
+    ///
+    /// The plan keeps the most flexible sources untapped (see
+    /// [`BacktrackingResolver`]). `ManaTap::source` indexes `mana_sources()`.
+    pub fn compute_tap_plan(&self, cost: &ManaCost) -> Option<(SmallVec<[ManaTap; 8]>, u8)> {
+        BacktrackingResolver::new().solve_with_life(cost, &self.mana_sources, self.max_life_payments)
+    }
+
+    /// All mana abilities of the player's permanents, as of the last `update`
+    pub fn mana_sources(&self) -> &[ManaSource] {
+        &self.mana_sources
     }
 
     /// Get the current mana capacity from simple sources only
@@ -369,128 +356,19 @@ impl ManaEngine {
     }
 }
 
-/// Determine if a land is a simple mana source (produces exactly one color)
-///
-/// Returns the color character if it's a simple source: W, U, B, R, G, C
-/// Returns None if it's a complex source or not a basic land.
-fn get_simple_mana_color(land_name: &str) -> Option<char> {
-    match land_name {
-        "Plains" => Some('W'),
-        "Island" => Some('U'),
-        "Swamp" => Some('B'),
-        "Mountain" => Some('R'),
-        "Forest" => Some('G'),
-        // Wastes produces colorless
-        "Wastes" => Some('C'),
-        // Any other land is considered complex for now
-        _ => None,
-    }
-}
-
-/// Check if a creature has a mana-producing activated ability
-///
-/// Detects patterns like "{T}: Add {G}" or "Add one mana of any color" in oracle text.
-/// This is used to identify creatures like Llanowar Elves and Birds of Paradise.
-fn has_mana_ability(card: &crate::core::Card) -> bool {
-    use crate::core::CardType;
-
-    // Only creatures can have mana abilities (for Phase 4)
-    if !card.types.contains(&CardType::Creature) {
-        return false;
-    }
-
-    let text_lower = card.text.to_lowercase();
-    // Check for tap-to-add-mana patterns
-    // Examples: "{T}: Add {G}", "Add one mana of any color", "{T}: Add {C}"
-    text_lower.contains("{t}: add") || (text_lower.contains("add") && text_lower.contains("mana"))
-}
-
-/// Determine mana production for a creature with mana abilities
-///
-/// Analyzes oracle text to determine what mana a creature can produce.
-/// Examples: Llanowar Elves "{T}: Add {G}", Birds of Paradise "Add one mana of any color"
-fn get_creature_mana_production(card: &crate::core::Card) -> Option<ManaProduction> {
-    let text_lower = card.text.to_lowercase();
-
-    // Check for any-color production (Birds of Paradise pattern)
-    if text_lower.contains("any color") {
-        return Some(ManaProduction::free(ManaProductionKind::AnyColor));
-    }
-
-    // Check for specific color production patterns
-    // Pattern: "{T}: Add {G}" or similar
-    if text_lower.contains("{t}: add {w}") || text_lower.contains("add {w}") {
-        return Some(ManaProduction::free(ManaProductionKind::Fixed(ManaColor::White)));
-    }
-    if text_lower.contains("{t}: add {u}") || text_lower.contains("add {u}") {
-        return Some(ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Blue)));
-    }
-    if text_lower.contains("{t}: add {b}") || text_lower.contains("add {b}") {
-        return Some(ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Black)));
-    }
-    if text_lower.contains("{t}: add {r}") || text_lower.contains("add {r}") {
-        return Some(ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Red)));
-    }
-    if text_lower.contains("{t}: add {g}") || text_lower.contains("add {g}") {
-        return Some(ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Green)));
-    }
-    if text_lower.contains("{t}: add {c}") || text_lower.contains("add {c}") {
-        return Some(ManaProduction::free(ManaProductionKind::Colorless));
-    }
-
-    None
-}
-
-/// Determine mana production for complex lands
-///
-/// Analyzes card subtypes and abilities to determine what mana a land can produce.
-/// Returns None if this isn't a mana-producing land or should be handled by simple check.
-fn get_complex_mana_production(card: &crate::core::Card) -> Option<ManaProduction> {
-    use crate::core::CardType;
-
-    // Must be a land
-    if !card.types.contains(&CardType::Land) {
-        return None;
-    }
-
-    // Check for dual lands by looking at basic land subtypes
-    let mut colors = Vec::new();
-
-    // Check subtypes for basic land types
-    for subtype in &card.subtypes {
-        let color = match subtype.as_str() {
-            "Plains" => Some(ManaColor::White),
-            "Island" => Some(ManaColor::Blue),
-            "Swamp" => Some(ManaColor::Black),
-            "Mountain" => Some(ManaColor::Red),
-            "Forest" => Some(ManaColor::Green),
-            _ => None,
-        };
-        if let Some(c) = color {
-            colors.push(c);
-        }
-    }
-
-    // If we have exactly 2 basic land subtypes, it's a dual land
-    if colors.len() == 2 {
-        return Some(ManaProduction::free(ManaProductionKind::Choice(colors)));
-    }
-
-    // Check oracle text for any-color lands (City of Brass pattern)
-    // Example: "Add one mana of any color"
-    let text_lower = card.text.to_lowercase();
-    if text_lower.contains("any color") {
-        return Some(ManaProduction::free(ManaProductionKind::AnyColor));
-    }
-
-    // Not a complex source we can handle yet
-    None
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{Card, CardType};
+    use crate::core::{ActivatedAbility, Card, CardType, Cost, Effect};
+
+    /// Llanowar Elves' "{T}: Add {G}." as the loader parses it
+    fn tap_for_green() -> ActivatedAbility {
+        let effect = Effect::AddMana {
+            player: PlayerId::new(0),
+            mana: ManaCost::from_string("G"),
+        };
+        ActivatedAbility::new(Cost::Tap, vec![effect], "Add {G}.".to_string(), true)
+    }
 
     #[test]
     fn test_mana_capacity_total() {
@@ -526,6 +404,7 @@ mod tests {
             green: 1,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
         assert!(capacity.can_pay_simple(&cost));
     }
@@ -551,6 +430,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
         assert!(!capacity.can_pay_simple(&cost));
     }
@@ -578,22 +458,11 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
         assert!(capacity.can_pay_simple(&cost));
     }
 
-    #[test]
-    fn test_simple_mana_color_recognition() {
-        assert_eq!(get_simple_mana_color("Plains"), Some('W'));
-        assert_eq!(get_simple_mana_color("Island"), Some('U'));
-        assert_eq!(get_simple_mana_color("Swamp"), Some('B'));
-        assert_eq!(get_simple_mana_color("Mountain"), Some('R'));
-        assert_eq!(get_simple_mana_color("Forest"), Some('G'));
-        assert_eq!(get_simple_mana_color("Wastes"), Some('C'));
-        assert_eq!(get_simple_mana_color("City of Brass"), None);
-        assert_eq!(get_simple_mana_color("Taiga"), None);
-    }
-
     #[test]
     fn test_mana_engine_update_simple_sources() {
         let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
@@ -603,6 +472,7 @@ mod tests {
         let mountain_id = game.next_card_id();
         let mut mountain = Card::new(mountain_id, "Mountain".to_string(), p1_id);
         mountain.types.push(CardType::Land);
+        mountain.subtypes.push("Mountain".into());
         mountain.controller = p1_id;
         game.cards.insert(mountain_id, mountain);
         game.battlefield.add(mountain_id);
@@ -610,6 +480,7 @@ mod tests {
         let island_id = game.next_card_id();
         let mut island = Card::new(island_id, "Island".to_string(), p1_id);
         island.types.push(CardType::Land);
+        island.subtypes.push("Island".into());
         island.controller = p1_id;
         game.cards.insert(island_id, island);
         game.battlefield.add(island_id);
@@ -638,6 +509,7 @@ mod tests {
             let land_id = game.next_card_id();
             let mut land = Card::new(land_id, "Mountain".to_string(), p1_id);
             land.types.push(CardType::Land);
+            land.subtypes.push("Mountain".into());
             land.controller = p1_id;
             game.cards.insert(land_id, land);
             game.battlefield.add(land_id);
@@ -660,36 +532,38 @@ mod tests {
     }
 
     #[test]
-    fn test_creature_mana_ability_detection() {
-        use crate::core::EntityId;
-
-        let p1_id = EntityId::new(0);
-
-        // Test Llanowar Elves pattern: "{T}: Add {G}"
-        let mut llanowar = Card::new(EntityId::new(1), "Llanowar Elves".to_string(), p1_id);
-        llanowar.types.push(CardType::Creature);
-        llanowar.text = "{T}: Add {G}.".to_string();
-        assert!(has_mana_ability(&llanowar));
-        assert_eq!(
-            get_creature_mana_production(&llanowar),
-            Some(ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Green)))
-        );
-
-        // Test Birds of Paradise pattern: "Add one mana of any color"
-        let mut birds = Card::new(EntityId::new(2), "Birds of Paradise".to_string(), p1_id);
-        birds.types.push(CardType::Creature);
-        birds.text = "{T}: Add one mana of any color.".to_string();
-        assert!(has_mana_ability(&birds));
-        assert_eq!(
-            get_creature_mana_production(&birds),
-            Some(ManaProduction::free(ManaProductionKind::AnyColor))
-        );
-
-        // Test non-mana creature
-        let mut bear = Card::new(EntityId::new(3), "Grizzly Bears".to_string(), p1_id);
-        bear.types.push(CardType::Creature);
-        bear.text = "".to_string();
-        assert!(!has_mana_ability(&bear));
+    fn test_mana_engine_special_symbols() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+
+        // A Mountain and a Snow-Covered Forest
+        for (name, land_type, snow) in [("Mountain", "Mountain", false), ("Snow-Covered Forest", "Forest", true)] {
+            let land_id = game.next_card_id();
+            let mut land = Card::new(land_id, name.to_string(), p1_id);
+            land.types.push(CardType::Land);
+            land.subtypes.push(land_type.into());
+            if snow {
+                land.subtypes.push("Snow".into());
+            }
+            land.controller = p1_id;
+            game.cards.insert(land_id, land);
+            game.battlefield.add(land_id);
+        }
+
+        let mut engine = ManaEngine::new(p1_id);
+        engine.update(&game);
+        assert_eq!(engine.simple_sources().len(), 2);
+
+        assert!(engine.can_pay(&ManaCost::from_string("R S")));
+        assert!(!engine.can_pay(&ManaCost::from_string("S S")));
+        assert!(engine.can_pay(&ManaCost::from_string("R/W G/U")));
+        assert!(!engine.can_pay(&ManaCost::from_string("W/U")));
+
+        // Phyrexian symbols can be paid with life the player has
+        assert!(engine.can_pay(&ManaCost::from_string("R G B/P")));
+        game.get_player_mut(p1_id).unwrap().life = 1;
+        engine.update(&game);
+        assert!(!engine.can_pay(&ManaCost::from_string("R G B/P")));
     }
 
     #[test]
@@ -701,6 +575,7 @@ mod tests {
         let forest_id = game.next_card_id();
         let mut forest = Card::new(forest_id, "Forest".to_string(), p1_id);
         forest.types.push(CardType::Land);
+        forest.subtypes.push("Forest".into());
         forest.controller = p1_id;
         game.cards.insert(forest_id, forest);
         game.battlefield.add(forest_id);
@@ -710,6 +585,7 @@ mod tests {
         elf.types.push(CardType::Creature);
         elf.controller = p1_id;
         elf.text = "{T}: Add {G}.".to_string();
+        elf.activated_abilities.push(tap_for_green());
         elf.turn_entered_battlefield = Some(game.turn.turn_number - 1); // Not summoning sick
         game.cards.insert(elf_id, elf);
         game.battlefield.add(elf_id);
@@ -739,6 +615,7 @@ mod tests {
         let forest_id = game.next_card_id();
         let mut forest = Card::new(forest_id, "Forest".to_string(), p1_id);
         forest.types.push(CardType::Land);
+        forest.subtypes.push("Forest".into());
         forest.controller = p1_id;
         game.cards.insert(forest_id, forest);
         game.battlefield.add(forest_id);
@@ -749,6 +626,7 @@ mod tests {
         elf.types.push(CardType::Creature);
         elf.controller = p1_id;
         elf.text = "{T}: Add {G}.".to_string();
+        elf.activated_abilities.push(tap_for_green());
         elf.turn_entered_battlefield = Some(game.turn.turn_number); // Summoning sick!
         game.cards.insert(elf_id, elf);
         game.battlefield.add(elf_id);
@@ -774,4 +652,53 @@ mod tests {
         let gg_cost = ManaCost::from_string("GG");
         assert!(!engine.can_pay(&gg_cost)); // Can't use summoning-sick creature
     }
+
+    #[test]
+    fn test_mana_engine_uses_parsed_abilities() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+
+        // A Mountain and a Sol Ring ({T}: Add {C}{C})
+        let mountain_id = game.next_card_id();
+        let mut mountain = Card::new(mountain_id, "Mountain".to_string(), p1_id);
+        mountain.types.push(CardType::Land);
+        mountain.subtypes.push("Mountain".into());
+        game.cards.insert(mountain_id, mountain);
+        game.battlefield.add(mountain_id);
+
+        let ring_id = game.next_card_id();
+        let mut ring = Card::new(ring_id, "Sol Ring".to_string(), p1_id);
+        ring.types.push(CardType::Artifact);
+        let effect = Effect::AddMana {
+            player: PlayerId::new(0),
+            mana: ManaCost::from_string("C").multiply(2),
+        };
+        ring.activated_abilities.push(ActivatedAbility::new(
+            Cost::Tap,
+            vec![effect],
+            "Add {C}{C}.".to_string(),
+            true,
+        ));
+        game.cards.insert(ring_id, ring);
+        game.battlefield.add(ring_id);
+
+        // A land named like a basic land but without the basic land type isn't one
+        let fake_id = game.next_card_id();
+        let mut fake = Card::new(fake_id, "Forest".to_string(), p1_id);
+        fake.types.push(CardType::Land);
+        game.cards.insert(fake_id, fake);
+        game.battlefield.add(fake_id);
+
+        let mut engine = ManaEngine::new(p1_id);
+        engine.update(&game);
+        assert_eq!(engine.simple_sources(), &[mountain_id]);
+        assert_eq!(engine.complex_sources(), &[ring_id]);
+
+        assert!(engine.can_pay(&ManaCost::from_string("2R")));
+        assert!(!engine.can_pay(&ManaCost::from_string("3R")));
+        assert!(!engine.can_pay(&ManaCost::from_string("G")));
+
+        let tap_order = engine.compute_tap_order(&ManaCost::from_string("2R")).unwrap();
+        assert_eq!(tap_order, vec![mountain_id, ring_id]);
+    }
 }
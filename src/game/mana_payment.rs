@@ -10,7 +10,7 @@
 //!
 //! - **SimpleManaResolver**: Handles basic lands (Mountains, Islands, etc.)
 //! - **GreedyManaResolver**: Java Forge-style greedy algorithm for complex sources
-//! - **BacktrackingResolver**: Complete search for optimal solutions (future)
+//! - **BacktrackingResolver**: Exact branch-and-bound search returning an optimal tap plan
 //! - **OptimalResolver**: Graph-based optimal solver (future)
 //!
 //! # Example
@@ -29,7 +29,10 @@
 //! }
 //! ```
 
-use crate::core::{CardId, ManaCost};
+use crate::core::{ActivatedAbility, Card, CardId, Color, Cost, Effect, ManaCost};
+use crate::game::GameState;
+use rustc_hash::FxHashMap;
+use smallvec::SmallVec;
 
 /// Result of checking whether a mana cost can be paid
 ///
@@ -70,6 +73,57 @@ pub struct ManaSource {
     pub has_summoning_sickness: bool,
 }
 
+impl ManaSource {
+    /// The mana sources of a permanent, one per mana ability it can activate
+    ///
+    /// Abilities whose life payment the controller can't afford are left out
+    /// (MTG Rules 119.4). A permanent with several abilities yields several
+    /// sources sharing its `card_id`; resolvers tap each card at most once.
+    pub fn for_permanent(game: &GameState, card: &Card) -> SmallVec<[ManaSource; 2]> {
+        let life = game.get_player(card.controller).map_or(0, |p| p.life);
+        let has_summoning_sickness = game.has_summoning_sickness(card.id);
+        mana_productions(card)
+            .into_iter()
+            .filter(|production| i32::from(production.life_cost) <= life)
+            .map(|production| ManaSource {
+                card_id: card.id,
+                production,
+                is_tapped: card.tapped,
+                has_summoning_sickness,
+            })
+            .collect()
+    }
+}
+
+/// What each of a permanent's mana abilities produces
+///
+/// Abilities come from the card's `AB$ Mana` scripts. Lands without any get the
+/// intrinsic abilities of their basic land types (MTG Rules 305.6). Only abilities
+/// with {T} in their cost are included - others, like Dark Ritual's or a
+/// sacrifice-for-mana ability, aren't something to tap while paying a cost.
+pub fn mana_productions(card: &Card) -> SmallVec<[ManaProduction; 2]> {
+    let mut productions: SmallVec<[ManaProduction; 2]> = if card.activated_abilities.iter().any(|a| a.is_mana_ability) {
+        card.activated_abilities
+            .iter()
+            .filter_map(ManaProduction::from_ability)
+            .collect()
+    } else if card.is_land() {
+        card.basic_land_colors()
+            .into_iter()
+            .filter_map(ManaColor::from_color)
+            .map(|color| ManaProduction::free(ManaProductionKind::Fixed(color)))
+            .collect()
+    } else {
+        SmallVec::new()
+    };
+    if card.is_snow() {
+        for production in &mut productions {
+            production.is_snow = true;
+        }
+    }
+    productions
+}
+
 /// What mana a source can produce and at what cost
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ManaProduction {
@@ -79,6 +133,15 @@ pub struct ManaProduction {
     /// Optional activation cost (e.g., pay {2} to produce mana)
     /// None means no mana cost (tap-only or free ability)
     pub activation_cost: Option<ManaCost>,
+
+    /// Whether the source is a snow permanent, so its mana can pay for {S}
+    pub is_snow: bool,
+
+    /// How much mana one activation adds (e.g. 2 for Sol Ring)
+    pub amount: u8,
+
+    /// Life paid as part of the activation cost (e.g. 1 for Mana Confluence)
+    pub life_cost: u8,
 }
 
 impl ManaProduction {
@@ -87,6 +150,9 @@ impl ManaProduction {
         Self {
             kind,
             activation_cost: None,
+            is_snow: false,
+            amount: 1,
+            life_cost: 0,
         }
     }
 
@@ -95,15 +161,124 @@ impl ManaProduction {
         Self {
             kind,
             activation_cost: Some(cost),
+            is_snow: false,
+            amount: 1,
+            life_cost: 0,
+        }
+    }
+
+    /// The same production from a snow source (MTG Rules 107.4h)
+    pub fn snow(self) -> Self {
+        Self { is_snow: true, ..self }
+    }
+
+    /// The same production adding `amount` mana per activation
+    pub fn with_amount(self, amount: u8) -> Self {
+        Self { amount, ..self }
+    }
+
+    /// The same production with life to pay on activation
+    pub fn with_life_cost(self, life_cost: u8) -> Self {
+        Self { life_cost, ..self }
+    }
+
+    /// What a mana ability produces, if it's one a payment can tap for
+    ///
+    /// The cost must include {T} and may add mana and life payments
+    /// ("T", "1 T", "T PayLife<1>"). The first AddMana or AddManaOfChoice effect
+    /// decides the production; other effects, like a painland's damage, are
+    /// ignored here. Abilities adding several colors at once (Signets) aren't
+    /// supported yet.
+    pub fn from_ability(ability: &ActivatedAbility) -> Option<Self> {
+        if !ability.is_mana_ability {
+            return None;
+        }
+
+        let costs = match &ability.cost {
+            Cost::Composite(costs) => costs.as_slice(),
+            cost => std::slice::from_ref(cost),
+        };
+        let (mut taps, mut activation_cost, mut life_cost) = (false, None, 0u8);
+        for cost in costs {
+            match cost {
+                Cost::Tap => taps = true,
+                Cost::TapAndMana(mana) => {
+                    taps = true;
+                    activation_cost = Some(*mana);
+                }
+                Cost::Mana(mana) => activation_cost = Some(*mana),
+                Cost::PayLife { amount } => life_cost = u8::try_from(*amount).ok()?,
+                _ => return None,
+            }
         }
+        if !taps {
+            return None;
+        }
+
+        let (kind, amount) = ability.effects.iter().find_map(|effect| match effect {
+            Effect::AddMana { mana, .. } => Self::fixed_kind(mana),
+            Effect::AddManaOfChoice { colors, amount, .. } => {
+                let mut choices: Vec<ManaColor> = colors.iter().copied().filter_map(ManaColor::from_color).collect();
+                choices.dedup();
+                let kind = match choices.len() {
+                    0 if colors.contains(&Color::Colorless) => ManaProductionKind::Colorless,
+                    0 => return None,
+                    1 => ManaProductionKind::Fixed(choices[0]),
+                    5 => ManaProductionKind::AnyColor,
+                    _ => ManaProductionKind::Choice(choices),
+                };
+                Some((kind, *amount))
+            }
+            _ => None,
+        })?;
+
+        Some(Self {
+            kind,
+            activation_cost: activation_cost.filter(|cost| cost.cmc() > 0),
+            is_snow: false,
+            amount,
+            life_cost,
+        })
+    }
+
+    /// The kind and amount of a fixed AddMana, if it adds a single kind of mana
+    fn fixed_kind(mana: &ManaCost) -> Option<(ManaProductionKind, u8)> {
+        let amounts = [
+            (ManaProductionKind::Fixed(ManaColor::White), mana.white),
+            (ManaProductionKind::Fixed(ManaColor::Blue), mana.blue),
+            (ManaProductionKind::Fixed(ManaColor::Black), mana.black),
+            (ManaProductionKind::Fixed(ManaColor::Red), mana.red),
+            (ManaProductionKind::Fixed(ManaColor::Green), mana.green),
+            (ManaProductionKind::Colorless, mana.colorless),
+        ];
+        let mut produced = amounts.into_iter().filter(|(_, amount)| *amount > 0);
+        let first = produced.next()?;
+        produced.next().is_none().then_some(first)
     }
 
     /// Get the net mana delta (production - cost) for total mana bounds checking
     /// This is an i8 because you can have negative delta (pay more than you produce)
     pub fn net_delta(&self) -> i8 {
-        let production = 1; // Each source produces 1 mana (we'll handle Amount$ later)
         let cost = self.activation_cost.as_ref().map(|c| c.cmc() as i8).unwrap_or(0);
-        production - cost
+        self.amount as i8 - cost
+    }
+
+    /// Whether activating costs nothing beyond {T} and adds exactly one mana
+    pub fn is_simple(&self) -> bool {
+        self.amount == 1
+            && self.activation_cost.is_none()
+            && self.life_cost == 0
+            && matches!(self.kind, ManaProductionKind::Fixed(_) | ManaProductionKind::Colorless)
+    }
+
+    /// Whether this production can add mana of the given color
+    pub fn can_produce(&self, color: ManaColor) -> bool {
+        match &self.kind {
+            ManaProductionKind::Fixed(c) => *c == color,
+            ManaProductionKind::Choice(colors) => colors.contains(&color),
+            ManaProductionKind::AnyColor => true,
+            ManaProductionKind::Colorless => false,
+        }
     }
 }
 
@@ -145,6 +320,34 @@ impl ManaColor {
         }
     }
 
+    /// Position in WUBRG order
+    pub fn index(self) -> usize {
+        match self {
+            ManaColor::White => 0,
+            ManaColor::Blue => 1,
+            ManaColor::Black => 2,
+            ManaColor::Red => 3,
+            ManaColor::Green => 4,
+        }
+    }
+
+    /// Convert from a card color (None for colorless)
+    pub fn from_color(color: Color) -> Option<Self> {
+        match color {
+            Color::White => Some(ManaColor::White),
+            Color::Blue => Some(ManaColor::Blue),
+            Color::Black => Some(ManaColor::Black),
+            Color::Red => Some(ManaColor::Red),
+            Color::Green => Some(ManaColor::Green),
+            Color::Colorless => None,
+        }
+    }
+
+    /// Convert to the card color
+    pub fn to_color(self) -> Color {
+        crate::core::WUBRG[self.index()]
+    }
+
     /// Parse from single-character representation
     pub fn from_char(c: char) -> Option<Self> {
         match c {
@@ -169,8 +372,41 @@ pub trait ManaPaymentResolver {
     /// - `PaymentResult::Yes(tap_order)` if we found a solution
     /// - `PaymentResult::No` if we can prove it's impossible
     /// - `PaymentResult::Maybe` if our algorithm couldn't find a solution but one might exist
+    ///
+    /// Implementations only need to handle generic, colored, colorless and snow mana;
+    /// hybrid and Phyrexian symbols are expanded by `check_payment_with_life`.
     fn check_payment(&self, cost: &ManaCost, sources: &[ManaSource]) -> PaymentResult;
 
+    /// Check payment of a cost that may contain hybrid or Phyrexian symbols
+    ///
+    /// Up to `max_life_payments` Phyrexian symbols may be paid with 2 life each.
+    /// Costs with only basic symbols go straight to `check_payment`, so the common
+    /// case costs nothing extra. Otherwise each way of paying the symbols is tried,
+    /// mana before life: the first `Yes` wins, and `Maybe` is returned if no option
+    /// worked but some were uncertain.
+    fn check_payment_with_life(&self, cost: &ManaCost, sources: &[ManaSource], max_life_payments: u8) -> PaymentResult {
+        if !cost.has_special_symbols() {
+            return self.check_payment(cost, sources);
+        }
+
+        let mut result = PaymentResult::No;
+        cost.for_each_payment_option(
+            max_life_payments,
+            &mut |option, _| match self.check_payment(option, sources) {
+                PaymentResult::Yes(tap_order) => {
+                    result = PaymentResult::Yes(tap_order);
+                    true
+                }
+                PaymentResult::Maybe => {
+                    result = PaymentResult::Maybe;
+                    false
+                }
+                PaymentResult::No => false,
+            },
+        );
+        result
+    }
+
     /// Quick bounds check without attempting to construct a solution
     ///
     /// This is a fast pessimistic check that returns:
@@ -191,7 +427,7 @@ pub trait ManaPaymentResolver {
     /// This is pessimistic: `Maybe` is treated as `No`.
     /// Returns `true` only if we have a definite solution.
     fn can_pay(&self, cost: &ManaCost, sources: &[ManaSource]) -> bool {
-        matches!(self.check_payment(cost, sources), PaymentResult::Yes(_))
+        matches!(self.check_payment_with_life(cost, sources, 0), PaymentResult::Yes(_))
     }
 
     /// Compute the actual tap order for paying a cost
@@ -202,7 +438,7 @@ pub trait ManaPaymentResolver {
     /// The returned vector should contain exactly the cards needed to pay
     /// the cost, in the order they should be tapped.
     fn compute_tap_order(&self, cost: &ManaCost, sources: &[ManaSource]) -> Option<Vec<CardId>> {
-        match self.check_payment(cost, sources) {
+        match self.check_payment_with_life(cost, sources, 0) {
             PaymentResult::Yes(tap_order) => Some(tap_order),
             _ => None,
         }
@@ -211,13 +447,15 @@ pub trait ManaPaymentResolver {
 
 /// Simple resolver for basic lands only
 ///
-/// This is the initial implementation that only handles lands that produce
-/// a single fixed color (Plains, Island, Swamp, Mountain, Forest, Wastes).
+/// This is the initial implementation that only handles sources with a single
+/// "{T}: Add one mana" ability of a fixed color (Plains, Island, ..., Wastes).
+/// Anything else makes it answer `Maybe`.
 ///
 /// This resolver uses a straightforward algorithm:
 /// 1. Count available mana of each color
-/// 2. Match specific color requirements first
-/// 3. Use remaining sources for generic costs
+/// 2. Match specific color requirements first, keeping snow sources for {S}
+/// 3. Use remaining snow sources for snow requirements
+/// 4. Use remaining sources for generic costs
 pub struct SimpleManaResolver;
 
 impl SimpleManaResolver {
@@ -241,6 +479,8 @@ impl ManaPaymentResolver for SimpleManaResolver {
         let mut red = 0u8;
         let mut green = 0u8;
         let mut colorless = 0u8;
+        // Snow sources of each color (WUBRG, then colorless)
+        let mut snow = [0u8; 6];
         let mut has_complex = false;
 
         for source in sources {
@@ -248,15 +488,32 @@ impl ManaPaymentResolver for SimpleManaResolver {
                 continue;
             }
 
+            if !source.production.is_simple() {
+                // Sources that add more than one mana or cost more than {T}
+                // need the GreedyManaResolver
+                has_complex = true;
+                continue;
+            }
+
             match &source.production.kind {
-                ManaProductionKind::Fixed(color) => match color {
-                    ManaColor::White => white += 1,
-                    ManaColor::Blue => blue += 1,
-                    ManaColor::Black => black += 1,
-                    ManaColor::Red => red += 1,
-                    ManaColor::Green => green += 1,
-                },
-                ManaProductionKind::Colorless => colorless += 1,
+                ManaProductionKind::Fixed(color) => {
+                    match color {
+                        ManaColor::White => white += 1,
+                        ManaColor::Blue => blue += 1,
+                        ManaColor::Black => black += 1,
+                        ManaColor::Red => red += 1,
+                        ManaColor::Green => green += 1,
+                    }
+                    if source.production.is_snow {
+                        snow[color.index()] += 1;
+                    }
+                }
+                ManaProductionKind::Colorless => {
+                    colorless += 1;
+                    if source.production.is_snow {
+                        snow[5] += 1;
+                    }
+                }
                 _ => {
                     // SimpleManaResolver doesn't handle complex sources
                     // If we encounter any, we return Maybe (backtracking might help)
@@ -290,6 +547,17 @@ impl ManaPaymentResolver for SimpleManaResolver {
             return PaymentResult::No;
         }
 
+        // Snow symbols need snow sources left over once colored requirements
+        // have used up the non-snow sources of their color
+        if cost.snow > 0 {
+            let available = [white, blue, black, red, green, colorless];
+            let required = [cost.white, cost.blue, cost.black, cost.red, cost.green, cost.colorless];
+            let spare_snow: u8 = (0..6).map(|i| snow[i].min(available[i] - required[i])).sum();
+            if spare_snow < cost.snow {
+                return PaymentResult::No;
+            }
+        }
+
         // Check if we have enough total mana for generic requirement
         let total = white
             .saturating_add(blue)
@@ -304,7 +572,8 @@ impl ManaPaymentResolver for SimpleManaResolver {
             .saturating_add(cost.black)
             .saturating_add(cost.red)
             .saturating_add(cost.green)
-            .saturating_add(cost.colorless);
+            .saturating_add(cost.colorless)
+            .saturating_add(cost.snow);
 
         let remaining = total.saturating_sub(used);
 
@@ -316,20 +585,26 @@ impl ManaPaymentResolver for SimpleManaResolver {
         let mut tap_order = Vec::new();
         let mut remaining_cost = *cost;
 
-        // Helper to tap sources of a specific color
+        // Helper to tap sources of a specific color, keeping snow sources for {S}
         let mut tap_color = |color: ManaColor, amount: u8, sources: &[ManaSource]| {
             let mut tapped = 0;
-            for source in sources {
-                if tapped >= amount {
-                    break;
-                }
-                if source.is_tapped || source.has_summoning_sickness || tap_order.contains(&source.card_id) {
-                    continue;
-                }
-                if let ManaProductionKind::Fixed(c) = source.production.kind {
-                    if c == color {
-                        tap_order.push(source.card_id);
-                        tapped += 1;
+            for use_snow in [false, true] {
+                for source in sources {
+                    if tapped >= amount {
+                        break;
+                    }
+                    if source.is_tapped
+                        || source.has_summoning_sickness
+                        || source.production.is_snow != use_snow
+                        || tap_order.contains(&source.card_id)
+                    {
+                        continue;
+                    }
+                    if let ManaProductionKind::Fixed(c) = source.production.kind {
+                        if c == color {
+                            tap_order.push(source.card_id);
+                            tapped += 1;
+                        }
                     }
                 }
             }
@@ -353,19 +628,41 @@ impl ManaPaymentResolver for SimpleManaResolver {
 
         // Tap colorless sources for colorless requirement
         let mut tapped_colorless = 0;
+        for use_snow in [false, true] {
+            for source in sources {
+                if tapped_colorless >= remaining_cost.colorless {
+                    break;
+                }
+                if source.is_tapped
+                    || source.has_summoning_sickness
+                    || source.production.is_snow != use_snow
+                    || tap_order.contains(&source.card_id)
+                {
+                    continue;
+                }
+                if source.production.kind == ManaProductionKind::Colorless {
+                    tap_order.push(source.card_id);
+                    tapped_colorless += 1;
+                }
+            }
+        }
+        remaining_cost.colorless = 0;
+
+        // Tap snow sources for snow requirement
+        let mut tapped_snow = 0;
         for source in sources {
-            if tapped_colorless >= remaining_cost.colorless {
+            if tapped_snow >= remaining_cost.snow {
                 break;
             }
             if source.is_tapped || source.has_summoning_sickness || tap_order.contains(&source.card_id) {
                 continue;
             }
-            if source.production.kind == ManaProductionKind::Colorless {
+            if source.production.is_snow {
                 tap_order.push(source.card_id);
-                tapped_colorless += 1;
+                tapped_snow += 1;
             }
         }
-        remaining_cost.colorless = 0;
+        remaining_cost.snow = 0;
 
         // Tap any remaining sources for generic cost
         let mut tapped_generic = 0;
@@ -401,7 +698,8 @@ impl ManaPaymentResolver for SimpleManaResolver {
 ///    - Dual lands that produce that color (e.g., Taiga for R)
 ///    - Any-color sources (e.g., City of Brass)
 /// 2. Pay colorless requirements with Wastes
-/// 3. Pay generic requirements with any remaining sources
+/// 3. Pay snow requirements with remaining snow sources
+/// 4. Pay generic requirements with any remaining sources
 ///
 /// The greedy approach preserves more flexible sources (any-color lands)
 /// for later requirements when possible.
@@ -412,14 +710,76 @@ impl GreedyManaResolver {
         Self
     }
 
-    /// Check if a source can produce a specific color
-    fn can_produce_color(production: &ManaProduction, color: ManaColor) -> bool {
-        match &production.kind {
-            ManaProductionKind::Fixed(c) => *c == color,
-            ManaProductionKind::Choice(colors) => colors.contains(&color),
-            ManaProductionKind::AnyColor => true,
-            ManaProductionKind::Colorless => false,
+    /// Whether the cost provably needs more mana than the sources can make
+    ///
+    /// Checks the total (accounting for activation costs via net delta) and each
+    /// color requirement. For the color bounds activation costs are IGNORED
+    /// (treating all sources as free), an optimistic approximation that still
+    /// proves impossibility when even free mana couldn't meet a requirement.
+    /// A permanent with several mana abilities only taps once, so it counts with
+    /// its best ability for each bound.
+    fn exceeds_bounds(cost: &ManaCost, sources: &[ManaSource]) -> bool {
+        let usable = |s: &&ManaSource| !s.is_tapped && !s.has_summoning_sickness;
+
+        // For example, Celestial Prism ({2}, {T}: Add one mana of any color) has delta of -1
+        let mut available_delta: i16 = 0; // Use i16 to handle negative deltas
+                                          // Upper bounds of each color (WUBRG, then colorless) and of snow mana
+        let mut max_colors = [0u8; 6];
+        let mut max_snow = 0u8;
+
+        for (i, source) in sources.iter().enumerate() {
+            if !usable(&source) || sources[..i].iter().filter(usable).any(|s| s.card_id == source.card_id) {
+                continue;
+            }
+            let abilities: SmallVec<[&ManaProduction; 2]> = sources[i..]
+                .iter()
+                .filter(usable)
+                .filter(|s| s.card_id == source.card_id)
+                .map(|s| &s.production)
+                .collect();
+            let best = |amount: &dyn Fn(&ManaProduction) -> u8| abilities.iter().map(|p| amount(p)).max().unwrap_or(0);
+
+            available_delta += abilities.iter().map(|p| p.net_delta() as i16).max().unwrap_or(0);
+            for color in [
+                ManaColor::White,
+                ManaColor::Blue,
+                ManaColor::Black,
+                ManaColor::Red,
+                ManaColor::Green,
+            ] {
+                let produced = best(&|p| if p.can_produce(color) { p.amount } else { 0 });
+                max_colors[color.index()] = max_colors[color.index()].saturating_add(produced);
+            }
+            let colorless = best(&|p| {
+                if p.kind == ManaProductionKind::Colorless {
+                    p.amount
+                } else {
+                    0
+                }
+            });
+            max_colors[5] = max_colors[5].saturating_add(colorless);
+            max_snow = max_snow.saturating_add(best(&|p| if p.is_snow { p.amount } else { 0 }));
         }
+
+        let needed = cost
+            .white
+            .saturating_add(cost.blue)
+            .saturating_add(cost.black)
+            .saturating_add(cost.red)
+            .saturating_add(cost.green)
+            .saturating_add(cost.colorless)
+            .saturating_add(cost.snow)
+            .saturating_add(cost.generic);
+
+        // If available_delta < needed, we definitely can't pay
+        available_delta < needed as i16
+            || cost.white > max_colors[0]
+            || cost.blue > max_colors[1]
+            || cost.black > max_colors[2]
+            || cost.red > max_colors[3]
+            || cost.green > max_colors[4]
+            || cost.colorless > max_colors[5]
+            || cost.snow > max_snow
     }
 
     /// Score a source for a specific color (lower = better = more specific)
@@ -445,98 +805,7 @@ impl Default for GreedyManaResolver {
 impl ManaPaymentResolver for GreedyManaResolver {
     fn check_payment(&self, cost: &ManaCost, sources: &[ManaSource]) -> PaymentResult {
         // First, do bounds checking to see if we can prove "No"
-
-        // Check total available mana (accounting for activation costs)
-        // Use net delta: production - cost for each source
-        // For example, Celestial Prism ({2}, {T}: Add one mana of any color) has delta of -1
-        let mut available_delta: i16 = 0; // Use i16 to handle negative deltas
-        for source in sources {
-            if !source.is_tapped && !source.has_summoning_sickness {
-                available_delta += source.production.net_delta() as i16;
-            }
-        }
-
-        let needed = cost
-            .white
-            .saturating_add(cost.blue)
-            .saturating_add(cost.black)
-            .saturating_add(cost.red)
-            .saturating_add(cost.green)
-            .saturating_add(cost.colorless)
-            .saturating_add(cost.generic);
-
-        // Can only prove "No" if the total delta is negative and insufficient
-        // If available_delta < needed, we definitely can't pay
-        if available_delta < needed as i16 {
-            return PaymentResult::No; // Provably impossible - not enough total mana (accounting for costs)
-        }
-
-        // Check if we can produce enough of each required color
-        // NOTE: For upper bound color checking, we IGNORE activation costs
-        // (treating all sources as free). This is an optimistic approximation
-        // that allows us to prove impossibility when we can't even meet the
-        // color requirements with free mana.
-        let mut max_white = 0u8;
-        let mut max_blue = 0u8;
-        let mut max_black = 0u8;
-        let mut max_red = 0u8;
-        let mut max_green = 0u8;
-        let mut max_colorless = 0u8;
-
-        for source in sources {
-            if source.is_tapped || source.has_summoning_sickness {
-                continue;
-            }
-
-            match &source.production.kind {
-                ManaProductionKind::Fixed(color) => match color {
-                    ManaColor::White => max_white += 1,
-                    ManaColor::Blue => max_blue += 1,
-                    ManaColor::Black => max_black += 1,
-                    ManaColor::Red => max_red += 1,
-                    ManaColor::Green => max_green += 1,
-                },
-                ManaProductionKind::Colorless => max_colorless += 1,
-                ManaProductionKind::Choice(colors) => {
-                    // Choice lands count toward each color they can produce
-                    for color in colors {
-                        match color {
-                            ManaColor::White => max_white += 1,
-                            ManaColor::Blue => max_blue += 1,
-                            ManaColor::Black => max_black += 1,
-                            ManaColor::Red => max_red += 1,
-                            ManaColor::Green => max_green += 1,
-                        }
-                    }
-                }
-                ManaProductionKind::AnyColor => {
-                    // Any-color lands count toward all colors
-                    max_white += 1;
-                    max_blue += 1;
-                    max_black += 1;
-                    max_red += 1;
-                    max_green += 1;
-                }
-            }
-        }
-
-        // If we can't produce enough of a specific color, it's provably impossible
-        if cost.white > max_white {
-            return PaymentResult::No;
-        }
-        if cost.blue > max_blue {
-            return PaymentResult::No;
-        }
-        if cost.black > max_black {
-            return PaymentResult::No;
-        }
-        if cost.red > max_red {
-            return PaymentResult::No;
-        }
-        if cost.green > max_green {
-            return PaymentResult::No;
-        }
-        if cost.colorless > max_colorless {
+        if Self::exceeds_bounds(cost, sources) {
             return PaymentResult::No;
         }
 
@@ -555,78 +824,7 @@ impl ManaPaymentResolver for GreedyManaResolver {
 
     fn quick_check(&self, cost: &ManaCost, sources: &[ManaSource]) -> PaymentResult {
         // Same bounds checks as check_payment, but don't try greedy algorithm
-
-        // Check total available mana using net delta (accounting for activation costs)
-        let mut available_delta: i16 = 0;
-        for source in sources {
-            if !source.is_tapped && !source.has_summoning_sickness {
-                available_delta += source.production.net_delta() as i16;
-            }
-        }
-
-        let needed = cost
-            .white
-            .saturating_add(cost.blue)
-            .saturating_add(cost.black)
-            .saturating_add(cost.red)
-            .saturating_add(cost.green)
-            .saturating_add(cost.colorless)
-            .saturating_add(cost.generic);
-
-        if available_delta < needed as i16 {
-            return PaymentResult::No;
-        }
-
-        // Quick color bounds check (simplified - just check if impossible)
-        let mut max_white = 0u8;
-        let mut max_blue = 0u8;
-        let mut max_black = 0u8;
-        let mut max_red = 0u8;
-        let mut max_green = 0u8;
-        let mut max_colorless = 0u8;
-
-        for source in sources {
-            if source.is_tapped || source.has_summoning_sickness {
-                continue;
-            }
-
-            match &source.production.kind {
-                ManaProductionKind::Fixed(color) => match color {
-                    ManaColor::White => max_white += 1,
-                    ManaColor::Blue => max_blue += 1,
-                    ManaColor::Black => max_black += 1,
-                    ManaColor::Red => max_red += 1,
-                    ManaColor::Green => max_green += 1,
-                },
-                ManaProductionKind::Colorless => max_colorless += 1,
-                ManaProductionKind::Choice(colors) => {
-                    for color in colors {
-                        match color {
-                            ManaColor::White => max_white += 1,
-                            ManaColor::Blue => max_blue += 1,
-                            ManaColor::Black => max_black += 1,
-                            ManaColor::Red => max_red += 1,
-                            ManaColor::Green => max_green += 1,
-                        }
-                    }
-                }
-                ManaProductionKind::AnyColor => {
-                    max_white += 1;
-                    max_blue += 1;
-                    max_black += 1;
-                    max_red += 1;
-                    max_green += 1;
-                }
-            }
-        }
-
-        if cost.white > max_white
-            || cost.blue > max_blue
-            || cost.black > max_black
-            || cost.red > max_red
-            || cost.green > max_green
-            || cost.colorless > max_colorless
-        {
+        if Self::exceeds_bounds(cost, sources) {
             return PaymentResult::No;
         }
 
@@ -637,12 +835,16 @@ impl ManaPaymentResolver for GreedyManaResolver {
 
 impl GreedyManaResolver {
     /// Try to pay using greedy algorithm, return tap order if successful
+    ///
+    /// Mana a source adds beyond what a requirement needs (Sol Ring's second {C})
+    /// is kept as surplus towards the generic part of the cost.
     fn try_greedy_payment(&self, cost: &ManaCost, sources: &[ManaSource]) -> Option<Vec<CardId>> {
         let mut tap_order = Vec::new();
         let mut remaining_cost = *cost;
+        let mut surplus = 0u8;
 
         // Helper to tap sources for a specific color
-        let mut tap_for_color = |color: ManaColor, amount: u8| {
+        let mut tap_for_color = |color: ManaColor, amount: u8, tap_order: &mut Vec<CardId>| {
             let mut tapped = 0u8;
 
             // Create list of available sources that can produce this color
@@ -653,85 +855,126 @@ impl GreedyManaResolver {
                     !s.is_tapped
                         && !s.has_summoning_sickness
                         && !tap_order.contains(&s.card_id)
-                        && Self::can_produce_color(&s.production, color)
+                        && s.production.can_produce(color)
                 })
                 .map(|(idx, s)| (idx, Self::score_for_color(&s.production, color)))
                 .collect();
 
-            // Sort by score (lower = more specific = tap first)
-            candidates.sort_by_key(|(_, score)| *score);
+            // Sort by score (lower = more specific = tap first), avoiding life payments
+            // and keeping snow sources for {S}
+            candidates.sort_by_key(|&(idx, score)| {
+                let production = &sources[idx].production;
+                (score, production.life_cost, production.is_snow)
+            });
 
             // Tap sources in priority order
             for (idx, _score) in candidates {
                 if tapped >= amount {
                     break;
                 }
+                if tap_order.contains(&sources[idx].card_id) {
+                    continue; // Another ability of the same permanent
+                }
                 tap_order.push(sources[idx].card_id);
-                tapped += 1;
+                tapped = tapped.saturating_add(sources[idx].production.amount);
             }
 
+            surplus = surplus.saturating_add(tapped.saturating_sub(amount));
             tapped >= amount
         };
 
         // Pay specific color requirements first
-        if remaining_cost.white > 0 && !tap_for_color(ManaColor::White, remaining_cost.white) {
+        if remaining_cost.white > 0 && !tap_for_color(ManaColor::White, remaining_cost.white, &mut tap_order) {
             return None;
         }
         remaining_cost.white = 0;
 
-        if remaining_cost.blue > 0 && !tap_for_color(ManaColor::Blue, remaining_cost.blue) {
+        if remaining_cost.blue > 0 && !tap_for_color(ManaColor::Blue, remaining_cost.blue, &mut tap_order) {
             return None;
         }
         remaining_cost.blue = 0;
 
-        if remaining_cost.black > 0 && !tap_for_color(ManaColor::Black, remaining_cost.black) {
+        if remaining_cost.black > 0 && !tap_for_color(ManaColor::Black, remaining_cost.black, &mut tap_order) {
             return None;
         }
         remaining_cost.black = 0;
 
-        if remaining_cost.red > 0 && !tap_for_color(ManaColor::Red, remaining_cost.red) {
+        if remaining_cost.red > 0 && !tap_for_color(ManaColor::Red, remaining_cost.red, &mut tap_order) {
             return None;
         }
         remaining_cost.red = 0;
 
-        if remaining_cost.green > 0 && !tap_for_color(ManaColor::Green, remaining_cost.green) {
+        if remaining_cost.green > 0 && !tap_for_color(ManaColor::Green, remaining_cost.green, &mut tap_order) {
             return None;
         }
         remaining_cost.green = 0;
 
-        // Pay colorless requirement with colorless sources
+        let usable = |s: &ManaSource, tap_order: &[CardId]| {
+            !s.is_tapped && !s.has_summoning_sickness && !tap_order.contains(&s.card_id)
+        };
+
+        // Pay colorless requirement with colorless sources, keeping snow sources for {S}
         if remaining_cost.colorless > 0 {
             let mut tapped = 0u8;
-            for source in sources {
-                if tapped >= remaining_cost.colorless {
-                    break;
-                }
-                if source.is_tapped || source.has_summoning_sickness || tap_order.contains(&source.card_id) {
-                    continue;
-                }
-                if source.production.kind == ManaProductionKind::Colorless {
-                    tap_order.push(source.card_id);
-                    tapped += 1;
+            for use_snow in [false, true] {
+                for source in sources {
+                    if tapped >= remaining_cost.colorless {
+                        break;
+                    }
+                    if !usable(source, &tap_order) || source.production.is_snow != use_snow {
+                        continue;
+                    }
+                    if source.production.kind == ManaProductionKind::Colorless {
+                        tap_order.push(source.card_id);
+                        tapped = tapped.saturating_add(source.production.amount);
+                    }
                 }
             }
             if tapped < remaining_cost.colorless {
                 return None;
             }
+            surplus = surplus.saturating_add(tapped - remaining_cost.colorless);
         }
         remaining_cost.colorless = 0;
 
-        // Pay generic cost with any remaining sources
-        if remaining_cost.generic > 0 {
+        // Pay snow requirement with any remaining snow sources
+        if remaining_cost.snow > 0 {
             let mut tapped = 0u8;
             for source in sources {
-                if tapped >= remaining_cost.generic {
+                if tapped >= remaining_cost.snow {
                     break;
                 }
-                if source.is_tapped || source.has_summoning_sickness || tap_order.contains(&source.card_id) {
+                if !usable(source, &tap_order) {
                     continue;
                 }
-                tap_order.push(source.card_id);
-                tapped += 1;
+                if source.production.is_snow {
+                    tap_order.push(source.card_id);
+                    tapped = tapped.saturating_add(source.production.amount);
+                }
+            }
+            if tapped < remaining_cost.snow {
+                return None;
+            }
+            surplus = surplus.saturating_add(tapped - remaining_cost.snow);
+        }
+        remaining_cost.snow = 0;
+
+        // Pay generic cost with surplus mana, then any remaining sources,
+        // tapping ones that don't cost life or mana first
+        if remaining_cost.generic > surplus {
+            let mut tapped = surplus;
+            for free_only in [true, false] {
+                for source in sources {
+                    if tapped >= remaining_cost.generic {
+                        break;
+                    }
+                    let is_free = source.production.life_cost == 0 && source.production.activation_cost.is_none();
+                    if !usable(source, &tap_order) || (free_only && !is_free) {
+                        continue;
+                    }
+                    tap_order.push(source.card_id);
+                    tapped = tapped.saturating_add(source.production.amount);
+                }
             }
             if tapped < remaining_cost.generic {
                 return None;
@@ -742,6 +985,431 @@ impl GreedyManaResolver {
     }
 }
 
+/// Most permanents the `BacktrackingResolver` searches over; with more it defers
+/// to the `GreedyManaResolver`
+pub const MAX_SEARCH_PERMANENTS: usize = 64;
+
+/// One mana ability to activate in a payment plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ManaTap {
+    /// The permanent to tap
+    pub card_id: CardId,
+
+    /// Index of the ability's source in the slice given to the resolver
+    pub source: usize,
+
+    /// The color of mana to make (`Color::Colorless` for {C})
+    pub color: Color,
+}
+
+/// Exact resolver: a branch-and-bound search over which sources to tap
+///
+/// Each permanent is either left untapped or activates one of its mana abilities
+/// for one color, so a Plains/Island and a Plains/Swamp dual pay for {W}{U} even
+/// though tapping them in a greedy order might not. Activation costs are added to
+/// what has to be paid (hybrid symbols in them count as generic).
+///
+/// Among the payments that work, the plan chosen pays the least life, then keeps
+/// the most flexible permanents untapped (the sum of how many kinds of mana each
+/// tapped permanent could make), then taps the fewest permanents.
+///
+/// Search state lives in fixed-size arrays on the stack, up to
+/// `MAX_SEARCH_PERMANENTS` permanents. Identical sources are tapped in order, so
+/// a board of basic lands doesn't blow up the search, and branches stop as soon
+/// as the permanents left can't make enough mana or a color the cost still
+/// needs. Different taps of the earlier permanents often leave the same mana and
+/// cost for the rest, so each such state is remembered with the best score it was
+/// reached with, and reaching it again no better is skipped. The search always
+/// runs to the end, so its answer is exact.
+pub struct BacktrackingResolver;
+
+impl BacktrackingResolver {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// The best tap plan for a cost of generic, colored, colorless and snow symbols
+    ///
+    /// Returns None if the cost can't be paid or there are too many permanents to search.
+    pub fn solve(&self, cost: &ManaCost, sources: &[ManaSource]) -> Option<SmallVec<[ManaTap; 8]>> {
+        match ManaSearch::new(sources) {
+            Some(mut search) => search.run(cost).map(|(_, plan)| plan),
+            None => None,
+        }
+    }
+
+    /// The best tap plan for any cost, and how many Phyrexian symbols it pays with life
+    ///
+    /// Each way of paying hybrid and Phyrexian symbols is searched; the plan paying
+    /// the least life (including life paid by mana abilities) wins.
+    pub fn solve_with_life(
+        &self,
+        cost: &ManaCost,
+        sources: &[ManaSource],
+        max_life_payments: u8,
+    ) -> Option<(SmallVec<[ManaTap; 8]>, u8)> {
+        let mut search = ManaSearch::new(sources)?;
+        Self::run_with_life(&mut search, cost, max_life_payments)
+    }
+
+    /// Search each way of paying hybrid and Phyrexian symbols for the best plan
+    fn run_with_life(
+        search: &mut ManaSearch,
+        cost: &ManaCost,
+        max_life_payments: u8,
+    ) -> Option<(SmallVec<[ManaTap; 8]>, u8)> {
+        if !cost.has_special_symbols() {
+            return search.run(cost).map(|(_, plan)| (plan, 0));
+        }
+
+        let mut best: Option<(Score, SmallVec<[ManaTap; 8]>, u8)> = None;
+        cost.for_each_payment_option(max_life_payments, &mut |option, life_payments| {
+            if let Some((score, plan)) = search.run(option) {
+                let score = (score.0 + 2 * u16::from(life_payments), score.1, score.2);
+                if best.as_ref().is_none_or(|(best_score, _, _)| score < *best_score) {
+                    best = Some((score, plan, life_payments));
+                }
+            }
+            false
+        });
+        best.map(|(_, plan, life_payments)| (plan, life_payments))
+    }
+}
+
+impl Default for BacktrackingResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManaPaymentResolver for BacktrackingResolver {
+    fn check_payment(&self, cost: &ManaCost, sources: &[ManaSource]) -> PaymentResult {
+        self.check_payment_with_life(cost, sources, 0)
+    }
+
+    fn check_payment_with_life(&self, cost: &ManaCost, sources: &[ManaSource], max_life_payments: u8) -> PaymentResult {
+        let Some(mut search) = ManaSearch::new(sources) else {
+            return GreedyManaResolver::new().check_payment_with_life(cost, sources, max_life_payments);
+        };
+        match Self::run_with_life(&mut search, cost, max_life_payments) {
+            Some((plan, _)) => PaymentResult::Yes(plan.iter().map(|tap| tap.card_id).collect()),
+            None => PaymentResult::No,
+        }
+    }
+}
+
+/// Life paid, flexibility of the tapped permanents, and permanents tapped (lower is better)
+type Score = (u16, u16, u16);
+
+/// The ability and color (WUBRG index, 5 for colorless) chosen for each permanent
+type Path = [Option<(u16, u8)>; MAX_SEARCH_PERMANENTS];
+
+/// Where a search is: the next permanent, whether the one before it was tapped,
+/// the mana made so far, and the basic symbols left to pay (see `basic_symbols`)
+type SearchState = (u8, bool, SearchPool, [u8; 8]);
+
+/// Mana made so far in a search, by color (WUBRG, then colorless)
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct SearchPool {
+    normal: [u8; 6],
+    snow: [u8; 6],
+}
+
+impl SearchPool {
+    fn total(&self) -> i16 {
+        self.normal.iter().chain(&self.snow).map(|&n| i16::from(n)).sum()
+    }
+
+    /// How much of a color the cost asks for that the pool doesn't have
+    fn shortfall(&self, cost: &ManaCost, color: usize) -> u8 {
+        let required = [cost.white, cost.blue, cost.black, cost.red, cost.green, cost.colorless][color];
+        required.saturating_sub(self.normal[color].saturating_add(self.snow[color]))
+    }
+
+    /// Whether the pool pays the cost
+    ///
+    /// Colored requirements use non-snow mana first, keeping snow mana for {S};
+    /// whatever is left pays the generic part.
+    fn covers(&self, cost: &ManaCost) -> bool {
+        let required = [cost.white, cost.blue, cost.black, cost.red, cost.green, cost.colorless];
+        let (mut spare, mut spare_snow) = (0u16, 0u16);
+        for ((&required, &normal), &snow) in required.iter().zip(&self.normal).zip(&self.snow) {
+            let from_normal = normal.min(required);
+            let from_snow = required - from_normal;
+            if snow < from_snow {
+                return false;
+            }
+            spare += u16::from(normal - from_normal);
+            spare_snow += u16::from(snow - from_snow);
+        }
+        spare_snow >= u16::from(cost.snow) && spare + spare_snow - u16::from(cost.snow) >= u16::from(cost.generic)
+    }
+}
+
+/// Search state of the `BacktrackingResolver`
+struct ManaSearch<'a> {
+    sources: &'a [ManaSource],
+    /// First usable source of each permanent, most specific permanents first
+    permanents: [u16; MAX_SEARCH_PERMANENTS],
+    count: usize,
+    /// How many kinds of mana each permanent can make
+    flexibility: [u16; MAX_SEARCH_PERMANENTS],
+    /// Whether a permanent is interchangeable with the one before it
+    same_as_previous: [bool; MAX_SEARCH_PERMANENTS],
+    /// Best net mana the permanents from each index on could add
+    potential: [i16; MAX_SEARCH_PERMANENTS + 1],
+    /// Kinds of mana (see `kinds_mask`) the permanents from each index on can make
+    producible: [u8; MAX_SEARCH_PERMANENTS + 1],
+    path: Path,
+    best: Option<(Score, Path)>,
+    /// Best score each state of the current run has been reached with
+    seen: FxHashMap<SearchState, Score>,
+}
+
+impl<'a> ManaSearch<'a> {
+    /// Index the usable permanents, or None if there are too many
+    fn new(sources: &'a [ManaSource]) -> Option<Self> {
+        let usable = |s: &ManaSource| !s.is_tapped && !s.has_summoning_sickness;
+        let mut search = ManaSearch {
+            sources,
+            permanents: [0; MAX_SEARCH_PERMANENTS],
+            count: 0,
+            flexibility: [0; MAX_SEARCH_PERMANENTS],
+            same_as_previous: [false; MAX_SEARCH_PERMANENTS],
+            potential: [0; MAX_SEARCH_PERMANENTS + 1],
+            producible: [0; MAX_SEARCH_PERMANENTS + 1],
+            path: [None; MAX_SEARCH_PERMANENTS],
+            best: None,
+            seen: FxHashMap::default(),
+        };
+
+        for (i, source) in sources.iter().enumerate() {
+            if !usable(source) || sources[..i].iter().any(|s| usable(s) && s.card_id == source.card_id) {
+                continue;
+            }
+            if search.count == MAX_SEARCH_PERMANENTS {
+                return None;
+            }
+            search.permanents[search.count] = i as u16;
+            search.count += 1;
+        }
+
+        // Most specific permanents first, with identical ones next to each other
+        let key = |first: u16| {
+            let production = &sources[first as usize].production;
+            let mask = abilities_of(sources, first).fold(0u8, |mask, i| mask | kinds_mask(&sources[i].production));
+            let cost = production.activation_cost.map_or(0, |c| c.cmc());
+            (
+                mask.count_ones(),
+                mask,
+                production.amount,
+                production.life_cost,
+                cost,
+                production.is_snow,
+                first,
+            )
+        };
+        let mut permanents = search.permanents;
+        permanents[..search.count].sort_unstable_by_key(|&first| key(first));
+        search.permanents = permanents;
+
+        for p in 0..search.count {
+            let first = search.permanents[p];
+            search.flexibility[p] = key(first).0 as u16;
+            search.same_as_previous[p] = p > 0 && {
+                let previous = search.permanents[p - 1];
+                search.abilities_of(first).count() == 1
+                    && search.abilities_of(previous).count() == 1
+                    && sources[first as usize].production == sources[previous as usize].production
+            };
+        }
+        for p in (0..search.count).rev() {
+            let best = search
+                .abilities_of(search.permanents[p])
+                .map(|i| i16::from(sources[i].production.net_delta()))
+                .max()
+                .unwrap_or(0);
+            search.potential[p] = search.potential[p + 1] + best.max(0);
+            search.producible[p] = search.producible[p + 1] | key(search.permanents[p]).1;
+        }
+        Some(search)
+    }
+
+    /// Indices of the usable sources (abilities) of the permanent whose first source is `first`
+    fn abilities_of(&self, first: u16) -> impl Iterator<Item = usize> + 'a {
+        abilities_of(self.sources, first)
+    }
+
+    /// Find the best plan for a cost of basic symbols, with its score
+    fn run(&mut self, cost: &ManaCost) -> Option<(Score, SmallVec<[ManaTap; 8]>)> {
+        self.best = None;
+        self.seen.clear();
+        self.search(0, SearchPool::default(), *cost, (0, 0, 0));
+        let (score, path) = self.best?;
+
+        // Sources with activation costs go last, so mana to pay for them is in the pool
+        let mut plan: SmallVec<[ManaTap; 8]> = path[..self.count]
+            .iter()
+            .filter_map(|&choice| choice)
+            .map(|(source, color)| ManaTap {
+                card_id: self.sources[source as usize].card_id,
+                source: source as usize,
+                color: if color < 5 {
+                    crate::core::WUBRG[color as usize]
+                } else {
+                    Color::Colorless
+                },
+            })
+            .collect();
+        plan.sort_by_key(|tap| self.sources[tap.source].production.activation_cost.is_some());
+        Some((score, plan))
+    }
+
+    fn search(&mut self, p: usize, pool: SearchPool, cost: ManaCost, score: Score) {
+        if pool.covers(&cost) {
+            if self.best.as_ref().is_none_or(|(best, _)| score < *best) {
+                self.best = Some((score, self.path));
+            }
+            return;
+        }
+        // Covering the cost takes another tap, which can't beat the best plan,
+        // and there may not be enough mana left at all, or none of a color it needs
+        if p == self.count
+            || self.best.as_ref().is_some_and(|(best, _)| score >= *best)
+            || pool.total() + self.potential[p] < basic_symbols(&cost)
+            || (0..6).any(|color| self.producible[p] & (1 << color) == 0 && pool.shortfall(&cost, color) > 0)
+        {
+            return;
+        }
+
+        // The rest of the search only depends on the state, so reaching it again
+        // with a score no better than before can't find a better plan
+        let previous_tapped = self.same_as_previous[p] && self.path[p - 1].is_some();
+        let state = (p as u8, previous_tapped, pool, symbols_of(&cost));
+        match self.seen.get_mut(&state) {
+            Some(seen) if *seen <= score => return,
+            Some(seen) => *seen = score,
+            None => {
+                self.seen.insert(state, score);
+            }
+        }
+
+        // Of interchangeable permanents, only tap one if the one before it was tapped
+        if !(self.same_as_previous[p] && self.path[p - 1].is_none()) {
+            let first = self.permanents[p];
+            for source in self.abilities_of(first) {
+                let production = &self.sources[source].production;
+                let mut next_cost = cost;
+                if let Some(activation_cost) = &production.activation_cost {
+                    add_activation_cost(&mut next_cost, activation_cost);
+                }
+                let next_score = (
+                    score.0 + u16::from(production.life_cost),
+                    score.1 + self.flexibility[p],
+                    score.2 + 1,
+                );
+                let (colors, n) = color_options(production, &pool, &next_cost);
+                let (amount, is_snow) = (production.amount, production.is_snow);
+                for &color in &colors[..n] {
+                    let mut next_pool = pool;
+                    let slot = if is_snow {
+                        &mut next_pool.snow[color as usize]
+                    } else {
+                        &mut next_pool.normal[color as usize]
+                    };
+                    *slot = slot.saturating_add(amount);
+                    self.path[p] = Some((source as u16, color));
+                    self.search(p + 1, next_pool, next_cost, next_score);
+                }
+            }
+        }
+        self.path[p] = None;
+        self.search(p + 1, pool, cost, score);
+    }
+}
+
+/// Indices of the usable sources (abilities) of the permanent whose first source is `first`
+fn abilities_of(sources: &[ManaSource], first: u16) -> impl Iterator<Item = usize> + '_ {
+    let card_id = sources[first as usize].card_id;
+    (first as usize..sources.len()).filter(move |&i| {
+        let s = &sources[i];
+        s.card_id == card_id && !s.is_tapped && !s.has_summoning_sickness
+    })
+}
+
+/// Which kinds of mana a production can make, as bits (WUBRG, then colorless)
+fn kinds_mask(production: &ManaProduction) -> u8 {
+    match &production.kind {
+        ManaProductionKind::Fixed(color) => 1 << color.index(),
+        ManaProductionKind::Choice(colors) => colors.iter().fold(0, |mask, c| mask | 1 << c.index()),
+        ManaProductionKind::AnyColor => 0b1_1111,
+        ManaProductionKind::Colorless => 1 << 5,
+    }
+}
+
+/// The colors worth trying for a production: each one the cost is short of, and
+/// one other for the generic or snow part (the rest would do the same)
+fn color_options(production: &ManaProduction, pool: &SearchPool, cost: &ManaCost) -> ([u8; 6], usize) {
+    let mask = kinds_mask(production);
+    let (mut colors, mut n) = ([0u8; 6], 0);
+    let mut other = None;
+    for color in 0..6u8 {
+        if mask & (1 << color) == 0 {
+            continue;
+        }
+        if pool.shortfall(cost, color as usize) > 0 {
+            colors[n] = color;
+            n += 1;
+        } else if other.is_none() {
+            other = Some(color);
+        }
+    }
+    if let Some(color) = other {
+        colors[n] = color;
+        n += 1;
+    }
+    (colors, n)
+}
+
+/// Add a mana ability's activation cost to what has to be paid
+fn add_activation_cost(cost: &mut ManaCost, activation_cost: &ManaCost) {
+    let plain = activation_cost.generic
+        + activation_cost.white
+        + activation_cost.blue
+        + activation_cost.black
+        + activation_cost.red
+        + activation_cost.green
+        + activation_cost.colorless
+        + activation_cost.snow;
+    cost.white += activation_cost.white;
+    cost.blue += activation_cost.blue;
+    cost.black += activation_cost.black;
+    cost.red += activation_cost.red;
+    cost.green += activation_cost.green;
+    cost.colorless += activation_cost.colorless;
+    cost.snow += activation_cost.snow;
+    cost.generic += activation_cost.generic + (activation_cost.cmc() - plain);
+}
+
+/// A cost's generic, colored (WUBRG), colorless and snow symbols
+fn symbols_of(cost: &ManaCost) -> [u8; 8] {
+    [
+        cost.generic,
+        cost.white,
+        cost.blue,
+        cost.black,
+        cost.red,
+        cost.green,
+        cost.colorless,
+        cost.snow,
+    ]
+}
+
+/// Total of a cost's generic, colored, colorless and snow symbols
+fn basic_symbols(cost: &ManaCost) -> i16 {
+    symbols_of(cost).iter().map(|&n| i16::from(n)).sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -790,6 +1458,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         assert!(resolver.can_pay(&cost, &sources));
@@ -819,6 +1488,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         assert!(!resolver.can_pay(&cost, &sources));
@@ -853,6 +1523,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         // SimpleManaResolver conservatively rejects when complex sources present
@@ -889,6 +1560,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         assert!(resolver.can_pay(&cost, &sources));
@@ -922,6 +1594,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         assert!(resolver.can_pay(&cost, &sources));
@@ -964,6 +1637,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         let tap_order = resolver.compute_tap_order(&cost, &sources).unwrap();
@@ -1007,6 +1681,7 @@ mod tests {
             green: 1,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         assert!(resolver.can_pay(&cost, &sources));
@@ -1035,6 +1710,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         assert!(!resolver.can_pay(&cost, &sources));
@@ -1070,6 +1746,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         // SimpleManaResolver returns Maybe when it encounters complex sources
@@ -1100,6 +1777,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         let result = resolver.check_payment(&cost, &sources);
@@ -1133,6 +1811,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         let result = resolver.check_payment(&cost, &sources);
@@ -1170,6 +1849,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         // Should return No - provably impossible
@@ -1197,6 +1877,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         // quick_check never returns Yes, even when payment is possible
@@ -1268,6 +1949,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         // The bounds check should not reject this (total delta = 2, needed = 2)
@@ -1310,6 +1992,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         let result = resolver.check_payment(&cost, &sources);
@@ -1339,6 +2022,7 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         // The color bounds check should pass (we can produce red, ignoring cost)
@@ -1376,10 +2060,409 @@ mod tests {
             green: 0,
             colorless: 0,
             x_count: 0,
+            ..ManaCost::new()
         };
 
         // quick_check should return No (delta = 1, needed = 2)
         let result = resolver.quick_check(&cost, &sources);
         assert_eq!(result, PaymentResult::No);
     }
+
+    #[test]
+    fn test_simple_resolver_snow_sources() {
+        let resolver = SimpleManaResolver::new();
+
+        // Snow-Covered Forest and Forest
+        let sources = vec![
+            ManaSource {
+                card_id: CardId::new(1),
+                production: ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Green)).snow(),
+                is_tapped: false,
+                has_summoning_sickness: false,
+            },
+            ManaSource {
+                card_id: CardId::new(2),
+                production: ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Green)),
+                is_tapped: false,
+                has_summoning_sickness: false,
+            },
+        ];
+
+        // {G}{S}: the plain Forest pays {G}, keeping the snow land for {S}
+        let cost = ManaCost::from_string("G S");
+        let tap_order = resolver.compute_tap_order(&cost, &sources).unwrap();
+        assert_eq!(tap_order, vec![CardId::new(2), CardId::new(1)]);
+
+        // {S}{S} needs two snow sources
+        assert_eq!(
+            resolver.check_payment(&ManaCost::from_string("S S"), &sources),
+            PaymentResult::No
+        );
+    }
+
+    #[test]
+    fn test_greedy_resolver_hybrid_and_phyrexian() {
+        let resolver = GreedyManaResolver::new();
+
+        // Mountain and Badlands (B or R)
+        let sources = vec![
+            ManaSource {
+                card_id: CardId::new(1),
+                production: ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Red)),
+                is_tapped: false,
+                has_summoning_sickness: false,
+            },
+            ManaSource {
+                card_id: CardId::new(2),
+                production: ManaProduction::free(ManaProductionKind::Choice(vec![ManaColor::Black, ManaColor::Red])),
+                is_tapped: false,
+                has_summoning_sickness: false,
+            },
+        ];
+
+        // {R/G}{R/G} is paid with red from both lands
+        assert!(resolver.can_pay(&ManaCost::from_string("R/G R/G"), &sources));
+        // {W/U} can't be paid with red or black
+        assert!(!resolver.can_pay(&ManaCost::from_string("W/U"), &sources));
+        // {2/U} falls back to two generic mana
+        assert!(resolver.can_pay(&ManaCost::from_string("2/U"), &sources));
+
+        // {1}{G/P}{G/P}: needs life for both Phyrexian symbols
+        let cost = ManaCost::from_string("1 G/P G/P");
+        assert!(!resolver.can_pay(&cost, &sources));
+        assert_eq!(resolver.check_payment_with_life(&cost, &sources, 1), PaymentResult::No);
+        match resolver.check_payment_with_life(&cost, &sources, 2) {
+            PaymentResult::Yes(tap_order) => assert_eq!(tap_order.len(), 1),
+            other => panic!("Expected Yes, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_production_from_mana_abilities() {
+        use crate::loader::CardScript;
+
+        let parse = |line: &str| {
+            let script = CardScript::parse(&[line.to_string()], &[]);
+            let ability = &script.activated[0];
+            let effects = ability.to_effects();
+            let cost = ability.cost().unwrap();
+            ManaProduction::from_ability(&ActivatedAbility::new(cost, effects, String::new(), true))
+        };
+
+        // Llanowar Elves, Birds of Paradise, Sol Ring, Mana Confluence, Celestial Prism
+        assert_eq!(
+            parse("A:AB$ Mana | Cost$ T | Produced$ G"),
+            Some(ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Green)))
+        );
+        assert_eq!(
+            parse("A:AB$ Mana | Cost$ T | Produced$ Any"),
+            Some(ManaProduction::free(ManaProductionKind::AnyColor))
+        );
+        assert_eq!(
+            parse("A:AB$ Mana | Cost$ T | Produced$ C | Amount$ 2"),
+            Some(ManaProduction::free(ManaProductionKind::Colorless).with_amount(2))
+        );
+        assert_eq!(
+            parse("A:AB$ Mana | Cost$ T PayLife<1> | Produced$ Any"),
+            Some(ManaProduction::free(ManaProductionKind::AnyColor).with_life_cost(1))
+        );
+        assert_eq!(
+            parse("A:AB$ Mana | Cost$ 2 T | Produced$ Any"),
+            Some(ManaProduction::with_cost(
+                ManaProductionKind::AnyColor,
+                ManaCost::from_string("2")
+            ))
+        );
+        assert_eq!(
+            parse("A:AB$ Mana | Cost$ T | Produced$ Combo R W"),
+            Some(ManaProduction::free(ManaProductionKind::Choice(vec![
+                ManaColor::Red,
+                ManaColor::White
+            ])))
+        );
+
+        // Not tapped for while paying: no {T} in the cost, or several colors at once
+        assert_eq!(parse("A:AB$ Mana | Cost$ Sac<1/CARDNAME> | Produced$ Any"), None);
+        assert_eq!(parse("A:AB$ Mana | Cost$ 1 T | Produced$ R W"), None);
+    }
+
+    #[test]
+    fn test_mana_sources_for_permanents() {
+        use crate::core::CardType;
+
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1_id = game.players[0].id;
+
+        // Taiga taps for either of its basic land types
+        let taiga_id = game.next_card_id();
+        let mut taiga = Card::new(taiga_id, "Taiga".to_string(), p1_id);
+        taiga.types.push(CardType::Land);
+        taiga.subtypes.push("Mountain".into());
+        taiga.subtypes.push("Forest".into());
+        game.cards.insert(taiga_id, taiga);
+
+        // Mana Confluence: {T}, Pay 1 life: Add one mana of any color
+        let confluence_id = game.next_card_id();
+        let mut confluence = Card::new(confluence_id, "Mana Confluence".to_string(), p1_id);
+        confluence.types.push(CardType::Land);
+        let effect = Effect::AddManaOfChoice {
+            player: crate::core::PlayerId::new(0),
+            colors: crate::core::WUBRG.to_vec(),
+            amount: 1,
+        };
+        confluence.activated_abilities.push(ActivatedAbility::new(
+            Cost::parse("T PayLife<1>").unwrap(),
+            vec![effect],
+            "Add one mana of any color.".to_string(),
+            true,
+        ));
+        game.cards.insert(confluence_id, confluence);
+
+        let taiga = game.cards.get(taiga_id).unwrap();
+        let sources = ManaSource::for_permanent(&game, taiga);
+        let kinds: Vec<_> = sources.iter().map(|s| s.production.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ManaProductionKind::Fixed(ManaColor::Red),
+                ManaProductionKind::Fixed(ManaColor::Green)
+            ]
+        );
+        assert!(sources.iter().all(|s| s.card_id == taiga_id));
+
+        // Both of Taiga's abilities only count once towards the bounds
+        let resolver = GreedyManaResolver::new();
+        assert!(resolver.can_pay(&ManaCost::from_string("G"), &sources));
+        assert_eq!(
+            resolver.check_payment(&ManaCost::from_string("RG"), &sources),
+            PaymentResult::No
+        );
+
+        let confluence = game.cards.get(confluence_id).unwrap();
+        assert_eq!(ManaSource::for_permanent(&game, confluence).len(), 1);
+        game.get_player_mut(p1_id).unwrap().life = 0;
+        let confluence = game.cards.get(confluence_id).unwrap();
+        assert!(ManaSource::for_permanent(&game, confluence).is_empty());
+    }
+
+    #[test]
+    fn test_greedy_resolver_multiple_mana_sources() {
+        let resolver = GreedyManaResolver::new();
+
+        // Sol Ring and a Mountain pay for {2}{R}, but not {3}{R}
+        let sources = vec![
+            ManaSource {
+                card_id: CardId::new(1),
+                production: ManaProduction::free(ManaProductionKind::Colorless).with_amount(2),
+                is_tapped: false,
+                has_summoning_sickness: false,
+            },
+            ManaSource {
+                card_id: CardId::new(2),
+                production: ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Red)),
+                is_tapped: false,
+                has_summoning_sickness: false,
+            },
+        ];
+        assert_eq!(
+            resolver.compute_tap_order(&ManaCost::from_string("2R"), &sources),
+            Some(vec![CardId::new(2), CardId::new(1)])
+        );
+        assert_eq!(
+            resolver.compute_tap_order(&ManaCost::from_string("C"), &sources),
+            Some(vec![CardId::new(1)])
+        );
+        assert!(!resolver.can_pay(&ManaCost::from_string("3R"), &sources));
+
+        // The simple resolver leaves these to the greedy one
+        assert_eq!(
+            SimpleManaResolver::new().check_payment(&ManaCost::from_string("2R"), &sources),
+            PaymentResult::Maybe
+        );
+    }
+
+    fn untapped(card_id: u32, production: ManaProduction) -> ManaSource {
+        ManaSource {
+            card_id: CardId::new(card_id),
+            production,
+            is_tapped: false,
+            has_summoning_sickness: false,
+        }
+    }
+
+    fn dual(card_id: u32, a: ManaColor, b: ManaColor) -> ManaSource {
+        untapped(card_id, ManaProduction::free(ManaProductionKind::Choice(vec![a, b])))
+    }
+
+    #[test]
+    fn test_backtracking_resolver_finds_dual_land_payment() {
+        let resolver = BacktrackingResolver::new();
+
+        // Scrubland (W/B) and Tundra (W/U) pay for {W}{U} only if Scrubland makes the W
+        let sources = vec![
+            dual(1, ManaColor::White, ManaColor::Black),
+            dual(2, ManaColor::White, ManaColor::Blue),
+        ];
+        let plan = resolver.solve(&ManaCost::from_string("WU"), &sources).unwrap();
+        let mut taps: Vec<_> = plan.iter().map(|tap| (tap.card_id, tap.color)).collect();
+        taps.sort_by_key(|&(card_id, _)| card_id);
+        assert_eq!(
+            taps,
+            vec![(CardId::new(1), Color::White), (CardId::new(2), Color::Blue)]
+        );
+        assert!(resolver.can_pay(&ManaCost::from_string("WU"), &sources));
+
+        // But nothing pays for {U}{U}
+        assert_eq!(
+            resolver.check_payment(&ManaCost::from_string("UU"), &sources),
+            PaymentResult::No
+        );
+    }
+
+    #[test]
+    fn test_backtracking_resolver_keeps_flexible_sources_untapped() {
+        let resolver = BacktrackingResolver::new();
+
+        let sources = vec![
+            untapped(1, ManaProduction::free(ManaProductionKind::AnyColor)), // City of Brass
+            dual(2, ManaColor::Red, ManaColor::Green),                       // Taiga
+            untapped(3, ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Red))),
+            untapped(4, ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Green))),
+        ];
+
+        // The Mountain and Forest pay for {1}{R}
+        let plan = resolver.solve(&ManaCost::from_string("1R"), &sources).unwrap();
+        let mut tapped: Vec<_> = plan.iter().map(|tap| tap.card_id).collect();
+        tapped.sort();
+        assert_eq!(tapped, vec![CardId::new(3), CardId::new(4)]);
+
+        // {R}{R}{G} needs the Taiga but not City of Brass
+        let plan = resolver.solve(&ManaCost::from_string("RRG"), &sources).unwrap();
+        assert!(plan.iter().all(|tap| tap.card_id != CardId::new(1)));
+        assert_eq!(plan.len(), 3);
+
+        // {W} can only come from City of Brass
+        let plan = resolver.solve(&ManaCost::from_string("W"), &sources).unwrap();
+        assert_eq!(
+            plan.as_slice(),
+            &[ManaTap {
+                card_id: CardId::new(1),
+                source: 0,
+                color: Color::White
+            }]
+        );
+    }
+
+    #[test]
+    fn test_backtracking_resolver_costs_and_life() {
+        let resolver = BacktrackingResolver::new();
+
+        // A Mountain pays for Mana Prism's {1} to make {U}, so Mana Prism taps last
+        let sources = vec![
+            untapped(
+                1,
+                ManaProduction::with_cost(ManaProductionKind::AnyColor, ManaCost::from_string("1")),
+            ),
+            untapped(2, ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Red))),
+        ];
+        let plan = resolver.solve(&ManaCost::from_string("U"), &sources).unwrap();
+        assert_eq!(
+            plan.as_slice(),
+            &[
+                ManaTap {
+                    card_id: CardId::new(2),
+                    source: 1,
+                    color: Color::Red
+                },
+                ManaTap {
+                    card_id: CardId::new(1),
+                    source: 0,
+                    color: Color::Blue
+                },
+            ]
+        );
+        assert!(resolver.solve(&ManaCost::from_string("RU"), &sources).is_none());
+
+        // A painland's colored mana costs life, so colorless is used where it can be
+        let painland = vec![
+            untapped(1, ManaProduction::free(ManaProductionKind::Colorless)),
+            untapped(
+                1,
+                ManaProduction::free(ManaProductionKind::Choice(vec![ManaColor::Red, ManaColor::Green]))
+                    .with_life_cost(1),
+            ),
+            untapped(2, ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Red))),
+        ];
+        let (plan, life_payments) = resolver
+            .solve_with_life(&ManaCost::from_string("1R"), &painland, 10)
+            .unwrap();
+        assert_eq!(life_payments, 0);
+        assert!(plan
+            .iter()
+            .any(|tap| tap.card_id == CardId::new(1) && tap.color == Color::Colorless));
+
+        // {R/P}{R/P} is paid with the Mountain and 2 life
+        let (plan, life_payments) = resolver
+            .solve_with_life(&ManaCost::from_string("{R/P}{R/P}"), &sources, 10)
+            .unwrap();
+        assert_eq!(life_payments, 1);
+        assert_eq!(plan.len(), 1);
+    }
+
+    #[test]
+    fn test_backtracking_resolver_prunes_unproducible_colors() {
+        let resolver = BacktrackingResolver::new();
+
+        // Painlands ({C}, or {B}/{R} for 1 life) aren't interchangeable, so without
+        // pruning on colors an unpayable {W} would search 3^40 plans
+        let sources: Vec<_> = (0..40)
+            .flat_map(|i| {
+                [
+                    untapped(i, ManaProduction::free(ManaProductionKind::Colorless)),
+                    untapped(
+                        i,
+                        ManaProduction::free(ManaProductionKind::Choice(vec![ManaColor::Black, ManaColor::Red]))
+                            .with_life_cost(1),
+                    ),
+                ]
+            })
+            .collect();
+        assert_eq!(
+            resolver.check_payment(&ManaCost::from_string("W"), &sources),
+            PaymentResult::No
+        );
+        assert!(matches!(
+            resolver.check_payment(&ManaCost::from_string("1BC"), &sources),
+            PaymentResult::Yes(_)
+        ));
+        assert!(resolver.solve(&ManaCost::from_string("30RG"), &sources).is_none());
+
+        // Many taps leave the same mana and cost for the rest, so the search runs
+        // to the end: {20}{B}{R} takes 20 colorless and 2 life, tapping 22 painlands
+        let mut search = ManaSearch::new(&sources).unwrap();
+        let (score, plan) = search.run(&ManaCost::from_string("20BR")).unwrap();
+        assert_eq!(score, (2, 66, 22));
+        assert_eq!(plan.iter().filter(|tap| tap.color == Color::Colorless).count(), 20);
+        let (score, _) = search.run(&ManaCost::from_string("10BBRR")).unwrap();
+        assert_eq!(score, (4, 42, 14));
+    }
+
+    #[test]
+    fn test_backtracking_resolver_many_identical_sources() {
+        let resolver = BacktrackingResolver::new();
+
+        // Identical basics are interchangeable, so 60 of them search quickly
+        let mut sources: Vec<_> = (0..30)
+            .map(|i| untapped(i, ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Green))))
+            .collect();
+        sources.extend((30..60).map(|i| untapped(i, ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Blue)))));
+        let plan = resolver.solve(&ManaCost::from_string("20GU"), &sources).unwrap();
+        assert_eq!(plan.len(), 22);
+        assert!(resolver.solve(&ManaCost::from_string("60G"), &sources).is_none());
+
+        // Beyond the limit the greedy resolver answers instead
+        sources.extend((60..70).map(|i| untapped(i, ManaProduction::free(ManaProductionKind::Fixed(ManaColor::Red)))));
+        assert!(resolver.solve(&ManaCost::from_string("R"), &sources).is_none());
+        assert!(resolver.can_pay(&ManaCost::from_string("R"), &sources));
+    }
 }
@@ -1,7 +1,10 @@
 //! Core game state and turn structure
 
 pub mod actions;
+pub mod attachments;
 pub mod combat;
+pub mod commander;
+pub mod continuous_effects;
 pub mod controller;
 pub mod fixed_script_controller;
 pub mod game_loop;
@@ -12,41 +15,55 @@ pub mod interactive_controller;
 pub mod logger;
 pub mod mana_engine;
 pub mod mana_payment;
+pub mod multiplayer;
 pub mod phase;
+pub mod planeswalkers;
 pub mod random_controller;
+pub mod replacement;
 pub mod replay_controller;
 pub mod rich_input_controller;
 pub mod snapshot;
+pub mod stack;
 pub mod state;
+pub mod state_based_actions;
 pub mod state_hash;
 pub mod stop_condition;
+pub mod transposition;
 pub mod zero_controller;
 
 #[cfg(test)]
 mod controller_tests;
 #[cfg(test)]
 mod counter_tests;
+#[cfg(test)]
+mod test_support;
+#[cfg(test)]
+mod undo_tests;
 
 pub use actions::GameAction;
-pub use combat::CombatState;
+pub use combat::{AttackTarget, CombatState};
 pub use controller::{format_choice_menu, GameStateView, PlayerController};
 pub use fixed_script_controller::FixedScriptController;
 pub use game_loop::{GameEndReason, GameLoop, GameResult, VerbosityLevel};
 pub use game_state_evaluator::{GameStateEvaluator, Score};
-pub use hand_setup::{setup_opening_hands, HandSetup};
+pub use hand_setup::{setup_opening_hands, HandSetup, OPENING_HAND_SIZE};
 pub use heuristic_controller::HeuristicController;
 pub use interactive_controller::InteractiveController;
 pub use logger::{GameLogger, LogEntry, OutputFormat, OutputMode};
 pub use mana_engine::{ManaCapacity, ManaEngine};
 pub use mana_payment::{
-    GreedyManaResolver, ManaColor, ManaPaymentResolver, ManaProduction, ManaSource, PaymentResult, SimpleManaResolver,
+    BacktrackingResolver, GreedyManaResolver, ManaColor, ManaPaymentResolver, ManaProduction, ManaSource, ManaTap,
+    PaymentResult, SimpleManaResolver,
 };
 pub use phase::{Phase, Step, TurnStructure};
 pub use random_controller::RandomController;
+pub use replacement::{FunctioningReplacement, RegisteredReplacement, ReplacementChoice, ReplacementChoices};
 pub use replay_controller::{ReplayChoice, ReplayController};
 pub use rich_input_controller::RichInputController;
 pub use snapshot::{ControllerState, ControllerType, GameSnapshot, SnapshotError};
+pub use stack::{SpellChoices, Stack, StackAbility, StackObject};
 pub use state::GameState;
-pub use state_hash::{compute_state_hash, format_hash};
+pub use state_hash::{compute_state_hash, compute_zobrist_hash, format_hash};
 pub use stop_condition::{StopCondition, StopPlayer};
+pub use transposition::{Bound, TableEntry, TableStats, TranspositionTable};
 pub use zero_controller::ZeroController;
@@ -0,0 +1,272 @@
+//! Multiplayer games (MTG Rules 101.4, 800)
+//!
+//! Players sit in turn order, which is the order of `GameState::players`.
+//! Whenever several players act at once they do so in APNAP order: the active
+//! player first, then the others in turn order. A player who loses a game that
+//! has other players left leaves it, taking everything they own with them
+//! (MTG Rules 800.4a); turns, priority and attacks skip players who have left.
+
+use crate::core::{CardId, PlayerId};
+use crate::game::phase::next_in_turn_order;
+use crate::game::{GameState, StackObject};
+use crate::zones::Zone;
+use crate::Result;
+use smallvec::SmallVec;
+
+impl GameState {
+    /// Is this player still in the game?
+    pub fn is_in_game(&self, player_id: PlayerId) -> bool {
+        self.get_player(player_id).is_ok_and(|p| !p.has_lost)
+    }
+
+    /// How many players are still in the game
+    pub fn players_in_game(&self) -> usize {
+        self.players.iter().filter(|p| !p.has_lost).count()
+    }
+
+    /// The players still in the game in APNAP order: the active player
+    /// (unless they've left) followed by the others in turn order (MTG Rules 101.4)
+    pub fn players_in_apnap_order(&self) -> SmallVec<[PlayerId; 4]> {
+        let start = self.get_player_idx(self.turn.active_player).unwrap_or(0);
+        (0..self.players.len())
+            .map(|offset| &self.players[(start + offset) % self.players.len()])
+            .filter(|p| !p.has_lost)
+            .map(|p| p.id)
+            .collect()
+    }
+
+    /// The next player after `player_id` in turn order who is still in the game
+    ///
+    /// Returns `player_id` itself if nobody else is left.
+    pub fn next_player_in_turn_order(&self, player_id: PlayerId) -> PlayerId {
+        match self.get_player_idx(player_id) {
+            Some(idx) => self.players[next_in_turn_order(&self.players, idx)].id,
+            None => player_id,
+        }
+    }
+
+    /// The opponents of a player who are still in the game, in turn order
+    pub fn opponents_in_game(&self, player_id: PlayerId) -> SmallVec<[PlayerId; 4]> {
+        self.players
+            .iter()
+            .filter(|p| p.id != player_id && !p.has_lost)
+            .map(|p| p.id)
+            .collect()
+    }
+
+    /// The player an untargeted "deals damage" effect controlled by `player_id`
+    /// hits: the next opponent in turn order who is still in the game
+    pub fn untargeted_damage_recipient(&self, player_id: PlayerId) -> Option<PlayerId> {
+        let next = self.next_player_in_turn_order(player_id);
+        (next != player_id).then_some(next)
+    }
+
+    /// A player who lost leaves a game that continues without them (MTG Rules 800.4a)
+    ///
+    /// Everything they own leaves the game, spells and abilities they control
+    /// cease to exist, and permanents they control but don't own are exiled.
+    /// Creatures still attacking them deal no combat damage.
+    pub(crate) fn leave_game(&mut self, player_id: PlayerId) -> Result<()> {
+        // Spells they own and abilities they control on the stack, top first so
+        // that the logged indices stay valid when rewinding
+        for index in (0..self.stack.len()).rev() {
+            let leaves = match &self.stack.objects()[index] {
+                StackObject::Spell { card_id, .. } => self.cards.get(*card_id).is_ok_and(|c| c.owner == player_id),
+                StackObject::TriggeredAbility(ability) | StackObject::ActivatedAbility { ability, .. } => {
+                    ability.controller == player_id
+                }
+            };
+            if leaves {
+                let object = self.stack.remove_at(index).expect("index is within the stack");
+                self.undo_log.log(crate::undo::GameAction::RemoveFromStack {
+                    index,
+                    object: Box::new(object),
+                });
+            }
+        }
+        for index in (0..self.pending_triggers.len()).rev() {
+            if self.pending_triggers[index].controller == player_id {
+                let ability = self.pending_triggers.remove(index);
+                self.undo_log.log(crate::undo::GameAction::RemovePendingTrigger {
+                    index,
+                    ability: Box::new(ability),
+                });
+            }
+        }
+
+        // Permanents they own leave the game, last first so that rewinding
+        // restores the battlefield's order
+        let owned: Vec<CardId> = self
+            .battlefield
+            .cards
+            .iter()
+            .rev()
+            .copied()
+            .filter(|&id| self.cards.get(id).is_ok_and(|card| card.owner == player_id))
+            .collect();
+        for card_id in owned {
+            self.detach(card_id)?;
+            let index = self.battlefield.position(card_id).unwrap_or_default();
+            self.battlefield.remove(card_id);
+            self.undo_log.log(crate::undo::GameAction::CardLeavesGame {
+                card_id,
+                zone: Zone::Battlefield,
+                owner: player_id,
+                index,
+            });
+        }
+
+        // The cards in their other zones
+        for zone in [Zone::Library, Zone::Hand, Zone::Graveyard, Zone::Exile, Zone::Command] {
+            let Some(cards) = self.get_player_zones_mut(player_id).and_then(|z| z.get_zone_mut(zone)) else {
+                continue;
+            };
+            let leaving = std::mem::take(&mut cards.cards);
+            for (index, &card_id) in leaving.iter().enumerate().rev() {
+                self.undo_log.log(crate::undo::GameAction::CardLeavesGame {
+                    card_id,
+                    zone,
+                    owner: player_id,
+                    index,
+                });
+            }
+        }
+
+        // Control effects from the permanents that left end; whatever they
+        // still control is exiled
+        self.apply_continuous_effects();
+        let controlled: Vec<(CardId, PlayerId)> = self
+            .battlefield
+            .cards
+            .iter()
+            .filter_map(|&id| self.cards.get(id).ok())
+            .filter(|card| card.controller == player_id)
+            .map(|card| (card.id, card.owner))
+            .collect();
+        for (card_id, owner) in controlled {
+            self.move_card(card_id, Zone::Battlefield, Zone::Exile, owner)?;
+        }
+
+        let player = self.get_player_mut(player_id)?;
+        player.left_game = true;
+        let name = player.name.to_string();
+        self.undo_log
+            .log(crate::undo::GameAction::PlayerLeavesGame { player_id });
+        self.logger.normal(&format!("{name} leaves the game"));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{Card, CardId, CardType, Effect, PlayerId, TargetRef};
+    use crate::game::test_support::add_to_hand;
+    use crate::game::GameState;
+    use crate::zones::Zone;
+
+    fn four_player_game() -> GameState {
+        let names = ["Alice", "Bob", "Carol", "Dave"].map(String::from).to_vec();
+        GameState::new_multiplayer(names, 20)
+    }
+
+    fn add_creature(game: &mut GameState, owner: PlayerId, zone: Zone) -> CardId {
+        let id = game.next_card_id();
+        let mut card = Card::new(id, "Grizzly Bears".to_string(), owner);
+        card.types.push(CardType::Creature);
+        card.power = Some(2);
+        card.toughness = Some(2);
+        game.cards.insert(id, card);
+        match zone {
+            Zone::Battlefield => game.battlefield.add(id),
+            zone => game
+                .get_player_zones_mut(owner)
+                .and_then(|z| z.get_zone_mut(zone))
+                .unwrap()
+                .add(id),
+        }
+        id
+    }
+
+    #[test]
+    fn test_apnap_order_skips_players_who_left() {
+        let mut game = four_player_game();
+        let ids: Vec<PlayerId> = game.players.iter().map(|p| p.id).collect();
+
+        game.turn.active_player = ids[2];
+        assert_eq!(
+            game.players_in_apnap_order().to_vec(),
+            vec![ids[2], ids[3], ids[0], ids[1]]
+        );
+
+        game.get_player_mut(ids[3]).unwrap().has_lost = true;
+        assert_eq!(game.players_in_apnap_order().to_vec(), vec![ids[2], ids[0], ids[1]]);
+        assert_eq!(game.next_player_in_turn_order(ids[2]), ids[0]);
+        assert_eq!(game.opponents_in_game(ids[0]).to_vec(), vec![ids[1], ids[2]]);
+        assert_eq!(game.players_in_game(), 3);
+        assert!(!game.is_in_game(ids[3]));
+    }
+
+    #[test]
+    fn test_untargeted_damage_skips_players_who_left() {
+        let mut game = four_player_game();
+        let ids: Vec<PlayerId> = game.players.iter().map(|p| p.id).collect();
+        game.get_player_mut(ids[1]).unwrap().has_lost = true;
+        assert_eq!(game.untargeted_damage_recipient(ids[0]), Some(ids[2]));
+        assert_eq!(game.untargeted_damage_recipient(ids[3]), Some(ids[0]));
+
+        let shock = add_to_hand(&mut game, ids[0], "Shock", |card| {
+            card.types.push(CardType::Instant);
+            card.effects.push(Effect::DealDamage {
+                target: TargetRef::None,
+                amount: 2,
+            });
+        });
+        game.cast_spell(ids[0], shock, vec![]).unwrap();
+        game.resolve_spell(shock).unwrap();
+
+        // Bob has left, so the damage goes to Carol, the next seat still playing
+        assert_eq!(game.get_player(ids[1]).unwrap().life, 20);
+        assert_eq!(game.get_player(ids[2]).unwrap().life, 18);
+        assert_eq!(game.get_player(ids[3]).unwrap().life, 20);
+    }
+
+    #[test]
+    fn test_loser_leaves_game_and_undo_restores_them() {
+        let mut game = four_player_game();
+        let (alice, bob, carol) = (game.players[0].id, game.players[1].id, game.players[2].id);
+
+        let bobs_bear = add_creature(&mut game, bob, Zone::Battlefield);
+        let bobs_card = add_creature(&mut game, bob, Zone::Hand);
+        let bobs_spell = add_creature(&mut game, bob, Zone::Library);
+        game.get_player_zones_mut(bob).unwrap().library.remove(bobs_spell);
+        game.stack.add(bobs_spell);
+        // Bob has gained control of one of Carol's creatures
+        let stolen = add_creature(&mut game, carol, Zone::Battlefield);
+        game.cards.get_mut(stolen).unwrap().controller = bob;
+        let alices_bear = add_creature(&mut game, alice, Zone::Battlefield);
+
+        let log_len = game.undo_log.actions().len();
+        game.get_player_mut(bob).unwrap().life = 0;
+        game.check_state_based_actions().unwrap();
+
+        // MTG Rules 800.4a: Bob's cards leave, Carol's creature is exiled
+        assert!(!game.is_in_game(bob));
+        assert!(game.get_player(bob).unwrap().left_game);
+        assert!(!game.is_game_over());
+        assert!(!game.battlefield.contains(bobs_bear));
+        assert!(!game.stack.contains(bobs_spell));
+        assert!(game.get_player_zones(bob).unwrap().hand.is_empty());
+        assert!(game.get_player_zones(carol).unwrap().exile.contains(stolen));
+        assert!(game.battlefield.contains(alices_bear));
+
+        while game.undo_log.actions().len() > log_len {
+            game.undo().unwrap();
+        }
+        assert!(game.is_in_game(bob));
+        assert!(!game.get_player(bob).unwrap().left_game);
+        assert!(game.battlefield.contains(bobs_bear));
+        assert!(game.battlefield.contains(stolen));
+        assert!(game.stack.contains(bobs_spell));
+        assert!(game.get_player_zones(bob).unwrap().hand.contains(bobs_card));
+    }
+}
@@ -148,6 +148,29 @@ impl TurnStructure {
         self.active_player = next_player;
         self.priority_player = None;
     }
+
+    /// Start the next turn, passing it to the next player in turn order who
+    /// is still in the game (MTG Rules 500.1, 800.4). Returns that player.
+    pub fn rotate(&mut self, players: &[crate::core::Player]) -> crate::core::PlayerId {
+        let current = players
+            .iter()
+            .position(|p| p.id == self.active_player)
+            .unwrap_or(self.active_player_idx);
+        let next_idx = next_in_turn_order(players, current);
+        self.next_turn(players[next_idx].id);
+        self.active_player_idx = next_idx;
+        self.active_player
+    }
+}
+
+/// Index of the next player after `idx` in turn order who hasn't left the game
+///
+/// Returns `idx` itself when every other player has left.
+pub(crate) fn next_in_turn_order(players: &[crate::core::Player], idx: usize) -> usize {
+    (1..=players.len())
+        .map(|offset| (idx + offset) % players.len())
+        .find(|&next| !players[next].has_lost)
+        .unwrap_or(idx)
 }
 
 #[cfg(test)]
@@ -196,6 +219,25 @@ mod tests {
         assert_eq!(turn.active_player, player2);
     }
 
+    #[test]
+    fn test_rotate_skips_players_who_left() {
+        use crate::core::Player;
+
+        let mut players: Vec<Player> = (0..4)
+            .map(|i| Player::new(PlayerId::new(i), format!("P{i}"), 20))
+            .collect();
+        let mut turn = TurnStructure::new_with_idx(players[0].id, 0);
+
+        assert_eq!(turn.rotate(&players), players[1].id);
+        assert_eq!(turn.active_player_idx, 1);
+
+        players[2].has_lost = true;
+        assert_eq!(turn.rotate(&players), players[3].id);
+        assert_eq!(turn.rotate(&players), players[0].id);
+        assert_eq!(turn.turn_number, 4);
+        assert_eq!(turn.active_player_idx, 0);
+    }
+
     #[test]
     fn test_sorcery_speed() {
         assert!(Step::Main1.is_sorcery_speed());
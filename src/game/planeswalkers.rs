@@ -0,0 +1,135 @@
+//! Planeswalkers (MTG Rules 306, 606)
+//!
+//! A planeswalker enters the battlefield with loyalty counters equal to its
+//! printed loyalty. Its loyalty abilities cost adding or removing loyalty
+//! counters, can only be activated at sorcery speed, and only one of them per
+//! turn. Creatures can attack planeswalkers, and damage dealt to a planeswalker
+//! removes that many loyalty counters; one with no loyalty is put into its
+//! owner's graveyard by state-based actions (704.5i).
+
+use crate::core::{CardId, CounterType, PlayerId};
+use crate::game::{AttackTarget, GameState};
+use crate::Result;
+
+impl GameState {
+    /// Put a planeswalker's starting loyalty counters on it as it enters the battlefield
+    pub(crate) fn enter_with_loyalty(&mut self, card_id: CardId) -> Result<()> {
+        let card = self.cards.get(card_id)?;
+        if let (true, Some(loyalty)) = (card.is_planeswalker(), card.loyalty) {
+            if loyalty > 0 {
+                self.add_counters(card_id, CounterType::Loyalty, loyalty)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Has one of this permanent's loyalty abilities been activated this turn?
+    pub fn loyalty_ability_activated_this_turn(&self, card_id: CardId) -> bool {
+        self.cards
+            .get(card_id)
+            .is_ok_and(|card| card.loyalty_activated_turn == Some(self.turn.turn_number))
+    }
+
+    /// Record that one of this permanent's loyalty abilities was activated (MTG Rules 606.3)
+    pub fn record_loyalty_activation(&mut self, card_id: CardId) -> Result<()> {
+        let turn_number = self.turn.turn_number;
+        let card = self.cards.get_mut(card_id)?;
+        let previous_turn = card.loyalty_activated_turn.replace(turn_number);
+        self.undo_log.log(crate::undo::GameAction::ActivateLoyaltyAbility {
+            card_id,
+            turn: turn_number,
+            previous_turn,
+        });
+        Ok(())
+    }
+
+    /// What creatures of `player_id` can attack: each opponent, then the
+    /// planeswalkers those opponents control (MTG Rules 508.1b)
+    pub fn attack_targets(&self, player_id: PlayerId) -> Vec<AttackTarget> {
+        let opponents = self.opponents_in_game(player_id);
+        let planeswalkers = self.battlefield.cards.iter().copied().filter(|&card_id| {
+            self.cards
+                .get(card_id)
+                .is_ok_and(|card| card.is_planeswalker() && opponents.contains(&card.controller))
+        });
+        opponents
+            .iter()
+            .copied()
+            .map(AttackTarget::Player)
+            .chain(planeswalkers.map(AttackTarget::Planeswalker))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{ActivatedAbility, Card, CardId, CardType, Cost, CounterType, PlayerId, TargetRef};
+    use crate::game::{AttackTarget, GameState};
+    use crate::zones::Zone;
+
+    fn add_planeswalker(game: &mut GameState, owner: PlayerId, loyalty: u8) -> CardId {
+        let id = game.next_card_id();
+        let mut card = Card::new(id, "Garruk Wildspeaker".to_string(), owner);
+        card.types.push(CardType::Planeswalker);
+        card.loyalty = Some(loyalty);
+        card.activated_abilities.push(ActivatedAbility::new(
+            Cost::parse("SubCounter<1/LOYALTY>").unwrap(),
+            Vec::new(),
+            "Untap two target lands.".to_string(),
+            false,
+        ));
+        game.cards.insert(id, card);
+        game.get_player_zones_mut(owner).unwrap().hand.add(id);
+        game.move_card(id, Zone::Hand, Zone::Battlefield, owner).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_planeswalker_enters_with_loyalty() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let garruk = add_planeswalker(&mut game, bob, 3);
+
+        assert_eq!(game.cards.get(garruk).unwrap().current_loyalty(), 3);
+        assert_eq!(
+            game.attack_targets(alice),
+            vec![AttackTarget::Player(bob), AttackTarget::Planeswalker(garruk)]
+        );
+        assert_eq!(game.attack_targets(bob), vec![AttackTarget::Player(alice)]);
+    }
+
+    #[test]
+    fn test_damage_removes_loyalty_until_it_dies() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let bob = game.players[1].id;
+        let garruk = add_planeswalker(&mut game, bob, 3);
+
+        game.deal_damage_from(None, &TargetRef::Permanent(garruk), 2, false)
+            .unwrap();
+        assert_eq!(game.cards.get(garruk).unwrap().current_loyalty(), 1);
+        assert_eq!(game.cards.get(garruk).unwrap().damage_marked, 0);
+
+        // MTG Rules 704.5i: a planeswalker with 0 loyalty goes to the graveyard
+        game.deal_damage_from(None, &TargetRef::Permanent(garruk), 5, false)
+            .unwrap();
+        game.check_state_based_actions().unwrap();
+        assert!(game.get_player_zones(bob).unwrap().graveyard.contains(garruk));
+    }
+
+    #[test]
+    fn test_loyalty_ability_once_per_turn() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let garruk = add_planeswalker(&mut game, alice, 3);
+        let cost = game.cards.get(garruk).unwrap().activated_abilities[0].cost.clone();
+
+        assert!(!game.loyalty_ability_activated_this_turn(garruk));
+        game.pay_ability_cost(alice, garruk, &cost).unwrap();
+        game.record_loyalty_activation(garruk).unwrap();
+        assert!(game.loyalty_ability_activated_this_turn(garruk));
+        assert_eq!(game.cards.get(garruk).unwrap().get_counter(CounterType::Loyalty), 2);
+
+        game.undo().unwrap();
+        assert!(!game.loyalty_ability_activated_this_turn(garruk));
+    }
+}
@@ -3,10 +3,10 @@
 //! This implementation uses specific callback methods instead of
 //! generic action choices. Makes random choices from available options.
 
-use crate::core::{CardId, ManaCost, PlayerId, SpellAbility};
+use crate::core::{CardId, Color, Cost, ManaCost, PlayerId, ReplacementKey, SpellAbility};
 use crate::game::controller::GameStateView;
 use crate::game::controller::PlayerController;
-use crate::game::format_choice_menu;
+use crate::game::{format_choice_menu, AttackTarget, StackAbility};
 use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
 use smallvec::SmallVec;
@@ -112,6 +112,49 @@ impl PlayerController for RandomController {
         Some(available[ability_index].clone())
     }
 
+    fn choose_modes(
+        &mut self,
+        view: &GameStateView,
+        _spell: CardId,
+        modes: &[String],
+        min: u8,
+        max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        // Pick a random number of modes, then that many distinct modes at random
+        let max = (max as usize).min(modes.len());
+        let min = (min as usize).min(max);
+        let count = self.rng.gen_range(min..=max);
+        let mut remaining: Vec<usize> = (0..modes.len()).collect();
+        remaining.shuffle(&mut self.rng);
+        let mut chosen: SmallVec<[usize; 4]> = remaining.into_iter().take(count).collect();
+        chosen.sort_unstable();
+        view.logger().controller_choice(
+            "RANDOM",
+            &format!("chose modes {:?} out of choices 0-{}", chosen, modes.len() - 1),
+        );
+        chosen
+    }
+
+    fn choose_x_value(&mut self, view: &GameStateView, _spell: CardId, max_x: u8) -> u8 {
+        let x = self.rng.gen_range(0..=max_x);
+        view.logger()
+            .controller_choice("RANDOM", &format!("chose X = {x} out of 0-{max_x}"));
+        x
+    }
+
+    fn choose_alternative_cost(&mut self, view: &GameStateView, _spell: CardId, _cost: &Cost) -> bool {
+        // Flip a coin
+        let alternative = self.rng.gen_bool(0.5);
+        view.logger().controller_choice(
+            "RANDOM",
+            &format!(
+                "chose to pay the {} cost",
+                if alternative { "alternative" } else { "mana" }
+            ),
+        );
+        alternative
+    }
+
     fn choose_targets(
         &mut self,
         view: &GameStateView,
@@ -143,6 +186,24 @@ impl PlayerController for RandomController {
         }
     }
 
+    fn choose_division(
+        &mut self,
+        view: &GameStateView,
+        _spell: CardId,
+        targets: &[CardId],
+        total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        // Each target gets 1, then each remaining point goes to a random target
+        let mut division: SmallVec<[u32; 4]> = targets.iter().map(|_| 1).collect();
+        for _ in targets.len() as u32..total {
+            let index = self.rng.gen_range(0..targets.len());
+            division[index] += 1;
+        }
+        view.logger()
+            .controller_choice("RANDOM", &format!("divided {total} as {division:?}"));
+        division
+    }
+
     fn choose_mana_sources_to_pay(
         &mut self,
         view: &GameStateView,
@@ -177,9 +238,25 @@ impl PlayerController for RandomController {
         sources
     }
 
-    fn choose_attackers(&mut self, view: &GameStateView, available_creatures: &[CardId]) -> SmallVec<[CardId; 8]> {
-        // Randomly decide whether each creature attacks
+    fn choose_mana_color(&mut self, view: &GameStateView, _source: CardId, colors: &[Color]) -> Color {
+        // Choose a random color
+        let color = colors[self.rng.gen_range(0..colors.len())];
+        view.logger()
+            .controller_choice("RANDOM", &format!("chose {color} mana"));
+        color
+    }
+
+    fn choose_attackers(
+        &mut self,
+        view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
+        // Randomly decide whether each creature attacks, and what it attacks
         let mut attackers = SmallVec::new();
+        if defenders.is_empty() {
+            return attackers;
+        }
 
         for (idx, &creature_id) in available_creatures.iter().enumerate() {
             // 50% chance each creature attacks
@@ -192,7 +269,12 @@ impl PlayerController for RandomController {
                         available_creatures.len()
                     ),
                 );
-                attackers.push(creature_id);
+                let defender = if defenders.len() > 1 {
+                    defenders[self.rng.gen_range(0..defenders.len())]
+                } else {
+                    defenders[0]
+                };
+                attackers.push((creature_id, defender));
             }
         }
 
@@ -273,6 +355,102 @@ impl PlayerController for RandomController {
         ordered_blockers.into_iter().collect()
     }
 
+    fn choose_trigger_order(&mut self, view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        // Randomly shuffle the order the triggers go on the stack
+        let mut order: SmallVec<[usize; 4]> = (0..triggers.len()).collect();
+        order.shuffle(&mut self.rng);
+
+        if triggers.len() >= 2 {
+            view.logger().controller_choice(
+                "RANDOM",
+                &format!("chose trigger order (shuffled {} triggers)", triggers.len()),
+            );
+        }
+
+        order
+    }
+
+    fn choose_replacement_effect(&mut self, view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        // Apply a random one first
+        let choice = self.rng.gen_range(0..effects.len());
+        view.logger().controller_choice(
+            "RANDOM",
+            &format!("chose replacement effect {} of {}", choice, effects.len()),
+        );
+        effects[choice]
+    }
+
+    fn choose_legend_to_keep(&mut self, view: &GameStateView, legends: &[CardId]) -> CardId {
+        // Keep a random one
+        let keep = legends[self.rng.gen_range(0..legends.len())];
+        view.logger().controller_choice(
+            "RANDOM",
+            &format!(
+                "chose to keep {} under the legend rule",
+                view.card_name(keep).unwrap_or_default()
+            ),
+        );
+        keep
+    }
+
+    fn choose_mulligan(&mut self, view: &GameStateView, hand: &[CardId], mulligans_taken: u8) -> bool {
+        // Flip a coin
+        let mulligan = self.rng.gen_bool(0.5);
+        view.logger().controller_choice(
+            "RANDOM",
+            &format!(
+                "chose to {} a {}-card hand after {} mulligans",
+                if mulligan { "mulligan" } else { "keep" },
+                hand.len(),
+                mulligans_taken
+            ),
+        );
+        mulligan
+    }
+
+    fn choose_cards_to_bottom(&mut self, view: &GameStateView, hand: &[CardId], count: usize) -> SmallVec<[CardId; 7]> {
+        // Randomly choose cards to put on the bottom
+        let mut hand_vec: Vec<CardId> = hand.to_vec();
+        hand_vec.shuffle(&mut self.rng);
+
+        let num_bottom = count.min(hand.len());
+        view.logger().controller_choice(
+            "RANDOM",
+            &format!(
+                "chose {} cards to put on the bottom (shuffled from {} cards in hand)",
+                num_bottom,
+                hand.len()
+            ),
+        );
+
+        hand_vec.iter().take(num_bottom).copied().collect()
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        view: &GameStateView,
+        _source: CardId,
+        _cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        // Randomly choose cards to pay the cost
+        let mut shuffled: Vec<CardId> = candidates.to_vec();
+        shuffled.shuffle(&mut self.rng);
+
+        let num_chosen = count.min(candidates.len());
+        view.logger().controller_choice(
+            "RANDOM",
+            &format!(
+                "chose {} cards to pay a cost (shuffled from {} candidates)",
+                num_chosen,
+                candidates.len()
+            ),
+        );
+
+        shuffled.iter().take(num_chosen).copied().collect()
+    }
+
     fn choose_cards_to_discard(
         &mut self,
         view: &GameStateView,
@@ -401,6 +579,26 @@ mod tests {
         assert!(valid_targets.contains(&targets[0]));
     }
 
+    #[test]
+    fn test_choose_modes_and_x() {
+        let player_id = EntityId::new(1);
+        let mut controller = RandomController::with_seed(player_id, 300);
+        let game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let view = GameStateView::new(&game, player_id);
+
+        let spell_id = EntityId::new(100);
+        let modes = vec!["Counter".to_string(), "Damage".to_string(), "Draw".to_string()];
+        for _ in 0..20 {
+            // Between one and two distinct modes
+            let chosen = controller.choose_modes(&view, spell_id, &modes, 1, 2);
+            assert!((1..=2).contains(&chosen.len()));
+            assert!(chosen.iter().all(|&m| m < modes.len()));
+            assert!(chosen.len() < 2 || chosen[0] != chosen[1]);
+
+            assert!(controller.choose_x_value(&view, spell_id, 3) <= 3);
+        }
+    }
+
     #[test]
     fn test_choose_mana_sources() {
         let player_id = EntityId::new(1);
@@ -435,12 +633,17 @@ mod tests {
         let view = GameStateView::new(&game, player_id);
 
         let creatures = vec![EntityId::new(20), EntityId::new(21), EntityId::new(22)];
-        let attackers = controller.choose_attackers(&view, &creatures);
+        let defenders = [
+            AttackTarget::Player(EntityId::new(2)),
+            AttackTarget::Planeswalker(EntityId::new(30)),
+        ];
+        let attackers = controller.choose_attackers(&view, &creatures, &defenders);
 
         // Should return a SmallVec (possibly empty)
-        // All attackers should be from the available creatures
-        for attacker in attackers.iter() {
+        // All attackers should be from the available creatures, each attacking a defender
+        for (attacker, target) in attackers.iter() {
             assert!(creatures.contains(attacker));
+            assert!(defenders.contains(target));
         }
     }
 
@@ -0,0 +1,982 @@
+//! Replacement and prevention effects at work (MTG Rules 614, 615, 616)
+//!
+//! Every event a replacement effect may modify goes through one of the
+//! `replace_*` methods here before it happens: `move_card`, damage, `draw_card`
+//! and life changes consult the functioning replacement effects, apply the ones
+//! that match and carry out what's left of the event.
+//!
+//! When several effects would modify the same event, the affected player
+//! chooses which one applies first (616.1). After each one the remaining
+//! effects are checked again against the modified event, and each effect
+//! applies to an event at most once (614.5).
+//!
+//! Events are carried out inside `GameState`, which can't ask a
+//! `PlayerController` in the middle of one. Instead, while the game loop carries
+//! out an action it has `GameState` record the first event that needs a choice
+//! (see [`ReplacementChoices`]). The loop then undoes the action, asks the
+//! player, and carries it out again with the answer. Outside the game loop,
+//! effects apply in timestamp order.
+
+use crate::core::{
+    CardId, Effect, PlayerId, ReplacementAction, ReplacementEffect, ReplacementEvent, ReplacementId, ReplacementKey,
+    SelectorContext, TargetRef,
+};
+use crate::game::GameState;
+use crate::zones::Zone;
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+/// A replacement effect created by a resolved spell or ability, kept in
+/// `GameState::replacement_effects` until the cleanup step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredReplacement {
+    pub id: ReplacementId,
+    /// The card whose spell or ability created the effect, if known ("Self")
+    pub source: Option<CardId>,
+    /// The player who controls the effect ("You")
+    pub controller: PlayerId,
+    pub effect: ReplacementEffect,
+}
+
+/// A functioning replacement effect together with its source and controller
+#[derive(Debug, Clone, Copy)]
+pub struct FunctioningReplacement<'a> {
+    pub source: Option<CardId>,
+    pub controller: PlayerId,
+    pub effect: &'a ReplacementEffect,
+}
+
+/// An event with several applicable replacement effects, waiting for the
+/// affected player to choose one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplacementChoice {
+    /// The affected player, or the controller of the affected object
+    pub player: PlayerId,
+    /// The applicable effects, in timestamp order
+    pub effects: SmallVec<[ReplacementKey; 4]>,
+}
+
+/// The replacement effect choices made for the events of one game action
+///
+/// The game loop carries out an action again after each choice. The choices
+/// made so far are used again, in order, for the events that come up; the
+/// first event past them stops asking and records its choice in `pending`.
+/// The logger is muted while repeating events that were already logged.
+#[derive(Debug, Clone, Default)]
+pub struct ReplacementChoices {
+    /// Whether events record the choices they need
+    asking: bool,
+    /// The effects chosen so far, in the order their events came up
+    answers: Vec<ReplacementKey>,
+    /// How many of the answers this run of the action has used
+    used: usize,
+    /// The first choice this run of the action needed and had no answer for
+    pending: Option<ReplacementChoice>,
+    /// Whether the logger was muted here
+    muted: bool,
+}
+
+/// An event replacement effects may modify
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReplaceableEvent {
+    Move {
+        card: CardId,
+        from: Zone,
+        to: Zone,
+    },
+    Damage {
+        source: Option<CardId>,
+        target: TargetRef,
+        combat: bool,
+    },
+    Draw {
+        player: PlayerId,
+    },
+    GainLife {
+        player: PlayerId,
+    },
+    LoseLife {
+        player: PlayerId,
+    },
+}
+
+/// What's left of an event after replacement effects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MoveOutcome {
+    /// Where the card goes; `None` if it doesn't move at all
+    pub destination: Option<Zone>,
+    /// Whether the permanent enters the battlefield tapped
+    pub enter_tapped: bool,
+}
+
+impl GameState {
+    /// Look up a functioning replacement effect
+    pub fn replacement_by_key(&self, key: ReplacementKey) -> Option<FunctioningReplacement<'_>> {
+        match key {
+            ReplacementKey::Card { card, index } => {
+                let card = self.cards.get(card).ok()?;
+                Some(FunctioningReplacement {
+                    source: Some(card.id),
+                    controller: card.controller,
+                    effect: card.replacement_effects.get(index)?,
+                })
+            }
+            ReplacementKey::Registered(id) => {
+                let registered = self.replacement_effects.iter().find(|r| r.id == id)?;
+                Some(FunctioningReplacement {
+                    source: registered.source,
+                    controller: registered.controller,
+                    effect: &registered.effect,
+                })
+            }
+        }
+    }
+
+    /// Start recording the replacement effect choices an action needs
+    pub fn begin_replacement_choices(&mut self) {
+        self.end_replacement_choices();
+        self.replacement_choices.asking = true;
+    }
+
+    /// The first replacement effect choice the last run of the action needed, if any
+    ///
+    /// The run is over, so the logger is unmuted for the player's choice.
+    pub fn take_replacement_choice(&mut self) -> Option<ReplacementChoice> {
+        let choice = self.replacement_choices.pending.take()?;
+        self.unmute_for_replacement_choices();
+        Some(choice)
+    }
+
+    /// Record the effect chosen for the next event, before the action is carried out again
+    ///
+    /// The events up to this one were logged already, so the logger stays muted
+    /// until the action gets past them.
+    pub fn answer_replacement_choice(&mut self, key: ReplacementKey) {
+        let choices = &mut self.replacement_choices;
+        choices.answers.push(key);
+        choices.used = 0;
+        choices.pending = None;
+        self.mute_for_replacement_choices();
+    }
+
+    /// Stop recording replacement effect choices and forget the answers
+    pub fn end_replacement_choices(&mut self) {
+        self.unmute_for_replacement_choices();
+        self.replacement_choices = ReplacementChoices::default();
+    }
+
+    /// Mute the logger while events that were already logged are carried out again
+    fn mute_for_replacement_choices(&mut self) {
+        if !self.replacement_choices.muted {
+            self.replacement_choices.muted = true;
+            self.logger.set_muted(true);
+        }
+    }
+
+    /// Undo `mute_for_replacement_choices`
+    fn unmute_for_replacement_choices(&mut self) {
+        if self.replacement_choices.muted {
+            self.replacement_choices.muted = false;
+            self.logger.set_muted(false);
+        }
+    }
+
+    /// Create a replacement effect that lasts until end of turn (Fog)
+    pub fn add_replacement_effect(
+        &mut self,
+        source: Option<CardId>,
+        controller: PlayerId,
+        effect: ReplacementEffect,
+    ) -> ReplacementId {
+        let id = self.next_id();
+        let registered = RegisteredReplacement {
+            id,
+            source,
+            controller,
+            effect,
+        };
+        self.replacement_effects.push(registered.clone());
+        self.undo_log.log(crate::undo::GameAction::AddReplacementEffect {
+            effect: Box::new(registered),
+        });
+        id
+    }
+
+    /// Remove an effect from the registry
+    fn remove_replacement_effect(&mut self, index: usize) {
+        let removed = self.replacement_effects.remove(index);
+        self.undo_log.log(crate::undo::GameAction::RemoveReplacementEffect {
+            index,
+            effect: Box::new(removed),
+        });
+    }
+
+    /// Use up `prevented` of a registered damage shield, ending it once none is left
+    fn use_damage_shield(&mut self, id: ReplacementId, prevented: u32) {
+        let Some(index) = self.replacement_effects.iter().position(|r| r.id == id) else {
+            return;
+        };
+        let previous = self.replacement_effects[index].clone();
+        let Some(remaining) = previous.effect.shield_remaining() else {
+            return;
+        };
+        if remaining <= prevented {
+            self.remove_replacement_effect(index);
+            return;
+        }
+        let mut registered = previous.clone();
+        registered.effect.action = ReplacementAction::PreventAmount(remaining - prevented);
+        self.replacement_effects[index] = registered.clone();
+        self.undo_log.log(crate::undo::GameAction::UseDamageShield {
+            index,
+            effect: Box::new(registered),
+            previous: Box::new(previous),
+        });
+    }
+
+    /// End the "this turn" replacement effects (MTG Rules 514.2)
+    pub fn end_replacement_effects(&mut self) {
+        while !self.replacement_effects.is_empty() {
+            self.remove_replacement_effect(self.replacement_effects.len() - 1);
+        }
+    }
+
+    /// Apply replacement effects to a card about to move from `from` to `to`
+    pub(crate) fn replace_move(&mut self, card: CardId, from: Zone, to: Zone) -> Result<MoveOutcome> {
+        let mut event = ReplaceableEvent::Move { card, from, to };
+        let mut outcome = MoveOutcome {
+            destination: Some(to),
+            enter_tapped: false,
+        };
+        let mut applied = SmallVec::new();
+        while let Some((key, action)) = self.next_replacement(&event, &mut applied)? {
+            match action {
+                ReplacementAction::EnterTapped => outcome.enter_tapped = true,
+                // Shields only prevent damage
+                ReplacementAction::PreventAmount(_) => {}
+                ReplacementAction::ChangeZone(zone) => {
+                    event = ReplaceableEvent::Move { card, from, to: zone };
+                    outcome.destination = Some(zone);
+                    outcome.enter_tapped = false;
+                }
+                ReplacementAction::Prevent => {
+                    return Ok(MoveOutcome {
+                        destination: None,
+                        ..outcome
+                    })
+                }
+                ReplacementAction::Instead(effects) => {
+                    self.replace_with_effects(key, &event, &effects)?;
+                    return Ok(MoveOutcome {
+                        destination: None,
+                        ..outcome
+                    });
+                }
+            }
+        }
+        // A commander goes to the command zone instead (MTG Rules 903.9)
+        if let Some(zone) = outcome.destination.and_then(|to| self.commander_replacement(card, to)) {
+            let name = self.cards.get(card)?.name.to_string();
+            self.logger.normal(&format!("{name} returns to the command zone"));
+            outcome.destination = Some(zone);
+        }
+        if outcome.destination != Some(Zone::Battlefield) {
+            outcome.enter_tapped = false;
+        }
+        Ok(outcome)
+    }
+
+    /// Apply replacement and prevention effects to damage about to be dealt
+    ///
+    /// Returns how much damage is still dealt.
+    pub(crate) fn replace_damage(
+        &mut self,
+        source: Option<CardId>,
+        target: &TargetRef,
+        amount: i32,
+        combat: bool,
+    ) -> Result<i32> {
+        let event = ReplaceableEvent::Damage {
+            source,
+            target: target.clone(),
+            combat,
+        };
+        self.replace_amount_event(event, amount)
+    }
+
+    /// Apply replacement effects to a card draw; returns whether the draw still happens
+    pub(crate) fn replace_draw(&mut self, player: PlayerId) -> Result<bool> {
+        Ok(self.replace_amount_event(ReplaceableEvent::Draw { player }, 1)? > 0)
+    }
+
+    /// Apply replacement effects to a life gain; returns the life still gained
+    pub(crate) fn replace_life_gain(&mut self, player: PlayerId, amount: i32) -> Result<i32> {
+        self.replace_amount_event(ReplaceableEvent::GainLife { player }, amount)
+    }
+
+    /// Apply replacement effects to a life loss; returns the life still lost
+    pub(crate) fn replace_life_loss(&mut self, player: PlayerId, amount: i32) -> Result<i32> {
+        self.replace_amount_event(ReplaceableEvent::LoseLife { player }, amount)
+    }
+
+    /// Apply replacement effects to an event that happens or doesn't, returning
+    /// `amount` if it still happens and 0 if it was prevented or replaced
+    fn replace_amount_event(&mut self, event: ReplaceableEvent, mut amount: i32) -> Result<i32> {
+        if amount <= 0 {
+            return Ok(amount);
+        }
+        let mut applied = SmallVec::new();
+        while let Some((key, action)) = self.next_replacement(&event, &mut applied)? {
+            match action {
+                // These only modify cards moving between zones
+                ReplacementAction::EnterTapped | ReplacementAction::ChangeZone(_) => {}
+                ReplacementAction::Prevent => return Ok(0),
+                ReplacementAction::PreventAmount(shield) => {
+                    let prevented = amount.min(shield as i32);
+                    amount -= prevented;
+                    if let ReplacementKey::Registered(id) = key {
+                        self.use_damage_shield(id, prevented as u32);
+                    }
+                    if amount == 0 {
+                        return Ok(0);
+                    }
+                }
+                ReplacementAction::Instead(effects) => {
+                    self.replace_with_effects(key, &event, &effects)?;
+                    return Ok(0);
+                }
+            }
+        }
+        Ok(amount)
+    }
+
+    /// Choose the next replacement effect to apply to `event`, if any still applies
+    ///
+    /// The effect is recorded in `applied`; single-use effects end here.
+    fn next_replacement(
+        &mut self,
+        event: &ReplaceableEvent,
+        applied: &mut SmallVec<[ReplacementKey; 4]>,
+    ) -> Result<Option<(ReplacementKey, ReplacementAction)>> {
+        let candidates = self.applicable_replacements(event, applied);
+        let Some(player) = self.affected_player(event) else {
+            return Ok(None);
+        };
+        let Some(&first) = candidates.first() else {
+            return Ok(None);
+        };
+        let key = if candidates.len() > 1 {
+            self.choose_replacement(player, candidates).unwrap_or(first)
+        } else {
+            first
+        };
+        applied.push(key);
+
+        let Some(replacement) = self.replacement_by_key(key) else {
+            return Ok(None);
+        };
+        let action = replacement.effect.action.clone();
+        let single_use = replacement.effect.single_use;
+        let source_name = replacement
+            .source
+            .and_then(|source| self.cards.get(source).ok())
+            .map_or_else(|| "An effect".to_string(), |card| card.name.to_string());
+        self.logger.normal(&format!(
+            "{source_name} replaces an event: {}",
+            replacement.effect.description
+        ));
+
+        if single_use {
+            if let ReplacementKey::Registered(id) = key {
+                if let Some(index) = self.replacement_effects.iter().position(|r| r.id == id) {
+                    self.remove_replacement_effect(index);
+                }
+            }
+        }
+        Ok(Some((key, action)))
+    }
+
+    /// The effect the affected player chose to apply first among `candidates`
+    ///
+    /// Uses the next answer recorded for this action. Past the answers, the
+    /// first event records the choice it needs and mutes the logger, as the
+    /// action will be carried out again; `None` means timestamp order.
+    fn choose_replacement(
+        &mut self,
+        player: PlayerId,
+        candidates: SmallVec<[ReplacementKey; 4]>,
+    ) -> Option<ReplacementKey> {
+        let choices = &mut self.replacement_choices;
+        if let Some(&answer) = choices.answers.get(choices.used) {
+            choices.used += 1;
+            if choices.used == choices.answers.len() {
+                self.unmute_for_replacement_choices();
+            }
+            return candidates.contains(&answer).then_some(answer);
+        }
+        if choices.asking && choices.pending.is_none() {
+            choices.pending = Some(ReplacementChoice {
+                player,
+                effects: candidates,
+            });
+            self.mute_for_replacement_choices();
+        }
+        None
+    }
+
+    /// The functioning replacement effects that would modify `event`, in timestamp order
+    fn applicable_replacements(
+        &self,
+        event: &ReplaceableEvent,
+        applied: &[ReplacementKey],
+    ) -> SmallVec<[ReplacementKey; 4]> {
+        let mut candidates = SmallVec::new();
+        let mut consider = |key: ReplacementKey| {
+            if applied.contains(&key) || self.replacements_applying.contains(&key) {
+                return;
+            }
+            if self
+                .replacement_by_key(key)
+                .is_some_and(|replacement| self.replacement_applies(replacement, event))
+            {
+                candidates.push(key);
+            }
+        };
+
+        // A card's effects on itself function as it moves from any zone (MTG Rules 614.12)
+        if let ReplaceableEvent::Move { card, .. } = event {
+            if !self.battlefield.contains(*card) {
+                if let Ok(moving) = self.cards.get(*card) {
+                    for (index, effect) in moving.replacement_effects.iter().enumerate() {
+                        if effect.affected.as_ref().is_some_and(|a| a.is_self_only()) {
+                            consider(ReplacementKey::Card { card: *card, index });
+                        }
+                    }
+                }
+            }
+        }
+        for &card_id in &self.battlefield.cards {
+            let count = self.cards.get(card_id).map_or(0, |card| card.replacement_effects.len());
+            for index in 0..count {
+                consider(ReplacementKey::Card { card: card_id, index });
+            }
+        }
+        for registered in &self.replacement_effects {
+            consider(ReplacementKey::Registered(registered.id));
+        }
+        candidates
+    }
+
+    /// Would this replacement effect modify `event`?
+    fn replacement_applies(&self, replacement: FunctioningReplacement, event: &ReplaceableEvent) -> bool {
+        let ctx = SelectorContext::new(replacement.controller, replacement.source);
+        let effect = replacement.effect;
+        let card_matches = |card_id: CardId, selector: &Option<crate::core::Selector>| {
+            selector
+                .as_ref()
+                .is_none_or(|s| self.cards.get(card_id).is_ok_and(|card| s.matches_card(card, &ctx)))
+        };
+        let player_matches = |player: PlayerId| effect.affected.as_ref().is_none_or(|s| s.matches_player(player, &ctx));
+
+        match (&effect.event, event) {
+            (ReplacementEvent::Moved { origin, destination }, ReplaceableEvent::Move { card, from, to }) => {
+                origin.is_none_or(|origin| origin == *from)
+                    && destination.is_none_or(|destination| destination == *to)
+                    && card_matches(*card, &effect.affected)
+            }
+            (ReplacementEvent::DamageDone { combat_only }, ReplaceableEvent::Damage { source, target, combat }) => {
+                let target_matches = match target {
+                    TargetRef::Player(player) => player_matches(*player),
+                    TargetRef::Permanent(card) => card_matches(*card, &effect.affected),
+                    TargetRef::None => false,
+                };
+                let source_matches = match (&effect.valid_source, source) {
+                    (None, _) => true,
+                    (Some(_), Some(source)) => card_matches(*source, &effect.valid_source),
+                    (Some(_), None) => false,
+                };
+                (!combat_only || *combat) && target_matches && source_matches
+            }
+            (ReplacementEvent::Draw, ReplaceableEvent::Draw { player })
+            | (ReplacementEvent::GainLife, ReplaceableEvent::GainLife { player })
+            | (ReplacementEvent::LoseLife, ReplaceableEvent::LoseLife { player }) => player_matches(*player),
+            _ => false,
+        }
+    }
+
+    /// The player who orders the replacement effects for an event (MTG Rules 616.1):
+    /// the affected player, or the controller of the affected object
+    fn affected_player(&self, event: &ReplaceableEvent) -> Option<PlayerId> {
+        match event {
+            ReplaceableEvent::Move { card, .. }
+            | ReplaceableEvent::Damage {
+                target: TargetRef::Permanent(card),
+                ..
+            } => self.cards.get(*card).ok().map(|card| card.controller),
+            ReplaceableEvent::Damage {
+                target: TargetRef::Player(player),
+                ..
+            }
+            | ReplaceableEvent::Draw { player }
+            | ReplaceableEvent::GainLife { player }
+            | ReplaceableEvent::LoseLife { player } => Some(*player),
+            ReplaceableEvent::Damage {
+                target: TargetRef::None,
+                ..
+            } => None,
+        }
+    }
+
+    /// Carry out the effects that happen instead of `event`
+    ///
+    /// Placeholder players are the effect's controller and placeholder cards the
+    /// card the event would have moved. The effect can't apply again to the
+    /// events its own effects cause (MTG Rules 614.5).
+    fn replace_with_effects(
+        &mut self,
+        key: ReplacementKey,
+        event: &ReplaceableEvent,
+        effects: &[Effect],
+    ) -> Result<()> {
+        let Some(replacement) = self.replacement_by_key(key) else {
+            return Ok(());
+        };
+        let (source, controller) = (replacement.source, replacement.controller);
+        let replaced_card = match event {
+            ReplaceableEvent::Move { card, .. } => *card,
+            _ => CardId::new(0),
+        };
+
+        self.replacements_applying.push(key);
+        let mut result = Ok(());
+        for effect in effects {
+            let mut effect = effect.clone();
+            match &mut effect {
+                Effect::DrawCards { player, .. }
+                | Effect::GainLife { player, .. }
+                | Effect::Mill { player, .. }
+                | Effect::AddMana { player, .. }
+                | Effect::AddManaOfChoice { player, .. }
+                | Effect::CreateToken { owner: player, .. }
+                | Effect::CreateReplacementEffects { controller: player, .. }
+                | Effect::DestroyAll { controller: player, .. }
+                | Effect::PumpAll { controller: player, .. }
+                    if player.as_u32() == 0 =>
+                {
+                    *player = controller
+                }
+                Effect::DestroyPermanent { target }
+                | Effect::TapPermanent { target }
+                | Effect::UntapPermanent { target }
+                | Effect::ExilePermanent { target }
+                | Effect::PumpCreature { target, .. }
+                | Effect::PutCounter { target, .. }
+                    if target.as_u32() == 0 =>
+                {
+                    *target = replaced_card
+                }
+                _ => {}
+            }
+            result = match source {
+                Some(source) => self.execute_effect_from(source, &effect),
+                None => self.execute_effect(&effect),
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+        self.replacements_applying.retain(|k| *k != key);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Card, CardType, Selector};
+
+    fn add_card(game: &mut GameState, owner: PlayerId, name: &str, card_type: CardType) -> CardId {
+        let id = game.next_card_id();
+        let mut card = Card::new(id, name.to_string(), owner);
+        card.types.push(card_type);
+        if card_type == CardType::Creature {
+            card.power = Some(2);
+            card.toughness = Some(2);
+        }
+        game.cards.insert(id, card);
+        game.get_player_zones_mut(owner).unwrap().hand.add(id);
+        id
+    }
+
+    fn replacement(
+        event: ReplacementEvent,
+        affected: Option<&str>,
+        action: ReplacementAction,
+        description: &str,
+    ) -> ReplacementEffect {
+        ReplacementEffect {
+            event,
+            affected: affected.map(Selector::parse),
+            valid_source: None,
+            action,
+            single_use: false,
+            description: description.to_string(),
+        }
+    }
+
+    fn dies() -> ReplacementEvent {
+        ReplacementEvent::Moved {
+            origin: Some(Zone::Battlefield),
+            destination: Some(Zone::Graveyard),
+        }
+    }
+
+    #[test]
+    fn test_land_enters_tapped() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let land = add_card(&mut game, alice, "Guildgate", CardType::Land);
+        game.cards.get_mut(land).unwrap().replacement_effects.push(replacement(
+            ReplacementEvent::Moved {
+                origin: None,
+                destination: Some(Zone::Battlefield),
+            },
+            Some("Card.Self"),
+            ReplacementAction::EnterTapped,
+            "CARDNAME enters the battlefield tapped.",
+        ));
+
+        game.play_land(alice, land).unwrap();
+        assert!(game.battlefield.contains(land));
+        assert!(game.cards.get(land).unwrap().tapped);
+
+        // Undoing the land drop, the tap and the move puts it back in hand untapped
+        while game.undo().unwrap() {}
+        assert!(!game.cards.get(land).unwrap().tapped);
+        assert!(game.get_player_zones(alice).unwrap().hand.contains(land));
+    }
+
+    #[test]
+    fn test_exile_instead_of_dying() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let bear = add_card(&mut game, alice, "Bear", CardType::Creature);
+        game.cards.get_mut(bear).unwrap().replacement_effects.push(replacement(
+            dies(),
+            Some("Card.Self"),
+            ReplacementAction::ChangeZone(Zone::Exile),
+            "If CARDNAME would die, exile it instead.",
+        ));
+        game.move_card(bear, Zone::Hand, Zone::Battlefield, alice).unwrap();
+
+        let destination = game.move_card(bear, Zone::Battlefield, Zone::Graveyard, alice).unwrap();
+        assert_eq!(destination, Zone::Exile);
+        let zones = game.get_player_zones(alice).unwrap();
+        assert!(zones.exile.contains(bear));
+        assert!(!zones.graveyard.contains(bear));
+    }
+
+    /// Carry out `action` the way the game loop does, answering each replacement
+    /// effect choice with the effect at the next index of `picks`
+    fn with_picks<R>(
+        game: &mut GameState,
+        picks: &[usize],
+        mut action: impl FnMut(&mut GameState) -> Result<R>,
+    ) -> (R, Vec<ReplacementChoice>) {
+        let mut asked = Vec::new();
+        game.begin_replacement_choices();
+        let start = game.undo_log.len();
+        let result = loop {
+            let result = action(game).unwrap();
+            let Some(choice) = game.take_replacement_choice() else {
+                break result;
+            };
+            while game.undo_log.len() > start {
+                game.undo().unwrap();
+            }
+            game.answer_replacement_choice(choice.effects[picks[asked.len()]]);
+            assert!(game.logger.is_muted());
+            asked.push(choice);
+        };
+        game.end_replacement_choices();
+        assert!(!game.logger.is_muted());
+        (result, asked)
+    }
+
+    #[test]
+    fn test_affected_player_chooses_replacement_effect_per_event() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let bear = add_card(&mut game, alice, "Bear", CardType::Creature);
+        let wolf = add_card(&mut game, alice, "Wolf", CardType::Creature);
+        game.move_card(bear, Zone::Hand, Zone::Battlefield, alice).unwrap();
+        game.move_card(wolf, Zone::Hand, Zone::Battlefield, alice).unwrap();
+
+        // Two effects would modify a creature dying: one exiles it, one returns it to hand
+        let exile = game.add_replacement_effect(
+            None,
+            bob,
+            replacement(
+                dies(),
+                Some("Creature"),
+                ReplacementAction::ChangeZone(Zone::Exile),
+                "exile",
+            ),
+        );
+        let bounce = game.add_replacement_effect(
+            None,
+            alice,
+            replacement(
+                dies(),
+                Some("Creature"),
+                ReplacementAction::ChangeZone(Zone::Hand),
+                "return to hand",
+            ),
+        );
+        let effects: SmallVec<[ReplacementKey; 4]> =
+            SmallVec::from_slice(&[ReplacementKey::Registered(exile), ReplacementKey::Registered(bounce)]);
+
+        // Alice controls the bear, so she chooses; once one effect has applied,
+        // the other no longer matches the modified event
+        let (destination, asked) = with_picks(&mut game, &[1], |game| {
+            game.move_card(bear, Zone::Battlefield, Zone::Graveyard, alice)
+        });
+        assert_eq!(destination, Zone::Hand);
+        assert_eq!(
+            asked,
+            vec![ReplacementChoice {
+                player: alice,
+                effects: effects.clone()
+            }]
+        );
+
+        // The same effects, another event: this time she picks the other one
+        let (destination, _) = with_picks(&mut game, &[0], |game| {
+            game.move_card(wolf, Zone::Battlefield, Zone::Graveyard, alice)
+        });
+        assert_eq!(destination, Zone::Exile);
+
+        // Outside the game loop nobody is asked, and effects apply in timestamp order
+        game.move_card(bear, Zone::Hand, Zone::Battlefield, alice).unwrap();
+        let destination = game.move_card(bear, Zone::Battlefield, Zone::Graveyard, alice).unwrap();
+        assert_eq!(destination, Zone::Exile);
+        assert_eq!(game.take_replacement_choice(), None);
+
+        // The effects end in the cleanup step, and undo brings them back
+        game.end_replacement_effects();
+        assert!(game.replacement_effects.is_empty());
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.replacement_effects.len(), 2);
+    }
+
+    #[test]
+    fn test_each_event_of_an_action_gets_its_own_choice() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let bear = add_card(&mut game, alice, "Bear", CardType::Creature);
+        let goblin = add_card(&mut game, bob, "Goblin", CardType::Creature);
+        game.move_card(bear, Zone::Hand, Zone::Battlefield, alice).unwrap();
+        game.move_card(goblin, Zone::Hand, Zone::Battlefield, bob).unwrap();
+        for (controller, zone, description) in [(alice, Zone::Exile, "exile"), (bob, Zone::Hand, "bounce")] {
+            game.add_replacement_effect(
+                None,
+                controller,
+                replacement(
+                    dies(),
+                    Some("Creature"),
+                    ReplacementAction::ChangeZone(zone),
+                    description,
+                ),
+            );
+        }
+
+        // Both creatures die in one action: each controller chooses for their own
+        let (destinations, asked) = with_picks(&mut game, &[1, 0], |game| {
+            Ok((
+                game.move_card(bear, Zone::Battlefield, Zone::Graveyard, alice)?,
+                game.move_card(goblin, Zone::Battlefield, Zone::Graveyard, bob)?,
+            ))
+        });
+        assert_eq!(destinations, (Zone::Hand, Zone::Exile));
+        assert_eq!(
+            asked.iter().map(|choice| choice.player).collect::<Vec<_>>(),
+            vec![alice, bob]
+        );
+    }
+
+    #[test]
+    fn test_prevention_shield_is_used_up() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let red = add_card(&mut game, bob, "Goblin", CardType::Creature);
+        game.cards.get_mut(red).unwrap().colors.push(crate::core::Color::Red);
+        let green = add_card(&mut game, bob, "Bear", CardType::Creature);
+        game.cards
+            .get_mut(green)
+            .unwrap()
+            .colors
+            .push(crate::core::Color::Green);
+
+        // "The next time a red source of your choice would deal damage to you this
+        // turn, prevent that damage" (Circle of Protection: Red)
+        let mut shield = replacement(
+            ReplacementEvent::DamageDone { combat_only: false },
+            Some("You"),
+            ReplacementAction::Prevent,
+            "Prevent the next damage a red source would deal to you",
+        );
+        shield.valid_source = Some(Selector::parse("Card.Red"));
+        shield.single_use = true;
+        game.add_replacement_effect(None, alice, shield);
+
+        // Damage from a green source isn't prevented
+        game.deal_damage_from(Some(green), &TargetRef::Player(alice), 2, false)
+            .unwrap();
+        assert_eq!(game.get_player(alice).unwrap().life, 18);
+
+        // The first damage from a red source is, and that uses up the shield
+        let dealt = game
+            .deal_damage_from(Some(red), &TargetRef::Player(alice), 3, false)
+            .unwrap();
+        assert_eq!(dealt, 0);
+        assert_eq!(game.get_player(alice).unwrap().life, 18);
+        assert!(game.replacement_effects.is_empty());
+        game.deal_damage_from(Some(red), &TargetRef::Player(alice), 3, false)
+            .unwrap();
+        assert_eq!(game.get_player(alice).unwrap().life, 15);
+    }
+
+    #[test]
+    fn test_damage_shield_is_used_up_across_events() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let goblin = add_card(&mut game, bob, "Goblin", CardType::Creature);
+
+        // "Prevent the next 3 damage that would be dealt to you this turn" (Healing Salve)
+        let id = game.add_replacement_effect(
+            None,
+            alice,
+            replacement(
+                ReplacementEvent::DamageDone { combat_only: false },
+                Some("You"),
+                ReplacementAction::PreventAmount(3),
+                "Prevent the next 3 damage that would be dealt to you this turn",
+            ),
+        );
+        game.reset_zobrist_hash();
+
+        // The first 2 damage is prevented and the shield keeps what's left
+        let dealt = game
+            .deal_damage_from(Some(goblin), &TargetRef::Player(alice), 2, false)
+            .unwrap();
+        assert_eq!(dealt, 0);
+        assert_eq!(game.get_player(alice).unwrap().life, 20);
+        assert_eq!(game.replacement_effects[0].effect.shield_remaining(), Some(1));
+        assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+
+        // The next event uses up the last 1, and the rest is dealt
+        let dealt = game
+            .deal_damage_from(Some(goblin), &TargetRef::Player(alice), 2, false)
+            .unwrap();
+        assert_eq!(dealt, 1);
+        assert_eq!(game.get_player(alice).unwrap().life, 19);
+        assert!(game.replacement_effects.is_empty());
+        assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+
+        // Undo brings the shield back as it was before each event
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.replacement_effects[0].effect.shield_remaining(), Some(1));
+        while game.undo_log.len() > 1 {
+            game.undo().unwrap();
+        }
+        assert_eq!(game.replacement_effects[0].id, id);
+        assert_eq!(game.replacement_effects[0].effect.shield_remaining(), Some(3));
+        assert_eq!(game.get_player(alice).unwrap().life, 20);
+        assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+    }
+
+    #[test]
+    fn test_draw_and_life_replacements() {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let card = add_card(&mut game, alice, "Island", CardType::Land);
+        game.move_card(card, Zone::Hand, Zone::Library, alice).unwrap();
+
+        // "If you would draw a card, gain 2 life instead"
+        game.add_replacement_effect(
+            None,
+            alice,
+            replacement(
+                ReplacementEvent::Draw,
+                Some("You"),
+                ReplacementAction::Instead(vec![Effect::GainLife {
+                    player: PlayerId::new(0),
+                    amount: 2,
+                }]),
+                "gain life instead",
+            ),
+        );
+        // "Your opponents can't gain life"
+        game.add_replacement_effect(
+            None,
+            bob,
+            replacement(
+                ReplacementEvent::GainLife,
+                Some("Opponent"),
+                ReplacementAction::Prevent,
+                "no life gain",
+            ),
+        );
+
+        assert_eq!(game.draw_card(alice).unwrap(), None);
+        assert!(game.get_player_zones(alice).unwrap().library.contains(card));
+        assert_eq!(game.get_player(alice).unwrap().life, 20);
+
+        // Bob isn't an opponent of himself
+        assert_eq!(game.gain_life(bob, 3).unwrap(), 3);
+        assert_eq!(game.get_player(bob).unwrap().life, 23);
+    }
+
+    #[test]
+    fn test_fog_prevents_combat_damage() {
+        use crate::game::zero_controller::ZeroController;
+        use crate::loader::CardLoader;
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let attacker = add_card(&mut game, alice, "Bear", CardType::Creature);
+        game.move_card(attacker, Zone::Hand, Zone::Battlefield, alice).unwrap();
+        game.combat.declare_attacker(attacker, bob);
+
+        let fog = CardLoader::parse(
+            r#"
+Name:Fog
+ManaCost:G
+Types:Instant
+A:SP$ Effect | ReplacementEffects$ FogPrevent | SpellDescription$ Prevent all combat damage that would be dealt this turn.
+SVar:FogPrevent:Event$ DamageDone | IsCombat$ True | Prevent$ True | Description$ Prevent all combat damage that would be dealt this turn.
+"#,
+        )
+        .unwrap();
+        let fog_id = game.next_card_id();
+        game.cards.insert(fog_id, fog.instantiate(fog_id, bob));
+        game.stack.add_with_targets(fog_id, vec![]);
+        game.resolve_spell(fog_id).unwrap();
+        assert_eq!(game.replacement_effects.len(), 1);
+        assert_eq!(game.replacement_effects[0].controller, bob);
+
+        let mut controller1 = ZeroController::new(alice);
+        let mut controller2 = ZeroController::new(bob);
+        game.assign_combat_damage(&mut controller1, &mut controller2, false)
+            .unwrap();
+        assert_eq!(game.get_player(bob).unwrap().life, 20);
+
+        // Noncombat damage is still dealt
+        game.deal_damage_from(Some(attacker), &TargetRef::Player(bob), 2, false)
+            .unwrap();
+        assert_eq!(game.get_player(bob).unwrap().life, 18);
+    }
+}
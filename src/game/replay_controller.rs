@@ -4,12 +4,13 @@
 //! choices (from the snapshot's intra-turn choice log), then hands control to the
 //! wrapped controller for subsequent choices.
 
-use crate::core::{CardId, ManaCost, PlayerId, SpellAbility};
+use crate::core::{CardId, Color, Cost, ManaCost, PlayerId, ReplacementKey, SpellAbility};
 use crate::game::controller::{GameStateView, PlayerController};
+use crate::game::{AttackTarget, StackAbility};
 use smallvec::SmallVec;
 
 /// A single recorded choice from a controller
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ReplayChoice {
     /// Choice of spell ability to play (or None to pass priority)
     SpellAbility(Option<SpellAbility>),
@@ -17,14 +18,36 @@ pub enum ReplayChoice {
     Targets(SmallVec<[CardId; 4]>),
     /// Choice of mana sources to tap
     ManaSources(SmallVec<[CardId; 8]>),
-    /// Choice of attackers
-    Attackers(SmallVec<[CardId; 8]>),
+    /// Choice of attackers and what each one attacks
+    Attackers(SmallVec<[(CardId, AttackTarget); 8]>),
     /// Choice of blockers
     Blockers(SmallVec<[(CardId, CardId); 8]>),
     /// Choice of damage assignment order
     DamageOrder(SmallVec<[CardId; 4]>),
     /// Choice of cards to discard
     Discard(SmallVec<[CardId; 7]>),
+    /// Choice of order for simultaneous triggers (indices into the pending triggers)
+    TriggerOrder(SmallVec<[usize; 4]>),
+    /// Choice of modes for a modal spell (indices into its modes)
+    Modes(SmallVec<[usize; 4]>),
+    /// Choice of the value of X for a spell
+    XValue(u8),
+    /// Decision to pay a spell's alternative cost (true) or its mana cost (false)
+    AlternativeCost(bool),
+    /// Choice of how to divide an amount among a spell's targets
+    Division(SmallVec<[u32; 4]>),
+    /// Choice of the replacement effect to apply first to an event
+    ReplacementEffect(ReplacementKey),
+    /// Choice of the color of mana a mana ability adds
+    ManaColor(Color),
+    /// Choice of legendary permanent to keep under the legend rule
+    LegendToKeep(CardId),
+    /// Decision to mulligan (true) or keep (false) an opening hand
+    Mulligan(bool),
+    /// Choice of cards to put on the bottom of the library after a mulligan
+    Bottom(SmallVec<[CardId; 7]>),
+    /// Choice of cards to pay part of an ability's cost (sacrifice, discard, etc.)
+    CostCards(SmallVec<[CardId; 8]>),
 }
 
 /// Controller that replays a sequence of choices then delegates to another controller
@@ -129,6 +152,61 @@ impl PlayerController for ReplayController {
         self.inner.choose_spell_ability_to_play(view, available)
     }
 
+    fn choose_modes(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        modes: &[String],
+        min: u8,
+        max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        // Try to consume a replay choice first
+        if let Some(chosen) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::Modes(m) = c {
+                Some(m.clone())
+            } else {
+                None
+            }
+        }) {
+            return chosen;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_modes(view, spell, modes, min, max)
+    }
+
+    fn choose_x_value(&mut self, view: &GameStateView, spell: CardId, max_x: u8) -> u8 {
+        // Try to consume a replay choice first
+        if let Some(x) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::XValue(x) = c {
+                Some(*x)
+            } else {
+                None
+            }
+        }) {
+            return x;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_x_value(view, spell, max_x)
+    }
+
+    fn choose_alternative_cost(&mut self, view: &GameStateView, spell: CardId, cost: &Cost) -> bool {
+        // Try to consume a replay choice first
+        if let Some(alternative) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::AlternativeCost(a) = c {
+                Some(*a)
+            } else {
+                None
+            }
+        }) {
+            return alternative;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_alternative_cost(view, spell, cost)
+    }
+
     fn choose_targets(
         &mut self,
         view: &GameStateView,
@@ -150,6 +228,28 @@ impl PlayerController for ReplayController {
         self.inner.choose_targets(view, spell, valid_targets)
     }
 
+    fn choose_division(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        targets: &[CardId],
+        total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        // Try to consume a replay choice first
+        if let Some(division) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::Division(d) = c {
+                Some(d.clone())
+            } else {
+                None
+            }
+        }) {
+            return division;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_division(view, spell, targets, total)
+    }
+
     fn choose_mana_sources_to_pay(
         &mut self,
         view: &GameStateView,
@@ -171,7 +271,28 @@ impl PlayerController for ReplayController {
         self.inner.choose_mana_sources_to_pay(view, cost, available_sources)
     }
 
-    fn choose_attackers(&mut self, view: &GameStateView, available_creatures: &[CardId]) -> SmallVec<[CardId; 8]> {
+    fn choose_mana_color(&mut self, view: &GameStateView, source: CardId, colors: &[Color]) -> Color {
+        // Try to consume a replay choice first
+        if let Some(color) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::ManaColor(color) = c {
+                Some(*color)
+            } else {
+                None
+            }
+        }) {
+            return color;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_mana_color(view, source, colors)
+    }
+
+    fn choose_attackers(
+        &mut self,
+        view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
         // Try to consume a replay choice first
         if let Some(attackers) = self.consume_replay_choice(|c| {
             if let ReplayChoice::Attackers(a) = c {
@@ -184,7 +305,7 @@ impl PlayerController for ReplayController {
         }
 
         // No replay choice available, delegate to inner controller
-        self.inner.choose_attackers(view, available_creatures)
+        self.inner.choose_attackers(view, available_creatures, defenders)
     }
 
     fn choose_blockers(
@@ -229,6 +350,109 @@ impl PlayerController for ReplayController {
         self.inner.choose_damage_assignment_order(view, attacker, blockers)
     }
 
+    fn choose_trigger_order(&mut self, view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        // Try to consume a replay choice first
+        if let Some(order) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::TriggerOrder(o) = c {
+                Some(o.clone())
+            } else {
+                None
+            }
+        }) {
+            return order;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_trigger_order(view, triggers)
+    }
+
+    fn choose_replacement_effect(&mut self, view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        // Try to consume a replay choice first
+        if let Some(key) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::ReplacementEffect(k) = c {
+                Some(*k)
+            } else {
+                None
+            }
+        }) {
+            return key;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_replacement_effect(view, effects)
+    }
+
+    fn choose_legend_to_keep(&mut self, view: &GameStateView, legends: &[CardId]) -> CardId {
+        // Try to consume a replay choice first
+        if let Some(keep) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::LegendToKeep(k) = c {
+                Some(*k)
+            } else {
+                None
+            }
+        }) {
+            return keep;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_legend_to_keep(view, legends)
+    }
+
+    fn choose_mulligan(&mut self, view: &GameStateView, hand: &[CardId], mulligans_taken: u8) -> bool {
+        // Try to consume a replay choice first
+        if let Some(mulligan) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::Mulligan(m) = c {
+                Some(*m)
+            } else {
+                None
+            }
+        }) {
+            return mulligan;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_mulligan(view, hand, mulligans_taken)
+    }
+
+    fn choose_cards_to_bottom(&mut self, view: &GameStateView, hand: &[CardId], count: usize) -> SmallVec<[CardId; 7]> {
+        // Try to consume a replay choice first
+        if let Some(cards) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::Bottom(b) = c {
+                Some(b.clone())
+            } else {
+                None
+            }
+        }) {
+            return cards;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_cards_to_bottom(view, hand, count)
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        view: &GameStateView,
+        source: CardId,
+        cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        // Try to consume a replay choice first
+        if let Some(cards) = self.consume_replay_choice(|c| {
+            if let ReplayChoice::CostCards(cards) = c {
+                Some(cards.clone())
+            } else {
+                None
+            }
+        }) {
+            return cards;
+        }
+
+        // No replay choice available, delegate to inner controller
+        self.inner.choose_cards_for_cost(view, source, cost, candidates, count)
+    }
+
     fn choose_cards_to_discard(
         &mut self,
         view: &GameStateView,
@@ -14,8 +14,9 @@
 //!
 //! Comma-separated clauses: `BlackKnight blocks WhiteKnight, SerraAngel blocks RoyalAssassin`
 
-use crate::core::{CardId, ManaCost, PlayerId, SpellAbility};
+use crate::core::{CardId, Color, Cost, ManaCost, PlayerId, ReplacementKey, SpellAbility};
 use crate::game::controller::{GameStateView, PlayerController};
+use crate::game::{AttackTarget, StackAbility};
 use smallvec::SmallVec;
 
 /// Controller that parses rich text commands
@@ -71,6 +72,26 @@ impl RichInputController {
         normalized_card.starts_with(&normalized_pattern)
     }
 
+    /// Split an "attack" clause into the creature pattern and what it attacks
+    ///
+    /// "grizzly bears" attacks the defending player; "grizzly bears at garruk"
+    /// attacks the matching player or planeswalker. Returns None if nothing
+    /// matches the part after "at".
+    pub fn parse_attack_clause<'c>(
+        clause: &'c str,
+        view: &GameStateView,
+        defenders: &[AttackTarget],
+    ) -> Option<(&'c str, AttackTarget)> {
+        match clause.split_once(" at ") {
+            Some((creature, defender)) => defenders
+                .iter()
+                .copied()
+                .find(|&target| Self::card_matches(&view.attack_target_name(target), defender.trim()))
+                .map(|target| (creature.trim(), target)),
+            None => defenders.first().map(|&target| (clause.trim(), target)),
+        }
+    }
+
     /// Parse a spell ability choice command
     ///
     /// Examples: "play swamp", "cast lightning bolt", "0", "pass"
@@ -147,6 +168,30 @@ impl PlayerController for RichInputController {
         }
     }
 
+    fn choose_modes(
+        &mut self,
+        _view: &GameStateView,
+        _spell: CardId,
+        modes: &[String],
+        min: u8,
+        _max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        // For now, just take the first modes
+        // TODO: Implement rich syntax for mode selection
+        (0..modes.len()).take(min as usize).collect()
+    }
+
+    fn choose_x_value(&mut self, _view: &GameStateView, _spell: CardId, max_x: u8) -> u8 {
+        // Pay as much as possible until there is syntax for choosing X
+        max_x
+    }
+
+    fn choose_alternative_cost(&mut self, _view: &GameStateView, _spell: CardId, _cost: &Cost) -> bool {
+        // Simple: pay the mana cost
+        // TODO: Implement rich syntax for alternative costs
+        false
+    }
+
     fn choose_targets(
         &mut self,
         _view: &GameStateView,
@@ -171,6 +216,21 @@ impl PlayerController for RichInputController {
         targets
     }
 
+    fn choose_division(
+        &mut self,
+        _view: &GameStateView,
+        _spell: CardId,
+        targets: &[CardId],
+        total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        // Split as evenly as possible, earlier targets getting the remainder
+        // TODO: Implement rich syntax for dividing
+        let count = targets.len() as u32;
+        (0..count)
+            .map(|i| total / count + u32::from(i < total % count))
+            .collect()
+    }
+
     fn choose_mana_sources_to_pay(
         &mut self,
         _view: &GameStateView,
@@ -188,7 +248,21 @@ impl PlayerController for RichInputController {
         sources
     }
 
-    fn choose_attackers(&mut self, view: &GameStateView, available_creatures: &[CardId]) -> SmallVec<[CardId; 8]> {
+    fn choose_mana_color(&mut self, _view: &GameStateView, _source: CardId, colors: &[Color]) -> Color {
+        // Simple: choose the first color
+        // TODO: Implement rich syntax for mana color choices
+        colors[0]
+    }
+
+    fn choose_attackers(
+        &mut self,
+        view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
+        let Some(&defender) = defenders.first() else {
+            return SmallVec::new();
+        };
         if available_creatures.is_empty() {
             return SmallVec::new();
         }
@@ -199,18 +273,27 @@ impl PlayerController for RichInputController {
             // Handle numeric choice (legacy format)
             if let Ok(num) = cmd.parse::<usize>() {
                 let num_attackers = num.min(available_creatures.len());
-                return available_creatures.iter().take(num_attackers).copied().collect();
+                return available_creatures
+                    .iter()
+                    .take(num_attackers)
+                    .map(|&creature| (creature, defender))
+                    .collect();
             }
 
-            // Parse "attack X" commands
-            let mut attackers = SmallVec::new();
+            // Parse "attack X" and "attack X at Y" commands
+            let mut attackers: SmallVec<[(CardId, AttackTarget); 8]> = SmallVec::new();
             for clause in command.split(';') {
                 let clause = clause.trim().to_lowercase();
-                if let Some(card_pattern) = clause.strip_prefix("attack ") {
+                if let Some(attack) = clause.strip_prefix("attack ") {
+                    let Some((card_pattern, target)) = Self::parse_attack_clause(attack, view, defenders) else {
+                        continue;
+                    };
                     for &creature_id in available_creatures {
                         if let Some(card_name) = view.card_name(creature_id) {
-                            if Self::card_matches(&card_name, card_pattern) && !attackers.contains(&creature_id) {
-                                attackers.push(creature_id);
+                            if Self::card_matches(&card_name, card_pattern)
+                                && !attackers.iter().any(|&(id, _)| id == creature_id)
+                            {
+                                attackers.push((creature_id, target));
                             }
                         }
                     }
@@ -306,6 +389,52 @@ impl PlayerController for RichInputController {
         blockers.iter().copied().collect()
     }
 
+    fn choose_trigger_order(&mut self, _view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        // Keep original order (no reordering via rich input yet)
+        (0..triggers.len()).collect()
+    }
+
+    fn choose_replacement_effect(&mut self, _view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        // Simple: apply the oldest one first
+        // TODO: Implement rich syntax for replacement effect choices
+        effects[0]
+    }
+
+    fn choose_legend_to_keep(&mut self, _view: &GameStateView, legends: &[CardId]) -> CardId {
+        // Simple: keep the first one
+        // TODO: Implement rich syntax for legend rule choices
+        legends[0]
+    }
+
+    fn choose_mulligan(&mut self, _view: &GameStateView, _hand: &[CardId], _mulligans_taken: u8) -> bool {
+        // Simple: always keep
+        // TODO: Implement rich syntax for mulligan decisions
+        false
+    }
+
+    fn choose_cards_to_bottom(
+        &mut self,
+        _view: &GameStateView,
+        hand: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 7]> {
+        // Simple: put the first N cards on the bottom
+        hand.iter().take(count).copied().collect()
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        _view: &GameStateView,
+        _source: CardId,
+        _cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        // Simple: pay with the first N candidates
+        // TODO: Implement rich syntax for cost choices
+        candidates.iter().take(count).copied().collect()
+    }
+
     fn choose_cards_to_discard(
         &mut self,
         _view: &GameStateView,
@@ -0,0 +1,578 @@
+//! The stack (MTG Rules 405)
+//!
+//! Spells are cards in the stack zone, but abilities on the stack are not cards.
+//! The stack therefore keeps an ordered list of stack objects instead of a plain
+//! card zone, so spells and abilities interleave in LIFO order.
+//!
+//! Triggered abilities don't go on the stack the moment they trigger. They wait in
+//! `GameState::pending_triggers` until a player would next receive priority; then each
+//! player, in APNAP order, puts theirs on the stack in an order they choose (MTG Rules 603.3b).
+//!
+//! Activated abilities other than mana abilities go on the stack as soon as their
+//! costs are paid (MTG Rules 602.2), so opponents get priority to respond.
+
+use crate::core::{CardId, Effect, PlayerId, Selector, SelectorContext};
+use crate::game::GameState;
+use crate::{MtgError, Result};
+use serde::{Deserialize, Serialize};
+
+/// An ability on the stack (or waiting to be put there)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackAbility {
+    /// The permanent the ability came from (it may leave play before resolution)
+    pub source: CardId,
+    /// The player who controls the ability
+    pub controller: PlayerId,
+    /// Effects with targets and players already filled in
+    pub effects: Vec<Effect>,
+    /// Targets chosen when the ability was put on the stack
+    pub targets: Vec<CardId>,
+    /// Description (for logging and for choosing trigger order)
+    pub description: String,
+    /// Restriction on what the ability may target (from ValidTgts$), checked again
+    /// on resolution
+    #[serde(default)]
+    pub target_restriction: Option<Selector>,
+}
+
+/// Choices made while casting a spell (MTG Rules 601.2b-d), used when it resolves
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SpellChoices {
+    /// Chosen modes of a modal spell, as indices into `Card::modes`
+    pub modes: Vec<usize>,
+    /// The value chosen for X (0 if the mana cost has no X)
+    pub x_value: u8,
+    /// Chosen targets
+    pub targets: Vec<CardId>,
+    /// How a divided amount is split among `targets` (one entry per target),
+    /// or empty if the spell doesn't divide anything
+    pub division: Vec<u32>,
+    /// Whether the spell's alternative cost was paid rather than its mana cost
+    pub alternative_cost: bool,
+}
+
+/// An object on the stack
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StackObject {
+    /// A spell (a card in the stack zone) with the choices made when it was cast
+    Spell { card_id: CardId, choices: SpellChoices },
+    /// A triggered ability
+    TriggeredAbility(StackAbility),
+    /// An activated ability; `ability_index` indexes the source's `activated_abilities`
+    ActivatedAbility {
+        ability: StackAbility,
+        ability_index: usize,
+    },
+}
+
+impl StackObject {
+    /// The ability, if this object is one
+    pub fn ability(&self) -> Option<&StackAbility> {
+        match self {
+            StackObject::Spell { .. } => None,
+            StackObject::TriggeredAbility(ability) | StackObject::ActivatedAbility { ability, .. } => Some(ability),
+        }
+    }
+}
+
+/// The stack, bottom first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stack {
+    objects: Vec<StackObject>,
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        Stack::default()
+    }
+
+    /// Put a spell on top of the stack
+    pub fn add(&mut self, card_id: CardId) {
+        self.add_with_targets(card_id, Vec::new());
+    }
+
+    /// Put a spell with already chosen targets on top of the stack
+    pub fn add_with_targets(&mut self, card_id: CardId, targets: Vec<CardId>) {
+        self.add_with_choices(
+            card_id,
+            SpellChoices {
+                targets,
+                ..SpellChoices::default()
+            },
+        );
+    }
+
+    /// Put a spell with all its casting choices already made on top of the stack
+    pub fn add_with_choices(&mut self, card_id: CardId, choices: SpellChoices) {
+        self.objects.push(StackObject::Spell { card_id, choices });
+    }
+
+    /// Remove a spell from anywhere in the stack
+    pub fn remove(&mut self, card_id: CardId) -> bool {
+        if let Some(pos) = self.spell_position(card_id) {
+            self.objects.remove(pos);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Position of a spell on the stack (0 is the bottom)
+    pub fn spell_position(&self, card_id: CardId) -> Option<usize> {
+        self.objects
+            .iter()
+            .position(|o| matches!(o, StackObject::Spell { card_id: id, .. } if *id == card_id))
+    }
+
+    /// The choices made when casting a spell on the stack
+    pub fn spell_choices(&self, card_id: CardId) -> Option<&SpellChoices> {
+        match &self.objects[self.spell_position(card_id)?] {
+            StackObject::Spell { choices, .. } => Some(choices),
+            _ => None,
+        }
+    }
+
+    /// The targets chosen for a spell on the stack
+    pub fn spell_targets(&self, card_id: CardId) -> Option<&[CardId]> {
+        self.spell_choices(card_id).map(|choices| choices.targets.as_slice())
+    }
+
+    fn spell_choices_mut(&mut self, card_id: CardId) -> Option<&mut SpellChoices> {
+        let pos = self.spell_position(card_id)?;
+        match &mut self.objects[pos] {
+            StackObject::Spell { choices, .. } => Some(choices),
+            _ => None,
+        }
+    }
+
+    /// Check if a spell is on the stack
+    pub fn contains(&self, card_id: CardId) -> bool {
+        self.spells().any(|id| id == card_id)
+    }
+
+    /// Spells on the stack, bottom first
+    pub fn spells(&self) -> impl Iterator<Item = CardId> + '_ {
+        self.objects.iter().filter_map(|o| match o {
+            StackObject::Spell { card_id, .. } => Some(*card_id),
+            _ => None,
+        })
+    }
+
+    /// All stack objects, bottom first
+    pub fn objects(&self) -> &[StackObject] {
+        &self.objects
+    }
+
+    /// The object that resolves next
+    pub fn top(&self) -> Option<&StackObject> {
+        self.objects.last()
+    }
+
+    pub fn push(&mut self, object: StackObject) {
+        self.objects.push(object);
+    }
+
+    pub fn pop(&mut self) -> Option<StackObject> {
+        self.objects.pop()
+    }
+
+    /// Remove the object at `index` (0 is the bottom of the stack)
+    pub fn remove_at(&mut self, index: usize) -> Option<StackObject> {
+        (index < self.objects.len()).then(|| self.objects.remove(index))
+    }
+
+    /// Put an object back at `index`, e.g. when rewinding `remove_at`
+    pub fn insert_at(&mut self, index: usize, object: StackObject) {
+        self.objects.insert(index.min(self.objects.len()), object);
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+impl GameState {
+    /// Record the choices made while casting a spell on the stack (MTG Rules 601.2b-c)
+    pub fn set_spell_choices(&mut self, card_id: CardId, choices: SpellChoices) -> Result<()> {
+        let slot = self
+            .stack
+            .spell_choices_mut(card_id)
+            .ok_or_else(|| MtgError::InvalidAction(format!("Spell {card_id} is not on the stack")))?;
+        let previous = std::mem::replace(slot, choices.clone());
+        self.undo_log.log(crate::undo::GameAction::SetSpellChoices {
+            card_id,
+            choices: Box::new(choices),
+            previous: Box::new(previous),
+        });
+        Ok(())
+    }
+
+    /// Record the targets of a spell on the stack (MTG Rules 601.2c)
+    pub fn set_spell_targets(&mut self, card_id: CardId, targets: Vec<CardId>) -> Result<()> {
+        let mut choices = self.stack.spell_choices(card_id).cloned().unwrap_or_default();
+        choices.targets = targets;
+        self.set_spell_choices(card_id, choices)
+    }
+
+    /// Undo helper: put back a spell's choices without logging
+    pub(crate) fn restore_spell_choices(&mut self, card_id: CardId, choices: SpellChoices) {
+        if let Some(slot) = self.stack.spell_choices_mut(card_id) {
+            *slot = choices;
+        }
+    }
+
+    /// The effects a spell on the stack will have, given the modes and X chosen for it
+    ///
+    /// A divided effect becomes one effect per target with that target's share
+    /// (MTG Rules 601.2d); other placeholder targets are filled in on resolution.
+    pub fn spell_effects(&self, card_id: CardId) -> Result<Vec<Effect>> {
+        let choices = self
+            .stack
+            .spell_choices(card_id)
+            .ok_or_else(|| MtgError::InvalidAction(format!("Spell {card_id} is not on the stack")))?;
+        let mut effects = self.cards.get(card_id)?.spell_effects(&choices.modes, choices.x_value);
+
+        if !choices.division.is_empty() {
+            if let Some(index) = effects.iter().position(|e| e.divisible_amount().is_some()) {
+                let divided = effects[index].clone();
+                let shares = choices
+                    .targets
+                    .iter()
+                    .zip(&choices.division)
+                    .map(|(&target, &share)| divided.with_divided_share(self.target_ref(target), share));
+                effects.splice(index..=index, shares);
+            }
+        }
+        Ok(effects)
+    }
+
+    /// Record a triggered ability; it goes on the stack the next time a player would receive priority
+    pub fn add_pending_trigger(&mut self, ability: StackAbility) {
+        self.pending_triggers.push(ability.clone());
+        self.undo_log.log(crate::undo::GameAction::AddPendingTrigger {
+            ability: Box::new(ability),
+        });
+    }
+
+    /// Indices into `pending_triggers` of the triggers controlled by a player
+    pub fn pending_triggers_for(&self, player_id: PlayerId) -> Vec<usize> {
+        self.pending_triggers
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.controller == player_id)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Fill in the chosen targets of a pending trigger (MTG Rules 603.3d)
+    ///
+    /// Each effect's placeholder target becomes the first chosen target.
+    pub fn target_pending_trigger(&mut self, index: usize, targets: &[CardId]) -> Result<()> {
+        let (Some(ability), Some(&target)) = (self.pending_triggers.get_mut(index), targets.first()) else {
+            return Err(MtgError::InvalidAction(format!(
+                "Can't target pending trigger {index} with {targets:?}"
+            )));
+        };
+        let previous = ability.clone();
+        ability.effects = ability
+            .effects
+            .iter()
+            .map(|effect| effect.with_target(target))
+            .collect();
+        ability.targets = targets.to_vec();
+        self.undo_log.log(crate::undo::GameAction::TargetPendingTrigger {
+            index,
+            ability: Box::new(ability.clone()),
+            previous: Box::new(previous),
+        });
+        Ok(())
+    }
+
+    /// Remove a pending trigger without putting it on the stack
+    ///
+    /// Used for a triggered ability that needs targets but has no legal ones
+    /// (MTG Rules 603.3d).
+    pub fn remove_pending_trigger(&mut self, index: usize) -> Result<StackAbility> {
+        if index >= self.pending_triggers.len() {
+            return Err(MtgError::InvalidAction(format!("No pending trigger {index}")));
+        }
+        let ability = self.pending_triggers.remove(index);
+        self.undo_log.log(crate::undo::GameAction::RemovePendingTrigger {
+            index,
+            ability: Box::new(ability.clone()),
+        });
+        Ok(ability)
+    }
+
+    /// Put a player's pending triggers on the stack (MTG Rules 603.3b)
+    ///
+    /// `order` indexes the player's pending triggers (as returned by
+    /// `pending_triggers_for`); the first one goes on the stack first and so
+    /// resolves last. It must be a permutation of all of them.
+    pub fn put_triggers_on_stack(&mut self, player_id: PlayerId, order: &[usize]) -> Result<()> {
+        let mine = self.pending_triggers_for(player_id);
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        if sorted != (0..mine.len()).collect::<Vec<_>>() {
+            return Err(MtgError::InvalidAction(format!(
+                "Trigger order {order:?} is not a permutation of {} pending triggers",
+                mine.len()
+            )));
+        }
+
+        let mut removed: Vec<usize> = Vec::with_capacity(order.len());
+        for &choice in order {
+            let original_index = mine[choice];
+            // Earlier removals shift later triggers down
+            let pending_index = original_index - removed.iter().filter(|&&r| r < original_index).count();
+            removed.push(original_index);
+
+            let ability = self.pending_triggers.remove(pending_index);
+            let message = format!(
+                "{} puts trigger on the stack: {}",
+                self.get_player(player_id).map(|p| p.name.as_str()).unwrap_or("Unknown"),
+                ability.description
+            );
+            self.logger.normal(&message);
+            self.stack.push(StackObject::TriggeredAbility(ability.clone()));
+            self.undo_log.log(crate::undo::GameAction::PutTriggerOnStack {
+                pending_index,
+                ability: Box::new(ability),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Put an activated ability on the stack (MTG Rules 602.2a)
+    ///
+    /// Costs must already be paid and `ability.effects` must have its targets filled in.
+    pub fn put_ability_on_stack(&mut self, ability: StackAbility, ability_index: usize) {
+        let message = format!(
+            "{} puts ability on the stack: {}",
+            self.get_player(ability.controller)
+                .map(|p| p.name.as_str())
+                .unwrap_or("Unknown"),
+            ability.description
+        );
+        self.logger.normal(&message);
+        let object = StackObject::ActivatedAbility { ability, ability_index };
+        self.stack.push(object.clone());
+        self.undo_log.log(crate::undo::GameAction::PutAbilityOnStack {
+            object: Box::new(object),
+        });
+    }
+
+    /// Resolve the ability on top of the stack
+    ///
+    /// The ability resolves even if its source has left the battlefield
+    /// (MTG Rules 113.7a). Returns the resolved ability for logging.
+    pub fn resolve_top_ability(&mut self) -> Result<StackAbility> {
+        if self.stack.top().and_then(StackObject::ability).is_none() {
+            return Err(MtgError::InvalidAction(
+                "Top of the stack is not an ability".to_string(),
+            ));
+        }
+        let object = self.stack.pop().expect("top of stack was just checked");
+        let ability = object.ability().expect("top of stack was just checked").clone();
+        self.undo_log.log(crate::undo::GameAction::ResolveAbility {
+            object: Box::new(object),
+        });
+
+        // MTG Rules 608.2b: an ability whose targets have all become illegal doesn't
+        // resolve; otherwise it doesn't affect the illegal ones
+        let restriction = ability.target_restriction.as_ref();
+        let illegal_targets: Vec<CardId> = ability
+            .targets
+            .iter()
+            .copied()
+            .filter(|&target_id| {
+                !self.is_legal_target(ability.controller, ability.source, target_id)
+                    || restriction.as_ref().is_some_and(|r| {
+                        let ctx = SelectorContext::new(ability.controller, Some(ability.source));
+                        !self.cards.get(target_id).is_ok_and(|t| r.matches_card(t, &ctx))
+                    })
+            })
+            .collect();
+
+        if !ability.targets.is_empty() && illegal_targets.len() == ability.targets.len() {
+            let message = format!(
+                "{} is countered on resolution (all its targets are illegal)",
+                ability.description
+            );
+            self.logger.normal(&message);
+        } else {
+            for effect in &ability.effects {
+                if effect.target_card().is_some_and(|t| illegal_targets.contains(&t)) {
+                    continue;
+                }
+                self.execute_effect_from(ability.source, effect)?;
+            }
+        }
+
+        // MTG Rules 704.3: state-based actions are checked once resolution is complete
+        self.check_state_based_actions()?;
+
+        Ok(ability)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::TargetRef;
+
+    fn ability(controller: PlayerId, description: &str, effects: Vec<Effect>) -> StackAbility {
+        StackAbility {
+            source: CardId::new(100),
+            controller,
+            effects,
+            targets: Vec::new(),
+            description: description.to_string(),
+            target_restriction: None,
+        }
+    }
+
+    #[test]
+    fn test_spells_and_abilities_interleave() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1 = game.players[0].id;
+
+        game.stack.add(CardId::new(10));
+        game.add_pending_trigger(ability(p1, "trigger", vec![]));
+        game.put_triggers_on_stack(p1, &[0]).unwrap();
+        game.stack.add(CardId::new(11));
+
+        assert_eq!(game.stack.len(), 3);
+        assert_eq!(
+            game.stack.spells().collect::<Vec<_>>(),
+            vec![CardId::new(10), CardId::new(11)]
+        );
+        assert!(game.stack.remove(CardId::new(11)));
+        assert!(matches!(game.stack.top(), Some(StackObject::TriggeredAbility(_))));
+    }
+
+    #[test]
+    fn test_put_triggers_on_stack_in_chosen_order() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1, p2) = (game.players[0].id, game.players[1].id);
+
+        game.add_pending_trigger(ability(p1, "first", vec![]));
+        game.add_pending_trigger(ability(p2, "opponent's", vec![]));
+        game.add_pending_trigger(ability(p1, "second", vec![]));
+
+        assert!(game.put_triggers_on_stack(p1, &[0, 0]).is_err());
+        game.put_triggers_on_stack(p1, &[1, 0]).unwrap();
+        assert_eq!(game.pending_triggers.len(), 1);
+        assert_eq!(game.pending_triggers_for(p2), vec![0]);
+
+        // "first" was put on the stack last, so it resolves first
+        let descriptions: Vec<_> = game
+            .stack
+            .objects()
+            .iter()
+            .map(|o| match o {
+                StackObject::Spell { .. } => "spell",
+                other => other.ability().unwrap().description.as_str(),
+            })
+            .collect();
+        assert_eq!(descriptions, vec!["second", "first"]);
+    }
+
+    #[test]
+    fn test_resolve_ability_and_undo() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1, p2) = (game.players[0].id, game.players[1].id);
+
+        game.add_pending_trigger(ability(
+            p1,
+            "deal 3 to opponent",
+            vec![Effect::DealDamage {
+                target: TargetRef::Player(p2),
+                amount: 3,
+            }],
+        ));
+        game.put_triggers_on_stack(p1, &[0]).unwrap();
+
+        let resolved = game.resolve_top_ability().unwrap();
+        assert_eq!(resolved.description, "deal 3 to opponent");
+        assert_eq!(game.get_player(p2).unwrap().life, 17);
+        assert!(game.stack.is_empty());
+        assert!(game.resolve_top_ability().is_err());
+
+        while game.undo().unwrap() {}
+        assert_eq!(game.get_player(p2).unwrap().life, 20);
+        assert!(game.stack.is_empty());
+        assert_eq!(game.pending_triggers.len(), 0);
+    }
+
+    #[test]
+    fn test_ability_with_only_illegal_targets_does_nothing() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1 = game.players[0].id;
+
+        let bear = game.next_card_id();
+        let mut card = crate::core::Card::new(bear, "Grizzly Bears".to_string(), p1);
+        card.types.push(crate::core::CardType::Creature);
+        card.power = Some(2);
+        card.toughness = Some(2);
+        game.cards.insert(bear, card);
+
+        let mut pump = ability(
+            p1,
+            "target creature gets +2/+2",
+            vec![Effect::PumpCreature {
+                target: bear,
+                power_bonus: 2,
+                toughness_bonus: 2,
+            }],
+        );
+        pump.targets = vec![bear];
+        game.put_ability_on_stack(pump, 0);
+
+        // The target is not on the battlefield
+        game.resolve_top_ability().unwrap();
+        assert_eq!(game.cards.get(bear).unwrap().current_power(), 2);
+        assert!(game.stack.is_empty());
+    }
+
+    #[test]
+    fn test_activated_ability_resolves_without_source() {
+        let mut game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let (p1, p2) = (game.players[0].id, game.players[1].id);
+
+        // The source (CardId 100) doesn't exist at all - the ability is independent of it
+        game.put_ability_on_stack(
+            ability(
+                p1,
+                "deal 2 to opponent",
+                vec![Effect::DealDamage {
+                    target: TargetRef::Player(p2),
+                    amount: 2,
+                }],
+            ),
+            0,
+        );
+        assert!(matches!(
+            game.stack.top(),
+            Some(StackObject::ActivatedAbility { ability_index: 0, .. })
+        ));
+
+        game.resolve_top_ability().unwrap();
+        assert_eq!(game.get_player(p2).unwrap().life, 18);
+        assert!(game.stack.is_empty());
+
+        // Undoing the resolution puts the same kind of object back on the stack
+        while game.undo().unwrap() {
+            if game.stack.len() == 1 {
+                assert!(matches!(game.stack.top(), Some(StackObject::ActivatedAbility { .. })));
+            }
+        }
+        assert!(game.stack.is_empty());
+        assert_eq!(game.get_player(p2).unwrap().life, 20);
+    }
+}
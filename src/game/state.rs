@@ -1,14 +1,17 @@
 //! Main game state structure
 
-use crate::core::{Card, CardId, EntityId, EntityStore, Player, PlayerId};
-use crate::game::{CombatState, GameLogger, TurnStructure};
-use crate::undo::UndoLog;
+use crate::core::{Card, CardId, EntityId, EntityStore, Player, PlayerId, ReplacementKey, TargetRef};
+use crate::game::{
+    CombatState, GameLogger, RegisteredReplacement, ReplacementChoices, Stack, StackAbility, TurnStructure,
+};
+use crate::undo::{Checkpoint, UndoLog};
 use crate::zones::{CardZone, PlayerZones, Zone};
 use crate::Result;
 use rand::SeedableRng;
 use rand_chacha::ChaCha12Rng;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::collections::HashMap;
 
 /// Complete game state
 ///
@@ -29,7 +32,10 @@ pub struct GameState {
     pub battlefield: CardZone,
 
     /// The stack (for spells and abilities)
-    pub stack: CardZone,
+    pub stack: Stack,
+
+    /// Triggered abilities waiting to be put on the stack, in the order they triggered
+    pub pending_triggers: Vec<StackAbility>,
 
     /// Turn structure
     pub turn: TurnStructure,
@@ -37,6 +43,25 @@ pub struct GameState {
     /// Combat state (active during combat phase)
     pub combat: CombatState,
 
+    /// Replacement effects created by resolved spells and abilities (Fog);
+    /// they end in the cleanup step
+    pub replacement_effects: Vec<RegisteredReplacement>,
+
+    /// The replacement effect choices made for the action being carried out
+    /// (MTG Rules 616.1, see `ReplacementChoices`)
+    #[serde(skip)]
+    pub(crate) replacement_choices: ReplacementChoices,
+
+    /// Replacement effects whose replacing effects are being carried out; they
+    /// don't apply to the events those cause (MTG Rules 614.5)
+    #[serde(skip)]
+    pub(crate) replacements_applying: Vec<ReplacementKey>,
+
+    /// In debug mode, the state hash first seen with each Zobrist hash, to catch
+    /// different states that the Zobrist hash can't tell apart
+    #[serde(skip)]
+    state_hashes_by_zobrist: RefCell<HashMap<u64, u64>>,
+
     /// Random number generator for gameplay (serializable for deterministic replay)
     /// This RNG is used by controllers and game logic for random decisions.
     /// Unlike the initial seed, this captures the CURRENT RNG state.
@@ -58,39 +83,51 @@ pub struct GameState {
 impl GameState {
     /// Create a new game with two players
     pub fn new_two_player(player1_name: String, player2_name: String, starting_life: i32) -> Self {
+        Self::new_multiplayer(vec![player1_name, player2_name], starting_life)
+    }
+
+    /// Create a new game with any number of players, seated in turn order
+    ///
+    /// The first player takes the first turn.
+    pub fn new_multiplayer(player_names: Vec<String>, starting_life: i32) -> Self {
         let mut next_id = 0;
 
         // Create players with unified IDs
-        let p1_id = PlayerId::new(next_id);
-        next_id += 1;
-        let p2_id = PlayerId::new(next_id);
-        next_id += 1;
-
-        let player1 = Player::new(p1_id, player1_name, starting_life);
-        let player2 = Player::new(p2_id, player2_name, starting_life);
-
-        let players = vec![player1, player2];
-
-        let player_zones = vec![(p1_id, PlayerZones::new(p1_id)), (p2_id, PlayerZones::new(p2_id))];
+        let mut players = Vec::with_capacity(player_names.len());
+        let mut player_zones = Vec::with_capacity(player_names.len());
+        for name in player_names {
+            let player_id = PlayerId::new(next_id);
+            next_id += 1;
+            players.push(Player::new(player_id, name, starting_life));
+            player_zones.push((player_id, PlayerZones::new(player_id)));
+        }
+        let first_player = players.first().map(|p| p.id).unwrap_or(PlayerId::new(0));
 
-        // Use a unified PlayerId for shared zones (battlefield, stack)
+        // Use a unified PlayerId for the shared battlefield zone
         // These don't belong to a specific player, but we need an ID for the zone
         let shared_id = PlayerId::new(next_id);
         next_id += 1;
 
-        GameState {
+        let mut game = GameState {
             cards: EntityStore::new(),
             players,
             player_zones,
             battlefield: CardZone::new(Zone::Battlefield, shared_id),
-            stack: CardZone::new(Zone::Stack, shared_id),
-            turn: TurnStructure::new_with_idx(p1_id, 0), // Player 1 starts at index 0
+            stack: Stack::new(),
+            pending_triggers: Vec::new(),
+            turn: TurnStructure::new_with_idx(first_player, 0), // Player 1 starts at index 0
             combat: CombatState::new(),
+            replacement_effects: Vec::new(),
+            replacement_choices: ReplacementChoices::default(),
+            replacements_applying: Vec::new(),
+            state_hashes_by_zobrist: RefCell::default(),
             rng: RefCell::new(ChaCha12Rng::seed_from_u64(0)), // Default seed, will be reseeded by game initialization
             next_entity_id: next_id,
             undo_log: UndoLog::new(),
             logger: GameLogger::new(),
-        }
+        };
+        game.reset_zobrist_hash();
+        game
     }
 
     /// Set the RNG seed for deterministic gameplay
@@ -106,8 +143,12 @@ impl GameState {
     ///
     /// This is a convenience method to avoid borrow checker issues when
     /// accessing both the RNG and player zones.
+    ///
+    /// Logs the library's previous order and the RNG's position, so undo
+    /// restores both.
     pub fn shuffle_library(&mut self, player_id: PlayerId) {
         use rand::seq::SliceRandom;
+        let prev_rng_word_pos = self.rng.get_mut().get_word_pos();
         // First, get a mutable reference to the library cards
         if let Some(zones) = self
             .player_zones
@@ -115,7 +156,13 @@ impl GameState {
             .find(|(id, _)| *id == player_id)
             .map(|(_, z)| z)
         {
-            zones.library.cards.shuffle(&mut *self.rng.borrow_mut());
+            let previous = zones.library.cards.clone();
+            zones.library.cards.shuffle(self.rng.get_mut());
+            self.undo_log.log(crate::undo::GameAction::ShuffleLibrary {
+                player_id,
+                previous,
+                prev_rng_word_pos,
+            });
         }
     }
 
@@ -190,6 +237,23 @@ impl GameState {
         self.players.iter().position(|p| p.id == id)
     }
 
+    /// The player a spell's target ID names, if it names one
+    ///
+    /// Players and cards share one ID space, so a spell dividing damage among
+    /// creatures and players records its player targets under their own IDs.
+    pub fn target_player(&self, target_id: CardId) -> Option<PlayerId> {
+        self.players
+            .iter()
+            .map(|p| p.id)
+            .find(|id| id.as_u32() == target_id.as_u32())
+    }
+
+    /// What a spell's target ID refers to: a player (see `target_player`) or a permanent
+    pub fn target_ref(&self, target_id: CardId) -> TargetRef {
+        self.target_player(target_id)
+            .map_or(TargetRef::Permanent(target_id), TargetRef::Player)
+    }
+
     /// Get the next player in turn order (for 2+ players)
     pub fn get_next_player_idx(&self, current_idx: usize) -> usize {
         (current_idx + 1) % self.players.len()
@@ -214,29 +278,69 @@ impl GameState {
     }
 
     /// Move a card from one zone to another
-    pub fn move_card(&mut self, card_id: CardId, from: Zone, to: Zone, owner: PlayerId) -> Result<()> {
-        // Remove from source zone
-        let removed = match from {
-            Zone::Battlefield => self.battlefield.remove(card_id),
-            Zone::Stack => self.stack.remove(card_id),
-            _ => {
-                if let Some(zones) = self.get_player_zones_mut(owner) {
-                    if let Some(zone) = zones.get_zone_mut(from) {
-                        zone.remove(card_id)
-                    } else {
-                        false
-                    }
-                } else {
-                    false
+    ///
+    /// Replacement effects may change where the card goes ("exile it instead"),
+    /// have it enter the battlefield tapped or replace the move entirely. Returns
+    /// the zone the card ended up in (`from` if it didn't move).
+    pub fn move_card(&mut self, card_id: CardId, from: Zone, to: Zone, owner: PlayerId) -> Result<Zone> {
+        let outcome = self.replace_move(card_id, from, to)?;
+        let Some(to) = outcome.destination else {
+            return Ok(from);
+        };
+        self.move_card_without_replacement(card_id, from, to, owner)?;
+
+        // A permanent that leaves the battlefield is no longer attached to anything (MTG Rules 400.7)
+        if from == Zone::Battlefield && to != Zone::Battlefield {
+            self.detach(card_id)?;
+        }
+        if to == Zone::Battlefield && from != Zone::Battlefield {
+            self.enter_with_loyalty(card_id)?;
+        }
+
+        if outcome.enter_tapped {
+            if let Ok(card) = self.cards.get_mut(card_id) {
+                if !card.tapped {
+                    card.tap();
+                    self.undo_log
+                        .log(crate::undo::GameAction::TapCard { card_id, tapped: true });
                 }
             }
-        };
+        }
+        Ok(to)
+    }
 
-        if !removed {
+    /// Move a card between zones exactly as asked, without applying replacement effects
+    ///
+    /// Used to rewind moves.
+    pub fn move_card_without_replacement(
+        &mut self,
+        card_id: CardId,
+        from: Zone,
+        to: Zone,
+        owner: PlayerId,
+    ) -> Result<()> {
+        // A permanent that leaves the battlefield becomes a new object without damage
+        if from == Zone::Battlefield && to != Zone::Battlefield && self.battlefield.contains(card_id) {
+            self.clear_damage(card_id)?;
+        }
+
+        // Clear a spell's casting choices as it leaves the stack, so that rewinding
+        // past this move also restores them
+        if from == Zone::Stack
+            && self
+                .stack
+                .spell_choices(card_id)
+                .is_some_and(|choices| *choices != crate::game::SpellChoices::default())
+        {
+            self.set_spell_choices(card_id, crate::game::SpellChoices::default())?;
+        }
+
+        // Remove from source zone
+        let Some(from_index) = self.take_from_zone(card_id, from, owner) else {
             return Err(crate::MtgError::InvalidAction(format!(
                 "Card {card_id} not found in source zone"
             )));
-        }
+        };
 
         // Add to destination zone
         match to {
@@ -257,15 +361,77 @@ impl GameState {
             from_zone: from,
             to_zone: to,
             owner,
+            from_index,
         });
 
+        self.update_continuous_effects_after_move(card_id, from, to);
+
         Ok(())
     }
 
+    /// Remove a card from a zone, returning the position it had there
+    pub(crate) fn take_from_zone(&mut self, card_id: CardId, zone: Zone, owner: PlayerId) -> Option<usize> {
+        match zone {
+            Zone::Battlefield => {
+                let index = self.battlefield.position(card_id)?;
+                self.battlefield.cards.remove(index);
+                Some(index)
+            }
+            Zone::Stack => {
+                let index = self.stack.spell_position(card_id)?;
+                self.stack.remove(card_id);
+                Some(index)
+            }
+            _ => {
+                let zone = self.get_player_zones_mut(owner)?.get_zone_mut(zone)?;
+                let index = zone.position(card_id)?;
+                zone.cards.remove(index);
+                Some(index)
+            }
+        }
+    }
+
+    /// Put a card back at the position it had in a zone (rewinding `take_from_zone`)
+    fn insert_into_zone(&mut self, card_id: CardId, zone: Zone, owner: PlayerId, index: usize) {
+        match zone {
+            Zone::Battlefield => self.battlefield.insert(index, card_id),
+            Zone::Stack => self.stack.insert_at(
+                index,
+                crate::game::StackObject::Spell {
+                    card_id,
+                    choices: crate::game::SpellChoices::default(),
+                },
+            ),
+            _ => {
+                if let Some(zone) = self.get_player_zones_mut(owner).and_then(|z| z.get_zone_mut(zone)) {
+                    zone.insert(index, card_id);
+                }
+            }
+        }
+    }
+
+    /// Recompute continuous effects after a card entered or left the battlefield
+    ///
+    /// A permanent that leaves stops being affected by them (MTG Rules 400.7).
+    fn update_continuous_effects_after_move(&mut self, card_id: CardId, from: Zone, to: Zone) {
+        if from == Zone::Battlefield && to != Zone::Battlefield {
+            if let Ok(card) = self.cards.get_mut(card_id) {
+                card.clear_derived();
+            }
+        }
+        if from == Zone::Battlefield || to == Zone::Battlefield {
+            self.apply_continuous_effects();
+        }
+    }
+
     /// Print state hash to normal log output if debug mode is enabled
     ///
     /// This is called before logging game actions to help debug divergence.
     /// Prints format: [STATE:a3f7b2c1] message
+    ///
+    /// Debug mode also cross-checks the incremental Zobrist hash, warning on
+    /// stderr when it has drifted from the hash of the state, or when it is the
+    /// same for two states with different state hashes.
     #[inline]
     pub fn debug_log_state_hash(&self, message: &str) {
         if self.logger.debug_state_hash_enabled() {
@@ -275,14 +441,68 @@ impl GameState {
             // This makes state hashes part of the deterministic game output
             self.logger
                 .normal(&format!("[STATE:{}] {}", format_hash(hash), message));
+            match self.check_zobrist_hash() {
+                Ok(zobrist) => {
+                    let first = *self.state_hashes_by_zobrist.borrow_mut().entry(zobrist).or_insert(hash);
+                    if first != hash {
+                        eprintln!(
+                            "WARNING: [STATE:{}] {}: Zobrist hash {zobrist:016x} was also the hash of state {}",
+                            format_hash(hash),
+                            message,
+                            format_hash(first)
+                        );
+                    }
+                }
+                Err(e) => eprintln!("WARNING: [STATE:{}] {}: {}", format_hash(hash), message, e),
+            }
         }
     }
 
+    /// Zobrist hash of the game state, maintained incrementally by the undo log
+    ///
+    /// Cheap enough to call at every node of a search. Equal states have equal
+    /// hashes as long as every change to the hashed features was logged; see
+    /// [`compute_zobrist_hash`](crate::game::compute_zobrist_hash).
+    pub fn zobrist_hash(&self) -> u64 {
+        self.undo_log.zobrist_hash()
+    }
+
+    /// Recompute the Zobrist hash from scratch
+    ///
+    /// Needed after setting up a game by changing the state directly, such as
+    /// putting decks into libraries, rather than through logged actions.
+    pub fn reset_zobrist_hash(&mut self) {
+        let hash = crate::game::compute_zobrist_hash(self);
+        self.undo_log.set_zobrist_hash(hash);
+    }
+
+    /// Check the incremental Zobrist hash against one computed from scratch
+    ///
+    /// Returns the hash, or an error if some change to the state wasn't logged.
+    pub fn check_zobrist_hash(&self) -> Result<u64> {
+        let expected = crate::game::compute_zobrist_hash(self);
+        let actual = self.zobrist_hash();
+        if actual != expected {
+            return Err(crate::MtgError::InvalidAction(format!(
+                "Zobrist hash {actual:016x} doesn't match the state's hash {expected:016x}"
+            )));
+        }
+        Ok(actual)
+    }
+
     /// Draw a card for a player
+    ///
+    /// Returns the card drawn; `None` if the library was empty or a replacement
+    /// effect replaced the draw.
     pub fn draw_card(&mut self, player_id: PlayerId) -> Result<Option<CardId>> {
+        if !self.replace_draw(player_id)? {
+            return Ok(None);
+        }
+
         if let Some(zones) = self.get_player_zones_mut(player_id) {
             if let Some(card_id) = zones.library.draw_top() {
                 zones.hand.add(card_id);
+                let from_index = zones.library.len();
 
                 // Log the card movement for undo
                 self.undo_log.log(crate::undo::GameAction::MoveCard {
@@ -290,11 +510,21 @@ impl GameState {
                     from_zone: crate::zones::Zone::Library,
                     to_zone: crate::zones::Zone::Hand,
                     owner: player_id,
+                    from_index,
                 });
 
                 return Ok(Some(card_id));
             }
         }
+
+        // MTG Rules 704.5b: the player loses the next time state-based actions are checked
+        if let Ok(player) = self.get_player_mut(player_id) {
+            if !player.drew_from_empty_library {
+                player.drew_from_empty_library = true;
+                self.undo_log
+                    .log(crate::undo::GameAction::DrawFromEmptyLibrary { player_id });
+            }
+        }
         Ok(None)
     }
 
@@ -345,7 +575,7 @@ impl GameState {
         };
 
         // Remove from stack
-        self.stack.remove(spell_id);
+        let from_index = self.take_from_zone(spell_id, Zone::Stack, owner_id).unwrap_or_default();
 
         // Move to owner's graveyard
         if let Some(zones) = self.get_player_zones_mut(owner_id) {
@@ -358,6 +588,7 @@ impl GameState {
             from_zone: crate::zones::Zone::Stack,
             to_zone: crate::zones::Zone::Graveyard,
             owner: owner_id,
+            from_index,
         });
 
         Ok(())
@@ -380,6 +611,29 @@ impl GameState {
         Ok(())
     }
 
+    /// Empty a player's mana pool and log for undo
+    pub fn empty_mana_pool(&mut self, player_id: PlayerId) -> Result<()> {
+        let pool = &mut self.get_player_mut(player_id)?.mana_pool;
+        let prev = *pool;
+        if prev == crate::core::ManaPool::new() {
+            return Ok(());
+        }
+        pool.clear();
+
+        self.undo_log.log(crate::undo::GameAction::EmptyManaPool {
+            player_id,
+            prev_white: prev.white,
+            prev_blue: prev.blue,
+            prev_black: prev.black,
+            prev_red: prev.red,
+            prev_green: prev.green,
+            prev_colorless: prev.colorless,
+            prev_snow: prev.snow,
+        });
+
+        Ok(())
+    }
+
     /// Add counters to a card and log for undo
     pub fn add_counters(&mut self, card_id: CardId, counter_type: crate::core::CounterType, amount: u8) -> Result<()> {
         if let Ok(card) = self.cards.get_mut(card_id) {
@@ -421,14 +675,99 @@ impl GameState {
         }
     }
 
+    /// Create a token on the battlefield under `controller`'s control and log for undo
+    ///
+    /// MTG Rules 111.2: the player who creates a token is its owner.
+    pub fn create_token(&mut self, token: &crate::core::TokenDefinition, controller: PlayerId) -> Result<CardId> {
+        let card_id = self.next_card_id();
+        let card = token.instantiate(card_id, controller);
+        self.cards.insert(card_id, card);
+        self.battlefield.add(card_id);
+
+        self.undo_log.log(crate::undo::GameAction::CreateToken { card_id });
+        self.record_entered_battlefield(card_id)?;
+
+        Ok(card_id)
+    }
+
+    /// Reverse `create_token`: remove the token and release its ID
+    pub(crate) fn uncreate_token(&mut self, card_id: CardId) {
+        self.battlefield.remove(card_id);
+        self.cards.remove(card_id);
+        if self.next_entity_id == card_id.as_u32() + 1 {
+            self.next_entity_id -= 1;
+        }
+    }
+
+    /// Mark damage on a permanent and log for undo
+    ///
+    /// Damage accumulates until it is removed in the cleanup step. Lethal damage is
+    /// handled by state-based actions, not here.
+    pub fn mark_damage(&mut self, card_id: CardId, amount: i32, from_deathtouch: bool) -> Result<()> {
+        if amount <= 0 {
+            return Ok(());
+        }
+
+        let card = self.cards.get_mut(card_id)?;
+        let prev_deathtouch = card.damaged_by_deathtouch;
+        card.damage_marked += amount;
+        card.damaged_by_deathtouch |= from_deathtouch;
+
+        self.undo_log.log(crate::undo::GameAction::MarkDamage {
+            card_id,
+            amount,
+            deathtouch: from_deathtouch,
+            prev_deathtouch,
+        });
+
+        Ok(())
+    }
+
+    /// Remove all damage from a permanent and log for undo
+    pub fn clear_damage(&mut self, card_id: CardId) -> Result<()> {
+        let card = self.cards.get_mut(card_id)?;
+        if card.damage_marked == 0 && !card.damaged_by_deathtouch {
+            return Ok(());
+        }
+
+        let prev_damage = card.damage_marked;
+        let prev_deathtouch = card.damaged_by_deathtouch;
+        card.damage_marked = 0;
+        card.damaged_by_deathtouch = false;
+
+        self.undo_log.log(crate::undo::GameAction::ClearDamage {
+            card_id,
+            prev_damage,
+            prev_deathtouch,
+        });
+
+        Ok(())
+    }
+
+    /// Remove damage from all permanents (MTG Rules 514.2, cleanup step)
+    pub fn clear_all_damage(&mut self) -> Result<()> {
+        let permanents: Vec<CardId> = self.battlefield.cards.to_vec();
+        for card_id in permanents {
+            self.clear_damage(card_id)?;
+        }
+        Ok(())
+    }
+
     /// Clear temporary effects at end of turn (Cleanup step)
     /// This resets power/toughness bonuses from pump spells
     pub fn cleanup_temporary_effects(&mut self) {
         for card_id in self.battlefield.cards.iter() {
             if let Ok(card) = self.cards.get_mut(*card_id) {
                 // Reset temporary bonuses (pump effects last until end of turn)
-                card.power_bonus = 0;
-                card.toughness_bonus = 0;
+                if card.power_bonus != 0 || card.toughness_bonus != 0 {
+                    self.undo_log.log(crate::undo::GameAction::PumpCreature {
+                        card_id: *card_id,
+                        power_delta: -card.power_bonus,
+                        toughness_delta: -card.toughness_bonus,
+                    });
+                    card.power_bonus = 0;
+                    card.toughness_bonus = 0;
+                }
             }
         }
     }
@@ -445,7 +784,6 @@ impl GameState {
         if !self.turn.advance_step() {
             // End of turn, move to next player
             let from_player = self.turn.active_player;
-            let next_player = self.get_next_player(self.turn.active_player)?;
             let old_turn_number = self.turn.turn_number;
 
             // Serialize RNG state BEFORE changing turns
@@ -456,7 +794,11 @@ impl GameState {
                 serde_json::to_vec(&*rng).ok()
             };
 
-            self.turn.next_turn(next_player);
+            let next_player = self.turn.rotate(&self.players);
+
+            // Reset per-turn state, logged before the turn change so that a rewind
+            // to the start of the turn keeps it
+            self.set_lands_played(next_player, 0)?;
 
             // Log the turn change with RNG state from before the turn change
             self.undo_log.log(crate::undo::GameAction::ChangeTurn {
@@ -465,11 +807,6 @@ impl GameState {
                 turn_number: old_turn_number + 1,
                 rng_state,
             });
-
-            // Reset per-turn state
-            if let Ok(player) = self.get_player_mut(next_player) {
-                player.reset_lands_played();
-            }
         } else {
             // Log the step advance
             self.undo_log.log(crate::undo::GameAction::AdvanceStep {
@@ -480,13 +817,32 @@ impl GameState {
         Ok(())
     }
 
-    /// Get the next player in turn order
-    fn get_next_player(&self, current_player: PlayerId) -> Result<PlayerId> {
-        let current_idx = self
-            .get_player_idx(current_player)
-            .ok_or(crate::MtgError::EntityNotFound(current_player.as_u32()))?;
-        let next_idx = self.get_next_player_idx(current_idx);
-        Ok(self.players[next_idx].id)
+    /// Set how many lands a player has played this turn, logging the change
+    pub(crate) fn set_lands_played(&mut self, player_id: PlayerId, count: u8) -> Result<()> {
+        let player = self.get_player_mut(player_id)?;
+        let previous = player.lands_played_this_turn;
+        if previous != count {
+            player.lands_played_this_turn = count;
+            self.undo_log.log(crate::undo::GameAction::SetLandsPlayed {
+                player_id,
+                count,
+                previous,
+            });
+        }
+        Ok(())
+    }
+
+    /// Record that a permanent entered the battlefield this turn (for summoning sickness)
+    pub(crate) fn record_entered_battlefield(&mut self, card_id: CardId) -> Result<()> {
+        let turn = self.turn.turn_number;
+        let card = self.cards.get_mut(card_id)?;
+        let previous = card.turn_entered_battlefield.replace(turn);
+        self.undo_log.log(crate::undo::GameAction::SetEnteredBattlefield {
+            card_id,
+            turn,
+            previous,
+        });
+        Ok(())
     }
 
     /// Check if the game is over
@@ -508,197 +864,334 @@ impl GameState {
     /// Returns Ok(true) if an action was undone, Ok(false) if log is empty.
     pub fn undo(&mut self) -> Result<bool> {
         if let Some(action) = self.undo_log.pop() {
-            match action {
-                crate::undo::GameAction::MoveCard {
-                    card_id,
-                    from_zone,
-                    to_zone,
-                    owner,
-                } => {
-                    // Move card back from to_zone to from_zone
-                    // Don't log this action since it's a revert
-                    let removed = match to_zone {
-                        Zone::Battlefield => self.battlefield.remove(card_id),
-                        Zone::Stack => self.stack.remove(card_id),
-                        _ => {
-                            if let Some(zones) = self.get_player_zones_mut(owner) {
-                                if let Some(zone) = zones.get_zone_mut(to_zone) {
-                                    zone.remove(card_id)
-                                } else {
-                                    eprintln!("UNDO BUG: Failed to get zone {to_zone:?} for undo");
-                                    false
-                                }
-                            } else {
-                                eprintln!("UNDO BUG: Failed to get player zones for {owner:?}");
-                                false
-                            }
-                        }
-                    };
-
-                    if !removed {
-                        // Find where the card actually is
-                        let mut actual_zone = None;
-                        if self.battlefield.contains(card_id) {
-                            actual_zone = Some("Battlefield");
-                        } else if self.stack.contains(card_id) {
-                            actual_zone = Some("Stack");
-                        } else if let Some(zones) = self.get_player_zones(owner) {
-                            if zones.hand.contains(card_id) {
-                                actual_zone = Some("Hand");
-                            } else if zones.library.contains(card_id) {
-                                actual_zone = Some("Library");
-                            } else if zones.graveyard.contains(card_id) {
-                                actual_zone = Some("Graveyard");
-                            } else if zones.exile.contains(card_id) {
-                                actual_zone = Some("Exile");
-                            }
-                        }
-                        eprintln!("UNDO BUG: Card {} not found in to_zone {:?}, cannot undo move from {:?} → {:?}. Card is actually in: {:?}",
-                                  card_id.as_u32(), to_zone, from_zone, to_zone, actual_zone);
+            self.revert_action(action)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Take a checkpoint that the game can later be rewound to
+    ///
+    /// Checkpoints nest: a search can take one before each move it tries, and
+    /// rewinding to an outer one also discards the inner ones.
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.undo_log.checkpoint()
+    }
+
+    /// Rewind the game to a checkpoint, undoing every action logged since
+    ///
+    /// Fails if the checkpoint is stale: the game was already rewound past it,
+    /// or a later checkpoint was committed.
+    pub fn rewind_to(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        let len = self.undo_log.checkpoint_len(checkpoint)?;
+        while self.undo_log.len() > len {
+            self.undo()?;
+        }
+        Ok(())
+    }
+
+    /// Commit to the moves made before a checkpoint, discarding the undo history
+    /// before it
+    ///
+    /// Once a move is committed (the opponent made it, say) a search never needs to
+    /// look behind it, so the log only has to hold the moves it's exploring.
+    /// Earlier checkpoints go stale.
+    pub fn commit(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        self.undo_log.commit(checkpoint)
+    }
+
+    /// Revert an action that was popped from the undo log, without logging
+    ///
+    /// Every undo goes through here, so reverting the actions logged since some
+    /// point restores the state exactly as it was at that point.
+    pub(crate) fn revert_action(&mut self, action: crate::undo::GameAction) -> Result<()> {
+        match action {
+            crate::undo::GameAction::MoveCard {
+                card_id,
+                from_zone,
+                to_zone,
+                owner,
+                from_index,
+            } => {
+                // Move card back from to_zone to from_zone, where it was
+                if self.take_from_zone(card_id, to_zone, owner).is_none() {
+                    return Err(crate::MtgError::InvalidAction(format!(
+                        "Can't undo move of card {card_id} from {from_zone:?}: it isn't in {to_zone:?}"
+                    )));
+                }
+                self.insert_into_zone(card_id, from_zone, owner, from_index);
+                self.update_continuous_effects_after_move(card_id, to_zone, from_zone);
+            }
+            crate::undo::GameAction::TapCard { card_id, tapped } => {
+                // Reverse the tap state
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    if tapped {
+                        card.untap();
                     } else {
-                        match from_zone {
-                            Zone::Battlefield => self.battlefield.add(card_id),
-                            Zone::Stack => self.stack.add(card_id),
-                            _ => {
-                                if let Some(zones) = self.get_player_zones_mut(owner) {
-                                    if let Some(zone) = zones.get_zone_mut(from_zone) {
-                                        zone.add(card_id);
-                                    }
-                                }
-                            }
-                        }
+                        card.tap();
                     }
                 }
-                crate::undo::GameAction::TapCard { card_id, tapped } => {
-                    // Reverse the tap state
-                    if let Ok(card) = self.cards.get_mut(card_id) {
-                        if tapped {
-                            card.untap();
-                        } else {
-                            card.tap();
-                        }
-                    }
+            }
+            crate::undo::GameAction::ModifyLife { player_id, delta } => {
+                // Apply the negative of the delta
+                // A loss it caused was logged separately, as PlayerLoses
+                if let Ok(player) = self.get_player_mut(player_id) {
+                    player.life -= delta;
                 }
-                crate::undo::GameAction::ModifyLife { player_id, delta } => {
-                    // Apply the negative of the delta
-                    if let Ok(player) = self.get_player_mut(player_id) {
-                        if delta > 0 {
-                            player.lose_life(delta);
-                        } else {
-                            player.gain_life(-delta);
-                        }
-                        // Recheck has_lost status
-                        if player.life > 0 {
-                            player.has_lost = false;
-                        }
-                    }
+            }
+            crate::undo::GameAction::AddMana { player_id, mana } => {
+                // Remove the mana that was added
+                if let Ok(player) = self.get_player_mut(player_id) {
+                    player.mana_pool.remove_mana(&mana);
                 }
-                crate::undo::GameAction::AddMana { player_id, mana } => {
-                    // Remove the mana that was added
-                    if let Ok(player) = self.get_player_mut(player_id) {
-                        // Subtract each color that was added
-                        if player.mana_pool.white >= mana.white {
-                            player.mana_pool.white -= mana.white;
-                        }
-                        if player.mana_pool.blue >= mana.blue {
-                            player.mana_pool.blue -= mana.blue;
-                        }
-                        if player.mana_pool.black >= mana.black {
-                            player.mana_pool.black -= mana.black;
-                        }
-                        if player.mana_pool.red >= mana.red {
-                            player.mana_pool.red -= mana.red;
-                        }
-                        if player.mana_pool.green >= mana.green {
-                            player.mana_pool.green -= mana.green;
-                        }
-                        if player.mana_pool.colorless >= mana.colorless {
-                            player.mana_pool.colorless -= mana.colorless;
-                        }
-                    }
+            }
+            crate::undo::GameAction::EmptyManaPool {
+                player_id,
+                prev_white,
+                prev_blue,
+                prev_black,
+                prev_red,
+                prev_green,
+                prev_colorless,
+                prev_snow,
+            } => {
+                // Restore previous mana pool state
+                if let Ok(player) = self.get_player_mut(player_id) {
+                    player.mana_pool.white = prev_white;
+                    player.mana_pool.blue = prev_blue;
+                    player.mana_pool.black = prev_black;
+                    player.mana_pool.red = prev_red;
+                    player.mana_pool.green = prev_green;
+                    player.mana_pool.colorless = prev_colorless;
+                    player.mana_pool.snow = prev_snow;
                 }
-                crate::undo::GameAction::EmptyManaPool {
-                    player_id,
-                    prev_white,
-                    prev_blue,
-                    prev_black,
-                    prev_red,
-                    prev_green,
-                    prev_colorless,
-                } => {
-                    // Restore previous mana pool state
-                    if let Ok(player) = self.get_player_mut(player_id) {
-                        player.mana_pool.white = prev_white;
-                        player.mana_pool.blue = prev_blue;
-                        player.mana_pool.black = prev_black;
-                        player.mana_pool.red = prev_red;
-                        player.mana_pool.green = prev_green;
-                        player.mana_pool.colorless = prev_colorless;
-                    }
+            }
+            crate::undo::GameAction::SpendMana {
+                player_id, prev_pool, ..
+            } => {
+                if let Ok(player) = self.get_player_mut(player_id) {
+                    player.mana_pool = prev_pool;
                 }
-                crate::undo::GameAction::AddCounter {
-                    card_id,
-                    counter_type,
-                    amount,
-                } => {
-                    // Remove the counters that were added
-                    if let Ok(card) = self.cards.get_mut(card_id) {
-                        card.remove_counter(counter_type, amount);
-                    }
+            }
+            crate::undo::GameAction::AddCounter {
+                card_id,
+                counter_type,
+                amount,
+            } => {
+                // Remove the counters that were added
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    card.remove_counter(counter_type, amount);
                 }
-                crate::undo::GameAction::RemoveCounter {
-                    card_id,
-                    counter_type,
-                    amount,
-                } => {
-                    // Add back the counters that were removed
-                    if let Ok(card) = self.cards.get_mut(card_id) {
-                        card.add_counter(counter_type, amount);
-                    }
+            }
+            crate::undo::GameAction::RemoveCounter {
+                card_id,
+                counter_type,
+                amount,
+            } => {
+                // Add back the counters that were removed
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    card.add_counter(counter_type, amount);
                 }
-                crate::undo::GameAction::AdvanceStep { from_step, to_step: _ } => {
-                    // Revert to previous step
-                    self.turn.current_step = from_step;
+            }
+            crate::undo::GameAction::AdvanceStep { from_step, to_step: _ } => {
+                // Revert to previous step
+                self.turn.current_step = from_step;
+            }
+            crate::undo::GameAction::ChangeTurn {
+                from_player,
+                to_player: _,
+                turn_number,
+                rng_state,
+            } => {
+                // Revert to the cleanup step of the previous turn
+                self.turn.active_player = from_player;
+                if let Some(idx) = self.players.iter().position(|p| p.id == from_player) {
+                    self.turn.active_player_idx = idx;
                 }
-                crate::undo::GameAction::ChangeTurn {
-                    from_player,
-                    to_player: _,
-                    turn_number,
-                    rng_state,
-                } => {
-                    // Revert to previous turn
-                    self.turn.active_player = from_player;
-                    self.turn.turn_number = turn_number - 1;
-
-                    // Restore RNG state if available
-                    if let Some(rng_bytes) = rng_state {
-                        if let Ok(rng) = serde_json::from_slice::<ChaCha12Rng>(&rng_bytes) {
-                            *self.rng.borrow_mut() = rng;
-                        }
-                    }
+                self.turn.turn_number = turn_number.saturating_sub(1);
+                self.turn.current_step = crate::game::Step::Cleanup;
 
-                    // Note: We don't reset lands_played here as that state
-                    // should be managed by separate actions if needed
-                }
-                crate::undo::GameAction::PumpCreature {
-                    card_id,
-                    power_delta,
-                    toughness_delta,
-                } => {
-                    // Reverse the pump effect
-                    if let Ok(card) = self.cards.get_mut(card_id) {
-                        card.power_bonus -= power_delta;
-                        card.toughness_bonus -= toughness_delta;
+                // Restore RNG state if available
+                if let Some(rng_bytes) = rng_state {
+                    if let Ok(rng) = serde_json::from_slice::<ChaCha12Rng>(&rng_bytes) {
+                        *self.rng.borrow_mut() = rng;
                     }
                 }
-                crate::undo::GameAction::ChoicePoint { .. } => {
-                    // Choice points don't need to be undone
+            }
+            crate::undo::GameAction::SetLandsPlayed {
+                player_id, previous, ..
+            } => {
+                if let Ok(player) = self.get_player_mut(player_id) {
+                    player.lands_played_this_turn = previous;
                 }
             }
-            Ok(true)
-        } else {
-            Ok(false)
+            crate::undo::GameAction::SetEnteredBattlefield { card_id, previous, .. } => {
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    card.turn_entered_battlefield = previous;
+                }
+            }
+            crate::undo::GameAction::DeclareAttacker {
+                card_id,
+                prev_combat_active,
+                ..
+            } => {
+                self.combat.undeclare_attacker(card_id, prev_combat_active);
+            }
+            crate::undo::GameAction::DeclareBlocker { card_id, .. } => {
+                self.combat.undeclare_blocker(card_id);
+            }
+            crate::undo::GameAction::SetCombat { previous, .. } => {
+                self.combat = *previous;
+            }
+            crate::undo::GameAction::ShuffleLibrary {
+                player_id,
+                previous,
+                prev_rng_word_pos,
+            } => {
+                if let Some(zones) = self.get_player_zones_mut(player_id) {
+                    zones.library.cards = previous;
+                }
+                self.rng.get_mut().set_word_pos(prev_rng_word_pos);
+            }
+            crate::undo::GameAction::UseRng { prev_word_pos } => {
+                self.rng.get_mut().set_word_pos(prev_word_pos);
+            }
+            crate::undo::GameAction::PumpCreature {
+                card_id,
+                power_delta,
+                toughness_delta,
+            } => {
+                // Reverse the pump effect
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    card.power_bonus -= power_delta;
+                    card.toughness_bonus -= toughness_delta;
+                }
+            }
+            crate::undo::GameAction::AddPendingTrigger { .. } => {
+                self.pending_triggers.pop();
+            }
+            crate::undo::GameAction::PutTriggerOnStack { pending_index, .. } => {
+                if let Some(crate::game::StackObject::TriggeredAbility(ability)) = self.stack.pop() {
+                    self.pending_triggers.insert(pending_index, ability);
+                }
+            }
+            crate::undo::GameAction::SetSpellChoices { card_id, previous, .. } => {
+                self.restore_spell_choices(card_id, *previous);
+            }
+            crate::undo::GameAction::PutAbilityOnStack { .. } => {
+                self.stack.pop();
+            }
+            crate::undo::GameAction::ResolveAbility { object } => {
+                self.stack.push(*object);
+            }
+            crate::undo::GameAction::CreateToken { card_id } => {
+                self.uncreate_token(card_id);
+            }
+            crate::undo::GameAction::AddReplacementEffect { .. } => {
+                self.replacement_effects.pop();
+            }
+            crate::undo::GameAction::RemoveReplacementEffect { index, effect } => {
+                self.replacement_effects.insert(index, *effect);
+            }
+            crate::undo::GameAction::UseDamageShield { index, previous, .. } => {
+                if let Some(registered) = self.replacement_effects.get_mut(index) {
+                    *registered = *previous;
+                }
+            }
+            crate::undo::GameAction::SetAttachedTo { card_id, previous, .. } => {
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    card.attached_to = previous;
+                }
+                self.apply_continuous_effects();
+            }
+            crate::undo::GameAction::ActivateLoyaltyAbility {
+                card_id, previous_turn, ..
+            } => {
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    card.loyalty_activated_turn = previous_turn;
+                }
+            }
+            crate::undo::GameAction::TokenCeasesToExist {
+                card_id,
+                zone,
+                owner,
+                index,
+            }
+            | crate::undo::GameAction::CardLeavesGame {
+                card_id,
+                zone,
+                owner,
+                index,
+            } => {
+                // Put the card back where it was when it ceased to exist or left
+                self.insert_into_zone(card_id, zone, owner, index);
+            }
+            crate::undo::GameAction::RemoveFromStack { index, object } => {
+                self.stack.insert_at(index, *object);
+            }
+            crate::undo::GameAction::TargetPendingTrigger { index, previous, .. } => {
+                if let Some(ability) = self.pending_triggers.get_mut(index) {
+                    *ability = *previous;
+                }
+            }
+            crate::undo::GameAction::RemovePendingTrigger { index, ability } => {
+                let index = index.min(self.pending_triggers.len());
+                self.pending_triggers.insert(index, *ability);
+            }
+            crate::undo::GameAction::MarkDamage {
+                card_id,
+                amount,
+                prev_deathtouch,
+                ..
+            } => {
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    card.damage_marked -= amount;
+                    card.damaged_by_deathtouch = prev_deathtouch;
+                }
+            }
+            crate::undo::GameAction::ClearDamage {
+                card_id,
+                prev_damage,
+                prev_deathtouch,
+            } => {
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    card.damage_marked = prev_damage;
+                    card.damaged_by_deathtouch = prev_deathtouch;
+                }
+            }
+            crate::undo::GameAction::PlayerLoses { player_id } => {
+                if let Ok(player) = self.get_player_mut(player_id) {
+                    player.has_lost = false;
+                }
+            }
+            crate::undo::GameAction::CastFromCommandZone { card_id } => {
+                if let Ok(card) = self.cards.get_mut(card_id) {
+                    card.commander_casts = card.commander_casts.saturating_sub(1);
+                }
+            }
+            crate::undo::GameAction::CommanderDamage {
+                player_id,
+                commander,
+                amount,
+            } => {
+                self.unrecord_commander_damage(player_id, commander, amount);
+            }
+            crate::undo::GameAction::PlayerLeavesGame { player_id } => {
+                if let Ok(player) = self.get_player_mut(player_id) {
+                    player.left_game = false;
+                }
+            }
+            crate::undo::GameAction::DrawFromEmptyLibrary { player_id } => {
+                if let Ok(player) = self.get_player_mut(player_id) {
+                    player.drew_from_empty_library = false;
+                }
+            }
+            crate::undo::GameAction::ChoicePoint { .. } => {
+                // Choice points don't need to be undone
+            }
         }
+        Ok(())
     }
 }
 
@@ -717,6 +1210,19 @@ mod tests {
         assert_eq!(game.turn.current_step, Step::Untap);
     }
 
+    #[test]
+    fn test_multiplayer_game_creation() {
+        let names = ["Alice", "Bob", "Carol", "Dave"].map(String::from).to_vec();
+        let game = GameState::new_multiplayer(names, 40);
+
+        assert_eq!(game.players.len(), 4);
+        assert_eq!(game.player_zones.len(), 4);
+        assert!(game.players.iter().all(|p| p.life == 40));
+        assert_eq!(game.turn.active_player, game.players[0].id);
+        assert_eq!(game.players[3].name.as_str(), "Dave");
+        assert!(game.get_player_zones(game.players[3].id).is_some());
+    }
+
     #[test]
     fn test_draw_card() {
         let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
@@ -774,35 +1280,43 @@ mod tests {
         let card_id = game.next_card_id();
         let mut card = Card::new(card_id, "Mountain", p1_id);
         card.types.push(CardType::Land);
+        card.subtypes.push("Mountain".into());
         game.cards.insert(card_id, card);
 
         if let Some(zones) = game.get_player_zones_mut(p1_id) {
             zones.hand.add(card_id);
         }
 
-        // Play the land - should log MoveCard
+        // Play the land - should log MoveCard, when it entered, and the land drop
         game.play_land(p1_id, card_id).unwrap();
-        assert_eq!(game.undo_log.len(), 1);
-        matches!(game.undo_log.peek().unwrap(), crate::undo::GameAction::MoveCard { .. });
+        assert_eq!(game.undo_log.len(), 3);
 
         // Tap for mana - should log TapCard and AddMana
         game.tap_for_mana(p1_id, card_id).unwrap();
-        assert_eq!(game.undo_log.len(), 3); // MoveCard, TapCard, AddMana
+        assert_eq!(game.undo_log.len(), 5);
 
         // Untap all - should log TapCard for untap
         game.untap_all(p1_id).unwrap();
-        assert_eq!(game.undo_log.len(), 4); // + TapCard (untapped)
+        assert_eq!(game.undo_log.len(), 6); // + TapCard (untapped)
 
         // Verify all actions are logged
         let actions = game.undo_log.actions();
         assert!(matches!(actions[0], crate::undo::GameAction::MoveCard { .. }));
         assert!(matches!(
             actions[1],
-            crate::undo::GameAction::TapCard { tapped: true, .. }
+            crate::undo::GameAction::SetEnteredBattlefield { previous: None, .. }
+        ));
+        assert!(matches!(
+            actions[2],
+            crate::undo::GameAction::SetLandsPlayed { previous: 0, .. }
         ));
-        assert!(matches!(actions[2], crate::undo::GameAction::AddMana { .. }));
         assert!(matches!(
             actions[3],
+            crate::undo::GameAction::TapCard { tapped: true, .. }
+        ));
+        assert!(matches!(actions[4], crate::undo::GameAction::AddMana { .. }));
+        assert!(matches!(
+            actions[5],
             crate::undo::GameAction::TapCard { tapped: false, .. }
         ));
     }
@@ -0,0 +1,504 @@
+//! State-based actions (MTG Rules 704)
+//!
+//! State-based actions are checked whenever a player would receive priority.
+//! All applicable actions are performed simultaneously, then the check repeats
+//! until none apply (MTG Rules 704.3).
+
+use crate::core::{CardId, CounterType, PlayerId};
+use crate::game::GameState;
+use crate::zones::Zone;
+use crate::Result;
+use smallvec::{smallvec, SmallVec};
+
+/// Poison counters needed to lose the game (MTG Rules 704.5c)
+const POISON_LOSS_THRESHOLD: u8 = 10;
+
+/// Why a player lost to a state-based action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LossReason {
+    ZeroLife,
+    EmptyLibraryDraw,
+    Poison,
+    CommanderDamage,
+}
+
+/// Why a permanent was put into its owner's graveyard by a state-based action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GraveyardReason {
+    ZeroToughness,
+    LethalDamage,
+    Deathtouch,
+    LegendRule,
+    UnattachedAura,
+    ZeroLoyalty,
+}
+
+/// One round of simultaneous state-based actions
+#[derive(Debug, Default)]
+struct PendingActions {
+    losses: Vec<(PlayerId, LossReason)>,
+    to_graveyard: Vec<(CardId, GraveyardReason)>,
+    /// Creatures with both +1/+1 and -1/-1 counters, and how many of each to remove
+    annihilate: Vec<(CardId, u8)>,
+    /// Tokens in a zone other than the battlefield, with that zone and the token's owner
+    tokens_ceasing: Vec<(CardId, Zone, PlayerId)>,
+    /// Equipment attached to something it can't equip
+    unattach: Vec<CardId>,
+}
+
+impl PendingActions {
+    fn is_empty(&self) -> bool {
+        self.losses.is_empty()
+            && self.to_graveyard.is_empty()
+            && self.annihilate.is_empty()
+            && self.tokens_ceasing.is_empty()
+            && self.unattach.is_empty()
+    }
+
+    fn send_to_graveyard(&mut self, card_id: CardId, reason: GraveyardReason) {
+        if !self.to_graveyard.iter().any(|(id, _)| *id == card_id) {
+            self.to_graveyard.push((card_id, reason));
+        }
+    }
+}
+
+impl GameState {
+    /// Check and perform state-based actions until none apply
+    ///
+    /// Implements MTG Rules 704.5a-c and 704.6c (player losses), 704.5d (tokens outside the
+    /// battlefield cease to exist), 704.5f (zero toughness), 704.5g-h (lethal and
+    /// deathtouch damage), 704.5i (zero loyalty), 704.5m (illegally attached Auras),
+    /// 704.5n (illegally attached Equipment) and 704.5q (+1/+1 and -1/-1 counter
+    /// annihilation). Every change goes through the undo log.
+    ///
+    /// The legend rule (704.5j) needs the controller's choice, so the game loop
+    /// applies it with `legend_rule_groups` and `apply_legend_rule`.
+    ///
+    /// Returns true if any state-based action was performed.
+    pub fn check_state_based_actions(&mut self) -> Result<bool> {
+        let mut performed_any = false;
+
+        loop {
+            // Toughness and keywords must reflect the current static abilities
+            self.apply_continuous_effects();
+            let pending = self.collect_state_based_actions();
+            if pending.is_empty() {
+                return Ok(performed_any);
+            }
+            performed_any = true;
+            self.perform_state_based_actions(pending)?;
+        }
+    }
+
+    /// Find every state-based action that currently applies (MTG Rules 704.3)
+    fn collect_state_based_actions(&self) -> PendingActions {
+        let mut pending = PendingActions::default();
+
+        // MTG Rules 704.5a-c, 704.6c: players with 0 or less life, who drew from an
+        // empty library, who have ten or more poison counters, or who were dealt 21
+        // combat damage by the same commander lose the game
+        for player in &self.players {
+            if player.has_lost {
+                continue;
+            }
+            let reason = if player.life <= 0 {
+                Some(LossReason::ZeroLife)
+            } else if player.drew_from_empty_library {
+                Some(LossReason::EmptyLibraryDraw)
+            } else if player.poison_counters >= POISON_LOSS_THRESHOLD {
+                Some(LossReason::Poison)
+            } else if self.has_lethal_commander_damage(player.id) {
+                Some(LossReason::CommanderDamage)
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                pending.losses.push((player.id, reason));
+            }
+        }
+
+        // MTG Rules 704.5d: a token in a zone other than the battlefield ceases to exist
+        for (owner, zones) in &self.player_zones {
+            for zone in [Zone::Graveyard, Zone::Hand, Zone::Exile, Zone::Library] {
+                let Some(cards) = zones.get_zone(zone) else {
+                    continue;
+                };
+                for &card_id in &cards.cards {
+                    if self.cards.get(card_id).is_ok_and(|card| card.is_token) {
+                        pending.tokens_ceasing.push((card_id, zone, *owner));
+                    }
+                }
+            }
+        }
+
+        for &card_id in &self.battlefield.cards {
+            let Ok(card) = self.cards.get(card_id) else {
+                continue;
+            };
+
+            // MTG Rules 704.5f: creature with toughness 0 or less (indestructible doesn't help)
+            if card.is_creature() && card.current_toughness() <= 0 {
+                pending.send_to_graveyard(card_id, GraveyardReason::ZeroToughness);
+            } else if card.is_creature() && !card.has_indestructible() {
+                // MTG Rules 704.5g/704.5h: lethal damage, or any damage from a deathtouch source
+                if card.damage_marked >= card.current_toughness() as i32 {
+                    pending.send_to_graveyard(card_id, GraveyardReason::LethalDamage);
+                } else if card.damaged_by_deathtouch {
+                    pending.send_to_graveyard(card_id, GraveyardReason::Deathtouch);
+                }
+            }
+
+            // MTG Rules 704.5i: planeswalker with 0 loyalty
+            if card.is_planeswalker() && card.current_loyalty() == 0 {
+                pending.send_to_graveyard(card_id, GraveyardReason::ZeroLoyalty);
+            }
+
+            // MTG Rules 704.5m: Aura not attached to a permanent it could enchant
+            if card.is_aura() {
+                let attached = card.attached_to.is_some_and(|target| self.can_attach(card_id, target));
+                if !attached {
+                    pending.send_to_graveyard(card_id, GraveyardReason::UnattachedAura);
+                }
+            }
+
+            // MTG Rules 704.5n: Equipment attached to something it can't equip
+            // becomes unattached but stays on the battlefield
+            if card.is_equipment() && card.attached_to.is_some_and(|target| !self.can_attach(card_id, target)) {
+                pending.unattach.push(card_id);
+            }
+
+            // MTG Rules 704.5q: remove N of each where N is the smaller count
+            let plus = card.get_counter(CounterType::P1P1);
+            let minus = card.get_counter(CounterType::M1M1);
+            if plus > 0 && minus > 0 {
+                pending.annihilate.push((card_id, plus.min(minus)));
+            }
+        }
+
+        pending
+    }
+
+    /// Find the legendary permanents the legend rule applies to (MTG Rules 704.5j)
+    ///
+    /// Returns each player controlling two or more legendary permanents with
+    /// the same name, together with those permanents in battlefield order.
+    pub fn legend_rule_groups(&self) -> Vec<(PlayerId, SmallVec<[CardId; 4]>)> {
+        let mut groups: Vec<(PlayerId, &str, SmallVec<[CardId; 4]>)> = Vec::new();
+        for card in self.battlefield.cards.iter().filter_map(|&id| self.cards.get(id).ok()) {
+            if !card.is_legendary() {
+                continue;
+            }
+            let name = card.name.as_str();
+            match groups
+                .iter_mut()
+                .find(|(controller, other_name, _)| *controller == card.controller && *other_name == name)
+            {
+                Some((_, _, legends)) => legends.push(card.id),
+                None => groups.push((card.controller, name, smallvec![card.id])),
+            }
+        }
+        groups
+            .into_iter()
+            .filter(|(_, _, legends)| legends.len() > 1)
+            .map(|(controller, _, legends)| (controller, legends))
+            .collect()
+    }
+
+    /// Apply the legend rule to one group from `legend_rule_groups` (MTG Rules 704.5j)
+    ///
+    /// The controller keeps `keep` and puts the rest of `legends` into their
+    /// owners' graveyards.
+    pub fn apply_legend_rule(&mut self, keep: CardId, legends: &[CardId]) -> Result<()> {
+        if !legends.contains(&keep) {
+            return Err(crate::MtgError::InvalidAction(format!(
+                "Legend rule: {keep} is not one of the legendary permanents"
+            )));
+        }
+        let mut pending = PendingActions::default();
+        for &card_id in legends.iter().filter(|&&card_id| card_id != keep) {
+            pending.send_to_graveyard(card_id, GraveyardReason::LegendRule);
+        }
+        self.perform_state_based_actions(pending)
+    }
+
+    /// Perform one round of state-based actions simultaneously
+    fn perform_state_based_actions(&mut self, pending: PendingActions) -> Result<()> {
+        let losers: Vec<PlayerId> = pending.losses.iter().map(|&(player_id, _)| player_id).collect();
+        for (player_id, reason) in pending.losses {
+            let player = self.get_player_mut(player_id)?;
+            player.has_lost = true;
+            let name = player.name.to_string();
+            self.undo_log.log(crate::undo::GameAction::PlayerLoses { player_id });
+
+            let cause = match reason {
+                LossReason::ZeroLife => "has 0 or less life",
+                LossReason::EmptyLibraryDraw => "drew from an empty library",
+                LossReason::Poison => "has ten or more poison counters",
+                LossReason::CommanderDamage => "was dealt 21 combat damage by a commander",
+            };
+            self.logger.normal(&format!("{name} loses the game ({cause})"));
+        }
+
+        for (card_id, zone, owner) in pending.tokens_ceasing {
+            let Some(index) = self.take_from_zone(card_id, zone, owner) else {
+                continue;
+            };
+            self.undo_log.log(crate::undo::GameAction::TokenCeasesToExist {
+                card_id,
+                zone,
+                owner,
+                index,
+            });
+        }
+
+        for card_id in pending.unattach {
+            self.detach(card_id)?;
+            let name = self.cards.get(card_id)?.name.to_string();
+            self.logger.normal(&format!("{name} ({card_id}) becomes unattached"));
+        }
+
+        for (card_id, count) in pending.annihilate {
+            self.remove_counters(card_id, CounterType::P1P1, count)?;
+            self.remove_counters(card_id, CounterType::M1M1, count)?;
+        }
+
+        for (card_id, reason) in pending.to_graveyard {
+            let (owner, name) = {
+                let card = self.cards.get(card_id)?;
+                (card.owner, card.name.to_string())
+            };
+            self.move_card(card_id, Zone::Battlefield, Zone::Graveyard, owner)?;
+
+            let cause = match reason {
+                GraveyardReason::ZeroToughness => "has 0 or less toughness",
+                GraveyardReason::LethalDamage => "lethal damage",
+                GraveyardReason::Deathtouch => "deathtouch",
+                GraveyardReason::LegendRule => "legend rule",
+                GraveyardReason::UnattachedAura => "is not attached to a legal permanent",
+                GraveyardReason::ZeroLoyalty => "has 0 loyalty",
+            };
+            self.logger
+                .normal(&format!("{name} ({card_id}) is put into the graveyard ({cause})"));
+        }
+
+        // MTG Rules 800.4a: in a game that goes on without them, the losers leave it
+        if !self.is_game_over() {
+            for player_id in losers {
+                self.leave_game(player_id)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Card, CardType, Subtype};
+
+    fn setup() -> (GameState, PlayerId, PlayerId) {
+        let game = GameState::new_two_player("P1".to_string(), "P2".to_string(), 20);
+        let p1 = game.players[0].id;
+        let p2 = game.players[1].id;
+        (game, p1, p2)
+    }
+
+    fn add_permanent(game: &mut GameState, name: &str, owner: PlayerId, setup: impl FnOnce(&mut Card)) -> CardId {
+        let card_id = game.next_card_id();
+        let mut card = Card::new(card_id, name, owner);
+        setup(&mut card);
+        game.cards.insert(card_id, card);
+        game.battlefield.add(card_id);
+        card_id
+    }
+
+    fn creature(power: i8, toughness: i8) -> impl FnOnce(&mut Card) {
+        move |card: &mut Card| {
+            card.types.push(CardType::Creature);
+            card.power = Some(power);
+            card.toughness = Some(toughness);
+        }
+    }
+
+    fn in_graveyard(game: &GameState, owner: PlayerId, card_id: CardId) -> bool {
+        game.get_player_zones(owner).unwrap().graveyard.contains(card_id)
+    }
+
+    #[test]
+    fn test_no_actions_on_stable_board() {
+        let (mut game, p1, _) = setup();
+        add_permanent(&mut game, "Grizzly Bears", p1, creature(2, 2));
+
+        assert!(!game.check_state_based_actions().unwrap());
+        assert!(game.undo_log.is_empty());
+    }
+
+    #[test]
+    fn test_zero_toughness_creature_dies() {
+        let (mut game, p1, _) = setup();
+        let bears = add_permanent(&mut game, "Grizzly Bears", p1, creature(2, 2));
+        game.cards.get_mut(bears).unwrap().toughness_bonus = -2;
+
+        assert!(game.check_state_based_actions().unwrap());
+        assert!(!game.battlefield.contains(bears));
+        assert!(in_graveyard(&game, p1, bears));
+    }
+
+    #[test]
+    fn test_zero_toughness_ignores_indestructible() {
+        let (mut game, p1, _) = setup();
+        let god = add_permanent(&mut game, "Indestructible God", p1, |card| {
+            creature(5, 0)(card);
+            card.keywords.push(crate::core::Keyword::Indestructible);
+        });
+
+        game.check_state_based_actions().unwrap();
+        assert!(in_graveyard(&game, p1, god));
+    }
+
+    #[test]
+    fn test_player_losses() {
+        let (mut game, p1, p2) = setup();
+        game.get_player_mut(p1).unwrap().poison_counters = 10;
+        game.get_player_mut(p2).unwrap().poison_counters = 9;
+
+        game.check_state_based_actions().unwrap();
+        assert!(game.get_player(p1).unwrap().has_lost);
+        assert!(!game.get_player(p2).unwrap().has_lost);
+
+        // Drawing from an empty library is remembered until the next check
+        assert_eq!(game.draw_card(p2).unwrap(), None);
+        assert!(!game.get_player(p2).unwrap().has_lost);
+        game.check_state_based_actions().unwrap();
+        assert!(game.get_player(p2).unwrap().has_lost);
+    }
+
+    #[test]
+    fn test_zero_life_loss_set_directly() {
+        let (mut game, p1, _) = setup();
+        game.get_player_mut(p1).unwrap().life = 0;
+
+        assert!(game.check_state_based_actions().unwrap());
+        assert!(game.get_player(p1).unwrap().has_lost);
+    }
+
+    #[test]
+    fn test_legend_rule_keeps_the_chosen_legend_per_controller() {
+        let (mut game, p1, p2) = setup();
+        let legendary = |card: &mut Card| {
+            creature(3, 3)(card);
+            card.subtypes.push(Subtype::new("Legendary"));
+        };
+        let first = add_permanent(&mut game, "Isamaru, Hound of Konda", p1, legendary);
+        let theirs = add_permanent(&mut game, "Isamaru, Hound of Konda", p2, legendary);
+        let second = add_permanent(&mut game, "Isamaru, Hound of Konda", p1, legendary);
+
+        assert!(
+            !game.check_state_based_actions().unwrap(),
+            "The legend rule waits for the controller's choice"
+        );
+        let groups = game.legend_rule_groups();
+        assert_eq!(groups.len(), 1, "Each player's legends are independent");
+        let (controller, legends) = &groups[0];
+        assert_eq!(*controller, p1);
+        assert_eq!(legends.as_slice(), &[first, second]);
+
+        assert!(game.apply_legend_rule(theirs, legends).is_err());
+        game.apply_legend_rule(first, legends).unwrap();
+        assert!(game.battlefield.contains(first));
+        assert!(in_graveyard(&game, p1, second));
+        assert!(game.battlefield.contains(theirs));
+        assert!(game.legend_rule_groups().is_empty());
+    }
+
+    #[test]
+    fn test_unattached_aura_goes_to_graveyard() {
+        let (mut game, p1, _) = setup();
+        let aura = |card: &mut Card| {
+            card.types.push(CardType::Enchantment);
+            card.subtypes.push(Subtype::new("Aura"));
+        };
+        let bears = add_permanent(&mut game, "Grizzly Bears", p1, creature(2, 2));
+        let attached = add_permanent(&mut game, "Holy Strength", p1, aura);
+        game.cards.get_mut(attached).unwrap().attached_to = Some(bears);
+        let loose = add_permanent(&mut game, "Rancor", p1, aura);
+
+        game.check_state_based_actions().unwrap();
+        assert!(game.battlefield.contains(attached));
+        assert!(in_graveyard(&game, p1, loose));
+
+        // Once the enchanted creature leaves, the Aura follows on the next check
+        game.move_card(bears, Zone::Battlefield, Zone::Graveyard, p1).unwrap();
+        game.check_state_based_actions().unwrap();
+        assert!(in_graveyard(&game, p1, attached));
+    }
+
+    #[test]
+    fn test_equipment_on_noncreature_becomes_unattached() {
+        let (mut game, p1, _) = setup();
+        let bears = add_permanent(&mut game, "Grizzly Bears", p1, creature(2, 2));
+        let sword = add_permanent(&mut game, "Short Sword", p1, |card| {
+            card.types.push(CardType::Artifact);
+            card.subtypes.push(Subtype::new("Equipment"));
+        });
+        assert!(game.attach(sword, bears).unwrap());
+
+        // The creature stops being a creature, so the Equipment falls off
+        game.cards.get_mut(bears).unwrap().types = vec![CardType::Artifact].into();
+        game.check_state_based_actions().unwrap();
+        assert!(game.battlefield.contains(sword));
+        assert_eq!(game.cards.get(sword).unwrap().attached_to, None);
+    }
+
+    #[test]
+    fn test_counter_annihilation() {
+        let (mut game, p1, _) = setup();
+        let bears = add_permanent(&mut game, "Grizzly Bears", p1, |card| {
+            creature(2, 2)(card);
+            card.counters.push((CounterType::P1P1, 3));
+            card.counters.push((CounterType::M1M1, 1));
+        });
+
+        game.check_state_based_actions().unwrap();
+        let card = game.cards.get(bears).unwrap();
+        assert_eq!(card.get_counter(CounterType::P1P1), 2);
+        assert_eq!(card.get_counter(CounterType::M1M1), 0);
+    }
+
+    #[test]
+    fn test_state_based_actions_are_undoable() {
+        let (mut game, p1, p2) = setup();
+        let bears = add_permanent(&mut game, "Grizzly Bears", p1, creature(2, 2));
+        game.cards.get_mut(bears).unwrap().toughness_bonus = -2;
+        game.get_player_mut(p2).unwrap().poison_counters = 10;
+
+        game.check_state_based_actions().unwrap();
+        assert!(game.get_player(p2).unwrap().has_lost);
+        assert!(in_graveyard(&game, p1, bears));
+
+        while game.undo().unwrap() {}
+        assert!(!game.get_player(p2).unwrap().has_lost);
+        assert!(game.battlefield.contains(bears));
+        assert!(!in_graveyard(&game, p1, bears));
+    }
+
+    #[test]
+    fn test_token_ceases_to_exist_off_battlefield() {
+        let (mut game, p1, _) = setup();
+        let soldier = crate::core::TokenDefinition::from_script_name("w_1_1_soldier");
+        let token = game.create_token(&soldier, p1).unwrap();
+        game.mark_damage(token, 1, false).unwrap();
+
+        // Dies to lethal damage, then ceases to exist in the graveyard
+        assert!(game.check_state_based_actions().unwrap());
+        assert!(!game.battlefield.contains(token));
+        assert!(!in_graveyard(&game, p1, token));
+
+        // Undo brings it back through the graveyard to the battlefield
+        game.undo().unwrap();
+        assert!(in_graveyard(&game, p1, token));
+        while game.undo().unwrap() {}
+        assert!(!game.cards.contains(token));
+    }
+}
@@ -1,10 +1,32 @@
-//! Deterministic state hashing for debugging snapshot/resume
+//! Deterministic state hashing for debugging snapshot/resume and for search
 //!
 //! This module provides functionality to compute a deterministic hash of game state,
 //! excluding metadata and ephemeral fields. Useful for tracking exactly when game
 //! states diverge during stop-go replay.
+//!
+//! [`compute_state_hash`] serializes the whole state, which is too slow to call at
+//! every node of a search. The Zobrist hash covers everything that decides what
+//! can happen next: the zone of each card; tapped permanents, counters, marked
+//! damage, pump bonuses, attachments, summoning sickness, loyalty activations and
+//! commander tax; attackers and blockers; spells on the stack with their modes,
+//! X, targets and division; abilities on the stack or waiting to go there;
+//! replacement effects; life totals, mana pools, lands played, poison counters,
+//! commander damage and players who drew from an empty library, lost or left the
+//! game; the step and the active player. It is the sum of one random key per
+//! feature value, so each logged [`GameAction`] changes it by a delta that depends
+//! on the action alone. `UndoLog` adds that delta when the action is logged and
+//! subtracts it when the action is popped, so rewinds restore the hash for free.
+//! The order of cards within a zone (or of objects on the stack) isn't hashed,
+//! nor is the RNG.
+//!
+//! In debug mode, [`GameState::debug_log_state_hash`] checks the Zobrist hash
+//! against [`compute_state_hash`], reporting states the Zobrist hash can't tell
+//! apart.
 
-use crate::game::GameState;
+use crate::core::{CardId, CounterType, ManaCost, ManaPool, PlayerId};
+use crate::game::{CombatState, GameState, RegisteredReplacement, SpellChoices, StackAbility, StackObject, Step};
+use crate::undo::GameAction;
+use crate::zones::Zone;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
@@ -15,7 +37,6 @@ use std::hash::{Hash, Hasher};
 /// - undo_log: Not gameplay state
 /// - logger: Presentation layer
 /// - show_choice_menu, output_mode, etc: Display settings
-/// - lands_played_this_turn: Turn-scoped counter (resets on rewind)
 const EXCLUDED_FIELDS: &[&str] = &[
     "choice_id",
     "undo_log",
@@ -69,9 +90,6 @@ fn strip_metadata(value: serde_json::Value) -> serde_json::Value {
                 map.remove(*field);
             }
 
-            // Also remove lands_played_this_turn which can differ after rewind
-            map.remove("lands_played_this_turn");
-
             // Recursively clean nested objects
             for (_, v) in map.iter_mut() {
                 *v = strip_metadata(v.clone());
@@ -89,6 +107,509 @@ pub fn format_hash(hash: u64) -> String {
     format!("{:08x}", (hash >> 32) as u32)
 }
 
+// Features hashed by the Zobrist hash
+const ZONE: u64 = 1;
+const TAPPED: u64 = 2;
+const COUNTER: u64 = 3;
+const LIFE: u64 = 4;
+const MANA: u64 = 5;
+const SNOW_MANA: u64 = 6;
+const STEP: u64 = 7;
+const ACTIVE_PLAYER: u64 = 8;
+const TURN: u64 = 9;
+const DAMAGE: u64 = 10;
+const POWER_BONUS: u64 = 11;
+const TOUGHNESS_BONUS: u64 = 12;
+const ATTACHED: u64 = 13;
+const ATTACKER: u64 = 14;
+const ATTACKED_PLANESWALKER: u64 = 15;
+const BLOCKER: u64 = 16;
+const COMBAT_ACTIVE: u64 = 17;
+const TRIGGERED_ABILITY: u64 = 18;
+const ACTIVATED_ABILITY: u64 = 19;
+const LOST: u64 = 20;
+const LEFT_GAME: u64 = 21;
+const DEATHTOUCH: u64 = 22;
+const ENTERED_BATTLEFIELD: u64 = 23;
+const LOYALTY_ACTIVATED: u64 = 24;
+const COMMANDER_CASTS: u64 = 25;
+const LANDS_PLAYED: u64 = 26;
+const POISON: u64 = 27;
+const DREW_FROM_EMPTY_LIBRARY: u64 = 28;
+const COMMANDER_DAMAGE: u64 = 29;
+const SPELL_CHOICES: u64 = 30;
+const PENDING_TRIGGER: u64 = 31;
+const REPLACEMENT_EFFECT: u64 = 32;
+
+/// The SplitMix64 finalizer, a cheap bijective mix of 64 bits
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// The random key of a feature value (derived, so it's the same in every process)
+fn key(feature: u64, a: u64, b: u64) -> u64 {
+    splitmix64(splitmix64(splitmix64(feature) ^ a) ^ b)
+}
+
+/// `count` copies of a key
+fn times(key: u64, count: i64) -> u64 {
+    key.wrapping_mul(count as u64)
+}
+
+/// The key if a flag is set
+fn flag(key: u64, set: bool) -> u64 {
+    if set {
+        key
+    } else {
+        0
+    }
+}
+
+/// A 64-bit digest of a value that has too many possible values for one key each
+fn digest(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn zone_key(card_id: CardId, zone: Zone) -> u64 {
+    key(ZONE, card_id.as_u32() as u64, zone as u64)
+}
+
+fn tapped_key(card_id: CardId) -> u64 {
+    key(TAPPED, card_id.as_u32() as u64, 0)
+}
+
+/// +1/+1 and -1/-1 counters annihilate (MTG Rules 704.5q), so -1/-1 counters
+/// take away +1/+1 counters' key: the hash depends only on the difference,
+/// which is all that's left after annihilating
+fn counter_key(card_id: CardId, counter_type: CounterType) -> u64 {
+    match counter_type {
+        CounterType::M1M1 => counter_key(card_id, CounterType::P1P1).wrapping_neg(),
+        _ => key(COUNTER, card_id.as_u32() as u64, counter_type as u64),
+    }
+}
+
+fn damage_key(card_id: CardId) -> u64 {
+    key(DAMAGE, card_id.as_u32() as u64, 0)
+}
+
+fn deathtouch_key(card_id: CardId) -> u64 {
+    key(DEATHTOUCH, card_id.as_u32() as u64, 0)
+}
+
+/// Key of the turn a permanent last entered the battlefield (for summoning sickness)
+fn entered_key(card_id: CardId, turn: Option<u32>) -> u64 {
+    turn.map_or(0, |turn| key(ENTERED_BATTLEFIELD, card_id.as_u32() as u64, turn.into()))
+}
+
+/// Key of the turn a planeswalker last activated a loyalty ability
+fn loyalty_activated_key(card_id: CardId, turn: Option<u32>) -> u64 {
+    turn.map_or(0, |turn| key(LOYALTY_ACTIVATED, card_id.as_u32() as u64, turn.into()))
+}
+
+fn commander_casts_key(card_id: CardId) -> u64 {
+    key(COMMANDER_CASTS, card_id.as_u32() as u64, 0)
+}
+
+/// Hash of "until end of turn" power and toughness bonuses
+fn pump_hash(card_id: CardId, power: i32, toughness: i32) -> u64 {
+    times(key(POWER_BONUS, card_id.as_u32() as u64, 0), power.into()).wrapping_add(times(
+        key(TOUGHNESS_BONUS, card_id.as_u32() as u64, 0),
+        toughness.into(),
+    ))
+}
+
+fn attached_key(card_id: CardId, attached_to: Option<CardId>) -> u64 {
+    match attached_to {
+        Some(target) => key(ATTACHED, card_id.as_u32() as u64, target.as_u32() as u64),
+        None => 0,
+    }
+}
+
+fn attacker_hash(card_id: CardId, defending_player: PlayerId, planeswalker: Option<CardId>) -> u64 {
+    let attacker = key(ATTACKER, card_id.as_u32() as u64, defending_player.as_u32() as u64);
+    match planeswalker {
+        Some(planeswalker) => attacker.wrapping_add(key(
+            ATTACKED_PLANESWALKER,
+            card_id.as_u32() as u64,
+            planeswalker.as_u32() as u64,
+        )),
+        None => attacker,
+    }
+}
+
+fn blocker_hash(card_id: CardId, attackers: &[CardId]) -> u64 {
+    attackers.iter().fold(0u64, |hash, attacker| {
+        hash.wrapping_add(key(BLOCKER, card_id.as_u32() as u64, attacker.as_u32() as u64))
+    })
+}
+
+/// Hash of who attacks and blocks whom
+fn combat_hash(combat: &CombatState) -> u64 {
+    let mut hash = if combat.combat_active {
+        key(COMBAT_ACTIVE, 0, 0)
+    } else {
+        0
+    };
+    for (&attacker, &defending_player) in &combat.attackers {
+        let planeswalker = combat.attacked_planeswalkers.get(&attacker).copied();
+        hash = hash.wrapping_add(attacker_hash(attacker, defending_player, planeswalker));
+    }
+    for (&blocker, attackers) in &combat.blockers {
+        hash = hash.wrapping_add(blocker_hash(blocker, attackers));
+    }
+    hash
+}
+
+/// The key of an ability on the stack or waiting to go there: its source,
+/// controller, targets and text (its effects come from the same text)
+fn ability_key(feature: u64, ability: &StackAbility, ability_index: usize) -> u64 {
+    let digest = digest(&(
+        ability.controller,
+        &ability.targets,
+        &ability.description,
+        ability_index,
+    ));
+    key(feature, ability.source.as_u32() as u64, digest)
+}
+
+fn pending_trigger_key(ability: &StackAbility) -> u64 {
+    ability_key(PENDING_TRIGGER, ability, 0)
+}
+
+/// The key of a spell's modes, X, targets and division (none for a spell
+/// without choices, so that moving a card to the stack is keyed by its zone alone)
+fn spell_choices_key(card_id: CardId, choices: &SpellChoices) -> u64 {
+    if *choices == SpellChoices::default() {
+        0
+    } else {
+        key(SPELL_CHOICES, card_id.as_u32() as u64, digest(choices))
+    }
+}
+
+/// The key of an object on the stack; a spell is keyed by its card being in
+/// the stack zone and by the choices it was cast with
+fn stack_object_key(object: &StackObject) -> u64 {
+    match object {
+        StackObject::Spell { card_id, choices } => {
+            zone_key(*card_id, Zone::Stack).wrapping_add(spell_choices_key(*card_id, choices))
+        }
+        StackObject::TriggeredAbility(ability) => ability_key(TRIGGERED_ABILITY, ability, 0),
+        StackObject::ActivatedAbility { ability, ability_index } => {
+            ability_key(ACTIVATED_ABILITY, ability, *ability_index)
+        }
+    }
+}
+
+/// The key of a registered replacement effect: its ID, source, controller and text
+fn replacement_key(registered: &RegisteredReplacement) -> u64 {
+    let digest = digest(&(
+        registered.source,
+        registered.controller,
+        &registered.effect.description,
+        registered.effect.single_use,
+        registered.effect.shield_remaining(),
+    ));
+    key(REPLACEMENT_EFFECT, registered.id.as_u32() as u64, digest)
+}
+
+fn lands_played_key(player_id: PlayerId) -> u64 {
+    key(LANDS_PLAYED, player_id.as_u32() as u64, 0)
+}
+
+fn poison_key(player_id: PlayerId) -> u64 {
+    key(POISON, player_id.as_u32() as u64, 0)
+}
+
+fn drew_from_empty_library_key(player_id: PlayerId) -> u64 {
+    key(DREW_FROM_EMPTY_LIBRARY, player_id.as_u32() as u64, 0)
+}
+
+fn commander_damage_key(player_id: PlayerId, commander: CardId) -> u64 {
+    key(COMMANDER_DAMAGE, player_id.as_u32() as u64, commander.as_u32() as u64)
+}
+
+fn lost_key(player_id: PlayerId) -> u64 {
+    key(LOST, player_id.as_u32() as u64, 0)
+}
+
+fn left_game_key(player_id: PlayerId) -> u64 {
+    key(LEFT_GAME, player_id.as_u32() as u64, 0)
+}
+
+fn life_key(player_id: PlayerId) -> u64 {
+    key(LIFE, player_id.as_u32() as u64, 0)
+}
+
+fn mana_key(player_id: PlayerId, index: usize) -> u64 {
+    key(MANA, player_id.as_u32() as u64, index as u64)
+}
+
+fn snow_mana_key(player_id: PlayerId, index: usize) -> u64 {
+    key(SNOW_MANA, player_id.as_u32() as u64, index as u64)
+}
+
+fn step_key(step: Step) -> u64 {
+    key(STEP, step as u64, 0)
+}
+
+fn active_player_key(player_id: PlayerId) -> u64 {
+    key(ACTIVE_PLAYER, player_id.as_u32() as u64, 0)
+}
+
+/// Hash of a mana pool's contents
+fn pool_hash(player_id: PlayerId, pool: &ManaPool) -> u64 {
+    let amounts = [pool.white, pool.blue, pool.black, pool.red, pool.green, pool.colorless];
+    let mut hash = 0u64;
+    for (index, (&amount, &snow)) in amounts.iter().zip(pool.snow.iter()).enumerate() {
+        hash = hash
+            .wrapping_add(times(mana_key(player_id, index), amount.into()))
+            .wrapping_add(times(snow_mana_key(player_id, index), snow.into()));
+    }
+    hash
+}
+
+/// Hash of mana added to a pool (all of it is snow mana if `mana.snow` is set)
+fn added_mana_hash(player_id: PlayerId, mana: &ManaCost) -> u64 {
+    let amounts = [mana.white, mana.blue, mana.black, mana.red, mana.green, mana.colorless];
+    let mut pool = ManaPool::new();
+    pool.white = mana.white;
+    pool.blue = mana.blue;
+    pool.black = mana.black;
+    pool.red = mana.red;
+    pool.green = mana.green;
+    pool.colorless = mana.colorless;
+    if mana.snow > 0 {
+        pool.snow = amounts;
+    }
+    pool_hash(player_id, &pool)
+}
+
+/// Compute the Zobrist hash of a game state from scratch
+///
+/// `GameState::zobrist_hash` returns the same value, maintained incrementally.
+pub fn compute_zobrist_hash(game: &GameState) -> u64 {
+    let mut hash = 0u64;
+
+    for &card_id in &game.battlefield.cards {
+        hash = hash.wrapping_add(zone_key(card_id, Zone::Battlefield));
+    }
+    for object in game.stack.objects() {
+        hash = hash.wrapping_add(stack_object_key(object));
+    }
+    for (_, zones) in &game.player_zones {
+        for zone in [Zone::Library, Zone::Hand, Zone::Graveyard, Zone::Exile, Zone::Command] {
+            if let Some(cards) = zones.get_zone(zone) {
+                for &card_id in &cards.cards {
+                    hash = hash.wrapping_add(zone_key(card_id, zone));
+                }
+            }
+        }
+    }
+    for ability in &game.pending_triggers {
+        hash = hash.wrapping_add(pending_trigger_key(ability));
+    }
+    for registered in &game.replacement_effects {
+        hash = hash.wrapping_add(replacement_key(registered));
+    }
+
+    for (&card_id, card) in game.cards.iter() {
+        if card.tapped {
+            hash = hash.wrapping_add(tapped_key(card_id));
+        }
+        for &(counter_type, amount) in &card.counters {
+            hash = hash.wrapping_add(times(counter_key(card_id, counter_type), amount.into()));
+        }
+        hash = hash
+            .wrapping_add(times(damage_key(card_id), card.damage_marked.into()))
+            .wrapping_add(flag(deathtouch_key(card_id), card.damaged_by_deathtouch))
+            .wrapping_add(pump_hash(card_id, card.power_bonus, card.toughness_bonus))
+            .wrapping_add(attached_key(card_id, card.attached_to))
+            .wrapping_add(entered_key(card_id, card.turn_entered_battlefield))
+            .wrapping_add(loyalty_activated_key(card_id, card.loyalty_activated_turn))
+            .wrapping_add(times(commander_casts_key(card_id), card.commander_casts.into()));
+    }
+
+    for player in &game.players {
+        hash = hash
+            .wrapping_add(times(life_key(player.id), player.life.into()))
+            .wrapping_add(pool_hash(player.id, &player.mana_pool))
+            .wrapping_add(times(lands_played_key(player.id), player.lands_played_this_turn.into()))
+            .wrapping_add(times(poison_key(player.id), player.poison_counters.into()))
+            .wrapping_add(flag(
+                drew_from_empty_library_key(player.id),
+                player.drew_from_empty_library,
+            ))
+            .wrapping_add(flag(lost_key(player.id), player.has_lost))
+            .wrapping_add(flag(left_game_key(player.id), player.left_game));
+        for &(commander, damage) in &player.commander_damage {
+            hash = hash.wrapping_add(times(commander_damage_key(player.id, commander), damage.into()));
+        }
+    }
+
+    hash.wrapping_add(combat_hash(&game.combat))
+        .wrapping_add(step_key(game.turn.current_step))
+        .wrapping_add(active_player_key(game.turn.active_player))
+        .wrapping_add(times(key(TURN, 0, 0), game.turn.turn_number.into()))
+}
+
+/// How an action changes the Zobrist hash of the state it's applied to
+///
+/// Every action is listed, so that a new one has to say how it changes the hash.
+pub fn zobrist_delta(action: &GameAction) -> u64 {
+    match action {
+        GameAction::MoveCard {
+            card_id,
+            from_zone,
+            to_zone,
+            ..
+        } => zone_key(*card_id, *to_zone).wrapping_sub(zone_key(*card_id, *from_zone)),
+        GameAction::TapCard { card_id, tapped } => {
+            let key = tapped_key(*card_id);
+            if *tapped {
+                key
+            } else {
+                key.wrapping_neg()
+            }
+        }
+        GameAction::ModifyLife { player_id, delta } => times(life_key(*player_id), (*delta).into()),
+        GameAction::AddMana { player_id, mana } => added_mana_hash(*player_id, mana),
+        GameAction::EmptyManaPool {
+            player_id,
+            prev_white,
+            prev_blue,
+            prev_black,
+            prev_red,
+            prev_green,
+            prev_colorless,
+            prev_snow,
+        } => {
+            let mut pool = ManaPool::new();
+            pool.white = *prev_white;
+            pool.blue = *prev_blue;
+            pool.black = *prev_black;
+            pool.red = *prev_red;
+            pool.green = *prev_green;
+            pool.colorless = *prev_colorless;
+            pool.snow = *prev_snow;
+            pool_hash(*player_id, &pool).wrapping_neg()
+        }
+        GameAction::SpendMana {
+            player_id,
+            prev_pool,
+            pool,
+        } => pool_hash(*player_id, pool).wrapping_sub(pool_hash(*player_id, prev_pool)),
+        GameAction::AddCounter {
+            card_id,
+            counter_type,
+            amount,
+        } => times(counter_key(*card_id, *counter_type), (*amount).into()),
+        GameAction::RemoveCounter {
+            card_id,
+            counter_type,
+            amount,
+        } => times(counter_key(*card_id, *counter_type), -i64::from(*amount)),
+        GameAction::AdvanceStep { from_step, to_step } => step_key(*to_step).wrapping_sub(step_key(*from_step)),
+        // A turn ends after its cleanup step, and the next one starts with its untap step
+        GameAction::ChangeTurn {
+            from_player, to_player, ..
+        } => step_key(Step::Untap)
+            .wrapping_sub(step_key(Step::Cleanup))
+            .wrapping_add(active_player_key(*to_player))
+            .wrapping_sub(active_player_key(*from_player))
+            .wrapping_add(key(TURN, 0, 0)),
+        GameAction::SetLandsPlayed {
+            player_id,
+            count,
+            previous,
+        } => times(lands_played_key(*player_id), i64::from(*count) - i64::from(*previous)),
+        GameAction::SetEnteredBattlefield {
+            card_id,
+            turn,
+            previous,
+        } => entered_key(*card_id, Some(*turn)).wrapping_sub(entered_key(*card_id, *previous)),
+        GameAction::DeclareAttacker {
+            card_id,
+            defending_player,
+            planeswalker,
+            prev_combat_active,
+        } => attacker_hash(*card_id, *defending_player, *planeswalker)
+            .wrapping_add(flag(key(COMBAT_ACTIVE, 0, 0), !prev_combat_active)),
+        GameAction::DeclareBlocker { card_id, attackers } => blocker_hash(*card_id, attackers),
+        GameAction::SetCombat { previous, combat } => combat_hash(combat).wrapping_sub(combat_hash(previous)),
+        // The order of a library and the RNG's position aren't hashed
+        GameAction::ShuffleLibrary { .. } | GameAction::UseRng { .. } => 0,
+        GameAction::PumpCreature {
+            card_id,
+            power_delta,
+            toughness_delta,
+        } => pump_hash(*card_id, *power_delta, *toughness_delta),
+        GameAction::AddPendingTrigger { ability } => pending_trigger_key(ability),
+        GameAction::PutTriggerOnStack { ability, .. } => {
+            ability_key(TRIGGERED_ABILITY, ability, 0).wrapping_sub(pending_trigger_key(ability))
+        }
+        GameAction::SetSpellChoices {
+            card_id,
+            choices,
+            previous,
+        } => spell_choices_key(*card_id, choices).wrapping_sub(spell_choices_key(*card_id, previous)),
+        GameAction::PutAbilityOnStack { object } => stack_object_key(object),
+        GameAction::ResolveAbility { object } | GameAction::RemoveFromStack { object, .. } => {
+            stack_object_key(object).wrapping_neg()
+        }
+        GameAction::CreateToken { card_id } => zone_key(*card_id, Zone::Battlefield),
+        GameAction::AddReplacementEffect { effect } => replacement_key(effect),
+        GameAction::RemoveReplacementEffect { effect, .. } => replacement_key(effect).wrapping_neg(),
+        GameAction::UseDamageShield { effect, previous, .. } => {
+            replacement_key(effect).wrapping_sub(replacement_key(previous))
+        }
+        GameAction::SetAttachedTo {
+            card_id,
+            attached_to,
+            previous,
+        } => attached_key(*card_id, *attached_to).wrapping_sub(attached_key(*card_id, *previous)),
+        GameAction::ActivateLoyaltyAbility {
+            card_id,
+            turn,
+            previous_turn,
+        } => loyalty_activated_key(*card_id, Some(*turn)).wrapping_sub(loyalty_activated_key(*card_id, *previous_turn)),
+        GameAction::TokenCeasesToExist { card_id, zone, .. } | GameAction::CardLeavesGame { card_id, zone, .. } => {
+            zone_key(*card_id, *zone).wrapping_neg()
+        }
+        GameAction::TargetPendingTrigger { ability, previous, .. } => {
+            pending_trigger_key(ability).wrapping_sub(pending_trigger_key(previous))
+        }
+        GameAction::RemovePendingTrigger { ability, .. } => pending_trigger_key(ability).wrapping_neg(),
+        GameAction::MarkDamage {
+            card_id,
+            amount,
+            deathtouch,
+            prev_deathtouch,
+        } => times(damage_key(*card_id), (*amount).into())
+            .wrapping_add(flag(deathtouch_key(*card_id), *deathtouch && !prev_deathtouch)),
+        GameAction::ClearDamage {
+            card_id,
+            prev_damage,
+            prev_deathtouch,
+        } => times(damage_key(*card_id), -i64::from(*prev_damage))
+            .wrapping_sub(flag(deathtouch_key(*card_id), *prev_deathtouch)),
+        GameAction::PlayerLoses { player_id } => lost_key(*player_id),
+        GameAction::CastFromCommandZone { card_id } => commander_casts_key(*card_id),
+        GameAction::CommanderDamage {
+            player_id,
+            commander,
+            amount,
+        } => times(commander_damage_key(*player_id, *commander), (*amount).into()),
+        GameAction::PlayerLeavesGame { player_id } => left_game_key(*player_id),
+        GameAction::DrawFromEmptyLibrary { player_id } => drew_from_empty_library_key(*player_id),
+        GameAction::ChoicePoint { .. } => 0,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,7 +622,8 @@ mod tests {
             "undo_log": ["action1", "action2"],
             "player": {
                 "life": 20,
-                "lands_played_this_turn": 1
+                "lands_played_this_turn": 1,
+                "logger": {"verbosity": 2}
             }
         });
 
@@ -112,7 +634,8 @@ mod tests {
             serde_json::json!({
                 "turn_number": 5,
                 "player": {
-                    "life": 20
+                    "life": 20,
+                    "lands_played_this_turn": 1
                 }
             })
         );
@@ -134,4 +657,287 @@ mod tests {
 
         assert_eq!(hash1, hash2);
     }
+
+    /// A game with three cards in Alice's hand, hashed from scratch
+    fn game_with_hand() -> (GameState, PlayerId, Vec<CardId>) {
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let mut cards = Vec::new();
+        for name in ["Grizzly Bears", "Forest", "Mountain"] {
+            let id = game.next_card_id();
+            game.cards.insert(id, crate::core::Card::new(id, name, alice));
+            game.get_player_zones_mut(alice).unwrap().hand.add(id);
+            cards.push(id);
+        }
+        game.reset_zobrist_hash();
+        (game, alice, cards)
+    }
+
+    #[test]
+    fn test_zobrist_hash_follows_actions_and_undo() {
+        use crate::core::{Effect, ManaCost};
+
+        let (mut game, alice, cards) = game_with_hand();
+        let start = game.zobrist_hash();
+        let mut hashes = Vec::new();
+
+        game.move_card(cards[0], Zone::Hand, Zone::Battlefield, alice).unwrap();
+        game.move_card(cards[1], Zone::Hand, Zone::Battlefield, alice).unwrap();
+        game.execute_effect(&Effect::TapPermanent { target: cards[1] }).unwrap();
+        game.add_counters(cards[0], CounterType::P1P1, 2).unwrap();
+        game.lose_life(alice, 3).unwrap();
+        game.execute_effect(&Effect::AddMana {
+            player: alice,
+            mana: ManaCost::from_string("GG"),
+        })
+        .unwrap();
+        game.pay_mana_cost(alice, &ManaCost::from_string("G")).unwrap();
+        game.empty_mana_pool(alice).unwrap();
+        game.advance_step().unwrap();
+        game.untap_all(alice).unwrap();
+
+        for _ in 0..game.undo_log.len() {
+            assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+            hashes.push(game.zobrist_hash());
+            game.undo().unwrap();
+        }
+        hashes.push(game.zobrist_hash());
+
+        assert_eq!(game.zobrist_hash(), start);
+        assert_eq!(compute_zobrist_hash(&game), start);
+        // Every logged action changed the hash
+        hashes.dedup();
+        assert_eq!(hashes.len(), 11);
+    }
+
+    #[test]
+    fn test_zobrist_hash_ignores_move_order() {
+        let (mut first, alice, cards) = game_with_hand();
+        let mut second = first.clone();
+
+        first.move_card(cards[1], Zone::Hand, Zone::Battlefield, alice).unwrap();
+        first.move_card(cards[2], Zone::Hand, Zone::Graveyard, alice).unwrap();
+        second.move_card(cards[2], Zone::Hand, Zone::Graveyard, alice).unwrap();
+        second
+            .move_card(cards[1], Zone::Hand, Zone::Battlefield, alice)
+            .unwrap();
+
+        assert_eq!(first.zobrist_hash(), second.zobrist_hash());
+        assert_ne!(first.zobrist_hash(), compute_zobrist_hash(&game_with_hand().0));
+    }
+
+    #[test]
+    fn test_zobrist_hash_with_annihilated_counters() {
+        let (mut game, alice, cards) = game_with_hand();
+        game.move_card(cards[0], Zone::Hand, Zone::Battlefield, alice).unwrap();
+        let before = game.zobrist_hash();
+
+        game.add_counters(cards[0], CounterType::P1P1, 2).unwrap();
+        game.add_counters(cards[0], CounterType::M1M1, 3).unwrap();
+        assert_eq!(game.cards.get(cards[0]).unwrap().get_counter(CounterType::M1M1), 1);
+        assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+
+        game.add_counters(cards[0], CounterType::P1P1, 1).unwrap();
+        assert_eq!(game.zobrist_hash(), before);
+    }
+
+    #[test]
+    fn test_zobrist_hash_covers_combat_damage_stack_and_players() {
+        use crate::core::{CardType, Effect};
+        use crate::game::test_support::{add_permanent, creature};
+        use crate::game::{AttackTarget, StackAbility};
+        use smallvec::smallvec;
+
+        let mut game = GameState::new_multiplayer(vec!["Alice".into(), "Bob".into(), "Carol".into()], 20);
+        let (alice, bob, carol) = (game.players[0].id, game.players[1].id, game.players[2].id);
+        let bears = add_permanent(&mut game, alice, "Grizzly Bears", creature(2, 2));
+        let wolf = add_permanent(&mut game, bob, "Timber Wolves", creature(1, 1));
+        let sword = add_permanent(&mut game, alice, "Vulshok Morningstar", |card| {
+            card.types.push(CardType::Artifact);
+            card.subtypes.push("Equipment".into());
+        });
+        let ability = StackAbility {
+            source: sword,
+            controller: alice,
+            effects: Vec::new(),
+            targets: vec![bears],
+            description: "Equip {2}".to_string(),
+            target_restriction: None,
+        };
+        game.reset_zobrist_hash();
+        let start = game.zobrist_hash();
+        let logged = game.undo_log.len();
+        let mut hashes = vec![start];
+
+        type Change<'a> = Box<dyn Fn(&mut GameState) + 'a>;
+        let steps: Vec<Change> = vec![
+            Box::new(|game| game.mark_damage(bears, 1, false).unwrap()),
+            Box::new(|game| game.clear_damage(bears).unwrap()),
+            Box::new(|game| {
+                game.execute_effect(&Effect::PumpCreature {
+                    target: bears,
+                    power_bonus: 3,
+                    toughness_bonus: 0,
+                })
+                .unwrap()
+            }),
+            Box::new(|game| assert!(game.attach(sword, bears).unwrap())),
+            Box::new(|game| game.add_attacker(bears, AttackTarget::Player(bob)).unwrap()),
+            Box::new(|game| game.add_blocker(wolf, smallvec![bears])),
+            Box::new(|game| game.end_combat()),
+            Box::new(|game| game.put_ability_on_stack(ability.clone(), 0)),
+            Box::new(|game| {
+                game.add_pending_trigger(ability.clone());
+                game.put_triggers_on_stack(alice, &[0]).unwrap();
+            }),
+            Box::new(|game| {
+                game.resolve_top_ability().unwrap();
+            }),
+            Box::new(|game| game.change_life(carol, -20).unwrap()),
+            Box::new(|game| game.leave_game(carol).unwrap()),
+        ];
+        for step in &steps {
+            step(&mut game);
+            assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+            hashes.push(game.zobrist_hash());
+        }
+        // Every step changed the hash
+        assert!(hashes.windows(2).all(|pair| pair[0] != pair[1]));
+
+        while game.undo_log.len() > logged {
+            game.undo().unwrap();
+            assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+        }
+        assert_eq!(game.zobrist_hash(), start);
+    }
+
+    /// Apply a change, checking that it changes the hash and that the
+    /// incremental hash keeps up with it
+    fn assert_changes_hash(game: &mut GameState, change: impl FnOnce(&mut GameState)) {
+        let before = game.zobrist_hash();
+        change(game);
+        assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+        assert_ne!(game.zobrist_hash(), before);
+    }
+
+    #[test]
+    fn test_zobrist_hash_covers_state_that_changes_legal_play() {
+        use crate::core::{ReplacementAction, ReplacementEffect, ReplacementEvent, TargetRef};
+        use crate::game::test_support::{add_permanent, add_to_hand, creature};
+        use crate::game::{SpellChoices, StackAbility};
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let (alice, bob) = (game.players[0].id, game.players[1].id);
+        let bears = add_permanent(&mut game, alice, "Grizzly Bears", creature(2, 2));
+        let fireball = add_to_hand(&mut game, alice, "Fireball", |_| {});
+        let commander = add_to_hand(&mut game, bob, "Kamahl, Pit Fighter", creature(6, 1));
+        let trigger = StackAbility {
+            source: bears,
+            controller: alice,
+            effects: Vec::new(),
+            targets: Vec::new(),
+            description: "When this enters, deal 1 damage to any target".to_string(),
+            target_restriction: None,
+        };
+        let fog = ReplacementEffect {
+            event: ReplacementEvent::DamageDone { combat_only: true },
+            affected: None,
+            valid_source: None,
+            action: ReplacementAction::Prevent,
+            single_use: false,
+            description: "Prevent all combat damage this turn".to_string(),
+        };
+        game.reset_zobrist_hash();
+
+        // Lands played and summoning sickness
+        assert_changes_hash(&mut game, |game| game.set_lands_played(alice, 1).unwrap());
+        assert_changes_hash(&mut game, |game| {
+            game.turn.turn_number += 1;
+            game.reset_zobrist_hash();
+            let before = game.zobrist_hash();
+            game.record_entered_battlefield(bears).unwrap();
+            assert_ne!(game.zobrist_hash(), before);
+        });
+
+        // Each of a spell's casting choices
+        game.move_card(fireball, Zone::Hand, Zone::Stack, alice).unwrap();
+        let mut choices = SpellChoices {
+            modes: vec![1],
+            ..SpellChoices::default()
+        };
+        assert_changes_hash(&mut game, |game| {
+            game.set_spell_choices(fireball, choices.clone()).unwrap()
+        });
+        choices.x_value = 4;
+        assert_changes_hash(&mut game, |game| {
+            game.set_spell_choices(fireball, choices.clone()).unwrap()
+        });
+        choices.targets = vec![bears];
+        assert_changes_hash(&mut game, |game| {
+            game.set_spell_choices(fireball, choices.clone()).unwrap()
+        });
+        choices.targets = vec![bears, commander];
+        choices.division = vec![1, 3];
+        assert_changes_hash(&mut game, |game| {
+            game.set_spell_choices(fireball, choices.clone()).unwrap()
+        });
+        choices.division = vec![3, 1];
+        assert_changes_hash(&mut game, |game| {
+            game.set_spell_choices(fireball, choices.clone()).unwrap()
+        });
+
+        // Pending triggers and their targets, and replacement effects
+        assert_changes_hash(&mut game, |game| game.add_pending_trigger(trigger.clone()));
+        assert_changes_hash(&mut game, |game| game.target_pending_trigger(0, &[commander]).unwrap());
+        assert_changes_hash(&mut game, |game| {
+            game.add_replacement_effect(None, alice, fog.clone());
+        });
+        assert_changes_hash(&mut game, |game| game.end_replacement_effects());
+
+        // What a damage shield still prevents, with life staying the same
+        let shield = ReplacementEffect {
+            event: ReplacementEvent::DamageDone { combat_only: false },
+            affected: Some(crate::core::Selector::parse("You")),
+            action: ReplacementAction::PreventAmount(3),
+            description: "Prevent the next 3 damage that would be dealt to you this turn".to_string(),
+            ..fog.clone()
+        };
+        assert_changes_hash(&mut game, |game| {
+            game.add_replacement_effect(None, alice, shield);
+        });
+        assert_changes_hash(&mut game, |game| {
+            game.deal_damage_from(None, &TargetRef::Player(alice), 2, false)
+                .unwrap();
+        });
+        assert_eq!(game.get_player(alice).unwrap().life, 20);
+
+        // Commander tax and damage, loyalty activations and deathtouch damage
+        assert_changes_hash(&mut game, |game| game.record_commander_cast(commander).unwrap());
+        assert_changes_hash(&mut game, |game| {
+            game.record_commander_damage(alice, commander, 6).unwrap()
+        });
+        assert_changes_hash(&mut game, |game| game.record_loyalty_activation(bears).unwrap());
+        assert_changes_hash(&mut game, |game| game.mark_damage(bears, 1, false).unwrap());
+        assert_changes_hash(&mut game, |game| game.mark_damage(bears, 1, true).unwrap());
+
+        // Drawing from an empty library, and poison counters (set up directly,
+        // as nothing in the game gives them yet)
+        assert_changes_hash(&mut game, |game| {
+            game.get_player_zones_mut(bob).unwrap().library.cards.clear();
+            game.reset_zobrist_hash();
+            let before = game.zobrist_hash();
+            assert_eq!(game.draw_card(bob).unwrap(), None);
+            assert_ne!(game.zobrist_hash(), before);
+        });
+        assert_changes_hash(&mut game, |game| {
+            game.get_player_mut(bob).unwrap().poison_counters = 3;
+            game.reset_zobrist_hash();
+        });
+
+        // Each change is taken back by undo
+        while !game.undo_log.is_empty() {
+            game.undo().unwrap();
+            assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+        }
+    }
 }
@@ -0,0 +1,45 @@
+//! Fixtures shared by the game module's unit tests
+
+use crate::core::{Card, CardId, CardType, PlayerId};
+use crate::game::GameState;
+use crate::zones::Zone;
+
+/// Create a card that isn't in any zone yet, letting `setup` fill in its characteristics
+pub(crate) fn new_card(game: &mut GameState, owner: PlayerId, name: &str, setup: impl FnOnce(&mut Card)) -> CardId {
+    let id = game.next_card_id();
+    let mut card = Card::new(id, name.to_string(), owner);
+    setup(&mut card);
+    game.cards.insert(id, card);
+    id
+}
+
+/// Create a card in its owner's hand
+pub(crate) fn add_to_hand(game: &mut GameState, owner: PlayerId, name: &str, setup: impl FnOnce(&mut Card)) -> CardId {
+    let id = new_card(game, owner, name, setup);
+    game.get_player_zones_mut(owner).unwrap().hand.add(id);
+    id
+}
+
+/// Create a permanent and move it from its owner's hand onto the battlefield
+///
+/// The move goes through `GameState::move_card`, so replacement effects apply
+/// and a planeswalker enters with its loyalty.
+pub(crate) fn add_permanent(
+    game: &mut GameState,
+    owner: PlayerId,
+    name: &str,
+    setup: impl FnOnce(&mut Card),
+) -> CardId {
+    let id = add_to_hand(game, owner, name, setup);
+    game.move_card(id, Zone::Hand, Zone::Battlefield, owner).unwrap();
+    id
+}
+
+/// Setup for a vanilla creature with the given power and toughness
+pub(crate) fn creature(power: i8, toughness: i8) -> impl Fn(&mut Card) {
+    move |card: &mut Card| {
+        card.types.push(CardType::Creature);
+        card.power = Some(power);
+        card.toughness = Some(toughness);
+    }
+}
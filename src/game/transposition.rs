@@ -0,0 +1,364 @@
+//! Transposition table for game-tree search
+//!
+//! Many move orders reach the same position: tapping lands in a different order,
+//! or casting two creatures either way round. A transposition table caches what a
+//! search learned about a position, keyed by its [Zobrist hash](crate::game::GameState::zobrist_hash),
+//! so the search can reuse it instead of exploring the position again. The hash
+//! covers everything that decides the legal moves, down to the land drops left
+//! and the X and targets of spells on the stack, so positions only share an
+//! entry if they play the same.
+//!
+//! The table is bounded: it has a fixed number of buckets of two entries, and the
+//! low bits of the hash pick the bucket. When a bucket is full, a new entry
+//! replaces the one from an older search, or else the shallower one, so deep
+//! results survive while recent ones still get a slot. Buckets are split among
+//! locked shards, so the table can be shared (`&TranspositionTable` is `Sync`)
+//! between the threads of a rayon-parallel search as well as used from one thread.
+
+use crate::game::ReplayChoice;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Entries per bucket
+const BUCKET_SIZE: usize = 2;
+
+/// Upper bound on the number of locked shards
+const MAX_SHARDS: usize = 64;
+
+/// How a stored value relates to the position's true value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The value is exact
+    Exact,
+    /// The search failed high: the true value is at least this (a cutoff)
+    Lower,
+    /// The search failed low: the true value is at most this
+    Upper,
+}
+
+/// What a search learned about a position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableEntry {
+    /// Hash of the position
+    pub key: u64,
+    /// Value of the position, from the searching player's perspective
+    pub value: i32,
+    /// Depth of the search below the position that found the value
+    pub depth: u16,
+    /// Whether the value is exact or a bound
+    pub bound: Bound,
+    /// Best move found at the position: a spell ability or another choice
+    pub best: Option<ReplayChoice>,
+    /// The search (see [`TranspositionTable::new_search`]) that stored the entry
+    generation: u32,
+}
+
+/// Hit and miss counts of a transposition table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TableStats {
+    /// Lookups that found the position
+    pub hits: u64,
+    /// Lookups that didn't
+    pub misses: u64,
+    /// Entries stored
+    pub stores: u64,
+    /// Stores that evicted a different position
+    pub evictions: u64,
+}
+
+impl TableStats {
+    /// Fraction of lookups that found the position (0 with no lookups)
+    pub fn hit_rate(&self) -> f64 {
+        let probes = self.hits + self.misses;
+        if probes == 0 {
+            0.0
+        } else {
+            self.hits as f64 / probes as f64
+        }
+    }
+}
+
+type Bucket = [Option<TableEntry>; BUCKET_SIZE];
+
+/// Bounded, concurrent cache of searched positions keyed by state hash
+pub struct TranspositionTable {
+    /// Buckets, split among shards (bucket `i` is in shard `i % shards.len()`)
+    shards: Box<[Mutex<Vec<Bucket>>]>,
+    /// Number of buckets, a power of two
+    buckets: usize,
+    generation: AtomicU32,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stores: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl TranspositionTable {
+    /// Create a table holding at least `capacity` entries (rounded up to a power of two)
+    pub fn new(capacity: usize) -> Self {
+        let buckets = capacity.div_ceil(BUCKET_SIZE).max(1).next_power_of_two();
+        let shard_count = buckets.min(MAX_SHARDS);
+        let shards = (0..shard_count)
+            .map(|_| Mutex::new(vec![Bucket::default(); buckets / shard_count]))
+            .collect();
+        TranspositionTable {
+            shards,
+            buckets,
+            generation: AtomicU32::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.buckets * BUCKET_SIZE
+    }
+
+    /// Number of entries stored
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                let shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+                shard.iter().flatten().filter(|entry| entry.is_some()).count()
+            })
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run `f` on the bucket for `key`
+    fn with_bucket<R>(&self, key: u64, f: impl FnOnce(&mut Bucket) -> R) -> R {
+        let index = key as usize & (self.buckets - 1);
+        let shard_count = self.shards.len();
+        let mut shard = self.shards[index % shard_count]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        f(&mut shard[index / shard_count])
+    }
+
+    /// Look up a position
+    pub fn probe(&self, key: u64) -> Option<TableEntry> {
+        let found = self.with_bucket(key, |bucket| {
+            bucket.iter().flatten().find(|entry| entry.key == key).cloned()
+        });
+        let counter = if found.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    /// Store what a search learned about a position
+    ///
+    /// Replaces an earlier entry for the same position. Otherwise takes an empty
+    /// slot of its bucket, or evicts the entry from the oldest search, and among
+    /// those the shallowest.
+    pub fn store(&self, key: u64, value: i32, depth: u16, bound: Bound, best: Option<ReplayChoice>) {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let entry = TableEntry {
+            key,
+            value,
+            depth,
+            bound,
+            best,
+            generation,
+        };
+        let evicted = self.with_bucket(key, |bucket| {
+            let slot = bucket
+                .iter()
+                .position(|slot| slot.as_ref().is_some_and(|old| old.key == key))
+                .or_else(|| bucket.iter().position(Option::is_none))
+                .unwrap_or_else(|| {
+                    (0..BUCKET_SIZE)
+                        .min_by_key(|&i| {
+                            let old = bucket[i].as_ref().expect("full bucket");
+                            (old.generation == generation, old.depth)
+                        })
+                        .unwrap_or(0)
+                });
+            bucket[slot].replace(entry).is_some_and(|old| old.key != key)
+        });
+        self.stores.fetch_add(1, Ordering::Relaxed);
+        if evicted {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Start a new search: entries stored so far are kept, but are evicted first
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Remove every entry and reset the statistics
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            let mut shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            shard.iter_mut().for_each(|bucket| *bucket = Bucket::default());
+        }
+        for counter in [&self.hits, &self.misses, &self.stores, &self.evictions] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Hit and miss counts since the table was created or cleared
+    pub fn stats(&self) -> TableStats {
+        TableStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{CardId, SpellAbility};
+
+    fn play_land(id: u32) -> Option<ReplayChoice> {
+        Some(ReplayChoice::SpellAbility(Some(SpellAbility::PlayLand {
+            card_id: CardId::new(id),
+        })))
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let table = TranspositionTable::new(100);
+        assert_eq!(table.capacity(), 128);
+        assert!(table.probe(42).is_none());
+
+        table.store(42, 7, 3, Bound::Exact, play_land(5));
+        let entry = table.probe(42).unwrap();
+        assert_eq!((entry.value, entry.depth, entry.bound), (7, 3, Bound::Exact));
+        assert_eq!(entry.best, play_land(5));
+
+        // A later result for the same position replaces the earlier one
+        table.store(42, -2, 1, Bound::Upper, None);
+        assert_eq!(table.probe(42).unwrap().value, -2);
+        assert_eq!(table.len(), 1);
+
+        let stats = table.stats();
+        assert_eq!((stats.hits, stats.misses, stats.stores, stats.evictions), (2, 1, 2, 0));
+        assert!((stats.hit_rate() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_replacement_prefers_deep_and_recent_entries() {
+        // One bucket, so every key collides
+        let table = TranspositionTable::new(2);
+        table.store(1, 0, 5, Bound::Exact, None);
+        table.store(2, 0, 2, Bound::Lower, None);
+
+        // The shallower entry is evicted
+        table.store(3, 0, 4, Bound::Exact, None);
+        assert!(table.probe(1).is_some());
+        assert!(table.probe(2).is_none());
+        assert!(table.probe(3).is_some());
+        assert_eq!(table.stats().evictions, 1);
+
+        // Entries from an earlier search go first, however deep
+        table.new_search();
+        table.store(4, 0, 1, Bound::Exact, None);
+        assert!(table.probe(3).is_none());
+        assert!(table.probe(1).is_some());
+        table.store(5, 0, 1, Bound::Exact, None);
+        assert!(table.probe(1).is_none());
+        assert!(table.probe(4).is_some());
+
+        table.clear();
+        assert!(table.is_empty());
+        assert_eq!(table.stats(), TableStats::default());
+    }
+
+    #[test]
+    fn test_transposed_move_orders_hit() {
+        use crate::core::{Card, Effect};
+        use crate::game::GameState;
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let lands: Vec<CardId> = (0..2)
+            .map(|_| {
+                let id = game.next_card_id();
+                game.cards.insert(id, Card::new(id, "Forest", alice));
+                game.battlefield.add(id);
+                id
+            })
+            .collect();
+        game.reset_zobrist_hash();
+        let mut other_order = game.clone();
+
+        let table = TranspositionTable::new(64);
+        for &land in &lands {
+            game.execute_effect(&Effect::TapPermanent { target: land }).unwrap();
+        }
+        table.store(game.zobrist_hash(), 10, 2, Bound::Exact, None);
+
+        for &land in lands.iter().rev() {
+            other_order
+                .execute_effect(&Effect::TapPermanent { target: land })
+                .unwrap();
+        }
+        assert_eq!(
+            table.probe(other_order.zobrist_hash()).map(|entry| entry.value),
+            Some(10)
+        );
+    }
+
+    #[test]
+    fn test_positions_with_different_legal_moves_miss() {
+        use crate::game::test_support::add_to_hand;
+        use crate::game::{GameState, SpellChoices};
+        use crate::zones::Zone;
+
+        let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 20);
+        let alice = game.players[0].id;
+        let fireball = add_to_hand(&mut game, alice, "Fireball", |_| {});
+        game.reset_zobrist_hash();
+        game.move_card(fireball, Zone::Hand, Zone::Stack, alice).unwrap();
+        let choices = SpellChoices {
+            x_value: 1,
+            ..SpellChoices::default()
+        };
+        game.set_spell_choices(fireball, choices.clone()).unwrap();
+
+        let table = TranspositionTable::new(64);
+        table.store(game.zobrist_hash(), 10, 2, Bound::Exact, None);
+
+        // Alice has used her land drop
+        let mut land_played = game.clone();
+        land_played.set_lands_played(alice, 1).unwrap();
+        assert_eq!(table.probe(land_played.zobrist_hash()), None);
+
+        // Fireball was cast with a different X
+        let mut bigger_x = game.clone();
+        bigger_x
+            .set_spell_choices(fireball, SpellChoices { x_value: 2, ..choices })
+            .unwrap();
+        assert_eq!(table.probe(bigger_x.zobrist_hash()), None);
+
+        assert!(table.probe(game.zobrist_hash()).is_some());
+    }
+
+    #[test]
+    fn test_shared_between_rayon_threads() {
+        use rayon::prelude::*;
+
+        let table = TranspositionTable::new(1 << 12);
+        (0..1000u64).into_par_iter().for_each(|key| {
+            table.store(key, key as i32, 1, Bound::Exact, None);
+        });
+        let found = (0..1000u64)
+            .into_par_iter()
+            .filter(|&key| table.probe(key).is_some_and(|entry| entry.value == key as i32))
+            .count();
+
+        assert_eq!(found, 1000);
+        assert_eq!(table.stats().hits, 1000);
+    }
+}
@@ -0,0 +1,440 @@
+//! Property tests for undo: random games rewound to each of their choice points
+//!
+//! Every mutation of the game state goes through a logged action, so undoing the
+//! actions logged after a choice point must restore the state exactly as it was
+//! when the choice was first presented. These tests play random games, record
+//! the state hash at every choice, then rewind step by step and compare.
+
+use crate::core::{
+    Card, CardId, CardType, Color, Cost, CounterType, Effect, Keyword, ManaCost, PlayerId, ReplacementKey,
+    SpellAbility, TargetRef, TokenDefinition,
+};
+use crate::game::controller::GameStateView;
+use crate::game::snapshot::ControllerType;
+use crate::game::{
+    compute_state_hash, AttackTarget, GameLoop, GameState, PlayerController, RandomController, StackAbility,
+    VerbosityLevel,
+};
+use smallvec::SmallVec;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The state when a choice was presented
+struct Recorded {
+    /// Length of the undo log
+    log_len: usize,
+    state_hash: u64,
+    zobrist_hash: u64,
+    /// The state itself, to report what a rewind failed to restore
+    json: serde_json::Value,
+}
+
+/// A random controller that records the state at each of its choices
+struct RecordingController {
+    inner: RandomController,
+    recorded: Rc<RefCell<Vec<Recorded>>>,
+}
+
+impl RecordingController {
+    fn new(player_id: PlayerId, seed: u64, recorded: Rc<RefCell<Vec<Recorded>>>) -> Self {
+        RecordingController {
+            inner: RandomController::with_seed(player_id, seed),
+            recorded,
+        }
+    }
+
+    fn record(&self, view: &GameStateView) {
+        let game = view.game();
+        self.recorded.borrow_mut().push(Recorded {
+            log_len: game.undo_log.len(),
+            state_hash: compute_state_hash(game),
+            zobrist_hash: game.zobrist_hash(),
+            json: state_json(game),
+        });
+    }
+}
+
+impl PlayerController for RecordingController {
+    fn player_id(&self) -> PlayerId {
+        self.inner.player_id()
+    }
+
+    fn choose_spell_ability_to_play(
+        &mut self,
+        view: &GameStateView,
+        available: &[SpellAbility],
+    ) -> Option<SpellAbility> {
+        self.record(view);
+        self.inner.choose_spell_ability_to_play(view, available)
+    }
+
+    fn choose_modes(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        modes: &[String],
+        min: u8,
+        max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        self.record(view);
+        self.inner.choose_modes(view, spell, modes, min, max)
+    }
+
+    fn choose_x_value(&mut self, view: &GameStateView, spell: CardId, max_x: u8) -> u8 {
+        self.record(view);
+        self.inner.choose_x_value(view, spell, max_x)
+    }
+
+    fn choose_alternative_cost(&mut self, view: &GameStateView, spell: CardId, cost: &Cost) -> bool {
+        self.record(view);
+        self.inner.choose_alternative_cost(view, spell, cost)
+    }
+
+    fn choose_targets(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        valid_targets: &[CardId],
+    ) -> SmallVec<[CardId; 4]> {
+        self.record(view);
+        self.inner.choose_targets(view, spell, valid_targets)
+    }
+
+    fn choose_division(
+        &mut self,
+        view: &GameStateView,
+        spell: CardId,
+        targets: &[CardId],
+        total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        self.record(view);
+        self.inner.choose_division(view, spell, targets, total)
+    }
+
+    fn choose_mana_sources_to_pay(
+        &mut self,
+        view: &GameStateView,
+        cost: &ManaCost,
+        available_sources: &[CardId],
+    ) -> SmallVec<[CardId; 8]> {
+        self.record(view);
+        self.inner.choose_mana_sources_to_pay(view, cost, available_sources)
+    }
+
+    fn choose_mana_color(&mut self, view: &GameStateView, source: CardId, colors: &[Color]) -> Color {
+        self.record(view);
+        self.inner.choose_mana_color(view, source, colors)
+    }
+
+    fn choose_attackers(
+        &mut self,
+        view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
+        self.record(view);
+        self.inner.choose_attackers(view, available_creatures, defenders)
+    }
+
+    fn choose_blockers(
+        &mut self,
+        view: &GameStateView,
+        available_blockers: &[CardId],
+        attackers: &[CardId],
+    ) -> SmallVec<[(CardId, CardId); 8]> {
+        self.record(view);
+        self.inner.choose_blockers(view, available_blockers, attackers)
+    }
+
+    fn choose_damage_assignment_order(
+        &mut self,
+        view: &GameStateView,
+        attacker: CardId,
+        blockers: &[CardId],
+    ) -> SmallVec<[CardId; 4]> {
+        self.record(view);
+        self.inner.choose_damage_assignment_order(view, attacker, blockers)
+    }
+
+    fn choose_trigger_order(&mut self, view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        self.record(view);
+        self.inner.choose_trigger_order(view, triggers)
+    }
+
+    fn choose_replacement_effect(&mut self, view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        self.record(view);
+        self.inner.choose_replacement_effect(view, effects)
+    }
+
+    fn choose_legend_to_keep(&mut self, view: &GameStateView, legends: &[CardId]) -> CardId {
+        self.record(view);
+        self.inner.choose_legend_to_keep(view, legends)
+    }
+
+    fn choose_mulligan(&mut self, view: &GameStateView, hand: &[CardId], mulligans_taken: u8) -> bool {
+        self.record(view);
+        self.inner.choose_mulligan(view, hand, mulligans_taken)
+    }
+
+    fn choose_cards_to_bottom(&mut self, view: &GameStateView, hand: &[CardId], count: usize) -> SmallVec<[CardId; 7]> {
+        self.record(view);
+        self.inner.choose_cards_to_bottom(view, hand, count)
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        view: &GameStateView,
+        source: CardId,
+        cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        self.record(view);
+        self.inner.choose_cards_for_cost(view, source, cost, candidates, count)
+    }
+
+    fn choose_cards_to_discard(
+        &mut self,
+        view: &GameStateView,
+        hand: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 7]> {
+        self.record(view);
+        self.inner.choose_cards_to_discard(view, hand, count)
+    }
+
+    fn on_priority_passed(&mut self, view: &GameStateView) {
+        self.inner.on_priority_passed(view)
+    }
+
+    fn on_game_end(&mut self, view: &GameStateView, won: bool) {
+        self.inner.on_game_end(view, won)
+    }
+
+    fn get_controller_type(&self) -> ControllerType {
+        self.inner.get_controller_type()
+    }
+}
+
+/// The state as JSON, without the fields the state hash leaves out
+fn state_json(game: &GameState) -> serde_json::Value {
+    let mut json = serde_json::to_value(game).unwrap();
+    let map = json.as_object_mut().unwrap();
+    map.remove("undo_log");
+    map.remove("logger");
+    json
+}
+
+/// Paths at which two JSON values differ
+fn differences(path: &str, before: &serde_json::Value, after: &serde_json::Value, found: &mut Vec<String>) {
+    use serde_json::Value;
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                match b.get(key) {
+                    Some(other) => differences(&format!("{path}.{key}"), value, other, found),
+                    None => found.push(format!("{path}.{key}: missing after rewind")),
+                }
+            }
+            for key in b.keys().filter(|key| !a.contains_key(*key)) {
+                found.push(format!("{path}.{key}: added by rewind"));
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                differences(&format!("{path}[{i}]"), x, y, found);
+            }
+        }
+        _ if before != after => found.push(format!("{path}: {before} became {after}")),
+        _ => {}
+    }
+}
+
+/// A two-player game with 32-card libraries of lands, creatures and spells
+fn random_game_state() -> GameState {
+    let mut game = GameState::new_two_player("Alice".to_string(), "Bob".to_string(), 12);
+    let ids: Vec<PlayerId> = game.players.iter().map(|p| p.id).collect();
+    for &owner in &ids {
+        for i in 0..32 {
+            let card_id = game.next_card_id();
+            let mut card = Card::new(card_id, "Test Card".to_string(), owner);
+            match i % 8 {
+                0 | 4 => {
+                    card.name = "Mountain".into();
+                    card.types.push(CardType::Land);
+                    card.subtypes.push("Mountain".into());
+                }
+                1 => {
+                    card.name = "Bear".into();
+                    card.types.push(CardType::Creature);
+                    card.mana_cost = ManaCost::from_string("1R");
+                    card.power = Some(2);
+                    card.toughness = Some(2);
+                }
+                2 => {
+                    card.name = "Raider".into();
+                    card.types.push(CardType::Creature);
+                    card.mana_cost = ManaCost::from_string("R");
+                    card.power = Some(2);
+                    card.toughness = Some(1);
+                    card.keywords.push(Keyword::Haste);
+                    card.keywords.push(Keyword::FirstStrike);
+                }
+                3 => {
+                    card.name = "Shock".into();
+                    card.types.push(CardType::Instant);
+                    card.mana_cost = ManaCost::from_string("R");
+                    card.effects.push(Effect::DealDamage {
+                        target: TargetRef::None,
+                        amount: 2,
+                    });
+                }
+                5 => {
+                    card.name = "Growth".into();
+                    card.types.push(CardType::Instant);
+                    card.mana_cost = ManaCost::from_string("R");
+                    card.effects.push(Effect::PumpCreature {
+                        target: CardId::new(0),
+                        power_bonus: 2,
+                        toughness_bonus: 2,
+                    });
+                }
+                6 => {
+                    card.name = "Blessing".into();
+                    card.types.push(CardType::Instant);
+                    card.mana_cost = ManaCost::from_string("R");
+                    card.effects.push(Effect::PutCounter {
+                        target: CardId::new(0),
+                        counter_type: CounterType::P1P1,
+                        amount: 1,
+                    });
+                }
+                _ => {
+                    card.name = "Recruit".into();
+                    card.types.push(CardType::Sorcery);
+                    card.mana_cost = ManaCost::from_string("1R");
+                    card.effects.push(Effect::CreateToken {
+                        owner: PlayerId::new(0),
+                        amount: 1,
+                        token: TokenDefinition::from_script_name("r_1_1_goblin"),
+                    });
+                    card.effects.push(Effect::DrawCards {
+                        player: PlayerId::new(0),
+                        count: 2,
+                    });
+                }
+            }
+            game.cards.insert(card_id, card);
+            game.get_player_zones_mut(owner).unwrap().library.add(card_id);
+        }
+    }
+    game.reset_zobrist_hash();
+    game
+}
+
+/// Play a random game, then rewind it to each of its choice points in turn
+fn play_and_rewind(seed: u64) {
+    let mut game = random_game_state();
+    let ids: Vec<PlayerId> = game.players.iter().map(|p| p.id).collect();
+    game.seed_rng(seed);
+
+    let recorded = Rc::new(RefCell::new(Vec::new()));
+    let mut alice = RecordingController::new(ids[0], seed, recorded.clone());
+    let mut bob = RecordingController::new(ids[1], seed + 1, recorded.clone());
+    GameLoop::new(&mut game)
+        .with_max_turns(12)
+        .with_verbosity(VerbosityLevel::Silent)
+        .run_game(&mut alice, &mut bob)
+        .unwrap();
+
+    let recorded = recorded.take();
+    assert!(recorded.len() > 20, "seed {seed}: only {} choices", recorded.len());
+    for (i, point) in recorded.iter().enumerate().rev() {
+        while game.undo_log.len() > point.log_len {
+            game.undo().unwrap();
+        }
+        if compute_state_hash(&game) != point.state_hash {
+            let mut found = Vec::new();
+            differences("game", &point.json, &state_json(&game), &mut found);
+            panic!(
+                "seed {seed}: rewinding to choice {i} (undo log length {}) left residue:\n{}",
+                point.log_len,
+                found.join("\n")
+            );
+        }
+        assert_eq!(game.zobrist_hash(), point.zobrist_hash, "seed {seed}, choice {i}");
+    }
+}
+
+#[test]
+fn test_rewind_random_games_to_every_choice_point() {
+    for seed in [1, 2, 3, 4, 5, 6, 7, 8] {
+        play_and_rewind(seed);
+    }
+}
+
+#[test]
+fn test_rewind_to_nested_checkpoints() {
+    let mut game = random_game_state();
+    let ids: Vec<PlayerId> = game.players.iter().map(|p| p.id).collect();
+    let mut alice = RandomController::with_seed(ids[0], 11);
+    let mut bob = RandomController::with_seed(ids[1], 12);
+    let mut play_turns = |game: &mut GameState, turns| {
+        GameLoop::new(game)
+            .with_verbosity(VerbosityLevel::Silent)
+            .run_turns(&mut alice, &mut bob, turns)
+            .unwrap();
+    };
+
+    let start_hash = compute_state_hash(&game);
+    let start = game.checkpoint();
+    play_turns(&mut game, 3);
+    let middle_hash = compute_state_hash(&game);
+    let middle = game.checkpoint();
+    play_turns(&mut game, 3);
+    let end = game.checkpoint();
+
+    game.rewind_to(&middle).unwrap();
+    assert_eq!(compute_state_hash(&game), middle_hash);
+    assert!(game.rewind_to(&end).is_err(), "rewound past the end checkpoint");
+
+    // Rewinding to the same checkpoint again is fine, and so is an outer one
+    game.rewind_to(&middle).unwrap();
+    game.rewind_to(&start).unwrap();
+    assert_eq!(compute_state_hash(&game), start_hash);
+    assert_eq!(game.undo_log.len(), 0);
+    assert!(game.rewind_to(&middle).is_err());
+}
+
+#[test]
+fn test_commit_discards_history_below_checkpoint() {
+    let mut game = random_game_state();
+    let ids: Vec<PlayerId> = game.players.iter().map(|p| p.id).collect();
+    let mut alice = RandomController::with_seed(ids[0], 21);
+    let mut bob = RandomController::with_seed(ids[1], 22);
+    let mut play_turns = |game: &mut GameState, turns| {
+        GameLoop::new(game)
+            .with_verbosity(VerbosityLevel::Silent)
+            .run_turns(&mut alice, &mut bob, turns)
+            .unwrap();
+    };
+
+    let start = game.checkpoint();
+    play_turns(&mut game, 2);
+    let committed_hash = compute_state_hash(&game);
+    let committed = game.checkpoint();
+    play_turns(&mut game, 2);
+    let explored = game.checkpoint();
+    play_turns(&mut game, 1);
+
+    game.commit(&committed).unwrap();
+    assert!(game.rewind_to(&start).is_err(), "history before the commit is gone");
+    assert!(game.commit(&start).is_err());
+
+    // Checkpoints at or after the committed one still work
+    game.rewind_to(&explored).unwrap();
+    game.rewind_to(&committed).unwrap();
+    assert_eq!(game.undo_log.len(), 0);
+    assert_eq!(compute_state_hash(&game), committed_hash);
+    assert_eq!(game.check_zobrist_hash().unwrap(), game.zobrist_hash());
+}
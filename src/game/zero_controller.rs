@@ -9,9 +9,10 @@
 //! - Blocks each attacker with one blocker
 //! - Discards the first N cards from hand
 
-use crate::core::{CardId, ManaCost, PlayerId, SpellAbility};
+use crate::core::{CardId, Color, Cost, ManaCost, PlayerId, ReplacementKey, SpellAbility};
 use crate::game::controller::GameStateView;
 use crate::game::controller::PlayerController;
+use crate::game::{AttackTarget, StackAbility};
 use smallvec::SmallVec;
 
 /// A controller that uses simple "first choice" heuristics
@@ -46,6 +47,27 @@ impl PlayerController for ZeroController {
         available.first().cloned()
     }
 
+    fn choose_modes(
+        &mut self,
+        _view: &GameStateView,
+        _spell: CardId,
+        modes: &[String],
+        min: u8,
+        _max: u8,
+    ) -> SmallVec<[usize; 4]> {
+        // Choose the first modes, as few as allowed
+        (0..modes.len()).take(min as usize).collect()
+    }
+
+    fn choose_x_value(&mut self, _view: &GameStateView, _spell: CardId, _max_x: u8) -> u8 {
+        0
+    }
+
+    fn choose_alternative_cost(&mut self, _view: &GameStateView, _spell: CardId, _cost: &Cost) -> bool {
+        // Always pay the mana cost
+        false
+    }
+
     fn choose_targets(
         &mut self,
         _view: &GameStateView,
@@ -62,6 +84,18 @@ impl PlayerController for ZeroController {
         }
     }
 
+    fn choose_division(
+        &mut self,
+        _view: &GameStateView,
+        _spell: CardId,
+        targets: &[CardId],
+        total: u32,
+    ) -> SmallVec<[u32; 4]> {
+        // Everything but 1 per other target goes to the first target
+        let rest = targets.len() as u32 - 1;
+        std::iter::once(total - rest).chain((0..rest).map(|_| 1)).collect()
+    }
+
     fn choose_mana_sources_to_pay(
         &mut self,
         _view: &GameStateView,
@@ -73,9 +107,25 @@ impl PlayerController for ZeroController {
         available_sources.iter().take(needed).copied().collect()
     }
 
-    fn choose_attackers(&mut self, _view: &GameStateView, available_creatures: &[CardId]) -> SmallVec<[CardId; 8]> {
-        // Attack with all available creatures
-        available_creatures.iter().copied().collect()
+    fn choose_mana_color(&mut self, _view: &GameStateView, _source: CardId, colors: &[Color]) -> Color {
+        // Always choose the first color
+        colors[0]
+    }
+
+    fn choose_attackers(
+        &mut self,
+        _view: &GameStateView,
+        available_creatures: &[CardId],
+        defenders: &[AttackTarget],
+    ) -> SmallVec<[(CardId, AttackTarget); 8]> {
+        // Attack the defending player with all available creatures
+        let Some(&defender) = defenders.first() else {
+            return SmallVec::new();
+        };
+        available_creatures
+            .iter()
+            .map(|&creature| (creature, defender))
+            .collect()
     }
 
     fn choose_blockers(
@@ -109,6 +159,48 @@ impl PlayerController for ZeroController {
         blockers.iter().copied().collect()
     }
 
+    fn choose_trigger_order(&mut self, _view: &GameStateView, triggers: &[StackAbility]) -> SmallVec<[usize; 4]> {
+        // Keep triggers in the order they triggered
+        (0..triggers.len()).collect()
+    }
+
+    fn choose_replacement_effect(&mut self, _view: &GameStateView, effects: &[ReplacementKey]) -> ReplacementKey {
+        // Always apply the oldest one first (timestamp order)
+        effects[0]
+    }
+
+    fn choose_legend_to_keep(&mut self, _view: &GameStateView, legends: &[CardId]) -> CardId {
+        // Always keep the first one
+        legends[0]
+    }
+
+    fn choose_mulligan(&mut self, _view: &GameStateView, _hand: &[CardId], _mulligans_taken: u8) -> bool {
+        // Always keep
+        false
+    }
+
+    fn choose_cards_to_bottom(
+        &mut self,
+        _view: &GameStateView,
+        hand: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 7]> {
+        // Put the first N cards from hand on the bottom
+        hand.iter().take(count.min(hand.len())).copied().collect()
+    }
+
+    fn choose_cards_for_cost(
+        &mut self,
+        _view: &GameStateView,
+        _source: CardId,
+        _cost: &Cost,
+        candidates: &[CardId],
+        count: usize,
+    ) -> SmallVec<[CardId; 8]> {
+        // Always choose the first N candidates
+        candidates.iter().take(count).copied().collect()
+    }
+
     fn choose_cards_to_discard(
         &mut self,
         _view: &GameStateView,
@@ -248,13 +340,14 @@ mod tests {
         let view = GameStateView::new(&game, player_id);
 
         let creatures = vec![EntityId::new(30), EntityId::new(31), EntityId::new(32)];
-        let attackers = controller.choose_attackers(&view, &creatures);
+        let bob = AttackTarget::Player(EntityId::new(2));
+        let attackers = controller.choose_attackers(&view, &creatures, &[bob]);
 
-        // Should attack with all creatures
+        // Should attack the first defender with all creatures
         assert_eq!(attackers.len(), 3);
-        assert_eq!(attackers[0], EntityId::new(30));
-        assert_eq!(attackers[1], EntityId::new(31));
-        assert_eq!(attackers[2], EntityId::new(32));
+        assert_eq!(attackers[0], (EntityId::new(30), bob));
+        assert_eq!(attackers[1], (EntityId::new(31), bob));
+        assert_eq!(attackers[2], (EntityId::new(32), bob));
     }
 
     #[test]
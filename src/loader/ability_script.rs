@@ -0,0 +1,1738 @@
+//! Structured parser for Forge ability scripts
+//!
+//! Forge describes abilities as `|`-separated lists of `Key$ Value` parameters.
+//! Spells and activated abilities live on `A:` lines, triggers on `T:` lines,
+//! and follow-up abilities are stored in `SVar:` lines that are referenced by
+//! name through `SubAbility$` (chained effects) or `Execute$` (trigger bodies):
+//!
+//! ```text
+//! A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ 3 | SubAbility$ DBGainLife | SpellDescription$ ...
+//! SVar:DBGainLife:DB$ GainLife | Defined$ You | LifeAmount$ 3
+//! ```
+//!
+//! Static abilities live on `S:` lines; the continuous ones (`Mode$ Continuous`)
+//! become [`StaticAbility`]s:
+//!
+//! ```text
+//! S:Mode$ Continuous | Affected$ Creature.YouCtrl | AddPower$ 1 | AddToughness$ 1 | Description$ ...
+//! ```
+//!
+//! Modal spells use the `Charm` API, whose `Choices$` names one SVar per mode,
+//! and amounts paid for with X name an SVar defined as `Count$xPaid`:
+//!
+//! ```text
+//! A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ X | SpellDescription$ ...
+//! SVar:X:Count$xPaid
+//! ```
+//!
+//! Replacement effects live on `R:` lines; what happens instead of the event is
+//! the ability named by `ReplaceWith$`. Spells like Fog create replacement
+//! effects through the `Effect` API, whose `ReplacementEffects$` names SVars
+//! written like `R:` lines:
+//!
+//! ```text
+//! R:Event$ Moved | ValidCard$ Card.Self | Destination$ Battlefield | ReplaceWith$ ETBTapped | Description$ ...
+//! SVar:ETBTapped:DB$ Tap | Defined$ Self | ETB$ True
+//! ```
+//!
+//! [`CardScript::parse`] turns those lines into a typed tree of [`AbilityScript`]
+//! nodes. Parameters that the engine does not understand are kept on the node and
+//! reported as [`ScriptDiagnostic`]s instead of being silently dropped.
+
+use crate::core::selector::{parse_card_type, parse_color};
+use crate::core::{
+    CardId, Color, ContinuousModification, Cost, CounterType, Effect, Keyword, ManaCost, PlayerId, ReplacementAction,
+    ReplacementEffect, ReplacementEvent, Selector, SpellMode, StaticAbility, Subtype, TargetRef, TokenDefinition,
+    AMOUNT_X, COUNT_ANY, COUNT_X,
+};
+use crate::zones::Zone;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Maximum depth of `SubAbility$`/`Execute$` chains before we assume a cycle
+const MAX_CHAIN_DEPTH: usize = 32;
+
+/// The kind of ability a script declares, taken from its leading parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbilityKind {
+    /// `SP$` - the ability of a spell (instants, sorceries, permanent spells)
+    Spell,
+    /// `AB$` - an activated ability of a permanent
+    Activated,
+    /// `DB$` - a sub-ability reached through `SubAbility$` or `Execute$`
+    Sub,
+}
+
+impl AbilityKind {
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "SP" => Some(AbilityKind::Spell),
+            "AB" => Some(AbilityKind::Activated),
+            "DB" => Some(AbilityKind::Sub),
+            _ => None,
+        }
+    }
+
+    /// The script prefix for this kind (e.g. "SP")
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AbilityKind::Spell => "SP",
+            AbilityKind::Activated => "AB",
+            AbilityKind::Sub => "DB",
+        }
+    }
+}
+
+/// Forge API type of an ability (the value of `SP$`/`AB$`/`DB$`)
+///
+/// Only the APIs the engine knows how to execute get their own variant;
+/// everything else is preserved in `Other` so it can be reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiType {
+    DealDamage,
+    Draw,
+    Destroy,
+    DestroyAll,
+    GainLife,
+    Pump,
+    PumpAll,
+    Tap,
+    Untap,
+    Mill,
+    Counter,
+    ChangeZone,
+    Mana,
+    Token,
+    PutCounter,
+    /// A modal spell; its modes are in [`AbilityScript::modes`]
+    Charm,
+    /// Creates an effect; its replacement effects are in [`AbilityScript::replacements`]
+    Effect,
+    /// Attaches an Aura or Equipment (the Aura spell itself, or equip)
+    Attach,
+    Other(String),
+}
+
+impl ApiType {
+    /// Parse an API name as it appears in card scripts
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "DealDamage" => ApiType::DealDamage,
+            "Draw" => ApiType::Draw,
+            "Destroy" => ApiType::Destroy,
+            "DestroyAll" => ApiType::DestroyAll,
+            "GainLife" => ApiType::GainLife,
+            "Pump" => ApiType::Pump,
+            "PumpAll" => ApiType::PumpAll,
+            "Tap" => ApiType::Tap,
+            "Untap" => ApiType::Untap,
+            "Mill" => ApiType::Mill,
+            "Counter" => ApiType::Counter,
+            "ChangeZone" => ApiType::ChangeZone,
+            "Mana" => ApiType::Mana,
+            "Token" => ApiType::Token,
+            "PutCounter" => ApiType::PutCounter,
+            "Charm" => ApiType::Charm,
+            "Effect" => ApiType::Effect,
+            "Attach" => ApiType::Attach,
+            other => ApiType::Other(other.to_string()),
+        }
+    }
+
+    /// The API name as it appears in card scripts
+    pub fn as_str(&self) -> &str {
+        match self {
+            ApiType::DealDamage => "DealDamage",
+            ApiType::Draw => "Draw",
+            ApiType::Destroy => "Destroy",
+            ApiType::DestroyAll => "DestroyAll",
+            ApiType::GainLife => "GainLife",
+            ApiType::Pump => "Pump",
+            ApiType::PumpAll => "PumpAll",
+            ApiType::Tap => "Tap",
+            ApiType::Untap => "Untap",
+            ApiType::Mill => "Mill",
+            ApiType::Counter => "Counter",
+            ApiType::ChangeZone => "ChangeZone",
+            ApiType::Mana => "Mana",
+            ApiType::Token => "Token",
+            ApiType::PutCounter => "PutCounter",
+            ApiType::Charm => "Charm",
+            ApiType::Effect => "Effect",
+            ApiType::Attach => "Attach",
+            ApiType::Other(name) => name,
+        }
+    }
+
+    /// Parameters understood by this specific API (in addition to [`COMMON_PARAMS`])
+    fn known_params(&self) -> &'static [&'static str] {
+        match self {
+            ApiType::DealDamage => &[
+                "NumDmg",
+                "DamageSource",
+                "DividedAsYouChoose",
+                "DivideEvenly",
+                "NoPrevention",
+            ],
+            ApiType::Draw => &["NumCards"],
+            ApiType::Destroy => &["NoRegen", "Sacrifice"],
+            ApiType::DestroyAll => &["ValidCards", "NoRegen"],
+            ApiType::GainLife => &["LifeAmount"],
+            ApiType::Pump => &["NumAtt", "NumDef", "KW", "Duration"],
+            ApiType::PumpAll => &["ValidCards", "NumAtt", "NumDef", "KW", "Duration"],
+            ApiType::Tap => &["ETB"],
+            ApiType::Untap => &[],
+            ApiType::Mill => &["NumCards"],
+            ApiType::Counter => &["Destination"],
+            ApiType::ChangeZone => &[
+                "Origin",
+                "Destination",
+                "ChangeType",
+                "ChangeNum",
+                "Hidden",
+                "Tapped",
+                "Shuffle",
+            ],
+            ApiType::Mana => &["Produced", "Amount", "RestrictValid"],
+            ApiType::Token => &[
+                "TokenScript",
+                "TokenAmount",
+                "TokenOwner",
+                "TokenTapped",
+                "RememberTokens",
+            ],
+            ApiType::PutCounter => &["CounterType", "CounterNum", "DividedAsYouChoose"],
+            ApiType::Charm => &["Choices", "CharmNum", "MinCharmNum", "CanRepeatModes"],
+            ApiType::Effect => &["ReplacementEffects", "Name", "Image", "Duration"],
+            ApiType::Attach => &["Object"],
+            ApiType::Other(_) => &[],
+        }
+    }
+}
+
+impl fmt::Display for ApiType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Parameters shared by every ability regardless of API
+const COMMON_PARAMS: &[&str] = &[
+    "Cost",
+    "ValidTgts",
+    "TgtPrompt",
+    "TargetType",
+    "TargetMin",
+    "TargetMax",
+    "TgtZone",
+    "TargetUnique",
+    "Defined",
+    "SubAbility",
+    "SpellDescription",
+    "StackDescription",
+    "PrecostDesc",
+    "CostDesc",
+    "IsCurse",
+    "NonBasicSpell",
+    "ActivationLimit",
+    "ActivationZone",
+    "SorcerySpeed",
+    "InstantSpeed",
+    "Activator",
+    "PlayerTurn",
+    "OptionalDecider",
+    "Planeswalker",
+    "Ultimate",
+];
+
+/// Parameter prefixes that are bookkeeping for AI hints, conditions and memory
+const COMMON_PARAM_PREFIXES: &[&str] = &["AI", "Condition", "Unless", "Remember", "Imprint", "Forget"];
+
+/// Parameters understood on `T:` lines
+const TRIGGER_PARAMS: &[&str] = &[
+    "Mode",
+    "Origin",
+    "Destination",
+    "ValidCard",
+    "ValidPlayer",
+    "ValidSource",
+    "ValidTarget",
+    "Phase",
+    "Execute",
+    "TriggerZones",
+    "TriggerDescription",
+    "OptionalDecider",
+    "CheckSVar",
+    "SVarCompare",
+    "IsPresent",
+    "PresentCompare",
+    "CombatDamage",
+    "Secondary",
+    "Static",
+];
+
+/// Parameters understood on `R:` lines
+const REPLACEMENT_PARAMS: &[&str] = &[
+    "Event",
+    "ActiveZones",
+    "ValidCard",
+    "ValidTarget",
+    "ValidSource",
+    "ValidPlayer",
+    "Origin",
+    "Destination",
+    "ReplaceWith",
+    "Prevent",
+    "PreventionEffect",
+    "IsCombat",
+    "ReplacementResult",
+    "Layer",
+    "Description",
+    "Secondary",
+];
+
+/// Parameters understood on `S:Mode$ Continuous` lines
+const CONTINUOUS_STATIC_PARAMS: &[&str] = &[
+    "Mode",
+    "Affected",
+    "AddPower",
+    "AddToughness",
+    "SetPower",
+    "SetToughness",
+    "AddKeyword",
+    "RemoveKeyword",
+    "RemoveAllAbilities",
+    "AddType",
+    "RemoveType",
+    "AddColor",
+    "SetColor",
+    "GainControl",
+    "Description",
+];
+
+fn is_known_param(api: &ApiType, key: &str) -> bool {
+    COMMON_PARAMS.contains(&key)
+        || api.known_params().contains(&key)
+        || COMMON_PARAM_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+}
+
+/// A problem found while parsing a card's scripts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptDiagnostic {
+    /// A parameter the engine does not interpret for this API
+    UnknownParameter { api: String, param: String },
+    /// An API the engine cannot execute yet
+    UnsupportedApi { api: String },
+    /// A `SubAbility$`/`Execute$` reference to an SVar that does not exist
+    MissingSVar { name: String },
+    /// A `SubAbility$`/`Execute$` chain that refers back to itself
+    CyclicReference { name: String },
+    /// A script segment that is not a `Key$ Value` pair
+    MalformedParameter { text: String },
+    /// A script line without a recognizable `SP$`/`AB$`/`DB$`/`Mode$` header
+    MissingHeader { line: String },
+    /// A static ability mode the engine cannot apply yet (anything but `Continuous`
+    /// and a spell's cost for extra targets)
+    UnsupportedStaticMode { mode: String },
+    /// A replacement event the engine cannot replace yet
+    UnsupportedReplacementEvent { event: String },
+    /// A `ValidTgts$`/`ValidCards$`/`Affected$` property the selector compiler does not understand
+    UnknownSelectorProperty { selector: String, property: String },
+}
+
+impl fmt::Display for ScriptDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptDiagnostic::UnknownParameter { api, param } => {
+                write!(f, "unknown parameter '{param}$' for {api}")
+            }
+            ScriptDiagnostic::UnsupportedApi { api } => write!(f, "unsupported ability API '{api}'"),
+            ScriptDiagnostic::UnsupportedStaticMode { mode } => write!(f, "unsupported static ability mode '{mode}'"),
+            ScriptDiagnostic::UnsupportedReplacementEvent { event } => {
+                write!(f, "unsupported replacement event '{event}'")
+            }
+            ScriptDiagnostic::MissingSVar { name } => write!(f, "reference to missing SVar '{name}'"),
+            ScriptDiagnostic::CyclicReference { name } => write!(f, "cyclic reference through SVar '{name}'"),
+            ScriptDiagnostic::MalformedParameter { text } => {
+                write!(f, "malformed parameter '{text}' (expected 'Key$ Value')")
+            }
+            ScriptDiagnostic::MissingHeader { line } => write!(f, "script line has no ability header: '{line}'"),
+            ScriptDiagnostic::UnknownSelectorProperty { selector, property } => {
+                write!(f, "unknown selector property '{property}' in '{selector}'")
+            }
+        }
+    }
+}
+
+/// Split a script body into `(key, value)` pairs
+///
+/// Segments are separated by `|`; each segment is split at its first `$`.
+fn split_params(body: &str, diagnostics: &mut Vec<ScriptDiagnostic>) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    for segment in body.split('|') {
+        let segment = segment.trim();
+        if segment.is_empty() {
+            continue;
+        }
+        match segment.split_once('$') {
+            Some((key, value)) => params.push((key.trim().to_string(), value.trim().to_string())),
+            None => diagnostics.push(ScriptDiagnostic::MalformedParameter {
+                text: segment.to_string(),
+            }),
+        }
+    }
+    params
+}
+
+fn find_param<'a>(params: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+/// A single parsed ability with its chained sub-abilities
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbilityScript {
+    /// Whether this is a spell, activated ability or sub-ability
+    pub kind: AbilityKind,
+    /// The ability's API (what it does)
+    pub api: ApiType,
+    /// All remaining `Key$ Value` parameters in script order
+    params: Vec<(String, String)>,
+    /// The ability referenced by `SubAbility$`, if any
+    pub sub_ability: Option<Box<AbilityScript>>,
+    /// For `Charm`, the abilities named by `Choices$`, one per mode
+    pub modes: Vec<AbilityScript>,
+    /// For `Effect`, the replacement effects named by `ReplacementEffects$`
+    pub replacements: Vec<ReplacementScript>,
+    /// Keys of parameters whose value is the X paid for the spell (`Count$xPaid`)
+    x_params: Vec<String>,
+}
+
+impl AbilityScript {
+    /// Get a parameter value by key (without the trailing `$`)
+    pub fn param(&self, key: &str) -> Option<&str> {
+        find_param(&self.params, key)
+    }
+
+    /// Check whether a parameter is present
+    pub fn has_param(&self, key: &str) -> bool {
+        self.param(key).is_some()
+    }
+
+    /// All parameters of this node in script order
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Iterate over this ability followed by each sub-ability in the chain
+    pub fn chain(&self) -> impl Iterator<Item = &AbilityScript> {
+        std::iter::successors(Some(self), |node| node.sub_ability.as_deref())
+    }
+
+    /// The `SpellDescription$` text, if any
+    pub fn description(&self) -> Option<&str> {
+        self.param("SpellDescription")
+    }
+
+    /// Parse the `Cost$` parameter
+    pub fn cost(&self) -> Option<Cost> {
+        self.param("Cost").and_then(Cost::parse)
+    }
+
+    /// The targeting restriction of this chain (the first `ValidTgts$`), compiled
+    pub fn target_restriction(&self) -> Option<Selector> {
+        self.chain()
+            .find_map(|node| node.param("ValidTgts"))
+            .map(Selector::parse)
+    }
+
+    /// How many targets this chain takes (`TargetMin$`/`TargetMax$` of its targeting node)
+    ///
+    /// Both default to 1. A count naming an SVar takes its value if that's a
+    /// number, and the X paid ([`COUNT_X`]) if it's `Count$xPaid`. A maximum
+    /// computed from the game, like Fireball's count of players and creatures,
+    /// is any number ([`COUNT_ANY`]): that count is every target there could be.
+    pub fn target_count(&self) -> (u8, u8) {
+        let Some(node) = self.chain().find(|node| node.has_param("ValidTgts")) else {
+            return (1, 1);
+        };
+        let count = |key, computed| {
+            if node.is_x_param(key) {
+                COUNT_X
+            } else {
+                node.param(key).map_or(1, |v| v.parse::<u8>().unwrap_or(computed))
+            }
+        };
+        let max = count("TargetMax", COUNT_ANY);
+        (count("TargetMin", 1).min(max), max)
+    }
+
+    /// Whether an amount in this chain is divided among its targets as the caster chooses
+    /// (`DividedAsYouChoose$`)
+    pub fn is_divided(&self) -> bool {
+        self.chain().any(|node| node.has_param("DividedAsYouChoose"))
+    }
+
+    /// Whether an amount in this chain is divided evenly, rounded down, among its targets
+    /// (`DivideEvenly$`)
+    pub fn is_divided_evenly(&self) -> bool {
+        self.chain().any(|node| node.has_param("DivideEvenly"))
+    }
+
+    /// Whether this is a mana ability (`AB$ Mana`); sub-abilities don't count
+    pub fn is_mana_ability(&self) -> bool {
+        self.api == ApiType::Mana
+    }
+
+    /// Whether a parameter's value is the X paid for the spell (e.g. "NumDmg$ X")
+    pub fn is_x_param(&self, key: &str) -> bool {
+        self.x_params.iter().any(|k| k == key)
+    }
+
+    /// The minimum and maximum number of modes to choose for a `Charm`
+    ///
+    /// `CharmNum$` defaults to 1, and `MinCharmNum$` to `CharmNum$`.
+    pub fn mode_count(&self) -> (u8, u8) {
+        let max = self.param("CharmNum").and_then(|v| v.parse::<u8>().ok()).unwrap_or(1);
+        let min = self
+            .param("MinCharmNum")
+            .and_then(|v| v.parse::<u8>().ok())
+            .unwrap_or(max);
+        (min.min(max), max)
+    }
+
+    /// The modes of a `Charm` in engine form
+    pub fn spell_modes(&self) -> Vec<SpellMode> {
+        self.modes
+            .iter()
+            .map(|mode| SpellMode {
+                description: mode.description().unwrap_or(mode.api.as_str()).to_string(),
+                effects: mode.to_effects(),
+                target_restriction: mode.target_restriction(),
+            })
+            .collect()
+    }
+
+    /// Parse an integer parameter, accepting a leading '+' (e.g. "NumAtt$ +3")
+    ///
+    /// X parameters give [`AMOUNT_X`], to be replaced when the spell is cast.
+    fn int_param(&self, key: &str) -> Option<i32> {
+        if self.is_x_param(key) {
+            return Some(AMOUNT_X);
+        }
+        self.param(key)
+            .and_then(|v| v.trim_start_matches('+').parse::<i32>().ok())
+    }
+
+    /// Parse a count parameter that defaults to 1 (e.g. "NumCards$ 2")
+    ///
+    /// X parameters give [`COUNT_X`]; values that aren't numbers give `None`.
+    fn count_param(&self, key: &str) -> Option<u8> {
+        if self.is_x_param(key) {
+            return Some(COUNT_X);
+        }
+        match self.param(key) {
+            Some(v) => v.parse::<u8>().ok(),
+            None => Some(1),
+        }
+    }
+
+    /// Convert the whole chain into engine effects
+    ///
+    /// Targets and players are filled with placeholder IDs (0) that are
+    /// resolved when the spell or ability resolves. Nodes whose API the
+    /// engine cannot execute produce no effect.
+    pub fn to_effects(&self) -> Vec<Effect> {
+        self.chain().filter_map(|node| node.node_effect()).collect()
+    }
+
+    /// Token script names referenced by `TokenScript$` anywhere in this chain or its modes
+    pub fn token_scripts(&self) -> Vec<&str> {
+        self.chain()
+            .filter_map(|node| node.param("TokenScript"))
+            .flat_map(|scripts| scripts.split(',').map(str::trim))
+            .chain(self.modes.iter().flat_map(|mode| mode.token_scripts()))
+            .collect()
+    }
+
+    /// Convert just this node (not its sub-abilities) into an effect
+    fn node_effect(&self) -> Option<Effect> {
+        let placeholder_card = CardId::new(0);
+        let placeholder_player = PlayerId::new(0);
+
+        match self.api {
+            ApiType::DealDamage => self.int_param("NumDmg").map(|amount| Effect::DealDamage {
+                target: TargetRef::None,
+                amount,
+            }),
+            ApiType::Draw => Some(Effect::DrawCards {
+                player: placeholder_player,
+                count: self.count_param("NumCards")?,
+            }),
+            ApiType::Destroy => Some(Effect::DestroyPermanent {
+                target: placeholder_card,
+            }),
+            ApiType::DestroyAll => Some(Effect::DestroyAll {
+                selector: Selector::parse(self.param("ValidCards")?),
+                controller: placeholder_player,
+            }),
+            ApiType::PumpAll => {
+                let power_bonus = self.int_param("NumAtt").unwrap_or(0);
+                let toughness_bonus = self.int_param("NumDef").unwrap_or(0);
+                if power_bonus == 0 && toughness_bonus == 0 {
+                    return None;
+                }
+                Some(Effect::PumpAll {
+                    selector: Selector::parse(self.param("ValidCards")?),
+                    controller: placeholder_player,
+                    power_bonus,
+                    toughness_bonus,
+                })
+            }
+            ApiType::GainLife => self.int_param("LifeAmount").map(|amount| Effect::GainLife {
+                player: placeholder_player,
+                amount,
+            }),
+            ApiType::Pump => {
+                let power_bonus = self.int_param("NumAtt").unwrap_or(0);
+                let toughness_bonus = self.int_param("NumDef").unwrap_or(0);
+                // TODO: Apply KW$ (granted keywords) once PumpCreature supports it
+                if power_bonus == 0 && toughness_bonus == 0 {
+                    return None;
+                }
+                Some(Effect::PumpCreature {
+                    target: placeholder_card,
+                    power_bonus,
+                    toughness_bonus,
+                })
+            }
+            ApiType::Tap => Some(Effect::TapPermanent {
+                target: placeholder_card,
+            }),
+            ApiType::Untap => Some(Effect::UntapPermanent {
+                target: placeholder_card,
+            }),
+            ApiType::Mill => Some(Effect::Mill {
+                player: placeholder_player,
+                count: self.count_param("NumCards")?,
+            }),
+            ApiType::Counter => Some(Effect::CounterSpell {
+                target: placeholder_card,
+            }),
+            ApiType::ChangeZone => {
+                // Only "exile target permanent" is supported so far (Swords to Plowshares)
+                if self.param("Origin") == Some("Battlefield") && self.param("Destination") == Some("Exile") {
+                    Some(Effect::ExilePermanent {
+                        target: placeholder_card,
+                    })
+                } else {
+                    None
+                }
+            }
+            ApiType::Mana => {
+                let produced = self.param("Produced")?;
+                let amount = self.param("Amount").and_then(|v| v.parse::<u8>().ok()).unwrap_or(1);
+                // "G" or "C" (fixed mana), "Combo W U" (one of several colors), "Any" (any color)
+                let choices = if produced == "Any" {
+                    Some("W U B R G")
+                } else {
+                    produced.strip_prefix("Combo")
+                };
+                match choices.map(|c| c.trim()) {
+                    Some(choices) => {
+                        let choices = if choices == "Any" { "W U B R G" } else { choices };
+                        let colors: Vec<Color> = choices
+                            .split_whitespace()
+                            .filter_map(|symbol| match symbol {
+                                "W" => Some(Color::White),
+                                "U" => Some(Color::Blue),
+                                "B" => Some(Color::Black),
+                                "R" => Some(Color::Red),
+                                "G" => Some(Color::Green),
+                                "C" => Some(Color::Colorless),
+                                _ => None,
+                            })
+                            .collect();
+                        if colors.is_empty() {
+                            return None;
+                        }
+                        Some(Effect::AddManaOfChoice {
+                            player: placeholder_player,
+                            colors,
+                            amount,
+                        })
+                    }
+                    None => Some(Effect::AddMana {
+                        player: placeholder_player,
+                        mana: ManaCost::from_string(produced).multiply(amount),
+                    }),
+                }
+            }
+            ApiType::Token => {
+                // Only the first script of a comma-separated TokenScript$ list is created
+                let script = self.param("TokenScript")?.split(',').next()?.trim();
+                let amount = self.count_param("TokenAmount")?;
+                // TokenOwner$ defaults to You; other owners aren't supported yet
+                if self.param("TokenOwner").is_some_and(|owner| owner != "You") {
+                    return None;
+                }
+                Some(Effect::CreateToken {
+                    owner: placeholder_player,
+                    amount,
+                    token: TokenDefinition::from_script_name(script),
+                })
+            }
+            ApiType::PutCounter => Some(Effect::PutCounter {
+                target: placeholder_card,
+                counter_type: CounterType::parse(self.param("CounterType")?)?,
+                amount: self.count_param("CounterNum")?,
+            }),
+            ApiType::Effect => {
+                let effects: Vec<ReplacementEffect> = self
+                    .replacements
+                    .iter()
+                    .filter_map(ReplacementScript::to_replacement_effect)
+                    .collect();
+                if effects.is_empty() {
+                    return None;
+                }
+                Some(Effect::CreateReplacementEffects {
+                    controller: placeholder_player,
+                    effects,
+                })
+            }
+            ApiType::Attach => Some(Effect::Attach {
+                attachment: placeholder_card,
+                target: placeholder_card,
+            }),
+            // The effects of a modal spell come from the modes chosen when it's cast
+            ApiType::Charm | ApiType::Other(_) => None,
+        }
+    }
+}
+
+/// A parsed triggered ability (`T:` line)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerScript {
+    /// Trigger mode (e.g. "ChangesZone", "Phase", "Attacks")
+    pub mode: String,
+    /// Remaining trigger parameters in script order
+    params: Vec<(String, String)>,
+    /// The ability referenced by `Execute$`, if it could be resolved
+    pub execute: Option<AbilityScript>,
+}
+
+impl TriggerScript {
+    /// Get a parameter value by key (without the trailing `$`)
+    pub fn param(&self, key: &str) -> Option<&str> {
+        find_param(&self.params, key)
+    }
+
+    /// The `TriggerDescription$` text, if any
+    pub fn description(&self) -> Option<&str> {
+        self.param("TriggerDescription")
+    }
+
+    /// Effects produced by the executed ability chain
+    pub fn to_effects(&self) -> Vec<Effect> {
+        self.execute.as_ref().map(|ab| ab.to_effects()).unwrap_or_default()
+    }
+
+    /// The targeting restriction of the executed ability chain, compiled
+    pub fn target_restriction(&self) -> Option<Selector> {
+        self.execute.as_ref().and_then(|ab| ab.target_restriction())
+    }
+}
+
+/// A parsed replacement effect (`R:` line, or an SVar named by `ReplacementEffects$`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplacementScript {
+    /// Replaced event (e.g. "Moved", "DamageDone")
+    pub event: String,
+    /// Remaining parameters in script order
+    params: Vec<(String, String)>,
+    /// The ability referenced by `ReplaceWith$`, if it could be resolved
+    pub replace_with: Option<AbilityScript>,
+}
+
+impl ReplacementScript {
+    /// Get a parameter value by key (without the trailing `$`)
+    pub fn param(&self, key: &str) -> Option<&str> {
+        find_param(&self.params, key)
+    }
+
+    /// The `Description$` text, if any
+    pub fn description(&self) -> Option<&str> {
+        self.param("Description")
+    }
+
+    /// The replacement effect in engine form, if the engine can apply it
+    ///
+    /// Zone lists like `Origin$ Hand,Library` match any zone.
+    pub fn to_replacement_effect(&self) -> Option<ReplacementEffect> {
+        let zone = |key: &str| self.param(key).and_then(Zone::parse);
+        let event = match self.event.as_str() {
+            "Moved" => ReplacementEvent::Moved {
+                origin: zone("Origin"),
+                destination: zone("Destination"),
+            },
+            "DamageDone" => ReplacementEvent::DamageDone {
+                combat_only: self.param("IsCombat") == Some("True"),
+            },
+            "Draw" => ReplacementEvent::Draw,
+            "GainLife" => ReplacementEvent::GainLife,
+            "LifeReduced" => ReplacementEvent::LoseLife,
+            _ => return None,
+        };
+
+        // A shield that exiles itself ("Defined$ Self | Origin$ Command") is used up once it applies
+        let replace_with = self.replace_with.as_ref();
+        let single_use = replace_with.is_some_and(|ab| {
+            ab.api == ApiType::ChangeZone
+                && ab.param("Defined") == Some("Self")
+                && ab.param("Origin") == Some("Command")
+        });
+
+        let prevents = |key| self.param(key) == Some("True");
+        let action = if prevents("Prevent") || prevents("PreventionEffect") {
+            ReplacementAction::Prevent
+        } else {
+            let ab = replace_with?;
+            match ab.api {
+                ApiType::Tap if ab.param("ETB") == Some("True") => ReplacementAction::EnterTapped,
+                ApiType::ChangeZone => ReplacementAction::ChangeZone(ab.param("Destination").and_then(Zone::parse)?),
+                _ => {
+                    let effects = ab.to_effects();
+                    if effects.is_empty() {
+                        return None;
+                    }
+                    ReplacementAction::Instead(effects)
+                }
+            }
+        };
+
+        Some(ReplacementEffect {
+            event,
+            affected: ["ValidCard", "ValidTarget", "ValidPlayer"]
+                .into_iter()
+                .find_map(|key| self.param(key))
+                .map(Selector::parse),
+            valid_source: self.param("ValidSource").map(Selector::parse),
+            action,
+            single_use,
+            description: self.description().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// A parsed static ability (`S:` line)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticScript {
+    /// Static mode (e.g. "Continuous", "CantAttack")
+    pub mode: String,
+    /// Remaining parameters in script order
+    params: Vec<(String, String)>,
+}
+
+impl StaticScript {
+    /// Get a parameter value by key (without the trailing `$`)
+    pub fn param(&self, key: &str) -> Option<&str> {
+        find_param(&self.params, key)
+    }
+
+    /// The `Description$` text, if any
+    pub fn description(&self) -> Option<&str> {
+        self.param("Description")
+    }
+
+    /// The generic mana a spell costs more for each target beyond the first, if
+    /// this is such a cost increase (Fireball's `Mode$ RaiseCost | ValidCard$ Card.Self
+    /// | Type$ Spell`)
+    ///
+    /// Its `Amount$` has to count the spell's targets, minus the first.
+    pub fn extra_target_cost(&self) -> Option<u8> {
+        if self.mode != "RaiseCost"
+            || self.param("ValidCard") != Some("Card.Self")
+            || self.param("Type") != Some("Spell")
+        {
+            return None;
+        }
+        let amount = self.param("Amount")?;
+        (amount.contains("Target") && amount.ends_with("/Minus.1")).then_some(1)
+    }
+
+    /// The continuous effect this ability generates, if it's a `Continuous` one
+    ///
+    /// Values we can't interpret (like `AddPower$ X`) are left out.
+    pub fn to_static_ability(&self) -> Option<StaticAbility> {
+        if self.mode != "Continuous" {
+            return None;
+        }
+        let number = |key: &str| {
+            self.param(key)
+                .and_then(|v| v.trim_start_matches('+').parse::<i32>().ok())
+        };
+        let list = |key: &str| {
+            self.param(key)
+                .map(|v| {
+                    v.split('&')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut modifications = Vec::new();
+        if self.param("GainControl") == Some("You") {
+            modifications.push(ContinuousModification::GainControl);
+        }
+        for name in list("AddType") {
+            modifications.push(match parse_card_type(name) {
+                Some(card_type) => ContinuousModification::AddType(card_type),
+                None => ContinuousModification::AddSubtype(Subtype::new(name)),
+            });
+        }
+        modifications.extend(
+            list("RemoveType")
+                .into_iter()
+                .filter_map(parse_card_type)
+                .map(ContinuousModification::RemoveType),
+        );
+        let set_colors: smallvec::SmallVec<[_; 2]> = list("SetColor").into_iter().filter_map(parse_color).collect();
+        if !set_colors.is_empty() {
+            modifications.push(ContinuousModification::SetColors(set_colors));
+        }
+        modifications.extend(
+            list("AddColor")
+                .into_iter()
+                .filter_map(parse_color)
+                .map(ContinuousModification::AddColor),
+        );
+        if self.param("RemoveAllAbilities") == Some("True") {
+            modifications.push(ContinuousModification::RemoveAllKeywords);
+        }
+        for (key, add) in [("AddKeyword", true), ("RemoveKeyword", false)] {
+            for name in list(key) {
+                let keyword = Keyword::from_name(name).unwrap_or_else(|| Keyword::Other(name.to_string()));
+                modifications.push(if add {
+                    ContinuousModification::AddKeyword(keyword)
+                } else {
+                    ContinuousModification::RemoveKeyword(keyword)
+                });
+            }
+        }
+        if let Some(power) = number("SetPower").and_then(|p| i8::try_from(p).ok()) {
+            modifications.push(ContinuousModification::SetPower(power));
+        }
+        if let Some(toughness) = number("SetToughness").and_then(|t| i8::try_from(t).ok()) {
+            modifications.push(ContinuousModification::SetToughness(toughness));
+        }
+        if let Some(power) = number("AddPower") {
+            modifications.push(ContinuousModification::AddPower(power));
+        }
+        if let Some(toughness) = number("AddToughness") {
+            modifications.push(ContinuousModification::AddToughness(toughness));
+        }
+
+        if modifications.is_empty() {
+            return None;
+        }
+        Some(StaticAbility {
+            affected: Selector::parse(self.param("Affected").unwrap_or("Card.Self")),
+            modifications,
+            description: self.description().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// All parsed scripts of a card
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CardScript {
+    /// Spell abilities (`A:SP$ ...`), in script order
+    pub spells: Vec<AbilityScript>,
+    /// Activated abilities (`A:AB$ ...`), in script order
+    pub activated: Vec<AbilityScript>,
+    /// Triggered abilities (`T:...`), in script order
+    pub triggers: Vec<TriggerScript>,
+    /// Static abilities (`S:...`), in script order
+    pub statics: Vec<StaticScript>,
+    /// Replacement effects (`R:...`), in script order
+    pub replacements: Vec<ReplacementScript>,
+    /// Unknown parameters, unresolved references and other problems
+    pub diagnostics: Vec<ScriptDiagnostic>,
+}
+
+impl CardScript {
+    /// Parse a card's ability lines together with its SVars
+    ///
+    /// `raw_abilities` holds lines with their prefix (e.g. `"A:SP$ Draw | ..."`);
+    /// `svars` holds `(name, body)` pairs from `SVar:` lines.
+    pub fn parse(raw_abilities: &[String], svars: &[(String, String)]) -> Self {
+        let svar_map: HashMap<&str, &str> = svars.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        let mut parser = Parser {
+            svars: svar_map,
+            diagnostics: Vec::new(),
+        };
+        let mut script = CardScript::default();
+
+        for line in raw_abilities {
+            if let Some(body) = line.strip_prefix("A:") {
+                let mut stack = Vec::new();
+                if let Some(ability) = parser.parse_ability(body, &mut stack) {
+                    match ability.kind {
+                        AbilityKind::Activated => script.activated.push(ability),
+                        AbilityKind::Spell | AbilityKind::Sub => script.spells.push(ability),
+                    }
+                }
+            } else if let Some(body) = line.strip_prefix("T:") {
+                if let Some(trigger) = parser.parse_trigger(body) {
+                    script.triggers.push(trigger);
+                }
+            } else if let Some(body) = line.strip_prefix("S:") {
+                if let Some(static_ability) = parser.parse_static(body) {
+                    script.statics.push(static_ability);
+                }
+            } else if let Some(body) = line.strip_prefix("R:") {
+                if let Some(replacement) = parser.parse_replacement(body, &mut Vec::new()) {
+                    script.replacements.push(replacement);
+                }
+            }
+        }
+
+        script.diagnostics = parser.diagnostics;
+        script
+    }
+
+    /// Token script names referenced by any spell, activated or triggered ability
+    pub fn token_scripts(&self) -> Vec<&str> {
+        let mut scripts: Vec<&str> = self
+            .spells
+            .iter()
+            .chain(&self.activated)
+            .chain(self.triggers.iter().filter_map(|trigger| trigger.execute.as_ref()))
+            .flat_map(|ability| ability.token_scripts())
+            .collect();
+        scripts.sort_unstable();
+        scripts.dedup();
+        scripts
+    }
+}
+
+struct Parser<'a> {
+    svars: HashMap<&'a str, &'a str>,
+    diagnostics: Vec<ScriptDiagnostic>,
+}
+
+impl Parser<'_> {
+    /// Parse one ability body, following `SubAbility$` references
+    ///
+    /// `stack` holds the SVar names currently being expanded, to detect cycles.
+    fn parse_ability(&mut self, body: &str, stack: &mut Vec<String>) -> Option<AbilityScript> {
+        let mut params = split_params(body, &mut self.diagnostics);
+        if params.is_empty() {
+            self.diagnostics
+                .push(ScriptDiagnostic::MissingHeader { line: body.to_string() });
+            return None;
+        }
+
+        let (head_key, head_value) = params.remove(0);
+        let Some(kind) = AbilityKind::from_key(&head_key) else {
+            self.diagnostics
+                .push(ScriptDiagnostic::MissingHeader { line: body.to_string() });
+            return None;
+        };
+        let api = ApiType::parse(&head_value);
+
+        if let ApiType::Other(name) = &api {
+            self.diagnostics
+                .push(ScriptDiagnostic::UnsupportedApi { api: name.clone() });
+        } else {
+            for (key, _) in &params {
+                if !is_known_param(&api, key) {
+                    self.diagnostics.push(ScriptDiagnostic::UnknownParameter {
+                        api: api.to_string(),
+                        param: key.clone(),
+                    });
+                }
+            }
+        }
+
+        // Target counts may name an SVar holding a plain number ("TargetMax$ Num" with "SVar:Num:2")
+        for (key, value) in params.iter_mut() {
+            if key == "TargetMin" || key == "TargetMax" {
+                if let Some(count) = self
+                    .svars
+                    .get(value.as_str())
+                    .filter(|body| body.trim().parse::<u8>().is_ok())
+                {
+                    *value = count.trim().to_string();
+                }
+            }
+        }
+
+        for (key, value) in &params {
+            if key == "ValidTgts" || key == "ValidCards" {
+                for property in Selector::parse(value).unknown_properties() {
+                    self.diagnostics.push(ScriptDiagnostic::UnknownSelectorProperty {
+                        selector: value.clone(),
+                        property: property.to_string(),
+                    });
+                }
+            }
+        }
+
+        let sub_ability = match find_param(&params, "SubAbility") {
+            Some(name) => self.resolve_svar(name, stack).map(Box::new),
+            None => None,
+        };
+
+        let modes = match (&api, find_param(&params, "Choices")) {
+            (ApiType::Charm, Some(choices)) => choices
+                .split(',')
+                .filter_map(|name| self.resolve_svar(name.trim(), stack))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let replacements = match (&api, find_param(&params, "ReplacementEffects")) {
+            (ApiType::Effect, Some(names)) => names
+                .split(',')
+                .filter_map(|name| self.resolve_replacement_svar(name.trim(), stack))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Amounts like "NumDmg$ X" or "NumAtt$ +X" refer to an SVar holding "Count$xPaid"
+        let x_params = params
+            .iter()
+            .filter(|(_, value)| {
+                let name = value.trim_start_matches('+');
+                self.svars.get(name).is_some_and(|body| body.trim() == "Count$xPaid")
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        Some(AbilityScript {
+            kind,
+            api,
+            params,
+            sub_ability,
+            modes,
+            replacements,
+            x_params,
+        })
+    }
+
+    /// Parse the ability stored in SVar `name`
+    fn resolve_svar(&mut self, name: &str, stack: &mut Vec<String>) -> Option<AbilityScript> {
+        if stack.iter().any(|n| n == name) || stack.len() >= MAX_CHAIN_DEPTH {
+            self.diagnostics
+                .push(ScriptDiagnostic::CyclicReference { name: name.to_string() });
+            return None;
+        }
+        let Some(body) = self.svars.get(name).copied() else {
+            self.diagnostics
+                .push(ScriptDiagnostic::MissingSVar { name: name.to_string() });
+            return None;
+        };
+
+        stack.push(name.to_string());
+        let ability = self.parse_ability(body, stack);
+        stack.pop();
+        ability
+    }
+
+    /// Parse the replacement effect stored in SVar `name`
+    fn resolve_replacement_svar(&mut self, name: &str, stack: &mut Vec<String>) -> Option<ReplacementScript> {
+        if stack.iter().any(|n| n == name) || stack.len() >= MAX_CHAIN_DEPTH {
+            self.diagnostics
+                .push(ScriptDiagnostic::CyclicReference { name: name.to_string() });
+            return None;
+        }
+        let Some(body) = self.svars.get(name).copied() else {
+            self.diagnostics
+                .push(ScriptDiagnostic::MissingSVar { name: name.to_string() });
+            return None;
+        };
+
+        stack.push(name.to_string());
+        let replacement = self.parse_replacement(body, stack);
+        stack.pop();
+        replacement
+    }
+
+    /// Parse an `R:` line body, following its `ReplaceWith$` reference
+    fn parse_replacement(&mut self, body: &str, stack: &mut Vec<String>) -> Option<ReplacementScript> {
+        let mut params = split_params(body, &mut self.diagnostics);
+        let Some(event_idx) = params.iter().position(|(k, _)| k == "Event") else {
+            self.diagnostics
+                .push(ScriptDiagnostic::MissingHeader { line: body.to_string() });
+            return None;
+        };
+        let (_, event) = params.remove(event_idx);
+
+        if !matches!(
+            event.as_str(),
+            "Moved" | "DamageDone" | "Draw" | "GainLife" | "LifeReduced"
+        ) {
+            self.diagnostics
+                .push(ScriptDiagnostic::UnsupportedReplacementEvent { event: event.clone() });
+        }
+        for (key, value) in &params {
+            if !REPLACEMENT_PARAMS.contains(&key.as_str()) {
+                self.diagnostics.push(ScriptDiagnostic::UnknownParameter {
+                    api: format!("replacement {event}"),
+                    param: key.clone(),
+                });
+            } else if key.starts_with("Valid") && key != "ValidPlayer" {
+                for property in Selector::parse(value).unknown_properties() {
+                    self.diagnostics.push(ScriptDiagnostic::UnknownSelectorProperty {
+                        selector: value.clone(),
+                        property: property.to_string(),
+                    });
+                }
+            }
+        }
+
+        let replace_with = match find_param(&params, "ReplaceWith") {
+            Some(name) => {
+                let name = name.to_string();
+                self.resolve_svar(&name, stack)
+            }
+            None => None,
+        };
+
+        Some(ReplacementScript {
+            event,
+            params,
+            replace_with,
+        })
+    }
+
+    /// Parse a `T:` line body, following its `Execute$` reference
+    fn parse_trigger(&mut self, body: &str) -> Option<TriggerScript> {
+        let mut params = split_params(body, &mut self.diagnostics);
+        let Some(mode_idx) = params.iter().position(|(k, _)| k == "Mode") else {
+            self.diagnostics
+                .push(ScriptDiagnostic::MissingHeader { line: body.to_string() });
+            return None;
+        };
+        let (_, mode) = params.remove(mode_idx);
+
+        for (key, _) in &params {
+            if !TRIGGER_PARAMS.contains(&key.as_str()) {
+                self.diagnostics.push(ScriptDiagnostic::UnknownParameter {
+                    api: format!("trigger {mode}"),
+                    param: key.clone(),
+                });
+            }
+        }
+
+        let execute = match find_param(&params, "Execute") {
+            Some(name) => {
+                let name = name.to_string();
+                self.resolve_svar(&name, &mut Vec::new())
+            }
+            None => None,
+        };
+
+        Some(TriggerScript { mode, params, execute })
+    }
+
+    /// Parse an `S:` line body
+    fn parse_static(&mut self, body: &str) -> Option<StaticScript> {
+        let mut params = split_params(body, &mut self.diagnostics);
+        let Some(mode_idx) = params.iter().position(|(k, _)| k == "Mode") else {
+            self.diagnostics
+                .push(ScriptDiagnostic::MissingHeader { line: body.to_string() });
+            return None;
+        };
+        let (_, mode) = params.remove(mode_idx);
+
+        // A cost increase's amount is usually an SVar ("Amount$ X" with "SVar:X:...")
+        if mode == "RaiseCost" {
+            for (key, value) in params.iter_mut() {
+                if key == "Amount" {
+                    if let Some(body) = self.svars.get(value.as_str()) {
+                        *value = body.trim().to_string();
+                    }
+                }
+            }
+        }
+        let script = StaticScript { mode, params };
+
+        if script.mode != "Continuous" {
+            if script.extra_target_cost().is_none() {
+                self.diagnostics.push(ScriptDiagnostic::UnsupportedStaticMode {
+                    mode: script.mode.clone(),
+                });
+            }
+        } else {
+            let mode = &script.mode;
+            for (key, value) in &script.params {
+                if !CONTINUOUS_STATIC_PARAMS.contains(&key.as_str()) {
+                    self.diagnostics.push(ScriptDiagnostic::UnknownParameter {
+                        api: format!("static {mode}"),
+                        param: key.clone(),
+                    });
+                } else if key == "Affected" {
+                    for property in Selector::parse(value).unknown_properties() {
+                        self.diagnostics.push(ScriptDiagnostic::UnknownSelectorProperty {
+                            selector: value.clone(),
+                            property: property.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Some(script)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn svars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_simple_spell() {
+        let lines = vec![
+            "A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ 3 | SpellDescription$ CARDNAME deals 3 damage to any target."
+                .to_string(),
+        ];
+        let script = CardScript::parse(&lines, &[]);
+
+        assert_eq!(script.spells.len(), 1);
+        let spell = &script.spells[0];
+        assert_eq!(spell.kind, AbilityKind::Spell);
+        assert_eq!(spell.api, ApiType::DealDamage);
+        assert_eq!(spell.param("NumDmg"), Some("3"));
+        assert_eq!(spell.param("ValidTgts"), Some("Any"));
+        assert!(spell.sub_ability.is_none());
+        assert!(script.diagnostics.is_empty(), "{:?}", script.diagnostics);
+    }
+
+    #[test]
+    fn test_sub_ability_chain() {
+        // Lightning Helix: the life gain is only reachable through SubAbility$
+        let lines = vec![
+            "A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ 3 | SubAbility$ DBGainLife | SpellDescription$ CARDNAME deals 3 damage to any target and you gain 3 life."
+                .to_string(),
+        ];
+        let svars = svars(&[("DBGainLife", "DB$ GainLife | Defined$ You | LifeAmount$ 3")]);
+        let script = CardScript::parse(&lines, &svars);
+
+        let spell = &script.spells[0];
+        let chain: Vec<_> = spell.chain().map(|n| n.api.clone()).collect();
+        assert_eq!(chain, vec![ApiType::DealDamage, ApiType::GainLife]);
+        assert_eq!(spell.sub_ability.as_ref().unwrap().kind, AbilityKind::Sub);
+
+        let effects = spell.to_effects();
+        assert_eq!(effects.len(), 2);
+        assert!(matches!(effects[0], Effect::DealDamage { amount: 3, .. }));
+        assert!(matches!(effects[1], Effect::GainLife { amount: 3, .. }));
+    }
+
+    #[test]
+    fn test_trigger_execute() {
+        let lines = vec![
+            "T:Mode$ ChangesZone | Origin$ Any | Destination$ Battlefield | ValidCard$ Card.Self | Execute$ TrigDraw | TriggerDescription$ When CARDNAME enters, draw two cards."
+                .to_string(),
+        ];
+        let svars = svars(&[("TrigDraw", "DB$ Draw | Defined$ You | NumCards$ 2")]);
+        let script = CardScript::parse(&lines, &svars);
+
+        assert_eq!(script.triggers.len(), 1);
+        let trigger = &script.triggers[0];
+        assert_eq!(trigger.mode, "ChangesZone");
+        assert_eq!(trigger.param("Destination"), Some("Battlefield"));
+        assert_eq!(
+            trigger.to_effects(),
+            vec![Effect::DrawCards {
+                player: PlayerId::new(0),
+                count: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reports_unknown_parameters_and_apis() {
+        let lines = vec![
+            "A:SP$ Draw | NumCards$ 1 | Frobnicate$ True | SubAbility$ DBScry".to_string(),
+            "A:SP$ Scry | ScryNum$ 2".to_string(),
+        ];
+        let svars = svars(&[("DBScry", "DB$ Scry | ScryNum$ 1")]);
+        let script = CardScript::parse(&lines, &svars);
+
+        assert!(script.diagnostics.contains(&ScriptDiagnostic::UnknownParameter {
+            api: "Draw".to_string(),
+            param: "Frobnicate".to_string(),
+        }));
+        assert!(script.diagnostics.contains(&ScriptDiagnostic::UnsupportedApi {
+            api: "Scry".to_string()
+        }));
+        // The unsupported sub-ability is still part of the tree, it just has no effect
+        assert_eq!(script.spells[0].chain().count(), 2);
+        assert_eq!(script.spells[0].to_effects().len(), 1);
+    }
+
+    #[test]
+    fn test_missing_and_cyclic_svars() {
+        let lines = vec![
+            "A:SP$ GainLife | LifeAmount$ 2 | SubAbility$ DBNope".to_string(),
+            "A:SP$ GainLife | LifeAmount$ 1 | SubAbility$ DBLoop".to_string(),
+        ];
+        let svars = svars(&[("DBLoop", "DB$ Draw | NumCards$ 1 | SubAbility$ DBLoop")]);
+        let script = CardScript::parse(&lines, &svars);
+
+        assert!(script.diagnostics.contains(&ScriptDiagnostic::MissingSVar {
+            name: "DBNope".to_string()
+        }));
+        assert!(script.diagnostics.contains(&ScriptDiagnostic::CyclicReference {
+            name: "DBLoop".to_string()
+        }));
+        assert_eq!(script.spells[1].chain().count(), 2);
+    }
+
+    #[test]
+    fn test_activated_ability_cost() {
+        let lines = vec!["A:AB$ Mana | Cost$ T | Produced$ C | Amount$ 2 | SpellDescription$ Add {C}{C}.".to_string()];
+        let script = CardScript::parse(&lines, &[]);
+
+        assert!(script.spells.is_empty());
+        let ability = &script.activated[0];
+        assert!(ability.is_mana_ability());
+        assert_eq!(ability.cost(), Some(Cost::Tap));
+        match &ability.to_effects()[0] {
+            Effect::AddMana { mana, .. } => assert_eq!(mana.colorless, 2),
+            other => panic!("Expected AddMana, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_mana_of_choice_abilities() {
+        let lines = vec![
+            "A:AB$ Mana | Cost$ T | Produced$ Any | SpellDescription$ Add one mana of any color.".to_string(),
+            "A:AB$ Mana | Cost$ T PayLife<1> | Produced$ Combo R G | SpellDescription$ Add {R} or {G}.".to_string(),
+            "A:SP$ Mana | Produced$ B | Amount$ 3 | SpellDescription$ Add {B}{B}{B}.".to_string(),
+        ];
+        let script = CardScript::parse(&lines, &[]);
+
+        match &script.activated[0].to_effects()[0] {
+            Effect::AddManaOfChoice { colors, amount, .. } => {
+                assert_eq!(colors.len(), 5);
+                assert_eq!(*amount, 1);
+            }
+            other => panic!("Expected AddManaOfChoice, got {other:?}"),
+        }
+        match &script.activated[1].to_effects()[0] {
+            Effect::AddManaOfChoice { colors, .. } => assert_eq!(colors, &vec![Color::Red, Color::Green]),
+            other => panic!("Expected AddManaOfChoice, got {other:?}"),
+        }
+        assert_eq!(script.activated[1].cost(), Some(Cost::parse("T PayLife<1>").unwrap()));
+        match &script.spells[0].to_effects()[0] {
+            Effect::AddMana { mana, .. } => assert_eq!(mana.black, 3),
+            other => panic!("Expected AddMana, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_target_restriction_and_all_effects() {
+        let lines = vec![
+            "A:SP$ Destroy | ValidTgts$ Creature.nonArtifact+nonBlack | TgtPrompt$ Select target creature".to_string(),
+            "A:SP$ DestroyAll | ValidCards$ Creature | NoRegen$ True | SpellDescription$ Destroy all creatures."
+                .to_string(),
+            "A:SP$ Pump | ValidTgts$ Creature.fancyLKI | NumAtt$ +1".to_string(),
+        ];
+        let script = CardScript::parse(&lines, &[]);
+
+        let restriction = script.spells[0].target_restriction().unwrap();
+        assert_eq!(restriction.to_string(), "Creature.nonArtifact+nonBlack");
+        assert!(script.spells[1].target_restriction().is_none());
+        assert!(matches!(script.spells[1].to_effects()[0], Effect::DestroyAll { .. }));
+        assert_eq!(
+            script.diagnostics,
+            vec![ScriptDiagnostic::UnknownSelectorProperty {
+                selector: "Creature.fancyLKI".to_string(),
+                property: "fancyLKI".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_token_ability() {
+        let lines = vec![
+            "A:SP$ Token | TokenAmount$ 2 | TokenScript$ w_1_1_soldier | SpellDescription$ Create two Soldiers."
+                .to_string(),
+            "T:Mode$ ChangesZone | Origin$ Any | Destination$ Battlefield | ValidCard$ Card.Self | Execute$ TrigToken"
+                .to_string(),
+        ];
+        let svars = vec![(
+            "TrigToken".to_string(),
+            "DB$ Token | TokenScript$ c_a_treasure_sac".to_string(),
+        )];
+        let script = CardScript::parse(&lines, &svars);
+
+        match &script.spells[0].to_effects()[0] {
+            Effect::CreateToken { amount, token, .. } => {
+                assert_eq!(*amount, 2);
+                assert_eq!(token.script, "w_1_1_soldier");
+                assert_eq!(token.name, "Soldier Token");
+            }
+            other => panic!("Expected CreateToken, got {other:?}"),
+        }
+        assert_eq!(script.token_scripts(), vec!["c_a_treasure_sac", "w_1_1_soldier"]);
+    }
+
+    #[test]
+    fn test_charm_modes() {
+        // Izzet Charm
+        let lines = vec!["A:SP$ Charm | Choices$ DBCounter,DBDmg,DBDraw".to_string()];
+        let svars = svars(&[
+            (
+                "DBCounter",
+                "DB$ Counter | TargetType$ Spell | ValidTgts$ Card.nonCreature | SpellDescription$ Counter target noncreature spell.",
+            ),
+            (
+                "DBDmg",
+                "DB$ DealDamage | ValidTgts$ Creature | NumDmg$ 2 | SpellDescription$ CARDNAME deals 2 damage to target creature.",
+            ),
+            ("DBDraw", "DB$ Draw | NumCards$ 2 | SpellDescription$ Draw two cards."),
+        ]);
+        let script = CardScript::parse(&lines, &svars);
+        assert!(script.diagnostics.is_empty(), "{:?}", script.diagnostics);
+
+        let charm = &script.spells[0];
+        assert_eq!(charm.api, ApiType::Charm);
+        assert!(charm.to_effects().is_empty());
+        assert_eq!(charm.mode_count(), (1, 1));
+
+        let modes = charm.spell_modes();
+        assert_eq!(modes.len(), 3);
+        assert_eq!(modes[1].description, "CARDNAME deals 2 damage to target creature.");
+        assert!(matches!(modes[1].effects[..], [Effect::DealDamage { amount: 2, .. }]));
+        assert_eq!(modes[1].target_restriction.as_ref().unwrap().to_string(), "Creature");
+        assert!(modes[2].target_restriction.is_none());
+    }
+
+    #[test]
+    fn test_charm_mode_count() {
+        let lines = vec![
+            "A:SP$ Charm | CharmNum$ 2 | Choices$ DBDraw,DBLife".to_string(),
+            "A:SP$ Charm | MinCharmNum$ 1 | CharmNum$ 2 | Choices$ DBDraw,DBLife".to_string(),
+        ];
+        let svars = svars(&[
+            ("DBDraw", "DB$ Draw | NumCards$ 1"),
+            ("DBLife", "DB$ GainLife | LifeAmount$ 3"),
+        ]);
+        let script = CardScript::parse(&lines, &svars);
+
+        assert_eq!(script.spells[0].mode_count(), (2, 2));
+        assert_eq!(script.spells[1].mode_count(), (1, 2));
+        // Modes without a description are named after their API
+        assert_eq!(script.spells[0].spell_modes()[0].description, "Draw");
+    }
+
+    #[test]
+    fn test_x_paid_amounts() {
+        let lines = vec![
+            "A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ X | SpellDescription$ CARDNAME deals X damage to any target."
+                .to_string(),
+            "A:SP$ Pump | ValidTgts$ Creature | NumAtt$ +X | NumDef$ +1".to_string(),
+            "A:SP$ Draw | NumCards$ X".to_string(),
+        ];
+        let svars = svars(&[("X", "Count$xPaid")]);
+        let script = CardScript::parse(&lines, &svars);
+        assert!(script.diagnostics.is_empty(), "{:?}", script.diagnostics);
+
+        assert!(script.spells[0].is_x_param("NumDmg"));
+        assert!(matches!(
+            script.spells[0].to_effects()[..],
+            [Effect::DealDamage { amount: AMOUNT_X, .. }]
+        ));
+        match &script.spells[1].to_effects()[0] {
+            Effect::PumpCreature {
+                power_bonus,
+                toughness_bonus,
+                ..
+            } => assert_eq!((*power_bonus, *toughness_bonus), (AMOUNT_X, 1)),
+            other => panic!("Expected PumpCreature, got {other:?}"),
+        }
+        assert!(matches!(
+            script.spells[2].to_effects()[0].with_x(4),
+            Effect::DrawCards { count: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn test_divided_damage_and_counters() {
+        let lines = vec![
+            // Arc Lightning
+            "A:SP$ DealDamage | ValidTgts$ Any | TgtPrompt$ Select any target | NumDmg$ 3 | TargetMin$ 1 | TargetMax$ 3 | DividedAsYouChoose$ 3".to_string(),
+            "A:SP$ PutCounter | ValidTgts$ Creature | CounterType$ P1P1 | CounterNum$ 3 | TargetMin$ 0 | TargetMax$ 3 | DividedAsYouChoose$ 3".to_string(),
+            "A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ 3".to_string(),
+        ];
+        let script = CardScript::parse(&lines, &[]);
+        assert!(script.diagnostics.is_empty(), "{:?}", script.diagnostics);
+
+        assert_eq!(script.spells[0].target_count(), (1, 3));
+        assert!(script.spells[0].is_divided());
+        assert_eq!(script.spells[0].to_effects()[0].divisible_amount(), Some(3));
+
+        assert_eq!(script.spells[1].target_count(), (0, 3));
+        assert!(matches!(
+            script.spells[1].to_effects()[..],
+            [Effect::PutCounter {
+                counter_type: CounterType::P1P1,
+                amount: 3,
+                ..
+            }]
+        ));
+
+        assert_eq!(script.spells[2].target_count(), (1, 1));
+        assert!(!script.spells[2].is_divided());
+    }
+
+    #[test]
+    fn test_target_counts_from_svars() {
+        let lines = vec![
+            "A:SP$ Tap | ValidTgts$ Creature | TargetMin$ 0 | TargetMax$ Num".to_string(),
+            "A:SP$ Tap | ValidTgts$ Creature | TargetMin$ X | TargetMax$ X".to_string(),
+            // Fireball's count of players and creatures is any number of targets
+            "A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ X | TargetMin$ 1 | TargetMax$ MaxTgts | DivideEvenly$ RoundedDown"
+                .to_string(),
+        ];
+        let svars = svars(&[
+            ("Num", "2"),
+            ("X", "Count$xPaid"),
+            ("MaxTgts", "PlayerCountPlayers$Amount/Plus.NumCreatures"),
+        ]);
+        let script = CardScript::parse(&lines, &svars);
+
+        assert_eq!(script.spells[0].target_count(), (0, 2));
+        assert_eq!(script.spells[1].target_count(), (COUNT_X, COUNT_X));
+        assert_eq!(script.spells[2].target_count(), (1, COUNT_ANY));
+        assert!(script.spells[2].is_divided_evenly());
+        assert!(!script.spells[2].is_divided());
+    }
+
+    #[test]
+    fn test_parse_extra_target_cost() {
+        let lines = vec![
+            "S:Mode$ RaiseCost | ValidCard$ Card.Self | Type$ Spell | Amount$ Y | EffectZone$ All | Description$ This spell costs {1} more to cast for each target beyond the first.".to_string(),
+            "S:Mode$ RaiseCost | ValidCard$ Creature | Type$ Spell | Amount$ 1".to_string(),
+        ];
+        let svars = svars(&[("Y", "TargetedObjects$Amount/Minus.1")]);
+        let script = CardScript::parse(&lines, &svars);
+
+        assert_eq!(script.statics[0].extra_target_cost(), Some(1));
+        assert_eq!(script.statics[1].extra_target_cost(), None);
+        assert_eq!(
+            script.diagnostics,
+            vec![ScriptDiagnostic::UnsupportedStaticMode {
+                mode: "RaiseCost".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_continuous_static() {
+        use crate::core::{CardType, Color, ContinuousModification, Keyword};
+
+        let lines = vec![
+            "S:Mode$ Continuous | Affected$ Creature.YouCtrl | AddPower$ 1 | AddToughness$ 1 | Description$ Creatures you control get +1/+1.".to_string(),
+            "S:Mode$ Continuous | Affected$ Card.Self | AddType$ Artifact & Creature & Golem | SetPower$ 3 | SetToughness$ 3 | SetColor$ Blue | AddKeyword$ Flying & First Strike".to_string(),
+            "S:Mode$ Continuous | Affected$ Creature.OppCtrl | AddPower$ -1 | IsPresent$ Card.Self+untapped".to_string(),
+            "S:Mode$ CantAttack | ValidCard$ Creature.Self".to_string(),
+        ];
+        let script = CardScript::parse(&lines, &[]);
+        assert_eq!(script.statics.len(), 4);
+        assert_eq!(
+            script.diagnostics,
+            vec![
+                ScriptDiagnostic::UnknownParameter {
+                    api: "static Continuous".to_string(),
+                    param: "IsPresent".to_string(),
+                },
+                ScriptDiagnostic::UnsupportedStaticMode {
+                    mode: "CantAttack".to_string(),
+                },
+            ]
+        );
+
+        let anthem = script.statics[0].to_static_ability().unwrap();
+        assert_eq!(anthem.description, "Creatures you control get +1/+1.");
+        assert_eq!(
+            anthem.modifications,
+            vec![
+                ContinuousModification::AddPower(1),
+                ContinuousModification::AddToughness(1)
+            ]
+        );
+
+        let animate = script.statics[1].to_static_ability().unwrap();
+        assert_eq!(
+            animate.modifications,
+            vec![
+                ContinuousModification::AddType(CardType::Artifact),
+                ContinuousModification::AddType(CardType::Creature),
+                ContinuousModification::AddSubtype(crate::core::Subtype::new("Golem")),
+                ContinuousModification::SetColors([Color::Blue].into_iter().collect()),
+                ContinuousModification::AddKeyword(Keyword::Flying),
+                ContinuousModification::AddKeyword(Keyword::FirstStrike),
+                ContinuousModification::SetPower(3),
+                ContinuousModification::SetToughness(3),
+            ]
+        );
+
+        assert_eq!(
+            script.statics[2].to_static_ability().unwrap().modifications,
+            vec![ContinuousModification::AddPower(-1)]
+        );
+        assert!(script.statics[3].to_static_ability().is_none());
+    }
+
+    #[test]
+    fn test_parse_replacement_effects() {
+        // Circle of Protection: Red's shield exiles itself once it has prevented damage
+        let lines = vec![
+            "A:SP$ Effect | ReplacementEffects$ Shield | SpellDescription$ Prevent the next damage.".to_string(),
+            "R:Event$ Mill | ValidPlayer$ You | Description$ Not replaced yet.".to_string(),
+        ];
+        let script = CardScript::parse(
+            &lines,
+            &svars(&[
+                (
+                    "Shield",
+                    "Event$ DamageDone | ValidTarget$ You | ValidSource$ Card.Red | PreventionEffect$ True | ReplaceWith$ ExileEffect | Description$ Prevent the next damage.",
+                ),
+                ("ExileEffect", "DB$ ChangeZone | Defined$ Self | Origin$ Command | Destination$ Exile"),
+            ]),
+        );
+
+        assert_eq!(script.spells[0].api, ApiType::Effect);
+        let shield = &script.spells[0].replacements[0];
+        assert_eq!(shield.event, "DamageDone");
+        let effect = shield.to_replacement_effect().unwrap();
+        assert!(effect.is_prevention());
+        assert!(effect.single_use);
+        assert_eq!(effect.valid_source, Some(Selector::parse("Card.Red")));
+        assert!(matches!(
+            script.spells[0].to_effects()[..],
+            [Effect::CreateReplacementEffects { ref effects, .. }] if effects.len() == 1
+        ));
+
+        assert_eq!(script.replacements.len(), 1);
+        assert_eq!(script.replacements[0].to_replacement_effect(), None);
+        assert_eq!(
+            script.diagnostics,
+            vec![ScriptDiagnostic::UnsupportedReplacementEvent {
+                event: "Mill".to_string()
+            }]
+        );
+    }
+}
@@ -2,7 +2,12 @@
 //!
 //! Loads card definitions from Forge's cardsfolder format
 
-use crate::core::{Card, CardName, CardType, Color, Keyword, ManaCost, Subtype, Trigger, TriggerEvent};
+use crate::core::{
+    Card, CardName, CardType, Color, Effect, Keyword, ManaCost, ReplacementAction, ReplacementEffect, ReplacementEvent,
+    Selector, Subtype, TokenDefinition, Trigger, TriggerEvent,
+};
+use crate::loader::ability_script::{ApiType, CardScript};
+use crate::zones::Zone;
 use crate::{MtgError, Result};
 use smallvec::SmallVec;
 use std::fs;
@@ -30,9 +35,11 @@ impl CardLoader {
         let mut colors = Vec::new();
         let mut power = None;
         let mut toughness = None;
+        let mut loyalty = None;
         let mut oracle = String::new();
         let mut raw_abilities = Vec::new();
         let mut raw_keywords = Vec::new();
+        let mut svars = Vec::new();
 
         for (line_num, line) in content.lines().enumerate() {
             let line = line.trim();
@@ -78,15 +85,35 @@ impl CardLoader {
                             )));
                         }
                     }
+                    // Starting loyalty of a planeswalker ("X" loyalty is not supported)
+                    "Loyalty" => loyalty = value.parse().ok(),
+                    // Explicit colors (used by token scripts, which have no mana cost)
+                    "Colors" => {
+                        colors.extend(value.split(',').filter_map(|c| match c.trim() {
+                            "white" => Some(Color::White),
+                            "blue" => Some(Color::Blue),
+                            "black" => Some(Color::Black),
+                            "red" => Some(Color::Red),
+                            "green" => Some(Color::Green),
+                            "colorless" => Some(Color::Colorless),
+                            _ => None,
+                        }));
+                    }
                     "Oracle" => oracle = value.to_string(),
                     // Keyword lines (K:)
                     "K" => {
                         raw_keywords.push(value.to_string());
                     }
-                    // Ability lines (A:, S:, T:, etc.)
-                    "A" | "S" | "T" => {
+                    // Ability lines (A:, S:, T:, R:)
+                    "A" | "S" | "T" | "R" => {
                         raw_abilities.push(format!("{key}:{value}"));
                     }
+                    // Script variables (SVar:Name:Body), referenced by SubAbility$/Execute$
+                    "SVar" => {
+                        if let Some((svar_name, body)) = value.split_once(':') {
+                            svars.push((svar_name.trim().to_string(), body.trim().to_string()));
+                        }
+                    }
                     _ => {} // Ignore other fields for now
                 }
             } else {
@@ -96,21 +123,10 @@ impl CardLoader {
             }
         }
 
-        // Derive colors from mana cost
-        if mana_cost.white > 0 {
-            colors.push(Color::White);
-        }
-        if mana_cost.blue > 0 {
-            colors.push(Color::Blue);
-        }
-        if mana_cost.black > 0 {
-            colors.push(Color::Black);
-        }
-        if mana_cost.red > 0 {
-            colors.push(Color::Red);
-        }
-        if mana_cost.green > 0 {
-            colors.push(Color::Green);
+        // Derive colors from mana cost unless a Colors: line gave them explicitly
+        // (hybrid and Phyrexian symbols count for each of their colors, MTG Rules 105.2)
+        if colors.is_empty() {
+            colors.extend(crate::core::WUBRG.into_iter().filter(|&c| mana_cost.contains_color(c)));
         }
         if colors.is_empty() {
             colors.push(Color::Colorless);
@@ -122,6 +138,8 @@ impl CardLoader {
             )
         })?;
 
+        let script = CardScript::parse(&raw_abilities, &svars);
+
         Ok(CardDefinition {
             name,
             mana_cost,
@@ -130,9 +148,13 @@ impl CardLoader {
             colors,
             power,
             toughness,
+            loyalty,
             oracle,
             raw_abilities,
             raw_keywords,
+            svars,
+            script,
+            tokens: Vec::new(),
         })
     }
 }
@@ -147,12 +169,20 @@ pub struct CardDefinition {
     pub colors: Vec<Color>,
     pub power: Option<i8>,
     pub toughness: Option<i8>,
+    /// Starting loyalty of a planeswalker (Loyalty: line)
+    pub loyalty: Option<u8>,
     pub oracle: String,
     /// Raw ability scripts from the card file (A:, S:, T: lines)
-    /// We'll parse these into actual effects later
     pub raw_abilities: Vec<String>,
     /// Raw keyword scripts from the card file (K: lines)
     pub raw_keywords: Vec<String>,
+    /// Script variables from the card file (SVar:Name:Body lines) as (name, body)
+    pub svars: Vec<(String, String)>,
+    /// Structured ability tree parsed from `raw_abilities` and `svars`
+    pub script: CardScript,
+    /// Token scripts referenced by `TokenScript$`, loaded from the tokenscripts folder
+    /// (scripts that couldn't be loaded fall back to [`TokenDefinition::from_script_name`])
+    pub tokens: Vec<TokenDefinition>,
 }
 
 impl CardDefinition {
@@ -165,69 +195,155 @@ impl CardDefinition {
         card.colors = SmallVec::from_vec(self.colors.clone());
         card.power = self.power;
         card.toughness = self.toughness;
+        card.loyalty = self.loyalty;
         card.text = self.oracle.clone();
 
         // Parse keywords
         card.keywords = self.parse_keywords();
 
-        // Parse abilities into effects (simplified parser for common cases)
+        // Parse abilities into effects, along with the spell's targeting restriction
         card.effects = self.parse_effects();
+        card.target_restriction = self.script.spells.iter().find_map(|spell| spell.target_restriction());
+        if let Some(spell) = self.script.spells.first() {
+            (card.min_targets, card.max_targets) = spell.target_count();
+            card.divided_as_you_choose = spell.is_divided();
+            card.divided_evenly = spell.is_divided_evenly();
+        }
+
+        // Modal spells (SP$ Charm) get their effects from the modes chosen on casting
+        if let Some(charm) = self.script.spells.iter().find(|spell| spell.api == ApiType::Charm) {
+            card.modes = charm.spell_modes();
+            (card.min_modes, card.max_modes) = charm.mode_count();
+        }
 
         // Parse triggered abilities
         card.triggers = self.parse_triggers();
 
         // Parse activated abilities
         card.activated_abilities = self.parse_activated_abilities();
+        card.activated_abilities.extend(self.parse_equip_abilities());
+
+        // An Aura spell always attaches to its target (MTG Rules 303.4f), even when
+        // the script doesn't say so with SP$ Attach
+        if card.is_aura() && !card.effects.iter().any(|e| matches!(e, Effect::Attach { .. })) {
+            card.effects.push(Effect::Attach {
+                attachment: crate::core::CardId::new(0),
+                target: crate::core::CardId::new(0),
+            });
+        }
 
-        // Add implicit mana ability for basic lands
-        // Basic lands (Plains, Island, Swamp, Mountain, Forest) have an implicit "{T}: Add {color}"
-        // ability that's not written in the card file
-        if card.is_land()
-            && self.subtypes.iter().any(|st| {
-                let st_str = st.as_str();
-                st_str == "Plains"
-                    || st_str == "Island"
-                    || st_str == "Swamp"
-                    || st_str == "Mountain"
-                    || st_str == "Forest"
-            })
+        // Continuous static abilities (S:Mode$ Continuous lines)
+        card.static_abilities = self
+            .script
+            .statics
+            .iter()
+            .filter_map(|s| s.to_static_ability())
+            .collect();
+        card.alternative_cost = self.alternative_cost();
+        card.extra_target_cost = self
+            .script
+            .statics
+            .iter()
+            .find_map(|s| s.extra_target_cost())
+            .unwrap_or(0);
+
+        // Replacement effects (R: lines), plus the older "enters the battlefield tapped" keyword
+        card.replacement_effects = self
+            .script
+            .replacements
+            .iter()
+            .filter_map(|r| r.to_replacement_effect())
+            .collect();
+        if self
+            .raw_keywords
+            .iter()
+            .any(|kw| kw.trim() == "CARDNAME enters the battlefield tapped.")
         {
-            // Determine which color to produce based on subtype
-            let mana_to_produce = if self.subtypes.iter().any(|st| st.as_str() == "Plains") {
-                ManaCost::from_string("W")
-            } else if self.subtypes.iter().any(|st| st.as_str() == "Island") {
-                ManaCost::from_string("U")
-            } else if self.subtypes.iter().any(|st| st.as_str() == "Swamp") {
-                ManaCost::from_string("B")
-            } else if self.subtypes.iter().any(|st| st.as_str() == "Mountain") {
-                ManaCost::from_string("R")
-            } else if self.subtypes.iter().any(|st| st.as_str() == "Forest") {
-                ManaCost::from_string("G")
-            } else {
-                ManaCost::new() // Shouldn't happen
-            };
+            card.replacement_effects.push(ReplacementEffect {
+                event: ReplacementEvent::Moved {
+                    origin: None,
+                    destination: Some(Zone::Battlefield),
+                },
+                affected: Some(Selector::parse("Card.Self")),
+                valid_source: None,
+                action: ReplacementAction::EnterTapped,
+                single_use: false,
+                description: "CARDNAME enters the battlefield tapped.".to_string(),
+            });
+        }
 
-            // Only add if we don't already have a mana ability
-            // (in case the card file explicitly defines one)
-            if !card.activated_abilities.iter().any(|ab| ab.is_mana_ability) {
-                use crate::core::{ActivatedAbility, Cost, Effect, PlayerId};
-
-                let ability = ActivatedAbility::new(
-                    Cost::Tap,
-                    vec![Effect::AddMana {
-                        player: PlayerId::new(0), // Placeholder - will be filled when activated
-                        mana: mana_to_produce,
-                    }],
-                    format!("Add {mana_to_produce}"),
-                    true, // This IS a mana ability
-                );
-                card.activated_abilities.push(ability);
+        // Swap name-derived token definitions for the loaded token scripts
+        if !self.tokens.is_empty() {
+            let effects = card
+                .effects
+                .iter_mut()
+                .chain(card.modes.iter_mut().flat_map(|m| m.effects.iter_mut()))
+                .chain(card.triggers.iter_mut().flat_map(|t| t.effects.iter_mut()))
+                .chain(card.activated_abilities.iter_mut().flat_map(|a| a.effects.iter_mut()));
+            for effect in effects {
+                if let Effect::CreateToken { token, .. } = effect {
+                    if let Some(loaded) = self.tokens.iter().find(|t| t.script == token.script) {
+                        *token = loaded.clone();
+                    }
+                }
+            }
+        }
+
+        // Add implicit mana abilities for basic land types
+        // Each basic land type (Plains, Island, Swamp, Mountain, Forest) grants an implicit
+        // "{T}: Add {color}" ability that's not written in the card file, so dual lands
+        // like Taiga get one ability per type. Only added if the card file doesn't
+        // explicitly define its own mana abilities.
+        if card.is_land() && !card.activated_abilities.iter().any(|ab| ab.is_mana_ability) {
+            for color in card.basic_land_colors() {
+                card.activated_abilities
+                    .push(crate::core::ActivatedAbility::basic_land_mana(color));
             }
         }
 
         card
     }
 
+    /// The card's color identity (MTG Rules 903.4), in WUBRG order
+    ///
+    /// The colors of the mana symbols in its mana cost and rules text (not
+    /// counting reminder text), its color indicator, and the colors of its
+    /// basic land types, which stand for the mana abilities they grant.
+    pub fn color_identity(&self) -> SmallVec<[Color; 5]> {
+        const WUBRG: [(Color, char, &str); 5] = [
+            (Color::White, 'W', "Plains"),
+            (Color::Blue, 'U', "Island"),
+            (Color::Black, 'B', "Swamp"),
+            (Color::Red, 'R', "Mountain"),
+            (Color::Green, 'G', "Forest"),
+        ];
+
+        // Mana symbols in rules text outside of parenthesized reminder text
+        let mut symbols = String::new();
+        let (mut in_reminder, mut in_symbol) = (0usize, false);
+        for c in self.oracle.chars() {
+            match c {
+                '(' => in_reminder += 1,
+                ')' => in_reminder = in_reminder.saturating_sub(1),
+                '{' => in_symbol = true,
+                '}' => in_symbol = false,
+                _ if in_symbol && in_reminder == 0 => symbols.push(c),
+                _ => {}
+            }
+        }
+
+        WUBRG
+            .iter()
+            .filter(|(color, symbol, land_type)| {
+                self.mana_cost.contains_color(*color)
+                    || self.colors.contains(color)
+                    || symbols.contains(*symbol)
+                    || self.subtypes.iter().any(|s| s.as_str() == *land_type)
+            })
+            .map(|(color, _, _)| *color)
+            .collect()
+    }
+
     /// Parse raw keywords into Keyword objects
     fn parse_keywords(&self) -> Vec<Keyword> {
         let mut keywords = Vec::new();
@@ -242,37 +358,18 @@ impl CardDefinition {
                 let keyword = match kw {
                     "Madness" => Keyword::Madness(param.to_string()),
                     "Flashback" => Keyword::Flashback(param.to_string()),
-                    "Enchant" => Keyword::Enchant(param.to_string()),
+                    // "Enchant:Creature.YouCtrl:creature you control" - the selector comes first
+                    "Enchant" => Keyword::Enchant(param.split(':').next().unwrap_or(param).trim().to_string()),
                     _ => Keyword::Other(keyword_str.clone()),
                 };
                 keywords.push(keyword);
+            } else if let Some(text) = keyword_str.trim().strip_prefix("Enchant ") {
+                // Older scripts spell the restriction out: "Enchant creature you control"
+                keywords.push(Keyword::Enchant(enchant_selector(text)));
             } else {
                 // Simple keywords (no parameters)
-                let kw = keyword_str.trim();
-                let keyword = match kw {
-                    "Flying" => Keyword::Flying,
-                    "First Strike" => Keyword::FirstStrike,
-                    "Double Strike" => Keyword::DoubleStrike,
-                    "Deathtouch" => Keyword::Deathtouch,
-                    "Haste" => Keyword::Haste,
-                    "Hexproof" => Keyword::Hexproof,
-                    "Indestructible" => Keyword::Indestructible,
-                    "Lifelink" => Keyword::Lifelink,
-                    "Menace" => Keyword::Menace,
-                    "Reach" => Keyword::Reach,
-                    "Trample" => Keyword::Trample,
-                    "Vigilance" => Keyword::Vigilance,
-                    "Defender" => Keyword::Defender,
-                    "Shroud" => Keyword::Shroud,
-                    "Choose a Background" => Keyword::ChooseABackground,
-                    // Protection variants
-                    "Protection from red" => Keyword::ProtectionFromRed,
-                    "Protection from blue" => Keyword::ProtectionFromBlue,
-                    "Protection from black" => Keyword::ProtectionFromBlack,
-                    "Protection from white" => Keyword::ProtectionFromWhite,
-                    "Protection from green" => Keyword::ProtectionFromGreen,
-                    _ => Keyword::Other(keyword_str.clone()),
-                };
+                let keyword =
+                    Keyword::from_name(keyword_str.trim()).unwrap_or_else(|| Keyword::Other(keyword_str.clone()));
                 keywords.push(keyword);
             }
         }
@@ -280,346 +377,69 @@ impl CardDefinition {
         keywords
     }
 
-    /// Parse raw abilities into Effect objects (simplified)
-    /// This is a temporary solution until we have a full ability parser
-    fn parse_effects(&self) -> Vec<crate::core::Effect> {
-        use crate::core::{Effect, PlayerId, TargetRef};
-
-        let mut effects = Vec::new();
-
-        for ability in &self.raw_abilities {
-            // Parse DealDamage abilities
-            // Format: "A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ 3 | ..."
-            if ability.contains("DealDamage") {
-                // Extract damage amount
-                if let Some(dmg_str) = ability.split("NumDmg$").nth(1) {
-                    if let Some(dmg_part) = dmg_str.trim().split(['|', ' ']).next() {
-                        if let Ok(amount) = dmg_part.trim().parse::<i32>() {
-                            // For now, use TargetRef::None - targeting will be filled in at cast time
-                            effects.push(Effect::DealDamage {
-                                target: TargetRef::None,
-                                amount,
-                            });
-                        }
-                    }
-                }
-            }
-
-            // Parse Draw abilities
-            // Format: "A:SP$ Draw | NumCards$ 3 | ValidTgts$ Player | ..."
-            // Format: "A:SP$ Draw | NumCards$ 1 | Defined$ You | ..." (draw yourself)
-            if ability.contains("SP$ Draw") {
-                // Extract number of cards to draw
-                if let Some(cards_str) = ability.split("NumCards$").nth(1) {
-                    if let Some(cards_part) = cards_str.trim().split(['|', ' ']).next() {
-                        if let Ok(count) = cards_part.trim().parse::<u8>() {
-                            // For now, use a placeholder player ID - will be filled in at cast time
-                            // Check if it targets a player or is self-draw
-                            effects.push(Effect::DrawCards {
-                                player: PlayerId::new(0), // Placeholder, will be set during resolution
-                                count,
-                            });
-                        }
-                    }
-                }
-            }
-
-            // Parse Destroy abilities
-            // Format: "A:SP$ Destroy | ValidTgts$ Creature | ..."
-            // Format: "A:SP$ Destroy | ValidTgts$ Creature.nonArtifact+nonBlack | ..."
-            if ability.contains("SP$ Destroy") {
-                // Destroy effects target a permanent
-                // Use placeholder card ID 0 - will be filled in at cast time
-                use crate::core::CardId;
-                effects.push(Effect::DestroyPermanent {
-                    target: CardId::new(0), // Placeholder, will be set during resolution
-                });
-            }
-
-            // Parse GainLife abilities
-            // Format: "A:SP$ GainLife | LifeAmount$ 7 | ..."
-            // Format: "DB$ GainLife | ValidTgts$ Player | LifeAmount$ 3 | ..." (with targeting)
-            if ability.contains("GainLife") {
-                // Extract life amount
-                if let Some(life_str) = ability.split("LifeAmount$").nth(1) {
-                    if let Some(life_part) = life_str.trim().split(['|', ' ']).next() {
-                        if let Ok(amount) = life_part.trim().parse::<i32>() {
-                            // Use placeholder player ID 0 - will be filled in at cast time
-                            effects.push(Effect::GainLife {
-                                player: PlayerId::new(0), // Placeholder, will be set during resolution
-                                amount,
-                            });
-                        }
-                    }
-                }
-            }
-
-            // Parse Pump abilities
-            // Format: "A:SP$ Pump | ValidTgts$ Creature | NumAtt$ +3 | NumDef$ +3 | ..."
-            if ability.contains("SP$ Pump") {
-                let mut power_bonus = 0;
-                let mut toughness_bonus = 0;
-
-                // Extract power bonus (NumAtt$)
-                if let Some(att_str) = ability.split("NumAtt$").nth(1) {
-                    if let Some(att_part) = att_str.trim().split(['|', ' ']).next() {
-                        // Handle +X or -X format
-                        let att_trimmed = att_part.trim().trim_start_matches('+');
-                        if let Ok(bonus) = att_trimmed.parse::<i32>() {
-                            power_bonus = bonus;
-                        }
-                    }
-                }
-
-                // Extract toughness bonus (NumDef$)
-                if let Some(def_str) = ability.split("NumDef$").nth(1) {
-                    if let Some(def_part) = def_str.trim().split(['|', ' ']).next() {
-                        // Handle +X or -X format
-                        let def_trimmed = def_part.trim().trim_start_matches('+');
-                        if let Ok(bonus) = def_trimmed.parse::<i32>() {
-                            toughness_bonus = bonus;
-                        }
-                    }
-                }
-
-                // Only add the effect if we successfully parsed at least one bonus
-                if power_bonus != 0 || toughness_bonus != 0 {
-                    use crate::core::CardId;
-                    effects.push(Effect::PumpCreature {
-                        target: CardId::new(0), // Placeholder, will be set during resolution
-                        power_bonus,
-                        toughness_bonus,
-                    });
-                }
-            }
-
-            // Parse Tap abilities
-            // Format: "A:SP$ Tap | ValidTgts$ Creature | ..."
-            if ability.contains("SP$ Tap") && !ability.contains("TapAll") {
-                use crate::core::CardId;
-                effects.push(Effect::TapPermanent {
-                    target: CardId::new(0), // Placeholder, will be set during resolution
-                });
-            }
-
-            // Parse Untap abilities
-            // Format: "A:SP$ Untap | ValidTgts$ Land | ..."
-            if ability.contains("SP$ Untap") {
-                use crate::core::CardId;
-                effects.push(Effect::UntapPermanent {
-                    target: CardId::new(0), // Placeholder, will be set during resolution
-                });
-            }
-
-            // Parse Mill abilities
-            // Format: "A:SP$ Mill | NumCards$ 3 | ValidTgts$ Player | ..."
-            if ability.contains("SP$ Mill") {
-                if let Some(cards_str) = ability.split("NumCards$").nth(1) {
-                    if let Some(cards_part) = cards_str.trim().split(['|', ' ']).next() {
-                        if let Ok(count) = cards_part.trim().parse::<u8>() {
-                            use crate::core::PlayerId;
-                            effects.push(Effect::Mill {
-                                player: PlayerId::new(0), // Placeholder, will be set during resolution
-                                count,
-                            });
-                        }
-                    }
-                }
-            }
-
-            // Parse Counter abilities
-            // Format: "A:SP$ Counter | TargetType$ Spell | ValidTgts$ Card | ..."
-            if ability.contains("SP$ Counter") {
-                use crate::core::CardId;
-                effects.push(Effect::CounterSpell {
-                    target: CardId::new(0), // Placeholder, will be set during resolution
-                });
-            }
-
-            // Parse ChangeZone abilities (exile effects)
-            // Format: "A:SP$ ChangeZone | Origin$ Battlefield | Destination$ Exile | ValidTgts$ Creature | ..."
-            // Example: Swords to Plowshares
-            if ability.contains("SP$ ChangeZone")
-                && ability.contains("Destination$ Exile")
-                && ability.contains("Origin$ Battlefield")
-            {
-                use crate::core::CardId;
-                effects.push(Effect::ExilePermanent {
-                    target: CardId::new(0), // Placeholder, will be set during resolution
-                });
-            }
-        }
+    /// The cost that may be paid rather than the mana cost (MTG Rules 118.9)
+    ///
+    /// Format: "SVar:AltCost:Cost$ Sac<2/Mountain> | Description$ You may sacrifice two Mountains rather than pay this spell's mana cost."
+    fn alternative_cost(&self) -> Option<crate::core::Cost> {
+        let (_, body) = self.svars.iter().find(|(name, _)| name == "AltCost")?;
+        body.split('|')
+            .find_map(|param| param.trim().strip_prefix("Cost$"))
+            .and_then(|cost| crate::core::Cost::parse(cost.trim()))
+    }
 
-        effects
+    /// Parse spell abilities (A:SP$ lines and their SubAbility$ chains) into Effect objects
+    fn parse_effects(&self) -> Vec<crate::core::Effect> {
+        self.script.spells.iter().flat_map(|spell| spell.to_effects()).collect()
     }
 
     /// Parse triggered abilities (T: lines)
-    /// This is a simplified parser for common ETB triggers
+    /// Trigger bodies are taken from the SVar referenced by Execute$
     fn parse_triggers(&self) -> Vec<Trigger> {
-        use crate::core::{Effect, PlayerId};
-
         let mut triggers = Vec::new();
 
-        for ability in &self.raw_abilities {
-            // Only process T: lines (triggered abilities)
-            if !ability.starts_with("T:") {
-                continue;
-            }
-
+        for trigger in &self.script.triggers {
             // Parse ETB triggers
             // Format: "T:Mode$ ChangesZone | Origin$ Any | Destination$ Battlefield | ValidCard$ Card.Self | Execute$ TrigDraw | TriggerDescription$ When..."
-            if ability.contains("Mode$ ChangesZone")
-                && ability.contains("Destination$ Battlefield")
-                && ability.contains("ValidCard$ Card.Self")
+            if trigger.mode == "ChangesZone"
+                && trigger.param("Destination") == Some("Battlefield")
+                && trigger.param("ValidCard") == Some("Card.Self")
             {
-                // Extract the Execute$ parameter to determine what effects to apply
-                let mut effects = Vec::new();
-
-                // Check if this is a draw trigger
-                if ability.contains("Execute$ TrigDraw") || ability.contains("Draw") {
-                    // Look for NumCards in the ability string
-                    // For now, default to drawing 1 card if we can't find the number
-                    let count = if let Some(cards_str) = ability.split("NumCards$").nth(1) {
-                        cards_str
-                            .trim()
-                            .split(['|', ' ', '\n'])
-                            .next()
-                            .and_then(|s| s.trim().parse::<u8>().ok())
-                            .unwrap_or(1)
-                    } else {
-                        1
-                    };
-
-                    effects.push(Effect::DrawCards {
-                        player: PlayerId::new(0), // Placeholder - will be filled when triggered
-                        count,
-                    });
-                }
-
-                // Check if this is a damage trigger
-                if ability.contains("Execute$ TrigDealDamage") || ability.contains("DealDamage") {
-                    // Look for NumDmg in the ability string
-                    let amount = if let Some(dmg_str) = ability.split("NumDmg$").nth(1) {
-                        dmg_str
-                            .trim()
-                            .split(['|', ' ', '\n'])
-                            .next()
-                            .and_then(|s| s.trim().parse::<i32>().ok())
-                            .unwrap_or(1)
-                    } else {
-                        1
-                    };
-
-                    effects.push(Effect::DealDamage {
-                        target: crate::core::TargetRef::None, // Will be filled when triggered
-                        amount,
-                    });
-                }
-
-                // Check if this is a gain life trigger
-                if ability.contains("Execute$ TrigGainLife")
-                    || (ability.contains("GainLife") && !ability.contains("DealDamage"))
-                {
-                    // Look for LifeAmount in the ability string
-                    let amount = if let Some(life_str) = ability.split("LifeAmount$").nth(1) {
-                        life_str
-                            .trim()
-                            .split(['|', ' ', '\n'])
-                            .next()
-                            .and_then(|s| s.trim().parse::<i32>().ok())
-                            .unwrap_or(1)
-                    } else {
-                        1
-                    };
-
-                    effects.push(Effect::GainLife {
-                        player: PlayerId::new(0), // Placeholder - will be filled when triggered
-                        amount,
-                    });
-                }
-
-                // Check if this is a destroy trigger
-                if ability.contains("Execute$ TrigDestroy") || ability.contains("Destroy") {
-                    use crate::core::CardId;
-                    effects.push(Effect::DestroyPermanent {
-                        target: CardId::new(0), // Placeholder - will be filled when triggered
-                    });
-                }
-
-                // Check if this is a pump trigger
-                if ability.contains("Execute$ TrigPump") || ability.contains("Pump") {
-                    // Look for NumAtt and NumDef in the ability string
-                    let power_bonus = if let Some(att_str) = ability.split("NumAtt$").nth(1) {
-                        att_str
-                            .trim()
-                            .split(['|', ' ', '\n'])
-                            .next()
-                            .and_then(|s| s.trim().strip_prefix('+').unwrap_or(s.trim()).parse::<i32>().ok())
-                            .unwrap_or(0)
-                    } else {
-                        0
-                    };
-
-                    let toughness_bonus = if let Some(def_str) = ability.split("NumDef$").nth(1) {
-                        def_str
-                            .trim()
-                            .split(['|', ' ', '\n'])
-                            .next()
-                            .and_then(|s| s.trim().strip_prefix('+').unwrap_or(s.trim()).parse::<i32>().ok())
-                            .unwrap_or(0)
-                    } else {
-                        0
-                    };
-
-                    if power_bonus != 0 || toughness_bonus != 0 {
-                        use crate::core::CardId;
-                        effects.push(Effect::PumpCreature {
-                            target: CardId::new(0), // Placeholder - will be filled when triggered
-                            power_bonus,
-                            toughness_bonus,
-                        });
-                    }
-                }
-
+                // Only spells announce X, so X is 0 in triggered abilities
+                let effects: Vec<Effect> = trigger.to_effects().iter().map(|e| e.with_x(0)).collect();
                 if !effects.is_empty() {
-                    // Extract description from TriggerDescription$ if available
-                    let description = if let Some(desc_str) = ability.split("TriggerDescription$").nth(1) {
-                        desc_str.trim().to_string()
-                    } else {
-                        "When this enters the battlefield".to_string()
-                    };
-
-                    triggers.push(Trigger::new(TriggerEvent::EntersBattlefield, effects, description));
+                    let description = trigger
+                        .description()
+                        .unwrap_or("When this enters the battlefield")
+                        .to_string();
+                    triggers.push(
+                        Trigger::new(TriggerEvent::EntersBattlefield, effects, description)
+                            .with_target_restriction(trigger.target_restriction()),
+                    );
                 }
             }
 
             // Parse phase triggers
             // Format: "T:Mode$ Phase | Phase$ Upkeep | ValidPlayer$ You | ..."
-            if ability.contains("Mode$ Phase") {
+            if trigger.mode == "Phase" {
                 // Determine which phase/step this triggers on
-                let trigger_event = if ability.contains("Phase$ Upkeep") {
-                    Some(TriggerEvent::BeginningOfUpkeep)
-                } else if ability.contains("Phase$ EndOfTurn") || ability.contains("Phase$ End") {
-                    Some(TriggerEvent::BeginningOfEndStep)
-                } else {
-                    None // Other phases not supported yet
+                let trigger_event = match trigger.param("Phase") {
+                    Some("Upkeep") => Some(TriggerEvent::BeginningOfUpkeep),
+                    Some("EndOfTurn") | Some("End") | Some("End of Turn") => Some(TriggerEvent::BeginningOfEndStep),
+                    _ => None, // Other phases not supported yet
                 };
 
                 if let Some(event) = trigger_event {
-                    // For now, create a simple trigger without complex conditions
-                    // TODO(mtg-111): Support CheckSVar$, SVarCompare$, Execute$ sub-abilities
+                    // TODO(mtg-111): Support CheckSVar$, SVarCompare$ conditions
                     // TODO(mtg-111): Support ValidPlayer$ filtering (You vs Opponent vs Each)
                     // TODO(mtg-111): Support OptionalDecider$ for optional triggers
-
-                    let description = if let Some(desc_str) = ability.split("TriggerDescription$").nth(1) {
-                        desc_str.trim().to_string()
-                    } else {
-                        "At the beginning of upkeep".to_string()
-                    };
-
-                    // For now, don't add effects - phase triggers usually need complex parsing
-                    // This creates a placeholder trigger that can be detected in the game loop
-                    // Real implementation will need to parse Execute$ and SubAbility references
+                    let description = trigger
+                        .description()
+                        .unwrap_or("At the beginning of upkeep")
+                        .to_string();
+
+                    // The Execute$ chain is parsed (see CardScript), but phase triggers stay
+                    // placeholders until ValidPlayer$ filtering is supported; otherwise
+                    // "your upkeep" triggers would also fire on the opponent's turn
                     triggers.push(Trigger::new(event, vec![], description));
                 }
             }
@@ -628,227 +448,92 @@ impl CardDefinition {
         triggers
     }
 
-    /// Parse activated abilities (A:AB$ lines)
+    /// Parse activated abilities (A:AB$ lines and their SubAbility$ chains)
     fn parse_activated_abilities(&self) -> Vec<crate::core::ActivatedAbility> {
-        use crate::core::{ActivatedAbility, Cost, Effect};
+        use crate::core::ActivatedAbility;
 
         let mut abilities = Vec::new();
 
-        for ability in &self.raw_abilities {
-            // Only process A:AB$ lines (activated abilities)
-            if !ability.starts_with("A:AB$") {
+        for ability in &self.script.activated {
+            // Skip abilities we can't parse the cost for
+            let Some(cost) = ability.cost() else {
                 continue;
-            }
-
-            // Extract cost from Cost$ parameter
-            let cost = if let Some(cost_str) = ability.split("Cost$").nth(1) {
-                if let Some(cost_part) = cost_str.trim().split('|').next() {
-                    Cost::parse(cost_part.trim())
-                } else {
-                    None
-                }
-            } else {
-                None
             };
 
-            if cost.is_none() {
-                continue; // Skip abilities we can't parse the cost for
-            }
-            let cost = cost.unwrap();
-
-            // Parse effects from the ability
-            let mut effects = Vec::new();
-
-            // AB$ Mana - Mana ability
-            if ability.contains("AB$ Mana") {
-                // Parse mana abilities: A:AB$ Mana | Cost$ T | Produced$ G
-                if let Some(produced_str) = ability.split("Produced$").nth(1) {
-                    if let Some(produced_part) = produced_str.trim().split('|').next() {
-                        let produced = produced_part.trim();
-
-                        // Parse the produced mana into a ManaCost
-                        // Simple cases: G, W, U, B, R, C (single color)
-                        // Complex cases: "Combo W U" (choice), "Any" (any color), "C C" (multiple colorless)
-                        let mana_cost = if produced == "Any" {
-                            // Any color - for now, default to colorless
-                            // TODO: Implement player choice for "Any"
-                            ManaCost::from_string("C")
-                        } else if produced.starts_with("Combo") {
-                            // Combo means choice between colors
-                            // For now, just use the first color mentioned
-                            // TODO: Implement player choice for Combo
-                            let colors = produced.strip_prefix("Combo").unwrap_or("").trim();
-                            let first_color = colors.split_whitespace().next().unwrap_or("C");
-                            ManaCost::from_string(first_color)
-                        } else {
-                            // Direct specification: "G", "C C", "W U", etc.
-                            ManaCost::from_string(produced)
-                        };
-
-                        // Check for Amount$ parameter (e.g., Amount$ 2 for Sol Ring)
-                        let amount = if let Some(amount_str) = ability.split("Amount$").nth(1) {
-                            if let Some(amount_part) = amount_str.trim().split('|').next() {
-                                amount_part.trim().parse::<u8>().unwrap_or(1)
-                            } else {
-                                1
-                            }
-                        } else {
-                            1
-                        };
-
-                        // Multiply mana by amount
-                        let final_mana = mana_cost.multiply(amount);
-
-                        // Create AddMana effect
-                        // Note: We don't know the player yet, so we'll use a placeholder
-                        // The actual player will be set when the ability is activated
-                        effects.push(Effect::AddMana {
-                            player: crate::core::EntityId::new(0), // Placeholder
-                            mana: final_mana,
-                        });
-                    }
-                }
-
-                // Extract description
-                let description = if let Some(desc_str) = ability.split("SpellDescription$").nth(1) {
-                    desc_str.trim().to_string()
-                } else {
-                    "Add mana".to_string()
-                };
-
-                // Add mana ability (marked as is_mana_ability = true)
-                if !effects.is_empty() {
-                    abilities.push(ActivatedAbility::new(
-                        cost.clone(),
-                        effects.clone(),
-                        description,
-                        true, // This IS a mana ability
-                    ));
-                    effects.clear(); // Clear for next ability
-                }
+            // Only spells announce X so far, so X is 0 in activated abilities
+            let effects: Vec<Effect> = ability.to_effects().iter().map(|e| e.with_x(0)).collect();
+            if effects.is_empty() {
                 continue;
             }
 
-            // AB$ DealDamage - Activated damage ability
-            if ability.contains("AB$ DealDamage") {
-                if let Some(dmg_str) = ability.split("NumDmg$").nth(1) {
-                    if let Some(dmg_part) = dmg_str.trim().split(['|', ' ']).next() {
-                        if let Ok(amount) = dmg_part.trim().parse::<i32>() {
-                            use crate::core::TargetRef;
-                            effects.push(Effect::DealDamage {
-                                target: TargetRef::None, // Placeholder
-                                amount,
-                            });
-                        }
-                    }
-                }
-            }
-
-            // AB$ GainLife - Activated life gain ability
-            if ability.contains("AB$ GainLife") {
-                if let Some(life_str) = ability.split("LifeAmount$").nth(1) {
-                    if let Some(life_part) = life_str.trim().split(['|', ' ']).next() {
-                        if let Ok(amount) = life_part.trim().parse::<i32>() {
-                            effects.push(Effect::GainLife {
-                                player: crate::core::EntityId::new(0), // Placeholder
-                                amount,
-                            });
-                        }
-                    }
-                }
-            }
-
-            // AB$ Draw - Activated card draw ability
-            if ability.contains("AB$ Draw") {
-                if let Some(cards_str) = ability.split("NumCards$").nth(1) {
-                    if let Some(cards_part) = cards_str.trim().split(['|', ' ']).next() {
-                        if let Ok(count) = cards_part.trim().parse::<u8>() {
-                            effects.push(Effect::DrawCards {
-                                player: crate::core::EntityId::new(0), // Placeholder
-                                count,
-                            });
-                        }
-                    }
-                }
-            }
-
-            // AB$ Pump - Activated creature boost ability
-            if ability.contains("AB$ Pump") {
-                let mut power_bonus = 0;
-                let mut toughness_bonus = 0;
-
-                // Parse NumAtt$ (power bonus)
-                if let Some(att_str) = ability.split("NumAtt$").nth(1) {
-                    if let Some(att_part) = att_str.trim().split(['|', ' ']).next() {
-                        // Handle +X or -X format
-                        let att_clean = att_part.trim().trim_start_matches('+');
-                        if let Ok(bonus) = att_clean.parse::<i32>() {
-                            power_bonus = bonus;
-                        }
-                    }
-                }
-
-                // Parse NumDef$ (toughness bonus)
-                if let Some(def_str) = ability.split("NumDef$").nth(1) {
-                    if let Some(def_part) = def_str.trim().split(['|', ' ']).next() {
-                        // Handle +X or -X format
-                        let def_clean = def_part.trim().trim_start_matches('+');
-                        if let Ok(bonus) = def_clean.parse::<i32>() {
-                            toughness_bonus = bonus;
-                        }
-                    }
-                }
-
-                // TODO: Parse KW$ (keywords to add) when PumpCreature effect supports it
-
-                effects.push(Effect::PumpCreature {
-                    target: crate::core::EntityId::new(0), // Placeholder
-                    power_bonus,
-                    toughness_bonus,
-                });
-            }
-
-            // AB$ Tap - Activated tap ability
-            if ability.contains("AB$ Tap") {
-                effects.push(Effect::TapPermanent {
-                    target: crate::core::EntityId::new(0), // Placeholder
-                });
-            }
-
-            // AB$ Untap - Activated untap ability
-            if ability.contains("AB$ Untap") {
-                effects.push(Effect::UntapPermanent {
-                    target: crate::core::EntityId::new(0), // Placeholder
-                });
-            }
-
-            // AB$ Destroy - Activated destroy ability
-            if ability.contains("AB$ Destroy") {
-                effects.push(Effect::DestroyPermanent {
-                    target: crate::core::EntityId::new(0), // Placeholder
-                });
-            }
-
-            // Extract description
-            let description = if let Some(desc_str) = ability.split("SpellDescription$").nth(1) {
-                desc_str.trim().to_string()
+            let is_mana_ability = ability.is_mana_ability();
+            let default_description = if is_mana_ability {
+                "Add mana"
             } else {
-                "Activated ability".to_string()
+                "Activated ability"
             };
+            let description = ability.description().unwrap_or(default_description).to_string();
 
-            // Only add if we have effects
-            if !effects.is_empty() {
-                abilities.push(ActivatedAbility::new(
-                    cost,
-                    effects,
-                    description,
-                    false, // Not a mana ability
-                ));
-            }
+            abilities.push(
+                ActivatedAbility::new(cost, effects, description, is_mana_ability)
+                    .with_target_restriction(ability.target_restriction())
+                    .with_sorcery_speed(ability.param("SorcerySpeed") == Some("True")),
+            );
         }
 
         abilities
     }
+
+    /// Build equip abilities from `K:Equip:<cost>` keywords (MTG Rules 702.6a)
+    ///
+    /// "Equip {N}" means "{N}: Attach to target creature you control. Equip only
+    /// as a sorcery."
+    fn parse_equip_abilities(&self) -> Vec<crate::core::ActivatedAbility> {
+        use crate::core::{ActivatedAbility, CardId, Cost};
+
+        self.raw_keywords
+            .iter()
+            .filter_map(|keyword| {
+                let cost_str = keyword.trim().strip_prefix("Equip:")?;
+                let cost_str = cost_str.split(':').next().unwrap_or(cost_str).trim();
+                let cost = Cost::parse(cost_str)?;
+                let effect = Effect::Attach {
+                    attachment: CardId::new(0),
+                    target: CardId::new(0),
+                };
+                Some(
+                    ActivatedAbility::new(cost, vec![effect], format!("Equip {cost_str}"), false)
+                        .with_target_restriction(Some(Selector::parse("Creature.YouCtrl")))
+                        .with_sorcery_speed(true),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Convert an older "Enchant creature you control" restriction to a selector
+fn enchant_selector(text: &str) -> String {
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+            .unwrap_or_default()
+    };
+    text.trim()
+        .split(" or ")
+        .map(|part| {
+            let part = part.trim();
+            if let Some(kind) = part.strip_suffix(" you control") {
+                format!("{}.YouCtrl", capitalize(kind.trim()))
+            } else if let Some(kind) = part.strip_suffix(" an opponent controls") {
+                format!("{}.OppCtrl", capitalize(kind.trim()))
+            } else {
+                capitalize(part)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 #[cfg(test)]
@@ -886,6 +571,35 @@ Oracle:Lightning Bolt deals 3 damage to any target.
         }
     }
 
+    #[test]
+    fn test_color_identity() {
+        let content = r#"
+Name:Birds of Paradise
+ManaCost:G
+Types:Creature Bird
+PT:0/1
+K:Flying
+A:AB$ Mana | Cost$ T | Produced$ Any | SpellDescription$ Add one mana of any color.
+Oracle:Flying\n{T}: Add one mana of any color.
+"#;
+        let birds = CardLoader::parse(content).unwrap();
+        assert_eq!(birds.color_identity().as_slice(), &[Color::Green]);
+
+        let content = r#"
+Name:Kor Skyfisher
+ManaCost:1 W
+Types:Creature Kor Soldier
+PT:2/3
+K:Flying
+Oracle:Flying\nWhen Kor Skyfisher enters, return a permanent you control to its owner's hand.\n{U}{R}: Do nothing. (Reminder {B} text.)
+"#;
+        let skyfisher = CardLoader::parse(content).unwrap();
+        assert_eq!(
+            skyfisher.color_identity().as_slice(),
+            &[Color::White, Color::Blue, Color::Red]
+        );
+    }
+
     #[test]
     fn test_parse_creature() {
         let content = r#"
@@ -927,6 +641,21 @@ Oracle:
         assert!(def.raw_abilities[0].contains("DealDamage"));
     }
 
+    #[test]
+    fn test_parse_hybrid_cost_colors() {
+        let content = r#"
+Name:Boros Guildmage
+ManaCost:R/W R/W
+Types:Creature Human Wizard
+PT:2/2
+Oracle:
+"#;
+
+        let def = CardLoader::parse(content).unwrap();
+        assert_eq!(def.mana_cost.cmc(), 2);
+        assert_eq!(def.colors, vec![Color::White, Color::Red]);
+    }
+
     #[test]
     fn test_parse_with_abilities() {
         let content = r#"
@@ -1066,4 +795,310 @@ Oracle:{T}: Prodigal Sorcerer deals 1 damage to any target.
             _ => panic!("Expected DealDamage effect, got {:?}", ability.effects[0]),
         }
     }
+
+    #[test]
+    fn test_basic_land_types_grant_mana_abilities() {
+        let content = r#"
+Name:Taiga
+ManaCost:no cost
+Types:Land Mountain Forest
+Oracle:({T}: Add {R} or {G}.)
+"#;
+        let taiga = CardLoader::parse(content).unwrap();
+        let card = taiga.instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        let produced: Vec<_> = card
+            .activated_abilities
+            .iter()
+            .filter(|ab| ab.is_mana_ability && ab.cost == crate::core::Cost::Tap)
+            .map(|ab| match &ab.effects[0] {
+                crate::core::Effect::AddMana { mana, .. } => (mana.red, mana.green),
+                other => panic!("Expected AddMana, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(produced, vec![(1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn test_parse_sub_ability_chain() {
+        let content = r#"
+Name:Lightning Helix
+ManaCost:R W
+Types:Instant
+A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ 3 | SubAbility$ DBGainLife | SpellDescription$ CARDNAME deals 3 damage to any target and you gain 3 life.
+SVar:DBGainLife:DB$ GainLife | Defined$ You | LifeAmount$ 3
+Oracle:Lightning Helix deals 3 damage to any target and you gain 3 life.
+"#;
+
+        let def = CardLoader::parse(content).unwrap();
+        assert_eq!(def.raw_abilities.len(), 1);
+        assert_eq!(def.svars.len(), 1);
+        assert!(def.script.diagnostics.is_empty(), "{:?}", def.script.diagnostics);
+
+        // The GainLife is only reachable through SubAbility$
+        use crate::core::Effect;
+        let effects = def.parse_effects();
+        assert_eq!(
+            effects.len(),
+            2,
+            "Lightning Helix should have 2 effects, got {effects:?}"
+        );
+        assert!(matches!(effects[0], Effect::DealDamage { amount: 3, .. }));
+        assert!(matches!(effects[1], Effect::GainLife { amount: 3, .. }));
+    }
+
+    #[test]
+    fn test_parse_etb_trigger_execute() {
+        let content = r#"
+Name:Elvish Visionary
+ManaCost:1 G
+Types:Creature Elf Shaman
+PT:1/1
+T:Mode$ ChangesZone | Origin$ Any | Destination$ Battlefield | ValidCard$ Card.Self | Execute$ TrigDraw | TriggerDescription$ When CARDNAME enters, draw a card.
+SVar:TrigDraw:DB$ Draw | Defined$ You | NumCards$ 1
+Oracle:When Elvish Visionary enters, draw a card.
+"#;
+
+        let def = CardLoader::parse(content).unwrap();
+
+        // The creature spell itself has no spell effects
+        assert!(def.parse_effects().is_empty());
+
+        let triggers = def.parse_triggers();
+        assert_eq!(triggers.len(), 1);
+        assert_eq!(triggers[0].event, TriggerEvent::EntersBattlefield);
+        assert!(matches!(
+            triggers[0].effects.as_slice(),
+            [crate::core::Effect::DrawCards { count: 1, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_parse_x_spell() {
+        let content = r#"
+Name:Blaze
+ManaCost:X R
+Types:Sorcery
+A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ X | SpellDescription$ CARDNAME deals X damage to any target.
+SVar:X:Count$xPaid
+Oracle:Blaze deals X damage to any target.
+"#;
+
+        let def = CardLoader::parse(content).unwrap();
+        let card = def.instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert_eq!(card.mana_cost.x_count, 1);
+        assert_eq!(card.mana_cost.with_x(3).cmc(), 4);
+        assert!(matches!(
+            card.spell_effects(&[], 3)[..],
+            [crate::core::Effect::DealDamage { amount: 3, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_parse_charm() {
+        let content = r#"
+Name:Test Charm
+ManaCost:W
+Types:Instant
+A:SP$ Charm | Choices$ DBLife,DBDraw
+SVar:DBLife:DB$ GainLife | Defined$ You | LifeAmount$ 4 | SpellDescription$ You gain 4 life.
+SVar:DBDraw:DB$ Draw | Defined$ You | NumCards$ 1 | SpellDescription$ Draw a card.
+Oracle:Choose one —\n• You gain 4 life.\n• Draw a card.
+"#;
+
+        let def = CardLoader::parse(content).unwrap();
+        let card = def.instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert!(card.is_modal());
+        assert_eq!((card.min_modes, card.max_modes), (1, 1));
+        assert_eq!(card.modes[0].description, "You gain 4 life.");
+        assert!(card.effects.is_empty());
+        assert!(matches!(
+            card.spell_effects(&[1], 0)[..],
+            [crate::core::Effect::DrawCards { count: 1, .. }]
+        ));
+        assert!(card.are_valid_modes(&[0]));
+        assert!(!card.are_valid_modes(&[]));
+        assert!(!card.are_valid_modes(&[0, 1]));
+        assert!(!card.are_valid_modes(&[2]));
+    }
+
+    #[test]
+    fn test_parse_static_abilities() {
+        use crate::core::{ContinuousModification, Keyword};
+
+        let content = r#"
+Name:Goblin King
+ManaCost:1 R R
+Types:Creature Goblin
+PT:2/2
+S:Mode$ Continuous | Affected$ Creature.Goblin+Other | AddPower$ 1 | AddToughness$ 1 | AddKeyword$ Mountainwalk | Description$ Other Goblin creatures get +1/+1 and have mountainwalk.
+S:Mode$ CantBlockBy | ValidAttacker$ Creature.Self | Description$ Not applied yet.
+Oracle:Other Goblin creatures get +1/+1 and have mountainwalk.
+"#;
+
+        let def = CardLoader::parse(content).unwrap();
+        assert_eq!(def.script.statics.len(), 2);
+        let card = def.instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert_eq!(card.static_abilities.len(), 1);
+        assert_eq!(
+            card.static_abilities[0].modifications,
+            vec![
+                ContinuousModification::AddKeyword(Keyword::Other("Mountainwalk".to_string())),
+                ContinuousModification::AddPower(1),
+                ContinuousModification::AddToughness(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_replacement_effects() {
+        use crate::core::{ReplacementAction, ReplacementEvent};
+
+        let content = r#"
+Name:Azorius Guildgate
+ManaCost:no cost
+Types:Land Gate
+R:Event$ Moved | ValidCard$ Card.Self | Destination$ Battlefield | ReplaceWith$ ETBTapped | ReplacementResult$ Updated | Description$ CARDNAME enters the battlefield tapped.
+SVar:ETBTapped:DB$ Tap | Defined$ Self | ETB$ True
+Oracle:Azorius Guildgate enters the battlefield tapped.
+"#;
+        let def = CardLoader::parse(content).unwrap();
+        assert!(def.script.diagnostics.is_empty(), "{:?}", def.script.diagnostics);
+        let card = def.instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert_eq!(card.replacement_effects.len(), 1);
+        assert_eq!(card.replacement_effects[0].action, ReplacementAction::EnterTapped);
+        assert!(card.replacement_effects[0].modifies_entering());
+
+        // Older scripts use a keyword instead
+        let content = r#"
+Name:Old Tapland
+ManaCost:no cost
+Types:Land
+K:CARDNAME enters the battlefield tapped.
+"#;
+        let card = CardLoader::parse(content)
+            .unwrap()
+            .instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert_eq!(card.replacement_effects.len(), 1);
+        assert_eq!(card.replacement_effects[0].action, ReplacementAction::EnterTapped);
+
+        let content = r#"
+Name:Spirit of the Tomb
+ManaCost:1 B
+Types:Creature Spirit
+PT:2/2
+R:Event$ Moved | ValidCard$ Card.Self | Origin$ Battlefield | Destination$ Graveyard | ReplaceWith$ Exile | Description$ If CARDNAME would die, exile it instead.
+SVar:Exile:DB$ ChangeZone | Hidden$ True | Origin$ All | Destination$ Exile | Defined$ ReplacedCard
+"#;
+        let card = CardLoader::parse(content)
+            .unwrap()
+            .instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        let effect = &card.replacement_effects[0];
+        assert_eq!(
+            effect.event,
+            ReplacementEvent::Moved {
+                origin: Some(Zone::Battlefield),
+                destination: Some(Zone::Graveyard),
+            }
+        );
+        assert_eq!(effect.action, ReplacementAction::ChangeZone(Zone::Exile));
+        assert!(!effect.single_use);
+    }
+
+    #[test]
+    fn test_parse_aura_and_equipment() {
+        use crate::core::{Cost, Keyword};
+
+        let content = r#"
+Name:Pacifism
+ManaCost:1 W
+Types:Enchantment Aura
+K:Enchant creature
+A:SP$ Attach | Cost$ 1 W | ValidTgts$ Creature | AILogic$ Curse
+"#;
+        let card = CardLoader::parse(content)
+            .unwrap()
+            .instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert!(card.keywords.contains(&Keyword::Enchant("Creature".to_string())));
+        assert!(matches!(card.effects[..], [Effect::Attach { .. }]));
+
+        // An Aura without SP$ Attach still attaches to its target
+        let content = r#"
+Name:Guardian Aura
+ManaCost:W
+Types:Enchantment Aura
+K:Enchant:Creature.YouCtrl:creature you control
+"#;
+        let card = CardLoader::parse(content)
+            .unwrap()
+            .instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert!(card
+            .keywords
+            .contains(&Keyword::Enchant("Creature.YouCtrl".to_string())));
+        assert!(matches!(card.effects[..], [Effect::Attach { .. }]));
+        assert_eq!(
+            enchant_selector("artifact or creature you control"),
+            "Artifact,Creature.YouCtrl"
+        );
+
+        let content = r#"
+Name:Bonesplitter
+ManaCost:1
+Types:Artifact Equipment
+K:Equip:1
+"#;
+        let card = CardLoader::parse(content)
+            .unwrap()
+            .instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert!(card.is_equipment());
+        let equip = &card.activated_abilities[0];
+        assert_eq!(equip.cost, Cost::parse("1").unwrap());
+        assert!(equip.sorcery_speed);
+        assert!(equip.target_restriction.is_some());
+        assert!(matches!(equip.effects[..], [Effect::Attach { .. }]));
+    }
+
+    #[test]
+    fn test_parse_planeswalker() {
+        let content = r#"
+Name:Ajani Goldmane
+ManaCost:2 W W
+Types:Legendary Planeswalker Ajani
+Loyalty:4
+A:AB$ GainLife | Cost$ AddCounter<1/LOYALTY> | Planeswalker$ True | LifeAmount$ 2 | SpellDescription$ You gain 2 life.
+A:AB$ Draw | Cost$ SubCounter<1/LOYALTY> | Planeswalker$ True | NumCards$ 1 | SpellDescription$ Draw a card.
+"#;
+        let card = CardLoader::parse(content)
+            .unwrap()
+            .instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert!(card.is_planeswalker());
+        assert_eq!(card.loyalty, Some(4));
+        assert_eq!(card.activated_abilities.len(), 2);
+        assert!(card
+            .activated_abilities
+            .iter()
+            .all(|ability| ability.is_loyalty_ability()));
+        assert_eq!(card.activated_abilities[0].cost.loyalty_change(), Some(1));
+        assert_eq!(card.activated_abilities[1].cost.loyalty_change(), Some(-1));
+    }
+
+    #[test]
+    fn test_parse_alternative_cost() {
+        let content = r#"
+Name:Fireblast
+ManaCost:4 R R
+Types:Instant
+A:SP$ DealDamage | ValidTgts$ Any | NumDmg$ 4 | SpellDescription$ CARDNAME deals 4 damage to any target.
+SVar:AltCost:Cost$ Sac<2/Mountain> | Description$ You may sacrifice two Mountains rather than pay this spell's mana cost.
+"#;
+        let card = CardLoader::parse(content)
+            .unwrap()
+            .instantiate(crate::core::CardId::new(1), crate::core::PlayerId::new(0));
+        assert_eq!(
+            card.alternative_cost,
+            Some(crate::core::Cost::SacrificePattern {
+                count: 2,
+                card_type: "Mountain".to_string()
+            })
+        );
+    }
 }
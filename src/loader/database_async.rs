@@ -4,7 +4,10 @@
 //! 1. Lazy loading: Load cards on-demand when requested (parallel I/O)
 //! 2. Eager loading: Load all cards upfront from cardsfolder (parallel I/O)
 
+use crate::core::TokenDefinition;
+use crate::loader::ability_script::ScriptDiagnostic;
 use crate::loader::card::{CardDefinition, CardLoader};
+use crate::loader::token::TokenLoader;
 use crate::{MtgError, Result};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -42,6 +45,11 @@ fn card_name_to_path(cardsfolder: &Path, card_name: &str) -> PathBuf {
         .join(format!("{normalized}.txt"))
 }
 
+/// Forge keeps token scripts next to the cardsfolder: "res/cardsfolder" -> "res/tokenscripts"
+fn tokenscripts_dir(cardsfolder: &Path) -> PathBuf {
+    cardsfolder.join("..").join("tokenscripts")
+}
+
 /// Async card database with lazy and eager loading support
 pub struct CardDatabase {
     /// Base directory containing card files
@@ -82,7 +90,7 @@ impl CardDatabase {
         }
 
         // Load asynchronously
-        match Self::load_card_async(path).await {
+        match Self::load_card_async(path, tokenscripts_dir(&self.cardsfolder)).await {
             Ok(card_def) => {
                 // Cache the loaded card in an Arc
                 let card_arc = Arc::new(card_def);
@@ -146,6 +154,7 @@ impl CardDatabase {
         // Stream card file paths using parallel directory walking (jwalk + rayon)
         // Key optimization: spawn loading tasks AS paths are discovered, not after
         let cardsfolder = self.cardsfolder.clone();
+        let tokenscripts = tokenscripts_dir(&self.cardsfolder);
 
         let (path_tx, mut path_rx) = tokio::sync::mpsc::unbounded_channel();
         let (result_tx, mut result_rx) = tokio::sync::mpsc::unbounded_channel::<Result<CardDefinition>>();
@@ -181,9 +190,10 @@ impl CardDatabase {
             while let Some(path) = path_rx.recv().await {
                 count += 1;
                 let result_tx = result_tx.clone();
+                let tokenscripts = tokenscripts.clone();
                 tokio::spawn(async move {
                     // Send the result (success or error) - don't filter
-                    let result = Self::load_card_async(path.clone()).await;
+                    let result = Self::load_card_async(path.clone(), tokenscripts).await;
                     if let Err(e) = &result {
                         eprintln!("Fatal error loading card from {path:?}: {e}");
                     }
@@ -212,13 +222,39 @@ impl CardDatabase {
         Ok((loaded, duration))
     }
 
-    /// Load a card from a file asynchronously
-    async fn load_card_async(path: PathBuf) -> Result<CardDefinition> {
+    /// Load a card from a file asynchronously, along with the token scripts it references
+    async fn load_card_async(path: PathBuf, tokenscripts: PathBuf) -> Result<CardDefinition> {
         let contents = tokio::fs::read_to_string(&path).await.map_err(MtgError::IoError)?;
 
-        CardLoader::parse(&contents).map_err(|e| {
+        let mut card_def = CardLoader::parse(&contents).map_err(|e| {
             // Enhance error message with file path for easier debugging
             MtgError::InvalidCardFormat(format!("Failed to parse card file '{}': {}", path.display(), e))
+        })?;
+
+        for script in card_def.script.token_scripts() {
+            if let Some(token) = Self::load_token_async(&tokenscripts, script).await? {
+                card_def.tokens.push(token);
+            }
+        }
+
+        Ok(card_def)
+    }
+
+    /// Load a token script by name (e.g. "w_1_1_soldier")
+    /// Returns None if the tokenscripts folder has no such script
+    pub async fn get_token(&self, script: &str) -> Result<Option<TokenDefinition>> {
+        Self::load_token_async(&tokenscripts_dir(&self.cardsfolder), script).await
+    }
+
+    async fn load_token_async(tokenscripts: &Path, script: &str) -> Result<Option<TokenDefinition>> {
+        let path = tokenscripts.join(format!("{script}.txt"));
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = tokio::fs::read_to_string(&path).await.map_err(MtgError::IoError)?;
+        TokenLoader::parse(script, &contents).map(Some).map_err(|e| {
+            MtgError::InvalidCardFormat(format!("Failed to parse token script '{}': {}", path.display(), e))
         })
     }
 
@@ -248,6 +284,21 @@ impl CardDatabase {
         let cards = self.cards.read().await;
         cards.is_empty()
     }
+
+    /// Problems found in the scripts of the loaded cards, sorted by card name
+    ///
+    /// Cards with unknown parameters or unsupported APIs still load, but the
+    /// engine ignores those parts, so they may not play as printed.
+    pub async fn script_diagnostics(&self) -> Vec<(String, Vec<ScriptDiagnostic>)> {
+        let cards = self.cards.read().await;
+        let mut diagnostics: Vec<(String, Vec<ScriptDiagnostic>)> = cards
+            .values()
+            .filter(|card| !card.script.diagnostics.is_empty())
+            .map(|card| (card.name.to_string(), card.script.diagnostics.clone()))
+            .collect();
+        diagnostics.sort_by(|a, b| a.0.cmp(&b.0));
+        diagnostics
+    }
 }
 
 #[cfg(test)]
@@ -328,4 +379,39 @@ mod tests {
         assert_eq!(loaded, 3);
         println!("Loaded {loaded} cards in {duration:?}");
     }
+
+    #[tokio::test]
+    async fn test_script_diagnostics_of_loaded_cards() {
+        let cardsfolder = std::env::temp_dir().join(format!("mtg_forge_diagnostics_{}", std::process::id()));
+        let cards = [
+            (
+                "s/shock.txt",
+                "Name:Shock\nManaCost:R\nTypes:Instant\nA:SP$ DealDamage | ValidTgts$ Any | NumDmg$ 2 | Flourish$ True\n",
+            ),
+            (
+                "f/forest.txt",
+                "Name:Forest\nManaCost:no cost\nTypes:Basic Land Forest\nOracle:({T}: Add {G}.)\n",
+            ),
+        ];
+        for (path, content) in cards {
+            let path = cardsfolder.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let db = CardDatabase::new(cardsfolder);
+        db.load_cards(&["Shock".to_string(), "Forest".to_string()])
+            .await
+            .unwrap();
+        let diagnostics = db.script_diagnostics().await;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].0, "Shock");
+        assert_eq!(
+            diagnostics[0].1,
+            vec![ScriptDiagnostic::UnknownParameter {
+                api: "DealDamage".to_string(),
+                param: "Flourish".to_string(),
+            }]
+        );
+    }
 }
@@ -18,15 +18,23 @@ impl DeckLoader {
     pub fn parse(content: &str) -> Result<DeckList> {
         let mut main_deck = Vec::new();
         let mut sideboard = Vec::new();
-        let mut in_sideboard = false;
+        let mut commander = Vec::new();
+        let mut section = Section::Main;
 
         for line in content.lines() {
             let line = line.trim();
 
-            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
-                if line.contains("Sideboard") {
-                    in_sideboard = true;
-                }
+            if line.starts_with('[') {
+                section = if line.contains("Sideboard") {
+                    Section::Sideboard
+                } else if line.eq_ignore_ascii_case("[Commander]") {
+                    Section::Commander
+                } else {
+                    Section::Main
+                };
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') {
                 continue;
             }
 
@@ -42,10 +50,10 @@ impl DeckLoader {
 
                     let entry = DeckEntry { card_name, count };
 
-                    if in_sideboard {
-                        sideboard.push(entry);
-                    } else {
-                        main_deck.push(entry);
+                    match section {
+                        Section::Main => main_deck.push(entry),
+                        Section::Sideboard => sideboard.push(entry),
+                        Section::Commander => commander.push(entry),
                     }
                 }
             }
@@ -55,10 +63,22 @@ impl DeckLoader {
             return Err(MtgError::InvalidDeckFormat("Empty deck".to_string()));
         }
 
-        Ok(DeckList { main_deck, sideboard })
+        Ok(DeckList {
+            main_deck,
+            sideboard,
+            commander,
+        })
     }
 }
 
+/// The section of a .dck file a card line belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Main,
+    Sideboard,
+    Commander,
+}
+
 /// Represents a deck entry (card name and count)
 #[derive(Debug, Clone)]
 pub struct DeckEntry {
@@ -71,6 +91,8 @@ pub struct DeckEntry {
 pub struct DeckList {
     pub main_deck: Vec<DeckEntry>,
     pub sideboard: Vec<DeckEntry>,
+    /// The deck's commanders (`[Commander]` section), empty outside Commander
+    pub commander: Vec<DeckEntry>,
 }
 
 impl DeckList {
@@ -84,13 +106,15 @@ impl DeckList {
         self.sideboard.iter().map(|e| e.count as usize).sum()
     }
 
-    /// Get unique card names from main deck and sideboard
+    /// Is this a Commander deck?
+    pub fn is_commander_deck(&self) -> bool {
+        !self.commander.is_empty()
+    }
+
+    /// Get unique card names from main deck, sideboard and commanders
     pub fn unique_card_names(&self) -> Vec<String> {
         let mut names = std::collections::HashSet::new();
-        for entry in &self.main_deck {
-            names.insert(entry.card_name.clone());
-        }
-        for entry in &self.sideboard {
+        for entry in self.main_deck.iter().chain(&self.sideboard).chain(&self.commander) {
             names.insert(entry.card_name.clone());
         }
         names.into_iter().collect()
@@ -129,4 +153,27 @@ Name=Test Deck
         assert_eq!(deck.sideboard[0].card_name, "Shock");
         assert_eq!(deck.sideboard[0].count, 15);
     }
+
+    #[test]
+    fn test_parse_commander_deck() {
+        let content = r#"
+[metadata]
+Name=Elves
+
+[Commander]
+1 Ezuri, Renegade Leader|C14
+
+[Main]
+40 Forest
+59 Llanowar Elves
+"#;
+
+        let deck = DeckLoader::parse(content).unwrap();
+        assert!(deck.is_commander_deck());
+        assert_eq!(deck.commander.len(), 1);
+        assert_eq!(deck.commander[0].card_name, "Ezuri, Renegade Leader");
+        assert_eq!(deck.total_cards(), 99);
+        assert!(deck.unique_card_names().contains(&"Ezuri, Renegade Leader".to_string()));
+        assert!(deck.sideboard.is_empty());
+    }
 }
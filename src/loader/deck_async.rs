@@ -20,6 +20,9 @@ pub async fn prefetch_deck_cards(db: &AsyncCardDatabase, deck: &DeckList) -> Res
     for entry in &deck.sideboard {
         unique_names.insert(entry.card_name.clone());
     }
+    for entry in &deck.commander {
+        unique_names.insert(entry.card_name.clone());
+    }
 
     let names: Vec<String> = unique_names.into_iter().collect();
     db.load_cards(&names).await
@@ -2,7 +2,8 @@
 //!
 //! Creates games from deck lists and card database
 
-use crate::core::PlayerId;
+use crate::core::{CardType, PlayerId};
+use crate::game::commander::COMMANDER_STARTING_LIFE;
 use crate::game::GameState;
 use crate::loader::{AsyncCardDatabase as CardDatabase, DeckList};
 use crate::{MtgError, Result};
@@ -27,20 +28,79 @@ impl<'a> GameInitializer<'a> {
         player2_deck: &DeckList,
         starting_life: i32,
     ) -> Result<GameState> {
-        let mut game = GameState::new_two_player(player1_name, player2_name, starting_life);
+        self.init_multiplayer_game(
+            vec![(player1_name, player1_deck), (player2_name, player2_deck)],
+            starting_life,
+        )
+        .await
+    }
+
+    /// Initialize a Commander game (MTG Rules 903) with any number of players
+    ///
+    /// Every deck must name its commanders in a `[Commander]` section and only
+    /// contain cards within their color identity. Players start at 40 life with
+    /// their commanders in the command zone.
+    pub async fn init_commander_game(&self, players: Vec<(String, &DeckList)>) -> Result<GameState> {
+        for (name, deck) in &players {
+            self.validate_commander_deck(deck)
+                .await
+                .map_err(|e| MtgError::InvalidDeckFormat(format!("{name}'s deck: {e}")))?;
+        }
+        self.init_multiplayer_game(players, COMMANDER_STARTING_LIFE).await
+    }
+
+    /// Check a Commander deck: it has a commander that can be one (MTG Rules
+    /// 903.3), and every card's color identity is within its commanders' (903.5c)
+    pub async fn validate_commander_deck(&self, deck: &DeckList) -> Result<()> {
+        if !deck.is_commander_deck() {
+            return Err(MtgError::InvalidDeckFormat("No [Commander] section".to_string()));
+        }
+
+        let mut identity = Vec::new();
+        for entry in &deck.commander {
+            let card_def = self.card_definition(&entry.card_name).await?;
+            let is_legendary_creature = card_def.types.contains(&CardType::Creature)
+                && card_def.subtypes.iter().any(|s| s.as_str() == "Legendary");
+            if !is_legendary_creature && !card_def.oracle.contains("can be your commander") {
+                return Err(MtgError::InvalidDeckFormat(format!(
+                    "{} can't be a commander",
+                    entry.card_name
+                )));
+            }
+            identity.extend(card_def.color_identity());
+        }
+
+        for entry in &deck.main_deck {
+            let card_def = self.card_definition(&entry.card_name).await?;
+            if let Some(color) = card_def.color_identity().into_iter().find(|c| !identity.contains(c)) {
+                return Err(MtgError::InvalidDeckFormat(format!(
+                    "{} has {color} in its color identity, outside its commanders'",
+                    entry.card_name
+                )));
+            }
+        }
+        Ok(())
+    }
 
-        // Get player IDs
-        let player1_id = game.players[0].id;
-        let player2_id = game.players[1].id;
+    /// Initialize a game with any number of players, given in turn order with their decks
+    ///
+    /// Cards in a deck's `[Commander]` section start in the command zone as that
+    /// player's commanders.
+    pub async fn init_multiplayer_game(
+        &self,
+        players: Vec<(String, &DeckList)>,
+        starting_life: i32,
+    ) -> Result<GameState> {
+        let names = players.iter().map(|(name, _)| name.clone()).collect();
+        let mut game = GameState::new_multiplayer(names, starting_life);
 
-        // Pre-load all unique cards from both decks to ensure deterministic CardID allocation
+        // Pre-load all unique cards from every deck to ensure deterministic CardID allocation
         // This populates the card database cache before we start allocating CardIDs
         let mut unique_cards = std::collections::HashSet::new();
-        for entry in &player1_deck.main_deck {
-            unique_cards.insert(entry.card_name.clone());
-        }
-        for entry in &player2_deck.main_deck {
-            unique_cards.insert(entry.card_name.clone());
+        for (_, deck) in &players {
+            for entry in deck.main_deck.iter().chain(&deck.commander) {
+                unique_cards.insert(entry.card_name.clone());
+            }
         }
 
         // Load all cards in parallel (into cache)
@@ -54,20 +114,30 @@ impl<'a> GameInitializer<'a> {
         // Now load decks sequentially - cards will come from cache, ensuring deterministic order
         // Deck 1: card1, card2, card3, ...
         // Deck 2: card1, card2, card3, ...
-        self.load_deck_into_game(&mut game, player1_id, player1_deck).await?;
+        for (idx, (_, deck)) in players.iter().enumerate() {
+            let player_id = game.players[idx].id;
+            self.load_deck_into_game(&mut game, player_id, deck).await?;
+        }
 
-        self.load_deck_into_game(&mut game, player2_id, player2_deck).await?;
+        // The decks were put into libraries directly, not through logged moves
+        game.reset_zobrist_hash();
 
         Ok(game)
     }
 
-    /// Load a deck into a player's library
+    /// Look up a card definition, failing if the card isn't in the database
+    async fn card_definition(&self, card_name: &str) -> Result<std::sync::Arc<crate::loader::CardDefinition>> {
+        self.card_db
+            .get_card(card_name)
+            .await?
+            .ok_or_else(|| MtgError::InvalidCardFormat(format!("Card not found in database: {card_name}")))
+    }
+
+    /// Load a deck into a player's library, and its commanders into their command zone
     async fn load_deck_into_game(&self, game: &mut GameState, player_id: PlayerId, deck: &DeckList) -> Result<()> {
         for entry in &deck.main_deck {
             // Look up the card definition
-            let card_def = self.card_db.get_card(&entry.card_name).await?.ok_or_else(|| {
-                MtgError::InvalidCardFormat(format!("Card not found in database: {}", entry.card_name))
-            })?;
+            let card_def = self.card_definition(&entry.card_name).await?;
 
             // Create the requested number of copies
             for _ in 0..entry.count {
@@ -84,6 +154,15 @@ impl<'a> GameInitializer<'a> {
             }
         }
 
+        for entry in &deck.commander {
+            let card_def = self.card_definition(&entry.card_name).await?;
+            for _ in 0..entry.count {
+                let card_id = game.next_card_id();
+                game.cards.insert(card_id, card_def.instantiate(card_id, player_id));
+                game.add_commander(card_id)?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -147,6 +226,7 @@ mod tests {
                 count: 1,
             }],
             sideboard: vec![],
+            commander: vec![],
         };
 
         let initializer = GameInitializer::new(&db);
@@ -156,4 +236,97 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_init_multiplayer_game() {
+        let db = CardDatabase::new(PathBuf::from("cardsfolder"));
+        let deck = DeckList {
+            main_deck: vec![],
+            sideboard: vec![],
+            commander: vec![],
+        };
+
+        let initializer = GameInitializer::new(&db);
+        let names = ["Alice", "Bob", "Carol", "Dave"];
+        let game = initializer
+            .init_multiplayer_game(names.iter().map(|name| (name.to_string(), &deck)).collect(), 40)
+            .await
+            .unwrap();
+
+        assert_eq!(game.players.len(), 4);
+        assert_eq!(game.players[2].name.as_str(), "Carol");
+        assert!(game.players.iter().all(|p| p.life == 40));
+        assert!(game.players.iter().all(|p| game.get_player_zones(p.id).is_some()));
+        assert_eq!(game.turn.active_player, game.players[0].id);
+    }
+
+    /// A cardsfolder with just the cards the Commander tests need
+    fn commander_cardsfolder() -> PathBuf {
+        let cardsfolder = std::env::temp_dir().join(format!("mtg_forge_commander_{}", std::process::id()));
+        let cards = [
+            (
+                "e/ezuri_renegade_leader.txt",
+                "Name:Ezuri, Renegade Leader\nManaCost:1 G G\nTypes:Legendary Creature Elf Warrior\nPT:2/2\nOracle:\n",
+            ),
+            (
+                "l/llanowar_elves.txt",
+                "Name:Llanowar Elves\nManaCost:G\nTypes:Creature Elf Druid\nPT:1/1\nOracle:{T}: Add {G}.\n",
+            ),
+            (
+                "f/forest.txt",
+                "Name:Forest\nManaCost:no cost\nTypes:Basic Land Forest\nOracle:({T}: Add {G}.)\n",
+            ),
+            (
+                "l/lightning_bolt.txt",
+                "Name:Lightning Bolt\nManaCost:R\nTypes:Instant\nOracle:Lightning Bolt deals 3 damage to any target.\n",
+            ),
+        ];
+        for (path, content) in cards {
+            let path = cardsfolder.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        cardsfolder
+    }
+
+    #[tokio::test]
+    async fn test_init_commander_game() {
+        let db = CardDatabase::new(commander_cardsfolder());
+        let deck =
+            DeckLoader::parse("[Commander]\n1 Ezuri, Renegade Leader\n[Main]\n50 Forest\n49 Llanowar Elves\n").unwrap();
+
+        let initializer = GameInitializer::new(&db);
+        let game = initializer
+            .init_commander_game(vec![("Alice".to_string(), &deck), ("Bob".to_string(), &deck)])
+            .await
+            .unwrap();
+
+        for player in &game.players {
+            assert_eq!(player.life, 40);
+            let zones = game.get_player_zones(player.id).unwrap();
+            assert_eq!(zones.library.len(), 99);
+            assert_eq!(zones.command.len(), 1);
+            assert_eq!(game.commanders(player.id), zones.command.cards);
+            assert!(game.can_cast_from_command_zone(player.id, zones.command.cards[0]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commander_deck_color_identity() {
+        let db = CardDatabase::new(commander_cardsfolder());
+        let initializer = GameInitializer::new(&db);
+
+        let off_color = DeckLoader::parse("[Commander]\n1 Ezuri, Renegade Leader\n[Main]\n1 Lightning Bolt\n").unwrap();
+        let err = initializer.validate_commander_deck(&off_color).await.unwrap_err();
+        assert!(err.to_string().contains("Lightning Bolt"), "{err}");
+
+        let not_legendary = DeckLoader::parse("[Commander]\n1 Llanowar Elves\n[Main]\n1 Forest\n").unwrap();
+        assert!(initializer.validate_commander_deck(&not_legendary).await.is_err());
+
+        let no_commander = DeckLoader::parse("[Main]\n1 Forest\n").unwrap();
+        let result = initializer
+            .init_commander_game(vec![("Alice".to_string(), &no_commander)])
+            .await;
+        assert!(result.is_err());
+    }
 }
@@ -2,17 +2,23 @@
 //!
 //! Parsers for the Forge card format (.txt) and deck format (.dck)
 
+pub mod ability_script;
 pub mod card;
 pub mod database_async;
 pub mod deck;
 pub mod deck_async;
 pub mod game_init;
+pub mod token;
 
+pub use ability_script::{
+    AbilityKind, AbilityScript, ApiType, CardScript, ReplacementScript, ScriptDiagnostic, StaticScript, TriggerScript,
+};
 pub use card::{CardDefinition, CardLoader};
 pub use database_async::CardDatabase as AsyncCardDatabase;
 pub use deck::{DeckEntry, DeckList, DeckLoader};
 pub use deck_async::prefetch_deck_cards;
 pub use game_init::GameInitializer;
+pub use token::TokenLoader;
 
 // Re-export AsyncCardDatabase as CardDatabase for convenience
 pub use database_async::CardDatabase;
@@ -0,0 +1,91 @@
+//! Token script loader
+//!
+//! Loads token definitions from Forge's `res/tokenscripts` folder. Token scripts
+//! use the card format, with a `Colors:` line in place of a mana cost:
+//!
+//! ```text
+//! Name:Soldier Token
+//! ManaCost:no cost
+//! Colors:white
+//! Types:Creature Soldier
+//! PT:1/1
+//! Oracle:
+//! ```
+
+use crate::core::{CardId, PlayerId, TokenDefinition};
+use crate::loader::card::CardLoader;
+use crate::{MtgError, Result};
+use std::fs;
+use std::path::Path;
+
+/// Token loader for tokenscript .txt files
+pub struct TokenLoader;
+
+impl TokenLoader {
+    /// Load a token from a tokenscript file; the script name is the file stem
+    pub fn load_from_file(path: &Path) -> Result<TokenDefinition> {
+        let script = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| MtgError::InvalidCardFormat(format!("Invalid token script path '{}'", path.display())))?;
+        let content = fs::read_to_string(path).map_err(MtgError::IoError)?;
+        Self::parse(script, &content).map_err(|e| {
+            MtgError::InvalidCardFormat(format!("Failed to parse token script '{}': {}", path.display(), e))
+        })
+    }
+
+    /// Parse a token script's text content
+    pub fn parse(script: &str, content: &str) -> Result<TokenDefinition> {
+        let definition = CardLoader::parse(content)?;
+        // Instantiating resolves keywords, triggers and abilities the same way as for cards
+        let card = definition.instantiate(CardId::new(0), PlayerId::new(0));
+
+        Ok(TokenDefinition {
+            script: script.to_string(),
+            name: card.name.to_string(),
+            types: card.types.to_vec(),
+            subtypes: card.subtypes.to_vec(),
+            colors: card.colors.to_vec(),
+            power: card.power,
+            toughness: card.toughness,
+            keywords: card.keywords,
+            triggers: card.triggers,
+            activated_abilities: card.activated_abilities,
+            static_abilities: card.static_abilities,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{CardType, Color, Effect, Keyword};
+
+    #[test]
+    fn test_parse_creature_token() {
+        let content = "Name:Spirit Token\nManaCost:no cost\nColors:white\nTypes:Creature Spirit\nPT:1/1\nK:Flying\n";
+        let token = TokenLoader::parse("w_1_1_spirit_flying", content).unwrap();
+
+        assert_eq!(token.script, "w_1_1_spirit_flying");
+        assert_eq!(token.name, "Spirit Token");
+        assert_eq!(token.types, vec![CardType::Creature]);
+        assert_eq!(token.colors, vec![Color::White]);
+        assert_eq!((token.power, token.toughness), (Some(1), Some(1)));
+        assert_eq!(token.keywords, vec![Keyword::Flying]);
+    }
+
+    #[test]
+    fn test_parse_token_with_ability() {
+        let content = "Name:Treasure Token\nManaCost:no cost\nColors:colorless\nTypes:Artifact Treasure\n\
+                       A:AB$ Mana | Cost$ T | Produced$ Any | SpellDescription$ Add one mana of any color.\n";
+        let token = TokenLoader::parse("c_a_treasure_sac", content).unwrap();
+
+        assert_eq!(token.colors, vec![Color::Colorless]);
+        assert_eq!(token.activated_abilities.len(), 1);
+        assert!(token.activated_abilities[0].is_mana_ability);
+        assert!(matches!(
+            token.activated_abilities[0].effects[0],
+            Effect::AddManaOfChoice { .. }
+        ));
+    }
+}
@@ -495,6 +495,15 @@ fn should_print(verbosity: VerbosityLevel, level: VerbosityLevel, suppress: bool
     verbosity >= level && !suppress
 }
 
+/// Print the problems found in the loaded cards' scripts, e.g. parameters the engine ignores
+async fn print_script_diagnostics(card_db: &CardDatabase) {
+    for (name, diagnostics) in card_db.script_diagnostics().await {
+        for diagnostic in diagnostics {
+            println!("  {name}: {diagnostic}");
+        }
+    }
+}
+
 // StopCondition is now imported from mtg_forge_rs::game module
 
 /// Run TUI with async card loading
@@ -632,12 +641,16 @@ async fn run_tui(
                     .chain(player.graveyard.iter())
                     .chain(player.library.iter())
                     .chain(player.exile.iter())
+                    .chain(player.command.iter())
                 {
                     card_names.insert(card_def.name.clone());
                 }
             }
             card_db.load_cards(&card_names.into_iter().collect::<Vec<_>>()).await?
         };
+        if should_print(verbosity, VerbosityLevel::Verbose, suppress_output) {
+            print_script_diagnostics(&card_db).await;
+        }
         if !suppress_output {
             println!("  Loaded {count} cards");
             eprintln!("  (Loading time: {:.2}ms)", duration.as_secs_f64() * 1000.0);
@@ -678,6 +691,9 @@ async fn run_tui(
             unique_names.extend(deck2.unique_card_names());
             card_db.load_cards(&unique_names).await?
         };
+        if should_print(verbosity, VerbosityLevel::Verbose, suppress_output) {
+            print_script_diagnostics(&card_db).await;
+        }
         if !suppress_output {
             println!("  Loaded {count} cards");
             eprintln!("  (Loading time: {:.2}ms)", duration.as_secs_f64() * 1000.0);
@@ -686,15 +702,25 @@ async fn run_tui(
             println!("Initializing game...");
         }
         let game_init = GameInitializer::new(&card_db);
-        game_init
-            .init_game(
-                p1_name.clone(),
-                &deck1,
-                p2_name.clone(),
-                &deck2,
-                20, // starting life
-            )
-            .await?
+        if deck1.is_commander_deck() || deck2.is_commander_deck() {
+            // Decks with a [Commander] section play Commander: 40 life, commanders in the command zone
+            if !suppress_output {
+                println!("  Commander game");
+            }
+            game_init
+                .init_commander_game(vec![(p1_name.clone(), &deck1), (p2_name.clone(), &deck2)])
+                .await?
+        } else {
+            game_init
+                .init_game(
+                    p1_name.clone(),
+                    &deck1,
+                    p2_name.clone(),
+                    &deck2,
+                    20, // starting life
+                )
+                .await?
+        }
     };
 
     // Set random seed if provided
@@ -60,7 +60,7 @@ pub enum CardModifier {
     IsRingBearer,
     /// Don't trigger ETB effects
     NoETBTrigs,
-    /// Token indicator (for future token support)
+    /// Token indicator with the token script name (e.g. "w_1_1_soldier")
     Token(String),
 }
 
@@ -75,16 +75,17 @@ pub fn parse_card_notation(notation: &str) -> Result<(String, Vec<CardModifier>)
     // First part is the card name (or token indicator)
     let card_name = parts[0].trim();
 
-    // Check if it's a token
-    if card_name.starts_with("t:") || card_name.starts_with("T:") {
-        return Ok((
-            card_name[2..].to_string(),
-            vec![CardModifier::Token(card_name[2..].to_string())],
-        ));
-    }
-
     let mut modifiers = Vec::new();
 
+    // Check if it's a token; tokens take the same modifiers as cards ("t:w_1_1_soldier|Tapped")
+    let card_name = if card_name.starts_with("t:") || card_name.starts_with("T:") {
+        let token_name = &card_name[2..];
+        modifiers.push(CardModifier::Token(token_name.to_string()));
+        token_name
+    } else {
+        card_name
+    };
+
     // Parse remaining parts as modifiers
     for part in &parts[1..] {
         let part = part.trim();
@@ -338,6 +339,11 @@ mod tests {
         let (name, mods) = parse_card_notation("t:1/1 G Saproling").unwrap();
         assert_eq!(name, "1/1 G Saproling");
         assert!(matches!(&mods[0], CardModifier::Token(s) if s == "1/1 G Saproling"));
+
+        let (name, mods) = parse_card_notation("t:w_1_1_soldier|Tapped").unwrap();
+        assert_eq!(name, "w_1_1_soldier");
+        assert!(matches!(&mods[0], CardModifier::Token(s) if s == "w_1_1_soldier"));
+        assert!(matches!(mods[1], CardModifier::Tapped));
     }
 
     #[test]
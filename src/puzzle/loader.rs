@@ -4,7 +4,7 @@
 //! with specific board states for testing.
 
 use crate::{
-    core::{Card, CardId, CardType, PlayerId},
+    core::{Card, CardId, CardType, CounterType, PlayerId, TokenDefinition},
     game::GameState,
     loader::AsyncCardDatabase,
     puzzle::{card_notation::CardModifier, CardDefinition, GameStateDefinition, PuzzleFile},
@@ -58,6 +58,9 @@ pub async fn load_puzzle_into_game(puzzle: &PuzzleFile, card_db: &AsyncCardDatab
         // Apply player state
         game.players[player_idx].life = player_state.life;
         game.players[player_idx].lands_played_this_turn = player_state.lands_played as u8;
+        if let Some(&poison) = player_state.counters.get(&CounterType::Poison) {
+            game.players[player_idx].poison_counters = poison.clamp(0, u8::MAX as i32) as u8;
+        }
 
         // Load cards into hand
         for card_def in &player_state.hand {
@@ -131,12 +134,28 @@ pub async fn load_puzzle_into_game(puzzle: &PuzzleFile, card_db: &AsyncCardDatab
             zones.exile.add(card_id);
         }
 
-        // Note: Command zone not yet in PlayerZones - will be added when needed
+        // Load cards into the command zone
+        for card_def in &player_state.command {
+            let card_id = {
+                let card = create_card_from_definition(card_def, player_id, &mut game, card_db).await?;
+                card.id
+            };
+            if let Some(id) = card_def.id {
+                id_map.insert(id, card_id);
+            }
+            let zones = game
+                .get_player_zones_mut(player_id)
+                .ok_or_else(|| MtgError::InvalidAction("Player zones not found".to_string()))?;
+            zones.command.add(card_id);
+        }
     }
 
     // Second pass: apply modifiers that depend on card IDs or need card refs
     apply_card_modifiers(&mut game, state_def, &id_map)?;
 
+    // The puzzle state was set up directly, not through logged actions
+    game.reset_zobrist_hash();
+
     Ok(game)
 }
 
@@ -147,25 +166,32 @@ async fn create_card_from_definition<'a>(
     game: &'a mut GameState,
     card_db: &AsyncCardDatabase,
 ) -> Result<&'a mut Card> {
-    // Check if it's a token
-    if card_def.is_token() {
-        return Err(MtgError::InvalidAction("Token support not yet implemented".to_string()));
-    }
-
-    // Load card from database
-    let paper_card = card_db
-        .get_card(&card_def.name)
-        .await?
-        .ok_or_else(|| MtgError::InvalidAction(format!("Card not found: {}", card_def.name)))?;
-
-    // Create game card with proper ID using instantiate method
     let card_id = game.next_card_id();
-    let mut card = paper_card.instantiate(card_id, owner);
+    let mut card = if card_def.is_token() {
+        // Prefer the tokenscript; fall back to what the script name itself encodes
+        let script = token_script_name(&card_def.name);
+        let token = match card_db.get_token(&script).await? {
+            Some(token) => token,
+            None => TokenDefinition::from_script_name(&script),
+        };
+        token.instantiate(card_id, owner)
+    } else {
+        // Load card from database
+        let paper_card = card_db
+            .get_card(&card_def.name)
+            .await?
+            .ok_or_else(|| MtgError::InvalidAction(format!("Card not found: {}", card_def.name)))?;
+
+        // Create game card with proper ID using instantiate method
+        paper_card.instantiate(card_id, owner)
+    };
 
     // Apply basic modifiers (tapped state and counters)
     for modifier in &card_def.modifiers {
         match modifier {
             CardModifier::Tapped => card.tapped = true,
+            CardModifier::Damage(damage) => card.damage_marked = *damage,
+            CardModifier::IsCommander => card.is_commander = true,
             CardModifier::Counters(counters) => {
                 // Convert HashMap to SmallVec format
                 for (counter_type, count) in counters {
@@ -187,11 +213,28 @@ async fn create_card_from_definition<'a>(
     game.cards.get_mut(card_id_value)
 }
 
+/// Convert a puzzle token name to a tokenscript name
+///
+/// Puzzles use either the script name directly ("w_1_1_soldier") or the older
+/// "P/T Colors Subtype" notation ("1/1 G Saproling" -> "g_1_1_saproling").
+fn token_script_name(name: &str) -> String {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    match words.as_slice() {
+        [pt, colors, rest @ ..] if pt.contains('/') => {
+            let (power, toughness) = pt.split_once('/').unwrap_or((pt, ""));
+            let mut parts = vec![colors.to_lowercase(), power.to_string(), toughness.to_string()];
+            parts.extend(rest.iter().map(|word| word.to_lowercase()));
+            parts.join("_")
+        }
+        _ => name.to_string(),
+    }
+}
+
 /// Apply card modifiers that need second pass (attachments, etc.) or card references
 fn apply_card_modifiers(
     game: &mut GameState,
     state_def: &GameStateDefinition,
-    _id_map: &HashMap<u32, CardId>,
+    id_map: &HashMap<u32, CardId>,
 ) -> Result<()> {
     // For now, just apply summoning sickness to creatures on battlefield
     // by checking if they should have it based on the current turn
@@ -226,7 +269,28 @@ fn apply_card_modifiers(
         }
     }
 
-    // TODO: Apply attachment modifiers when card attachment support is added
+    // Attach Auras and Equipment to the cards they reference by puzzle id
+    let attachments = state_def
+        .players
+        .iter()
+        .flat_map(|p| p.battlefield.iter())
+        .filter_map(|def| {
+            let target = def.modifiers.iter().find_map(|m| match m {
+                CardModifier::AttachedTo(target) => Some(*target),
+                _ => None,
+            })?;
+            Some((def.id?, target))
+        });
+    for (id, target) in attachments {
+        let (Some(&card_id), Some(&target_id)) = (id_map.get(&id), id_map.get(&target)) else {
+            return Err(MtgError::InvalidAction(format!(
+                "Invalid AttachedTo reference: {id} -> {target}"
+            )));
+        };
+        game.cards.get_mut(card_id)?.attached_to = Some(target_id);
+    }
+    game.apply_continuous_effects();
+
     // TODO: Apply other advanced modifiers as needed
 
     Ok(())
@@ -235,7 +299,6 @@ fn apply_card_modifiers(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::CounterType;
     use crate::puzzle::PuzzleFile;
     use std::sync::Arc;
 
@@ -315,4 +378,44 @@ p1life=20
             panic!("Expected Counters modifier");
         }
     }
+
+    #[tokio::test]
+    async fn test_load_puzzle_with_tokens() {
+        let puzzle_contents = r#"
+[metadata]
+Name:Token Test
+Goal:Win
+Turns:1
+Difficulty:Easy
+
+[state]
+turn=1
+activeplayer=p0
+activephase=MAIN1
+p0battlefield=t:w_1_1_soldier|Tapped;t:1/1 G Saproling
+"#;
+
+        let puzzle = PuzzleFile::parse(puzzle_contents).unwrap();
+        // Without a tokenscripts folder the definition comes from the script name
+        let card_db = AsyncCardDatabase::new(std::path::PathBuf::from("cardsfolder"));
+        let game = load_puzzle_into_game(&puzzle, &card_db).await.unwrap();
+
+        let tokens: Vec<&Card> = game
+            .battlefield
+            .cards
+            .iter()
+            .map(|id| game.cards.get(*id).unwrap())
+            .collect();
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens.iter().all(|card| card.is_token && card.is_creature()));
+        assert_eq!(tokens[0].name.as_str(), "Soldier Token");
+        assert!(tokens[0].tapped);
+        assert_eq!(tokens[1].name.as_str(), "Saproling Token");
+    }
+
+    #[test]
+    fn test_token_script_name() {
+        assert_eq!(token_script_name("w_1_1_soldier"), "w_1_1_soldier");
+        assert_eq!(token_script_name("1/1 G Saproling"), "g_1_1_saproling");
+    }
 }
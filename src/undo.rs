@@ -6,8 +6,12 @@
 use crate::core::{CardId, CounterType, PlayerId};
 use crate::zones::Zone;
 use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
 
+use crate::game::state_hash::zobrist_delta;
 use crate::game::GameState;
+use crate::{MtgError, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Atomic game actions that can be logged and undone
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +22,9 @@ pub enum GameAction {
         from_zone: Zone,
         to_zone: Zone,
         owner: PlayerId,
+        /// Position the card had in `from_zone`, so undo puts it back there
+        #[serde(default)]
+        from_index: usize,
     },
 
     /// Tap/untap a permanent
@@ -26,7 +33,7 @@ pub enum GameAction {
     /// Modify life total (delta is the change, not absolute value)
     ModifyLife { player_id: PlayerId, delta: i32 },
 
-    /// Add mana to pool
+    /// Add mana to pool (`mana.snow` is set when the mana came from a snow source)
     AddMana {
         player_id: PlayerId,
         mana: crate::core::ManaCost,
@@ -41,6 +48,16 @@ pub enum GameAction {
         prev_red: u8,
         prev_green: u8,
         prev_colorless: u8,
+        /// How much of each color (WUBRG, then colorless) came from snow sources
+        #[serde(default)]
+        prev_snow: [u8; 6],
+    },
+
+    /// Mana was spent from a pool to pay a cost (stores the pool before and after)
+    SpendMana {
+        player_id: PlayerId,
+        prev_pool: crate::core::ManaPool,
+        pool: crate::core::ManaPool,
     },
 
     /// Add counter to card
@@ -72,6 +89,54 @@ pub enum GameAction {
         rng_state: Option<Vec<u8>>,
     },
 
+    /// The number of lands a player played this turn changed to `count` (stores
+    /// the previous count)
+    SetLandsPlayed {
+        player_id: PlayerId,
+        count: u8,
+        previous: u8,
+    },
+
+    /// A permanent entered the battlefield on `turn` (stores the turn it last entered, for undo)
+    SetEnteredBattlefield {
+        card_id: CardId,
+        turn: u32,
+        previous: Option<u32>,
+    },
+
+    /// A creature was declared as an attacker (stores whether combat had begun, for undo)
+    DeclareAttacker {
+        card_id: CardId,
+        defending_player: PlayerId,
+        /// The planeswalker attacked, if it isn't attacking a player
+        planeswalker: Option<CardId>,
+        prev_combat_active: bool,
+    },
+
+    /// A creature was declared as a blocker of `attackers`
+    DeclareBlocker {
+        card_id: CardId,
+        attackers: SmallVec<[CardId; 2]>,
+    },
+
+    /// The combat state was replaced, e.g. cleared at end of combat (stores the
+    /// previous state and the new one)
+    SetCombat {
+        previous: Box<crate::game::CombatState>,
+        combat: Box<crate::game::CombatState>,
+    },
+
+    /// A player's library was shuffled (stores its previous order and the RNG's
+    /// position before the shuffle)
+    ShuffleLibrary {
+        player_id: PlayerId,
+        previous: Vec<CardId>,
+        prev_rng_word_pos: u128,
+    },
+
+    /// The game's RNG was used for a random choice (stores its previous position)
+    UseRng { prev_word_pos: u128 },
+
     /// Pump creature (temporary stat modification)
     PumpCreature {
         card_id: CardId,
@@ -79,6 +144,143 @@ pub enum GameAction {
         toughness_delta: i32,
     },
 
+    /// A triggered ability was added to the end of `GameState::pending_triggers`
+    AddPendingTrigger { ability: Box<crate::game::StackAbility> },
+
+    /// A pending trigger at `pending_index` was put on top of the stack
+    PutTriggerOnStack {
+        pending_index: usize,
+        ability: Box<crate::game::StackAbility>,
+    },
+
+    /// The casting choices (modes, X, targets) of a spell on the stack changed
+    /// (they're made at cast time and cleared when the spell leaves the stack)
+    SetSpellChoices {
+        card_id: CardId,
+        choices: Box<crate::game::SpellChoices>,
+        previous: Box<crate::game::SpellChoices>,
+    },
+
+    /// An activated ability was put on top of the stack
+    PutAbilityOnStack { object: Box<crate::game::StackObject> },
+
+    /// The ability on top of the stack was removed to resolve
+    /// (its effects are logged separately)
+    ResolveAbility { object: Box<crate::game::StackObject> },
+
+    /// Create a token on the battlefield (undo removes it entirely)
+    CreateToken { card_id: CardId },
+
+    /// A replacement effect was added to the end of `GameState::replacement_effects`
+    AddReplacementEffect {
+        effect: Box<crate::game::RegisteredReplacement>,
+    },
+
+    /// The replacement effect at `index` ended (it was used up or the turn ended)
+    RemoveReplacementEffect {
+        index: usize,
+        effect: Box<crate::game::RegisteredReplacement>,
+    },
+
+    /// The damage shield at `index` prevented some damage (`previous` is the
+    /// effect before, `effect` with what it still prevents)
+    UseDamageShield {
+        index: usize,
+        effect: Box<crate::game::RegisteredReplacement>,
+        previous: Box<crate::game::RegisteredReplacement>,
+    },
+
+    /// An Aura or Equipment was attached to a permanent or unattached
+    /// (`previous` is what it was attached to before)
+    SetAttachedTo {
+        card_id: CardId,
+        attached_to: Option<CardId>,
+        previous: Option<CardId>,
+    },
+
+    /// A loyalty ability of a planeswalker was activated on `turn` (stores the
+    /// previous turn for undo)
+    ActivateLoyaltyAbility {
+        card_id: CardId,
+        turn: u32,
+        previous_turn: Option<u32>,
+    },
+
+    /// A token outside the battlefield ceased to exist (MTG Rules 704.5d)
+    TokenCeasesToExist {
+        card_id: CardId,
+        zone: Zone,
+        owner: PlayerId,
+        /// Position the card had in `zone`
+        #[serde(default)]
+        index: usize,
+    },
+
+    /// A card left the game along with its owner (MTG Rules 800.4a)
+    CardLeavesGame {
+        card_id: CardId,
+        zone: Zone,
+        owner: PlayerId,
+        /// Position the card had in `zone`
+        #[serde(default)]
+        index: usize,
+    },
+
+    /// The stack object at `index` ceased to exist (its controller left the game)
+    RemoveFromStack {
+        index: usize,
+        object: Box<crate::game::StackObject>,
+    },
+
+    /// The targets of the pending trigger at `index` were chosen (MTG Rules 603.3d)
+    TargetPendingTrigger {
+        index: usize,
+        ability: Box<crate::game::StackAbility>,
+        previous: Box<crate::game::StackAbility>,
+    },
+
+    /// The pending trigger at `index` ceased to exist (its controller left the game,
+    /// or it had no legal targets)
+    RemovePendingTrigger {
+        index: usize,
+        ability: Box<crate::game::StackAbility>,
+    },
+
+    /// Mark damage on a permanent (`deathtouch` is whether it came from a source
+    /// with deathtouch; prev_deathtouch restores the deathtouch flag)
+    MarkDamage {
+        card_id: CardId,
+        amount: i32,
+        deathtouch: bool,
+        prev_deathtouch: bool,
+    },
+
+    /// Remove all damage from a permanent (stores previous state for undo)
+    ClearDamage {
+        card_id: CardId,
+        prev_damage: i32,
+        prev_deathtouch: bool,
+    },
+
+    /// Player lost the game through a state-based action (MTG Rules 704.5a-c)
+    PlayerLoses { player_id: PlayerId },
+
+    /// A commander was cast from the command zone, raising its commander tax (MTG Rules 903.8)
+    CastFromCommandZone { card_id: CardId },
+
+    /// A commander dealt combat damage to a player (MTG Rules 903.10a)
+    CommanderDamage {
+        player_id: PlayerId,
+        commander: CardId,
+        amount: i32,
+    },
+
+    /// Player left a multiplayer game after losing it (MTG Rules 800.4a)
+    PlayerLeavesGame { player_id: PlayerId },
+
+    /// Player attempted to draw from an empty library (MTG Rules 704.5b)
+    DrawFromEmptyLibrary { player_id: PlayerId },
+
     /// Mark a choice point (for tree search and replay)
     ///
     /// Stores both the fact that a choice occurred and what that choice was,
@@ -95,151 +297,29 @@ impl GameAction {
     /// Apply the inverse of this action to undo it
     ///
     /// Returns Ok(()) if successful, Err if the action cannot be undone
-    pub fn undo(&self, game: &mut GameState) -> Result<(), String> {
-        match self {
-            GameAction::MoveCard {
-                card_id,
-                from_zone,
-                to_zone,
-                owner,
-            } => {
-                // Reverse the move: move from to_zone back to from_zone
-                game.move_card(*card_id, *to_zone, *from_zone, *owner)
-                    .map_err(|e| format!("Failed to undo MoveCard: {}", e))?;
-            }
-
-            GameAction::TapCard { card_id, tapped } => {
-                // Reverse tap state
-                if let Ok(card) = game.cards.get_mut(*card_id) {
-                    card.tapped = !tapped;
-                } else {
-                    return Err(format!("Card {} not found for TapCard undo", card_id.as_u32()));
-                }
-            }
-
-            GameAction::ModifyLife { player_id, delta } => {
-                // Reverse the life change
-                if let Some(player) = game.players.iter_mut().find(|p| p.id == *player_id) {
-                    player.life -= delta;
-                } else {
-                    return Err(format!("Player {} not found for ModifyLife undo", player_id.as_u32()));
-                }
-            }
-
-            GameAction::AddMana { player_id, mana } => {
-                // Remove the mana that was added
-                if let Some(player) = game.players.iter_mut().find(|p| p.id == *player_id) {
-                    player.mana_pool.white = player.mana_pool.white.saturating_sub(mana.white);
-                    player.mana_pool.blue = player.mana_pool.blue.saturating_sub(mana.blue);
-                    player.mana_pool.black = player.mana_pool.black.saturating_sub(mana.black);
-                    player.mana_pool.red = player.mana_pool.red.saturating_sub(mana.red);
-                    player.mana_pool.green = player.mana_pool.green.saturating_sub(mana.green);
-                    player.mana_pool.colorless = player.mana_pool.colorless.saturating_sub(mana.colorless);
-                } else {
-                    return Err(format!("Player {} not found for AddMana undo", player_id.as_u32()));
-                }
-            }
-
-            GameAction::EmptyManaPool {
-                player_id,
-                prev_white,
-                prev_blue,
-                prev_black,
-                prev_red,
-                prev_green,
-                prev_colorless,
-            } => {
-                // Restore previous mana pool state
-                if let Some(player) = game.players.iter_mut().find(|p| p.id == *player_id) {
-                    player.mana_pool.white = *prev_white;
-                    player.mana_pool.blue = *prev_blue;
-                    player.mana_pool.black = *prev_black;
-                    player.mana_pool.red = *prev_red;
-                    player.mana_pool.green = *prev_green;
-                    player.mana_pool.colorless = *prev_colorless;
-                } else {
-                    return Err(format!(
-                        "Player {} not found for EmptyManaPool undo",
-                        player_id.as_u32()
-                    ));
-                }
-            }
-
-            GameAction::AddCounter {
-                card_id,
-                counter_type,
-                amount,
-            } => {
-                // Remove the counters that were added
-                game.remove_counters(*card_id, *counter_type, *amount)
-                    .map_err(|e| format!("Failed to undo AddCounter: {}", e))?;
-            }
-
-            GameAction::RemoveCounter {
-                card_id,
-                counter_type,
-                amount,
-            } => {
-                // Add back the counters that were removed
-                game.add_counters(*card_id, *counter_type, *amount)
-                    .map_err(|e| format!("Failed to undo RemoveCounter: {}", e))?;
-            }
-
-            GameAction::AdvanceStep { from_step, to_step: _ } => {
-                // Restore previous step
-                game.turn.current_step = *from_step;
-            }
-
-            GameAction::ChangeTurn {
-                from_player,
-                to_player: _,
-                turn_number,
-                rng_state,
-            } => {
-                // Restore previous turn state
-                game.turn.active_player = *from_player;
-                // Find the player index
-                if let Some(idx) = game.players.iter().position(|p| p.id == *from_player) {
-                    game.turn.active_player_idx = idx;
-                }
-
-                // Restore turn number to the previous turn
-                // ChangeTurn logs the NEW turn number, so previous is turn_number - 1
-                game.turn.turn_number = turn_number.saturating_sub(1);
-
-                // Restore RNG state if available
-                if let Some(rng_bytes) = rng_state {
-                    use serde::Deserialize;
-                    let mut deserializer = serde_json::Deserializer::from_slice(rng_bytes);
-                    if let Ok(rng) = rand_chacha::ChaCha12Rng::deserialize(&mut deserializer) {
-                        *game.rng.borrow_mut() = rng;
-                    } else {
-                        return Err("Failed to deserialize RNG state".to_string());
-                    }
-                }
-            }
+    pub fn undo(&self, game: &mut GameState) -> std::result::Result<(), String> {
+        game.revert_action(self.clone()).map_err(|e| e.to_string())
+    }
+}
 
-            GameAction::PumpCreature {
-                card_id,
-                power_delta,
-                toughness_delta,
-            } => {
-                // Reverse the pump by applying negative deltas
-                if let Ok(card) = game.cards.get_mut(*card_id) {
-                    // Handle Option<i8> by mapping to subtract the delta
-                    card.power = card.power.map(|p| p.saturating_sub(*power_delta as i8));
-                    card.toughness = card.toughness.map(|t| t.saturating_sub(*toughness_delta as i8));
-                } else {
-                    return Err(format!("Card {} not found for PumpCreature undo", card_id.as_u32()));
-                }
-            }
+/// Source of checkpoint IDs, unique across all games
+static NEXT_CHECKPOINT_ID: AtomicU64 = AtomicU64::new(0);
 
-            GameAction::ChoicePoint { .. } => {
-                // ChoicePoints don't modify game state, nothing to undo
-            }
-        }
+/// A point in a game's history that the game can be rewound to
+///
+/// Taken with [`GameState::checkpoint`]. A checkpoint goes stale once the game
+/// is rewound past it, or once a later checkpoint is committed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Checkpoint {
+    id: u64,
+    /// Number of actions logged since the game began when the checkpoint was taken
+    position: usize,
+}
 
-        Ok(())
+impl Checkpoint {
+    /// Number of actions logged since the game began when the checkpoint was taken
+    pub fn position(&self) -> usize {
+        self.position
     }
 }
 
@@ -257,6 +337,20 @@ pub struct UndoLog {
 
     /// Mark positions for choice points
     choice_points: Vec<usize>,
+
+    /// Zobrist hash of the game state, updated as actions are logged and popped
+    ///
+    /// Kept up to date even when logging is disabled.
+    #[serde(default)]
+    zobrist: u64,
+
+    /// Number of actions discarded from the front of the log by commits
+    #[serde(default)]
+    committed: usize,
+
+    /// Checkpoints that can still be rewound to, oldest first
+    #[serde(skip)]
+    checkpoints: Vec<Checkpoint>,
 }
 
 impl UndoLog {
@@ -265,6 +359,9 @@ impl UndoLog {
             actions: Vec::new(),
             enabled: true,
             choice_points: Vec::new(),
+            zobrist: 0,
+            committed: 0,
+            checkpoints: Vec::new(),
         }
     }
 
@@ -274,11 +371,23 @@ impl UndoLog {
             actions: Vec::new(),
             enabled: false,
             choice_points: Vec::new(),
+            zobrist: 0,
+            committed: 0,
+            checkpoints: Vec::new(),
         }
     }
 
+    /// Whether actions are logged, so they can be undone
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
     /// Log an action
+    ///
+    /// The action must already have been applied to the game state; its effect
+    /// on the state's features is folded into the Zobrist hash.
     pub fn log(&mut self, action: GameAction) {
+        self.zobrist = self.zobrist.wrapping_add(zobrist_delta(&action));
         if self.enabled {
             self.actions.push(action);
         }
@@ -297,8 +406,77 @@ impl UndoLog {
     }
 
     /// Pop and return the most recent action
+    ///
+    /// The caller reverts it, so its change to the Zobrist hash is taken back.
     pub fn pop(&mut self) -> Option<GameAction> {
-        self.actions.pop()
+        let action = self.actions.pop()?;
+        self.zobrist = self.zobrist.wrapping_sub(zobrist_delta(&action));
+        self.drop_checkpoints_after(self.position());
+        Some(action)
+    }
+
+    /// Number of actions logged since the game began, including committed ones
+    pub fn position(&self) -> usize {
+        self.committed + self.actions.len()
+    }
+
+    /// Take a checkpoint at the current end of the log
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        let checkpoint = Checkpoint {
+            id: NEXT_CHECKPOINT_ID.fetch_add(1, Ordering::Relaxed),
+            position: self.position(),
+        };
+        self.checkpoints.push(checkpoint);
+        checkpoint
+    }
+
+    /// Check that a checkpoint can still be rewound to
+    ///
+    /// Returns the length the log had when it was taken.
+    pub fn checkpoint_len(&self, checkpoint: &Checkpoint) -> Result<usize> {
+        if !self.enabled {
+            return Err(MtgError::InvalidAction(
+                "Can't rewind to a checkpoint: undo logging is disabled".to_string(),
+            ));
+        }
+        if !self.checkpoints.contains(checkpoint) {
+            return Err(MtgError::InvalidAction(format!(
+                "Stale checkpoint at position {}: the game was rewound past it or a later checkpoint was committed",
+                checkpoint.position
+            )));
+        }
+        Ok(checkpoint.position - self.committed)
+    }
+
+    /// Discard the history before a checkpoint
+    ///
+    /// The game can no longer be rewound past it, so earlier checkpoints go
+    /// stale; the checkpoint itself and later ones stay usable.
+    pub fn commit(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        let len = self.checkpoint_len(checkpoint)?;
+        self.actions.drain(..len);
+        self.choice_points.retain(|&point| point >= len);
+        self.choice_points.iter_mut().for_each(|point| *point -= len);
+        self.committed += len;
+        self.checkpoints.retain(|other| other.position >= checkpoint.position);
+        Ok(())
+    }
+
+    /// Forget checkpoints taken after `position`: the actions since were undone
+    fn drop_checkpoints_after(&mut self, position: usize) {
+        while self.checkpoints.last().is_some_and(|c| c.position > position) {
+            self.checkpoints.pop();
+        }
+    }
+
+    /// Zobrist hash of the game state (see [`compute_zobrist_hash`](crate::game::compute_zobrist_hash))
+    pub fn zobrist_hash(&self) -> u64 {
+        self.zobrist
+    }
+
+    /// Replace the Zobrist hash, after the state was changed without logging
+    pub fn set_zobrist_hash(&mut self, hash: u64) {
+        self.zobrist = hash;
     }
 
     /// Get number of actions in log
@@ -314,6 +492,7 @@ impl UndoLog {
     pub fn rewind_to_choice_point(&mut self) {
         if let Some(checkpoint) = self.choice_points.pop() {
             self.actions.truncate(checkpoint);
+            self.drop_checkpoints_after(self.position());
         }
     }
 
@@ -345,7 +524,7 @@ impl UndoLog {
                     // DON'T undo the ChangeTurn action - we want the snapshot to represent
                     // the START of this turn, not the END of the previous turn.
                     // Put it back on the log so the game state stays at the turn boundary.
-                    self.actions.push(action);
+                    self.log(action);
                     actions_rewound -= 1; // Don't count this as rewound since we kept it
                     turn_number = Some(tn);
                     break;
@@ -385,6 +564,7 @@ impl UndoLog {
     pub fn clear(&mut self) {
         self.actions.clear();
         self.choice_points.clear();
+        self.checkpoints.clear();
     }
 
     /// Get all actions (for debugging/serialization)
@@ -451,6 +631,40 @@ mod tests {
         assert_eq!(log.len(), 2);
     }
 
+    #[test]
+    fn test_nested_checkpoints_and_commit() {
+        let mut log = UndoLog::new();
+        let lose_one = || GameAction::ModifyLife {
+            player_id: PlayerId::new(1),
+            delta: -1,
+        };
+
+        log.log(lose_one());
+        let outer = log.checkpoint();
+        log.log(lose_one());
+        let inner = log.checkpoint();
+        log.log(lose_one());
+        assert_eq!(log.checkpoint_len(&outer).unwrap(), 1);
+        assert_eq!(log.checkpoint_len(&inner).unwrap(), 2);
+
+        // Popping past the inner checkpoint makes it stale
+        log.pop();
+        log.pop();
+        assert!(log.checkpoint_len(&inner).is_err());
+        assert_eq!(log.checkpoint_len(&outer).unwrap(), 1);
+
+        // Committing discards the history before the checkpoint, and earlier checkpoints
+        log.log(lose_one());
+        let later = log.checkpoint();
+        log.log(lose_one());
+        log.commit(&later).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log.position(), 3);
+        assert_eq!(later.position(), 2);
+        assert!(log.checkpoint_len(&outer).is_err());
+        assert_eq!(log.checkpoint_len(&later).unwrap(), 0);
+    }
+
     #[test]
     fn test_disabled_log() {
         let mut log = UndoLog::disabled();
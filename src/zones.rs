@@ -15,6 +15,22 @@ pub enum Zone {
     Command,
 }
 
+impl Zone {
+    /// Parse a zone name as it appears in card scripts (e.g. "Graveyard")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "Library" => Some(Zone::Library),
+            "Hand" => Some(Zone::Hand),
+            "Battlefield" => Some(Zone::Battlefield),
+            "Graveyard" => Some(Zone::Graveyard),
+            "Exile" => Some(Zone::Exile),
+            "Stack" => Some(Zone::Stack),
+            "Command" => Some(Zone::Command),
+            _ => None,
+        }
+    }
+}
+
 /// A zone containing cards (ordered for Library/Graveyard, unordered for others)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CardZone {
@@ -58,6 +74,16 @@ impl CardZone {
         self.cards.contains(&card_id)
     }
 
+    /// Position of a card in the zone (0 is the bottom of a library)
+    pub fn position(&self, card_id: CardId) -> Option<usize> {
+        self.cards.iter().position(|&id| id == card_id)
+    }
+
+    /// Insert a card at a position, or at the end if the zone is shorter
+    pub fn insert(&mut self, index: usize, card_id: CardId) {
+        self.cards.insert(index.min(self.cards.len()), card_id);
+    }
+
     pub fn len(&self) -> usize {
         self.cards.len()
     }
@@ -100,6 +126,7 @@ pub struct PlayerZones {
     pub hand: CardZone,
     pub graveyard: CardZone,
     pub exile: CardZone,
+    pub command: CardZone,
 }
 
 impl PlayerZones {
@@ -109,6 +136,7 @@ impl PlayerZones {
             hand: CardZone::new(Zone::Hand, player_id),
             graveyard: CardZone::new(Zone::Graveyard, player_id),
             exile: CardZone::new(Zone::Exile, player_id),
+            command: CardZone::new(Zone::Command, player_id),
         }
     }
 
@@ -118,6 +146,7 @@ impl PlayerZones {
             Zone::Hand => Some(&self.hand),
             Zone::Graveyard => Some(&self.graveyard),
             Zone::Exile => Some(&self.exile),
+            Zone::Command => Some(&self.command),
             _ => None,
         }
     }
@@ -128,6 +157,7 @@ impl PlayerZones {
             Zone::Hand => Some(&mut self.hand),
             Zone::Graveyard => Some(&mut self.graveyard),
             Zone::Exile => Some(&mut self.exile),
+            Zone::Command => Some(&mut self.command),
             _ => None,
         }
     }
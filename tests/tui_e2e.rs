@@ -279,6 +279,7 @@ async fn test_discard_to_hand_size() -> Result<()> {
         let card_id = EntityId::<Card>::new(1000 + i);
         let mut card = Card::new(card_id, "Mountain", p1_id);
         card.types.push(CardType::Land);
+        card.subtypes.push("Mountain".into());
         game.cards.insert(card_id, card);
 
         if let Some(zones) = game.get_player_zones_mut(p1_id) {
@@ -618,6 +619,7 @@ async fn test_spell_casting_unwind_on_mana_failure() -> Result<()> {
         green: 0,
         colorless: 0,
         x_count: 0,
+        ..ManaCost::new()
     };
     game.cards.insert(spell_id, spell_card);
 
@@ -638,8 +640,10 @@ async fn test_spell_casting_unwind_on_mana_failure() -> Result<()> {
     let result = game.cast_spell_8_step(
         p1_id,
         spell_id,
-        |_state, _card_id| Vec::new(), // No targets
-        |_state, _cost| Vec::new(),    // No mana sources available
+        |_state, _card_id| Default::default(),           // No modes or X
+        |_state, _card_id| Vec::new(),                   // No targets
+        |_state, _card_id, _targets, _total| Vec::new(), // Nothing to divide
+        |_state, _cost| Vec::new(),                      // No mana sources available
     );
 
     // The cast should fail with an error
@@ -664,7 +668,7 @@ async fn test_spell_casting_unwind_on_mana_failure() -> Result<()> {
 
     // Verify spell is NOT on stack
     assert!(
-        !game.stack.cards.contains(&spell_id),
+        !game.stack.contains(spell_id),
         "Spell should not be on stack after failed cast"
     );
 
@@ -686,3 +690,37 @@ async fn test_spell_casting_unwind_on_mana_failure() -> Result<()> {
 
     Ok(())
 }
+
+/// Test that a Commander game starts at 40 life with the commanders in the command zone
+#[tokio::test]
+async fn test_tui_commander_game() -> Result<()> {
+    let cardsfolder = PathBuf::from("cardsfolder");
+    if !cardsfolder.exists() {
+        // Skip test if cardsfolder doesn't exist
+        return Ok(());
+    }
+    let card_db = CardDatabase::new(cardsfolder);
+
+    let deck = DeckLoader::load_from_file(&PathBuf::from("decks/commander_elves.dck"))?;
+    assert!(deck.is_commander_deck());
+
+    let game_init = GameInitializer::new(&card_db);
+    let mut game = game_init
+        .init_commander_game(vec![("Player 1".to_string(), &deck), ("Player 2".to_string(), &deck)])
+        .await?;
+    game.seed_rng(42);
+
+    let players: Vec<_> = game.players.iter().map(|p| p.id).collect();
+    for &player_id in &players {
+        assert_eq!(game.get_player(player_id)?.life, 40);
+        assert_eq!(game.commanders(player_id).len(), 1);
+    }
+
+    let mut controller1 = ZeroController::new(players[0]);
+    let mut controller2 = ZeroController::new(players[1]);
+    let mut game_loop = GameLoop::new(&mut game).with_verbosity(VerbosityLevel::Silent);
+    let result = game_loop.run_game(&mut controller1, &mut controller2)?;
+
+    assert!(result.winner.is_some(), "Game should have a winner");
+    Ok(())
+}
@@ -251,7 +251,7 @@ async fn test_full_game_undo_replay() -> Result<()> {
     let p2_exile_size = p2_zones.exile.cards.len();
 
     let battlefield_size = game_loop.game.battlefield.cards.len();
-    let stack_size = game_loop.game.stack.cards.len();
+    let stack_size = game_loop.game.stack.len();
 
     println!("\nGame state after full rewind:");
     println!("  P1 life: {p1_life_after_rewind} (initial: 20)");
@@ -816,8 +816,8 @@ fn verify_state_equivalence(
 
     // Verify stack
     assert_eq!(
-        current.stack.cards.len(),
-        snapshot.stack.cards.len(),
+        current.stack.len(),
+        snapshot.stack.len(),
         "[Iter {}] Stack size mismatch",
         iteration
     );